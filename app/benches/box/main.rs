@@ -1,6 +1,9 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
-use libblur::{BlurImage, BlurImageMut, BoxBlurParameters, FastBlurChannels, ThreadingPolicy};
+use libblur::{
+    AlphaMode, BlurImage, BlurImageMut, BoxBlurParameters, EdgeMode, EdgeMode2D, FastBlurChannels,
+    Scalar, ThreadingPolicy,
+};
 use opencv::core::{find_file, Mat, Point, Size, BORDER_DEFAULT};
 use opencv::imgcodecs::{imread, IMREAD_COLOR};
 
@@ -33,7 +36,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(77),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -62,7 +68,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(77),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -95,7 +104,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(77),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -128,7 +140,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(77),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -157,7 +172,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(15),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -185,7 +203,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(15),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -232,7 +253,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(15),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -309,7 +333,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &src_image,
                 &mut dst_image,
                 BoxBlurParameters::new(35),
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -2,7 +2,8 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
 
 use libblur::{
-    AnisotropicRadius, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, ThreadingPolicy,
+    AlphaMode, AnisotropicRadius, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy,
 };
 
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -27,6 +28,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -46,6 +49,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -67,6 +72,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -88,6 +95,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -115,6 +124,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -134,6 +145,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -2,7 +2,8 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
 
 use libblur::{
-    AnisotropicRadius, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, ThreadingPolicy,
+    AlphaMode, AnisotropicRadius, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy,
 };
 
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -26,6 +27,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -45,6 +48,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -66,6 +71,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -85,6 +92,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(15),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -106,6 +115,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -133,6 +144,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -152,6 +165,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
                 EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -1,9 +1,9 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use image::{GenericImageView, ImageReader};
 use libblur::{
-    filter_1d_exact, gaussian_kernel_1d, sigma_size, BlurImage, BlurImageMut, ConvolutionMode,
-    EdgeMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams, IeeeBinaryConvolutionMode, Scalar,
-    ThreadingPolicy,
+    filter_1d_exact, gaussian_kernel_1d, sigma_size, AlphaMode, BlurImage, BlurImageMut,
+    ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams,
+    IeeeBinaryConvolutionMode, Scalar, ThreadingPolicy,
 };
 use opencv::core::{
     find_file, split, AlgorithmHint, Mat, Size, Vector, BORDER_DEFAULT, CV_16UC4, CV_32FC3,
@@ -65,6 +65,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Single,
                 ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -114,6 +115,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -146,6 +148,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -162,6 +165,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -205,6 +209,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 IeeeBinaryConvolutionMode::Normal,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -247,6 +252,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -274,6 +280,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -340,6 +347,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -356,6 +364,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 ThreadingPolicy::Adaptive,
                 ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -399,6 +408,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     EdgeMode::Clamp.as_2d(),
                     ThreadingPolicy::Adaptive,
                     ConvolutionMode::Exact,
+                    AlphaMode::Premultiplied,
                 )
                 .unwrap();
             })
@@ -415,6 +425,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     EdgeMode::Clamp.as_2d(),
                     ThreadingPolicy::Adaptive,
                     ConvolutionMode::Exact,
+                    AlphaMode::Premultiplied,
                 )
                 .unwrap();
             })
@@ -467,6 +478,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     EdgeMode::Clamp.as_2d(),
                     ThreadingPolicy::Adaptive,
                     ConvolutionMode::FixedPoint,
+                    AlphaMode::Premultiplied,
                 )
                 .unwrap();
             })
@@ -530,6 +542,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     EdgeMode::Clamp.as_2d(),
                     ThreadingPolicy::Adaptive,
                     ConvolutionMode::Exact,
+                    AlphaMode::Premultiplied,
                 )
                 .unwrap();
             })
@@ -546,6 +559,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     EdgeMode::Clamp.as_2d(),
                     ThreadingPolicy::Adaptive,
                     ConvolutionMode::FixedPoint,
+                    AlphaMode::Premultiplied,
                 )
                 .unwrap();
             })
@@ -2,7 +2,7 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
 use libblur::{
     filter_2d_rgb_fft, generate_motion_kernel, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels,
-    KernelShape, Scalar, ThreadingPolicy,
+    FftConvolutionMode, KernelShape, Scalar, ThreadingPolicy,
 };
 
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -28,7 +28,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             dimensions.1,
             FastBlurChannels::Channels3,
         );
-        let motion = generate_motion_kernel(35, 24.);
+        let motion = generate_motion_kernel(35, 24.).unwrap();
         b.iter(|| {
             filter_2d_rgb_fft::<u8, f32>(
                 &src_image,
@@ -38,6 +38,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 EdgeMode::Clamp.as_2d(),
                 Scalar::default(),
                 ThreadingPolicy::Adaptive,
+                FftConvolutionMode::Auto,
             )
             .unwrap();
         })
@@ -1,6 +1,9 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use image::{EncodableLayout, GenericImageView, ImageReader};
-use libblur::{AnisotropicRadius, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use libblur::{
+    AlphaMode, AnisotropicRadius, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy,
+};
 use opencv::core::{find_file, AlgorithmHint, Mat, Size, CV_32FC4, CV_8UC3, CV_8UC4};
 use opencv::imgcodecs::{imread, IMREAD_COLOR};
 
@@ -24,6 +27,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &mut dst_image,
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -42,6 +48,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &mut dst_image,
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -83,6 +92,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &mut dst_image,
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         })
@@ -125,6 +137,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &mut dst_image,
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Adaptive,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         });
@@ -143,6 +158,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 &mut dst_image,
                 AnisotropicRadius::new(77),
                 ThreadingPolicy::Single,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
             )
             .unwrap();
         });
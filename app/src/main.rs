@@ -37,7 +37,7 @@ use libblur::{
     fast_gaussian, fast_gaussian_next, fast_gaussian_next_u16, filter_1d_complex,
     filter_1d_complex_fixed_point, filter_2d_fft_complex, filter_2d_rgb_fft,
     filter_2d_rgb_fft_complex, filter_2d_rgba_fft, gaussian_blur, gaussian_kernel_1d, lens_kernel,
-    median_blur, sigma_size, stack_blur, stack_blur_f32, stack_blur_u16, tent_blur,
+    median_blur, sigma_size, stack_blur, stack_blur_f32, stack_blur_u16, tent_blur, AlphaMode,
     AnisotropicRadius, BilateralBlurParams, BlurImage, BlurImageMut, BoxBlurParameters,
     CLTParameters, ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams,
     ImageSize, KernelShape, Scalar, ThreadingPolicy, TransferFunction,
@@ -87,7 +87,10 @@ fn fuzz_8bit(
             x_axis_kernel: x_kernel_size as u32,
             y_axis_kernel: y_kernel_size as u32,
         },
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
         threading,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -211,6 +214,7 @@ fn main() {
     //     EdgeMode::Clamp.as_2d(),
     //     ThreadingPolicy::Single,
     //     ConvolutionMode::FixedPoint,
+    //     AlphaMode::Premultiplied,
     // )
     // .unwrap();
 
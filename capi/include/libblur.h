@@ -0,0 +1,243 @@
+#ifndef LIBBLUR_H
+#define LIBBLUR_H
+
+/* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */
+
+#include <stdint.h>
+
+// Result of every call of C API
+typedef enum LibblurStatus {
+  LIBBLUR_STATUS_OK = 0,
+  // Required pointer was null
+  LIBBLUR_STATUS_NULL_POINTER = 1,
+  // Unknown pixel type or operation is not available for this pixel type
+  LIBBLUR_STATUS_UNSUPPORTED_PIXEL_TYPE = 2,
+  // Unknown channels count
+  LIBBLUR_STATUS_INVALID_CHANNELS = 3,
+  // Unknown edge mode
+  LIBBLUR_STATUS_INVALID_EDGE_MODE = 4,
+  LIBBLUR_STATUS_ZERO_BASE_SIZE = 5,
+  LIBBLUR_STATUS_MINIMUM_SLICE_SIZE_MISMATCH = 6,
+  LIBBLUR_STATUS_MINIMUM_STRIDE_SIZE_MISMATCH = 7,
+  LIBBLUR_STATUS_ODD_KERNEL = 8,
+  LIBBLUR_STATUS_KERNEL_SIZE_MISMATCH = 9,
+  LIBBLUR_STATUS_IMAGES_MUST_MATCH = 10,
+  LIBBLUR_STATUS_STRIDE_IS_NOT_SUPPORTED = 11,
+  LIBBLUR_STATUS_FFT_CHANNELS_NOT_SUPPORTED = 12,
+  LIBBLUR_STATUS_EXCEEDING_POINTER_SIZE = 13,
+  LIBBLUR_STATUS_NEGATIVE_OR_ZERO_SIGMA = 14,
+  LIBBLUR_STATUS_INVALID_ARGUMENTS = 15,
+  LIBBLUR_STATUS_FFT_ERROR = 16,
+  LIBBLUR_STATUS_ROI_OUT_OF_BOUNDS = 17,
+  // Unexpected internal failure
+  LIBBLUR_STATUS_PANIC = 18,
+  // Parameter is out of its valid range or not finite
+  LIBBLUR_STATUS_INVALID_PARAMETER = 19,
+} LibblurStatus;
+
+// Pixel element type of [LibblurImage]
+typedef enum LibblurPixelType {
+  LIBBLUR_PIXEL_TYPE_U8 = 0,
+  LIBBLUR_PIXEL_TYPE_U16 = 1,
+  LIBBLUR_PIXEL_TYPE_F32 = 2,
+} LibblurPixelType;
+
+// Edge mode values accepted by C API, see [EdgeMode] for more info
+typedef enum LibblurEdgeMode {
+  LIBBLUR_EDGE_MODE_CLAMP = 0,
+  LIBBLUR_EDGE_MODE_WRAP = 1,
+  LIBBLUR_EDGE_MODE_REFLECT = 2,
+  LIBBLUR_EDGE_MODE_REFLECT101 = 3,
+  LIBBLUR_EDGE_MODE_CONSTANT = 4,
+} LibblurEdgeMode;
+
+// Opaque image handle.
+//
+// Created by [libblur_image_alloc] or [libblur_image_wrap], must be released by [libblur_image_free].
+typedef struct LibblurImage LibblurImage;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Allocates zeroed image with default stride.
+//
+// Returns null if arguments are invalid.
+//
+// # Arguments
+//
+// * `channels`: Channels count from 1 to 4.
+// * `pixel_type`: One of [LibblurPixelType] values.
+struct LibblurImage *libblur_image_alloc(uint32_t width,
+                                         uint32_t height,
+                                         uint32_t channels,
+                                         uint32_t pixel_type);
+
+// Wraps memory of the caller into image handle without copying.
+//
+// Memory must stay valid and must not be accessed by the caller while the handle is used.
+// Returns null if arguments are invalid.
+//
+// # Arguments
+//
+// * `data`: Pointer to the first pixel, must be aligned for `pixel_type`.
+// * `stride`: Row stride in elements, not in bytes, 0 means `width * channels`.
+// * `channels`: Channels count from 1 to 4.
+// * `pixel_type`: One of [LibblurPixelType] values.
+struct LibblurImage *libblur_image_wrap(void *data,
+                                        uint32_t width,
+                                        uint32_t height,
+                                        uint32_t stride,
+                                        uint32_t channels,
+                                        uint32_t pixel_type);
+
+// Releases image handle, wrapped memory of the caller is not released.
+void libblur_image_free(struct LibblurImage *image);
+
+// Returns pointer to the first pixel of the image, null if image is null.
+//
+// Owned images may be reallocated when used as a destination with a different size.
+void *libblur_image_data(struct LibblurImage *image);
+
+// Returns width of the image, 0 if image is null.
+uint32_t libblur_image_width(const struct LibblurImage *image);
+
+// Returns height of the image, 0 if image is null.
+uint32_t libblur_image_height(const struct LibblurImage *image);
+
+// Returns row stride of the image in elements, 0 if image is null.
+uint32_t libblur_image_stride(const struct LibblurImage *image);
+
+// Returns static null terminated description of the status.
+const char *libblur_status_description(uint32_t status);
+
+// Performs gaussian blur, see [crate::gaussian_blur] for more info.
+//
+// # Arguments
+//
+// * `kernel_size`: Odd kernel size, 0 means kernel computed from sigma.
+// * `sigma`: Sigma, 0 or negative means sigma computed from kernel size.
+// * `edge_mode`: One of [LibblurEdgeMode] values, constant edge mode uses zero border.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_gaussian_blur(const struct LibblurImage *src,
+                                         struct LibblurImage *dst,
+                                         uint32_t kernel_size,
+                                         double sigma,
+                                         uint32_t edge_mode,
+                                         uint32_t threads);
+
+// Performs box blur, see [crate::box_blur] for more info.
+//
+// # Arguments
+//
+// * `kernel_size`: Odd kernel size.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_box_blur(const struct LibblurImage *src,
+                                    struct LibblurImage *dst,
+                                    uint32_t kernel_size,
+                                    uint32_t edge_mode,
+                                    const double *border_constant,
+                                    uint32_t threads);
+
+// Performs stack blur in place, see [crate::stack_blur] for more info.
+//
+// # Arguments
+//
+// * `radius`: Blur radius.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_stack_blur(struct LibblurImage *image,
+                                      uint32_t radius,
+                                      uint32_t edge_mode,
+                                      const double *border_constant,
+                                      uint32_t threads);
+
+// Performs fast gaussian approximation in place, see [crate::fast_gaussian] for more info.
+//
+// # Arguments
+//
+// * `radius`: Blur radius.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_fast_gaussian(struct LibblurImage *image,
+                                         uint32_t radius,
+                                         uint32_t edge_mode,
+                                         const double *border_constant,
+                                         uint32_t threads);
+
+// Performs bilateral filter on u8 images, see [crate::bilateral_filter] for more info.
+//
+// # Arguments
+//
+// * `kernel_size`: Odd kernel size.
+// * `spatial_sigma`: Spatial sigma, must be positive.
+// * `range_sigma`: Range sigma, must be positive.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_bilateral_filter(const struct LibblurImage *src,
+                                            struct LibblurImage *dst,
+                                            uint32_t kernel_size,
+                                            float spatial_sigma,
+                                            float range_sigma,
+                                            uint32_t edge_mode,
+                                            const double *border_constant,
+                                            uint32_t threads);
+
+// Performs fast bilateral filter, see [crate::fast_bilateral_filter] for more info.
+//
+// # Arguments
+//
+// * `spatial_sigma`: Spatial sigma, must be positive.
+// * `range_sigma`: Range sigma, must be positive.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_fast_bilateral_filter(const struct LibblurImage *src,
+                                                 struct LibblurImage *dst,
+                                                 float spatial_sigma,
+                                                 float range_sigma,
+                                                 uint32_t threads);
+
+// Performs median blur, see [crate::median_blur_constant_time], [crate::median_blur_u16]
+// and [crate::median_blur_f32] for more info.
+//
+// # Arguments
+//
+// * `radius`: Median radius.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_median_blur(const struct LibblurImage *src,
+                                       struct LibblurImage *dst,
+                                       uint32_t radius,
+                                       uint32_t edge_mode,
+                                       const double *border_constant,
+                                       uint32_t threads);
+
+// Performs 2D convolution with arbitrary kernel, see [crate::filter_2d] for more info.
+//
+// # Arguments
+//
+// * `kernel`: Row-major kernel of `kernel_width * kernel_height` values.
+// * `kernel_width`: Odd kernel width.
+// * `kernel_height`: Odd kernel height.
+// * `edge_mode`: One of [LibblurEdgeMode] values.
+// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+// * `threads`: 0 means adaptive threading, otherwise threads count.
+enum LibblurStatus libblur_filter_2d(const struct LibblurImage *src,
+                                     struct LibblurImage *dst,
+                                     const float *kernel,
+                                     uint32_t kernel_width,
+                                     uint32_t kernel_height,
+                                     uint32_t edge_mode,
+                                     const double *border_constant,
+                                     uint32_t threads);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LIBBLUR_H */
@@ -31,7 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    BlurImage, BlurImageMut, BoxBlurParameters, BufferStore, FastBlurChannels, ThreadingPolicy,
+    AlphaMode, BlurImage, BlurImageMut, BoxBlurParameters, BufferStore, EdgeMode, EdgeMode2D,
+    FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -117,7 +118,10 @@ fn fuzz_16bit(
             x_axis_kernel: x_kernel_size as u32,
             y_axis_kernel: y_kernel_size as u32,
         },
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
         threading_policy,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -31,8 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    fast_gaussian, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
-    FastBlurChannels, ThreadingPolicy,
+    fast_gaussian, AlphaMode, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
+    FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -112,6 +112,8 @@ fn fuzz_image(
         AnisotropicRadius::create(x_radius as u32, y_radius as u32),
         threading_policy,
         EdgeMode2D::new(edge_mode),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 
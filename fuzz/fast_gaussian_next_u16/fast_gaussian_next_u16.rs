@@ -31,8 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    fast_gaussian_next_u16, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
-    FastBlurChannels, ThreadingPolicy,
+    fast_gaussian_next_u16, AlphaMode, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode,
+    EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -105,6 +105,8 @@ fn fuzz_image(
         AnisotropicRadius::create(x_radius as u32, y_radius as u32),
         ThreadingPolicy::Single,
         EdgeMode2D::new(edge_mode),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
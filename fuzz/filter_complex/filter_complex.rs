@@ -183,6 +183,18 @@ fn fuzz_8bit(
             )
             .unwrap();
         }
+        FastBlurChannels::Channels2 => {
+            filter_1d_complex::<u8, f32, 2>(
+                &src_image,
+                &mut dst_image,
+                &x_kernel,
+                &y_kernel,
+                edge_modes,
+                Scalar::default(),
+                threading_policy,
+            )
+            .unwrap();
+        }
         FastBlurChannels::Channels3 => {
             filter_1d_complex::<u8, f32, 3>(
                 &src_image,
@@ -241,6 +253,18 @@ fn fuzz_16bit(
             )
             .unwrap();
         }
+        FastBlurChannels::Channels2 => {
+            filter_1d_complex::<u16, f32, 2>(
+                &src_image,
+                &mut dst_image,
+                &x_kernel,
+                &y_kernel,
+                edge_mode,
+                Scalar::default(),
+                threading_policy,
+            )
+            .unwrap();
+        }
         FastBlurChannels::Channels3 => {
             filter_1d_complex::<u16, f32, 3>(
                 &src_image,
@@ -299,6 +323,18 @@ fn fuzz_f32(
             )
             .unwrap();
         }
+        FastBlurChannels::Channels2 => {
+            filter_1d_complex::<f32, f32, 2>(
+                &src_image,
+                &mut dst_image,
+                &x_kernel,
+                &y_kernel,
+                edge_mode,
+                Scalar::default(),
+                threading_policy,
+            )
+            .unwrap();
+        }
         FastBlurChannels::Channels3 => {
             filter_1d_complex::<f32, f32, 3>(
                 &src_image,
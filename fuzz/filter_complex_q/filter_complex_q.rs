@@ -172,6 +172,18 @@ fn fuzz_8bit(
             )
             .unwrap();
         }
+        FastBlurChannels::Channels2 => {
+            filter_1d_complex_fixed_point::<u8, i16, f32, 2>(
+                &src_image,
+                &mut dst_image,
+                &x_kernel,
+                &y_kernel,
+                edge_mode,
+                Scalar::default(),
+                threading_policy,
+            )
+            .unwrap();
+        }
         FastBlurChannels::Channels3 => {
             filter_1d_complex_fixed_point::<u8, i16, f32, 3>(
                 &src_image,
@@ -230,6 +242,18 @@ fn fuzz_16bit(
             )
             .unwrap();
         }
+        FastBlurChannels::Channels2 => {
+            filter_1d_complex_fixed_point::<u16, i32, f32, 2>(
+                &src_image,
+                &mut dst_image,
+                &x_kernel,
+                &y_kernel,
+                edge_mode,
+                Scalar::default(),
+                threading_policy,
+            )
+            .unwrap();
+        }
         FastBlurChannels::Channels3 => {
             filter_1d_complex_fixed_point::<u16, i32, f32, 3>(
                 &src_image,
@@ -31,8 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels,
-    GaussianBlurParams, ThreadingPolicy,
+    AlphaMode, BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D,
+    FastBlurChannels, GaussianBlurParams, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -138,6 +138,7 @@ fn fuzz_8bit(
         edge_mode,
         ThreadingPolicy::Single,
         ConvolutionMode::FixedPoint,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 
@@ -148,6 +149,7 @@ fn fuzz_8bit(
         edge_mode,
         ThreadingPolicy::Single,
         ConvolutionMode::Exact,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -31,7 +31,7 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    BlurImage, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D, FastBlurChannels,
+    AlphaMode, BlurImage, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D, FastBlurChannels,
     GaussianBlurParams, IeeeBinaryConvolutionMode, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
@@ -126,6 +126,7 @@ fn fuzz_f32(
         } else {
             IeeeBinaryConvolutionMode::Normal
         },
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -31,8 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels,
-    GaussianBlurParams, ThreadingPolicy,
+    AlphaMode, BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D,
+    FastBlurChannels, GaussianBlurParams, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -133,6 +133,7 @@ fn fuzz_8bit(
             ThreadingPolicy::Single
         },
         ConvolutionMode::FixedPoint,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 
@@ -147,6 +148,7 @@ fn fuzz_8bit(
             ThreadingPolicy::Single
         },
         ConvolutionMode::Exact,
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -31,8 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels,
-    GaussianBlurParams, ThreadingPolicy,
+    AlphaMode, BlurImage, BlurImageMut, BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D,
+    FastBlurChannels, GaussianBlurParams, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -126,6 +126,7 @@ fn fuzz_16bit(
         } else {
             ConvolutionMode::Exact
         },
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
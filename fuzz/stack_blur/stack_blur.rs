@@ -31,7 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    stack_blur, AnisotropicRadius, BlurImageMut, BufferStore, FastBlurChannels, ThreadingPolicy,
+    stack_blur, AlphaMode, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
+    FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -113,6 +114,9 @@ fn fuzz_image(
         &mut dst_image,
         AnisotropicRadius::create(x_radius as u32, y_radius as u32),
         threading_policy,
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
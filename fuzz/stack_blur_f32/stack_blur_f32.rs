@@ -31,7 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    stack_blur_f32, AnisotropicRadius, BlurImageMut, BufferStore, FastBlurChannels, ThreadingPolicy,
+    stack_blur_f32, AlphaMode, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
+    FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -112,6 +113,9 @@ fn fuzz_image(
         &mut dst_image,
         AnisotropicRadius::create(x_radius as u32, y_radius as u32),
         threading_policy,
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
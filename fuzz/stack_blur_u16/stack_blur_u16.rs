@@ -31,7 +31,8 @@
 
 use arbitrary::Arbitrary;
 use libblur::{
-    stack_blur_u16, AnisotropicRadius, BlurImageMut, BufferStore, FastBlurChannels, ThreadingPolicy,
+    stack_blur_u16, AlphaMode, AnisotropicRadius, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D,
+    FastBlurChannels, Scalar, ThreadingPolicy,
 };
 use libfuzzer_sys::fuzz_target;
 
@@ -75,6 +76,18 @@ fuzz_target!(|data: SrcImage| {
                 data.additional_padding as usize % 50,
             );
         }
+        FastBlurChannels::Channels2 => {
+            fuzz_image(
+                data.src_width as usize,
+                data.src_height as usize,
+                data.x_radius as usize,
+                data.y_radius as usize,
+                FastBlurChannels::Channels2,
+                data.value,
+                mp,
+                data.additional_padding as usize % 50,
+            );
+        }
         FastBlurChannels::Channels3 => {
             fuzz_image(
                 data.src_width as usize,
@@ -130,12 +143,18 @@ fn fuzz_image(
         &mut dst_image,
         AnisotropicRadius::create(x_radius as u32, y_radius as u32),
         threading_policy,
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
     stack_blur_u16(
         &mut dst_image,
         AnisotropicRadius::create(x_radius as u32 + 500, y_radius as u32 + 500),
         threading_policy,
+        EdgeMode2D::new(EdgeMode::Clamp),
+        Scalar::default(),
+        AlphaMode::Premultiplied,
     )
     .unwrap();
 }
@@ -29,9 +29,9 @@
 #![allow(clippy::manual_clamp)]
 
 use crate::{
-    AnisotropicRadius, BlurError, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode, EdgeMode2D,
-    FastBlurChannels, GaussianBlurParams, KernelShape, Scalar, ThreadingPolicy, TransferFunction,
-    filter_2d, filter_2d_fft, gaussian_blur, stack_blur,
+    AlphaMode, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode,
+    EdgeMode2D, FastBlurChannels, FftConvolutionMode, GaussianBlurParams, KernelShape, Scalar,
+    ThreadingPolicy, TransferFunction, filter_2d, filter_2d_fft, gaussian_blur, stack_blur,
 };
 use num_traits::AsPrimitive;
 use std::fmt::Debug;
@@ -66,6 +66,19 @@ impl Blend<u8> for u8 {
                         ((m as u32 * (*src) as u32 + (255 - m as u32) * (*dst) as u32) / 255) as u8;
                 }
             }
+            FastBlurChannels::Channels2 => {
+                for ((dst, src), mask) in destination
+                    .as_chunks_mut::<2>()
+                    .0
+                    .iter_mut()
+                    .zip(source.as_chunks::<2>().0.iter())
+                    .zip(mask.iter())
+                {
+                    let m = *mask;
+                    dst[0] =
+                        ((m as u32 * src[0] as u32 + (255 - m as u32) * dst[0] as u32) / 255) as u8;
+                }
+            }
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 for ((dst, src), mask) in destination
                     .chunks_exact_mut(channels)
@@ -97,6 +110,9 @@ impl BlurEdges<u8> for u8 {
             &mut blur_img,
             AnisotropicRadius::new(radius),
             ThreadingPolicy::Adaptive,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
     }
@@ -135,6 +151,7 @@ impl Blur<u8> for u8 {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Adaptive,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
     }
@@ -205,6 +222,11 @@ impl Gamma<u8> for u8 {
             FastBlurChannels::Plane => gamma.iter_mut().for_each(|dst| {
                 *dst = lut_table[*dst as usize];
             }),
+            FastBlurChannels::Channels2 => {
+                gamma.as_chunks_mut::<2>().0.iter_mut().for_each(|dst| {
+                    dst[0] = lut_table[dst[0] as usize];
+                })
+            }
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 gamma.chunks_exact_mut(channels.channels()).for_each(|dst| {
                     dst[0] = lut_table[dst[0] as usize];
@@ -273,6 +295,7 @@ impl Edges<u8> for u8 {
                 edge_modes,
                 border_constant,
                 ThreadingPolicy::Adaptive,
+                FftConvolutionMode::Auto,
             )
             .unwrap();
         } else {
@@ -318,6 +341,13 @@ impl Linearize<u8> for u8 {
             FastBlurChannels::Plane => dst.iter_mut().zip(source).for_each(|(dst, src)| {
                 *dst = lut_table[*src as usize];
             }),
+            FastBlurChannels::Channels2 => dst
+                .chunks_exact_mut(2)
+                .zip(source.chunks_exact(2))
+                .for_each(|(dst, src)| {
+                    dst[0] = lut_table[src[0] as usize];
+                    dst[1] = src[1];
+                }),
             FastBlurChannels::Channels3 => dst
                 .chunks_exact_mut(3)
                 .zip(source.chunks_exact(3))
@@ -358,6 +388,7 @@ impl Grayscale<u8> for u8 {
 
         match channels {
             FastBlurChannels::Plane => source.to_vec(),
+            FastBlurChannels::Channels2 => source.chunks_exact(2).map(|src| src[0]).collect(),
             FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => {
                 let mut dest = vec![0u8; width * height];
 
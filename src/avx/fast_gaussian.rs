@@ -28,6 +28,7 @@
 use crate::EdgeMode;
 use crate::avx::fast_gaussian_next::AvxSseI32x8;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::store_u8_u32;
 use crate::sse::utils::load_u8_s32_fast;
 use crate::unsafe_slice::UnsafeSlice;
@@ -78,7 +79,9 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
     end: u32,
     edge_mode: EdgeMode,
 ) {
-    let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(1024);
+    let ring_size = fg_ring_size(radius);
+    let ring_mask = ring_size as i64 - 1;
+    let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(ring_size);
     let buffer = full_buffer.as_mut_slice();
 
     let initial_sum = ((radius * radius) >> 1) as i32;
@@ -114,7 +117,7 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
 
                     let ps01 = _mm256_cvtepi32_ps(summs0);
                     let ps23 = _mm256_cvtepi32_ps(summs1);
-                    let ps45 = _mm256_cvtepi32_ps(summs1);
+                    let ps45 = _mm256_cvtepi32_ps(summs2);
 
                     let r01 = _mm256_mul_ps(ps01, v_weight);
                     let r23 = _mm256_mul_ps(ps23, v_weight);
@@ -145,8 +148,8 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
                     store_u8_u32::<CN>(dst_ptr4, prepared_px4);
                     store_u8_u32::<CN>(dst_ptr5, prepared_px5);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -167,7 +170,7 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
                     diffs1 = _mm256_add_epi32(diffs1, _mm256_sub_epi32(a_stored1, d_stored1));
                     diffs2 = _mm256_add_epi32(diffs2, _mm256_sub_epi32(a_stored2, d_stored2));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm256_load_si256(da_b.as_ptr().cast());
                     let mut stored1 = _mm256_load_si256(da_b[1..].as_ptr().cast());
@@ -199,7 +202,7 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
                 let pixel_color4 = load_u8_s32_fast::<CN>(s_ptr4);
                 let pixel_color5 = load_u8_s32_fast::<CN>(s_ptr5);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
 
                 let px01 = _mm256_inserti128_si256::<1>(
                     _mm256_castsi128_si256(pixel_color0),
@@ -252,8 +255,8 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u8_u32::<CN>(dst_ptr, pixel_u32);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let mut d_stored = _mm_load_si128(d_buf_ptr.as_ptr().cast());
@@ -264,7 +267,7 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = _mm_load_si128(buf_ptr.as_ptr().cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -278,7 +281,7 @@ fn fg_horizontal_pass_avx_u8_impl<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -318,7 +321,9 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
     end: u32,
     edge_mode: EdgeMode,
 ) {
-    let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(1024);
+    let ring_size = fg_ring_size(radius);
+    let ring_mask = ring_size as i64 - 1;
+    let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(ring_size);
     let buffer = full_buffer.as_mut_slice();
 
     let initial_sum = ((radius * radius) >> 1) as i32;
@@ -387,8 +392,8 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
                     store_u8_u32::<CN>(dst_ptr4, prepared_px4);
                     store_u8_u32::<CN>(dst_ptr5, prepared_px5);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -409,7 +414,7 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
                     diffs1 = _mm256_add_epi32(diffs1, _mm256_sub_epi32(a_stored1, d_stored1));
                     diffs2 = _mm256_add_epi32(diffs2, _mm256_sub_epi32(a_stored2, d_stored2));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm256_load_si256(da_b.as_ptr().cast());
                     let mut stored1 = _mm256_load_si256(da_b[1..].as_ptr().cast());
@@ -441,7 +446,7 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
                 let pixel_color4 = load_u8_s32_fast::<CN>(s_ptr4);
                 let pixel_color5 = load_u8_s32_fast::<CN>(s_ptr5);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 let px01 = _mm256_inserti128_si256::<1>(
                     _mm256_castsi128_si256(pixel_color0),
@@ -495,8 +500,8 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u8_u32::<CN>(dst_ptr, pixel_u32);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_load_si128(d_buf_ptr.cast());
@@ -507,7 +512,7 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_load_si128(buf_ptr.cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -521,7 +526,7 @@ fn fg_vertical_pass_avx_u8_def<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
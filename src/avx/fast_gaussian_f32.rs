@@ -29,6 +29,7 @@ use crate::EdgeMode;
 use crate::avx::fast_gaussian_next_f32::AvxSseF32x8;
 use crate::avx::utils::_mm256_opt_fnmlaf_ps;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::{_mm_opt_fnmlaf_ps, load_f32, store_f32};
 use crate::unsafe_slice::UnsafeSlice;
 use crate::util::ScratchBuffer;
@@ -107,7 +108,9 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
         edge_mode: EdgeMode,
     ) {
         unsafe {
-            let mut full_buffer = ScratchBuffer::<[AvxSseF32x8; 3], 1024>::new(1024);
+            let ring_size = fg_ring_size(radius);
+            let ring_mask = ring_size as i64 - 1;
+            let mut full_buffer = ScratchBuffer::<[AvxSseF32x8; 3], 1024>::new(ring_size);
             let buffer = full_buffer.as_mut_slice();
 
             let radius_64 = radius as i64;
@@ -157,8 +160,8 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                         store_f32::<CN>(dst_ptr4, _mm256_castps256_ps128(prepared_px2));
                         store_f32::<CN>(dst_ptr5, _mm256_extractf128_ps::<1>(prepared_px2));
 
-                        let arr_index = ((x - radius_64) & 1023) as usize;
-                        let d_arr_index = (x & 1023) as usize;
+                        let arr_index = ((x - radius_64) & ring_mask) as usize;
+                        let d_arr_index = (x & ring_mask) as usize;
 
                         let da_b = buffer.get_unchecked(d_arr_index);
                         let da = buffer.get_unchecked(arr_index);
@@ -184,7 +187,7 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                             _mm256_opt_fnmlaf_ps::<FMA>(a_s2, d_s2, v_double),
                         );
                     } else if x + radius_64 >= 0 {
-                        let arr_index = (x & 1023) as usize;
+                        let arr_index = (x & ring_mask) as usize;
                         let da_b = buffer.get_unchecked(arr_index);
                         let s0 = _mm256_load_ps(da_b.as_ptr().cast());
                         let s1 = _mm256_load_ps(da_b[1..].as_ptr().cast());
@@ -212,7 +215,7 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                     let px4 = load_f32::<CN>(s_ptr4);
                     let px5 = load_f32::<CN>(s_ptr5);
 
-                    let arr_index = ((x + radius_64) & 1023) as usize;
+                    let arr_index = ((x + radius_64) & ring_mask) as usize;
 
                     let px01 = _mm256_insertf128_ps::<1>(_mm256_castps128_ps256(px0), px1);
                     let px23 = _mm256_insertf128_ps::<1>(_mm256_castps128_ps256(px2), px3);
@@ -254,8 +257,8 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                         let dst_ptr = bytes.get_ptr(bytes_offset);
                         store_f32::<CN>(dst_ptr, pixel);
 
-                        let arr_index = ((x - radius_64) & 1023) as usize;
-                        let d_arr_index = (x & 1023) as usize;
+                        let arr_index = ((x - radius_64) & ring_mask) as usize;
+                        let d_arr_index = (x & ring_mask) as usize;
 
                         let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                         let d_stored = _mm_load_ps(d_buf_ptr.cast());
@@ -272,7 +275,7 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                             ),
                         );
                     } else if x + radius_64 >= 0 {
-                        let arr_index = (x & 1023) as usize;
+                        let arr_index = (x & ring_mask) as usize;
                         let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                         let stored = _mm_load_ps(buf_ptr.cast());
                         diffs = _mm_opt_fnmlaf_ps::<FMA>(
@@ -289,7 +292,7 @@ impl<const CN: usize, const FMA: bool> HorizontalExecutionUnit<CN, FMA> {
                     let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                     let pixel_color = load_f32::<CN>(s_ptr);
 
-                    let arr_index = ((x + radius_64) & 1023) as usize;
+                    let arr_index = ((x + radius_64) & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                     diffs = _mm_add_ps(diffs, pixel_color);
@@ -371,7 +374,9 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
         edge_mode: EdgeMode,
     ) {
         unsafe {
-            let mut full_buffer = ScratchBuffer::<[AvxSseF32x8; 3], 1024>::new(1024);
+            let ring_size = fg_ring_size(radius);
+            let ring_mask = ring_size as i64 - 1;
+            let mut full_buffer = ScratchBuffer::<[AvxSseF32x8; 3], 1024>::new(ring_size);
             let buffer = full_buffer.as_mut_slice();
 
             let v_double = _mm256_set1_ps(2.);
@@ -422,8 +427,8 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                         store_f32::<CN>(dst_ptr4, _mm256_castps256_ps128(prepared_px2));
                         store_f32::<CN>(dst_ptr5, _mm256_extractf128_ps::<1>(prepared_px2));
 
-                        let arr_index = ((y - radius_64) & 1023) as usize;
-                        let d_arr_index = (y & 1023) as usize;
+                        let arr_index = ((y - radius_64) & ring_mask) as usize;
+                        let d_arr_index = (y & ring_mask) as usize;
 
                         let da_b = buffer.get_unchecked(d_arr_index);
                         let da = buffer.get_unchecked(arr_index);
@@ -449,7 +454,7 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                             _mm256_opt_fnmlaf_ps::<FMA>(a_s2, d_s2, v_double),
                         );
                     } else if y + radius_64 >= 0 {
-                        let arr_index = (y & 1023) as usize;
+                        let arr_index = (y & ring_mask) as usize;
                         let da_b = buffer.get_unchecked(arr_index);
                         let s0 = _mm256_load_ps(da_b.as_ptr().cast());
                         let s1 = _mm256_load_ps(da_b[1..].as_ptr().cast());
@@ -477,7 +482,7 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                     let px4 = load_f32::<CN>(s_ptr4);
                     let px5 = load_f32::<CN>(s_ptr5);
 
-                    let arr_index = ((y + radius_64) & 1023) as usize;
+                    let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                     let px01 = _mm256_insertf128_ps::<1>(_mm256_castps128_ps256(px0), px1);
                     let px23 = _mm256_insertf128_ps::<1>(_mm256_castps128_ps256(px2), px3);
@@ -520,8 +525,8 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                         let dst_ptr = bytes.get_ptr(bytes_offset);
                         store_f32::<CN>(dst_ptr, pixel);
 
-                        let arr_index = ((y - radius_64) & 1023) as usize;
-                        let d_arr_index = (y & 1023) as usize;
+                        let arr_index = ((y - radius_64) & ring_mask) as usize;
+                        let d_arr_index = (y & ring_mask) as usize;
 
                         let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                         let d_stored = _mm_load_ps(d_buf_ptr.cast());
@@ -538,7 +543,7 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                             ),
                         );
                     } else if y + radius_64 >= 0 {
-                        let arr_index = (y & 1023) as usize;
+                        let arr_index = (y & ring_mask) as usize;
                         let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                         let stored = _mm_load_ps(buf_ptr.cast());
                         diffs = _mm_opt_fnmlaf_ps::<FMA>(
@@ -555,7 +560,7 @@ impl<const CN: usize, const FMA: bool> VerticalExecutionUnit<CN, FMA> {
                     let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                     let pixel_color = load_f32::<CN>(s_ptr);
 
-                    let arr_index = ((y + radius_64) & 1023) as usize;
+                    let arr_index = ((y + radius_64) & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                     diffs = _mm_add_ps(diffs, pixel_color);
@@ -28,6 +28,7 @@
 use crate::EdgeMode;
 use crate::avx::fast_gaussian_next::AvxSseI32x8;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::store_u16_u32;
 use crate::sse::utils::load_u16_s32_fast;
 use crate::unsafe_slice::UnsafeSlice;
@@ -63,7 +64,9 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(ring_size);
         let buffer = full_buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -122,8 +125,8 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
                     store_u16_u32::<CN>(bytes.get_ptr(current_y4 + current_px), prepared_px4);
                     store_u16_u32::<CN>(bytes.get_ptr(current_y5 + current_px), prepared_px5);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -144,7 +147,7 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
                     diffs1 = _mm256_add_epi32(diffs1, _mm256_sub_epi32(a_stored1, d_stored1));
                     diffs2 = _mm256_add_epi32(diffs2, _mm256_sub_epi32(a_stored2, d_stored2));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm256_load_si256(da_b.as_ptr().cast());
                     let mut stored1 = _mm256_load_si256(da_b[1..].as_ptr().cast());
@@ -169,7 +172,7 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
                 let pixel_color4 = load_u16_s32_fast::<CN>(bytes.get_ptr(current_y4 + next_row_px));
                 let pixel_color5 = load_u16_s32_fast::<CN>(bytes.get_ptr(current_y5 + next_row_px));
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
 
                 let px01 = _mm256_inserti128_si256::<1>(
                     _mm256_castsi128_si256(pixel_color0),
@@ -221,8 +224,8 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
 
                     store_u16_u32::<CN>(bytes.get_ptr(bytes_offset), pixel_u32);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_load_si128(d_buf_ptr.cast());
@@ -233,7 +236,7 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_load_si128(buf_ptr.cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -247,7 +250,7 @@ fn fg_horizontal_pass_avx_u16_def<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_u16_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -288,7 +291,9 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[AvxSseI32x8; 3], 1024>::new(ring_size);
         let buffer = full_buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -356,8 +361,8 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
                     store_u16_u32::<CN>(dst_ptr4, prepared_px4);
                     store_u16_u32::<CN>(dst_ptr5, prepared_px5);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -378,7 +383,7 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
                     diffs1 = _mm256_add_epi32(diffs1, _mm256_sub_epi32(a_stored1, d_stored1));
                     diffs2 = _mm256_add_epi32(diffs2, _mm256_sub_epi32(a_stored2, d_stored2));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm256_load_si256(da_b.as_ptr().cast());
                     let mut stored1 = _mm256_load_si256(da_b[1..].as_ptr().cast());
@@ -403,7 +408,7 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
                 let pixel_color4 = load_u16_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px4));
                 let pixel_color5 = load_u16_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px5));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 let px01 = _mm256_inserti128_si256::<1>(
                     _mm256_castsi128_si256(pixel_color0),
@@ -456,8 +461,8 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
 
                     store_u16_u32::<CN>(bytes.get_ptr(bytes_offset), pixel_u32);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_load_si128(d_buf_ptr.cast());
@@ -468,7 +473,7 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_load_si128(buf_ptr.cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -482,7 +487,7 @@ fn fg_vertical_pass_avx_u16_def<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u16_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -29,8 +29,8 @@
 #![allow(clippy::needless_range_loop)]
 
 use crate::avx::median::median_3::{SimdU8, load8, load16, load32};
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::{BlurImage, BlurImageMut, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use std::arch::x86_64::{
     _mm_storeu_si64, _mm_storeu_si128, _mm256_castsi256_si128, _mm256_storeu_si256,
 };
@@ -184,7 +184,7 @@ pub(crate) fn avx_median_blur_7x7(
     threading_policy: ThreadingPolicy,
 ) {
     let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let dst_stride = dst.row_stride() as usize;
     let height = src.height as usize;
     let src_stride = src.row_stride() as usize;
@@ -214,6 +214,11 @@ fn write_scalar_7x7(dst: &mut [u8], rows: [&[u8]; 7], x: usize, width: usize, ch
             let px = load_scalar_7x7::<1>(rows, x, width);
             dst[x] = px[0];
         }
+        2 => {
+            let px = load_scalar_7x7::<2>(rows, x, width);
+            dst[x] = px[0];
+            dst[x + 1] = px[1];
+        }
         3 => {
             let px = load_scalar_7x7::<3>(rows, x, width);
             dst[x] = px[0];
@@ -36,6 +36,7 @@ mod fast_gaussian_next_u16_large;
 mod fast_gaussian_u16;
 mod median;
 mod pack;
+mod premultiply;
 mod utils;
 mod v_load;
 mod v_store;
@@ -51,5 +52,9 @@ pub(crate) use fast_gaussian_next_u16_large::{
 pub(crate) use fast_gaussian_u16::{fg_horizontal_pass_avx_u16, fg_vertical_pass_avx_u16};
 pub(crate) use median::{avx_median_blur_3x3, avx_median_blur_5x5, avx_median_blur_7x7};
 pub(crate) use pack::*;
+pub(crate) use premultiply::{
+    premultiply_row_avx_f32, premultiply_row_avx_u8, premultiply_row_avx_u16,
+    unpremultiply_row_avx_f32, unpremultiply_row_avx_u8, unpremultiply_row_avx_u16,
+};
 pub(crate) use v_load::*;
 pub(crate) use v_store::*;
@@ -0,0 +1,211 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::premultiply::{
+    PREMULTIPLY_U16_INV_MAX, premultiply_row_f32, premultiply_row_u8, premultiply_row_u16,
+    unpremultiply_row_f32, unpremultiply_row_u8, unpremultiply_row_u16,
+};
+use std::arch::x86_64::*;
+
+#[inline(always)]
+unsafe fn mul_div255_u16(v: __m256i, a: __m256i) -> __m256i {
+    unsafe {
+        let t = _mm256_add_epi16(_mm256_mullo_epi16(v, a), _mm256_set1_epi16(128));
+        _mm256_srli_epi16::<8>(_mm256_add_epi16(t, _mm256_srli_epi16::<8>(t)))
+    }
+}
+
+/// Scales two RGBA pixels held in f32 by `max / alpha` and clamps them to `max`,
+/// zero alpha gives zero.
+#[inline(always)]
+unsafe fn unpremultiply_ps(p: __m256, max: __m256) -> __m256i {
+    unsafe {
+        let a = _mm256_shuffle_ps::<0xFF>(p, p);
+        let mask = _mm256_cmp_ps::<_CMP_NEQ_UQ>(a, _mm256_setzero_ps());
+        let scale = _mm256_and_ps(_mm256_div_ps(max, a), mask);
+        let r = _mm256_min_ps(
+            _mm256_add_ps(_mm256_mul_ps(p, scale), _mm256_set1_ps(0.5)),
+            max,
+        );
+        _mm256_cvttps_epi32(r)
+    }
+}
+
+#[inline(always)]
+unsafe fn premultiply_ps_u16(p: __m256) -> __m256i {
+    unsafe {
+        let a = _mm256_shuffle_ps::<0xFF>(p, p);
+        let scale = _mm256_mul_ps(a, _mm256_set1_ps(PREMULTIPLY_U16_INV_MAX));
+        _mm256_cvttps_epi32(_mm256_add_ps(_mm256_mul_ps(p, scale), _mm256_set1_ps(0.5)))
+    }
+}
+
+/// Packs four RGBA pixels stored as i32 pairs back to u16 in original order.
+#[inline(always)]
+unsafe fn pack_pixels_u16(r0: __m256i, r1: __m256i) -> __m256i {
+    unsafe { _mm256_permute4x64_epi64::<0b1101_1000>(_mm256_packus_epi32(r0, r1)) }
+}
+
+pub(crate) fn premultiply_row_avx_u8(row: &mut [u8]) {
+    unsafe { premultiply_row_avx_u8_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn premultiply_row_avx_u8_impl(row: &mut [u8]) {
+    unsafe {
+        let shuffle_alpha = _mm256_setr_epi8(
+            3, 3, 3, 3, 7, 7, 7, 7, 11, 11, 11, 11, 15, 15, 15, 15, 3, 3, 3, 3, 7, 7, 7, 7, 11, 11,
+            11, 11, 15, 15, 15, 15,
+        );
+        let alpha_mask = _mm256_setr_epi8(
+            0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0,
+            -1, 0, 0, 0, -1,
+        );
+        let zeros = _mm256_setzero_si256();
+        let mut chunks = row.chunks_exact_mut(32);
+        for chunk in &mut chunks {
+            let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let a = _mm256_shuffle_epi8(v, shuffle_alpha);
+            let lo = mul_div255_u16(
+                _mm256_unpacklo_epi8(v, zeros),
+                _mm256_unpacklo_epi8(a, zeros),
+            );
+            let hi = mul_div255_u16(
+                _mm256_unpackhi_epi8(v, zeros),
+                _mm256_unpackhi_epi8(a, zeros),
+            );
+            let r = _mm256_blendv_epi8(_mm256_packus_epi16(lo, hi), v, alpha_mask);
+            _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, r);
+        }
+        premultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_avx_u8(row: &mut [u8]) {
+    unsafe { unpremultiply_row_avx_u8_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unpremultiply_row_avx_u8_impl(row: &mut [u8]) {
+    unsafe {
+        let alpha_mask = _mm_setr_epi8(0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1, 0, 0, 0, -1);
+        let max = _mm256_set1_ps(255.);
+        let mut chunks = row.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            let p0 = _mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(v));
+            let p1 = _mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(_mm_srli_si128::<8>(v)));
+            let packed = pack_pixels_u16(unpremultiply_ps(p0, max), unpremultiply_ps(p1, max));
+            let packed = _mm_packus_epi16(
+                _mm256_castsi256_si128(packed),
+                _mm256_extracti128_si256::<1>(packed),
+            );
+            let r = _mm_blendv_epi8(packed, v, alpha_mask);
+            _mm_storeu_si128(chunk.as_mut_ptr() as *mut __m128i, r);
+        }
+        unpremultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn premultiply_row_avx_u16(row: &mut [u16]) {
+    unsafe { premultiply_row_avx_u16_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn premultiply_row_avx_u16_impl(row: &mut [u16]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let p0 = _mm256_cvtepi32_ps(_mm256_cvtepu16_epi32(_mm256_castsi256_si128(v)));
+            let p1 = _mm256_cvtepi32_ps(_mm256_cvtepu16_epi32(_mm256_extracti128_si256::<1>(v)));
+            let packed = pack_pixels_u16(premultiply_ps_u16(p0), premultiply_ps_u16(p1));
+            let r = _mm256_blend_epi16::<0b1000_1000>(packed, v);
+            _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, r);
+        }
+        premultiply_row_u16(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_avx_u16(row: &mut [u16]) {
+    unsafe { unpremultiply_row_avx_u16_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unpremultiply_row_avx_u16_impl(row: &mut [u16]) {
+    unsafe {
+        let max = _mm256_set1_ps(65535.);
+        let mut chunks = row.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let p0 = _mm256_cvtepi32_ps(_mm256_cvtepu16_epi32(_mm256_castsi256_si128(v)));
+            let p1 = _mm256_cvtepi32_ps(_mm256_cvtepu16_epi32(_mm256_extracti128_si256::<1>(v)));
+            let packed = pack_pixels_u16(unpremultiply_ps(p0, max), unpremultiply_ps(p1, max));
+            let r = _mm256_blend_epi16::<0b1000_1000>(packed, v);
+            _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, r);
+        }
+        unpremultiply_row_u16(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn premultiply_row_avx_f32(row: &mut [f32]) {
+    unsafe { premultiply_row_avx_f32_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn premultiply_row_avx_f32_impl(row: &mut [f32]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            let p = _mm256_loadu_ps(chunk.as_ptr());
+            let a = _mm256_shuffle_ps::<0xFF>(p, p);
+            let r = _mm256_blend_ps::<0b1000_1000>(_mm256_mul_ps(p, a), p);
+            _mm256_storeu_ps(chunk.as_mut_ptr(), r);
+        }
+        premultiply_row_f32(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_avx_f32(row: &mut [f32]) {
+    unsafe { unpremultiply_row_avx_f32_impl(row) }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unpremultiply_row_avx_f32_impl(row: &mut [f32]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            let p = _mm256_loadu_ps(chunk.as_ptr());
+            let a = _mm256_shuffle_ps::<0xFF>(p, p);
+            let mask = _mm256_cmp_ps::<_CMP_NEQ_UQ>(a, _mm256_setzero_ps());
+            let r = _mm256_blend_ps::<0b1000_1000>(_mm256_and_ps(_mm256_div_ps(p, a), mask), p);
+            _mm256_storeu_ps(chunk.as_mut_ptr(), r);
+        }
+        unpremultiply_row_f32(chunks.into_remainder());
+    }
+}
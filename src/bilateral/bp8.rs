@@ -28,11 +28,12 @@
  */
 #![allow(clippy::manual_clamp)]
 
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::filter1d::{Arena, ArenaPads, make_arena};
+use crate::multichannel::blur_split_planes;
 use crate::{
     BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
 };
-use novtb::{ParallelZonedIterator, TbSliceMut};
 
 /// Pre-compute exp LUTs  ────────────────────────────────────────────────────
 ///   range LUT  : 256 entries  — exp(-ΔI² / 2σ_r²)
@@ -325,7 +326,7 @@ fn bilateral_filter_impl<const N: usize>(
     }
 
     let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let thread_pool = novtb::ThreadPool::new(thread_count as usize);
+    let thread_pool = crate::executor::ThreadPool::new(thread_count as usize);
     dst.projected()
         .tb_par_chunks_exact_mut(dst_row_stride)
         .for_each_enumerated(&thread_pool, |y, dst| {
@@ -345,10 +346,25 @@ pub struct BilateralBlurParams {
 }
 
 impl BilateralBlurParams {
-    fn validate(&self) -> Result<(), BlurError> {
+    pub(crate) fn validate(&self) -> Result<(), BlurError> {
         if self.kernel_size.is_multiple_of(2) {
             return Err(BlurError::OddKernel(self.kernel_size));
         }
+        if self.kernel_size.checked_mul(self.kernel_size).is_none() {
+            return Err(BlurError::ExceedingPointerSize);
+        }
+        if !self.spatial_sigma.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name: "spatial_sigma",
+                value: self.spatial_sigma as f64,
+            });
+        }
+        if !self.range_sigma.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name: "range_sigma",
+                value: self.range_sigma as f64,
+            });
+        }
         if self.spatial_sigma <= 0.0 {
             return Err(BlurError::NegativeOrZeroSigma);
         }
@@ -401,6 +417,16 @@ pub fn bilateral_filter(
             constant_border,
             threading_policy,
         ),
+        FastBlurChannels::Channels2 => blur_split_planes(src, dst, |src, dst| {
+            bilateral_filter_impl::<1>(
+                src,
+                dst,
+                copied_params,
+                edge_modes,
+                constant_border,
+                threading_policy,
+            )
+        }),
         FastBlurChannels::Channels3 => bilateral_filter_impl::<3>(
             src,
             dst,
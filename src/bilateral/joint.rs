@@ -0,0 +1,450 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::bilateral::BilateralBlurParams;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::filter1d::{ArenaPads, make_arena};
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+pub(crate) trait JointSample:
+    Copy + Clone + Default + Debug + Send + Sync + 'static + AsPrimitive<f32>
+where
+    f64: AsPrimitive<Self>,
+{
+    /// Maximum value of the type, used to normalize range distance into `0..1`
+    const SCALE: f32;
+    fn from_f32(v: f32) -> Self;
+}
+
+impl JointSample for u8 {
+    const SCALE: f32 = u8::MAX as f32;
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u8::MAX as f32) as u8
+    }
+}
+
+impl JointSample for u16 {
+    const SCALE: f32 = u16::MAX as f32;
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u16::MAX as f32) as u16
+    }
+}
+
+impl JointSample for f32 {
+    const SCALE: f32 = 1.;
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+}
+
+fn joint_bilateral_impl<T: JointSample, const CN: usize, const GN: usize>(
+    src: &BlurImage<T>,
+    guide: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f64: AsPrimitive<T>,
+{
+    let kernel_size = params.kernel_size;
+    let pads = ArenaPads::constant(kernel_size / 2);
+    let (src_arena, src_cfg) = make_arena::<T, CN>(
+        src.projected(),
+        src.row_stride() as usize,
+        src.size(),
+        pads,
+        edge_modes,
+        constant_border,
+    )?;
+    let (guide_arena, guide_cfg) = make_arena::<T, GN>(
+        guide.projected(),
+        guide.row_stride() as usize,
+        guide.size(),
+        pads,
+        edge_modes,
+        constant_border,
+    )?;
+
+    let half = (kernel_size / 2) as f32;
+    let recip_spatial = 1. / (2. * params.spatial_sigma * params.spatial_sigma);
+    let spatial: Vec<f32> = (0..kernel_size * kernel_size)
+        .map(|i| {
+            let dx = (i % kernel_size) as f32 - half;
+            let dy = (i / kernel_size) as f32 - half;
+            (-(dx * dx + dy * dy) * recip_spatial).exp()
+        })
+        .collect();
+    let normalization = 1. / T::SCALE;
+    let recip_range =
+        normalization * normalization / (2. * params.range_sigma * params.range_sigma);
+
+    // Alpha of luma-alpha and RGBA guides does not participate in range distance
+    let range_channels = match GN {
+        1 | 2 => 1,
+        _ => 3,
+    };
+    let width = src.width as usize;
+    let src_arena_stride = src_cfg.width * CN;
+    let guide_arena_stride = guide_cfg.width * GN;
+    let dst_stride = dst.row_stride() as usize;
+
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(src.width, src.height));
+    dst.projected()
+        .tb_par_chunks_exact_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, dst_row| {
+            for (x, dst) in dst_row[..width * CN].chunks_exact_mut(CN).enumerate() {
+                let center_offset =
+                    (y + guide_cfg.pad_h) * guide_arena_stride + (x + guide_cfg.pad_w) * GN;
+                let center = &guide_arena[center_offset..center_offset + GN];
+                let mut sums = [0f32; CN];
+                let mut weights = 0f32;
+                for (ky, spatial_row) in spatial.chunks_exact(kernel_size).enumerate() {
+                    let guide_row = &guide_arena[(y + ky) * guide_arena_stride + x * GN..];
+                    let src_row = &src_arena[(y + ky) * src_arena_stride + x * CN..];
+                    for ((&spatial_weight, g), s) in spatial_row
+                        .iter()
+                        .zip(guide_row.chunks_exact(GN))
+                        .zip(src_row.chunks_exact(CN))
+                    {
+                        let mut distance = 0f32;
+                        for c in 0..range_channels {
+                            let d = g[c].as_() - center[c].as_();
+                            distance += d * d;
+                        }
+                        let w = spatial_weight * (-distance * recip_range).exp();
+                        for (sum, &v) in sums.iter_mut().zip(s.iter()) {
+                            *sum += v.as_() * w;
+                        }
+                        weights += w;
+                    }
+                }
+                let recip = if weights == 0. { 1. } else { 1. / weights };
+                for (dst, &sum) in dst.iter_mut().zip(sums.iter()) {
+                    *dst = T::from_f32(sum * recip);
+                }
+            }
+        });
+    Ok(())
+}
+
+fn dispatch_guide<T: JointSample, const CN: usize>(
+    src: &BlurImage<T>,
+    guide: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f64: AsPrimitive<T>,
+{
+    let _dispatcher = match guide.channels {
+        FastBlurChannels::Plane => joint_bilateral_impl::<T, CN, 1>,
+        FastBlurChannels::Channels2 => joint_bilateral_impl::<T, CN, 2>,
+        FastBlurChannels::Channels3 => joint_bilateral_impl::<T, CN, 3>,
+        FastBlurChannels::Channels4 => joint_bilateral_impl::<T, CN, 4>,
+    };
+    _dispatcher(
+        src,
+        guide,
+        dst,
+        params,
+        edge_modes,
+        constant_border,
+        threading_policy,
+    )
+}
+
+fn joint_bilateral_dispatch<T: JointSample>(
+    src: &BlurImage<T>,
+    guide: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f64: AsPrimitive<T>,
+{
+    params.validate()?;
+    src.check_layout()?;
+    guide.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    if src.width != guide.width || src.height != guide.height {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    if params.kernel_size == 1 {
+        return src.copy_to_mut(dst);
+    }
+    let _dispatcher = match src.channels {
+        FastBlurChannels::Plane => dispatch_guide::<T, 1>,
+        FastBlurChannels::Channels2 => dispatch_guide::<T, 2>,
+        FastBlurChannels::Channels3 => dispatch_guide::<T, 3>,
+        FastBlurChannels::Channels4 => dispatch_guide::<T, 4>,
+    };
+    _dispatcher(
+        src,
+        guide,
+        dst,
+        params,
+        edge_modes,
+        constant_border,
+        threading_policy,
+    )
+}
+
+/// Joint (cross) bilateral filter.
+///
+/// Range weights are computed from the guide image, while the source image is smoothed.
+/// Guide must have the same size as the source, but may have different channels count,
+/// distance is computed over color channels of the guide, alpha of luma-alpha and RGBA guides is ignored.
+/// Range sigma is expressed in normalized `0..1` units.
+///
+/// This is very slow filter.
+///
+/// # Arguments
+///
+/// * `src`: Src image.
+/// * `guide`: Guide image.
+/// * `dst`: Dst image.
+/// * `params`: See [BilateralBlurParams] for more info.
+/// * `edge_modes`: Border modes, see [crate::EdgeMode] for more info.
+/// * `constant_border`: Scalar value for constant border mode, applied to source and guide.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: Result<(), BlurError>
+///
+pub fn joint_bilateral_filter(
+    src: &BlurImage<u8>,
+    guide: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    joint_bilateral_dispatch(
+        src,
+        guide,
+        dst,
+        params,
+        edge_modes,
+        constant_border,
+        threading_policy,
+    )
+}
+
+/// Joint (cross) bilateral filter.
+///
+/// See [joint_bilateral_filter] for more info.
+///
+/// # Arguments
+///
+/// * `src`: Src image.
+/// * `guide`: Guide image.
+/// * `dst`: Dst image.
+/// * `params`: See [BilateralBlurParams] for more info.
+/// * `edge_modes`: Border modes, see [crate::EdgeMode] for more info.
+/// * `constant_border`: Scalar value for constant border mode, applied to source and guide.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: Result<(), BlurError>
+///
+pub fn joint_bilateral_filter_u16(
+    src: &BlurImage<u16>,
+    guide: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    joint_bilateral_dispatch(
+        src,
+        guide,
+        dst,
+        params,
+        edge_modes,
+        constant_border,
+        threading_policy,
+    )
+}
+
+/// Joint (cross) bilateral filter.
+///
+/// See [joint_bilateral_filter] for more info, images are expected to be in `0..1` range.
+///
+/// # Arguments
+///
+/// * `src`: Src image.
+/// * `guide`: Guide image.
+/// * `dst`: Dst image.
+/// * `params`: See [BilateralBlurParams] for more info.
+/// * `edge_modes`: Border modes, see [crate::EdgeMode] for more info.
+/// * `constant_border`: Scalar value for constant border mode, applied to source and guide.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: Result<(), BlurError>
+///
+pub fn joint_bilateral_filter_f32(
+    src: &BlurImage<f32>,
+    guide: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: BilateralBlurParams,
+    edge_modes: EdgeMode2D,
+    constant_border: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    joint_bilateral_dispatch(
+        src,
+        guide,
+        dst,
+        params,
+        edge_modes,
+        constant_border,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_joint_bilateral_follows_guide() {
+        let width = 24usize;
+        let height = 16usize;
+        // Depth-like plane without edge, guide has a sharp vertical edge
+        let src: Vec<u16> = (0..width * height)
+            .map(|i| ((i % width) * 1000) as u16)
+            .collect();
+        let guide: Vec<u16> = (0..width * height * 3)
+            .map(|i| {
+                if (i / 3) % width < width / 2 {
+                    0
+                } else {
+                    60000
+                }
+            })
+            .collect();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let guide_image = BlurImage::borrow(
+            &guide,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        joint_bilateral_filter_u16(
+            &src_image,
+            &guide_image,
+            &mut dst,
+            BilateralBlurParams {
+                kernel_size: 9,
+                spatial_sigma: 3.,
+                range_sigma: 0.1,
+            },
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        let row = &dst[8 * width..9 * width];
+        // Nothing leaks through the guide edge
+        let left_max = row[..width / 2].iter().max().copied().unwrap();
+        let right_min = row[width / 2..].iter().min().copied().unwrap();
+        assert!(left_max <= 11000, "{left_max}");
+        assert!(right_min >= 12000, "{right_min}");
+    }
+
+    #[test]
+    fn test_joint_bilateral_ignores_luma_alpha_guide_alpha() {
+        let width = 20usize;
+        let height = 12usize;
+        let src: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 37) % 251) as u8)
+            .collect();
+        let luma = |i: usize| if (i % width) < width / 2 { 30u8 } else { 220u8 };
+        let run = |alpha: &dyn Fn(usize) -> u8| {
+            let guide: Vec<u8> = (0..width * height)
+                .flat_map(|i| [luma(i), alpha(i)])
+                .collect();
+            let src_image = BlurImage::borrow(
+                &src,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels3,
+            );
+            let guide_image = BlurImage::borrow(
+                &guide,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels2,
+            );
+            let mut dst = BlurImageMut::default();
+            joint_bilateral_filter(
+                &src_image,
+                &guide_image,
+                &mut dst,
+                BilateralBlurParams {
+                    kernel_size: 7,
+                    spatial_sigma: 2.,
+                    range_sigma: 0.1,
+                },
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            dst.data.borrow().to_vec()
+        };
+        let opaque = run(&|_| 255);
+        let varying = run(&|i| ((i * 53) % 256) as u8);
+        assert_eq!(opaque, varying);
+    }
+}
@@ -27,7 +27,9 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 mod bp8;
+mod joint;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 mod neon;
 
 pub use bp8::{BilateralBlurParams, bilateral_filter};
+pub use joint::{joint_bilateral_filter, joint_bilateral_filter_f32, joint_bilateral_filter_u16};
@@ -26,14 +26,19 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::channels_configuration::FastBlurChannels;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::padded_blur::{blur_in_place_padded, blur_padded, needs_padding};
+use crate::premultiply::{AlphaPremultiply, blur_straight_staged};
 use crate::primitives::PrimitiveCast;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
 use crate::util::{ScratchBuffer, check_slice_size};
-use crate::{BlurError, BlurImage, BlurImageMut, ThreadingPolicy};
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, EdgeMode, EdgeMode2D, Scalar,
+    ThreadingPolicy,
+};
 #[cfg(feature = "nightly_f16")]
 use core::f16;
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use num_traits::AsPrimitive;
 use num_traits::cast::FromPrimitive;
 use std::fmt::Debug;
@@ -48,6 +53,7 @@ pub struct BoxBlurParameters {
 }
 
 /// Central limit theorem based blurs parameters.
+#[derive(Copy, Clone, Debug)]
 pub struct CLTParameters {
     /// X-axis sigma
     pub x_sigma: f32,
@@ -55,6 +61,9 @@ pub struct CLTParameters {
     pub y_sigma: f32,
 }
 
+/// Box widths are computed in f32, sigma above 2^24 loses integer precision
+const MAX_CLT_SIGMA: f32 = 16_777_216.;
+
 impl CLTParameters {
     pub fn new(sigma: f32) -> CLTParameters {
         CLTParameters {
@@ -64,6 +73,18 @@ impl CLTParameters {
     }
 
     fn validate(&self) -> Result<(), BlurError> {
+        if !self.x_sigma.is_finite() || self.x_sigma > MAX_CLT_SIGMA {
+            return Err(BlurError::InvalidParameter {
+                name: "x_sigma",
+                value: self.x_sigma as f64,
+            });
+        }
+        if !self.y_sigma.is_finite() || self.y_sigma > MAX_CLT_SIGMA {
+            return Err(BlurError::InvalidParameter {
+                name: "y_sigma",
+                value: self.y_sigma as f64,
+            });
+        }
         if self.x_sigma <= 0. {
             return Err(BlurError::NegativeOrZeroSigma);
         }
@@ -72,6 +93,26 @@ impl CLTParameters {
         }
         Ok(())
     }
+
+    /// Total support of `passes` consecutive box blurs
+    fn support(&self, passes: usize) -> AnisotropicRadius {
+        AnisotropicRadius::create(
+            create_box_gauss(self.x_sigma, passes).iter().sum(),
+            create_box_gauss(self.y_sigma, passes).iter().sum(),
+        )
+    }
+
+    /// Box blurs making up `passes` consecutive passes
+    fn box_passes(&self, passes: usize) -> Vec<BoxBlurParameters> {
+        create_box_gauss(self.x_sigma, passes)
+            .iter()
+            .zip(create_box_gauss(self.y_sigma, passes).iter())
+            .map(|(&x, &y)| BoxBlurParameters {
+                x_axis_kernel: x * 2 + 1,
+                y_axis_kernel: y * 2 + 1,
+            })
+            .collect()
+    }
 }
 
 impl BoxBlurParameters {
@@ -91,6 +132,10 @@ impl BoxBlurParameters {
         (self.y_axis_kernel / 2).max(1)
     }
 
+    fn support(&self) -> AnisotropicRadius {
+        AnisotropicRadius::create(self.x_radius(), self.y_radius())
+    }
+
     fn validate(&self) -> Result<(), BlurError> {
         if self.x_axis_kernel.is_multiple_of(2) {
             return Err(BlurError::OddKernel(self.x_axis_kernel as usize));
@@ -390,7 +435,7 @@ fn box_blur_horizontal_pass<
 {
     let _dispatcher_horizontal = T::get_horizontal_pass::<CN>();
     let unsafe_dst = UnsafeSlice::new(dst);
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
         let segment_size = height / thread_count;
         let start_y = thread_index as u32 * segment_size;
@@ -637,7 +682,7 @@ fn box_blur_vertical_pass<
     let _dispatcher_vertical = T::get_box_vertical_pass();
     let unsafe_dst = UnsafeSlice::new(dst);
 
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
         let total_width = width as usize * CN;
         let segment_size = total_width / thread_count as usize;
@@ -966,7 +1011,7 @@ where
 
     if thread_count > 1 {
         let tile_size = height as usize / thread_count as usize;
-        let pool = novtb::ThreadPool::new(thread_count as usize);
+        let pool = crate::executor::ThreadPool::new(thread_count as usize);
         dst.tb_par_chunks_mut(dst_stride as usize * tile_size)
             .for_each_enumerated_with_context(
                 &pool,
@@ -1268,6 +1313,118 @@ where
     Ok(())
 }
 
+/// Runs box passes in place on RGBA image, ring buffer reads source rows behind
+/// the ones it writes, so passes always go through a full transient image.
+fn box_blur_in_place<
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>
+        + BoxBlurHorizontalPass<T>
+        + BoxBlurVerticalPass<T>
+        + Debug,
+>(
+    image: &mut BlurImageMut<T>,
+    passes: &[BoxBlurParameters],
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f32: ToStorage<T>,
+{
+    image.check_layout(None)?;
+    let width = image.width;
+    let height = image.height;
+    let thread_count = threading_policy.thread_count(width, height) as u32;
+    let stride = image.row_stride();
+    let working_stride = width as usize * 4;
+    let mut transient: Vec<T> = vec![T::default(); working_stride * height as usize];
+    let data = image.projected();
+    for pass in passes {
+        box_blur_horizontal_pass::<T, 4>(
+            data,
+            stride,
+            &mut transient,
+            working_stride as u32,
+            width,
+            height,
+            pass.x_radius(),
+            thread_count,
+        );
+        box_blur_vertical_pass::<T, 4>(
+            &transient,
+            working_stride as u32,
+            data,
+            stride,
+            width,
+            height,
+            pass.y_radius(),
+            thread_count,
+        );
+    }
+    Ok(())
+}
+
+/// Blurs straight alpha RGBA image, premultiplied source is staged in the destination
+/// and blurred there in place.
+#[allow(clippy::too_many_arguments)]
+fn box_blur_straight_alpha<
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>
+        + BoxBlurHorizontalPass<T>
+        + BoxBlurVerticalPass<T>
+        + AlphaPremultiply
+        + 'static,
+>(
+    image: &BlurImage<T>,
+    dst_image: &mut BlurImageMut<T>,
+    passes: &[BoxBlurParameters],
+    support: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f32: ToStorage<T>,
+    f64: PrimitiveCast<T>,
+{
+    blur_straight_staged(
+        image,
+        dst_image,
+        border_constant,
+        |staged, border_constant| {
+            if !needs_padding(edge_modes) {
+                return box_blur_in_place(staged, passes, threading_policy);
+            }
+            blur_in_place_padded(
+                staged,
+                support,
+                edge_modes,
+                border_constant,
+                |mode| mode == EdgeMode::Clamp,
+                |padded, _| box_blur_in_place(padded, passes, threading_policy),
+            )
+        },
+    )
+}
+
 /// Performs box blur on the image.
 ///
 /// Convergence of this function is very high so strong effect applies very fast
@@ -1279,14 +1436,20 @@ where
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - see [BoxBlurParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn box_blur(
     image: &BlurImage<u8>,
     dst_image: &mut BlurImageMut<u8>,
     parameters: BoxBlurParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
@@ -1295,11 +1458,44 @@ pub fn box_blur(
     if parameters.x_axis_kernel == 1 && parameters.y_axis_kernel == 1 {
         return image.copy_to_mut(dst_image);
     }
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &[parameters],
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                box_blur(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let width = image.width;
     let height = image.height;
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let _dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<u8, 4>,
     };
@@ -1329,26 +1525,66 @@ pub fn box_blur(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - see [BoxBlurParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn box_blur_u16(
     image: &BlurImage<u16>,
     dst_image: &mut BlurImageMut<u16>,
     parameters: BoxBlurParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
+    parameters.validate()?;
     if parameters.x_axis_kernel == 1 && parameters.y_axis_kernel == 1 {
         return image.copy_to_mut(dst_image);
     }
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &[parameters],
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                box_blur_u16(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let width = image.width;
     let height = image.height;
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<u16, 4>,
     };
@@ -1378,14 +1614,20 @@ pub fn box_blur_u16(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - see [BoxBlurParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn box_blur_f32(
     image: &BlurImage<f32>,
     dst_image: &mut BlurImageMut<f32>,
     parameters: BoxBlurParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
@@ -1394,11 +1636,44 @@ pub fn box_blur_f32(
     if parameters.x_axis_kernel == 1 && parameters.y_axis_kernel == 1 {
         return image.copy_to_mut(dst_image);
     }
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &[parameters],
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                box_blur_f32(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let width = image.width;
     let height = image.height;
     let thread_count = threading_policy.thread_count(width, height) as u32;
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => box_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => box_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => box_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => box_blur_impl::<f32, 4>,
     };
@@ -1425,10 +1700,10 @@ fn create_box_gauss(sigma: f32, n: usize) -> Vec<u32> {
     let mut wl: u32 = w_ideal.floor() as u32;
 
     if wl.is_multiple_of(2) {
-        wl -= 1;
+        wl = wl.saturating_sub(1).max(1);
     };
 
-    let wu = wl + 2;
+    let wu = wl.saturating_add(2);
 
     let wl_float = wl as f32;
     let m_ideal = (12.0 * sigma * sigma
@@ -1537,21 +1812,60 @@ where
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn tent_blur(
     image: &BlurImage<u8>,
     dst_image: &mut BlurImageMut<u8>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(2),
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                tent_blur(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<u8, 4>,
     };
@@ -1587,21 +1901,60 @@ pub fn tent_blur(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn tent_blur_u16(
     image: &BlurImage<u16>,
     dst_image: &mut BlurImageMut<u16>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(2),
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                tent_blur_u16(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<u16, 4>,
     };
@@ -1635,21 +1988,60 @@ pub fn tent_blur_u16(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn tent_blur_f32(
     image: &BlurImage<f32>,
     dst_image: &mut BlurImageMut<f32>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(2),
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(2),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                tent_blur_f32(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => tent_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => tent_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => tent_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => tent_blur_impl::<f32, 4>,
     };
@@ -1763,21 +2155,60 @@ where
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn gaussian_box_blur(
     image: &BlurImage<u8>,
     dst_image: &mut BlurImageMut<u8>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(3),
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                gaussian_box_blur(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let dispatcher = match image.channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<u8, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<u8, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<u8, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<u8, 4>,
     };
@@ -1814,23 +2245,62 @@ pub fn gaussian_box_blur(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
 /// * `threading_policy` - Threading policy, see [ThreadingPolicy] for more info.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn gaussian_box_blur_u16(
     image: &BlurImage<u16>,
     dst_image: &mut BlurImageMut<u16>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(3),
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                gaussian_box_blur_u16(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let channels = image.channels;
     let executor = match channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<u16, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<u16, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<u16, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<u16, 4>,
     };
@@ -1867,23 +2337,62 @@ pub fn gaussian_box_blur_u16(
 /// * `image` - Source immutable image, see [BlurImage] for more info.
 /// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
 /// * `parameters` - See [CLTParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
 /// * `threading_policy` - Threading policy, see [ThreadingPolicy] for more info.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn gaussian_box_blur_f32(
     image: &BlurImage<f32>,
     dst_image: &mut BlurImageMut<f32>,
     parameters: CLTParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout()?;
     dst_image.check_layout(Some(image))?;
     image.size_matches_mut(dst_image)?;
     parameters.validate()?;
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return box_blur_straight_alpha(
+            image,
+            dst_image,
+            &parameters.box_passes(3),
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            threading_policy,
+        );
+    }
+    if needs_padding(edge_modes) {
+        return blur_padded(
+            image,
+            dst_image,
+            parameters.support(3),
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |src, dst, inner_modes| {
+                gaussian_box_blur_f32(
+                    src,
+                    dst,
+                    parameters,
+                    inner_modes,
+                    border_constant,
+                    threading_policy,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let channels = image.channels;
     let dispatcher = match channels {
         FastBlurChannels::Plane => gaussian_box_blur_impl::<f32, 1>,
+        FastBlurChannels::Channels2 => gaussian_box_blur_impl::<f32, 2>,
         FastBlurChannels::Channels3 => gaussian_box_blur_impl::<f32, 3>,
         FastBlurChannels::Channels4 => gaussian_box_blur_impl::<f32, 4>,
     };
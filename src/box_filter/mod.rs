@@ -34,13 +34,19 @@ mod neon;
 mod sse;
 #[cfg(all(target_arch = "aarch64", feature = "sve"))]
 mod sve;
+mod variable_blur;
 
 pub use box_blur::*;
+pub use variable_blur::{
+    BlurMap, VariableBlurParameters, variable_blur, variable_blur_f32, variable_blur_u16,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{BlurImage, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+    use crate::{
+        AlphaMode, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, Scalar, ThreadingPolicy,
+    };
 
     macro_rules! compare_u8_stat {
         ($dst: expr, $radius: expr, $threading: expr) => {
@@ -110,7 +116,10 @@ mod tests {
             &src_image,
             &mut dst,
             BoxBlurParameters::new(k_size),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
             threading_policy,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst, k_size, threading_policy);
@@ -136,7 +145,10 @@ mod tests {
             &src_image,
             &mut dst,
             BoxBlurParameters::new(k_size),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
             threading_policy,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst, k_size, threading_policy);
@@ -162,7 +174,10 @@ mod tests {
             &src_image,
             &mut dst,
             BoxBlurParameters::new(k_size),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
             threading_policy,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_f32_stat!(dst, k_size, threading_policy);
@@ -191,4 +206,58 @@ mod tests {
         test_box_rgb_f32(5, ThreadingPolicy::Adaptive);
         test_box_rgb_f32(71, ThreadingPolicy::Adaptive);
     }
+
+    #[test]
+    fn test_box_f32_edge_modes() {
+        use crate::edge_mode::make_edge_lookup;
+        let width: usize = 23;
+        let height: usize = 17;
+        let kernel = 7u32;
+        let pad = kernel as usize / 2;
+        let src: Vec<f32> = (0..width * height)
+            .map(|x| ((x * 7919) % 251) as f32 / 251.)
+            .collect();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+            EdgeMode::Constant,
+        ] {
+            let mut dst = BlurImageMut::default();
+            box_blur_f32(
+                &src_image,
+                &mut dst,
+                BoxBlurParameters::new(kernel),
+                edge_mode.as_2d(),
+                Scalar::dup(0.25),
+                ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
+            )
+            .unwrap();
+            let x_lookup = make_edge_lookup(edge_mode, width, pad);
+            let y_lookup = make_edge_lookup(edge_mode, height, pad);
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = 0f32;
+                    for ky in 0..kernel as usize {
+                        for kx in 0..kernel as usize {
+                            sum += match (y_lookup[y + ky], x_lookup[x + kx]) {
+                                (Some(sy), Some(sx)) => src[sy * width + sx],
+                                _ => 0.25,
+                            };
+                        }
+                    }
+                    let expected = sum / (kernel * kernel) as f32;
+                    let actual = dst.data.borrow()[y * width + x];
+                    assert!(
+                        (expected - actual).abs() < 1e-4,
+                        "Expected {expected}, but it was {actual} at ({x}, {y}) for {edge_mode:?}"
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,477 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::channels_configuration::FastBlurChannels;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::to_storage::ToStorage;
+use crate::{BlurError, BlurImage, BlurImageMut, ThreadingPolicy};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Per-pixel map that drives the strength of [variable_blur].
+///
+/// Map must be a single plane image with the same dimensions as the blurred image.
+#[derive(Copy, Clone)]
+pub enum BlurMap<'a> {
+    /// Value `0..=255` maps linearly into `0..=max_radius`, useful for masks and depth maps.
+    Normalized(&'a BlurImage<'a, u8>),
+    /// Box radius in pixels, clamped to `max_radius`.
+    Radius(&'a BlurImage<'a, f32>),
+    /// Gaussian sigma in pixels.
+    Sigma(&'a BlurImage<'a, f32>),
+}
+
+impl BlurMap<'_> {
+    fn check(&self, width: u32, height: u32) -> Result<(), BlurError> {
+        let (map_width, map_height, channels) = match self {
+            BlurMap::Normalized(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+            BlurMap::Radius(map) | BlurMap::Sigma(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+        };
+        if map_width != width || map_height != height || channels != FastBlurChannels::Plane {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        Ok(())
+    }
+
+    /// Returns variance of requested gaussian for the pixel
+    #[inline]
+    fn variance(&self, x: usize, y: usize, max_radius: f32) -> f32 {
+        let radius_variance = |r: f32| {
+            let r = r.max(0.).min(max_radius);
+            r * (r + 1.) / 3.
+        };
+        match self {
+            BlurMap::Normalized(map) => {
+                let v = map.data[y * map.row_stride() as usize + x];
+                radius_variance(v as f32 * (1. / 255.) * max_radius)
+            }
+            BlurMap::Radius(map) => radius_variance(map.data[y * map.row_stride() as usize + x]),
+            BlurMap::Sigma(map) => {
+                let sigma = map.data[y * map.row_stride() as usize + x];
+                if sigma.is_finite() { sigma * sigma } else { 0. }
+            }
+        }
+    }
+}
+
+/// Parameters of spatially varying blur
+#[derive(Copy, Clone, Debug)]
+pub struct VariableBlurParameters {
+    /// Upper bound of the box radius used on each pass
+    pub max_radius: u32,
+    /// Count of box passes, more passes converge closer to a gaussian, 3 is a good default.
+    pub passes: u32,
+}
+
+impl VariableBlurParameters {
+    pub fn new(max_radius: u32) -> VariableBlurParameters {
+        VariableBlurParameters {
+            max_radius,
+            passes: 3,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.passes == 0 || self.passes > 6 {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+/// Computes per-pass box radius for each pixel, so that `passes` boxes
+/// have the same variance as requested by the map.
+fn make_pass_radii(
+    map: &BlurMap,
+    width: usize,
+    height: usize,
+    params: VariableBlurParameters,
+    pool: &crate::executor::ThreadPool,
+) -> Vec<f32> {
+    let mut radii = vec![0f32; width * height];
+    let max_radius = params.max_radius as f32;
+    let passes = params.passes as f32;
+    radii
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(pool, |y, row| {
+            for (x, dst) in row.iter_mut().enumerate() {
+                let variance = map.variance(x, y, max_radius);
+                // Box of width `w` has variance (w^2 - 1) / 12
+                let box_width = (12. * variance / passes + 1.).sqrt();
+                *dst = ((box_width - 1.) * 0.5).max(0.).min(max_radius);
+            }
+        });
+    radii
+}
+
+/// Builds summed area table with one zero row and column at the front.
+fn make_summed_area<const CN: usize>(
+    src: &[f32],
+    width: usize,
+    height: usize,
+    pool: &crate::executor::ThreadPool,
+) -> Vec<f64> {
+    let sat_stride = (width + 1) * CN;
+    let mut sat = vec![0f64; sat_stride * (height + 1)];
+    sat[sat_stride..]
+        .tb_par_chunks_exact_mut(sat_stride)
+        .for_each_enumerated(pool, |y, row| {
+            let src_row = &src[y * width * CN..(y + 1) * width * CN];
+            let mut acc = [0f64; CN];
+            for (dst, src) in row[CN..].chunks_exact_mut(CN).zip(src_row.chunks_exact(CN)) {
+                for ((acc, dst), &src) in acc.iter_mut().zip(dst.iter_mut()).zip(src.iter()) {
+                    *acc += src as f64;
+                    *dst = *acc;
+                }
+            }
+        });
+    for y in 1..height {
+        let (previous, current) = sat.split_at_mut((y + 1) * sat_stride);
+        let previous = &previous[y * sat_stride..];
+        for (dst, src) in current[..sat_stride].iter_mut().zip(previous.iter()) {
+            *dst += *src;
+        }
+    }
+    sat
+}
+
+#[inline(always)]
+fn box_sum<const CN: usize>(
+    sat: &[f64],
+    sat_stride: usize,
+    x: usize,
+    y: usize,
+    radius: usize,
+    width: usize,
+    height: usize,
+) -> ([f64; CN], f64) {
+    let x0 = x.saturating_sub(radius);
+    let y0 = y.saturating_sub(radius);
+    let x1 = (x + radius + 1).min(width);
+    let y1 = (y + radius + 1).min(height);
+    let mut sum = [0f64; CN];
+    let r0 = y0 * sat_stride;
+    let r1 = y1 * sat_stride;
+    for (c, dst) in sum.iter_mut().enumerate() {
+        *dst = sat[r1 + x1 * CN + c] - sat[r0 + x1 * CN + c] - sat[r1 + x0 * CN + c]
+            + sat[r0 + x0 * CN + c];
+    }
+    (sum, ((x1 - x0) * (y1 - y0)) as f64)
+}
+
+fn variable_box_pass<const CN: usize>(
+    src: &[f32],
+    dst: &mut [f32],
+    radii: &[f32],
+    width: usize,
+    height: usize,
+    pool: &crate::executor::ThreadPool,
+) {
+    let sat = make_summed_area::<CN>(src, width, height, pool);
+    let sat_stride = (width + 1) * CN;
+    dst.tb_par_chunks_exact_mut(width * CN)
+        .for_each_enumerated(pool, |y, row| {
+            let radii_row = &radii[y * width..(y + 1) * width];
+            for (x, (dst, &radius)) in row.chunks_exact_mut(CN).zip(radii_row).enumerate() {
+                let lower = radius.floor();
+                let weight = (radius - lower) as f64;
+                let lower = lower as usize;
+                let (sum, area) = box_sum::<CN>(&sat, sat_stride, x, y, lower, width, height);
+                if weight == 0. {
+                    let recip = 1. / area;
+                    for c in 0..CN {
+                        dst[c] = (sum[c] * recip) as f32;
+                    }
+                } else {
+                    // Fractional radius is blended between two neighbouring boxes
+                    // to avoid banding on smooth gradients of the map.
+                    let (upper_sum, upper_area) =
+                        box_sum::<CN>(&sat, sat_stride, x, y, lower + 1, width, height);
+                    let recip = 1. / area;
+                    let upper_recip = 1. / upper_area;
+                    for c in 0..CN {
+                        let v0 = sum[c] * recip;
+                        let v1 = upper_sum[c] * upper_recip;
+                        dst[c] = (v0 + (v1 - v0) * weight) as f32;
+                    }
+                }
+            }
+        });
+}
+
+fn variable_blur_impl<T, const CN: usize>(
+    image: &BlurImage<T>,
+    dst_image: &mut BlurImageMut<T>,
+    map: BlurMap,
+    params: VariableBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(image.width, image.height));
+    let radii = make_pass_radii(&map, width, height, params, &pool);
+
+    let row_size = width * CN;
+    let src_stride = image.row_stride() as usize;
+    let mut working = vec![0f32; row_size * height];
+    for (dst, src) in working
+        .chunks_exact_mut(row_size)
+        .zip(image.data.chunks(src_stride))
+    {
+        for (dst, src) in dst.iter_mut().zip(src[..row_size].iter()) {
+            *dst = src.as_();
+        }
+    }
+
+    let mut transient = vec![0f32; row_size * height];
+    for _ in 0..params.passes {
+        variable_box_pass::<CN>(&working, &mut transient, &radii, width, height, &pool);
+        std::mem::swap(&mut working, &mut transient);
+    }
+
+    let dst_stride = dst_image.row_stride() as usize;
+    for (dst, src) in dst_image
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(working.chunks_exact(row_size))
+    {
+        for (dst, &src) in dst[..row_size].iter_mut().zip(src.iter()) {
+            *dst = src.to_();
+        }
+    }
+    Ok(())
+}
+
+fn variable_blur_dispatch<T>(
+    image: &BlurImage<T>,
+    dst_image: &mut BlurImageMut<T>,
+    map: BlurMap,
+    params: VariableBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    image.check_layout()?;
+    dst_image.check_layout(Some(image))?;
+    image.size_matches_mut(dst_image)?;
+    params.validate()?;
+    map.check(image.width, image.height)?;
+    let _dispatcher = match image.channels {
+        FastBlurChannels::Plane => variable_blur_impl::<T, 1>,
+        FastBlurChannels::Channels2 => variable_blur_impl::<T, 2>,
+        FastBlurChannels::Channels3 => variable_blur_impl::<T, 3>,
+        FastBlurChannels::Channels4 => variable_blur_impl::<T, 4>,
+    };
+    _dispatcher(image, dst_image, map, params, threading_policy)
+}
+
+/// Performs spatially varying blur, where strength of the blur is taken per pixel from the map.
+///
+/// Blur is made from several passes of a box filter evaluated on the summed area table,
+/// so cost does not depend on the radius. Fractional radii are interpolated,
+/// so smooth maps (depth of field, tilt-shift gradients, soft masks) produce smooth results.
+/// Near the image edges the kernel is clipped.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `image` - Source immutable image, see [BlurImage] for more info.
+/// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
+/// * `map` - Per pixel blur strength, see [BlurMap] for more info.
+/// * `params` - see [VariableBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+///
+/// returns: ()
+///
+pub fn variable_blur(
+    image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    map: BlurMap,
+    params: VariableBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch(image, dst_image, map, params, threading_policy)
+}
+
+/// Performs spatially varying blur, where strength of the blur is taken per pixel from the map.
+///
+/// See [variable_blur] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Source immutable image, see [BlurImage] for more info.
+/// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
+/// * `map` - Per pixel blur strength, see [BlurMap] for more info.
+/// * `params` - see [VariableBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+///
+/// returns: ()
+///
+pub fn variable_blur_u16(
+    image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    map: BlurMap,
+    params: VariableBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch(image, dst_image, map, params, threading_policy)
+}
+
+/// Performs spatially varying blur, where strength of the blur is taken per pixel from the map.
+///
+/// See [variable_blur] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Source immutable image, see [BlurImage] for more info.
+/// * `dst_image` - Destination mutable image, see [BlurImageMut] for more info.
+/// * `map` - Per pixel blur strength, see [BlurMap] for more info.
+/// * `params` - see [VariableBlurParameters] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+///
+/// returns: ()
+///
+pub fn variable_blur_f32(
+    image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    map: BlurMap,
+    params: VariableBlurParameters,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch(image, dst_image, map, params, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlphaMode, BoxBlurParameters, EdgeMode, Scalar, box_blur};
+
+    fn make_pattern(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height * 3)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_zero_map_is_identity() {
+        let width = 33usize;
+        let height = 27usize;
+        let src = make_pattern(width, height);
+        let map_data = vec![0u8; width * height];
+        let map = BlurImage::borrow(
+            &map_data,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        variable_blur(
+            &image,
+            &mut dst,
+            BlurMap::Normalized(&map),
+            VariableBlurParameters::new(15),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert_eq!(dst.data.borrow(), src.as_slice());
+    }
+
+    #[test]
+    fn test_single_pass_matches_box_blur() {
+        let width = 64usize;
+        let height = 48usize;
+        let radius = 4usize;
+        let src = make_pattern(width, height);
+        let map_data = vec![radius as f32; width * height];
+        let map = BlurImage::borrow(
+            &map_data,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        variable_blur(
+            &image,
+            &mut dst,
+            BlurMap::Radius(&map),
+            VariableBlurParameters {
+                max_radius: 10,
+                passes: 1,
+            },
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let mut reference = BlurImageMut::default();
+        box_blur(
+            &image,
+            &mut reference,
+            BoxBlurParameters::new(radius as u32 * 2 + 1),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        let reference = reference.data.borrow();
+        for y in radius..height - radius {
+            for x in radius * 3..(width - radius) * 3 {
+                let diff =
+                    (dst[y * width * 3 + x] as i32 - reference[y * width * 3 + x] as i32).abs();
+                assert!(diff <= 1, "Diff {diff} at {x}, {y}");
+            }
+        }
+    }
+}
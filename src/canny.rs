@@ -0,0 +1,381 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::gradient::first_derivatives;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode, FastBlurChannels,
+    GaussianBlurParams, GradientKernel, GradientNorm, Scalar, ThreadingPolicy, gaussian_blur,
+    gaussian_blur_u16,
+};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+#[derive(Debug, Copy, Clone)]
+/// Parameters of Canny edge detector
+pub struct CannyParams {
+    /// Optional gaussian blur performed before computing gradients, see [GaussianBlurParams]
+    pub pre_blur: Option<GaussianBlurParams>,
+    /// Gradient magnitude lower than this is never an edge
+    pub low_threshold: f32,
+    /// Gradient magnitude higher than this is always an edge
+    pub high_threshold: f32,
+    /// Sobel aperture size, must be one of 3, 5, 7
+    pub aperture: u32,
+    /// See [GradientNorm] for more info
+    pub norm: GradientNorm,
+}
+
+impl CannyParams {
+    /// Sobel aperture 3 with L2 gradient norm and without pre-blur.
+    pub fn new(low_threshold: f32, high_threshold: f32) -> CannyParams {
+        CannyParams {
+            pre_blur: None,
+            low_threshold,
+            high_threshold,
+            aperture: 3,
+            norm: GradientNorm::L2,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if !self.low_threshold.is_finite()
+            || !self.high_threshold.is_finite()
+            || self.low_threshold < 0.
+            || self.low_threshold > self.high_threshold
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        if !matches!(self.aperture, 3 | 5 | 7) {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+const NOT_EDGE: u8 = 0;
+const WEAK_EDGE: u8 = 1;
+const STRONG_EDGE: u8 = 2;
+
+fn canny_impl<T>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<u8>,
+    params: CannyParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    let (gx, gy) = first_derivatives(
+        image,
+        GradientKernel::Sobel,
+        params.aperture,
+        EdgeMode::Reflect101.as_2d(),
+        Scalar::default(),
+        threading_policy,
+    )?;
+
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let cn = image.channels.channels();
+
+    let thread_count = threading_policy.thread_count(image.width, image.height);
+    let pool = crate::executor::ThreadPool::new(thread_count);
+
+    // For multichannel images the channel with the strongest gradient wins
+    let mut magnitude = vec![0f32; width * height];
+    let mut directions = vec![0u8; width * height];
+    let gx = gx.data.borrow();
+    let gy = gy.data.borrow();
+
+    magnitude
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(&pool, |y, dst| {
+            let gx = &gx[y * width * cn..(y + 1) * width * cn];
+            let gy = &gy[y * width * cn..(y + 1) * width * cn];
+            for ((dst, gx), gy) in dst
+                .iter_mut()
+                .zip(gx.chunks_exact(cn))
+                .zip(gy.chunks_exact(cn))
+            {
+                let mut best = 0f32;
+                for (&dx, &dy) in gx.iter().zip(gy.iter()) {
+                    let v = match params.norm {
+                        GradientNorm::L1 => dx.abs() + dy.abs(),
+                        GradientNorm::L2 => dx.hypot(dy),
+                    };
+                    best = best.max(v);
+                }
+                *dst = best;
+            }
+        });
+
+    directions
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(&pool, |y, dst| {
+            let gx = &gx[y * width * cn..(y + 1) * width * cn];
+            let gy = &gy[y * width * cn..(y + 1) * width * cn];
+            for ((dst, gx), gy) in dst
+                .iter_mut()
+                .zip(gx.chunks_exact(cn))
+                .zip(gy.chunks_exact(cn))
+            {
+                let mut best = -1f32;
+                let mut best_dx = 0f32;
+                let mut best_dy = 0f32;
+                for (&dx, &dy) in gx.iter().zip(gy.iter()) {
+                    let v = dx.abs() + dy.abs();
+                    if v > best {
+                        best = v;
+                        best_dx = dx;
+                        best_dy = dy;
+                    }
+                }
+                *dst = quantize_direction(best_dx, best_dy);
+            }
+        });
+
+    // Non-maximum suppression with double threshold
+    let mut edges = vec![NOT_EDGE; width * height];
+    let magnitude_slice = magnitude.as_slice();
+    let directions_slice = directions.as_slice();
+    edges
+        .tb_par_chunks_exact_mut(width)
+        .for_each_enumerated(&pool, |y, dst| {
+            let fetch = |x: isize, y: isize| -> f32 {
+                if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                    0.
+                } else {
+                    magnitude_slice[y as usize * width + x as usize]
+                }
+            };
+            for (x, dst) in dst.iter_mut().enumerate() {
+                let m = magnitude_slice[y * width + x];
+                if m <= params.low_threshold {
+                    continue;
+                }
+                let (ox, oy) = match directions_slice[y * width + x] {
+                    0 => (1isize, 0isize),
+                    1 => (1, 1),
+                    2 => (0, 1),
+                    _ => (-1, 1),
+                };
+                let (xi, yi) = (x as isize, y as isize);
+                let n0 = fetch(xi - ox, yi - oy);
+                let n1 = fetch(xi + ox, yi + oy);
+                if m > n0 && m >= n1 {
+                    *dst = if m > params.high_threshold {
+                        STRONG_EDGE
+                    } else {
+                        WEAK_EDGE
+                    };
+                }
+            }
+        });
+
+    // Hysteresis, weak edges connected to strong ones are promoted
+    let mut stack = Vec::new();
+    for (i, &edge) in edges.iter().enumerate() {
+        if edge == STRONG_EDGE {
+            stack.push(i);
+        }
+    }
+    while let Some(i) = stack.pop() {
+        let x = (i % width) as isize;
+        let y = (i / width) as isize;
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                    continue;
+                }
+                let j = ny as usize * width + nx as usize;
+                if edges[j] == WEAK_EDGE {
+                    edges[j] = STRONG_EDGE;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+
+    let dst_stride = destination.row_stride() as usize;
+    destination
+        .projected()
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, dst| {
+            for (dst, &src) in dst[..width]
+                .iter_mut()
+                .zip(edges[y * width..(y + 1) * width].iter())
+            {
+                *dst = if src == STRONG_EDGE { 255 } else { 0 };
+            }
+        });
+    Ok(())
+}
+
+/// Quantizes gradient direction into one of 4 sectors: 0°, 45°, 90°, 135°
+#[inline]
+fn quantize_direction(dx: f32, dy: f32) -> u8 {
+    const TAN_22_5: f32 = 0.41421357;
+    const TAN_67_5: f32 = 2.4142137;
+    let ax = dx.abs();
+    let ay = dy.abs();
+    if ay <= ax * TAN_22_5 {
+        0
+    } else if ay >= ax * TAN_67_5 {
+        2
+    } else if (dx >= 0.) == (dy >= 0.) {
+        1
+    } else {
+        3
+    }
+}
+
+/// Performs Canny edge detection on the image
+///
+/// Output is always a single plane image where edges are 255 and everything else is 0.
+/// For images with several channels gradient of the strongest channel is used.
+/// Gradients are computed with [EdgeMode::Reflect101] border handling.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `destination`: Destination edge map, must be [FastBlurChannels::Plane].
+/// * `params`: See [CannyParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn canny(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: CannyParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    params.validate()?;
+    destination.check_layout_with_size(image.width, image.height, FastBlurChannels::Plane)?;
+    if let Some(pre_blur) = params.pre_blur {
+        let mut blurred = BlurImageMut::default();
+        gaussian_blur(
+            image,
+            &mut blurred,
+            pre_blur,
+            EdgeMode::Reflect101.as_2d(),
+            threading_policy,
+            ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
+        )?;
+        canny_impl(
+            &blurred.to_immutable_ref(),
+            destination,
+            params,
+            threading_policy,
+        )
+    } else {
+        canny_impl(image, destination, params, threading_policy)
+    }
+}
+
+/// Performs Canny edge detection on the 16-bit image
+///
+/// Output is always a single plane image where edges are 255 and everything else is 0.
+/// For images with several channels gradient of the strongest channel is used.
+/// Gradients are computed with [EdgeMode::Reflect101] border handling.
+/// Thresholds are in the same units as source image.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `destination`: Destination edge map, must be [FastBlurChannels::Plane].
+/// * `params`: See [CannyParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn canny_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u8>,
+    params: CannyParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    params.validate()?;
+    destination.check_layout_with_size(image.width, image.height, FastBlurChannels::Plane)?;
+    if let Some(pre_blur) = params.pre_blur {
+        let mut blurred = BlurImageMut::default();
+        gaussian_blur_u16(
+            image,
+            &mut blurred,
+            pre_blur,
+            EdgeMode::Reflect101.as_2d(),
+            threading_policy,
+            ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
+        )?;
+        canny_impl(
+            &blurred.to_immutable_ref(),
+            destination,
+            params,
+            threading_policy,
+        )
+    } else {
+        canny_impl(image, destination, params, threading_policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canny_vertical_step() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let mut src = vec![0u8; width * height];
+        for row in src.chunks_exact_mut(width) {
+            for v in row[width / 2..].iter_mut() {
+                *v = 200;
+            }
+        }
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        canny(
+            &src_image,
+            &mut dst,
+            CannyParams::new(50., 150.),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        for (y, row) in dst.data.borrow().chunks_exact(width).enumerate() {
+            let edges = row.iter().filter(|&&x| x == 255).count();
+            assert_eq!(edges, 1, "Exactly one edge pixel expected at row {y}");
+            assert!(row[width / 2 - 1] == 255 || row[width / 2] == 255);
+        }
+    }
+}
@@ -0,0 +1,1114 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+//! C ABI of the library.
+//!
+//! Images are passed as opaque [LibblurImage] handles that either own their memory
+//! or wrap memory of the caller, every function returns [LibblurStatus].
+//! Enumerations are received as plain integers and validated, so an invalid value
+//! from a foreign caller is reported as an error instead of being undefined behavior.
+//! Panics never cross the boundary and are reported as [LibblurStatus::Panic].
+#![allow(clippy::missing_safety_doc)]
+
+use crate::{
+    AlphaMode, AnisotropicRadius, BilateralBlurParams, BlurError, BlurImageMut, BoxBlurParameters,
+    BufferStore, ConvolutionMode, EdgeMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams,
+    IeeeBinaryConvolutionMode, KernelShape, Scalar, ThreadingPolicy, bilateral_filter, box_blur,
+    box_blur_f32, box_blur_u16, fast_bilateral_filter, fast_bilateral_filter_f32,
+    fast_bilateral_filter_u16, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16, filter_2d,
+    gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, median_blur_constant_time,
+    median_blur_f32, median_blur_u16, stack_blur, stack_blur_f32, stack_blur_u16,
+};
+use std::ffi::{c_char, c_void};
+use std::num::NonZeroUsize;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// Result of every call of C API
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum LibblurStatus {
+    Ok = 0,
+    /// Required pointer was null
+    NullPointer = 1,
+    /// Unknown pixel type or operation is not available for this pixel type
+    UnsupportedPixelType = 2,
+    /// Unknown channels count
+    InvalidChannels = 3,
+    /// Unknown edge mode
+    InvalidEdgeMode = 4,
+    ZeroBaseSize = 5,
+    MinimumSliceSizeMismatch = 6,
+    MinimumStrideSizeMismatch = 7,
+    OddKernel = 8,
+    KernelSizeMismatch = 9,
+    ImagesMustMatch = 10,
+    StrideIsNotSupported = 11,
+    FftChannelsNotSupported = 12,
+    ExceedingPointerSize = 13,
+    NegativeOrZeroSigma = 14,
+    InvalidArguments = 15,
+    FftError = 16,
+    RoiOutOfBounds = 17,
+    /// Unexpected internal failure
+    Panic = 18,
+    /// Parameter is out of its valid range or not finite
+    InvalidParameter = 19,
+}
+
+impl From<&BlurError> for LibblurStatus {
+    fn from(value: &BlurError) -> Self {
+        match value {
+            BlurError::ZeroBaseSize => LibblurStatus::ZeroBaseSize,
+            BlurError::MinimumSliceSizeMismatch(_) => LibblurStatus::MinimumSliceSizeMismatch,
+            BlurError::MinimumStrideSizeMismatch(_) => LibblurStatus::MinimumStrideSizeMismatch,
+            BlurError::OddKernel(_) => LibblurStatus::OddKernel,
+            BlurError::KernelSizeMismatch(_) => LibblurStatus::KernelSizeMismatch,
+            BlurError::ImagesMustMatch => LibblurStatus::ImagesMustMatch,
+            BlurError::StrideIsNotSupported => LibblurStatus::StrideIsNotSupported,
+            BlurError::FftChannelsNotSupported => LibblurStatus::FftChannelsNotSupported,
+            BlurError::ExceedingPointerSize => LibblurStatus::ExceedingPointerSize,
+            BlurError::NegativeOrZeroSigma => LibblurStatus::NegativeOrZeroSigma,
+            BlurError::InvalidArguments => LibblurStatus::InvalidArguments,
+            BlurError::FftError(_) => LibblurStatus::FftError,
+            BlurError::RoiOutOfBounds => LibblurStatus::RoiOutOfBounds,
+            BlurError::InvalidChannels(_) => LibblurStatus::InvalidChannels,
+            BlurError::InvalidEdgeMode(_) => LibblurStatus::InvalidEdgeMode,
+            BlurError::InvalidParameter { .. } => LibblurStatus::InvalidParameter,
+        }
+    }
+}
+
+impl From<BlurError> for LibblurStatus {
+    fn from(value: BlurError) -> Self {
+        LibblurStatus::from(&value)
+    }
+}
+
+/// Pixel element type of [LibblurImage]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum LibblurPixelType {
+    U8 = 0,
+    U16 = 1,
+    F32 = 2,
+}
+
+/// Edge mode values accepted by C API, see [EdgeMode] for more info
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum LibblurEdgeMode {
+    Clamp = 0,
+    Wrap = 1,
+    Reflect = 2,
+    Reflect101 = 3,
+    Constant = 4,
+}
+
+enum ImageStore {
+    U8(BlurImageMut<'static, u8>),
+    U16(BlurImageMut<'static, u16>),
+    F32(BlurImageMut<'static, f32>),
+}
+
+impl ImageStore {
+    fn deep_copy(&self) -> ImageStore {
+        match self {
+            ImageStore::U8(image) => ImageStore::U8(image.to_immutable_ref().clone_as_mut()),
+            ImageStore::U16(image) => ImageStore::U16(image.to_immutable_ref().clone_as_mut()),
+            ImageStore::F32(image) => ImageStore::F32(image.to_immutable_ref().clone_as_mut()),
+        }
+    }
+}
+
+/// Opaque image handle.
+///
+/// Created by [libblur_image_alloc] or [libblur_image_wrap], must be released by [libblur_image_free].
+pub struct LibblurImage {
+    store: ImageStore,
+}
+
+fn channels_from_u32(channels: u32) -> Result<FastBlurChannels, LibblurStatus> {
+    FastBlurChannels::try_from(channels as usize).map_err(LibblurStatus::from)
+}
+
+fn pixel_type_from_u32(pixel_type: u32) -> Result<LibblurPixelType, LibblurStatus> {
+    match pixel_type {
+        0 => Ok(LibblurPixelType::U8),
+        1 => Ok(LibblurPixelType::U16),
+        2 => Ok(LibblurPixelType::F32),
+        _ => Err(LibblurStatus::UnsupportedPixelType),
+    }
+}
+
+fn edge_modes_from_u32(edge_mode: u32) -> Result<EdgeMode2D, LibblurStatus> {
+    EdgeMode::try_from(edge_mode as usize)
+        .map(EdgeMode::as_2d)
+        .map_err(LibblurStatus::from)
+}
+
+/// Zero threads means adaptive threading, see [ThreadingPolicy] for more info
+fn threading_policy(threads: u32) -> ThreadingPolicy {
+    match threads {
+        0 => ThreadingPolicy::Adaptive,
+        1 => ThreadingPolicy::Single,
+        threads => NonZeroUsize::new(threads as usize)
+            .map(ThreadingPolicy::Fixed)
+            .unwrap_or(ThreadingPolicy::Single),
+    }
+}
+
+unsafe fn border_constant(border: *const f64) -> Scalar {
+    if border.is_null() {
+        return Scalar::default();
+    }
+    let v = unsafe { std::slice::from_raw_parts(border, 4) };
+    Scalar::new(v[0], v[1], v[2], v[3])
+}
+
+fn guard<F: FnOnce() -> Result<(), LibblurStatus>>(f: F) -> LibblurStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => LibblurStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => LibblurStatus::Panic,
+    }
+}
+
+/// Runs out-of-place operation, same handle as source and destination is allowed
+unsafe fn with_images<F>(src: *const LibblurImage, dst: *mut LibblurImage, f: F) -> LibblurStatus
+where
+    F: FnOnce(&ImageStore, &mut ImageStore) -> Result<(), BlurError>,
+{
+    if src.is_null() || dst.is_null() {
+        return LibblurStatus::NullPointer;
+    }
+    guard(|| {
+        let copy;
+        let src_store = if std::ptr::eq(src, dst) {
+            copy = unsafe { (*src).store.deep_copy() };
+            &copy
+        } else {
+            unsafe { &(*src).store }
+        };
+        let dst_store = unsafe { &mut (*dst).store };
+        f(src_store, dst_store).map_err(LibblurStatus::from)
+    })
+}
+
+unsafe fn with_image<F>(image: *mut LibblurImage, f: F) -> LibblurStatus
+where
+    F: FnOnce(&mut ImageStore) -> Result<(), BlurError>,
+{
+    if image.is_null() {
+        return LibblurStatus::NullPointer;
+    }
+    guard(|| f(unsafe { &mut (*image).store }).map_err(LibblurStatus::from))
+}
+
+fn into_handle(store: ImageStore) -> *mut LibblurImage {
+    Box::into_raw(Box::new(LibblurImage { store }))
+}
+
+/// Allocates zeroed image with default stride.
+///
+/// Returns null if arguments are invalid.
+///
+/// # Arguments
+///
+/// * `channels`: Channels count from 1 to 4.
+/// * `pixel_type`: One of [LibblurPixelType] values.
+#[unsafe(no_mangle)]
+pub extern "C" fn libblur_image_alloc(
+    width: u32,
+    height: u32,
+    channels: u32,
+    pixel_type: u32,
+) -> *mut LibblurImage {
+    let (Ok(channels), Ok(pixel_type)) =
+        (channels_from_u32(channels), pixel_type_from_u32(pixel_type))
+    else {
+        return std::ptr::null_mut();
+    };
+    if width == 0
+        || height == 0
+        || (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|x| x.checked_mul(channels.channels()))
+            .and_then(|x| x.checked_mul(4))
+            .is_none_or(|x| x > isize::MAX as usize)
+    {
+        return std::ptr::null_mut();
+    }
+    into_handle(match pixel_type {
+        LibblurPixelType::U8 => ImageStore::U8(BlurImageMut::alloc(width, height, channels)),
+        LibblurPixelType::U16 => ImageStore::U16(BlurImageMut::alloc(width, height, channels)),
+        LibblurPixelType::F32 => ImageStore::F32(BlurImageMut::alloc(width, height, channels)),
+    })
+}
+
+/// Wraps memory of the caller into image handle without copying.
+///
+/// Memory must stay valid and must not be accessed by the caller while the handle is used.
+/// Returns null if arguments are invalid.
+///
+/// # Arguments
+///
+/// * `data`: Pointer to the first pixel, must be aligned for `pixel_type`.
+/// * `stride`: Row stride in elements, not in bytes, 0 means `width * channels`.
+/// * `channels`: Channels count from 1 to 4.
+/// * `pixel_type`: One of [LibblurPixelType] values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_wrap(
+    data: *mut c_void,
+    width: u32,
+    height: u32,
+    stride: u32,
+    channels: u32,
+    pixel_type: u32,
+) -> *mut LibblurImage {
+    let (Ok(channels), Ok(pixel_type)) =
+        (channels_from_u32(channels), pixel_type_from_u32(pixel_type))
+    else {
+        return std::ptr::null_mut();
+    };
+    if data.is_null() || width == 0 || height == 0 {
+        return std::ptr::null_mut();
+    }
+    let Some(row_size) = width.checked_mul(channels.channels() as u32) else {
+        return std::ptr::null_mut();
+    };
+    let stride = if stride == 0 { row_size } else { stride };
+    if stride < row_size {
+        return std::ptr::null_mut();
+    }
+    let Some(len) = (stride as usize)
+        .checked_mul(height as usize - 1)
+        .and_then(|x| x.checked_add(row_size as usize))
+    else {
+        return std::ptr::null_mut();
+    };
+
+    macro_rules! wrap {
+        ($variant:ident, $t:ty) => {{
+            if !data.cast::<$t>().is_aligned()
+                || len
+                    .checked_mul(size_of::<$t>())
+                    .is_none_or(|x| x > isize::MAX as usize)
+            {
+                return std::ptr::null_mut();
+            }
+            let slice = unsafe { std::slice::from_raw_parts_mut(data.cast::<$t>(), len) };
+            ImageStore::$variant(BlurImageMut {
+                data: BufferStore::Borrowed(slice),
+                width,
+                height,
+                stride,
+                channels,
+            })
+        }};
+    }
+
+    into_handle(match pixel_type {
+        LibblurPixelType::U8 => wrap!(U8, u8),
+        LibblurPixelType::U16 => wrap!(U16, u16),
+        LibblurPixelType::F32 => wrap!(F32, f32),
+    })
+}
+
+/// Releases image handle, wrapped memory of the caller is not released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_free(image: *mut LibblurImage) {
+    if !image.is_null() {
+        drop(unsafe { Box::from_raw(image) });
+    }
+}
+
+/// Returns pointer to the first pixel of the image, null if image is null.
+///
+/// Owned images may be reallocated when used as a destination with a different size.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_data(image: *mut LibblurImage) -> *mut c_void {
+    if image.is_null() {
+        return std::ptr::null_mut();
+    }
+    match unsafe { &mut (*image).store } {
+        ImageStore::U8(image) => image.data.borrow_mut().as_mut_ptr().cast(),
+        ImageStore::U16(image) => image.data.borrow_mut().as_mut_ptr().cast(),
+        ImageStore::F32(image) => image.data.borrow_mut().as_mut_ptr().cast(),
+    }
+}
+
+/// Returns width of the image, 0 if image is null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_width(image: *const LibblurImage) -> u32 {
+    if image.is_null() {
+        return 0;
+    }
+    match unsafe { &(*image).store } {
+        ImageStore::U8(image) => image.width,
+        ImageStore::U16(image) => image.width,
+        ImageStore::F32(image) => image.width,
+    }
+}
+
+/// Returns height of the image, 0 if image is null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_height(image: *const LibblurImage) -> u32 {
+    if image.is_null() {
+        return 0;
+    }
+    match unsafe { &(*image).store } {
+        ImageStore::U8(image) => image.height,
+        ImageStore::U16(image) => image.height,
+        ImageStore::F32(image) => image.height,
+    }
+}
+
+/// Returns row stride of the image in elements, 0 if image is null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_image_stride(image: *const LibblurImage) -> u32 {
+    if image.is_null() {
+        return 0;
+    }
+    match unsafe { &(*image).store } {
+        ImageStore::U8(image) => image.row_stride(),
+        ImageStore::U16(image) => image.row_stride(),
+        ImageStore::F32(image) => image.row_stride(),
+    }
+}
+
+/// Returns static null terminated description of the status.
+#[unsafe(no_mangle)]
+pub extern "C" fn libblur_status_description(status: u32) -> *const c_char {
+    let description: &'static std::ffi::CStr = match status {
+        0 => c"Ok",
+        1 => c"Required pointer is null",
+        2 => c"Pixel type is not supported",
+        3 => c"Channels count is not supported",
+        4 => c"Unknown edge mode",
+        5 => c"Image size must not be zero",
+        6 => c"Minimum image slice size mismatch",
+        7 => c"Minimum stride size mismatch",
+        8 => c"Kernel size must be odd",
+        9 => c"Kernel size mismatch",
+        10 => c"Source and destination images must match in their dimensions",
+        11 => c"Stride is not supported",
+        12 => c"Fft supports only planar images",
+        13 => c"Image bounds and blurring kernel/radius exceeds pointer capacity",
+        14 => c"Negative or zero sigma is not supported",
+        15 => c"Invalid arguments",
+        16 => c"Fft error",
+        17 => c"Region of interest must lie inside image bounds",
+        18 => c"Unexpected internal failure",
+        19 => c"Parameter is out of its valid range",
+        _ => c"Unknown status",
+    };
+    description.as_ptr()
+}
+
+/// Performs gaussian blur, see [crate::gaussian_blur] for more info.
+///
+/// # Arguments
+///
+/// * `kernel_size`: Odd kernel size, 0 means kernel computed from sigma.
+/// * `sigma`: Sigma, 0 or negative means sigma computed from kernel size.
+/// * `edge_mode`: One of [LibblurEdgeMode] values, constant edge mode uses zero border.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_gaussian_blur(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    kernel_size: u32,
+    sigma: f64,
+    edge_mode: u32,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let params = if kernel_size == 0 && sigma > 0. {
+        GaussianBlurParams::new_from_sigma(sigma)
+    } else if kernel_size != 0 && sigma <= 0. {
+        GaussianBlurParams::new_from_kernel(kernel_size as f64)
+    } else if kernel_size != 0 {
+        GaussianBlurParams::new(kernel_size, sigma)
+    } else {
+        return LibblurStatus::InvalidArguments;
+    };
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => gaussian_blur(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                threading_policy,
+                ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
+            ),
+            (ImageStore::U16(src), ImageStore::U16(dst)) => gaussian_blur_u16(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                threading_policy,
+                ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
+            ),
+            (ImageStore::F32(src), ImageStore::F32(dst)) => gaussian_blur_f32(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                threading_policy,
+                IeeeBinaryConvolutionMode::default(),
+                AlphaMode::Premultiplied,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+/// Performs box blur, see [crate::box_blur] for more info.
+///
+/// # Arguments
+///
+/// * `kernel_size`: Odd kernel size.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_box_blur(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    kernel_size: u32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let border = unsafe { self::border_constant(border_constant) };
+    let params = BoxBlurParameters::new(kernel_size);
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => box_blur(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                border,
+                threading_policy,
+                AlphaMode::Premultiplied,
+            ),
+            (ImageStore::U16(src), ImageStore::U16(dst)) => box_blur_u16(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                border,
+                threading_policy,
+                AlphaMode::Premultiplied,
+            ),
+            (ImageStore::F32(src), ImageStore::F32(dst)) => box_blur_f32(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                border,
+                threading_policy,
+                AlphaMode::Premultiplied,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+/// Performs stack blur in place, see [crate::stack_blur] for more info.
+///
+/// # Arguments
+///
+/// * `radius`: Blur radius.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_stack_blur(
+    image: *mut LibblurImage,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let border = unsafe { self::border_constant(border_constant) };
+    let radius = AnisotropicRadius::new(radius);
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_image(image, |image| match image {
+            ImageStore::U8(image) => stack_blur(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+            ImageStore::U16(image) => stack_blur_u16(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+            ImageStore::F32(image) => stack_blur_f32(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+        })
+    }
+}
+
+/// Performs fast gaussian approximation in place, see [crate::fast_gaussian] for more info.
+///
+/// # Arguments
+///
+/// * `radius`: Blur radius.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_fast_gaussian(
+    image: *mut LibblurImage,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let border = unsafe { self::border_constant(border_constant) };
+    let radius = AnisotropicRadius::new(radius);
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_image(image, |image| match image {
+            ImageStore::U8(image) => fast_gaussian(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+            ImageStore::U16(image) => fast_gaussian_u16(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+            ImageStore::F32(image) => fast_gaussian_f32(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border,
+                AlphaMode::Premultiplied,
+            ),
+        })
+    }
+}
+
+/// Performs bilateral filter on u8 images, see [crate::bilateral_filter] for more info.
+///
+/// # Arguments
+///
+/// * `kernel_size`: Odd kernel size.
+/// * `spatial_sigma`: Spatial sigma, must be positive.
+/// * `range_sigma`: Range sigma, must be positive.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn libblur_bilateral_filter(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let border = unsafe { self::border_constant(border_constant) };
+    let params = BilateralBlurParams {
+        kernel_size: kernel_size as usize,
+        spatial_sigma,
+        range_sigma,
+    };
+    let threading_policy = threading_policy(threads);
+    if !src.is_null() && unsafe { !matches!((*src).store, ImageStore::U8(_)) } {
+        return LibblurStatus::UnsupportedPixelType;
+    }
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => bilateral_filter(
+                &src.to_immutable_ref(),
+                dst,
+                params,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+/// Performs fast bilateral filter, see [crate::fast_bilateral_filter] for more info.
+///
+/// # Arguments
+///
+/// * `spatial_sigma`: Spatial sigma, must be positive.
+/// * `range_sigma`: Range sigma, must be positive.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_fast_bilateral_filter(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    threads: u32,
+) -> LibblurStatus {
+    if !(spatial_sigma > 0. && range_sigma > 0.) {
+        return LibblurStatus::NegativeOrZeroSigma;
+    }
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => fast_bilateral_filter(
+                &src.to_immutable_ref(),
+                dst,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            ),
+            (ImageStore::U16(src), ImageStore::U16(dst)) => fast_bilateral_filter_u16(
+                &src.to_immutable_ref(),
+                dst,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            ),
+            (ImageStore::F32(src), ImageStore::F32(dst)) => fast_bilateral_filter_f32(
+                &src.to_immutable_ref(),
+                dst,
+                spatial_sigma,
+                range_sigma,
+                threading_policy,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+/// Performs median blur, see [crate::median_blur_constant_time], [crate::median_blur_u16]
+/// and [crate::median_blur_f32] for more info.
+///
+/// # Arguments
+///
+/// * `radius`: Median radius.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn libblur_median_blur(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    radius: u32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let border = unsafe { self::border_constant(border_constant) };
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => median_blur_constant_time(
+                &src.to_immutable_ref(),
+                dst,
+                radius,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            (ImageStore::U16(src), ImageStore::U16(dst)) => median_blur_u16(
+                &src.to_immutable_ref(),
+                dst,
+                radius,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            (ImageStore::F32(src), ImageStore::F32(dst)) => median_blur_f32(
+                &src.to_immutable_ref(),
+                dst,
+                radius,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+/// Performs 2D convolution with arbitrary kernel, see [crate::filter_2d] for more info.
+///
+/// # Arguments
+///
+/// * `kernel`: Row-major kernel of `kernel_width * kernel_height` values.
+/// * `kernel_width`: Odd kernel width.
+/// * `kernel_height`: Odd kernel height.
+/// * `edge_mode`: One of [LibblurEdgeMode] values.
+/// * `border_constant`: Nullable pointer to 4 values used with constant edge mode.
+/// * `threads`: 0 means adaptive threading, otherwise threads count.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn libblur_filter_2d(
+    src: *const LibblurImage,
+    dst: *mut LibblurImage,
+    kernel: *const f32,
+    kernel_width: u32,
+    kernel_height: u32,
+    edge_mode: u32,
+    border_constant: *const f64,
+    threads: u32,
+) -> LibblurStatus {
+    if kernel.is_null() {
+        return LibblurStatus::NullPointer;
+    }
+    let edge_modes = match edge_modes_from_u32(edge_mode) {
+        Ok(edge_modes) => edge_modes,
+        Err(status) => return status,
+    };
+    let Some(kernel_len) = (kernel_width as usize).checked_mul(kernel_height as usize) else {
+        return LibblurStatus::ExceedingPointerSize;
+    };
+    let kernel = unsafe { std::slice::from_raw_parts(kernel, kernel_len) };
+    let shape = KernelShape::new(kernel_width as usize, kernel_height as usize);
+    let border = unsafe { self::border_constant(border_constant) };
+    let threading_policy = threading_policy(threads);
+    unsafe {
+        with_images(src, dst, |src, dst| match (src, dst) {
+            (ImageStore::U8(src), ImageStore::U8(dst)) => filter_2d::<u8, f32>(
+                &src.to_immutable_ref(),
+                dst,
+                kernel,
+                shape,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            (ImageStore::U16(src), ImageStore::U16(dst)) => filter_2d::<u16, f32>(
+                &src.to_immutable_ref(),
+                dst,
+                kernel,
+                shape,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            (ImageStore::F32(src), ImageStore::F32(dst)) => filter_2d::<f32, f32>(
+                &src.to_immutable_ref(),
+                dst,
+                kernel,
+                shape,
+                edge_modes,
+                border,
+                threading_policy,
+            ),
+            _ => Err(BlurError::ImagesMustMatch),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlurImage;
+
+    fn make_pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|x| ((x * 7919) % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_capi_gaussian_matches_rust() {
+        let (width, height) = (31u32, 17u32);
+        let mut data = make_pattern(width as usize * height as usize * 3);
+        let src = BlurImage::borrow(&data, width, height, FastBlurChannels::Channels3);
+        let mut expected = BlurImageMut::default();
+        gaussian_blur(
+            &src,
+            &mut expected,
+            GaussianBlurParams::new(7, 2.),
+            EdgeMode::Reflect101.as_2d(),
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+
+        unsafe {
+            let src = libblur_image_wrap(
+                data.as_mut_ptr().cast(),
+                width,
+                height,
+                0,
+                3,
+                LibblurPixelType::U8 as u32,
+            );
+            assert!(!src.is_null());
+            assert_eq!(libblur_image_stride(src), width * 3);
+            let dst = libblur_image_alloc(width, height, 3, LibblurPixelType::U8 as u32);
+            assert!(!dst.is_null());
+            let status =
+                libblur_gaussian_blur(src, dst, 7, 2., LibblurEdgeMode::Reflect101 as u32, 1);
+            assert_eq!(status, LibblurStatus::Ok);
+            let out = std::slice::from_raw_parts(
+                libblur_image_data(dst).cast::<u8>(),
+                expected.data.borrow().len(),
+            );
+            assert_eq!(out, expected.data.borrow());
+
+            // Same handle as source and destination blurs in place
+            let status =
+                libblur_gaussian_blur(src, src, 7, 2., LibblurEdgeMode::Reflect101 as u32, 1);
+            assert_eq!(status, LibblurStatus::Ok);
+            libblur_image_free(src);
+            libblur_image_free(dst);
+        }
+        assert_eq!(data, expected.data.borrow());
+    }
+
+    #[test]
+    fn test_capi_in_place_and_filter_2d() {
+        let (width, height) = (24u32, 19u32);
+        let data = make_pattern(width as usize * height as usize);
+        let mut expected =
+            BlurImage::borrow(&data, width, height, FastBlurChannels::Plane).clone_as_mut();
+        stack_blur(
+            &mut expected,
+            AnisotropicRadius::new(3),
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+
+        unsafe {
+            let image = libblur_image_alloc(width, height, 1, LibblurPixelType::U8 as u32);
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                libblur_image_data(image).cast::<u8>(),
+                data.len(),
+            );
+            let status =
+                libblur_stack_blur(image, 3, LibblurEdgeMode::Clamp as u32, std::ptr::null(), 1);
+            assert_eq!(status, LibblurStatus::Ok);
+            let out =
+                std::slice::from_raw_parts(libblur_image_data(image).cast::<u8>(), data.len());
+            assert_eq!(out, expected.data.borrow());
+
+            // Identity kernel keeps image unchanged
+            let kernel = [0f32, 0., 0., 0., 1., 0., 0., 0., 0.];
+            let dst = libblur_image_alloc(width, height, 1, LibblurPixelType::U8 as u32);
+            let status = libblur_filter_2d(
+                image,
+                dst,
+                kernel.as_ptr(),
+                3,
+                3,
+                LibblurEdgeMode::Constant as u32,
+                [1., 1., 1., 1.].as_ptr(),
+                0,
+            );
+            assert_eq!(status, LibblurStatus::Ok);
+            let filtered =
+                std::slice::from_raw_parts(libblur_image_data(dst).cast::<u8>(), data.len());
+            assert_eq!(filtered, expected.data.borrow());
+            libblur_image_free(image);
+            libblur_image_free(dst);
+        }
+    }
+
+    #[test]
+    fn test_capi_median_u8_respects_edge_mode() {
+        let (width, height) = (21u32, 13u32);
+        let mut data = make_pattern(width as usize * height as usize);
+        let src = BlurImage::borrow(&data, width, height, FastBlurChannels::Plane);
+        let border = [255., 255., 255., 255.];
+        let mut expected = BlurImageMut::default();
+        median_blur_constant_time(
+            &src,
+            &mut expected,
+            2,
+            EdgeMode::Constant.as_2d(),
+            Scalar::new(255., 255., 255., 255.),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+
+        unsafe {
+            let src = libblur_image_wrap(
+                data.as_mut_ptr().cast(),
+                width,
+                height,
+                0,
+                1,
+                LibblurPixelType::U8 as u32,
+            );
+            let dst = libblur_image_alloc(width, height, 1, LibblurPixelType::U8 as u32);
+            let status = libblur_median_blur(
+                src,
+                dst,
+                2,
+                LibblurEdgeMode::Constant as u32,
+                border.as_ptr(),
+                1,
+            );
+            assert_eq!(status, LibblurStatus::Ok);
+            let out = std::slice::from_raw_parts(
+                libblur_image_data(dst).cast::<u8>(),
+                expected.data.borrow().len(),
+            );
+            assert_eq!(out, expected.data.borrow());
+            libblur_image_free(src);
+            libblur_image_free(dst);
+        }
+    }
+
+    #[test]
+    fn test_capi_errors() {
+        assert!(libblur_image_alloc(0, 10, 1, LibblurPixelType::U8 as u32).is_null());
+        assert!(libblur_image_alloc(10, 10, 5, LibblurPixelType::U8 as u32).is_null());
+        assert!(libblur_image_alloc(10, 10, 1, 3).is_null());
+        unsafe {
+            let mut data = vec![0f32; 17];
+            let unaligned = data.as_mut_ptr().cast::<u8>().add(1).cast();
+            assert!(
+                libblur_image_wrap(unaligned, 4, 4, 0, 1, LibblurPixelType::F32 as u32).is_null()
+            );
+            assert!(
+                libblur_image_wrap(
+                    std::ptr::null_mut(),
+                    4,
+                    4,
+                    0,
+                    1,
+                    LibblurPixelType::U8 as u32
+                )
+                .is_null()
+            );
+            assert!(
+                libblur_image_wrap(
+                    data.as_mut_ptr().cast(),
+                    4,
+                    4,
+                    3,
+                    1,
+                    LibblurPixelType::F32 as u32
+                )
+                .is_null()
+            );
+
+            let src = libblur_image_alloc(8, 8, 1, LibblurPixelType::F32 as u32);
+            let dst = libblur_image_alloc(8, 8, 1, LibblurPixelType::F32 as u32);
+            let dst_u8 = libblur_image_alloc(8, 8, 1, LibblurPixelType::U8 as u32);
+            assert_eq!(
+                libblur_gaussian_blur(std::ptr::null(), dst, 3, 0., 0, 1),
+                LibblurStatus::NullPointer
+            );
+            assert_eq!(
+                libblur_gaussian_blur(src, dst, 3, 0., 9, 1),
+                LibblurStatus::InvalidEdgeMode
+            );
+            assert_eq!(
+                libblur_gaussian_blur(src, dst, 0, 0., 0, 1),
+                LibblurStatus::InvalidArguments
+            );
+            assert_eq!(
+                libblur_box_blur(src, dst_u8, 3, 0, std::ptr::null(), 1),
+                LibblurStatus::ImagesMustMatch
+            );
+            assert_eq!(
+                libblur_bilateral_filter(src, dst, 3, 1., 1., 0, std::ptr::null(), 1),
+                LibblurStatus::UnsupportedPixelType
+            );
+            assert_eq!(
+                libblur_fast_bilateral_filter(src, dst, 0., 1., 1),
+                LibblurStatus::NegativeOrZeroSigma
+            );
+            assert_eq!(
+                libblur_filter_2d(src, dst, std::ptr::null(), 3, 3, 0, std::ptr::null(), 1),
+                LibblurStatus::NullPointer
+            );
+            libblur_image_free(src);
+            libblur_image_free(dst);
+            libblur_image_free(dst_u8);
+            libblur_image_free(std::ptr::null_mut());
+        }
+        let description = libblur_status_description(LibblurStatus::ImagesMustMatch as u32);
+        assert!(!description.is_null());
+    }
+}
@@ -25,6 +25,8 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
+
 #[repr(C)]
 #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 /// Declares channels count
@@ -34,9 +36,12 @@
 /// in case of linear transformation alpha plane expected to be last so if colorspace has 4 channels then it should be
 /// RGBA, BGRA etc
 /// *NOTE*: For images with alpha even that order of alpha doesn't matter alpha must be premultiplied!
+/// See [crate::AlphaMode] and [crate::BlurImageMut::premultiply_alpha] for straight alpha images.
 pub enum FastBlurChannels {
     /// Single plane image
     Plane = 1,
+    /// Luma with alpha, two planes of complex values etc
+    Channels2 = 2,
     /// RGB, BGR etc
     Channels3 = 3,
     /// RGBA, BGRA etc
@@ -48,21 +53,23 @@ impl FastBlurChannels {
     pub const fn channels(&self) -> usize {
         match self {
             FastBlurChannels::Plane => 1,
+            FastBlurChannels::Channels2 => 2,
             FastBlurChannels::Channels3 => 3,
             FastBlurChannels::Channels4 => 4,
         }
     }
 }
 
-impl From<usize> for FastBlurChannels {
-    fn from(value: usize) -> Self {
+impl TryFrom<usize> for FastBlurChannels {
+    type Error = BlurError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
         match value {
-            1 => FastBlurChannels::Plane,
-            3 => FastBlurChannels::Channels3,
-            4 => FastBlurChannels::Channels4,
-            _ => {
-                panic!("Unknown value");
-            }
+            1 => Ok(FastBlurChannels::Plane),
+            2 => Ok(FastBlurChannels::Channels2),
+            3 => Ok(FastBlurChannels::Channels3),
+            4 => Ok(FastBlurChannels::Channels4),
+            _ => Err(BlurError::InvalidChannels(value)),
         }
     }
 }
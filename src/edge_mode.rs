@@ -25,6 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
 use std::ops::Index;
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
@@ -72,17 +73,17 @@ pub enum EdgeMode {
     Constant = 4,
 }
 
-impl From<usize> for EdgeMode {
-    fn from(value: usize) -> Self {
+impl TryFrom<usize> for EdgeMode {
+    type Error = BlurError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
         match value {
-            0 => EdgeMode::Clamp,
-            1 => EdgeMode::Wrap,
-            2 => EdgeMode::Reflect,
-            3 => EdgeMode::Reflect101,
-            4 => EdgeMode::Constant,
-            _ => {
-                unreachable!("Unknown edge mode for value: {value}");
-            }
+            0 => Ok(EdgeMode::Clamp),
+            1 => Ok(EdgeMode::Wrap),
+            2 => Ok(EdgeMode::Reflect),
+            3 => Ok(EdgeMode::Reflect101),
+            4 => Ok(EdgeMode::Constant),
+            _ => Err(BlurError::InvalidEdgeMode(value)),
         }
     }
 }
@@ -142,6 +143,49 @@ macro_rules! clamp_edge {
     }};
 }
 
+/// Maps padded coordinates `-pad..len + pad` into source coordinates.
+///
+/// `None` means that value must be taken from a border constant.
+pub(crate) fn make_edge_lookup(edge_mode: EdgeMode, len: usize, pad: usize) -> Vec<Option<usize>> {
+    let len = len as i64;
+    let pad = pad as i64;
+    (-pad..len + pad)
+        .map(|i| edge_index(edge_mode, i, len))
+        .collect()
+}
+
+/// Maps coordinate `i` into `0..len`, `None` means that value must be taken from a border constant.
+#[inline]
+pub(crate) fn edge_index(edge_mode: EdgeMode, i: i64, len: i64) -> Option<usize> {
+    if edge_mode == EdgeMode::Constant && (i < 0 || i >= len) {
+        None
+    } else {
+        Some(clamp_edge!(edge_mode, i, 0i64, len))
+    }
+}
+
+/// Provides access to image pixels with padded coordinates resolved by lookup tables
+/// made by [make_edge_lookup].
+pub(crate) struct BorderedSource<'a, T: Copy, const CN: usize> {
+    pub(crate) src: &'a [T],
+    pub(crate) src_stride: usize,
+    pub(crate) x_lookup: &'a [Option<usize>],
+    pub(crate) y_lookup: &'a [Option<usize>],
+    pub(crate) constant: [T; 4],
+}
+
+impl<T: Copy, const CN: usize> BorderedSource<'_, T, CN> {
+    #[inline(always)]
+    pub(crate) fn fetch(&self, padded_x: usize, padded_y: usize, c: usize) -> T {
+        match (self.y_lookup[padded_y], self.x_lookup[padded_x]) {
+            (Some(y), Some(x)) => unsafe {
+                *self.src.get_unchecked(y * self.src_stride + x * CN + c)
+            },
+            _ => self.constant[c],
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct BorderHandle {
     pub edge_mode: EdgeMode,
@@ -0,0 +1,437 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use std::cell::Cell;
+use std::sync::Mutex;
+
+/// Executes independent row-band tasks of a blur.
+///
+/// All multithreaded paths of the library split the work into a number of tasks
+/// and hand them to the executor installed with [with_executor].
+/// When no executor is installed, tasks are run on scoped threads spawned for the call.
+///
+/// Implement this trait to run libblur inside an existing job system.
+/// [rayon::ThreadPool] implements it out of the box.
+pub trait BlurExecutor: Sync {
+    /// Amount of tasks executor is able to run concurrently.
+    ///
+    /// Used instead of available CPU count by [crate::ThreadingPolicy].
+    fn concurrency(&self) -> usize;
+
+    /// Runs `task(i)` for every `i` in `0..tasks`.
+    ///
+    /// Tasks may be run in any order and on any thread, but this method must not return
+    /// until every task is finished.
+    fn execute(&self, tasks: usize, task: &(dyn Fn(usize) + Sync));
+}
+
+impl BlurExecutor for rayon::ThreadPool {
+    fn concurrency(&self) -> usize {
+        self.current_num_threads()
+    }
+
+    fn execute(&self, tasks: usize, task: &(dyn Fn(usize) + Sync)) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        self.install(|| (0..tasks).into_par_iter().for_each(task));
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ExecutorRef(*const (dyn BlurExecutor + 'static));
+
+// SAFETY: BlurExecutor is Sync, pointer is only dereferenced while the executor is borrowed
+// by `with_executor`.
+unsafe impl Send for ExecutorRef {}
+unsafe impl Sync for ExecutorRef {}
+
+thread_local! {
+    static CURRENT_EXECUTOR: Cell<Option<ExecutorRef>> = const { Cell::new(None) };
+}
+
+struct RestoreExecutor(Option<ExecutorRef>);
+
+impl Drop for RestoreExecutor {
+    fn drop(&mut self) {
+        CURRENT_EXECUTOR.with(|current| current.set(self.0));
+    }
+}
+
+fn install<R>(executor: Option<ExecutorRef>, f: impl FnOnce() -> R) -> R {
+    let _restore = RestoreExecutor(CURRENT_EXECUTOR.with(|current| current.replace(executor)));
+    f()
+}
+
+/// Runs `f` with all libblur multithreaded work scheduled on `executor`.
+///
+/// Executor is installed for the current thread and for tasks it runs, so nested
+/// multithreaded calls go through it as well.
+/// [crate::ThreadingPolicy::Single] keeps running on the calling thread.
+///
+/// # Examples
+///
+/// ```
+/// use libblur::{AlphaMode, BlurImage, BlurImageMut, BoxBlurParameters, EdgeMode, FastBlurChannels, Scalar, ThreadingPolicy};
+/// let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+/// let src = vec![0u8; 64 * 64];
+/// let src = BlurImage::borrow(&src, 64, 64, FastBlurChannels::Plane);
+/// let mut dst = BlurImageMut::alloc(64, 64, FastBlurChannels::Plane);
+/// libblur::with_executor(&pool, || {
+///     libblur::box_blur(&src, &mut dst, BoxBlurParameters::new(5),
+///         EdgeMode::Clamp.as_2d(), Scalar::default(), ThreadingPolicy::Adaptive, AlphaMode::Premultiplied)
+/// })
+/// .unwrap();
+/// ```
+pub fn with_executor<R>(executor: &dyn BlurExecutor, f: impl FnOnce() -> R) -> R {
+    // SAFETY: lifetime is erased only for the duration of `f`,
+    // previous executor is restored before `executor` borrow ends, even on panic.
+    let executor: &(dyn BlurExecutor + 'static) = unsafe { std::mem::transmute(executor) };
+    install(Some(ExecutorRef(executor)), f)
+}
+
+/// Concurrency of the installed executor, if any.
+pub(crate) fn executor_concurrency() -> Option<usize> {
+    CURRENT_EXECUTOR
+        .with(|current| current.get())
+        .map(|executor| unsafe { &*executor.0 }.concurrency().max(1))
+}
+
+fn run_tasks(tasks: usize, task: &(dyn Fn(usize) + Sync)) {
+    if tasks <= 1 {
+        (0..tasks).for_each(task);
+        return;
+    }
+    match CURRENT_EXECUTOR.with(|current| current.get()) {
+        Some(executor) => {
+            let executor_ref = unsafe { &*executor.0 };
+            executor_ref.execute(tasks, &|i| install(Some(executor), || task(i)));
+        }
+        None => novtb::ThreadPool::new(tasks).parallel_for(task),
+    }
+}
+
+/// Amount of threads the work is split into.
+pub(crate) struct ThreadPool {
+    amount: usize,
+}
+
+impl ThreadPool {
+    pub(crate) fn new(size: usize) -> ThreadPool {
+        ThreadPool {
+            amount: size.max(1),
+        }
+    }
+
+    pub(crate) fn thread_count(&self) -> usize {
+        self.amount
+    }
+
+    pub(crate) fn parallel_for<F>(&self, job: F)
+    where
+        F: Fn(usize) + Send + Sync,
+    {
+        if self.amount <= 1 {
+            job(0);
+            return;
+        }
+        run_tasks(self.amount, &job);
+    }
+}
+
+/// Splits `slice` into at most `pool.thread_count()` groups of whole chunks,
+/// `f` receives index of the first chunk in the group and the group.
+fn run_groups<'data, T: Send, F>(pool: &ThreadPool, slice: &'data mut [T], chunk_size: usize, f: F)
+where
+    F: Fn(usize, &'data mut [T]) + Sync,
+{
+    let total_chunks = slice.len().div_ceil(chunk_size);
+    if total_chunks == 0 {
+        return;
+    }
+    let group_chunks = total_chunks.div_ceil(pool.amount);
+    if group_chunks >= total_chunks {
+        f(0, slice);
+        return;
+    }
+    let groups = slice
+        .chunks_mut(group_chunks * chunk_size)
+        .enumerate()
+        .map(|(i, group)| Mutex::new(Some((i * group_chunks, group))))
+        .collect::<Vec<_>>();
+    run_tasks(groups.len(), &|i| {
+        let group = groups[i]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some((base, group)) = group {
+            f(base, group);
+        }
+    });
+}
+
+/// Parallel iterator over mutable non-overlapping chunks of a slice, remainder is skipped
+pub(crate) struct ChunksExactMut<'data, T: Send> {
+    chunk_size: usize,
+    slice: &'data mut [T],
+}
+
+impl<'data, T: Send> ChunksExactMut<'data, T> {
+    fn new(chunk_size: usize, slice: &'data mut [T]) -> Self {
+        assert_ne!(chunk_size, 0, "Chunk size must be non-zero");
+        let exact_len = slice.len() - slice.len() % chunk_size;
+        ChunksExactMut {
+            chunk_size,
+            slice: &mut slice[..exact_len],
+        }
+    }
+}
+
+/// Parallel iterator over mutable non-overlapping chunks of a slice, last chunk may be shorter
+pub(crate) struct ChunksMut<'data, T: Send> {
+    chunk_size: usize,
+    slice: &'data mut [T],
+}
+
+impl<'data, T: Send> ChunksMut<'data, T> {
+    fn new(chunk_size: usize, slice: &'data mut [T]) -> Self {
+        assert_ne!(chunk_size, 0, "Chunk size must be non-zero");
+        ChunksMut { chunk_size, slice }
+    }
+
+    /// Pairs every chunk with an item of `other`, the shorter side determines amount of pairs.
+    pub(crate) fn zip<I: Iterator>(self, other: I) -> ZipChunks<'data, T, I> {
+        ZipChunks { inner: self, other }
+    }
+}
+
+pub(crate) struct ZipChunks<'data, T: Send, I: Iterator> {
+    inner: ChunksMut<'data, T>,
+    other: I,
+}
+
+pub(crate) trait TbSliceMut<T: Send> {
+    fn tb_par_chunks_exact_mut(&mut self, chunk_size: usize) -> ChunksExactMut<'_, T>;
+    fn tb_par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T>;
+}
+
+impl<T: Send> TbSliceMut<T> for [T] {
+    fn tb_par_chunks_exact_mut(&mut self, chunk_size: usize) -> ChunksExactMut<'_, T> {
+        ChunksExactMut::new(chunk_size, self)
+    }
+
+    fn tb_par_chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<'_, T> {
+        ChunksMut::new(chunk_size, self)
+    }
+}
+
+pub(crate) trait ParallelZonedIterator: Sized + Send {
+    type Item: Send;
+
+    fn for_each_enumerated_with_context<F, C, CF>(self, pool: &ThreadPool, make_context: CF, f: F)
+    where
+        F: Fn(usize, &mut C, Self::Item) + Send + Sync,
+        C: Send,
+        CF: Fn() -> C + Send + Sync;
+
+    fn for_each_enumerated<F>(self, pool: &ThreadPool, f: F)
+    where
+        F: Fn(usize, Self::Item) + Send + Sync,
+    {
+        self.for_each_enumerated_with_context(pool, || (), |i, _, item| f(i, item));
+    }
+}
+
+impl<'data, T: Send> ParallelZonedIterator for ChunksExactMut<'data, T> {
+    type Item = &'data mut [T];
+
+    fn for_each_enumerated_with_context<F, C, CF>(self, pool: &ThreadPool, make_context: CF, f: F)
+    where
+        F: Fn(usize, &mut C, Self::Item) + Send + Sync,
+        C: Send,
+        CF: Fn() -> C + Send + Sync,
+    {
+        let chunk_size = self.chunk_size;
+        run_groups(pool, self.slice, chunk_size, |base, group| {
+            let mut ctx = make_context();
+            for (i, chunk) in group.chunks_exact_mut(chunk_size).enumerate() {
+                f(base + i, &mut ctx, chunk);
+            }
+        });
+    }
+}
+
+impl<'data, T: Send> ParallelZonedIterator for ChunksMut<'data, T> {
+    type Item = &'data mut [T];
+
+    fn for_each_enumerated_with_context<F, C, CF>(self, pool: &ThreadPool, make_context: CF, f: F)
+    where
+        F: Fn(usize, &mut C, Self::Item) + Send + Sync,
+        C: Send,
+        CF: Fn() -> C + Send + Sync,
+    {
+        let chunk_size = self.chunk_size;
+        run_groups(pool, self.slice, chunk_size, |base, group| {
+            let mut ctx = make_context();
+            for (i, chunk) in group.chunks_mut(chunk_size).enumerate() {
+                f(base + i, &mut ctx, chunk);
+            }
+        });
+    }
+}
+
+impl<'data, T, I> ParallelZonedIterator for ZipChunks<'data, T, I>
+where
+    T: Send,
+    I: Iterator + Send,
+    I::Item: Send,
+{
+    type Item = (&'data mut [T], I::Item);
+
+    fn for_each_enumerated_with_context<F, C, CF>(self, pool: &ThreadPool, make_context: CF, f: F)
+    where
+        F: Fn(usize, &mut C, Self::Item) + Send + Sync,
+        C: Send,
+        CF: Fn() -> C + Send + Sync,
+    {
+        let chunk_size = self.inner.chunk_size;
+        let total_chunks = self.inner.slice.len().div_ceil(chunk_size);
+        if total_chunks.div_ceil(pool.amount) >= total_chunks {
+            // Single group, pairs are consumed in place without collecting them
+            let mut ctx = make_context();
+            for (i, pair) in self
+                .inner
+                .slice
+                .chunks_mut(chunk_size)
+                .zip(self.other)
+                .enumerate()
+            {
+                f(i, &mut ctx, pair);
+            }
+            return;
+        }
+        let items = self
+            .other
+            .take(total_chunks)
+            .map(|item| Mutex::new(Some(item)))
+            .collect::<Vec<_>>();
+        let slice_len = (items.len() * chunk_size).min(self.inner.slice.len());
+        run_groups(
+            pool,
+            &mut self.inner.slice[..slice_len],
+            chunk_size,
+            |base, group| {
+                let mut ctx = make_context();
+                for (i, chunk) in group.chunks_mut(chunk_size).enumerate() {
+                    let item = items[base + i]
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .take();
+                    if let Some(item) = item {
+                        f(base + i, &mut ctx, (chunk, item));
+                    }
+                }
+            },
+        );
+    }
+}
+
+/// Runs `f` for every index in `0..cap` splitting the range between threads.
+pub(crate) fn parallel_range<F>(pool: &ThreadPool, cap: usize, f: F)
+where
+    F: Fn(usize) + Send + Sync,
+{
+    parallel_range_with_context(pool, cap, || (), |i, _| f(i));
+}
+
+/// Runs `f` for every index in `0..cap` splitting the range between threads,
+/// every thread receives its own context.
+pub(crate) fn parallel_range_with_context<F, C, CF>(
+    pool: &ThreadPool,
+    cap: usize,
+    make_context: CF,
+    f: F,
+) where
+    F: Fn(usize, &mut C) + Send + Sync,
+    C: Send,
+    CF: Fn() -> C + Send + Sync,
+{
+    if cap == 0 {
+        return;
+    }
+    let group_size = cap.div_ceil(pool.amount);
+    run_tasks(cap.div_ceil(group_size), &|group| {
+        let mut ctx = make_context();
+        for i in group * group_size..((group + 1) * group_size).min(cap) {
+            f(i, &mut ctx);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingExecutor {
+        calls: AtomicUsize,
+    }
+
+    impl BlurExecutor for CountingExecutor {
+        fn concurrency(&self) -> usize {
+            3
+        }
+
+        fn execute(&self, tasks: usize, task: &(dyn Fn(usize) + Sync)) {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            (0..tasks).rev().for_each(task);
+        }
+    }
+
+    #[test]
+    fn test_installed_executor_runs_all_chunks() {
+        let executor = CountingExecutor {
+            calls: AtomicUsize::new(0),
+        };
+        let mut data = [0usize; 23];
+        with_executor(&executor, || {
+            assert_eq!(executor_concurrency(), Some(3));
+            let pool = ThreadPool::new(executor_concurrency().unwrap());
+            data.tb_par_chunks_mut(4)
+                .zip(100..)
+                .for_each_enumerated(&pool, |i, (chunk, item)| {
+                    for v in chunk.iter_mut() {
+                        *v = i * 1000 + item;
+                    }
+                });
+        });
+        assert_eq!(executor_concurrency(), None);
+        assert_eq!(executor.calls.load(Ordering::Relaxed), 1);
+        for (i, v) in data.iter().enumerate() {
+            assert_eq!(*v, (i / 4) * 1000 + 100 + i / 4);
+        }
+    }
+}
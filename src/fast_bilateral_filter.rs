@@ -26,16 +26,15 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::gaussian::gaussian_kernel_1d;
+use crate::executor::{ParallelZonedIterator, TbSliceMut, ThreadPool, parallel_range};
+use crate::gaussian::fill_gaussian_kernel_1d;
+use crate::safe_math::{SafeAdd, SafeMul};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{BlurError, BlurImage, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use crate::{BlurError, BlurImage, BlurImageMut, BlurWorkspace, FastBlurChannels, ThreadingPolicy};
 use num_traits::AsPrimitive;
 use num_traits::real::Real;
-use rayon::ThreadPool;
-use rayon::iter::{IndexedParallelIterator, ParallelIterator};
-use rayon::prelude::{IntoParallelIterator, ParallelSlice, ParallelSliceMut};
 use std::fmt::{Debug, Display};
-use std::ops::{Add, AddAssign, Div, Index, Mul, MulAssign, Sub};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub};
 
 #[derive(Debug, Clone, Copy)]
 struct Vector<T> {
@@ -181,19 +180,14 @@ struct Array3D<'a, T> {
     x_dim: usize,
     y_dim: usize,
     z_dim: usize,
-    store: UnsafeSlice<'a, Vector<T>>,
+    store: UnsafeSlice<'a, [T; 2]>,
 }
 
 impl<'a, T> Array3D<'a, T>
 where
     T: Default + Clone,
 {
-    pub fn new(
-        slice: &'a mut [Vector<T>],
-        width: usize,
-        height: usize,
-        z: usize,
-    ) -> Array3D<'a, T> {
+    pub fn new(slice: &'a mut [[T; 2]], width: usize, height: usize, z: usize) -> Array3D<'a, T> {
         Array3D {
             x_dim: width,
             y_dim: height,
@@ -203,38 +197,21 @@ where
     }
 }
 
-impl<T> Index<usize> for Array3D<'_, T> {
-    type Output = Vector<T>;
-
-    #[inline]
-    fn index(&self, index: usize) -> &Self::Output {
-        self.store.get(index)
-    }
-}
-
-impl<T> Array3D<'_, T>
-where
-    T: Clone,
-{
-    #[inline]
-    pub fn find(&self, x: usize, y: usize, z: usize) -> &Vector<T> {
-        self.store.get((x * self.y_dim + y) * self.z_dim + z)
-    }
-}
-
 impl<T> Array3D<'_, T>
 where
     T: Clone + Copy,
 {
     #[inline]
     pub fn get(&self, x: usize, y: usize, z: usize) -> Vector<T> {
-        *self.store.get((x * self.y_dim + y) * self.z_dim + z)
+        let [vx, vy] = *self.store.get((x * self.y_dim + y) * self.z_dim + z);
+        Vector::new(vx, vy)
     }
 
     #[inline]
     pub fn set(&self, x: usize, y: usize, z: usize, val: Vector<T>) {
         unsafe {
-            self.store.write((x * self.y_dim + y) * self.z_dim + z, val);
+            self.store
+                .write((x * self.y_dim + y) * self.z_dim + z, [val.x, val.y]);
         }
     }
 }
@@ -294,6 +271,46 @@ where
     }
 }
 
+const GRID_PADDING_XY: f32 = 2.;
+const GRID_PADDING_Z: f32 = 2.;
+
+/// Bilateral grid dimensions of a plane with values starting from `base_min`
+struct GridShape<T> {
+    base_min: T,
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl<T: Copy + Sub<T, Output = T> + AsPrimitive<f32>> GridShape<T> {
+    fn new(
+        width: u32,
+        height: u32,
+        base_min: T,
+        base_max: T,
+        spatial_sigma: f32,
+        range_sigma: f32,
+    ) -> GridShape<T> {
+        let base_delta = base_max - base_min;
+        GridShape {
+            base_min,
+            width: (((width - 1) as f32 * (1. / spatial_sigma)) + 1. + 2. * GRID_PADDING_XY)
+                as usize,
+            height: (((height - 1) as f32 * (1. / spatial_sigma)) + 1. + 2. * GRID_PADDING_XY)
+                as usize,
+            depth: ((base_delta.as_() * (1. / range_sigma)) + 1. + 2. * GRID_PADDING_Z) as usize,
+        }
+    }
+
+    /// Amount of grid cells, tiny sigmas produce grids that can't be allocated
+    fn len(&self) -> Result<usize, BlurError> {
+        self.width
+            .checked_mul(self.height)
+            .and_then(|x| x.checked_mul(self.depth))
+            .ok_or(BlurError::ExceedingPointerSize)
+    }
+}
+
 #[allow(clippy::manual_clamp)]
 fn fast_bilateral_filter_impl<
     T: Copy
@@ -313,41 +330,35 @@ fn fast_bilateral_filter_impl<
         + Send
         + Sync,
 >(
-    img: &BlurImage<T>,
+    img: &[T],
     dst: &mut [T],
+    width: u32,
+    height: u32,
+    shape: &GridShape<T>,
     spatial_sigma: f32,
     range_sigma: f32,
+    grids: &mut [[T; 2]],
+    pool: &ThreadPool,
 ) where
     f32: AsPrimitive<T>,
 {
-    let width = img.width;
-    let height = img.height;
-    let mut base_max = T::min_value();
-    let mut base_min = T::max_value();
-    for item in img.data.as_ref().iter() {
-        base_min = item.min(base_min);
-        base_max = item.max(base_max);
-    }
-
-    let base_delta = base_max - base_min;
-
-    let padding_xy = 2.;
-    let padding_z = 2.;
+    let base_min = shape.base_min;
+    let padding_xy = GRID_PADDING_XY;
+    let padding_z = GRID_PADDING_Z;
 
     let spatial_sigma_scale = 1. / spatial_sigma;
     let range_sigma_scale = 1. / range_sigma;
 
-    let small_width = (((width - 1) as f32 * spatial_sigma_scale) + 1. + 2. * padding_xy) as usize;
-    let small_height =
-        (((height - 1) as f32 * spatial_sigma_scale) + 1. + 2. * padding_xy) as usize;
-    let small_depth = ((base_delta.as_() * range_sigma_scale) + 1. + 2. * padding_z) as usize;
+    let small_width = shape.width;
+    let small_height = shape.height;
+    let small_depth = shape.depth;
 
-    let mut target = vec![Vector::<T>::default(); small_height * small_depth * small_width];
+    let (target, target2) = grids.split_at_mut(grids.len() / 2);
+    target.fill([T::default(); 2]);
 
-    let mut data = Array3D::<T>::new(&mut target, small_width, small_height, small_depth);
+    let mut data = Array3D::<T>::new(target, small_width, small_height, small_depth);
 
-    let stride = img.row_stride() as usize;
-    let img = img.data.as_ref();
+    let stride = width as usize;
 
     for x in 0..width as usize {
         let small_x = ((x as f32) * spatial_sigma_scale + 0.5f32) + padding_xy;
@@ -358,25 +369,23 @@ fn fast_bilateral_filter_impl<
             let small_y = ((y as f32) * spatial_sigma_scale + 0.5f32) + padding_xy;
             let small_z = ((z.as_()) * range_sigma_scale + 0.5f32) + padding_z;
 
-            let mut d = *data.find(small_x as usize, small_y as usize, small_z as usize);
+            let mut d = data.get(small_x as usize, small_y as usize, small_z as usize);
             d.x += pixel;
             d.y += 1.0f32.as_();
             data.set(small_x as usize, small_y as usize, small_z as usize, d)
         }
     }
 
-    let mut target2 = vec![Vector::<T>::default(); small_height * small_depth * small_width];
-
-    let mut buffer = Array3D::<T>::new(&mut target2, small_width, small_height, small_depth);
+    let mut buffer = Array3D::<T>::new(target2, small_width, small_height, small_depth);
 
     let preferred_sigma = 1.0f32;
-    let grid_kernel_size = 7u32; // 6*sigma+1, fixed
-    let gaussian_kernel = gaussian_kernel_1d(grid_kernel_size, preferred_sigma);
+    let mut gaussian_kernel = [0f32; 7]; // 6*sigma+1, fixed
+    fill_gaussian_kernel_1d(&mut gaussian_kernel, preferred_sigma);
     let half_kernel = gaussian_kernel.len() / 2;
 
     // Unrolled 3D convolution
 
-    (0..small_depth).into_par_iter().for_each(|z| {
+    parallel_range(pool, small_depth, |z| {
         for y in 0..small_height {
             for x in 0..half_kernel.min(small_width) {
                 let mut sum = data
@@ -432,7 +441,7 @@ fn fast_bilateral_filter_impl<
 
     std::mem::swap(&mut buffer, &mut data);
 
-    (0..small_height).into_par_iter().for_each(|y| {
+    parallel_range(pool, small_height, |y| {
         for x in 0..small_width {
             for z in 0..half_kernel.min(small_depth) {
                 let mut sum = data
@@ -488,7 +497,7 @@ fn fast_bilateral_filter_impl<
 
     std::mem::swap(&mut buffer, &mut data);
 
-    (0..small_depth).into_par_iter().for_each(|z| {
+    parallel_range(pool, small_depth, |z| {
         for x in 0..small_width {
             for y in 0..half_kernel.min(small_height) {
                 let mut sum = data
@@ -544,10 +553,9 @@ fn fast_bilateral_filter_impl<
 
     std::mem::swap(&mut buffer, &mut data);
 
-    dst.par_chunks_exact_mut(width as usize)
-        .zip(img.par_chunks_exact(width as usize))
-        .enumerate()
-        .for_each(|(y, (row, src_row))| {
+    dst.tb_par_chunks_exact_mut(width as usize)
+        .for_each_enumerated(pool, |y, row| {
+            let src_row = &img[y * width as usize..(y + 1) * width as usize];
             for (x, (t, s)) in row.iter_mut().zip(src_row.iter()).enumerate() {
                 let z = (*s - base_min).as_();
                 let d = data.trilinear_interpolation(
@@ -561,7 +569,7 @@ fn fast_bilateral_filter_impl<
                     0f32.as_()
                 };
             }
-        })
+        });
 }
 
 pub trait BilinearWorkingItem<T> {
@@ -606,379 +614,130 @@ impl BilinearWorkingItem<f32> for f32 {
     }
 }
 
-fn fast_bilateral_filter_plane_impl<
+fn check_sigmas(spatial_sigma: f32, range_sigma: f32) -> Result<(), BlurError> {
+    for (name, sigma) in [
+        ("spatial_sigma", spatial_sigma),
+        ("range_sigma", range_sigma),
+    ] {
+        if !sigma.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name,
+                value: sigma as f64,
+            });
+        }
+        if sigma <= 0. {
+            return Err(BlurError::NegativeOrZeroSigma);
+        }
+    }
+    Ok(())
+}
+
+/// Filters every channel separately, channel is extracted into a plane of the workspace,
+/// filtered into another plane and written back into the destination.
+fn fast_bilateral_filter_channels_impl<
     V: Copy + Default + 'static + BilinearWorkingItem<V> + Debug + Send + Sync,
+    const CN: usize,
 >(
     img: &BlurImage<V>,
     dst: &mut BlurImageMut<V>,
     spatial_sigma: f32,
     range_sigma: f32,
     pool: &ThreadPool,
+    workspace: &mut BlurWorkspace<f32>,
 ) -> Result<(), BlurError> {
-    pool.install(|| {
-        img.check_layout()?;
-        dst.check_layout(Some(img))?;
-        img.size_matches_mut(dst)?;
-        let width = img.width;
-        let height = img.height;
-        assert!(
-            !(spatial_sigma <= 0. || range_sigma <= 0.0),
-            "Spatial sigma and range sigma must be more than 0"
-        );
-        let mut chan0 = vec![0f32; width as usize * height as usize];
-        for (dst, src) in chan0
-            .chunks_exact_mut(width as usize)
-            .zip(img.data.chunks_exact(img.row_stride() as usize))
-        {
-            for (r, &src) in dst.iter_mut().zip(src.iter()) {
-                *r = src.to_bilinear_f32();
+    img.check_layout_channels(CN)?;
+    dst.check_layout_channels(CN, Some(img))?;
+    img.size_matches_mut(dst)?;
+    check_sigmas(spatial_sigma, range_sigma)?;
+    let width = img.width;
+    let height = img.height;
+    let plane_size = (width as usize).safe_mul(height as usize)?;
+    let src_stride = img.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+
+    for c in 0..CN {
+        let mut base_min = f32::MAX;
+        let mut base_max = f32::MIN;
+        for src in img.data.chunks_exact(src_stride) {
+            for src in src.as_chunks::<CN>().0.iter().take(width as usize) {
+                let v = src[c].to_bilinear_f32();
+                base_min = v.min(base_min);
+                base_max = v.max(base_max);
             }
         }
-        let in_image = BlurImage::borrow(&chan0, width, height, FastBlurChannels::Plane);
-        let mut working_dst = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
 
-        fast_bilateral_filter_impl(
-            &in_image,
-            working_dst.data.borrow_mut(),
+        let shape = GridShape::new(
+            width,
+            height,
+            base_min,
+            base_max,
             spatial_sigma,
             range_sigma,
         );
+        let grids_size = shape.len()?.safe_mul(4)?;
+        let scratch = workspace.take(plane_size.safe_mul(2)?.safe_add(grids_size)?)?;
+        let (plane, rest) = scratch.split_at_mut(plane_size);
+        let (filtered, grids) = rest.split_at_mut(plane_size);
 
-        let dst_stride = dst.row_stride() as usize;
-
-        for (dst, src) in dst.data.borrow_mut().chunks_exact_mut(dst_stride).zip(
-            working_dst
-                .data
-                .borrow()
-                .chunks_exact(working_dst.row_stride() as usize),
-        ) {
-            for (r, &src) in dst.iter_mut().zip(src.iter()) {
-                *r = V::from_bilinear_f32(src);
-            }
-        }
-        Ok(())
-    })
-}
-
-#[cfg(feature = "image")]
-pub(crate) fn fast_bilateral_filter_gray_alpha_impl<
-    V: Copy + Default + 'static + BilinearWorkingItem<V> + Send + Sync + Debug,
->(
-    img: &BlurImage<V>,
-    dst: &mut BlurImageMut<V>,
-    spatial_sigma: f32,
-    range_sigma: f32,
-    threading_policy: ThreadingPolicy,
-) -> Result<(), BlurError> {
-    img.check_layout_channels(2)?;
-    dst.check_layout_channels(2, Some(img))?;
-    assert!(
-        !(spatial_sigma <= 0. || range_sigma <= 0.0),
-        "Spatial sigma and range sigma must be more than 0"
-    );
-
-    let thread_count = threading_policy.thread_count(img.width, img.height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-
-    pool.install(|| {
-        let width = img.width;
-        let height = img.height;
-        let mut in_image0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        for ((dst0, dst1), src) in in_image0
-            .data
-            .borrow_mut()
+        for (dst, src) in plane
             .chunks_exact_mut(width as usize)
-            .zip(in_image1.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(img.data.chunks_exact(img.row_stride() as usize))
+            .zip(img.data.chunks_exact(src_stride))
         {
-            for ((r, g), src) in dst0
-                .iter_mut()
-                .zip(dst1.iter_mut())
-                .zip(src.as_chunks::<2>().0.iter())
-            {
-                *r = src[0].to_bilinear_f32();
-                *g = src[1].to_bilinear_f32();
+            for (r, src) in dst.iter_mut().zip(src.as_chunks::<CN>().0.iter()) {
+                *r = src[c].to_bilinear_f32();
             }
         }
 
-        let mut working_dst0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-
-        let ref0 = in_image0.to_immutable_ref();
-        let ref1 = in_image1.to_immutable_ref();
-
-        pool.scope(|s| {
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref0,
-                    working_dst0.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref1,
-                    working_dst1.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-        });
-
-        let dst_stride = dst.row_stride() as usize;
-
-        dst.data
-            .borrow_mut()
-            .chunks_exact_mut(dst_stride)
-            .zip(working_dst0.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst1.data.borrow().chunks_exact(width as usize))
-            .for_each(|((dst, src0), src1)| {
-                for ((dst, src0), src1) in dst
-                    .as_chunks_mut::<2>()
-                    .0
-                    .iter_mut()
-                    .zip(src0.iter())
-                    .zip(src1.iter())
-                {
-                    dst[0] = V::from_bilinear_f32(*src0);
-                    dst[1] = V::from_bilinear_f32(*src1);
-                }
-            });
-        Ok(())
-    })
-}
-
-fn fast_bilateral_filter_rgb_impl<
-    V: Copy + Default + 'static + BilinearWorkingItem<V> + Send + Sync + Debug,
->(
-    img: &BlurImage<V>,
-    dst: &mut BlurImageMut<V>,
-    spatial_sigma: f32,
-    range_sigma: f32,
-    pool: &ThreadPool,
-) -> Result<(), BlurError> {
-    pool.install(|| {
-        img.check_layout()?;
-        dst.check_layout(None)?;
-        img.size_matches_mut(dst)?;
-        let width = img.width;
-        let height = img.height;
-        assert!(
-            !(spatial_sigma <= 0. || range_sigma <= 0.0),
-            "Spatial sigma and range sigma must be more than 0"
+        fast_bilateral_filter_impl(
+            plane,
+            filtered,
+            width,
+            height,
+            &shape,
+            spatial_sigma,
+            range_sigma,
+            grids.as_chunks_mut::<2>().0,
+            pool,
         );
-        let mut in_image0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image2 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        for (((dst0, dst1), dst2), src) in in_image0
+
+        for (dst, src) in dst
             .data
             .borrow_mut()
-            .chunks_exact_mut(width as usize)
-            .zip(in_image1.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(in_image2.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(img.data.chunks_exact(img.row_stride() as usize))
+            .chunks_exact_mut(dst_stride)
+            .zip(filtered.chunks_exact(width as usize))
         {
-            for (((r, g), b), src) in dst0
-                .iter_mut()
-                .zip(dst1.iter_mut())
-                .zip(dst2.iter_mut())
-                .zip(src.as_chunks::<3>().0.iter())
-            {
-                *r = src[0].to_bilinear_f32();
-                *g = src[1].to_bilinear_f32();
-                *b = src[2].to_bilinear_f32();
+            for (dst, &src) in dst.as_chunks_mut::<CN>().0.iter_mut().zip(src.iter()) {
+                dst[c] = V::from_bilinear_f32(src);
             }
         }
-
-        let mut working_dst0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst2 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-
-        let ref0 = in_image0.to_immutable_ref();
-        let ref1 = in_image1.to_immutable_ref();
-        let ref2 = in_image2.to_immutable_ref();
-
-        pool.scope(|s| {
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref0,
-                    working_dst0.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref1,
-                    working_dst1.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref2,
-                    working_dst2.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-        });
-
-        let dst_stride = dst.row_stride() as usize;
-
-        dst.data
-            .borrow_mut()
-            .chunks_exact_mut(dst_stride)
-            .zip(working_dst0.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst1.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst2.data.borrow().chunks_exact(width as usize))
-            .for_each(|(((dst, src0), src1), src2)| {
-                for (((dst, src0), src1), src2) in dst
-                    .as_chunks_mut::<3>()
-                    .0
-                    .iter_mut()
-                    .zip(src0.iter())
-                    .zip(src1.iter())
-                    .zip(src2)
-                {
-                    dst[0] = V::from_bilinear_f32(*src0);
-                    dst[1] = V::from_bilinear_f32(*src1);
-                    dst[2] = V::from_bilinear_f32(*src2);
-                }
-            });
-
-        Ok(())
-    })
+    }
+    Ok(())
 }
 
-fn fast_bilateral_filter_rgba_impl<
-    V: Copy + Default + 'static + BilinearWorkingItem<V> + Sync + Send + Debug,
+fn fast_bilateral_filter_dispatch<
+    V: Copy + Default + 'static + BilinearWorkingItem<V> + Debug + Send + Sync,
 >(
-    img: &BlurImage<V>,
+    src: &BlurImage<V>,
     dst: &mut BlurImageMut<V>,
     spatial_sigma: f32,
     range_sigma: f32,
-    pool: &ThreadPool,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<f32>,
 ) -> Result<(), BlurError> {
-    pool.install(|| {
-        img.check_layout()?;
-        dst.check_layout(Some(img))?;
-        img.size_matches_mut(dst)?;
-        let width = img.width;
-        let height = img.height;
-        assert!(
-            !(spatial_sigma <= 0. || range_sigma <= 0.0),
-            "Spatial sigma and range sigma must be more than 0"
-        );
-        let mut in_image0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image2 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut in_image3 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        for ((((dst0, dst1), dst2), dst3), src) in in_image0
-            .data
-            .borrow_mut()
-            .chunks_exact_mut(width as usize)
-            .zip(in_image1.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(in_image2.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(in_image3.data.borrow_mut().chunks_exact_mut(width as usize))
-            .zip(img.data.chunks_exact(img.row_stride() as usize))
-        {
-            for ((((r, g), b), a), src) in dst0
-                .iter_mut()
-                .zip(dst1.iter_mut())
-                .zip(dst2.iter_mut())
-                .zip(dst3.iter_mut())
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                *r = src[0].to_bilinear_f32();
-                *g = src[1].to_bilinear_f32();
-                *b = src[2].to_bilinear_f32();
-                *a = src[3].to_bilinear_f32();
-            }
-        }
-
-        let mut working_dst0 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst1 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst2 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-        let mut working_dst3 = BlurImageMut::alloc(width, height, FastBlurChannels::Plane);
-
-        let ref0 = in_image0.to_immutable_ref();
-        let ref1 = in_image1.to_immutable_ref();
-        let ref2 = in_image2.to_immutable_ref();
-        let ref3 = in_image3.to_immutable_ref();
-
-        pool.scope(|s| {
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref0,
-                    working_dst0.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref1,
-                    working_dst1.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref2,
-                    working_dst2.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            });
-
-            s.spawn(|_| {
-                fast_bilateral_filter_impl(
-                    &ref3,
-                    working_dst3.data.borrow_mut(),
-                    spatial_sigma,
-                    range_sigma,
-                );
-            })
-        });
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
 
-        let dst_stride = dst.row_stride() as usize;
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = ThreadPool::new(thread_count);
 
-        dst.data
-            .borrow_mut()
-            .chunks_exact_mut(dst_stride)
-            .zip(working_dst0.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst1.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst2.data.borrow().chunks_exact(width as usize))
-            .zip(working_dst3.data.borrow().chunks_exact(width as usize))
-            .for_each(|((((dst, src0), src1), src2), src3)| {
-                for ((((dst, src0), src1), src2), src3) in dst
-                    .as_chunks_mut::<4>()
-                    .0
-                    .iter_mut()
-                    .zip(src0.iter())
-                    .zip(src1.iter())
-                    .zip(src2.iter())
-                    .zip(src3.iter())
-                {
-                    dst[0] = V::from_bilinear_f32(*src0);
-                    dst[1] = V::from_bilinear_f32(*src1);
-                    dst[2] = V::from_bilinear_f32(*src2);
-                    dst[3] = V::from_bilinear_f32(*src3);
-                }
-            });
-
-        Ok(())
-    })
+    let dispatcher = match src.channels {
+        FastBlurChannels::Plane => fast_bilateral_filter_channels_impl::<V, 1>,
+        FastBlurChannels::Channels2 => fast_bilateral_filter_channels_impl::<V, 2>,
+        FastBlurChannels::Channels3 => fast_bilateral_filter_channels_impl::<V, 3>,
+        FastBlurChannels::Channels4 => fast_bilateral_filter_channels_impl::<V, 4>,
+    };
+    dispatcher(src, dst, spatial_sigma, range_sigma, &pool, workspace)
 }
 
 /// Performs fast bilateral filter on the 8-bit image
@@ -1002,29 +761,47 @@ pub fn fast_bilateral_filter(
     range_sigma: f32,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError> {
-    src.check_layout()?;
-    dst.check_layout(Some(src))?;
-    src.size_matches_mut(dst)?;
-    let channels = src.channels;
-
-    let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    fast_bilateral_filter_dispatch(
+        src,
+        dst,
+        spatial_sigma,
+        range_sigma,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
+}
 
-    match channels {
-        FastBlurChannels::Plane => {
-            fast_bilateral_filter_plane_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels3 => {
-            fast_bilateral_filter_rgb_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels4 => {
-            fast_bilateral_filter_rgba_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-    }
-    Ok(())
+/// Performs fast bilateral filter on the 8-bit image using provided workspace
+///
+/// Same as [fast_bilateral_filter], channel planes and bilateral grids are taken
+/// from the provided [BlurWorkspace] and reused between calls. Grid depth depends
+/// on the value range of the image, so the workspace may grow when a wider range is filtered.
+///
+/// # Arguments
+///
+/// * `src`: Source image, see [BlurImage] for more info
+/// * `dst`: Destination image, see [BlurImageMut] for more info
+/// * `spatial_sigma`: Spatial sigma
+/// * `range_sigma`: Range sigma
+/// * `threading_policy`: Threads usage policy
+/// * `workspace`: Reusable scratch memory, see [BlurWorkspace] for more info
+///
+pub fn fast_bilateral_filter_with_workspace(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<f32>,
+) -> Result<(), BlurError> {
+    fast_bilateral_filter_dispatch(
+        src,
+        dst,
+        spatial_sigma,
+        range_sigma,
+        threading_policy,
+        workspace,
+    )
 }
 
 /// Performs fast bilateral filter on the up to 16-bit image
@@ -1051,29 +828,14 @@ pub fn fast_bilateral_filter_u16(
     range_sigma: f32,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError> {
-    src.check_layout()?;
-    dst.check_layout(Some(src))?;
-    src.size_matches_mut(dst)?;
-    let channels = src.channels;
-
-    let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-
-    match channels {
-        FastBlurChannels::Plane => {
-            fast_bilateral_filter_plane_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels3 => {
-            fast_bilateral_filter_rgb_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels4 => {
-            fast_bilateral_filter_rgba_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-    }
-    Ok(())
+    fast_bilateral_filter_dispatch(
+        src,
+        dst,
+        spatial_sigma,
+        range_sigma,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
 }
 
 /// Performs fast bilateral filter on the f32 image
@@ -1100,27 +862,12 @@ pub fn fast_bilateral_filter_f32(
     range_sigma: f32,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError> {
-    src.check_layout()?;
-    dst.check_layout(Some(src))?;
-    src.size_matches_mut(dst)?;
-    let channels = src.channels;
-
-    let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-
-    match channels {
-        FastBlurChannels::Plane => {
-            fast_bilateral_filter_plane_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels3 => {
-            fast_bilateral_filter_rgb_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-        FastBlurChannels::Channels4 => {
-            fast_bilateral_filter_rgba_impl(src, dst, spatial_sigma, range_sigma, &pool)?;
-        }
-    }
-    Ok(())
+    fast_bilateral_filter_dispatch(
+        src,
+        dst,
+        spatial_sigma,
+        range_sigma,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
 }
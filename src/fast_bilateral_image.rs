@@ -26,9 +26,8 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::fast_bilateral_filter::fast_bilateral_filter_gray_alpha_impl;
 use crate::{
-    BlurImage, BlurImageMut, BufferStore, FastBlurChannels, ThreadingPolicy, fast_bilateral_filter,
+    BlurImage, BlurImageMut, FastBlurChannels, ThreadingPolicy, fast_bilateral_filter,
     fast_bilateral_filter_f32, fast_bilateral_filter_u16,
 };
 use image::{
@@ -82,29 +81,21 @@ pub fn fast_bilateral_filter_image(
             Some(DynamicImage::ImageLuma8(new_gray_image))
         }
         DynamicImage::ImageLumaA8(luma_alpha_image) => {
-            let captured_image = BlurImage {
-                data: std::borrow::Cow::Borrowed(&luma_alpha_image),
-                stride: luma_alpha_image.width() * 2,
-                width: luma_alpha_image.width(),
-                height: luma_alpha_image.height(),
-                channels: FastBlurChannels::Plane,
-            };
+            let mut new_image = BlurImageMut::alloc(
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
+            );
 
-            let mut new_image = BlurImageMut {
-                data: BufferStore::Owned(vec![
-                    0u8;
-                    luma_alpha_image.width() as usize
-                        * luma_alpha_image.height() as usize
-                        * 2
-                ]),
-                stride: luma_alpha_image.width() * 2,
-                width: luma_alpha_image.width(),
-                height: luma_alpha_image.height(),
-                channels: FastBlurChannels::Plane,
-            };
+            let blur_image = BlurImage::borrow(
+                &luma_alpha_image,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
+            );
 
-            fast_bilateral_filter_gray_alpha_impl(
-                &captured_image,
+            fast_bilateral_filter(
+                &blur_image,
                 &mut new_image,
                 spatial_sigma,
                 range_sigma,
@@ -205,30 +196,22 @@ pub fn fast_bilateral_filter_image(
             )?;
             Some(DynamicImage::ImageLuma16(new_rgb_image))
         }
-        DynamicImage::ImageLumaA16(gray_alpha_16) => {
-            let captured_image = BlurImage {
-                data: std::borrow::Cow::Borrowed(&gray_alpha_16),
-                stride: gray_alpha_16.width() * 2,
-                width: gray_alpha_16.width(),
-                height: gray_alpha_16.height(),
-                channels: FastBlurChannels::Plane,
-            };
+        DynamicImage::ImageLumaA16(luma_alpha_image) => {
+            let mut new_image = BlurImageMut::alloc(
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
+            );
 
-            let mut new_image = BlurImageMut {
-                data: BufferStore::Owned(vec![
-                    0u16;
-                    gray_alpha_16.width() as usize
-                        * gray_alpha_16.height() as usize
-                        * 2
-                ]),
-                stride: gray_alpha_16.width() * 2,
-                width: gray_alpha_16.width(),
-                height: gray_alpha_16.height(),
-                channels: FastBlurChannels::Plane,
-            };
+            let blur_image = BlurImage::borrow(
+                &luma_alpha_image,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
+            );
 
-            fast_bilateral_filter_gray_alpha_impl(
-                &captured_image,
+            fast_bilateral_filter_u16(
+                &blur_image,
                 &mut new_image,
                 spatial_sigma,
                 range_sigma,
@@ -237,8 +220,8 @@ pub fn fast_bilateral_filter_image(
             .unwrap();
 
             let new_gray_image = ImageBuffer::<LumaA<u16>, Vec<u16>>::from_raw(
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
                 new_image.data.borrow().to_vec(),
             )?;
             Some(DynamicImage::ImageLumaA16(new_gray_image))
@@ -38,6 +38,8 @@ use crate::neon::{
     fg_horizontal_pass_neon_f32, fg_horizontal_pass_neon_u8, fg_vertical_pass_neon_f32,
     fg_vertical_pass_neon_u8,
 };
+use crate::padded_blur::{blur_in_place_padded, needs_constant_padding};
+use crate::premultiply::blur_straight_in_place;
 use crate::primitives::PrimitiveCast;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
@@ -53,11 +55,24 @@ use crate::sse::{
 use crate::threading_policy::ThreadingPolicy;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 use crate::wasm32::{fg_horizontal_pass_wasm_u8, fg_vertical_pass_wasm_u8};
-use crate::{AnisotropicRadius, BlurError, BlurImageMut, EdgeMode, EdgeMode2D};
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImageMut, EdgeMode, EdgeMode2D,
+    MAX_INTEGER_RADIUS, Scalar,
+};
 
 const BASE_RADIUS_I64_CUTOFF: u32 = 180;
+/// Sum of u8 values fits i32 while 255 * radius^2 < 2^31,
+/// larger radius is blurred with i64 accumulator.
+const U8_RADIUS_I64_CUTOFF: u32 = 2896;
+
+/// Size of the ring buffer holding `2 * radius + 1` last differences,
+/// power of two so position in ring is taken by mask.
+pub(crate) fn fg_ring_size(radius: u32) -> usize {
+    (radius as usize * 2 + 1).next_power_of_two().max(1024)
+}
 
 macro_rules! update_differences_inside {
     ($dif_r:expr, $buffer_r:expr, $arr_index:expr, $d_arr_index:expr) => {{
@@ -85,6 +100,7 @@ macro_rules! update_sum_in {
     }};
 }
 
+// Sums are not biased, rounding happens once when the value is stored.
 macro_rules! write_out_blurred {
     ($sum:expr, $weight:expr, $bytes:expr, $bytes_offset:expr) => {{
         let sum_f: M = $sum.cast_();
@@ -101,6 +117,7 @@ macro_rules! impl_generic_call {
         $radius:expr, $threading_policy:expr) => {
         let _dispatch = match $channels_type {
             FastBlurChannels::Plane => fast_gaussian_impl::<$store_type, 1>,
+            FastBlurChannels::Channels2 => fast_gaussian_impl::<$store_type, 2>,
             FastBlurChannels::Channels3 => fast_gaussian_impl::<$store_type, 3>,
             FastBlurChannels::Channels4 => fast_gaussian_impl::<$store_type, 4>,
         };
@@ -134,41 +151,6 @@ macro_rules! impl_margin_call {
     };
 }
 
-trait InitialValue {
-    fn get_initial(radius: usize) -> i64;
-}
-
-impl InitialValue for f32 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
-#[cfg(feature = "nightly_f16")]
-impl InitialValue for f16 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
-impl InitialValue for f64 {
-    fn get_initial(_: usize) -> i64 {
-        0i64
-    }
-}
-
-impl InitialValue for u8 {
-    fn get_initial(radius: usize) -> i64 {
-        ((radius * radius) >> 1) as i64
-    }
-}
-
-impl InitialValue for u16 {
-    fn get_initial(radius: usize) -> i64 {
-        ((radius * radius) >> 1) as i64
-    }
-}
-
 /// # Params
 /// `T` - type of buffer
 /// `J` - accumulator type
@@ -183,13 +165,7 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
     end: u32,
     edge_mode: EdgeMode,
 ) where
-    T: std::ops::AddAssign
-        + 'static
-        + std::ops::SubAssign
-        + Copy
-        + Default
-        + PrimitiveCast<J>
-        + InitialValue,
+    T: std::ops::AddAssign + 'static + std::ops::SubAssign + Copy + Default + PrimitiveCast<J>,
     J: Copy
         + Default
         + std::ops::Mul<Output = J>
@@ -203,24 +179,28 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
     i64: PrimitiveCast<J>,
     f64: PrimitiveCast<M>,
 {
-    let zero_j: J = PrimitiveCast::cast_(0i32);
-    let mut buffer_r = [zero_j; 1024];
-    let mut buffer_g = [zero_j; 1024];
-    let mut buffer_b = [zero_j; 1024];
-    let mut buffer_a = [zero_j; 1024];
+    let ring_size = fg_ring_size(radius);
+    let ring_mask = ring_size as i64 - 1;
+    let mut buffer_r = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_g = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_b = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_a = ScratchBuffer::<J, 1024>::new(ring_size);
+    let buffer_r = buffer_r.as_mut_slice();
+    let buffer_g = buffer_g.as_mut_slice();
+    let buffer_b = buffer_b.as_mut_slice();
+    let buffer_a = buffer_a.as_mut_slice();
     let radius_64 = radius as i64;
     let height_wide = height as i64;
-    let initial: J = PrimitiveCast::cast_(T::get_initial(radius as usize));
     let weight = PrimitiveCast::cast_(1f64 / (radius as f64 * radius as f64));
     for x in start..width.min(end) {
         let mut dif_r: J = 0i32.cast_();
-        let mut sum_r: J = initial;
+        let mut sum_r: J = 0i32.cast_();
         let mut dif_g: J = 0i32.cast_();
-        let mut sum_g: J = initial;
+        let mut sum_g: J = 0i32.cast_();
         let mut dif_b: J = 0i32.cast_();
-        let mut sum_b: J = initial;
+        let mut sum_b: J = 0i32.cast_();
         let mut dif_a: J = 0i32.cast_();
-        let mut sum_a: J = initial;
+        let mut sum_a: J = 0i32.cast_();
 
         let current_px = (x * CN as u32) as usize;
 
@@ -241,8 +221,8 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
                     write_out_blurred!(sum_a, weight, bytes, bytes_offset + 3);
                 }
 
-                let arr_index = ((y - radius_64) & 1023) as usize;
-                let d_arr_index = (y & 1023) as usize;
+                let arr_index = ((y - radius_64) & ring_mask) as usize;
+                let d_arr_index = (y & ring_mask) as usize;
                 update_differences_inside!(dif_r, buffer_r, arr_index, d_arr_index);
                 if CN > 1 {
                     update_differences_inside!(dif_g, buffer_g, arr_index, d_arr_index);
@@ -254,7 +234,7 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
                     update_differences_inside!(dif_a, buffer_a, arr_index, d_arr_index);
                 }
             } else if y + radius_64 >= 0 {
-                let arr_index = (y & 1023) as usize;
+                let arr_index = (y & ring_mask) as usize;
                 update_differences_out!(dif_r, buffer_r, arr_index);
                 if CN > 1 {
                     update_differences_out!(dif_g, buffer_g, arr_index);
@@ -273,7 +253,7 @@ fn fg_vertical_pass<T, J, M, const CN: usize>(
 
             let px_idx = next_row_y + next_row_x;
 
-            let arr_index = ((y + radius_64) & 1023) as usize;
+            let arr_index = ((y + radius_64) & ring_mask) as usize;
 
             update_sum_in!(bytes, px_idx, dif_r, sum_r, buffer_r, arr_index);
             if CN > 1 {
@@ -304,13 +284,7 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
     end: u32,
     edge_mode: EdgeMode,
 ) where
-    T: std::ops::AddAssign
-        + 'static
-        + std::ops::SubAssign
-        + Copy
-        + Default
-        + PrimitiveCast<J>
-        + InitialValue,
+    T: std::ops::AddAssign + 'static + std::ops::SubAssign + Copy + Default + PrimitiveCast<J>,
     J: Copy
         + Default
         + std::ops::Mul<Output = J>
@@ -324,23 +298,28 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
     f64: PrimitiveCast<M>,
     i64: PrimitiveCast<J>,
 {
-    let mut buffer_r = [0i32.cast_(); 1024];
-    let mut buffer_g = [0i32.cast_(); 1024];
-    let mut buffer_b = [0i32.cast_(); 1024];
-    let mut buffer_a = [0i32.cast_(); 1024];
+    let ring_size = fg_ring_size(radius);
+    let ring_mask = ring_size as i64 - 1;
+    let mut buffer_r = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_g = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_b = ScratchBuffer::<J, 1024>::new(ring_size);
+    let mut buffer_a = ScratchBuffer::<J, 1024>::new(ring_size);
+    let buffer_r = buffer_r.as_mut_slice();
+    let buffer_g = buffer_g.as_mut_slice();
+    let buffer_b = buffer_b.as_mut_slice();
+    let buffer_a = buffer_a.as_mut_slice();
     let radius_64 = radius as i64;
     let width_wide = width as i64;
     let weight: M = (1f64 / (radius as f64 * radius as f64)).cast_();
-    let initial: J = T::get_initial(radius as usize).cast_();
     for y in start..height.min(end) {
         let mut dif_r: J = 0i32.cast_();
-        let mut sum_r: J = initial;
+        let mut sum_r: J = 0i32.cast_();
         let mut dif_g: J = 0i32.cast_();
-        let mut sum_g: J = initial;
+        let mut sum_g: J = 0i32.cast_();
         let mut dif_b: J = 0i32.cast_();
-        let mut sum_b: J = initial;
+        let mut sum_b: J = 0i32.cast_();
         let mut dif_a: J = 0i32.cast_();
-        let mut sum_a: J = initial;
+        let mut sum_a: J = 0i32.cast_();
 
         let current_y = ((y as i64) * (stride as i64)) as usize;
 
@@ -362,8 +341,8 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
                     write_out_blurred!(sum_a, weight, bytes, bytes_offset + 3);
                 }
 
-                let arr_index = ((x - radius_64) & 1023) as usize;
-                let d_arr_index = (x & 1023) as usize;
+                let arr_index = ((x - radius_64) & ring_mask) as usize;
+                let d_arr_index = (x & ring_mask) as usize;
                 update_differences_inside!(dif_r, buffer_r, arr_index, d_arr_index);
                 if CN > 1 {
                     update_differences_inside!(dif_g, buffer_g, arr_index, d_arr_index);
@@ -375,7 +354,7 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
                     update_differences_inside!(dif_a, buffer_a, arr_index, d_arr_index);
                 }
             } else if x + radius_64 >= 0 {
-                let arr_index = (x & 1023) as usize;
+                let arr_index = (x & ring_mask) as usize;
                 update_differences_out!(dif_r, buffer_r, arr_index);
                 if CN > 1 {
                     update_differences_out!(dif_g, buffer_g, arr_index);
@@ -393,7 +372,7 @@ fn fg_horizontal_pass<T, J, M, const CN: usize>(
 
             let bytes_offset = next_row_y + next_row_x;
 
-            let arr_index = ((x + radius_64) & 1023) as usize;
+            let arr_index = ((x + radius_64) & ring_mask) as usize;
 
             update_sum_in!(bytes, bytes_offset, dif_r, sum_r, buffer_r, arr_index);
             if CN > 1 {
@@ -509,25 +488,27 @@ impl FastGaussianDispatchProvider<u8> for u8 {
             u32,
             u32,
             EdgeMode,
-        ) = if BASE_RADIUS_I64_CUTOFF > radius {
+        ) = if U8_RADIUS_I64_CUTOFF > radius {
             fg_horizontal_pass::<u8, i32, f32, CN>
         } else {
             fg_horizontal_pass::<u8, i64, f64, CN>
         };
         #[cfg(all(target_arch = "aarch64", feature = "sve"))]
         {
-            if std::arch::is_aarch64_feature_detected!("sve2") {
+            if BASE_RADIUS_I64_CUTOFF > radius && std::arch::is_aarch64_feature_detected!("sve2") {
                 use crate::sve::fg_horizontal_pass_neon_u8_sve;
                 return fg_horizontal_pass_neon_u8_sve::<CN>;
             }
         }
         #[cfg(all(target_arch = "aarch64", feature = "neon"))]
         {
-            if BASE_RADIUS_I64_CUTOFF > radius {
+            if U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_horizontal = fg_horizontal_pass_neon_u8::<CN>;
                 #[cfg(feature = "rdm")]
                 {
-                    if std::arch::is_aarch64_feature_detected!("rdm") {
+                    if BASE_RADIUS_I64_CUTOFF > radius
+                        && std::arch::is_aarch64_feature_detected!("rdm")
+                    {
                         use crate::neon::fg_horizontal_pass_neon_u8_rdm;
                         _dispatcher_horizontal = fg_horizontal_pass_neon_u8_rdm::<CN>;
                     }
@@ -536,14 +517,14 @@ impl FastGaussianDispatchProvider<u8> for u8 {
         }
         #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
         {
-            if BASE_RADIUS_I64_CUTOFF > radius {
+            if U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_horizontal = fg_horizontal_pass_wasm_u8::<CN>;
             }
         }
         #[cfg(all(target_arch = "x86_64", feature = "avx"))]
         {
             let has_avx = std::arch::is_x86_feature_detected!("avx2");
-            if has_avx && BASE_RADIUS_I64_CUTOFF > radius {
+            if has_avx && U8_RADIUS_I64_CUTOFF > radius {
                 use crate::avx::fg_horizontal_pass_sse_u8;
                 return fg_horizontal_pass_sse_u8::<CN>;
             }
@@ -551,7 +532,7 @@ impl FastGaussianDispatchProvider<u8> for u8 {
         #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
         {
             let is_sse_available = std::arch::is_x86_feature_detected!("sse4.1");
-            if is_sse_available && BASE_RADIUS_I64_CUTOFF > radius {
+            if is_sse_available && U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_horizontal = fg_horizontal_pass_sse_u8::<CN>;
             }
         }
@@ -570,25 +551,27 @@ impl FastGaussianDispatchProvider<u8> for u8 {
             start: u32,
             end: u32,
             EdgeMode,
-        ) = if BASE_RADIUS_I64_CUTOFF > radius {
+        ) = if U8_RADIUS_I64_CUTOFF > radius {
             fg_vertical_pass::<u8, i32, f32, CN>
         } else {
             fg_vertical_pass::<u8, i64, f64, CN>
         };
         #[cfg(all(target_arch = "aarch64", feature = "sve"))]
         {
-            if std::arch::is_aarch64_feature_detected!("sve2") {
+            if BASE_RADIUS_I64_CUTOFF > radius && std::arch::is_aarch64_feature_detected!("sve2") {
                 use crate::sve::fg_vertical_pass_neon_u8_sve;
                 return fg_vertical_pass_neon_u8_sve::<CN>;
             }
         }
         #[cfg(all(target_arch = "aarch64", feature = "neon"))]
         {
-            if BASE_RADIUS_I64_CUTOFF > radius {
+            if U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_vertical = fg_vertical_pass_neon_u8::<CN>;
                 #[cfg(feature = "rdm")]
                 {
-                    if std::arch::is_aarch64_feature_detected!("rdm") {
+                    if BASE_RADIUS_I64_CUTOFF > radius
+                        && std::arch::is_aarch64_feature_detected!("rdm")
+                    {
                         use crate::neon::fg_vertical_pass_neon_u8_rdm;
                         _dispatcher_vertical = fg_vertical_pass_neon_u8_rdm::<CN>;
                     }
@@ -597,14 +580,14 @@ impl FastGaussianDispatchProvider<u8> for u8 {
         }
         #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
         {
-            if BASE_RADIUS_I64_CUTOFF > radius {
+            if U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_vertical = fg_vertical_pass_wasm_u8::<CN>;
             }
         }
         #[cfg(all(target_arch = "x86_64", feature = "avx"))]
         {
             let has_avx = std::arch::is_x86_feature_detected!("avx2");
-            if has_avx && BASE_RADIUS_I64_CUTOFF > radius {
+            if has_avx && U8_RADIUS_I64_CUTOFF > radius {
                 use crate::avx::fg_vertical_pass_avx_u8;
                 return fg_vertical_pass_avx_u8::<CN>;
             }
@@ -612,7 +595,7 @@ impl FastGaussianDispatchProvider<u8> for u8 {
         #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
         {
             let is_sse_available = std::arch::is_x86_feature_detected!("sse4.1");
-            if is_sse_available && BASE_RADIUS_I64_CUTOFF > radius {
+            if is_sse_available && U8_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_vertical = fg_vertical_pass_sse_u8::<CN>;
             }
         }
@@ -778,7 +761,6 @@ fn fast_gaussian_impl<
         + PrimitiveCast<i64>
         + PrimitiveCast<f32>
         + PrimitiveCast<f64>
-        + InitialValue
         + FastGaussianDispatchProvider<T>,
     const CN: usize,
 >(
@@ -807,7 +789,7 @@ fn fast_gaussian_impl<
     ) = T::get_vertical::<CN>(radius.y_axis);
     let mut _dispatcher_horizontal: fn(&UnsafeSlice<T>, u32, u32, u32, u32, u32, u32, EdgeMode) =
         T::get_horizontal::<CN>(radius.x_axis);
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
         let segment_size = width as usize / thread_count as usize;
 
@@ -850,30 +832,68 @@ fn fast_gaussian_impl<
 
 /// Performs gaussian approximation on the image.
 ///
-/// Fast gaussian approximation for u8 image, limited to 319 radius, sometimes on the very bright images may start ringing on a very large radius.
+/// Fast gaussian approximation for u8 image, sometimes on the very bright images may start ringing on a very large radius.
 /// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed
 /// Results better than in stack blur however this a little slower.
-/// This is a very fast approximation using i32 accumulator size with radius less that *BASE_RADIUS_I64_CUTOFF*,
-/// after it to avoid overflowing fallback to i64 accumulator will be used with some computational slowdown with factor ~2.
+/// This is a very fast approximation using i32 accumulator size with radius less that *U8_RADIUS_I64_CUTOFF* (2896),
+/// after it to avoid overflowing fallback to i64 accumulator will be used. This fallback has no SIMD
+/// implementation, so expect it to be several times slower than the vectorized i32 path.
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
 /// * `image` - Image to work in place, see [BlurImageMut] for more info.
-/// * `radius` - Radius more than 319 is not supported. To use larger radius convert image to f32 and use function for f32.
+/// * `radius` - Radius up to 65535, minimum is one.
 /// * `threading_policy` - Threads usage policy.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided,
+/// or radius exceeds 65535.
 pub fn fast_gaussian(
     image: &mut BlurImageMut<u8>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
-    let radius = radius.clamp(1, 319);
+    radius.check_max(MAX_INTEGER_RADIUS)?;
+    let radius = radius.max(1);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 2 + 1, radius.y_axis * 2 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
@@ -894,31 +914,70 @@ pub fn fast_gaussian(
 
 /// Performs gaussian approximation on the image.
 ///
-/// Fast gaussian approximation for u16 image, limited to 319 radius, sometimes on the very bright images may start ringing on a very large radius.
+/// Fast gaussian approximation for u16 image, sometimes on the very bright images may start ringing on a very large radius.
 /// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed.
+/// Radius from *BASE_RADIUS_I64_CUTOFF* (180) uses i64 accumulator, which has no SIMD implementation
+/// and is several times slower.
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
 /// * `image` - Image to work in place, see [BlurImageMut] for more info.
-/// * `radius` - Radius more than 255 is not supported. To use larger radius convert image to f32 and use function for f32.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `radius` - Radius up to 65535, minimum is one.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided,
+/// or radius exceeds 65535.
 pub fn fast_gaussian_u16(
     image: &mut BlurImageMut<u16>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
+    radius.check_max(MAX_INTEGER_RADIUS)?;
+    let radius = radius.max(1);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian_u16(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 2 + 1, radius.y_axis * 2 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_u16(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
     let data = image.data.borrow_mut();
-    let radius = radius.clamp(1, 255);
     impl_margin_call!(
         u16,
         channels,
@@ -944,23 +1003,58 @@ pub fn fast_gaussian_u16(
 /// * `image` - Image to work in place, see [BlurImageMut] for more info.
 /// * `radius` - almost any radius is supported.
 /// * `transfer_function` - Transfer function in linear colorspace.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn fast_gaussian_f32(
     image: &mut BlurImageMut<f32>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
+    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian_f32(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 2 + 1, radius.y_axis * 2 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_f32(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
     let data = image.data.borrow_mut();
-    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
     impl_margin_call!(
         f32,
         channels,
@@ -986,10 +1080,11 @@ pub fn fast_gaussian_f32(
 /// * `image` - Image to work in place, see [BlurImageMut] for more info.
 /// * `radius` - almost any radius is supported.
 /// * `threading_policy` - Threads usage policy.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 #[cfg(feature = "nightly_f16")]
 #[cfg_attr(docsrs, doc(cfg(feature = "nightly_f16")))]
 pub fn fast_gaussian_f16(
@@ -997,14 +1092,33 @@ pub fn fast_gaussian_f16(
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
+    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 2 + 1, radius.y_axis * 2 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_f16(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
     let data = image.data.borrow_mut();
-    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
     impl_margin_call!(
         f16,
         channels,
@@ -1034,6 +1148,53 @@ mod tests {
         min + (lcg_rand(state) as usize % (max - min + 1))
     }
 
+    #[test]
+    fn test_fast_gaussian_u8_horizontal_matches_scalar() {
+        let mut rng = 0x5eed_u64;
+        let width = 47usize;
+        let height = 13usize;
+        let mut src = vec![0u8; width * height * 3];
+        for v in src.iter_mut() {
+            *v = lcg_rand(&mut rng) as u8;
+        }
+        let radius = 4;
+        let stride = (width * 3) as u32;
+
+        let mut dispatched = src.clone();
+        let horizontal = u8::get_horizontal::<3>(radius);
+        horizontal(
+            &UnsafeSlice::new(&mut dispatched),
+            stride,
+            width as u32,
+            height as u32,
+            radius,
+            0,
+            height as u32,
+            EdgeMode::Clamp,
+        );
+
+        let mut reference = src.clone();
+        fg_horizontal_pass::<u8, i32, f32, 3>(
+            &UnsafeSlice::new(&mut reference),
+            stride,
+            width as u32,
+            height as u32,
+            radius,
+            0,
+            height as u32,
+            EdgeMode::Clamp,
+        );
+
+        for (i, (&a, &b)) in dispatched.iter().zip(reference.iter()).enumerate() {
+            let diff = (a as i32 - b as i32).abs();
+            assert!(
+                diff <= 1,
+                "Row {}, expected {b}, but it was {a}",
+                i / (width * 3)
+            );
+        }
+    }
+
     #[test]
     fn test_fast_gaussian_u8_q_k5() {
         let mut rng = 0xdeadbeef_u64
@@ -1057,6 +1218,8 @@ mod tests {
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for (i, &cn) in dst.iter().enumerate() {
@@ -1091,6 +1254,8 @@ mod tests {
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for &cn in dst.iter() {
@@ -1125,6 +1290,8 @@ mod tests {
             AnisotropicRadius::new(25),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for &cn in dst.iter() {
@@ -1135,4 +1302,193 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fast_gaussian_u8_constant_border() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let radius = 5usize;
+        let pad = radius * 6;
+        let constant = 40u8;
+        let src: Vec<u8> = (0..width * height)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect();
+        let padded_width = width + 2 * pad;
+        let padded_height = height + 2 * pad;
+        let mut reference = vec![constant; padded_width * padded_height];
+        for (y, row) in src.chunks_exact(width).enumerate() {
+            let start = (y + pad) * padded_width + pad;
+            reference[start..start + width].copy_from_slice(row);
+        }
+        let mut reference_image = BlurImageMut::borrow(
+            &mut reference,
+            padded_width as u32,
+            padded_height as u32,
+            FastBlurChannels::Plane,
+        );
+        fast_gaussian(
+            &mut reference_image,
+            AnisotropicRadius::new(radius as u32),
+            ThreadingPolicy::Single,
+            EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        let mut dst = src.clone();
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        fast_gaussian(
+            &mut dst_image,
+            AnisotropicRadius::new(radius as u32),
+            ThreadingPolicy::Single,
+            EdgeMode2D::new(EdgeMode::Constant),
+            Scalar::dup(constant as f64),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        for (y, row) in dst.chunks_exact(width).enumerate() {
+            let start = (y + pad) * padded_width + pad;
+            assert_eq!(row, &reference[start..start + width]);
+        }
+    }
+
+    /// Clamped separable triangle filter with weights `radius - |k|`, vertical pass first
+    fn triangle_reference(src: &[u8], width: usize, height: usize, radius: i64) -> Vec<u8> {
+        let blur_line = |line: &[u8]| -> Vec<u8> {
+            let n = line.len() as i64;
+            (0..n)
+                .map(|i| {
+                    let sum: i64 = (1 - radius..radius)
+                        .map(|k| (radius - k.abs()) * line[(i + k).clamp(0, n - 1) as usize] as i64)
+                        .sum();
+                    (sum as f64 / (radius * radius) as f64).round() as u8
+                })
+                .collect()
+        };
+        let mut dst = src.to_vec();
+        for x in 0..width {
+            let column: Vec<u8> = (0..height).map(|y| dst[y * width + x]).collect();
+            for (y, v) in blur_line(&column).into_iter().enumerate() {
+                dst[y * width + x] = v;
+            }
+        }
+        for row in dst.chunks_exact_mut(width) {
+            let blurred = blur_line(row);
+            row.copy_from_slice(&blurred);
+        }
+        dst
+    }
+
+    #[test]
+    fn test_fast_gaussian_u8_large_radius() {
+        let width: usize = 96;
+        let height: usize = 64;
+        let src: Vec<u8> = (0..width * height)
+            .map(|i| (((i % width) * 5 + (i / width) * 11) % 256) as u8)
+            .collect();
+        for radius in [600u32, 3000] {
+            let reference = triangle_reference(&src, width, height, radius as i64);
+            let mut dst = src.clone();
+            let mut dst_image = BlurImageMut::borrow(
+                &mut dst,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Plane,
+            );
+            fast_gaussian(
+                &mut dst_image,
+                AnisotropicRadius::new(radius),
+                ThreadingPolicy::Single,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .unwrap();
+            for (i, (&a, &b)) in dst.iter().zip(reference.iter()).enumerate() {
+                let diff = (a as i32 - b as i32).abs();
+                assert!(
+                    diff <= 2,
+                    "Radius {radius}: diff expected to be less than 2, but it was {diff} at {i}"
+                );
+            }
+        }
+        for radius in [2895u32, 2896, 3000, 5000, 20000] {
+            for edge_mode in [
+                EdgeMode::Clamp,
+                EdgeMode::Wrap,
+                EdgeMode::Reflect,
+                EdgeMode::Reflect101,
+            ] {
+                let mut flat = vec![100u8; width * height * 3];
+                let mut flat_image = BlurImageMut::borrow(
+                    &mut flat,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                );
+                fast_gaussian(
+                    &mut flat_image,
+                    AnisotropicRadius::new(radius),
+                    ThreadingPolicy::Single,
+                    EdgeMode2D::new(edge_mode),
+                    Scalar::default(),
+                    AlphaMode::Premultiplied,
+                )
+                .unwrap();
+                assert!(
+                    flat.iter().all(|&x| x == 100),
+                    "Flat image changed for radius {radius} and {edge_mode:?}"
+                );
+            }
+        }
+        let mut dst = src.clone();
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        assert!(matches!(
+            fast_gaussian(
+                &mut dst_image,
+                AnisotropicRadius::create(5, 65536),
+                ThreadingPolicy::Single,
+                EdgeMode2D::new(EdgeMode::Clamp),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            ),
+            Err(BlurError::InvalidParameter {
+                name: "y_radius",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fast_gaussian_u16_large_radius() {
+        let width: usize = 48;
+        let height: usize = 40;
+        let mut dst = vec![17234u16; width * height * 3];
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        fast_gaussian_u16(
+            &mut dst_image,
+            AnisotropicRadius::create(1000, 700),
+            ThreadingPolicy::Single,
+            EdgeMode2D::new(EdgeMode::Reflect101),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        assert!(dst.iter().all(|&x| x == 17234), "Flat image changed");
+    }
 }
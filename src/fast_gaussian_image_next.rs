@@ -27,8 +27,8 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::{
-    AnisotropicRadius, BlurImageMut, EdgeMode2D, FastBlurChannels, ThreadingPolicy,
-    fast_gaussian_next, fast_gaussian_next_f32, fast_gaussian_next_u16,
+    AlphaMode, AnisotropicRadius, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy, fast_gaussian_next, fast_gaussian_next_f32, fast_gaussian_next_u16,
 };
 use image::{
     DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage, RgbImage,
@@ -43,7 +43,7 @@ use image::{
 ///
 /// * `image`: Dynamic image provided by image crate.
 /// * `radius`: radius.
-/// * `edge_mode` - Rule to handle edge mode.
+/// * `edge_mode` - Rule to handle edge mode, [crate::EdgeMode::Constant] uses zero border.
 /// * `threading_policy` - Threads usage policy.
 ///
 #[must_use]
@@ -62,63 +62,41 @@ pub fn fast_gaussian_next_blur_image(
                 gray.height(),
                 FastBlurChannels::Plane,
             );
-            fast_gaussian_next(&mut blur_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_gray_image = GrayImage::from_raw(gray.width(), gray.height(), new_image)?;
             Some(DynamicImage::ImageLuma8(new_gray_image))
         }
         DynamicImage::ImageLumaA8(luma_alpha_image) => {
-            let mut intensity_plane =
-                vec![0u8; luma_alpha_image.width() as usize * luma_alpha_image.height() as usize];
-            let mut alpha_plane =
-                vec![0u8; luma_alpha_image.width() as usize * luma_alpha_image.height() as usize];
-            let raw_buffer = luma_alpha_image.as_raw();
+            let mut new_image = luma_alpha_image.as_raw().to_vec();
 
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter_mut()
-                .zip(alpha_plane.iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let mut intensity_image = BlurImageMut::borrow(
-                &mut intensity_plane,
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            fast_gaussian_next(&mut intensity_image, radius, threading_policy, edge_modes).ok()?;
-
-            let mut alpha_image = BlurImageMut::borrow(
-                &mut alpha_plane,
+            let mut b_rgb_image = BlurImageMut::borrow(
+                &mut new_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
 
-            fast_gaussian_next(&mut alpha_image, radius, threading_policy, edge_modes).ok()?;
-
-            let mut new_raw_buffer =
-                vec![
-                    0u8;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter()
-                .zip(alpha_plane.iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
-
+            fast_gaussian_next(
+                &mut b_rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_gray_image = GrayAlphaImage::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image,
             )?;
             Some(DynamicImage::ImageLumaA8(new_gray_image))
         }
@@ -132,7 +110,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels3,
             );
 
-            fast_gaussian_next(&mut b_rgb_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next(
+                &mut b_rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_rgb_image =
                 RgbImage::from_raw(rgb_image.width(), rgb_image.height(), new_image)?;
             Some(DynamicImage::ImageRgb8(new_rgb_image))
@@ -147,7 +133,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels4,
             );
 
-            fast_gaussian_next(&mut b_rgba_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next(
+                &mut b_rgba_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_rgba_image =
                 RgbaImage::from_raw(rgba_image.width(), rgba_image.height(), new_image)?;
             Some(DynamicImage::ImageRgba8(new_rgba_image))
@@ -162,8 +156,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Plane,
             );
 
-            fast_gaussian_next_u16(&mut intensity_image, radius, threading_policy, edge_modes)
-                .ok()?;
+            fast_gaussian_next_u16(
+                &mut intensity_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(
                 luma_16.width(),
@@ -172,57 +173,30 @@ pub fn fast_gaussian_next_blur_image(
             )?;
             Some(DynamicImage::ImageLuma16(new_rgb_image))
         }
-        DynamicImage::ImageLumaA16(gray_alpha_16) => {
-            let mut intensity_plane =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize];
-            let mut alpha_plane =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize];
-            let raw_buffer = gray_alpha_16.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter_mut()
-                .zip(alpha_plane.iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
+        DynamicImage::ImageLumaA16(luma_alpha_image) => {
+            let mut new_image = luma_alpha_image.as_raw().to_vec();
 
-            let mut intensity_image = BlurImageMut::borrow(
-                &mut intensity_plane,
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                FastBlurChannels::Plane,
-            );
-
-            fast_gaussian_next_u16(&mut intensity_image, radius, threading_policy, edge_modes)
-                .ok()?;
-
-            let mut alpha_image = BlurImageMut::borrow(
-                &mut alpha_plane,
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                FastBlurChannels::Plane,
+            let mut rgb_image = BlurImageMut::borrow(
+                &mut new_image,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
             );
 
-            fast_gaussian_next_u16(&mut alpha_image, radius, threading_policy, edge_modes).ok()?;
-
-            let mut new_raw_buffer =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize * 2];
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter()
-                .zip(alpha_plane.iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
+            fast_gaussian_next_u16(
+                &mut rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_gray_image = ImageBuffer::<LumaA<u16>, Vec<u16>>::from_raw(
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                new_raw_buffer,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                new_image,
             )?;
             Some(DynamicImage::ImageLumaA16(new_gray_image))
         }
@@ -236,7 +210,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels3,
             );
 
-            fast_gaussian_next_u16(&mut rgb_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next_u16(
+                &mut rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw(
                 rgb_16_image.width(),
@@ -255,7 +237,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels4,
             );
 
-            fast_gaussian_next_u16(&mut rgba_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next_u16(
+                &mut rgba_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgba_image = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_raw(
                 rgba_16_image.width(),
@@ -274,7 +264,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels3,
             );
 
-            fast_gaussian_next_f32(&mut rgb_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next_f32(
+                &mut rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_rgb_image =
                 Rgb32FImage::from_raw(rgb_image_f32.width(), rgb_image_f32.height(), new_image)?;
             Some(DynamicImage::ImageRgb32F(new_rgb_image))
@@ -289,7 +287,15 @@ pub fn fast_gaussian_next_blur_image(
                 FastBlurChannels::Channels4,
             );
 
-            fast_gaussian_next_f32(&mut rgb_image, radius, threading_policy, edge_modes).ok()?;
+            fast_gaussian_next_f32(
+                &mut rgb_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image =
                 Rgba32FImage::from_raw(rgba_image_f32.width(), rgba_image_f32.height(), new_image)?;
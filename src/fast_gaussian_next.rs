@@ -33,6 +33,8 @@ use crate::neon::{
     fgn_horizontal_pass_neon_f32, fgn_horizontal_pass_neon_u8, fgn_vertical_pass_neon_f32,
     fgn_vertical_pass_neon_u8,
 };
+use crate::padded_blur::{blur_in_place_padded, needs_constant_padding};
+use crate::premultiply::blur_straight_in_place;
 use crate::primitives::PrimitiveCast;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
@@ -53,7 +55,7 @@ use crate::unsafe_slice::UnsafeSlice;
 use crate::wasm32::{
     fast_gaussian_next_horizontal_pass_wasm_u8, fast_gaussian_next_vertical_pass_wasm_u8,
 };
-use crate::{AnisotropicRadius, BlurError, EdgeMode2D};
+use crate::{AlphaMode, AnisotropicRadius, BlurError, EdgeMode2D, Scalar};
 use crate::{BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy};
 #[cfg(feature = "nightly_f16")]
 use core::f16;
@@ -66,6 +68,7 @@ macro_rules! impl_generic_call {
     ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $threading_policy:expr) => {
         let _dispatcher = match $channels_type {
             FastBlurChannels::Plane => fast_gaussian_next_impl::<$store_type, 1>,
+            FastBlurChannels::Channels2 => fast_gaussian_next_impl::<$store_type, 2>,
             FastBlurChannels::Channels3 => fast_gaussian_next_impl::<$store_type, 3>,
             FastBlurChannels::Channels4 => fast_gaussian_next_impl::<$store_type, 4>,
         };
@@ -889,7 +892,7 @@ fn fast_gaussian_next_impl<
     let thread_count = threading_policy.thread_count(width, height) as u32;
 
     let unsafe_image = UnsafeSlice::new(bytes);
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     pool.parallel_for(|thread_index| {
         let segment_size = width / thread_count;
         let start_x = thread_index as u32 * segment_size;
@@ -939,18 +942,53 @@ fn fast_gaussian_next_impl<
 /// * `image` - Image to blur in-place, see [BlurImageMut] for more info
 /// * `radius` - Radius is limited to 280
 /// * `threading_policy` - Threads usage policy
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn fast_gaussian_next(
     image: &mut BlurImageMut<u8>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
     let radius = radius.clamp(1, 280);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian_next(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 3 + 1, radius.y_axis * 3 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_next(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
@@ -980,22 +1018,57 @@ pub fn fast_gaussian_next(
 /// * `image` - Image to blur in-place, see [BlurImageMut] for more info.
 /// * `radius` - Radius is limited to 152.
 /// * `threading_policy` - Threads usage policy.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn fast_gaussian_next_u16(
     image: &mut BlurImageMut<u16>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
+    let acq_radius = radius.clamp(1, 152);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian_next_u16(
+                image,
+                acq_radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(acq_radius.x_axis * 3 + 1, acq_radius.y_axis * 3 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_next_u16(
+                    padded,
+                    acq_radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
-    let acq_radius = radius.clamp(1, 152);
     impl_margin_call!(
         u16,
         channels,
@@ -1020,22 +1093,57 @@ pub fn fast_gaussian_next_u16(
 /// * `image` - Image to blur in-place, see [BlurImageMut] for more info.
 /// * `radius` - Almost any radius is supported, in real world radius > 300 is too big for this implementation.
 /// * `threading_policy` - Threads usage policy.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 pub fn fast_gaussian_next_f32(
     image: &mut BlurImageMut<f32>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
+    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            fast_gaussian_next_f32(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            AnisotropicRadius::create(radius.x_axis * 3 + 1, radius.y_axis * 3 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_next_f32(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
-    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
     impl_margin_call!(
         f32,
         channels,
@@ -1060,10 +1168,11 @@ pub fn fast_gaussian_next_f32(
 /// * `image` - Image to blur in-place, see [BlurImageMut] for more info.
 /// * `radius` - Almost any radius is supported, in real world radius > 300 is too big for this implementation.
 /// * `threading_policy` - Threads usage policy.
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 #[cfg(feature = "nightly_f16")]
 #[cfg_attr(docsrs, doc(cfg(feature = "nightly_f16")))]
 pub fn fast_gaussian_next_f16(
@@ -1071,13 +1180,32 @@ pub fn fast_gaussian_next_f16(
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
     edge_modes: EdgeMode2D,
+    border_constant: Scalar,
 ) -> Result<(), BlurError> {
     in_place.check_layout(None)?;
+    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
+    if needs_constant_padding(edge_modes) {
+        return blur_in_place_padded(
+            in_place,
+            AnisotropicRadius::create(radius.x_axis * 3 + 1, radius.y_axis * 3 + 1),
+            edge_modes,
+            border_constant,
+            |mode| mode != EdgeMode::Constant,
+            |padded, inner_modes| {
+                fast_gaussian_next_f16(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                )
+            },
+        );
+    }
     let channels = in_place.channels;
     let stride = in_place.row_stride();
     let width = in_place.width;
     let height = in_place.height;
-    let radius = AnisotropicRadius::create(radius.x_axis.max(1), radius.y_axis.max(1));
     impl_margin_call!(
         f16,
         channels,
@@ -1112,6 +1240,8 @@ mod tests {
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for (i, &cn) in dst.iter().enumerate() {
@@ -1139,6 +1269,8 @@ mod tests {
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for &cn in dst.iter() {
@@ -1166,6 +1298,8 @@ mod tests {
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
             EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for &cn in dst.iter() {
@@ -1176,4 +1310,58 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fast_gaussian_next_u8_constant_border() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let radius = 5usize;
+        let pad = radius * 6;
+        let constant = 40u8;
+        let src: Vec<u8> = (0..width * height)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect();
+        let padded_width = width + 2 * pad;
+        let padded_height = height + 2 * pad;
+        let mut reference = vec![constant; padded_width * padded_height];
+        for (y, row) in src.chunks_exact(width).enumerate() {
+            let start = (y + pad) * padded_width + pad;
+            reference[start..start + width].copy_from_slice(row);
+        }
+        let mut reference_image = BlurImageMut::borrow(
+            &mut reference,
+            padded_width as u32,
+            padded_height as u32,
+            FastBlurChannels::Plane,
+        );
+        fast_gaussian_next(
+            &mut reference_image,
+            AnisotropicRadius::new(radius as u32),
+            ThreadingPolicy::Single,
+            EdgeMode2D::new(EdgeMode::Clamp),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        let mut dst = src.clone();
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        fast_gaussian_next(
+            &mut dst_image,
+            AnisotropicRadius::new(radius as u32),
+            ThreadingPolicy::Single,
+            EdgeMode2D::new(EdgeMode::Constant),
+            Scalar::dup(constant as f64),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        for (y, row) in dst.chunks_exact(width).enumerate() {
+            let start = (y + pad) * padded_width + pad;
+            assert_eq!(row, &reference[start..start + width]);
+        }
+    }
 }
@@ -400,6 +400,26 @@ where
         return Err(BlurError::ImagesMustMatch);
     }
 
+    fill_arena_row::<T, CN>(row, image, source_y, kernel_size, border_mode, scalar);
+    Ok(())
+}
+
+/// Same as [write_arena_row] for callers that already validated image layout and row size
+pub(crate) fn fill_arena_row<T, const CN: usize>(
+    row: &mut [T],
+    image: &BlurImage<T>,
+    source_y: usize,
+    kernel_size: KernelShape,
+    border_mode: EdgeMode,
+    scalar: Scalar,
+) where
+    T: Default + Copy + Send + Sync + 'static + Debug,
+    f64: PrimitiveCast<T>,
+{
+    let pad_w = kernel_size.width / 2;
+
+    let image_size = image.size();
+
     let source_offset = source_y * image.row_stride() as usize;
 
     let source_row = &image.data.as_ref()[source_offset..(source_offset + image_size.width * CN)];
@@ -453,8 +473,8 @@ where
         }
     }
 
-    for (x, dst) in
-        (image_size.width..(image_size.width + pad_w)).zip(row.chunks_exact_mut(CN).rev())
+    for (x, dst) in (image_size.width..(image_size.width + pad_w))
+        .zip(row[(pad_w + image_size.width) * CN..].chunks_exact_mut(CN))
     {
         match border_mode {
             EdgeMode::Clamp => {
@@ -496,8 +516,6 @@ where
             }
         }
     }
-
-    Ok(())
 }
 
 #[derive(Clone)]
@@ -529,6 +547,35 @@ pub(crate) fn make_arena_columns<T, const CN: usize>(
     border_mode: EdgeMode,
     scalar_projection: [T; CN],
 ) -> Result<ArenaColumns<T>, BlurError>
+where
+    T: Default + Copy + Send + Sync + 'static,
+{
+    let pad_size = (kernel_size.height / 2) * image_size.width * CN;
+    let mut top_pad = vec![T::default(); pad_size];
+    let mut bottom_pad = vec![T::default(); pad_size];
+    write_arena_columns::<T, CN>(
+        image,
+        image_size,
+        kernel_size,
+        border_mode,
+        scalar_projection,
+        &mut top_pad,
+        &mut bottom_pad,
+    )?;
+    Ok(ArenaColumns::new(top_pad, bottom_pad))
+}
+
+/// Writes top and bottom column pads into provided slices,
+/// each must hold `kernel_size.height / 2` rows of the image.
+pub(crate) fn write_arena_columns<T, const CN: usize>(
+    image: &[T],
+    image_size: ImageSize,
+    kernel_size: KernelShape,
+    border_mode: EdgeMode,
+    scalar_projection: [T; CN],
+    top_pad: &mut [T],
+    bottom_pad: &mut [T],
+) -> Result<(), BlurError>
 where
     T: Default + Copy + Send + Sync + 'static,
 {
@@ -542,6 +589,8 @@ where
                     kernel_size,
                     border_mode,
                     scalar_projection,
+                    top_pad,
+                    bottom_pad,
                 )
             };
         }
@@ -556,6 +605,8 @@ where
                     kernel_size,
                     border_mode,
                     scalar_projection,
+                    top_pad,
+                    bottom_pad,
                 )
             };
         }
@@ -566,6 +617,8 @@ where
         kernel_size,
         border_mode,
         scalar_projection,
+        top_pad,
+        bottom_pad,
     )
 }
 
@@ -577,7 +630,9 @@ unsafe fn mac_avx2<T, const CN: usize>(
     kernel_size: KernelShape,
     border_mode: EdgeMode,
     scalar_projection: [T; CN],
-) -> Result<ArenaColumns<T>, BlurError>
+    top_pad: &mut [T],
+    bottom_pad: &mut [T],
+) -> Result<(), BlurError>
 where
     T: Default + Copy + Send + Sync + 'static,
 {
@@ -587,6 +642,8 @@ where
         kernel_size,
         border_mode,
         scalar_projection,
+        top_pad,
+        bottom_pad,
     )
 }
 
@@ -598,7 +655,9 @@ unsafe fn mac_sse_4_1<T, const CN: usize>(
     kernel_size: KernelShape,
     border_mode: EdgeMode,
     scalar_projection: [T; CN],
-) -> Result<ArenaColumns<T>, BlurError>
+    top_pad: &mut [T],
+    bottom_pad: &mut [T],
+) -> Result<(), BlurError>
 where
     T: Default + Copy + Send + Sync + 'static,
 {
@@ -608,6 +667,8 @@ where
         kernel_size,
         border_mode,
         scalar_projection,
+        top_pad,
+        bottom_pad,
     )
 }
 
@@ -619,7 +680,9 @@ fn make_arena_columns_exec<T, const CN: usize>(
     kernel_size: KernelShape,
     border_mode: EdgeMode,
     scalar_projection: [T; CN],
-) -> Result<ArenaColumns<T>, BlurError>
+    top_pad: &mut [T],
+    bottom_pad: &mut [T],
+) -> Result<(), BlurError>
 where
     T: Default + Copy + Send + Sync + 'static,
 {
@@ -631,9 +694,10 @@ where
         CN,
     )?;
     let pad_h = kernel_size.height / 2;
-
-    let mut top_pad = vec![T::default(); pad_h * image_size.width * CN];
-    let mut bottom_pad = vec![T::default(); pad_h * image_size.width * CN];
+    let pad_size = pad_h * image_size.width * CN;
+    if top_pad.len() != pad_size || bottom_pad.len() != pad_size {
+        return Err(BlurError::ImagesMustMatch);
+    }
 
     let top_pad_stride = image_size.width * CN;
 
@@ -737,5 +801,5 @@ where
         }
     }
 
-    Ok(ArenaColumns::new(top_pad, bottom_pad))
+    Ok(())
 }
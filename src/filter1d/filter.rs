@@ -27,18 +27,22 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::edge_mode::clamp_edge;
-use crate::filter1d::arena::{Arena, make_arena_columns, make_arena_row, write_arena_row};
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::filter1d::arena::{Arena, fill_arena_row, write_arena_columns};
 use crate::filter1d::filter_1d_column_handler::{
     Filter1DColumnHandler, Filter1DColumnHandlerMultipleRows,
 };
 use crate::filter1d::filter_1d_row_handler::Filter1DRowHandler;
 use crate::filter1d::filter_element::KernelShape;
-use crate::filter1d::filter_scan::{is_symmetric_1d, scan_se_1d_flat};
+use crate::filter1d::filter_scan::is_symmetric_1d;
 use crate::primitives::PrimitiveCast;
 use crate::safe_math::{SafeAdd, SafeMul};
 use crate::to_storage::ToStorage;
-use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode2D, ImageSize, Scalar, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
+use crate::util::ScratchBuffer;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, BlurWorkspace, EdgeMode, EdgeMode2D, ImageSize, Scalar,
+    ThreadingPolicy,
+};
 use num_traits::MulAdd;
 use std::fmt::Debug;
 use std::ops::Mul;
@@ -73,6 +77,60 @@ pub fn filter_1d_exact<T, F, const N: usize>(
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
 ) -> Result<(), BlurError>
+where
+    T: Copy
+        + PrimitiveCast<F>
+        + Default
+        + Send
+        + Sync
+        + Filter1DRowHandler<T, F>
+        + Filter1DColumnHandler<T, F>
+        + Debug
+        + Filter1DColumnHandlerMultipleRows<T, F>,
+    F: ToStorage<T> + Mul<F> + MulAdd<F, Output = F> + Send + Sync + PartialEq + Default,
+    i32: PrimitiveCast<F>,
+    f64: PrimitiveCast<T>,
+{
+    filter_1d_exact_with_workspace::<T, F, N>(
+        image,
+        destination,
+        row_kernel,
+        column_kernel,
+        edge_modes,
+        border_constant,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
+}
+
+/// Performs 2D separable convolution on the image using reusable scratch memory.
+///
+/// Same as [filter_1d_exact], but intermediate buffers are taken from the provided
+/// [BlurWorkspace] and reused between calls. With [ThreadingPolicy::Single] repeated calls
+/// on images of the same size do not allocate.
+///
+/// # Arguments
+///
+/// * `image`: Source image
+/// * `destination`: Destination image
+/// * `row_kernel`: Row kernel, *size must be odd*!
+/// * `column_kernel`: Column kernel, *size must be odd*!
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value
+/// * `threading_policy`: See [ThreadingPolicy] for more info
+/// * `workspace`: Scratch memory reused between calls, see [BlurWorkspace].
+///
+#[allow(clippy::too_many_arguments)]
+pub fn filter_1d_exact_with_workspace<T, F, const N: usize>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    row_kernel: &[F],
+    column_kernel: &[F],
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
+) -> Result<(), BlurError>
 where
     T: Copy
         + PrimitiveCast<F>
@@ -97,6 +155,7 @@ where
             edge_modes,
             border_constant,
             threading_policy,
+            workspace,
         );
     }
     image.check_layout_channels(N)?;
@@ -122,69 +181,79 @@ where
 
     _ = (destination.stride as usize).safe_mul(3)?;
 
-    let scanned_row_kernel = scan_se_1d_flat(row_kernel);
-    let scanned_column_kernel = scan_se_1d_flat(column_kernel);
     let is_column_kernel_symmetrical = is_symmetric_1d(column_kernel);
     let is_row_kernel_symmetrical = is_symmetric_1d(row_kernel);
 
     let image_size = image.size();
 
     let thread_count =
-        threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
-
-    let mut transient_image = vec![T::default(); image_size.width * image_size.height * N];
+        threading_policy.thread_count(image_size.width as u32, image_size.height as u32);
+    let pool = crate::executor::ThreadPool::new(thread_count);
 
     let row_handler = T::get_row_handler::<N>(is_row_kernel_symmetrical);
 
-    transient_image
-        .tb_par_chunks_exact_mut(image_size.width * N)
-        .for_each_enumerated(&pool, |y, dst_row| {
-            let pad_w = scanned_row_kernel.len() / 2;
-            let (row, arena_width) = make_arena_row::<T, N>(
-                image,
-                y,
-                KernelShape::new(row_kernel.len(), 0),
-                edge_modes.horizontal,
-                border_constant,
-            )
-            .unwrap();
-
-            row_handler(
-                Arena::new(arena_width, 1, pad_w, 0, N),
-                &row,
-                dst_row,
-                image_size,
-                scanned_row_kernel.as_slice(),
-            );
-        });
-
-    let column_kernel_shape = KernelShape::new(0, scanned_column_kernel.len());
-
-    let column_arena_k = make_arena_columns::<T, N>(
-        transient_image.as_slice(),
-        image_size,
-        column_kernel_shape,
-        edge_modes.vertical,
-        (0..N)
-            .map(|x| border_constant[x].cast_())
-            .collect::<Vec<T>>()
-            .try_into()
-            .unwrap(),
+    let pad_w = row_kernel.len() / 2;
+    let pad_h = column_kernel.len() / 2;
+    let src_stride = image_size.width * N;
+    let arena_width = src_stride.safe_add(pad_w.safe_mul(2 * N)?)?;
+    let padded_height = image_size.height.safe_add(pad_h * 2)?;
+    let padded_size = padded_height.safe_mul(src_stride)?;
+    let band_rows = image_size.height.div_ceil(thread_count);
+    let bands_count = image_size.height.div_ceil(band_rows);
+
+    // Transient image with column pads above and below it, followed by one row arena per band
+    let (scratch, rows_table) = workspace.take_with_rows(
+        padded_size.safe_add(arena_width.safe_mul(bands_count)?)?,
+        padded_height,
     )?;
+    let (padded_image, arenas) = scratch.split_at_mut(padded_size);
+
+    {
+        let (top_pad, rest) = padded_image.split_at_mut(pad_h * src_stride);
+        let (transient_image, bottom_pad) = rest.split_at_mut(image_size.height * src_stride);
+
+        transient_image
+            .tb_par_chunks_mut(band_rows * src_stride)
+            .zip(arenas.chunks_exact_mut(arena_width))
+            .for_each_enumerated(&pool, |band, (dst_rows, arena)| {
+                for (i, dst_row) in dst_rows.chunks_exact_mut(src_stride).enumerate() {
+                    fill_arena_row::<T, N>(
+                        arena,
+                        image,
+                        band * band_rows + i,
+                        KernelShape::new(row_kernel.len(), 0),
+                        edge_modes.horizontal,
+                        border_constant,
+                    );
+                    row_handler(
+                        Arena::new(image_size.width + pad_w * 2, 1, pad_w, 0, N),
+                        arena,
+                        dst_row,
+                        image_size,
+                        row_kernel,
+                    );
+                }
+            });
 
-    let top_pad = column_arena_k.top_pad.as_slice();
-    let bottom_pad = column_arena_k.bottom_pad.as_slice();
-
-    let pad_h = column_kernel_shape.height / 2;
+        write_arena_columns::<T, N>(
+            transient_image,
+            image_size,
+            KernelShape::new(0, column_kernel.len()),
+            edge_modes.vertical,
+            std::array::from_fn(|x| border_constant[x].cast_()),
+            top_pad,
+            bottom_pad,
+        )?;
+    }
 
-    let transient_image_slice = transient_image.as_slice();
+    let padded_image: &[T] = padded_image;
+    let padded_rows = rows_table.fill(|y| &padded_image[y * src_stride..(y + 1) * src_stride]);
 
     let column_handler = T::get_column_handler(is_column_kernel_symmetrical);
     let _column_multiple_rows = T::get_column_handler_multiple_rows(is_column_kernel_symmetrical);
 
-    let src_stride = image_size.width * N;
     let dst_stride = destination.row_stride() as usize;
+    let kernel_height = column_kernel.len();
 
     let mut _dest_slice = destination.projected();
 
@@ -197,46 +266,13 @@ where
             .for_each_enumerated(&pool, |y, row| {
                 use crate::filter1d::filter_1d_column_handler::FilterBrows;
                 let y = y * 3;
-                let brows0 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y,
-                );
-
-                let brows1 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 1,
-                );
-
-                let brows2 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 2,
-                );
-
-                let brows_slice0 = brows0.as_slice();
-                let brows_slice1 = brows1.as_slice();
-                let brows_slice2 = brows2.as_slice();
-
-                let brows_vec = [brows_slice0, brows_slice1, brows_slice2];
-
-                let brows = FilterBrows { brows: brows_vec };
+                let brows = FilterBrows {
+                    brows: [
+                        &padded_rows[y..y + kernel_height],
+                        &padded_rows[y + 1..y + 1 + kernel_height],
+                        &padded_rows[y + 2..y + 2 + kernel_height],
+                    ],
+                };
 
                 column_multiple_rows(
                     Arena::new(image_size.width, pad_h, 0, pad_h, N),
@@ -244,7 +280,7 @@ where
                     row,
                     image_size,
                     dst_stride,
-                    &scanned_column_kernel,
+                    column_kernel,
                 );
             });
         _processed_y = _dest_slice.chunks_exact_mut(dst_stride * 3).len() * 3;
@@ -260,34 +296,12 @@ where
             .for_each_enumerated(&pool, |y, row| {
                 let y = y * 2;
                 use crate::filter1d::filter_1d_column_handler::FilterBrows;
-                let brows0 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y,
-                );
-
-                let brows1 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 1,
-                );
+                let brows0 = &padded_rows[y..y + kernel_height];
+                let brows1 = &padded_rows[y + 1..y + 1 + kernel_height];
 
-                let brows_slice0 = brows0.as_slice();
-                let brows_slice1 = brows1.as_slice();
-
-                let brows_vec = [brows_slice0, brows_slice1, brows_slice1];
-
-                let brows = FilterBrows { brows: brows_vec };
+                let brows = FilterBrows {
+                    brows: [brows0, brows1, brows1],
+                };
 
                 column_multiple_rows(
                     Arena::new(image_size.width, pad_h, 0, pad_h, N),
@@ -295,7 +309,7 @@ where
                     row,
                     image_size,
                     dst_stride,
-                    &scanned_column_kernel,
+                    column_kernel,
                 );
             });
         _processed_y = _dest_slice.chunks_exact_mut(dst_stride * 2).len() * 2;
@@ -307,32 +321,22 @@ where
     _dest_slice
         .tb_par_chunks_mut(dst_stride)
         .for_each_enumerated(&pool, |y, row| {
-            let brows = create_brows(
-                image_size,
-                column_kernel_shape,
-                top_pad,
-                bottom_pad,
-                pad_h,
-                transient_image_slice,
-                src_stride,
-                _processed_y + y,
-            );
-
-            let brows_slice = brows.as_slice();
+            let y = _processed_y + y;
             let row = &mut row[..image_size.width * N];
 
             column_handler(
                 Arena::new(image_size.width, pad_h, 0, pad_h, N),
-                brows_slice,
+                &padded_rows[y..y + kernel_height],
                 row,
                 image_size,
-                &scanned_column_kernel,
+                column_kernel,
             );
         });
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn filter_1d_exact_sliding_buffer<T, F, const N: usize>(
     image: &BlurImage<T>,
     destination: &mut BlurImageMut<T>,
@@ -341,6 +345,7 @@ fn filter_1d_exact_sliding_buffer<T, F, const N: usize>(
     edge_modes: EdgeMode2D,
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
 ) -> Result<(), BlurError>
 where
     T: Copy
@@ -356,6 +361,7 @@ where
     i32: PrimitiveCast<F>,
     f64: PrimitiveCast<T>,
 {
+    const MAX_STACK_KERNEL: usize = 61;
     image.check_layout_channels(N)?;
     destination.check_layout_channels(N, Some(image))?;
     image.only_size_matches_mut(destination)?;
@@ -373,8 +379,6 @@ where
         .safe_mul(N)?
         .safe_add(pad_w.safe_mul(2 * N)?);
 
-    let scanned_row_kernel = scan_se_1d_flat(row_kernel);
-    let scanned_column_kernel = scan_se_1d_flat(column_kernel);
     let is_column_kernel_symmetrical = is_symmetric_1d(column_kernel);
     let is_row_kernel_symmetrical = is_symmetric_1d(row_kernel);
 
@@ -383,7 +387,7 @@ where
     let thread_count =
         threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
 
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let tile_size = (image_size.height as u32 / thread_count).clamp(1, image_size.height as u32);
 
@@ -394,18 +398,25 @@ where
 
     let dest_stride = destination.row_stride() as usize;
 
+    let buffer_size = row_stride.safe_mul(column_kernel.len())?;
+    let arena_width = row_stride + (row_kernel.len() / 2) * 2 * N;
+    let tile_scratch_size = buffer_size.safe_add(arena_width)?;
+
     if thread_count > 1 {
-        destination
-            .projected()
-            .tb_par_chunks_mut(dest_stride * tile_size as usize)
-            .for_each_enumerated(&pool, |cy, dst_rows| {
+        let tiles_count = image_size.height.div_ceil(tile_size as usize);
+        let scratch = workspace.take(tile_scratch_size.safe_mul(tiles_count)?)?;
+        scratch
+            .tb_par_chunks_mut(tile_scratch_size)
+            .zip(
+                destination
+                    .projected()
+                    .chunks_mut(dest_stride * tile_size as usize),
+            )
+            .for_each_enumerated(&pool, |cy, (tile_scratch, dst_rows)| {
                 let source_y = cy * tile_size as usize;
-                let mut buffer = vec![T::default(); row_stride * scanned_column_kernel.len()];
+                let (buffer, row_buffer) = tile_scratch.split_at_mut(buffer_size);
 
-                let pad_w = scanned_row_kernel.len() / 2;
-                let mut row_buffer = vec![T::default(); image_size.width * N + pad_w * 2 * N];
-
-                let column_kernel_len = scanned_column_kernel.len();
+                let column_kernel_len = column_kernel.len();
 
                 let mut start_ky = column_kernel_len / 2 + 1;
 
@@ -413,64 +424,18 @@ where
 
                 let half_kernel = column_kernel_len / 2;
 
-                // preload top edge
-                if source_y == 0 {
-                    let pad_w = scanned_row_kernel.len() / 2;
-                    if row_buffer.is_empty() {
-                        row_buffer = vec![T::default(); image_size.width * N + pad_w * 2 * N];
-                    }
-                    write_arena_row::<T, N>(
-                        &mut row_buffer,
+                // preload rows above the tile
+                for src_y in 0..=half_kernel {
+                    filter_source_row::<T, F, N>(
                         image,
-                        0,
-                        KernelShape::new(row_kernel.len(), 0),
-                        edge_modes.horizontal,
+                        src_y as i64 + source_y as i64 - half_kernel as i64 - 1,
+                        row_buffer,
+                        &mut buffer[src_y * row_stride..(src_y + 1) * row_stride],
+                        row_kernel,
+                        edge_modes,
                         border_constant,
-                    )
-                    .unwrap();
-                    row_handler(
-                        Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                        &row_buffer,
-                        &mut buffer[..row_stride],
-                        image_size,
-                        &scanned_row_kernel,
+                        row_handler,
                     );
-
-                    let (src_row, rest) = buffer.split_at_mut(row_stride);
-                    for dst in rest.chunks_exact_mut(row_stride).take(half_kernel) {
-                        for (dst, src) in dst.iter_mut().zip(src_row.iter()) {
-                            *dst = *src;
-                        }
-                    }
-                } else {
-                    for src_y in 0..=half_kernel {
-                        let s_y = clamp_edge!(
-                            edge_modes.vertical,
-                            src_y as i64 + source_y as i64 - half_kernel as i64 - 1,
-                            0i64,
-                            image_size.height as i64
-                        );
-                        let pad_w = scanned_row_kernel.len() / 2;
-                        if row_buffer.is_empty() {
-                            row_buffer = vec![T::default(); image_size.width * N + pad_w * 2 * N];
-                        }
-                        write_arena_row::<T, N>(
-                            &mut row_buffer,
-                            image,
-                            s_y,
-                            KernelShape::new(row_kernel.len(), 0),
-                            edge_modes.horizontal,
-                            border_constant,
-                        )
-                        .unwrap();
-                        row_handler(
-                            Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                            &row_buffer,
-                            &mut buffer[src_y * row_stride..(src_y + 1) * row_stride],
-                            image_size,
-                            &scanned_row_kernel,
-                        );
-                    }
                 }
 
                 let rows_count = dst_rows.len() / dest_stride;
@@ -478,36 +443,21 @@ where
                 for (y, dy) in
                     (source_y..source_y + rows_count + half_kernel).zip(0..rows_count + half_kernel)
                 {
-                    let new_y = if y < image_size.height {
-                        y
-                    } else {
-                        clamp_edge!(
-                            edge_modes.vertical,
-                            y as i64,
-                            0i64,
-                            image_size.height as i64
-                        )
-                    };
-
-                    write_arena_row::<T, N>(
-                        &mut row_buffer,
+                    filter_source_row::<T, F, N>(
                         image,
-                        new_y,
-                        KernelShape::new(row_kernel.len(), 0),
-                        edge_modes.horizontal,
-                        border_constant,
-                    )
-                    .unwrap();
-                    row_handler(
-                        Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                        &row_buffer,
+                        y as i64,
+                        row_buffer,
                         &mut buffer[start_ky * row_stride..(start_ky + 1) * row_stride],
-                        image_size,
-                        &scanned_row_kernel,
+                        row_kernel,
+                        edge_modes,
+                        border_constant,
+                        row_handler,
                     );
 
                     if dy >= half_kernel {
-                        let mut brows = vec![image.data.as_ref(); column_kernel_len];
+                        let mut brows_stack =
+                            ScratchBuffer::<&[T], MAX_STACK_KERNEL>::new(column_kernel_len);
+                        let brows = brows_stack.as_mut_slice();
 
                         for (i, brow) in brows.iter_mut().enumerate() {
                             let ky = (i + start_ky + 1) % column_kernel_len;
@@ -521,10 +471,10 @@ where
 
                         column_handler(
                             Arena::new(image_size.width, half_kernel, 0, half_kernel, N),
-                            &brows,
+                            brows,
                             dst,
                             image_size,
-                            &scanned_column_kernel,
+                            column_kernel,
                         );
                     }
 
@@ -533,73 +483,47 @@ where
                 }
             });
     } else {
-        let mut buffer = vec![T::default(); row_stride * scanned_column_kernel.len()];
+        let scratch = workspace.take(tile_scratch_size)?;
+        let (buffer, row_buffer) = scratch.split_at_mut(buffer_size);
 
-        let pad_w = scanned_row_kernel.len() / 2;
-        let mut row_buffer = vec![T::default(); image_size.width * N + pad_w * 2 * N];
-
-        // preload top edge
-        write_arena_row::<T, N>(
-            &mut row_buffer,
-            image,
-            0,
-            KernelShape::new(row_kernel.len(), 0),
-            edge_modes.horizontal,
-            border_constant,
-        )?;
-        row_handler(
-            Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-            &row_buffer,
-            &mut buffer[..row_stride],
-            image_size,
-            &scanned_row_kernel,
-        );
-
-        let column_kernel_len = scanned_column_kernel.len();
+        let column_kernel_len = column_kernel.len();
 
         let half_kernel = column_kernel_len / 2;
 
-        let (src_row, rest) = buffer.split_at_mut(row_stride);
-        for dst in rest.chunks_exact_mut(row_stride).take(half_kernel) {
-            for (dst, src) in dst.iter_mut().zip(src_row.iter()) {
-                *dst = *src;
-            }
+        // preload rows above the image
+        for src_y in 0..=half_kernel {
+            filter_source_row::<T, F, N>(
+                image,
+                src_y as i64 - half_kernel as i64 - 1,
+                row_buffer,
+                &mut buffer[src_y * row_stride..(src_y + 1) * row_stride],
+                row_kernel,
+                edge_modes,
+                border_constant,
+                row_handler,
+            );
         }
 
         let mut start_ky = column_kernel_len / 2 + 1;
 
         start_ky %= column_kernel_len;
 
-        for y in 1..image_size.height + half_kernel {
-            let new_y = if y < image_size.height {
-                y
-            } else {
-                clamp_edge!(
-                    edge_modes.vertical,
-                    y as i64,
-                    0i64,
-                    image_size.height as i64
-                )
-            };
-
-            write_arena_row::<T, N>(
-                &mut row_buffer,
+        for y in 0..image_size.height + half_kernel {
+            filter_source_row::<T, F, N>(
                 image,
-                new_y,
-                KernelShape::new(row_kernel.len(), 0),
-                edge_modes.horizontal,
-                border_constant,
-            )?;
-            row_handler(
-                Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                &row_buffer,
+                y as i64,
+                row_buffer,
                 &mut buffer[start_ky * row_stride..(start_ky + 1) * row_stride],
-                image_size,
-                &scanned_row_kernel,
+                row_kernel,
+                edge_modes,
+                border_constant,
+                row_handler,
             );
 
             if y >= half_kernel {
-                let mut brows = vec![image.data.as_ref(); column_kernel_len];
+                let mut brows_stack =
+                    ScratchBuffer::<&[T], MAX_STACK_KERNEL>::new(column_kernel_len);
+                let brows = brows_stack.as_mut_slice();
 
                 for (i, brow) in brows.iter_mut().enumerate() {
                     let ky = (i + start_ky + 1) % column_kernel_len;
@@ -613,10 +537,10 @@ where
 
                 column_handler(
                     Arena::new(image_size.width, half_kernel, 0, half_kernel, N),
-                    &brows,
+                    brows,
                     dst,
                     image_size,
-                    &scanned_column_kernel,
+                    column_kernel,
                 );
             }
 
@@ -628,6 +552,47 @@ where
     Ok(())
 }
 
+/// Filters horizontally source row `y` into `dst`, rows outside of the image follow vertical edge mode
+#[allow(clippy::too_many_arguments)]
+#[inline]
+fn filter_source_row<T, F, const N: usize>(
+    image: &BlurImage<T>,
+    y: i64,
+    row_buffer: &mut [T],
+    dst: &mut [T],
+    row_kernel: &[F],
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    row_handler: fn(Arena, &[T], &mut [T], ImageSize, &[F]),
+) where
+    T: Default + Copy + Send + Sync + 'static + Debug,
+    f64: PrimitiveCast<T>,
+{
+    let image_size = image.size();
+    let height = image_size.height as i64;
+    if (y < 0 || y >= height) && edge_modes.vertical == EdgeMode::Constant {
+        for (i, dst) in row_buffer.iter_mut().enumerate() {
+            *dst = border_constant[i % N].cast_();
+        }
+    } else {
+        fill_arena_row::<T, N>(
+            row_buffer,
+            image,
+            clamp_edge!(edge_modes.vertical, y, 0i64, height),
+            KernelShape::new(row_kernel.len(), 0),
+            edge_modes.horizontal,
+            border_constant,
+        );
+    }
+    row_handler(
+        Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
+        row_buffer,
+        dst,
+        image_size,
+        row_kernel,
+    );
+}
+
 pub(crate) fn create_brows<'a, T>(
     image_size: ImageSize,
     column_kernel_shape: KernelShape,
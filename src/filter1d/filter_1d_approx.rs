@@ -28,33 +28,32 @@
  */
 #![forbid(unsafe_code)]
 use crate::edge_mode::{BorderHandle, clamp_edge};
-use crate::filter1d::arena::{Arena, make_arena_columns, write_arena_row};
-use crate::filter1d::filter::create_brows;
-use crate::filter1d::filter_1d_column_handler_approx::BuildColumnHandlerApprox;
-use crate::filter1d::filter_1d_row_handler_approx::Filter1DRowHandlerApprox;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::filter1d::arena::{Arena, fill_arena_row, write_arena_columns};
+use crate::filter1d::filter_1d_column_handler_approx::{
+    BuildColumnHandlerApprox, ResolveColumnHandlerApprox,
+};
+use crate::filter1d::filter_1d_row_handler_approx::{
+    Filter1DRowHandlerApprox, ResolveRowHandlerApprox,
+};
 use crate::filter1d::filter_element::KernelShape;
-use crate::filter1d::filter_scan::{is_symmetric_1d, scan_se_1d_flat};
-use crate::filter1d::row_handler_small_approx::{BuildRowHandlerBInter, RowsHolder, RowsHolderMut};
+use crate::filter1d::filter_scan::is_symmetric_1d;
+use crate::filter1d::row_handler_small_approx::{
+    BuildRowHandlerBInter, ResolveRowHandlerBInter, RowsHolder, RowsHolderMut,
+};
 use crate::filter1d::to_approx_storage::{ApproxLevel, ToApproxStorage};
 use crate::primitives::PrimitiveCast;
 use crate::safe_math::{SafeAdd, SafeMul};
 use crate::to_storage::ToStorage;
-use crate::util::{CowScratch, ScratchBuffer};
-use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, EdgeMode2D, Scalar, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
+use crate::util::ScratchBuffer;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, BlurWorkspace, EdgeMode, EdgeMode2D, Scalar,
+    ThreadingPolicy,
+};
 use num_traits::{Float, MulAdd};
 use std::fmt::Debug;
 use std::ops::{Add, Mul, Shl, Shr};
-
-struct RowContext<T> {
-    arena: Vec<T>,
-    arena_width: usize,
-}
-
-struct RowContextSmall<T: Copy + Default + Debug> {
-    scratch: ScratchBuffer<T, 4096>,
-    row_buffer: CowScratch<T, 4096>,
-}
+use std::sync::Arc;
 
 /// Performs 2D separable approximated convolution on single plane image
 ///
@@ -112,19 +111,249 @@ where
     i32: PrimitiveCast<F> + PrimitiveCast<I>,
     i64: PrimitiveCast<I> + PrimitiveCast<F>,
     f64: PrimitiveCast<T>,
+{
+    filter_1d_approx_with_workspace::<T, F, I, N>(
+        image,
+        destination,
+        row_kernel,
+        column_kernel,
+        edge_modes,
+        border_constant,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
+}
+
+/// Performs 2D separable approximated convolution using reusable scratch memory.
+///
+/// Same as [filter_1d_approx], but intermediate image buffers are taken from the provided
+/// [BlurWorkspace] and reused between calls. Fixed point kernels are still computed
+/// on every call, use [crate::GaussianPlan] to compute them once.
+///
+/// # Arguments
+///
+/// * `image`: Source image
+/// * `destination`: Destination image
+/// * `row_kernel`: Row kernel, *size must be odd*!
+/// * `column_kernel`: Column kernel, *size must be odd*!
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value
+/// * `threading_policy`: See [ThreadingPolicy] for more info
+/// * `workspace`: Scratch memory reused between calls, see [BlurWorkspace].
+///
+#[allow(clippy::too_many_arguments)]
+pub fn filter_1d_approx_with_workspace<T, F, I, const N: usize>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    row_kernel: &[F],
+    column_kernel: &[F],
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
+) -> Result<(), BlurError>
+where
+    T: Copy
+        + PrimitiveCast<F>
+        + Default
+        + Send
+        + Sync
+        + Filter1DRowHandlerApprox<T, I>
+        + BuildColumnHandlerApprox<T, I>
+        + BuildRowHandlerBInter<T, I>
+        + Debug,
+    F: ToStorage<T> + Mul<F> + MulAdd<F, Output = F> + Send + Sync + PrimitiveCast<I> + Float,
+    I: Copy
+        + Mul<Output = I>
+        + Add<Output = I>
+        + Shr<I, Output = I>
+        + Default
+        + 'static
+        + ToApproxStorage<T>
+        + PrimitiveCast<F>
+        + PartialEq
+        + Sync
+        + Send
+        + ApproxLevel
+        + Shl<Output = I>,
+    i32: PrimitiveCast<F> + PrimitiveCast<I>,
+    i64: PrimitiveCast<I> + PrimitiveCast<F>,
+    f64: PrimitiveCast<T>,
+{
+    let kernels = ApproxKernels::<I>::new(row_kernel, column_kernel);
+    let handlers = ApproxHandlers::new::<I, N>(&kernels);
+    filter_1d_approx_prepared::<T, I, N>(
+        image,
+        destination,
+        &kernels,
+        &handlers,
+        edge_modes,
+        border_constant,
+        threading_policy,
+        workspace,
+    )
+}
+
+/// Fixed point kernels, scaled once and reused across calls.
+#[derive(Debug, Clone)]
+pub(crate) struct ApproxKernels<I> {
+    pub(crate) row: Vec<I>,
+    pub(crate) column: Vec<I>,
+    pub(crate) is_row_symmetric: bool,
+    pub(crate) is_column_symmetric: bool,
+}
+
+impl<I: Copy + Default + PartialEq + ApproxLevel + Shl<Output = I> + 'static> ApproxKernels<I> {
+    pub(crate) fn new<F>(row_kernel: &[F], column_kernel: &[F]) -> ApproxKernels<I>
+    where
+        F: Float + PrimitiveCast<I> + 'static,
+        I: PrimitiveCast<F>,
+        i32: PrimitiveCast<I>,
+    {
+        let one_i: I = 1.cast_();
+        let base_level: I = one_i << I::approx_level().cast_();
+        let initial_scale: F = base_level.cast_();
+
+        ApproxKernels {
+            row: row_kernel
+                .iter()
+                .map(|&x| (x * initial_scale).cast_())
+                .collect(),
+            column: column_kernel
+                .iter()
+                .map(|&x| (x * initial_scale).cast_())
+                .collect(),
+            is_row_symmetric: is_symmetric_1d(row_kernel),
+            is_column_symmetric: is_symmetric_1d(column_kernel),
+        }
+    }
+}
+
+/// Row and column handlers resolved once for fixed point kernels and reused across calls.
+#[derive(Clone)]
+pub(crate) struct ApproxHandlers<T> {
+    row_binter: Option<Arc<dyn ResolveRowHandlerBInter<T> + Send + Sync>>,
+    row: Arc<dyn ResolveRowHandlerApprox<T> + Send + Sync>,
+    column: Arc<dyn ResolveColumnHandlerApprox<T> + Send + Sync>,
+}
+
+impl<T> ApproxHandlers<T> {
+    pub(crate) fn new<I, const N: usize>(kernels: &ApproxKernels<I>) -> ApproxHandlers<T>
+    where
+        T: Filter1DRowHandlerApprox<T, I>
+            + BuildColumnHandlerApprox<T, I>
+            + BuildRowHandlerBInter<T, I>,
+    {
+        ApproxHandlers {
+            row_binter: T::build_row_handler_binter::<N>(kernels.is_row_symmetric, &kernels.row),
+            row: T::get_row_handler_apr::<N>(kernels.is_row_symmetric, &kernels.row),
+            column: T::build_column_handler(kernels.is_column_symmetric, &kernels.column),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn filter_1d_approx_prepared<T, I, const N: usize>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    kernels: &ApproxKernels<I>,
+    handlers: &ApproxHandlers<T>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
+) -> Result<(), BlurError>
+where
+    T: Copy
+        + Default
+        + Send
+        + Sync
+        + Filter1DRowHandlerApprox<T, I>
+        + BuildColumnHandlerApprox<T, I>
+        + BuildRowHandlerBInter<T, I>
+        + Debug
+        + 'static,
+    I: Copy
+        + Mul<Output = I>
+        + Add<Output = I>
+        + Shr<I, Output = I>
+        + Default
+        + 'static
+        + ToApproxStorage<T>
+        + PartialEq
+        + Sync
+        + Send
+        + ApproxLevel
+        + Shl<Output = I>,
+    i32: PrimitiveCast<I>,
+    i64: PrimitiveCast<I>,
+    f64: PrimitiveCast<T>,
 {
     const SMALL_KERNEL_CUTOFF: usize = 61;
-    if column_kernel.len() <= SMALL_KERNEL_CUTOFF {
-        return filter_1d_approx_sliding_buffer::<T, F, I, N>(
+    if kernels.column.len() <= SMALL_KERNEL_CUTOFF {
+        filter_1d_approx_sliding_buffer::<T, I, N>(
             image,
             destination,
-            row_kernel,
-            column_kernel,
+            kernels,
+            handlers,
             edge_modes,
             border_constant,
             threading_policy,
-        );
+            workspace,
+        )
+    } else {
+        filter_1d_approx_transient::<T, I, N>(
+            image,
+            destination,
+            kernels,
+            handlers,
+            edge_modes,
+            border_constant,
+            threading_policy,
+            workspace,
+        )
     }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn filter_1d_approx_transient<T, I, const N: usize>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    kernels: &ApproxKernels<I>,
+    handlers: &ApproxHandlers<T>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
+) -> Result<(), BlurError>
+where
+    T: Copy
+        + Default
+        + Send
+        + Sync
+        + Filter1DRowHandlerApprox<T, I>
+        + BuildColumnHandlerApprox<T, I>
+        + BuildRowHandlerBInter<T, I>
+        + Debug
+        + 'static,
+    I: Copy
+        + Mul<Output = I>
+        + Add<Output = I>
+        + Shr<I, Output = I>
+        + Default
+        + 'static
+        + ToApproxStorage<T>
+        + PartialEq
+        + Sync
+        + Send
+        + ApproxLevel
+        + Shl<Output = I>,
+    i32: PrimitiveCast<I>,
+    i64: PrimitiveCast<I>,
+    f64: PrimitiveCast<T>,
+{
+    let row_kernel = kernels.row.as_slice();
+    let column_kernel = kernels.column.as_slice();
     image.check_layout_channels(N)?;
     destination.check_layout_channels(N, Some(image))?;
     image.only_size_matches_mut(destination)?;
@@ -148,89 +377,72 @@ where
 
     _ = (destination.stride as usize).safe_mul(3)?;
 
-    let one_i: I = 1.cast_();
-    let base_level: I = one_i << I::approx_level().cast_();
-    let initial_scale: F = base_level.cast_();
-
-    let scaled_row_kernel = row_kernel
-        .iter()
-        .map(|&x| (x * initial_scale).cast_())
-        .collect::<Vec<I>>();
-    let scaled_column_kernel = column_kernel
-        .iter()
-        .map(|&x| (x * initial_scale).cast_())
-        .collect::<Vec<I>>();
-
-    let scanned_row_kernel = scan_se_1d_flat::<I>(&scaled_row_kernel);
-    let scanned_column_kernel = scan_se_1d_flat::<I>(&scaled_column_kernel);
-    let scanned_column_kernel_slice = scanned_column_kernel.as_slice();
-    let is_column_kernel_symmetric = is_symmetric_1d(column_kernel);
-    let is_row_kernel_symmetric = is_symmetric_1d(row_kernel);
-
     let image_size = image.size();
 
     let thread_count =
-        threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
-
-    let mut transient_image = vec![T::default(); image_size.width * image_size.height * N];
-
-    let row_handler =
-        T::get_row_handler_apr::<N>(is_row_kernel_symmetric, scanned_row_kernel.as_slice());
-    transient_image
-        .tb_par_chunks_exact_mut(image_size.width * N)
-        .for_each_enumerated_with_context(
-            &pool,
-            || {
-                let arena_width = image_size.width * N + pad_w * 2 * N;
-                let row = vec![T::default(); arena_width];
-                RowContext {
-                    arena: row,
-                    arena_width,
-                }
-            },
-            |y, ctx, dst_row| {
-                let pad_w = scanned_row_kernel.len() / 2;
-                write_arena_row::<T, N>(
-                    &mut ctx.arena,
-                    image,
-                    y,
-                    KernelShape::new(row_kernel.len(), 0),
-                    edge_modes.horizontal,
-                    border_constant,
-                )
-                .unwrap();
+        threading_policy.thread_count(image_size.width as u32, image_size.height as u32);
+    let pool = crate::executor::ThreadPool::new(thread_count);
 
-                row_handler.single_row(
-                    Arena::new(ctx.arena_width, 1, pad_w, 0, N),
-                    &ctx.arena,
-                    dst_row,
-                    image_size,
-                );
-            },
-        );
-
-    let transient_image_slice = transient_image.as_slice();
-
-    let column_kernel_shape = KernelShape::new(0, scanned_column_kernel_slice.len());
-    let column_arena_k = make_arena_columns::<T, N>(
-        transient_image.as_slice(),
-        image_size,
-        column_kernel_shape,
-        edge_modes.vertical,
-        (0..N)
-            .map(|x| border_constant[x].cast_())
-            .collect::<Vec<T>>()
-            .try_into()
-            .unwrap(),
+    let row_handler = handlers.row.as_ref();
+    let column_handler = handlers.column.as_ref();
+
+    let pad_w = row_kernel.len() / 2;
+    let pad_h = column_kernel.len() / 2;
+    let src_stride = image_size.width * N;
+    let arena_width = src_stride.safe_add(pad_w.safe_mul(2 * N)?)?;
+    let padded_height = image_size.height.safe_add(pad_h * 2)?;
+    let padded_size = padded_height.safe_mul(src_stride)?;
+    let band_rows = image_size.height.div_ceil(thread_count);
+    let bands_count = image_size.height.div_ceil(band_rows);
+
+    // Transient image with column pads above and below it, followed by one row arena per band
+    let (scratch, rows_table) = workspace.take_with_rows(
+        padded_size.safe_add(arena_width.safe_mul(bands_count)?)?,
+        padded_height,
     )?;
+    let (padded_image, arenas) = scratch.split_at_mut(padded_size);
+
+    {
+        let (top_pad, rest) = padded_image.split_at_mut(pad_h * src_stride);
+        let (transient_image, bottom_pad) = rest.split_at_mut(image_size.height * src_stride);
+
+        transient_image
+            .tb_par_chunks_mut(band_rows * src_stride)
+            .zip(arenas.chunks_exact_mut(arena_width))
+            .for_each_enumerated(&pool, |band, (dst_rows, arena)| {
+                for (i, dst_row) in dst_rows.chunks_exact_mut(src_stride).enumerate() {
+                    fill_arena_row::<T, N>(
+                        arena,
+                        image,
+                        band * band_rows + i,
+                        KernelShape::new(row_kernel.len(), 0),
+                        edge_modes.horizontal,
+                        border_constant,
+                    );
+                    row_handler.single_row(
+                        Arena::new(image_size.width + pad_w * 2, 1, pad_w, 0, N),
+                        arena,
+                        dst_row,
+                        image_size,
+                    );
+                }
+            });
 
-    let top_pad = column_arena_k.top_pad.as_slice();
-    let bottom_pad = column_arena_k.bottom_pad.as_slice();
+        write_arena_columns::<T, N>(
+            transient_image,
+            image_size,
+            KernelShape::new(0, column_kernel.len()),
+            edge_modes.vertical,
+            std::array::from_fn(|x| border_constant[x].cast_()),
+            top_pad,
+            bottom_pad,
+        )?;
+    }
 
-    let pad_h = column_kernel_shape.height / 2;
-    let column_handler = T::build_column_handler(is_column_kernel_symmetric, &scaled_column_kernel);
-    let src_stride = image_size.width * N;
+    let padded_image: &[T] = padded_image;
+    let padded_rows = rows_table.fill(|y| &padded_image[y * src_stride..(y + 1) * src_stride]);
+
+    let kernel_height = column_kernel.len();
     let dst_stride = destination.row_stride() as usize;
 
     let mut _dest_slice = destination.projected();
@@ -244,46 +456,13 @@ where
             .for_each_enumerated(&pool, |y, row| {
                 use crate::filter1d::filter_1d_column_handler::FilterBrows;
                 let y = y * 3;
-                let brows0 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y,
-                );
-
-                let brows1 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 1,
-                );
-
-                let brows2 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 2,
-                );
-
-                let brows_slice0 = brows0.as_slice();
-                let brows_slice1 = brows1.as_slice();
-                let brows_slice2 = brows2.as_slice();
-
-                let brows_vec = [brows_slice0, brows_slice1, brows_slice2];
-
-                let brows = FilterBrows { brows: brows_vec };
+                let brows = FilterBrows {
+                    brows: [
+                        &padded_rows[y..y + kernel_height],
+                        &padded_rows[y + 1..y + 1 + kernel_height],
+                        &padded_rows[y + 2..y + 2 + kernel_height],
+                    ],
+                };
 
                 column_handler.multiple_rows(
                     Arena::new(image_size.width, pad_h, 0, pad_h, N),
@@ -306,34 +485,10 @@ where
             .for_each_enumerated(&pool, |y, row| {
                 use crate::filter1d::filter_1d_column_handler::FilterBrows;
                 let y = y * 2;
-                let brows0 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y,
-                );
-
-                let brows1 = create_brows(
-                    image_size,
-                    column_kernel_shape,
-                    top_pad,
-                    bottom_pad,
-                    pad_h,
-                    transient_image_slice,
-                    src_stride,
-                    y + 1,
-                );
-
-                let brows_slice0 = brows0.as_slice();
-                let brows_slice1 = brows1.as_slice();
-
-                let brows_vec = [brows_slice0, brows_slice1, brows_slice1];
-
-                let brows = FilterBrows { brows: brows_vec };
+                let brows1 = &padded_rows[y + 1..y + 1 + kernel_height];
+                let brows = FilterBrows {
+                    brows: [&padded_rows[y..y + kernel_height], brows1, brows1],
+                };
 
                 column_handler.multiple_rows(
                     Arena::new(image_size.width, pad_h, 0, pad_h, N),
@@ -352,23 +507,12 @@ where
     _dest_slice
         .tb_par_chunks_mut(dst_stride)
         .for_each_enumerated(&pool, |y, row| {
-            let brows = create_brows(
-                image_size,
-                column_kernel_shape,
-                top_pad,
-                bottom_pad,
-                pad_h,
-                transient_image_slice,
-                src_stride,
-                _processed_y + y,
-            );
-
-            let brows_slice = brows.as_slice();
+            let y = _processed_y + y;
             let row = &mut row[..image_size.width * N];
 
             column_handler.single_row(
                 Arena::new(image_size.width, pad_h, 0, pad_h, N),
-                brows_slice,
+                &padded_rows[y..y + kernel_height],
                 row,
                 image_size,
             );
@@ -377,26 +521,27 @@ where
     Ok(())
 }
 
-fn filter_1d_approx_sliding_buffer<T, F, I, const N: usize>(
+#[allow(clippy::too_many_arguments)]
+fn filter_1d_approx_sliding_buffer<T, I, const N: usize>(
     image: &BlurImage<T>,
     destination: &mut BlurImageMut<T>,
-    row_kernel: &[F],
-    column_kernel: &[F],
+    kernels: &ApproxKernels<I>,
+    handlers: &ApproxHandlers<T>,
     edge_modes: EdgeMode2D,
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<T>,
 ) -> Result<(), BlurError>
 where
     T: Copy
-        + PrimitiveCast<F>
         + Default
         + Send
         + Sync
         + Filter1DRowHandlerApprox<T, I>
         + BuildColumnHandlerApprox<T, I>
         + BuildRowHandlerBInter<T, I>
-        + Debug,
-    F: ToStorage<T> + Mul<F> + MulAdd<F, Output = F> + Send + Sync + PrimitiveCast<I> + Float,
+        + Debug
+        + 'static,
     I: Copy
         + Mul<Output = I>
         + Add<Output = I>
@@ -404,17 +549,18 @@ where
         + Default
         + 'static
         + ToApproxStorage<T>
-        + PrimitiveCast<F>
         + PartialEq
         + Sync
         + Send
         + ApproxLevel
         + Shl<Output = I>,
-    i32: PrimitiveCast<F> + PrimitiveCast<I>,
-    i64: PrimitiveCast<I> + PrimitiveCast<F>,
+    i32: PrimitiveCast<I>,
+    i64: PrimitiveCast<I>,
     f64: PrimitiveCast<T>,
 {
-    const MAX_STACK_KERNEL: usize = 35;
+    let row_kernel = kernels.row.as_slice();
+    let column_kernel = kernels.column.as_slice();
+    const MAX_STACK_KERNEL: usize = 61;
     image.check_layout_channels(N)?;
     destination.check_layout_channels(N, Some(image))?;
     image.only_size_matches_mut(destination)?;
@@ -434,63 +580,95 @@ where
 
     const B_INTER_CUTOFF: usize = 29;
 
-    let one_i: I = 1.cast_();
-    let base_level: I = one_i << I::approx_level().cast_();
-    let initial_scale: F = base_level.cast_();
-
-    let scaled_row_kernel = row_kernel
-        .iter()
-        .map(|&x| (x * initial_scale).cast_())
-        .collect::<Vec<I>>();
-    let scaled_column_kernel = column_kernel
-        .iter()
-        .map(|&x| (x * initial_scale).cast_())
-        .collect::<Vec<I>>();
-
-    let scanned_row_kernel = scan_se_1d_flat::<I>(&scaled_row_kernel);
-    let is_column_kernel_symmetric = is_symmetric_1d(column_kernel);
-    let is_row_kernel_symmetric = is_symmetric_1d(row_kernel);
-
     let image_size = image.size();
 
     let thread_count =
         threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let tile_size = (image_size.height as u32 / thread_count).clamp(1, image_size.height as u32);
 
-    let row_handler_binter =
-        T::build_row_handler_binter::<N>(is_row_kernel_symmetric, &scaled_row_kernel);
-    let row_handler =
-        T::get_row_handler_apr::<N>(is_row_kernel_symmetric, scanned_row_kernel.as_slice());
-    let column_handler = T::build_column_handler(is_column_kernel_symmetric, &scaled_column_kernel);
+    let row_handler_binter = handlers.row_binter.as_ref();
+    let row_handler = handlers.row.as_ref();
+    let column_handler = handlers.column.as_ref();
 
     let row_stride = image_size.width * N;
     let src_row_stride = image.row_stride() as usize;
 
     let dest_stride = destination.row_stride() as usize;
 
-    if thread_count > 1 {
-        destination
-            .projected()
-            .tb_par_chunks_mut(dest_stride * tile_size as usize)
-            .for_each_enumerated_with_context(
-                &pool,
-                || RowContextSmall {
-                    scratch: ScratchBuffer::<T, 4096>::new(row_stride * scaled_column_kernel.len()),
-                    row_buffer: CowScratch::new(),
-                },
-                |cy, ctx, dst_rows| {
-                    let source_y = cy * tile_size as usize;
-                    let buffer = ctx.scratch.as_mut_slice();
-
-                    let row_buffer = &mut ctx.row_buffer;
+    let buffer_size = row_stride.safe_mul(column_kernel.len())?;
+    let arena_width = row_stride + (row_kernel.len() / 2) * 2 * N;
+    let tile_scratch_size = buffer_size.safe_add(arena_width)?;
 
-                    let column_kernel_len = scaled_column_kernel.len();
-                    let half_kernel = column_kernel_len / 2;
+    if thread_count > 1 {
+        let tiles_count = image_size.height.div_ceil(tile_size as usize);
+        let scratch = workspace.take(tile_scratch_size.safe_mul(tiles_count)?)?;
+        scratch
+            .tb_par_chunks_mut(tile_scratch_size)
+            .zip(
+                destination
+                    .projected()
+                    .chunks_mut(dest_stride * tile_size as usize),
+            )
+            .for_each_enumerated(&pool, |cy, (tile_scratch, dst_rows)| {
+                let source_y = cy * tile_size as usize;
+                let (buffer, row_buffer) = tile_scratch.split_at_mut(buffer_size);
+
+                let column_kernel_len = column_kernel.len();
+                let half_kernel = column_kernel_len / 2;
+
+                // preload top edge
+                if source_y == 0 {
+                    if let Some(handler) = row_handler_binter.as_ref()
+                        && row_kernel.len() < B_INTER_CUTOFF
+                        && edge_modes.horizontal != EdgeMode::Constant
+                    {
+                        handler.handle_row(
+                            BorderHandle {
+                                edge_mode: edge_modes.horizontal,
+                                scalar: border_constant,
+                            },
+                            &RowsHolder {
+                                holder: [&image.data.as_ref()[..image_size.width * N]],
+                            },
+                            &mut RowsHolderMut {
+                                holder: [&mut buffer[..row_stride]],
+                            },
+                            image_size,
+                        );
+                    } else {
+                        let row = &mut *row_buffer;
+                        fill_arena_row::<T, N>(
+                            row,
+                            image,
+                            0,
+                            KernelShape::new(row_kernel.len(), 0),
+                            edge_modes.horizontal,
+                            border_constant,
+                        );
+                        row_handler.single_row(
+                            Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
+                            row,
+                            &mut buffer[..row_stride],
+                            image_size,
+                        );
+                    }
 
-                    // preload top edge
-                    if source_y == 0 {
+                    let (src_row, rest) = buffer.split_at_mut(row_stride);
+                    for dst in rest.chunks_exact_mut(row_stride).take(half_kernel) {
+                        for (dst, src) in dst.iter_mut().zip(src_row.iter()) {
+                            *dst = *src;
+                        }
+                    }
+                } else {
+                    for src_y in 0..=half_kernel {
+                        let s_y = clamp_edge!(
+                            edge_modes.vertical,
+                            src_y as i64 + source_y as i64 - half_kernel as i64 - 1,
+                            0i64,
+                            image_size.height as i64
+                        );
                         if let Some(handler) = row_handler_binter.as_ref()
                             && row_kernel.len() < B_INTER_CUTOFF
                             && edge_modes.horizontal != EdgeMode::Constant
@@ -501,180 +679,123 @@ where
                                     scalar: border_constant,
                                 },
                                 &RowsHolder {
-                                    holder: [&image.data.as_ref()[..image_size.width * N]],
+                                    holder: [&image.data.as_ref()[s_y * src_row_stride
+                                        ..s_y * src_row_stride + image_size.width * N]],
                                 },
                                 &mut RowsHolderMut {
-                                    holder: [&mut buffer[..row_stride]],
+                                    holder: [
+                                        &mut buffer[src_y * row_stride..(src_y + 1) * row_stride]
+                                    ],
                                 },
                                 image_size,
                             );
                         } else {
-                            let pad_w = scanned_row_kernel.len() / 2;
-                            let row = row_buffer.get_or_init(image_size.width * N + pad_w * 2 * N);
-                            write_arena_row::<T, N>(
+                            let row = &mut *row_buffer;
+                            fill_arena_row::<T, N>(
                                 row,
                                 image,
-                                0,
+                                s_y,
                                 KernelShape::new(row_kernel.len(), 0),
                                 edge_modes.horizontal,
                                 border_constant,
-                            )
-                            .unwrap();
+                            );
                             row_handler.single_row(
                                 Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
                                 row,
-                                &mut buffer[..row_stride],
+                                &mut buffer[src_y * row_stride..(src_y + 1) * row_stride],
                                 image_size,
                             );
                         }
-
-                        let (src_row, rest) = buffer.split_at_mut(row_stride);
-                        for dst in rest.chunks_exact_mut(row_stride).take(half_kernel) {
-                            for (dst, src) in dst.iter_mut().zip(src_row.iter()) {
-                                *dst = *src;
-                            }
-                        }
-                    } else {
-                        for src_y in 0..=half_kernel {
-                            let s_y = clamp_edge!(
-                                edge_modes.vertical,
-                                src_y as i64 + source_y as i64 - half_kernel as i64 - 1,
-                                0i64,
-                                image_size.height as i64
-                            );
-                            if let Some(handler) = row_handler_binter.as_ref()
-                                && row_kernel.len() < B_INTER_CUTOFF
-                                && edge_modes.horizontal != EdgeMode::Constant
-                            {
-                                handler.handle_row(
-                                    BorderHandle {
-                                        edge_mode: edge_modes.horizontal,
-                                        scalar: border_constant,
-                                    },
-                                    &RowsHolder {
-                                        holder: [&image.data.as_ref()[s_y * src_row_stride
-                                            ..s_y * src_row_stride + image_size.width * N]],
-                                    },
-                                    &mut RowsHolderMut {
-                                        holder: [&mut buffer
-                                            [src_y * row_stride..(src_y + 1) * row_stride]],
-                                    },
-                                    image_size,
-                                );
-                            } else {
-                                let pad_w = scanned_row_kernel.len() / 2;
-                                let row =
-                                    row_buffer.get_or_init(image_size.width * N + pad_w * 2 * N);
-                                write_arena_row::<T, N>(
-                                    row,
-                                    image,
-                                    s_y,
-                                    KernelShape::new(row_kernel.len(), 0),
-                                    edge_modes.horizontal,
-                                    border_constant,
-                                )
-                                .unwrap();
-                                row_handler.single_row(
-                                    Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                                    row,
-                                    &mut buffer[src_y * row_stride..(src_y + 1) * row_stride],
-                                    image_size,
-                                );
-                            }
-                        }
                     }
+                }
 
-                    let mut start_ky = column_kernel_len / 2 + 1;
+                let mut start_ky = column_kernel_len / 2 + 1;
 
-                    start_ky %= column_kernel_len;
+                start_ky %= column_kernel_len;
 
-                    let rows_count = dst_rows.len() / dest_stride;
+                let rows_count = dst_rows.len() / dest_stride;
 
-                    for (y, dy) in (source_y..source_y + rows_count + half_kernel)
-                        .zip(0..rows_count + half_kernel)
+                for (y, dy) in
+                    (source_y..source_y + rows_count + half_kernel).zip(0..rows_count + half_kernel)
+                {
+                    let new_y = if y < image_size.height {
+                        y
+                    } else {
+                        clamp_edge!(
+                            edge_modes.vertical,
+                            y as i64,
+                            0i64,
+                            image_size.height as i64
+                        )
+                    };
+
+                    if let Some(handler) = row_handler_binter.as_ref()
+                        && row_kernel.len() < B_INTER_CUTOFF
+                        && edge_modes.horizontal != EdgeMode::Constant
                     {
-                        let new_y = if y < image_size.height {
-                            y
-                        } else {
-                            clamp_edge!(
-                                edge_modes.vertical,
-                                y as i64,
-                                0i64,
-                                image_size.height as i64
-                            )
-                        };
+                        handler.handle_row(
+                            BorderHandle {
+                                edge_mode: edge_modes.horizontal,
+                                scalar: border_constant,
+                            },
+                            &RowsHolder {
+                                holder: [&image.data.as_ref()[new_y * src_row_stride
+                                    ..new_y * src_row_stride + image_size.width * N]],
+                            },
+                            &mut RowsHolderMut {
+                                holder: [
+                                    &mut buffer[start_ky * row_stride..(start_ky + 1) * row_stride]
+                                ],
+                            },
+                            image_size,
+                        );
+                    } else {
+                        let row = &mut *row_buffer;
+                        fill_arena_row::<T, N>(
+                            row,
+                            image,
+                            new_y,
+                            KernelShape::new(row_kernel.len(), 0),
+                            edge_modes.horizontal,
+                            border_constant,
+                        );
+                        row_handler.single_row(
+                            Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
+                            row,
+                            &mut buffer[start_ky * row_stride..(start_ky + 1) * row_stride],
+                            image_size,
+                        );
+                    }
 
-                        if let Some(handler) = row_handler_binter.as_ref()
-                            && row_kernel.len() < B_INTER_CUTOFF
-                            && edge_modes.horizontal != EdgeMode::Constant
-                        {
-                            handler.handle_row(
-                                BorderHandle {
-                                    edge_mode: edge_modes.horizontal,
-                                    scalar: border_constant,
-                                },
-                                &RowsHolder {
-                                    holder: [&image.data.as_ref()[new_y * src_row_stride
-                                        ..new_y * src_row_stride + image_size.width * N]],
-                                },
-                                &mut RowsHolderMut {
-                                    holder: [&mut buffer
-                                        [start_ky * row_stride..(start_ky + 1) * row_stride]],
-                                },
-                                image_size,
-                            );
-                        } else {
-                            let row = row_buffer.get_or_unwrap_mut();
-                            write_arena_row::<T, N>(
-                                row,
-                                image,
-                                new_y,
-                                KernelShape::new(row_kernel.len(), 0),
-                                edge_modes.horizontal,
-                                border_constant,
-                            )
-                            .unwrap();
-                            row_handler.single_row(
-                                Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
-                                row,
-                                &mut buffer[start_ky * row_stride..(start_ky + 1) * row_stride],
-                                image_size,
-                            );
+                    if dy >= half_kernel {
+                        let mut brows_stack =
+                            ScratchBuffer::<&[T], MAX_STACK_KERNEL>::new(column_kernel_len);
+                        let brows_storage = brows_stack.as_mut_slice();
+                        for (i, brow) in brows_storage.iter_mut().enumerate() {
+                            let ky = (i + start_ky + 1) % column_kernel_len;
+                            *brow = &buffer[ky * row_stride..(ky + 1) * row_stride];
                         }
 
-                        if dy >= half_kernel {
-                            let mut brows_stack =
-                                ScratchBuffer::<&[T], MAX_STACK_KERNEL>::new(column_kernel_len);
-                            let brows_storage = brows_stack.as_mut_slice();
-                            for (i, brow) in brows_storage.iter_mut().enumerate() {
-                                let ky = (i + start_ky + 1) % column_kernel_len;
-                                *brow = &buffer[ky * row_stride..(ky + 1) * row_stride];
-                            }
-
-                            let dy = dy - half_kernel;
+                        let dy = dy - half_kernel;
 
-                            let dst = &mut dst_rows
-                                [dy * dest_stride..dy * dest_stride + image_size.width * N];
-
-                            column_handler.single_row(
-                                Arena::new(image_size.width, half_kernel, 0, half_kernel, N),
-                                brows_storage,
-                                dst,
-                                image_size,
-                            );
-                        }
+                        let dst = &mut dst_rows
+                            [dy * dest_stride..dy * dest_stride + image_size.width * N];
 
-                        start_ky += 1;
-                        start_ky %= column_kernel_len;
+                        column_handler.single_row(
+                            Arena::new(image_size.width, half_kernel, 0, half_kernel, N),
+                            brows_storage,
+                            dst,
+                            image_size,
+                        );
                     }
-                },
-            );
-    } else {
-        let mut scratch_buffer =
-            ScratchBuffer::<T, 4096>::new(row_stride * scaled_column_kernel.len());
-        let buffer = scratch_buffer.as_mut_slice();
 
-        let mut row_buffer: CowScratch<T, 4096> = CowScratch::new();
+                    start_ky += 1;
+                    start_ky %= column_kernel_len;
+                }
+            });
+    } else {
+        let scratch = workspace.take(tile_scratch_size)?;
+        let (buffer, row_buffer) = scratch.split_at_mut(buffer_size);
 
         // preload top edge
         if let Some(handler) = row_handler_binter.as_ref()
@@ -695,16 +816,15 @@ where
                 image_size,
             );
         } else {
-            let pad_w = scanned_row_kernel.len() / 2;
-            let row = row_buffer.get_or_init(image_size.width * N + pad_w * 2 * N);
-            write_arena_row::<T, N>(
+            let row = &mut *row_buffer;
+            fill_arena_row::<T, N>(
                 row,
                 image,
                 0,
                 KernelShape::new(row_kernel.len(), 0),
                 edge_modes.horizontal,
                 border_constant,
-            )?;
+            );
             row_handler.single_row(
                 Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
                 row,
@@ -713,7 +833,7 @@ where
             );
         }
 
-        let column_kernel_len = scaled_column_kernel.len();
+        let column_kernel_len = column_kernel.len();
 
         let half_kernel = column_kernel_len / 2;
 
@@ -759,15 +879,15 @@ where
                     image_size,
                 );
             } else {
-                let row = row_buffer.get_or_unwrap_mut();
-                write_arena_row::<T, N>(
+                let row = &mut *row_buffer;
+                fill_arena_row::<T, N>(
                     row,
                     image,
                     new_y,
                     KernelShape::new(row_kernel.len(), 0),
                     edge_modes.horizontal,
                     border_constant,
-                )?;
+                );
                 row_handler.single_row(
                     Arena::new(image_size.width, 1, row_kernel.len() / 2, 0, N),
                     row,
@@ -27,6 +27,7 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::edge_mode::clamp_edge;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::filter1d::arena::{Arena, make_arena_columns, make_arena_row, write_arena_row};
 use crate::filter1d::filter::create_brows;
 use crate::filter1d::filter_complex_dispatch_q::ComplexDispatchQ;
@@ -36,7 +37,6 @@ use crate::filter1d::to_approx_storage_complex::{ApproxComplexLevel, ToApproxSto
 use crate::primitives::PrimitiveCast;
 use crate::safe_math::{SafeAdd, SafeMul};
 use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode2D, Scalar, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use num_complex::Complex;
 use num_traits::{MulAdd, Num};
 use std::fmt::Debug;
@@ -172,7 +172,7 @@ where
 
     let thread_count =
         threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let mut transient_image =
         vec![Complex::<I>::default(); image_size.width * image_size.height * N];
@@ -339,7 +339,7 @@ where
 
     let thread_count =
         threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let tile_size = (image_size.height as u32 / thread_count).clamp(1, image_size.height as u32);
 
@@ -26,21 +26,6 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::primitives::PrimitiveCast;
-
-pub(crate) fn scan_se_1d_flat<F>(kernel: &[F]) -> Vec<F>
-where
-    F: Copy + PartialEq + 'static + Default,
-    i32: PrimitiveCast<F>,
-{
-    let mut left_front = vec![F::default(); kernel.len()];
-
-    for (dst, src) in left_front.iter_mut().zip(kernel.iter()) {
-        *dst = *src;
-    }
-    left_front
-}
-
 pub(crate) fn is_symmetric_1d<F>(kernel: &[F]) -> bool
 where
     F: Copy + PartialEq + 'static,
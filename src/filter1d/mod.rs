@@ -67,8 +67,10 @@ mod to_approx_storage;
 mod to_approx_storage_complex;
 
 pub(crate) use arena::{Arena, ArenaPads, make_arena};
-pub use filter::filter_1d_exact;
-pub use filter_1d_approx::filter_1d_approx;
+pub(crate) use arena_roi::copy_roi;
+pub use filter::{filter_1d_exact, filter_1d_exact_with_workspace};
+pub(crate) use filter_1d_approx::{ApproxHandlers, ApproxKernels, filter_1d_approx_prepared};
+pub use filter_1d_approx::{filter_1d_approx, filter_1d_approx_with_workspace};
 pub use filter_complex::filter_1d_complex;
 pub use filter_complex_q::filter_1d_complex_fixed_point;
 pub use filter_element::KernelShape;
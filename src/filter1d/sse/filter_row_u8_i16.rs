@@ -57,8 +57,6 @@ fn filter_rgb_row_sse_u8_i16_impl<const N: usize>(
     scanned_kernel: &[i16],
 ) {
     unsafe {
-        const N: usize = 3;
-
         let src = arena_src;
 
         let length = scanned_kernel.len();
@@ -116,7 +116,7 @@ pub(crate) fn convolve_segment_2d<T, F>(
                     k_weight,
                 );
             }
-            *dst.get_unchecked_mut(cx) = k0.to_();
+            *dst.get_unchecked_mut(x) = k0.to_();
         }
     }
 }
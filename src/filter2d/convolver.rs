@@ -26,18 +26,26 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::edge_mode::{reflect_index, reflect_index_101};
-use crate::filter2d::fft_utils::fft_next_good_size_real;
+use crate::edge_mode::clamp_edge;
+use crate::executor::{ThreadPool, parallel_range_with_context};
+use crate::filter2d::fft_utils::{fft_next_good_size, fft_next_good_size_real};
+use crate::filter2d::scan_se_2d::check_kernel_size;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{BlurImageMut, EdgeMode, EdgeMode2D, FftNumber};
-use novtb::{ThreadPool, parallel_range_with_context};
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode, EdgeMode2D, FftNumber, ImageSize, KernelShape,
+    Scalar,
+};
 use num_complex::Complex;
 use num_traits::{AsPrimitive, Zero};
 use std::fmt::Debug;
+use std::sync::Mutex;
+use zaft::FftDirection;
 
+/// Overlap-save convolution, image is processed tile by tile so memory
+/// is bounded by tile size and thread count rather than by image size.
 pub(crate) struct FftConvolve<T> {
-    pub(crate) border_constant: T,
+    pub(crate) border_constant: [T; 4],
     pub(crate) edge_mode: EdgeMode2D,
     pub(crate) thread_count: usize,
     pub(crate) block_rows: usize,
@@ -50,20 +58,52 @@ struct TileContext<T> {
     scratch: Vec<Complex<T>>,
 }
 
+#[derive(Copy, Clone)]
+struct TileGeometry {
+    fft_rows: usize,
+    fft_cols: usize,
+    ker_rows: usize,
+    ker_cols: usize,
+}
+
 impl<T: Copy + 'static + Zero + FftNumber> FftConvolve<T>
 where
     f64: AsPrimitive<T>,
 {
-    fn fill_tile(
+    pub(crate) fn new(
+        border_constant: Scalar,
+        edge_mode: EdgeMode2D,
+        thread_count: usize,
+        tile_size: ImageSize,
+        image_size: ImageSize,
+    ) -> FftConvolve<T> {
+        FftConvolve {
+            border_constant: [
+                border_constant[0].as_(),
+                border_constant[1].as_(),
+                border_constant[2].as_(),
+                border_constant[3].as_(),
+            ],
+            edge_mode,
+            thread_count,
+            block_rows: tile_size.height.min(image_size.height).max(1),
+            block_cols: tile_size.width.min(image_size.width).max(1),
+        }
+    }
+
+    fn fill_tile<K: Copy + Default + Debug + AsPrimitive<T>, const CN: usize>(
         &self,
         tile: &mut [T],
         tile_cols: usize,
-        signal: &[T],
-        sig_rows: usize,
-        sig_cols: usize,
+        src: &BlurImage<K>,
+        channel: usize,
         src_row_start: isize,
         src_col_start: isize,
     ) {
+        let sig_rows = src.height as usize;
+        let sig_cols = src.width as usize;
+        let src_stride = src.row_stride() as usize;
+        let signal = src.data.as_ref();
         tile.chunks_exact_mut(tile_cols)
             .enumerate()
             .for_each(|(tr, row)| {
@@ -73,7 +113,7 @@ where
                 if sr < 0 || sr >= sig_rows as isize {
                     row.iter_mut().enumerate().for_each(|(tc, pixel)| {
                         let sc = src_col_start + tc as isize;
-                        *pixel = self.sample(signal, sig_rows, sig_cols, sr, sc);
+                        *pixel = self.sample::<K, CN>(src, channel, sr, sc);
                     });
                     return;
                 }
@@ -90,16 +130,19 @@ where
                     .enumerate()
                     .for_each(|(tc, pixel)| {
                         let sc = src_col_start + tc as isize;
-                        *pixel = self.sample(signal, sig_rows, sig_cols, sr, sc);
+                        *pixel = self.sample::<K, CN>(src, channel, sr, sc);
                     });
 
-                // Center — bulk memcpy directly from signal row, no bounds check needed
+                // Center — read directly from signal row, no bounds check needed
                 if col_start_in_tile < col_end_in_tile {
                     let sig_col_start = (src_col_start + col_start_in_tile as isize) as usize;
-                    let sig_row_offset = sr as usize * sig_cols + sig_col_start;
-                    let copy_len = col_end_in_tile - col_start_in_tile;
-                    row[col_start_in_tile..col_end_in_tile]
-                        .copy_from_slice(&signal[sig_row_offset..sig_row_offset + copy_len]);
+                    let sig_row_offset = sr as usize * src_stride + sig_col_start * CN + channel;
+                    for (dst, src) in row[col_start_in_tile..col_end_in_tile]
+                        .iter_mut()
+                        .zip(signal[sig_row_offset..].iter().step_by(CN))
+                    {
+                        *dst = src.as_();
+                    }
                 }
 
                 // Right border columns — out of bounds horizontally
@@ -108,174 +151,101 @@ where
                     .enumerate()
                     .for_each(|(tc, pixel)| {
                         let sc = src_col_start + (col_end_in_tile + tc) as isize;
-                        *pixel = self.sample(signal, sig_rows, sig_cols, sr, sc);
+                        *pixel = self.sample::<K, CN>(src, channel, sr, sc);
                     });
             });
     }
 
     #[inline]
-    fn sample(&self, signal: &[T], sig_rows: usize, sig_cols: usize, r: isize, c: isize) -> T {
-        let h = sig_rows as isize;
-        let w = sig_cols as isize;
+    fn sample<K: Copy + Default + Debug + AsPrimitive<T>, const CN: usize>(
+        &self,
+        src: &BlurImage<K>,
+        channel: usize,
+        r: isize,
+        c: isize,
+    ) -> T {
+        let h = src.height as isize;
+        let w = src.width as isize;
+        let src_stride = src.row_stride() as usize;
+        let signal = src.data.as_ref();
 
         if r >= 0 && r < h && c >= 0 && c < w {
-            return *unsafe { signal.get_unchecked(r as usize * sig_cols + c as usize) };
+            return signal[r as usize * src_stride + c as usize * CN + channel].as_();
         }
 
-        let er = match self.edge_mode.vertical {
-            EdgeMode::Constant => return self.border_constant,
-            EdgeMode::Clamp => r.clamp(0, h - 1),
-            EdgeMode::Reflect => reflect_index(r, h) as isize,
-            EdgeMode::Reflect101 => reflect_index_101(r, h) as isize,
-            EdgeMode::Wrap => r.rem_euclid(h),
-        };
+        // Only out of bounds coordinate is remapped, the other one may be in bounds
+        if (self.edge_mode.vertical == EdgeMode::Constant && (r < 0 || r >= h))
+            || (self.edge_mode.horizontal == EdgeMode::Constant && (c < 0 || c >= w))
+        {
+            return self.border_constant[channel];
+        }
 
-        let ec = match self.edge_mode.horizontal {
-            EdgeMode::Constant => return self.border_constant,
-            EdgeMode::Clamp => c.clamp(0, w - 1),
-            EdgeMode::Reflect => reflect_index(c, w) as isize,
-            EdgeMode::Reflect101 => reflect_index_101(c, w) as isize,
-            EdgeMode::Wrap => c.rem_euclid(w),
-        };
+        let er = clamp_edge!(self.edge_mode.vertical, r, 0, h);
+        let ec = clamp_edge!(self.edge_mode.horizontal, c, 0, w);
 
-        signal[er as usize * sig_cols + ec as usize]
+        signal[er * src_stride + ec * CN + channel].as_()
     }
 
-    // /// full-linear convolution
-    // pub fn overlap_save_2d(
-    //     &self,
-    //     signal: &[T],
-    //     sig_rows: usize,
-    //     sig_cols: usize,
-    //     kernel: &[T],
-    //     ker_rows: usize,
-    //     ker_cols: usize,
-    // ) -> Vec<T> {
-    //     assert_eq!(signal.len(), sig_rows * sig_cols);
-    //     assert_eq!(kernel.len(), ker_rows * ker_cols);
-    //
-    //     let out_rows = sig_rows + ker_rows - 1;
-    //     let out_cols = sig_cols + ker_cols - 1;
-    //
-    //     let fft_rows = (self.block_rows + ker_rows - 1).next_power_of_two();
-    //     let fft_cols = (self.block_cols + ker_cols - 1).next_power_of_two();
-    //     let step_rows = fft_rows - (ker_rows - 1);
-    //     let step_cols = fft_cols - (ker_cols - 1);
-    //
-    //     let tile_len = fft_rows * fft_cols;
-    //     let complex_tile_len = fft_rows * ((fft_cols / 2) + 1);
-    //
-    //     let mut ker_spec = vec![Complex::new(T::zero(), T::zero()); complex_tile_len];
-    //     let mut ker_arena = vec![T::zero(); fft_rows * fft_cols];
-    //     ker_arena
-    //         .chunks_exact_mut(fft_cols)
-    //         .zip(kernel.chunks_exact(ker_cols))
-    //         .for_each(|(arena_row, ker_row)| {
-    //             arena_row[..ker_cols].copy_from_slice(ker_row);
-    //         });
-    //
-    //     let r2c = T::make_r2c_executor(fft_cols, fft_rows, 1).unwrap();
-    //     let c2r = T::make_c2r_executor(fft_cols, fft_rows, 1).unwrap();
-    //
-    //     let total_scratch = r2c.scratch_length().max(c2r.scratch_length());
-    //
-    //     r2c.execute(&ker_arena, &mut ker_spec).unwrap();
-    //
-    //     let num_row_frames = out_rows.div_ceil(step_rows);
-    //     let num_col_frames = out_cols.div_ceil(step_cols);
-    //     let total_frames = num_row_frames * num_col_frames;
-    //
-    //     let mut out = vec![T::zero(); out_rows * out_cols];
-    //     let unsafe_out = UnsafeSlice::new(&mut out);
-    //
-    //     let novtb = ThreadPool::new(self.thread_count);
-    //     parallel_range_with_context(
-    //         &novtb,
-    //         total_frames,
-    //         || TileContext {
-    //             tile: vec![T::zero(); tile_len],
-    //             complex_tile: vec![Complex::new(T::zero(), T::zero()); complex_tile_len],
-    //             scratch: vec![Complex::new(T::zero(), T::zero()); total_scratch],
-    //         },
-    //         |frame_idx, ctx| {
-    //             let fr = frame_idx / num_col_frames;
-    //             let fc = frame_idx % num_col_frames;
-    //
-    //             let out_row = fr * step_rows;
-    //             let out_col = fc * step_cols;
-    //             let src_row = out_row as isize - (ker_rows as isize - 1);
-    //             let src_col = out_col as isize - (ker_cols as isize - 1);
-    //
-    //             self.fill_tile(
-    //                 &mut ctx.tile,
-    //                 fft_cols,
-    //                 signal,
-    //                 sig_rows,
-    //                 sig_cols,
-    //                 src_row,
-    //                 src_col,
-    //             );
-    //
-    //             _ = r2c.execute_with_scratch(&ctx.tile, &mut ctx.complex_tile, &mut ctx.scratch);
-    //             T::mul_spectrum(
-    //                 &mut ctx.complex_tile,
-    //                 &ker_spec,
-    //                 (fft_cols / 2) + 1,
-    //                 fft_rows,
-    //                 (1. / (fft_rows * fft_cols) as f64).as_(),
-    //             );
-    //             _ = c2r.execute_with_scratch(
-    //                 &mut ctx.complex_tile,
-    //                 &mut ctx.tile,
-    //                 &mut ctx.scratch,
-    //             );
-    //
-    //             let valid_rows = step_rows.min(out_rows.saturating_sub(out_row));
-    //             let valid_cols = step_cols.min(out_cols.saturating_sub(out_col));
-    //
-    //             // Tiles write to non-overlapping output regions so no data races.
-    //             // Each (fr, fc) pair owns exactly out[out_row..out_row+valid_rows,
-    //             // out_col..out_col+valid_cols] — no two frames share a row+col pair.
-    //             (0..valid_rows).for_each(|vr| {
-    //                 let out_row_offset = (out_row + vr) * out_cols + out_col;
-    //                 let tile_row_offset = (ker_rows - 1 + vr) * fft_cols + (ker_cols - 1);
-    //                 let out_ptr = unsafe_out.get_ptr(out_row_offset);
-    //                 let dst_slice = unsafe { std::slice::from_raw_parts_mut(out_ptr, valid_cols) };
-    //                 dst_slice
-    //                     .copy_from_slice(&ctx.tile[tile_row_offset..tile_row_offset + valid_cols]);
-    //             });
-    //         },
-    //     );
-    //     out
-    // }
-
-    pub(crate) fn overlap_save_2d<K: Clone + Debug + Default + Copy + 'static + Send + Sync>(
+    /// Convolves every channel of `src` with real kernel.
+    pub(crate) fn convolve<K, const CN: usize>(
         &self,
+        src: &BlurImage<K>,
         dst: &mut BlurImageMut<K>,
-        signal: &[T],
-        sig_rows: usize,
-        sig_cols: usize,
         kernel: &[T],
-        ker_rows: usize,
-        ker_cols: usize,
-    ) where
+        kernel_shape: KernelShape,
+    ) -> Result<(), BlurError>
+    where
+        K: Copy + Default + Send + Sync + Debug + AsPrimitive<T>,
         T: ToStorage<K>,
     {
-        assert_eq!(signal.len(), sig_rows * sig_cols);
-        assert_eq!(kernel.len(), ker_rows * ker_cols);
-
-        let out_rows = sig_rows + ker_rows - 1;
-        let out_cols = sig_cols + ker_cols - 1;
+        check_kernel_size(kernel, kernel_shape)?;
+        self.overlap_save_2d::<K, CN>(src, dst, kernel, kernel_shape)
+    }
 
-        let fft_rows = fft_next_good_size_real(self.block_rows + ker_rows - 1);
-        let fft_cols = fft_next_good_size_real(self.block_cols + ker_cols - 1);
-        let step_rows = fft_rows - (ker_rows - 1);
-        let step_cols = fft_cols - (ker_cols - 1);
+    /// Convolves every channel of `src` with complex kernel, real part of the result is stored.
+    pub(crate) fn convolve_complex<K, const CN: usize>(
+        &self,
+        src: &BlurImage<K>,
+        dst: &mut BlurImageMut<K>,
+        kernel: &[Complex<T>],
+        kernel_shape: KernelShape,
+    ) -> Result<(), BlurError>
+    where
+        K: Copy + Default + Send + Sync + Debug + AsPrimitive<T>,
+        T: ToStorage<K>,
+    {
+        check_kernel_size(kernel, kernel_shape)?;
+        if kernel.iter().all(|x| x.im == T::zero()) {
+            // Real kernel is cheaper to convolve with R2C transform
+            let real_kernel = kernel.iter().map(|x| x.re).collect::<Vec<T>>();
+            return self.overlap_save_2d::<K, CN>(src, dst, &real_kernel, kernel_shape);
+        }
+        self.overlap_save_2d_complex::<K, CN>(src, dst, kernel, kernel_shape)
+    }
 
-        let tile_len = fft_rows * fft_cols;
-        let complex_tile_len = fft_rows * ((fft_cols / 2) + 1);
+    fn overlap_save_2d<K, const CN: usize>(
+        &self,
+        src: &BlurImage<K>,
+        dst: &mut BlurImageMut<K>,
+        kernel: &[T],
+        kernel_shape: KernelShape,
+    ) -> Result<(), BlurError>
+    where
+        K: Copy + Default + Send + Sync + Debug + AsPrimitive<T>,
+        T: ToStorage<K>,
+    {
+        let ker_rows = kernel_shape.height;
+        let ker_cols = kernel_shape.width;
+        let geometry = TileGeometry {
+            fft_rows: fft_next_good_size_real(self.block_rows + ker_rows - 1),
+            fft_cols: fft_next_good_size_real(self.block_cols + ker_cols - 1),
+            ker_rows,
+            ker_cols,
+        };
+        let (fft_rows, fft_cols) = (geometry.fft_rows, geometry.fft_cols);
+        let complex_cols = (fft_cols / 2) + 1;
 
-        let mut ker_spec = vec![Complex::new(T::zero(), T::zero()); complex_tile_len];
+        let mut ker_spec = vec![Complex::new(T::zero(), T::zero()); fft_rows * complex_cols];
         let mut ker_arena = vec![T::zero(); fft_rows * fft_cols];
         ker_arena
             .chunks_exact_mut(fft_cols)
@@ -284,107 +254,390 @@ where
                 arena_row[..ker_cols].copy_from_slice(ker_row);
             });
 
-        let r2c = T::make_r2c_executor(fft_cols, fft_rows, 1).unwrap();
-        let c2r = T::make_c2r_executor(fft_cols, fft_rows, 1).unwrap();
+        let r2c = T::make_r2c_executor(fft_cols, fft_rows, 1)?;
+        let c2r = T::make_c2r_executor(fft_cols, fft_rows, 1)?;
 
-        let half_r = ker_rows / 2; // row offset into full conv output
-        let half_c = ker_cols / 2; // col offset into full conv output
+        r2c.execute(&ker_arena, &mut ker_spec)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+        let norm_factor: T = (1. / (fft_rows * fft_cols) as f64).as_();
+
+        self.overlap_save_exec::<K, CN>(
+            src,
+            dst,
+            &geometry,
+            ker_spec.len(),
+            r2c.scratch_length().max(c2r.scratch_length()),
+            |ctx| {
+                r2c.execute_with_scratch(&ctx.tile, &mut ctx.complex_tile, &mut ctx.scratch)
+                    .map_err(|x| BlurError::FftError(x.to_string()))?;
+                T::mul_spectrum(
+                    &mut ctx.complex_tile,
+                    &ker_spec,
+                    complex_cols,
+                    fft_rows,
+                    norm_factor,
+                );
+                c2r.execute_with_scratch(&mut ctx.complex_tile, &mut ctx.tile, &mut ctx.scratch)
+                    .map_err(|x| BlurError::FftError(x.to_string()))
+            },
+        )
+    }
+
+    fn overlap_save_2d_complex<K, const CN: usize>(
+        &self,
+        src: &BlurImage<K>,
+        dst: &mut BlurImageMut<K>,
+        kernel: &[Complex<T>],
+        kernel_shape: KernelShape,
+    ) -> Result<(), BlurError>
+    where
+        K: Copy + Default + Send + Sync + Debug + AsPrimitive<T>,
+        T: ToStorage<K>,
+    {
+        let ker_rows = kernel_shape.height;
+        let ker_cols = kernel_shape.width;
+        let geometry = TileGeometry {
+            fft_rows: fft_next_good_size(self.block_rows + ker_rows - 1),
+            fft_cols: fft_next_good_size(self.block_cols + ker_cols - 1),
+            ker_rows,
+            ker_cols,
+        };
+        let (fft_rows, fft_cols) = (geometry.fft_rows, geometry.fft_cols);
+
+        let mut ker_spec = vec![Complex::new(T::zero(), T::zero()); fft_rows * fft_cols];
+        ker_spec
+            .chunks_exact_mut(fft_cols)
+            .zip(kernel.chunks_exact(ker_cols))
+            .for_each(|(arena_row, ker_row)| {
+                arena_row[..ker_cols].copy_from_slice(ker_row);
+            });
+
+        let forward = T::make_fft(fft_cols, fft_rows, FftDirection::Forward, 1)?;
+        let inverse = T::make_fft(fft_cols, fft_rows, FftDirection::Inverse, 1)?;
+
+        forward
+            .execute(&mut ker_spec)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+        let norm_factor: T = (1. / (fft_rows * fft_cols) as f64).as_();
+
+        self.overlap_save_exec::<K, CN>(
+            src,
+            dst,
+            &geometry,
+            ker_spec.len(),
+            forward.scratch_length().max(inverse.scratch_length()),
+            |ctx| {
+                for (dst, &src) in ctx.complex_tile.iter_mut().zip(ctx.tile.iter()) {
+                    *dst = Complex::new(src, T::zero());
+                }
+                forward
+                    .execute_with_scratch(&mut ctx.complex_tile, &mut ctx.scratch)
+                    .map_err(|x| BlurError::FftError(x.to_string()))?;
+                T::mul_spectrum(
+                    &mut ctx.complex_tile,
+                    &ker_spec,
+                    fft_cols,
+                    fft_rows,
+                    norm_factor,
+                );
+                inverse
+                    .execute_with_scratch(&mut ctx.complex_tile, &mut ctx.scratch)
+                    .map_err(|x| BlurError::FftError(x.to_string()))?;
+                for (dst, src) in ctx.tile.iter_mut().zip(ctx.complex_tile.iter()) {
+                    *dst = src.re;
+                }
+                Ok(())
+            },
+        )
+    }
 
-        let total_scratch = r2c.scratch_length().max(c2r.scratch_length());
+    fn overlap_save_exec<K, const CN: usize>(
+        &self,
+        src: &BlurImage<K>,
+        dst: &mut BlurImageMut<K>,
+        geometry: &TileGeometry,
+        complex_tile_len: usize,
+        scratch_len: usize,
+        convolve_tile: impl Fn(&mut TileContext<T>) -> Result<(), BlurError> + Send + Sync,
+    ) -> Result<(), BlurError>
+    where
+        K: Copy + Default + Send + Sync + Debug + AsPrimitive<T>,
+        T: ToStorage<K>,
+    {
+        let sig_rows = src.height as usize;
+        let sig_cols = src.width as usize;
+        let TileGeometry {
+            fft_rows,
+            fft_cols,
+            ker_rows,
+            ker_cols,
+        } = *geometry;
 
-        r2c.execute(&ker_arena, &mut ker_spec).unwrap();
+        let out_rows = sig_rows + ker_rows - 1;
+        let out_cols = sig_cols + ker_cols - 1;
 
-        let num_row_frames = out_rows.div_ceil(step_rows);
-        let num_col_frames = out_cols.div_ceil(step_cols);
+        let step_rows = fft_rows - (ker_rows - 1);
+        let step_cols = fft_cols - (ker_cols - 1);
+
+        let half_r = ker_rows / 2; // row offset into full conv output
+        let half_c = ker_cols / 2; // col offset into full conv output
+
+        // Only frames covering [half, half + size) of full convolution output are computed
+        let first_row_frame = half_r / step_rows;
+        let first_col_frame = half_c / step_cols;
+        let num_row_frames = out_rows.min(half_r + sig_rows).div_ceil(step_rows) - first_row_frame;
+        let num_col_frames = out_cols.min(half_c + sig_cols).div_ceil(step_cols) - first_col_frame;
         let total_frames = num_row_frames * num_col_frames;
 
         let dst_stride = dst.row_stride() as usize;
         let unsafe_dst = UnsafeSlice::new(dst.data.borrow_mut());
 
-        let novtb = ThreadPool::new(self.thread_count);
+        // First failed transform is reported, remaining frames are skipped
+        let error = Mutex::new(None);
+
+        let pool = ThreadPool::new(self.thread_count);
         parallel_range_with_context(
-            &novtb,
+            &pool,
             total_frames,
             || TileContext {
-                tile: vec![T::zero(); tile_len],
+                tile: vec![T::zero(); fft_rows * fft_cols],
                 complex_tile: vec![Complex::new(T::zero(), T::zero()); complex_tile_len],
-                scratch: vec![Complex::new(T::zero(), T::zero()); total_scratch],
+                scratch: vec![Complex::new(T::zero(), T::zero()); scratch_len],
             },
             |frame_idx, ctx| {
-                let fr = frame_idx / num_col_frames;
-                let fc = frame_idx % num_col_frames;
+                if error.lock().unwrap().is_some() {
+                    return;
+                }
+
+                let fr = frame_idx / num_col_frames + first_row_frame;
+                let fc = frame_idx % num_col_frames + first_col_frame;
 
                 let out_row = fr * step_rows;
                 let out_col = fc * step_cols;
                 let src_row = out_row as isize - (ker_rows as isize - 1);
                 let src_col = out_col as isize - (ker_cols as isize - 1);
 
-                self.fill_tile(
-                    &mut ctx.tile,
-                    fft_cols,
-                    signal,
-                    sig_rows,
-                    sig_cols,
-                    src_row,
-                    src_col,
-                );
-
-                _ = r2c.execute_with_scratch(&ctx.tile, &mut ctx.complex_tile, &mut ctx.scratch);
-                T::mul_spectrum(
-                    &mut ctx.complex_tile,
-                    &ker_spec,
-                    (fft_cols / 2) + 1,
-                    fft_rows,
-                    (1. / (fft_rows * fft_cols) as f64).as_(),
-                );
-                _ = c2r.execute_with_scratch(
-                    &mut ctx.complex_tile,
-                    &mut ctx.tile,
-                    &mut ctx.scratch,
-                );
-
                 let valid_rows = step_rows.min(out_rows.saturating_sub(out_row));
                 let valid_cols = step_cols.min(out_cols.saturating_sub(out_col));
 
-                // Tiles write to non-overlapping output regions so no data races.
-                // Each (fr, fc) pair owns exactly out[out_row..out_row+valid_rows,
-                // out_col..out_col+valid_cols] — no two frames share a row+col pair.
-                (0..valid_rows).for_each(|vr| {
-                    let dst_row = (out_row + vr) as isize - half_r as isize;
-                    if dst_row < 0 || dst_row >= sig_rows as isize {
-                        return; // this tile row falls in the ramp-up/ramp-down border
-                    }
-
-                    let tile_row_offset = (ker_rows - 1 + vr) * fft_cols + (ker_cols - 1);
+                // find the valid column range that lands inside dst
+                let dst_col_start = out_col as isize - half_c as isize;
+                let valid_start = (-dst_col_start).max(0) as usize; // skip left ramp
+                let valid_end = (sig_cols as isize - dst_col_start)
+                    .min(valid_cols as isize)
+                    .max(0) as usize;
 
-                    // find the valid column range that lands inside dst
-                    let dst_col_start = out_col as isize - half_c as isize;
-
-                    // clamp to [0, sig_cols)
-                    let valid_start = (-dst_col_start).max(0) as usize; // skip left ramp
-                    let valid_end = (sig_cols as isize - dst_col_start)
-                        .min(valid_cols as isize)
-                        .max(0) as usize;
+                if valid_start >= valid_end {
+                    return;
+                }
 
-                    if valid_start >= valid_end {
+                for channel in 0..CN {
+                    self.fill_tile::<K, CN>(
+                        &mut ctx.tile,
+                        fft_cols,
+                        src,
+                        channel,
+                        src_row,
+                        src_col,
+                    );
+
+                    if let Err(err) = convolve_tile(ctx) {
+                        error.lock().unwrap().get_or_insert(err);
                         return;
                     }
 
-                    let dst_offset = dst_row as usize * dst_stride
-                        + (dst_col_start + valid_start as isize) as usize;
-                    let tile_offset = tile_row_offset + valid_start;
-                    let copy_len = valid_end - valid_start;
+                    // Tiles write to non-overlapping output regions so no data races.
+                    // Each (fr, fc) pair owns exactly out[out_row..out_row+valid_rows,
+                    // out_col..out_col+valid_cols] — no two frames share a row+col pair.
+                    (0..valid_rows).for_each(|vr| {
+                        let dst_row = (out_row + vr) as isize - half_r as isize;
+                        if dst_row < 0 || dst_row >= sig_rows as isize {
+                            return; // this tile row falls in the ramp-up/ramp-down border
+                        }
+
+                        let tile_row_offset = (ker_rows - 1 + vr) * fft_cols + (ker_cols - 1);
+                        let dst_offset = dst_row as usize * dst_stride
+                            + (dst_col_start + valid_start as isize) as usize * CN
+                            + channel;
+
+                        for (i, s) in ctx.tile
+                            [tile_row_offset + valid_start..tile_row_offset + valid_end]
+                            .iter()
+                            .enumerate()
+                        {
+                            unsafe {
+                                unsafe_dst.write(dst_offset + i * CN, s.to_());
+                            }
+                        }
+                    });
+                }
+            },
+        );
+        match error.into_inner().unwrap() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
 
-                    let dst_slice = unsafe {
-                        std::slice::from_raw_parts_mut(unsafe_dst.get_ptr(dst_offset), copy_len)
-                    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FastBlurChannels, FftConvolutionMode, ThreadingPolicy, filter_2d_fft_complex,
+        filter_2d_rgb_fft,
+    };
+    use std::num::NonZeroUsize;
+
+    fn make_noise(len: usize, seed: &mut u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (*seed >> 24) as u8
+            })
+            .collect()
+    }
 
-                    dst_slice
-                        .iter_mut()
-                        .zip(ctx.tile[tile_offset..tile_offset + copy_len].iter())
-                        .for_each(|(d, s)| *d = s.to_());
-                });
-            },
+    fn assert_close(a: &BlurImageMut<u8>, b: &BlurImageMut<u8>) {
+        for (i, (&a, &b)) in a
+            .data
+            .borrow()
+            .iter()
+            .zip(b.data.borrow().iter())
+            .enumerate()
+        {
+            let diff = (a as i32 - b as i32).abs();
+            assert!(
+                diff <= 1,
+                "Diff expected to be <= 1 but it was {diff} at {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fft_tiled_matches_whole_rgb() {
+        let width = 83usize;
+        let height = 61usize;
+        let mut seed = 0x2545f491u32;
+        let src = make_noise(width * height * 3, &mut seed);
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
         );
+        let kernel_shape = KernelShape::new(13, 8);
+        let mut kernel = make_noise(kernel_shape.width * kernel_shape.height, &mut seed)
+            .iter()
+            .map(|&x| x as f32)
+            .collect::<Vec<f32>>();
+        let kernel_sum = kernel.iter().sum::<f32>();
+        kernel.iter_mut().for_each(|x| *x /= kernel_sum);
+
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+            EdgeMode::Constant,
+        ] {
+            let border_constant = Scalar::new(17., 200., 90., 0.);
+            let mut reference = BlurImageMut::default();
+            filter_2d_rgb_fft::<u8, f32>(
+                &src_image,
+                &mut reference,
+                &kernel,
+                kernel_shape,
+                edge_mode.as_2d(),
+                border_constant,
+                ThreadingPolicy::Single,
+                FftConvolutionMode::Whole,
+            )
+            .unwrap();
+            for (tile_width, tile_height) in [(1, 1), (7, 30), (64, 64), (500, 500)] {
+                let mut dst = BlurImageMut::default();
+                filter_2d_rgb_fft::<u8, f32>(
+                    &src_image,
+                    &mut dst,
+                    &kernel,
+                    kernel_shape,
+                    edge_mode.as_2d(),
+                    border_constant,
+                    ThreadingPolicy::Fixed(NonZeroUsize::new(3).unwrap()),
+                    FftConvolutionMode::Tiled {
+                        tile_width,
+                        tile_height,
+                    },
+                )
+                .unwrap();
+                assert_close(&dst, &reference);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_tiled_matches_whole_complex() {
+        let width = 71usize;
+        let height = 45usize;
+        let mut seed = 0x1b873593u32;
+        let src = make_noise(width * height, &mut seed);
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let kernel_shape = KernelShape::new(9, 11);
+        let kernel = make_noise(kernel_shape.width * kernel_shape.height * 2, &mut seed)
+            .chunks_exact(2)
+            .map(|x| Complex::new(x[0] as f32 / 12000., x[1] as f32 / 12000.))
+            .collect::<Vec<Complex<f32>>>();
+
+        let mut reference = BlurImageMut::default();
+        filter_2d_fft_complex::<u8, f32>(
+            &src_image,
+            &mut reference,
+            &kernel,
+            kernel_shape,
+            EdgeMode::Reflect.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+            FftConvolutionMode::Whole,
+        )
+        .unwrap();
+        let mut dst = BlurImageMut::default();
+        filter_2d_fft_complex::<u8, f32>(
+            &src_image,
+            &mut dst,
+            &kernel,
+            kernel_shape,
+            EdgeMode::Reflect.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Fixed(NonZeroUsize::new(2).unwrap()),
+            FftConvolutionMode::Tiled {
+                tile_width: 16,
+                tile_height: 24,
+            },
+        )
+        .unwrap();
+        assert_close(&dst, &reference);
+
+        assert!(matches!(
+            filter_2d_fft_complex::<u8, f32>(
+                &src_image,
+                &mut dst,
+                &kernel,
+                kernel_shape,
+                EdgeMode::Reflect.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+                FftConvolutionMode::Tiled {
+                    tile_width: 0,
+                    tile_height: 24,
+                },
+            ),
+            Err(BlurError::InvalidParameter {
+                name: "tile_width",
+                ..
+            })
+        ));
     }
 }
@@ -0,0 +1,426 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter2d::fft_plan::FftConvolutionPlan;
+use crate::to_storage::ToStorage;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode, EdgeMode2D, FftNumber, KernelShape,
+    MismatchedSize, Scalar, ThreadingPolicy,
+};
+use num_complex::Complex;
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+/// Checks PSF shape and makes it sum to one, so deconvolution preserves brightness.
+fn normalized_psf<F: FftNumber>(
+    psf: &[F],
+    psf_shape: KernelShape,
+) -> Result<Vec<Complex<F>>, BlurError>
+where
+    f64: AsPrimitive<F>,
+{
+    if psf_shape.width * psf_shape.height != psf.len() {
+        return Err(BlurError::KernelSizeMismatch(MismatchedSize {
+            expected: psf_shape.width * psf_shape.height,
+            received: psf.len(),
+        }));
+    }
+    let sum = psf.iter().fold(F::zero(), |acc, &x| acc + x);
+    if sum == F::zero() {
+        return Err(BlurError::InvalidParameter {
+            name: "psf",
+            value: 0.,
+        });
+    }
+    Ok(psf
+        .iter()
+        .map(|&x| Complex::new(x / sum, F::zero()))
+        .collect())
+}
+
+/// Wiener deconvolution, restores an image blurred by known point spread function.
+///
+/// Image spectrum is multiplied by `conj(H) / (|H|^2 + noise_to_signal)`,
+/// where `H` is spectrum of the PSF. PSF is normalized to sum to one.
+/// Image is padded with linear ramps between opposite edges, so FFT boundaries
+/// do not ring into the image.
+/// This method supports all channel layouts, every channel is restored separately.
+///
+/// # Arguments
+///
+/// * `src`: Blurred image.
+/// * `dst`: Destination image.
+/// * `psf`: Point spread function which blurred the image, e.g. [crate::generate_motion_kernel] or [crate::lens_kernel].
+/// * `psf_shape`: PSF size, see [KernelShape] for more info.
+/// * `noise_to_signal`: Noise to signal power ratio, 0 gives inverse filter which amplifies noise,
+///   0.001-0.05 is a reasonable range for 8-bit images.
+/// * `threading_policy`: Threading policy according to [ThreadingPolicy].
+/// * `F`: Intermediate internal type for fft, only `f32` and `f64` is supported.
+pub fn wiener_deconvolution<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    psf: &[F],
+    psf_shape: KernelShape,
+    noise_to_signal: f64,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<F> + Debug,
+    F: FftNumber + ToStorage<T>,
+    f64: AsPrimitive<T> + AsPrimitive<F>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+
+    if !noise_to_signal.is_finite() || noise_to_signal < 0. {
+        return Err(BlurError::InvalidParameter {
+            name: "noise_to_signal",
+            value: noise_to_signal,
+        });
+    }
+
+    let psf = normalized_psf(psf, psf_shape)?;
+
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let mut plan = FftConvolutionPlan::<T, F>::new_with_thread_count(
+        src.size(),
+        src.channels,
+        &psf,
+        psf_shape,
+        EdgeMode::Clamp.as_2d(),
+        Scalar::default(),
+        thread_count,
+    )?;
+    plan.set_tapered_padding(true);
+
+    let nsr: F = noise_to_signal.as_();
+    plan.map_kernel_spectrum(|h| {
+        let power = h.re * h.re + h.im * h.im + nsr;
+        if power == F::zero() {
+            return Complex::new(F::zero(), F::zero());
+        }
+        Complex::new(h.re / power, (F::zero() - h.im) / power)
+    });
+
+    plan.execute(src, dst)
+}
+
+/// Richardson-Lucy deconvolution, iteratively restores an image blurred by known point spread function.
+///
+/// Each iteration convolves current estimate with the PSF, and corrects estimate by
+/// the ratio of the blurred image to that convolution correlated with the PSF.
+/// More iterations restore more details and amplify more noise, 10-50 is a typical range.
+/// PSF is normalized to sum to one.
+/// This method supports all channel layouts, every channel is restored separately.
+///
+/// # Arguments
+///
+/// * `src`: Blurred image.
+/// * `dst`: Destination image.
+/// * `psf`: Point spread function which blurred the image, e.g. [crate::generate_motion_kernel] or [crate::lens_kernel].
+/// * `psf_shape`: PSF size, see [KernelShape] for more info.
+/// * `iterations`: Iterations count, 0 copies the source.
+/// * `edge_modes`: See [crate::EdgeMode] and [EdgeMode2D] for more info, [crate::EdgeMode::Constant] pads with zeros.
+/// * `threading_policy`: Threading policy according to [ThreadingPolicy].
+/// * `F`: Intermediate internal type for fft, only `f32` and `f64` is supported.
+pub fn richardson_lucy_deconvolution<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    psf: &[F],
+    psf_shape: KernelShape,
+    iterations: usize,
+    edge_modes: EdgeMode2D,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<F> + Debug,
+    F: FftNumber + ToStorage<T> + ToStorage<F> + AsPrimitive<F>,
+    f64: AsPrimitive<T> + AsPrimitive<F>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+
+    let psf = normalized_psf(psf, psf_shape)?;
+
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
+    let row_length = width as usize * channels.channels();
+
+    let mut observed: Vec<F> = Vec::with_capacity(row_length * height as usize);
+    for row in src
+        .data
+        .chunks(src.row_stride() as usize)
+        .take(height as usize)
+    {
+        observed.extend(row[..row_length].iter().map(|&x| x.as_()));
+    }
+
+    let thread_count = threading_policy.thread_count(width, height);
+    let mut plan = FftConvolutionPlan::<F, F>::new_with_thread_count(
+        src.size(),
+        channels,
+        &psf,
+        psf_shape,
+        edge_modes,
+        Scalar::default(),
+        thread_count,
+    )?;
+    // Correlation with the PSF is convolution with conjugated spectrum
+    let mut adjoint_plan = plan.clone();
+    adjoint_plan.map_kernel_spectrum(|h| Complex::new(h.re, F::zero() - h.im));
+
+    let mut estimate = observed.clone();
+    let mut ratio = vec![F::default(); observed.len()];
+    let mut correction = vec![F::default(); observed.len()];
+
+    let eps: F = 1e-6f64.as_();
+
+    for _ in 0..iterations {
+        plan.execute(
+            &BlurImage::borrow(&estimate, width, height, channels),
+            &mut BlurImageMut::borrow(&mut ratio, width, height, channels),
+        )?;
+        for (ratio, &observed) in ratio.iter_mut().zip(observed.iter()) {
+            let blurred = if *ratio > eps { *ratio } else { eps };
+            *ratio = observed / blurred;
+        }
+        adjoint_plan.execute(
+            &BlurImage::borrow(&ratio, width, height, channels),
+            &mut BlurImageMut::borrow(&mut correction, width, height, channels),
+        )?;
+        for (estimate, &correction) in estimate.iter_mut().zip(correction.iter()) {
+            let v = *estimate * correction;
+            *estimate = if v > F::zero() { v } else { F::zero() };
+        }
+    }
+
+    let dst_stride = dst.row_stride() as usize;
+    for (dst, src) in dst
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(estimate.chunks_exact(row_length))
+    {
+        for (dst, &src) in dst[..row_length].iter_mut().zip(src.iter()) {
+            *dst = src.to_();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FastBlurChannels, FftConvolutionMode, filter_2d_rgb_fft};
+
+    fn make_pattern(width: usize, height: usize) -> Vec<u8> {
+        let mut data = vec![0u8; width * height * 3];
+        for (y, row) in data.chunks_exact_mut(width * 3).enumerate() {
+            for (x, px) in row.chunks_exact_mut(3).enumerate() {
+                let cell = ((x / 6) + (y / 9)) % 2;
+                px[0] = if cell == 0 { 40 } else { 210 };
+                px[1] = if (x / 11) % 2 == 0 { 70 } else { 180 };
+                px[2] = ((x * 7 + y * 3) % 160 + 40) as u8;
+            }
+        }
+        data
+    }
+
+    fn interior_error<V: AsPrimitive<f64>>(
+        a: &[V],
+        b: &[V],
+        width: usize,
+        height: usize,
+        border: usize,
+    ) -> f64 {
+        let mut sum = 0f64;
+        let mut count = 0usize;
+        for y in border..height - border {
+            for x in border * 3..(width - border) * 3 {
+                let i = y * width * 3 + x;
+                sum += (a[i].as_() - b[i].as_()).abs();
+                count += 1;
+            }
+        }
+        sum / count as f64
+    }
+
+    #[test]
+    fn test_deconvolution_restores_motion_blur() {
+        let width = 96usize;
+        let height = 80usize;
+        let sharp = make_pattern(width, height);
+        let psf_size = 9usize;
+        let psf = crate::generate_motion_kernel(psf_size, 25.).unwrap();
+        let psf_shape = KernelShape::new(psf_size, psf_size);
+        let edge_modes = EdgeMode::Reflect101.as_2d();
+
+        let sharp_image = BlurImage::borrow(
+            &sharp,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut blurred = BlurImageMut::default();
+        filter_2d_rgb_fft::<u8, f32>(
+            &sharp_image,
+            &mut blurred,
+            &psf,
+            psf_shape,
+            edge_modes,
+            Scalar::default(),
+            ThreadingPolicy::Single,
+            FftConvolutionMode::Whole,
+        )
+        .unwrap();
+        let blurred_error = interior_error(blurred.data.borrow(), &sharp, width, height, psf_size);
+
+        let blurred_image = blurred.to_immutable_ref();
+
+        let mut wiener = BlurImageMut::default();
+        wiener_deconvolution::<u8, f32>(
+            &blurred_image,
+            &mut wiener,
+            &psf,
+            psf_shape,
+            0.002,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let wiener_error = interior_error(wiener.data.borrow(), &sharp, width, height, psf_size);
+        assert!(
+            wiener_error < blurred_error * 0.7,
+            "Wiener error {wiener_error} expected to be much less than blurred {blurred_error}"
+        );
+
+        let mut lucy = BlurImageMut::default();
+        richardson_lucy_deconvolution::<u8, f32>(
+            &blurred_image,
+            &mut lucy,
+            &psf,
+            psf_shape,
+            30,
+            edge_modes,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let lucy_error = interior_error(lucy.data.borrow(), &sharp, width, height, psf_size);
+        assert!(
+            lucy_error < blurred_error * 0.8,
+            "Richardson-Lucy error {lucy_error} expected to be much less than blurred {blurred_error}"
+        );
+
+        assert!(matches!(
+            wiener_deconvolution::<u8, f32>(
+                &blurred_image,
+                &mut wiener,
+                &psf,
+                psf_shape,
+                -1.,
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::InvalidParameter {
+                name: "noise_to_signal",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_wiener_inverts_defocus_exactly() {
+        let width = 64usize;
+        let height = 56usize;
+        let border = 16usize;
+        // Content is kept away from edges, so padding agrees with the blur model
+        let sharp = make_pattern(width, height)
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                let y = i / (width * 3);
+                let x = (i % (width * 3)) / 3;
+                if y < border || y >= height - border || x < border || x >= width - border {
+                    100.
+                } else {
+                    v as f64
+                }
+            })
+            .collect::<Vec<f64>>();
+        let psf_size = 7usize;
+        let psf = (0..psf_size * psf_size)
+            .map(|i| {
+                let x = (i % psf_size) as f64 - 3.;
+                let y = (i / psf_size) as f64 - 3.;
+                (-(x * x + y * y) / (2. * 1.3 * 1.3)).exp()
+            })
+            .collect::<Vec<f64>>();
+        let psf_shape = KernelShape::new(psf_size, psf_size);
+
+        let sharp_image = BlurImage::borrow(
+            &sharp,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut plan = FftConvolutionPlan::<f64, f64>::new(
+            sharp_image.size(),
+            FastBlurChannels::Channels3,
+            &normalized_psf(&psf, psf_shape)
+                .unwrap()
+                .iter()
+                .map(|x| x.re)
+                .collect::<Vec<f64>>(),
+            psf_shape,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let mut blurred = BlurImageMut::default();
+        plan.execute(&sharp_image, &mut blurred).unwrap();
+
+        let mut restored = BlurImageMut::default();
+        wiener_deconvolution::<f64, f64>(
+            &blurred.to_immutable_ref(),
+            &mut restored,
+            &psf,
+            psf_shape,
+            0.,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let error = interior_error(restored.data.borrow(), &sharp, width, height, 4);
+        assert!(
+            error < 0.01,
+            "Restored error expected to be < 0.01 but it was {error}"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, ImageSize, KernelShape};
+
+/// Strategy of FFT convolution.
+///
+/// Whole image FFT pads an image to a good FFT size and keeps complex planes
+/// of the full padded size, which for very large images takes gigabytes of memory.
+/// Tiled overlap-save transforms an image tile by tile, memory is bounded by tile size
+/// and thread count, and tiles are processed in parallel according to [crate::ThreadingPolicy].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum FftConvolutionMode {
+    /// Chooses tiling from image and kernel size.
+    /// Images which padded plane exceeds 4096x4096 are always tiled.
+    #[default]
+    Auto,
+    /// Whole image is padded and transformed at once.
+    Whole,
+    /// Image is convolved by overlap-save in tiles producing `tile_width` x `tile_height`
+    /// pixels each, every tile FFT additionally covers kernel size - 1 of overlap.
+    Tiled {
+        tile_width: usize,
+        tile_height: usize,
+    },
+}
+
+const AUTO_TILED_AREA: usize = 4096 * 4096;
+
+fn auto_tile_side(kernel_side: usize) -> usize {
+    if kernel_side >= 200 {
+        kernel_side.next_power_of_two().max(512)
+    } else if kernel_side > 95 {
+        512
+    } else if kernel_side > 66 {
+        256
+    } else {
+        128
+    }
+}
+
+impl FftConvolutionMode {
+    /// Returns tile size if convolution should be tiled.
+    ///
+    /// `tile_small_kernels` also tiles kernels smaller than 200x200 in auto mode,
+    /// where overlap-save is faster than whole image transform.
+    pub(crate) fn tile_size(
+        self,
+        image_size: ImageSize,
+        kernel_shape: KernelShape,
+        tile_small_kernels: bool,
+    ) -> Result<Option<ImageSize>, BlurError> {
+        match self {
+            FftConvolutionMode::Auto => {
+                let small_kernel = kernel_shape.width < 200 && kernel_shape.height < 200;
+                let padded_area = (image_size.width + kernel_shape.width)
+                    .saturating_mul(image_size.height + kernel_shape.height);
+                if (tile_small_kernels && small_kernel) || padded_area > AUTO_TILED_AREA {
+                    Ok(Some(ImageSize::new(
+                        auto_tile_side(kernel_shape.width),
+                        auto_tile_side(kernel_shape.height),
+                    )))
+                } else {
+                    Ok(None)
+                }
+            }
+            FftConvolutionMode::Whole => Ok(None),
+            FftConvolutionMode::Tiled {
+                tile_width,
+                tile_height,
+            } => {
+                if tile_width == 0 {
+                    return Err(BlurError::InvalidParameter {
+                        name: "tile_width",
+                        value: 0.,
+                    });
+                }
+                if tile_height == 0 {
+                    return Err(BlurError::InvalidParameter {
+                        name: "tile_height",
+                        value: 0.,
+                    });
+                }
+                Ok(Some(ImageSize::new(tile_width, tile_height)))
+            }
+        }
+    }
+}
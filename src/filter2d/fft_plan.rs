@@ -0,0 +1,584 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::make_edge_lookup;
+use crate::fast_divide::{DividerIsize, RemEuclidFast};
+use crate::filter1d::ArenaPads;
+use crate::filter2d::fft_utils::fft_next_good_size;
+use crate::filter2d::scan_se_2d::{check_kernel_size, scan_se_2d_complex};
+use crate::to_storage::ToStorage;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, FftNumber, ImageSize,
+    KernelShape, Scalar, ThreadingPolicy,
+};
+use num_complex::Complex;
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use zaft::{FftDirection, TwoDimensionalFftExecutor};
+
+/// Reusable FFT convolution for a fixed image size and kernel.
+///
+/// Kernel is padded and transformed once when plan is created, forward and inverse
+/// FFT instances, border lookups, spectrum and scratch buffers are kept by the plan.
+/// Executing the plan repeatedly, e.g. applying the same bokeh kernel to every frame of a video,
+/// costs only the image transforms and spectrum multiplication.
+///
+/// Every channel is convolved with the same kernel, result matches [crate::filter_2d_fft]
+/// applied to each channel separately.
+///
+/// # Examples
+///
+/// ```
+/// use libblur::{BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, FftConvolutionPlan,
+///     ImageSize, KernelShape, Scalar, ThreadingPolicy};
+/// let frame = vec![0u8; 64 * 48 * 3];
+/// let src = BlurImage::borrow(&frame, 64, 48, FastBlurChannels::Channels3);
+/// let mut dst = BlurImageMut::alloc(64, 48, FastBlurChannels::Channels3);
+/// let kernel = vec![1f32 / 81.; 81];
+/// let mut plan = FftConvolutionPlan::<u8, f32>::new(
+///     ImageSize::new(64, 48),
+///     FastBlurChannels::Channels3,
+///     &kernel,
+///     KernelShape::new(9, 9),
+///     EdgeMode::Clamp.as_2d(),
+///     Scalar::default(),
+///     ThreadingPolicy::Single,
+/// )
+/// .unwrap();
+/// for _ in 0..3 {
+///     plan.execute(&src, &mut dst).unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct FftConvolutionPlan<T, FftIntermediate> {
+    size: ImageSize,
+    channels: FastBlurChannels,
+    border_constant: Scalar,
+    fft_width: usize,
+    fft_height: usize,
+    pads: ArenaPads,
+    x_lookup: Vec<Option<usize>>,
+    y_lookup: Vec<Option<usize>>,
+    is_identity: bool,
+    tapered_padding: bool,
+    norm_factor: FftIntermediate,
+    kernel_spectrum: Vec<Complex<FftIntermediate>>,
+    spectrum: Vec<Complex<FftIntermediate>>,
+    scratch: Vec<Complex<FftIntermediate>>,
+    fft_forward: Arc<dyn TwoDimensionalFftExecutor<FftIntermediate> + Send + Sync>,
+    fft_inverse: Arc<dyn TwoDimensionalFftExecutor<FftIntermediate> + Send + Sync>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, FftIntermediate> FftConvolutionPlan<T, FftIntermediate>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<FftIntermediate> + Debug,
+    FftIntermediate: FftNumber + ToStorage<T>,
+    f64: AsPrimitive<T> + AsPrimitive<FftIntermediate>,
+{
+    /// Creates FFT convolution plan with real kernel.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the images plan will be executed on.
+    /// * `channels` - Channels layout, see [FastBlurChannels].
+    /// * `kernel` - Kernel.
+    /// * `kernel_shape` - Kernel size, see [KernelShape] for more info.
+    /// * `edge_modes` - See [crate::EdgeMode] and [EdgeMode2D] for more info.
+    /// * `border_constant` - If [crate::EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+    /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+    ///
+    /// # Errors
+    /// Returns an error if size is zero, kernel is empty or do not match its shape,
+    /// or FFT cannot be created.
+    pub fn new<F>(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        kernel: &[F],
+        kernel_shape: KernelShape,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<Self, BlurError>
+    where
+        F: AsPrimitive<FftIntermediate>,
+    {
+        let complex_kernel = kernel
+            .iter()
+            .map(|&x| Complex {
+                re: x.as_(),
+                im: 0.0f64.as_(),
+            })
+            .collect::<Vec<_>>();
+        Self::new_complex(
+            size,
+            channels,
+            &complex_kernel,
+            kernel_shape,
+            edge_modes,
+            border_constant,
+            threading_policy,
+        )
+    }
+
+    /// Creates FFT convolution plan with complex kernel.
+    ///
+    /// See [FftConvolutionPlan::new] for arguments description.
+    pub fn new_complex(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        kernel: &[Complex<FftIntermediate>],
+        kernel_shape: KernelShape,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<Self, BlurError> {
+        let thread_count = threading_policy.thread_count(size.width as u32, size.height as u32);
+        Self::new_with_thread_count(
+            size,
+            channels,
+            kernel,
+            kernel_shape,
+            edge_modes,
+            border_constant,
+            thread_count,
+        )
+    }
+
+    pub(crate) fn new_with_thread_count(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        kernel: &[Complex<FftIntermediate>],
+        kernel_shape: KernelShape,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+        thread_count: usize,
+    ) -> Result<Self, BlurError> {
+        if size.width == 0 || size.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+
+        let kernel_width = kernel_shape.width;
+        let kernel_height = kernel_shape.height;
+        check_kernel_size(kernel, kernel_shape)?;
+
+        let is_identity = scan_se_2d_complex(kernel, kernel_shape).is_empty();
+
+        let best_width = fft_next_good_size(size.width + kernel_width);
+        let best_height = fft_next_good_size(size.height + kernel_height);
+
+        let arena_pad_left = (best_width - size.width) / 2;
+        let arena_pad_right = best_width - size.width - arena_pad_left;
+        let arena_pad_top = (best_height - size.height) / 2;
+        let arena_pad_bottom = best_height - size.height - arena_pad_top;
+
+        let fft_forward = FftIntermediate::make_fft(
+            best_width,
+            best_height,
+            FftDirection::Forward,
+            thread_count,
+        )?;
+        let fft_inverse = FftIntermediate::make_fft(
+            best_width,
+            best_height,
+            FftDirection::Inverse,
+            thread_count,
+        )?;
+
+        let mut scratch = vec![
+            Complex::<FftIntermediate>::default();
+            fft_forward
+                .scratch_length()
+                .max(fft_inverse.scratch_length())
+        ];
+
+        let mut kernel_spectrum =
+            vec![Complex::<FftIntermediate>::default(); best_height * best_width];
+
+        let shift_x = kernel_width as isize / 2;
+        let shift_y = kernel_height as isize / 2;
+
+        if best_height - 1 <= 1 || best_width - 1 <= 1 {
+            // fast divide do not support <= 1
+            kernel
+                .chunks_exact(kernel_width)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    for (x, item) in row.iter().enumerate() {
+                        let new_y =
+                            (y as isize - shift_y).rem_euclid(best_height as isize) as usize;
+                        let new_x = (x as isize - shift_x).rem_euclid(best_width as isize) as usize;
+                        kernel_spectrum[new_y * best_width + new_x] = *item;
+                    }
+                });
+        } else {
+            let divider_height = DividerIsize::new(best_height as isize);
+            let divider_width = DividerIsize::new(best_width as isize);
+            kernel
+                .chunks_exact(kernel_width)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    for (x, item) in row.iter().enumerate() {
+                        let new_y =
+                            (y as isize - shift_y).rem_euclid_fast(&divider_height) as usize;
+                        let new_x = (x as isize - shift_x).rem_euclid_fast(&divider_width) as usize;
+                        kernel_spectrum[new_y * best_width + new_x] = *item;
+                    }
+                });
+        }
+
+        fft_forward
+            .execute_with_scratch(&mut kernel_spectrum, &mut scratch)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+        let norm_factor = 1f64 / (best_width * best_height) as f64;
+
+        // Right and bottom pads are never smaller than the left and top ones
+        let x_lookup = make_edge_lookup(edge_modes.horizontal, size.width, arena_pad_right)
+            [arena_pad_right - arena_pad_left..][..best_width]
+            .to_vec();
+        let y_lookup = make_edge_lookup(edge_modes.vertical, size.height, arena_pad_bottom)
+            [arena_pad_bottom - arena_pad_top..][..best_height]
+            .to_vec();
+
+        Ok(FftConvolutionPlan {
+            size,
+            channels,
+            border_constant,
+            fft_width: best_width,
+            fft_height: best_height,
+            pads: ArenaPads::new(
+                arena_pad_left,
+                arena_pad_top,
+                arena_pad_right,
+                arena_pad_bottom,
+            ),
+            x_lookup,
+            y_lookup,
+            is_identity,
+            tapered_padding: false,
+            norm_factor: norm_factor.as_(),
+            kernel_spectrum,
+            spectrum: vec![Complex::<FftIntermediate>::default(); best_height * best_width],
+            scratch,
+            fft_forward,
+            fft_inverse,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Replaces every element of precomputed kernel spectrum with `op` result.
+    pub(crate) fn map_kernel_spectrum(
+        &mut self,
+        op: impl Fn(Complex<FftIntermediate>) -> Complex<FftIntermediate>,
+    ) {
+        for v in self.kernel_spectrum.iter_mut() {
+            *v = op(*v);
+        }
+    }
+
+    /// Replaces padding by linear ramps between opposite image edges,
+    /// so periodic extension of padded image has no jumps at FFT boundaries.
+    ///
+    /// Inverse filters amplify such jumps into ringing over the whole image.
+    pub(crate) fn set_tapered_padding(&mut self, tapered_padding: bool) {
+        self.tapered_padding = tapered_padding;
+    }
+
+    fn taper_padding(&mut self) {
+        let ImageSize { width, height } = self.size;
+        let fft_width = self.fft_width;
+        let fft_height = self.fft_height;
+        let pad_left = self.pads.pad_left;
+        let pad_top = self.pads.pad_top;
+
+        let gap_width = fft_width - width;
+        if gap_width > 0 {
+            for row in self
+                .spectrum
+                .chunks_exact_mut(fft_width)
+                .skip(pad_top)
+                .take(height)
+            {
+                let left = row[pad_left].re;
+                let right = row[pad_left + width - 1].re;
+                for g in 0..gap_width {
+                    let t: FftIntermediate = ((g + 1) as f64 / (gap_width + 1) as f64).as_();
+                    let x = (pad_left + width + g) % fft_width;
+                    row[x].re = right + (left - right) * t;
+                }
+            }
+        }
+
+        let gap_height = fft_height - height;
+        for g in 0..gap_height {
+            let t: FftIntermediate = ((g + 1) as f64 / (gap_height + 1) as f64).as_();
+            let y = (pad_top + height + g) % fft_height;
+            for x in 0..fft_width {
+                let top = self.spectrum[pad_top * fft_width + x].re;
+                let bottom = self.spectrum[(pad_top + height - 1) * fft_width + x].re;
+                self.spectrum[y * fft_width + x].re = bottom + (top - bottom) * t;
+            }
+        }
+    }
+
+    /// Image size this plan was created for.
+    pub fn size(&self) -> ImageSize {
+        self.size
+    }
+
+    /// Channels layout this plan was created for.
+    pub fn channels(&self) -> FastBlurChannels {
+        self.channels
+    }
+
+    /// Size of the padded FFT plane, image and kernel are transformed at this size.
+    pub fn fft_size(&self) -> ImageSize {
+        ImageSize::new(self.fft_width, self.fft_height)
+    }
+
+    /// Convolves `src` with the kernel the plan was created for.
+    ///
+    /// # Errors
+    /// Returns an error if images layout is invalid or do not match plan size and channels.
+    pub fn execute(
+        &mut self,
+        src: &BlurImage<T>,
+        dst: &mut BlurImageMut<T>,
+    ) -> Result<(), BlurError> {
+        src.check_layout()?;
+        dst.check_layout(Some(src))?;
+        src.size_matches_mut(dst)?;
+        if src.size() != self.size || src.channels != self.channels {
+            return Err(BlurError::ImagesMustMatch);
+        }
+
+        if self.is_identity {
+            let dst_stride = dst.row_stride() as usize;
+            let row_length = self.size.width * self.channels.channels();
+            for (src, dst) in src
+                .data
+                .chunks_exact(src.row_stride() as usize)
+                .zip(dst.data.borrow_mut().chunks_exact_mut(dst_stride))
+            {
+                dst[..row_length].copy_from_slice(&src[..row_length]);
+            }
+            return Ok(());
+        }
+
+        match self.channels {
+            FastBlurChannels::Plane => self.execute_impl::<1>(src, dst),
+            FastBlurChannels::Channels2 => self.execute_impl::<2>(src, dst),
+            FastBlurChannels::Channels3 => self.execute_impl::<3>(src, dst),
+            FastBlurChannels::Channels4 => self.execute_impl::<4>(src, dst),
+        }
+    }
+
+    fn execute_impl<const CN: usize>(
+        &mut self,
+        src: &BlurImage<T>,
+        dst: &mut BlurImageMut<T>,
+    ) -> Result<(), BlurError> {
+        let src_stride = src.row_stride() as usize;
+        let dst_stride = dst.row_stride() as usize;
+        let row_length = self.size.width * CN;
+        let dst_data = dst.data.borrow_mut();
+
+        for c in 0..CN {
+            let constant: T = self.border_constant[c].as_();
+            let constant: FftIntermediate = constant.as_();
+            for (dst_row, &y) in self
+                .spectrum
+                .chunks_exact_mut(self.fft_width)
+                .zip(self.y_lookup.iter())
+            {
+                let src_row = y.map(|y| &src.data[y * src_stride..]);
+                for (dst, &x) in dst_row.iter_mut().zip(self.x_lookup.iter()) {
+                    let re = match (src_row, x) {
+                        (Some(src_row), Some(x)) => src_row[x * CN + c].as_(),
+                        _ => constant,
+                    };
+                    *dst = Complex::<FftIntermediate> { re, im: 0f64.as_() };
+                }
+            }
+
+            if self.tapered_padding {
+                self.taper_padding();
+            }
+
+            self.fft_forward
+                .execute_with_scratch(&mut self.spectrum, &mut self.scratch)
+                .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+            FftIntermediate::mul_spectrum(
+                &mut self.spectrum,
+                &self.kernel_spectrum,
+                self.fft_width,
+                self.fft_height,
+                self.norm_factor,
+            );
+
+            self.fft_inverse
+                .execute_with_scratch(&mut self.spectrum, &mut self.scratch)
+                .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+            for (dst_chunk, src_chunk) in dst_data.chunks_exact_mut(dst_stride).zip(
+                self.spectrum
+                    .chunks_exact(self.fft_width)
+                    .skip(self.pads.pad_top),
+            ) {
+                for (dst, src) in dst_chunk[..row_length]
+                    .as_chunks_mut::<CN>()
+                    .0
+                    .iter_mut()
+                    .zip(src_chunk.iter().skip(self.pads.pad_left))
+                {
+                    dst[c] = src.re.to_();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdgeMode, filter_2d_rgb};
+
+    #[test]
+    fn test_fft_plan_reuse_matches_filter_2d() {
+        let width = 61usize;
+        let height = 47usize;
+        let kernel_shape = KernelShape::new(15, 11);
+        let mut seed = 0x2545f491u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            seed >> 24
+        };
+        let mut kernel = (0..kernel_shape.width * kernel_shape.height)
+            .map(|_| next() as f32)
+            .collect::<Vec<f32>>();
+        let kernel_sum = kernel.iter().sum::<f32>();
+        kernel.iter_mut().for_each(|x| *x /= kernel_sum);
+        // FFT convolves while filter_2d correlates, reference uses flipped kernel
+        let flipped_kernel = kernel.iter().rev().copied().collect::<Vec<f32>>();
+
+        for (edge_modes, border_constant) in [
+            (EdgeMode::Reflect101.as_2d(), Scalar::default()),
+            (
+                EdgeMode2D::anisotropy(EdgeMode::Constant, EdgeMode::Clamp),
+                Scalar::dup(40.),
+            ),
+        ] {
+            let mut plan = FftConvolutionPlan::<u8, f32>::new(
+                ImageSize::new(width, height),
+                FastBlurChannels::Channels3,
+                &kernel,
+                kernel_shape,
+                edge_modes,
+                border_constant,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+
+            for _ in 0..3 {
+                let src = (0..width * height * 3)
+                    .map(|_| next() as u8)
+                    .collect::<Vec<u8>>();
+                let src_image = BlurImage::borrow(
+                    &src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                );
+                let mut reference = BlurImageMut::default();
+                filter_2d_rgb(
+                    &src_image,
+                    &mut reference,
+                    &flipped_kernel,
+                    kernel_shape,
+                    edge_modes,
+                    border_constant,
+                    ThreadingPolicy::Single,
+                )
+                .unwrap();
+                let mut dst = BlurImageMut::default();
+                plan.execute(&src_image, &mut dst).unwrap();
+                for (i, (&a, &b)) in dst
+                    .data
+                    .borrow()
+                    .iter()
+                    .zip(reference.data.borrow().iter())
+                    .enumerate()
+                {
+                    let diff = (a as i32 - b as i32).abs();
+                    assert!(
+                        diff <= 1,
+                        "Diff expected to be <= 1 but it was {diff} at {i}"
+                    );
+                }
+            }
+
+            let src = vec![0u8; width * height];
+            let src_image =
+                BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+            let mut dst = BlurImageMut::default();
+            assert!(matches!(
+                plan.execute(&src_image, &mut dst),
+                Err(BlurError::ImagesMustMatch)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_fft_plan_rejects_empty_kernel() {
+        for (kernel, kernel_shape, name) in [
+            (vec![], KernelShape::new(0, 0), "kernel_width"),
+            (vec![], KernelShape::new(0, 5), "kernel_width"),
+            (vec![], KernelShape::new(5, 0), "kernel_height"),
+        ] {
+            let plan = FftConvolutionPlan::<u8, f32>::new::<f32>(
+                ImageSize::new(16, 16),
+                FastBlurChannels::Plane,
+                &kernel,
+                kernel_shape,
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            );
+            assert!(
+                matches!(plan, Err(BlurError::InvalidParameter { name: n, .. }) if n == name),
+                "Empty kernel {kernel_shape:?} must be rejected"
+            );
+        }
+    }
+}
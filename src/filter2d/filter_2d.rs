@@ -26,16 +26,16 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::filter1d::{ArenaPads, KernelShape, make_arena};
 use crate::filter2d::filter_2d_handler::Filter2dHandler;
 use crate::filter2d::scan_point_2d::ScanPoint2d;
-use crate::filter2d::scan_se_2d::scan_se_2d;
+use crate::filter2d::scan_se_2d::{check_kernel_size, scan_se_2d};
 use crate::to_storage::ToStorage;
 use crate::{
-    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, ImageSize, MismatchedSize,
-    Scalar, ThreadingPolicy,
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, ImageSize, Scalar,
+    ThreadingPolicy,
 };
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use num_traits::{AsPrimitive, MulAdd};
 use std::fmt::Debug;
 use std::ops::Mul;
@@ -92,6 +92,15 @@ where
             border_constant,
             threading_policy,
         ),
+        FastBlurChannels::Channels2 => filter_2d_arbitrary::<T, F, 2>(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            edge_modes,
+            border_constant,
+            threading_policy,
+        ),
         FastBlurChannels::Channels3 => filter_2d_arbitrary::<T, F, 3>(
             src,
             dst,
@@ -150,14 +159,7 @@ where
 {
     src.check_layout_channels(CN)?;
     dst.check_layout_channels(CN, Some(src))?;
-    let kernel_width = kernel_shape.width;
-    let kernel_height = kernel_shape.height;
-    if kernel_height * kernel_width != kernel.len() {
-        return Err(BlurError::KernelSizeMismatch(MismatchedSize {
-            expected: kernel_height * kernel_width,
-            received: kernel.len(),
-        }));
-    }
+    check_kernel_size(kernel, kernel_shape)?;
 
     let analyzed_se = scan_se_2d(kernel, kernel_shape);
 
@@ -189,7 +191,7 @@ where
 
     let thread_count =
         threading_policy.thread_count(image_size.width as u32, image_size.height as u32) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let arena_source_slice = arena_source.as_slice();
     let kernel_slice = analyzed_se.as_slice();
@@ -230,3 +232,41 @@ where
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_filter_2d_f32_keeps_flat_row_tail() {
+        // 13 RGB pixels give a row tail not covered by the four lane loop
+        let width = 13usize;
+        let height = 7usize;
+        let src = vec![0.625f32; width * height * 3];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let kernel = vec![1f32 / 9.; 9];
+        let mut dst = BlurImageMut::default();
+        filter_2d::<f32, f32>(
+            &src_image,
+            &mut dst,
+            &kernel,
+            KernelShape::new(3, 3),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        for (i, &v) in dst.data.borrow().iter().enumerate() {
+            assert!(
+                (v - 0.625).abs() < 1e-5,
+                "Value at {i} expected to be 0.625, but it was {v}"
+            );
+        }
+    }
+}
@@ -29,13 +29,12 @@
 
 use crate::fast_divide::{DividerIsize, RemEuclidFast};
 use crate::filter1d::{ArenaPads, make_arena};
-use crate::filter2d::convolver::FftConvolve;
 use crate::filter2d::fft_utils::fft_next_good_size_real;
 use crate::filter2d::mul_spectrum::SpectrumMultiplier;
+use crate::filter2d::scan_se_2d::check_kernel_size;
 use crate::to_storage::ToStorage;
 use crate::{
-    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, KernelShape, MismatchedSize,
-    Scalar,
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, KernelShape, Scalar,
 };
 use num_complex::Complex;
 use num_traits::{AsPrimitive, Num};
@@ -171,53 +170,7 @@ where
 
     let kernel_width = kernel_shape.width;
     let kernel_height = kernel_shape.height;
-    if kernel_height * kernel_width != kernel.len() {
-        return Err(BlurError::KernelSizeMismatch(MismatchedSize {
-            expected: kernel_height * kernel_width,
-            received: kernel.len(),
-        }));
-    }
-
-    if kernel_width < 200 && kernel_height < 200 {
-        let block_size_x = if kernel_width > 95 {
-            512
-        } else if kernel_width > 66 {
-            256
-        } else {
-            128
-        };
-        let block_size_y = if kernel_height > 95 {
-            512
-        } else if kernel_height > 66 {
-            256
-        } else {
-            128
-        };
-        let convolver = FftConvolve {
-            border_constant: border_constant.v0.as_(),
-            edge_mode: edge_modes,
-            thread_count,
-            block_cols: block_size_x,
-            block_rows: block_size_y,
-        };
-        let arena_source = src
-            .data
-            .as_ref()
-            .iter()
-            .map(|&v| v.as_())
-            .collect::<Vec<F>>();
-
-        convolver.overlap_save_2d(
-            dst,
-            &arena_source,
-            src.height as usize,
-            src.width as usize,
-            kernel,
-            kernel_shape.height,
-            kernel_shape.width,
-        );
-        return Ok(());
-    }
+    check_kernel_size(kernel, kernel_shape)?;
 
     let image_size = src.size();
 
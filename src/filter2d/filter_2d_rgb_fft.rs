@@ -26,14 +26,15 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use crate::filter2d::convolver::FftConvolve;
 use crate::filter2d::filter_2d_fft::filter_2d_fft_impl;
 use crate::filter2d::filter_2d_fft_real::{FftNumber, filter_2d_fft_real_impl};
 use crate::filter2d::gather_channel::{gather_channel, squash_channel};
-use crate::filter2d::scan_se_2d::{scan_se_2d, scan_se_2d_complex};
+use crate::filter2d::scan_se_2d::{check_kernel_size, scan_se_2d, scan_se_2d_complex};
 use crate::to_storage::ToStorage;
 use crate::{
-    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, KernelShape, Scalar,
-    ThreadingPolicy,
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, FftConvolutionMode,
+    KernelShape, Scalar, ThreadingPolicy,
 };
 use num_complex::Complex;
 use num_traits::AsPrimitive;
@@ -51,6 +52,7 @@ use std::fmt::Debug;
 /// * `kernel_shape`: Kernel size, see [KernelShape] for more info.
 /// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
 /// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `fft_mode`: See [FftConvolutionMode], tiled mode bounds memory for very large images.
 /// * `FftIntermediate`: Intermediate internal type for fft, only `f32` and `f64` is supported.
 ///
 /// returns: Result<(), String>
@@ -63,6 +65,7 @@ pub fn filter_2d_rgb_fft<T, F>(
     edge_modes: EdgeMode2D,
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    fft_mode: FftConvolutionMode,
 ) -> Result<(), BlurError>
 where
     T: AsPrimitive<F> + Copy + Default + Send + Sync + Default + Debug,
@@ -77,6 +80,7 @@ where
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
+    check_kernel_size(kernel, kernel_shape)?;
 
     let analyzed_se = scan_se_2d(kernel, kernel_shape);
     if analyzed_se.is_empty() {
@@ -95,6 +99,17 @@ where
 
     let thread_count = threading_policy.thread_count(src.width, src.height);
 
+    if let Some(tile_size) = fft_mode.tile_size(src.size(), kernel_shape, true)? {
+        return FftConvolve::new(
+            border_constant,
+            edge_modes,
+            thread_count,
+            tile_size,
+            src.size(),
+        )
+        .convolve::<T, 3>(src, dst, kernel, kernel_shape);
+    }
+
     let image_size = src.size();
 
     let mut working_channel = BlurImageMut::alloc(
@@ -160,6 +175,7 @@ where
 /// * `kernel_shape`: Kernel size, see [KernelShape] for more info.
 /// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
 /// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `fft_mode`: See [FftConvolutionMode], tiled mode bounds memory for very large images.
 /// * `FftIntermediate`: Intermediate internal type for fft, only `f32` and `f64` is supported.
 ///
 /// returns: Result<(), String>
@@ -172,6 +188,7 @@ pub fn filter_2d_rgb_fft_complex<T, FftIntermediate>(
     edge_modes: EdgeMode2D,
     border_constant: Scalar,
     threading_policy: ThreadingPolicy,
+    fft_mode: FftConvolutionMode,
 ) -> Result<(), BlurError>
 where
     T: Copy + Default + Send + Sync + AsPrimitive<FftIntermediate> + Debug,
@@ -185,6 +202,7 @@ where
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
+    check_kernel_size(kernel, kernel_shape)?;
 
     let analyzed_se = scan_se_2d_complex(kernel, kernel_shape);
     if analyzed_se.is_empty() {
@@ -202,7 +220,18 @@ where
     }
 
     let thread_count = threading_policy.thread_count(src.width, src.height);
-    let pool = novtb::ThreadPool::new(thread_count);
+
+    if let Some(tile_size) = fft_mode.tile_size(src.size(), kernel_shape, false)? {
+        return FftConvolve::new(
+            border_constant,
+            edge_modes,
+            thread_count,
+            tile_size,
+            src.size(),
+        )
+        .convolve_complex::<T, 3>(src, dst, kernel, kernel_shape);
+    }
+    let pool = crate::executor::ThreadPool::new(thread_count);
 
     let image_size = src.size();
 
@@ -33,6 +33,12 @@ mod convolve_op_fp;
 #[cfg(feature = "fft")]
 mod convolver;
 #[cfg(feature = "fft")]
+mod deconvolution;
+#[cfg(feature = "fft")]
+mod fft_mode;
+#[cfg(feature = "fft")]
+mod fft_plan;
+#[cfg(feature = "fft")]
 mod fft_utils;
 mod filter_2d;
 #[cfg(feature = "fft")]
@@ -56,7 +62,15 @@ mod scan_point_2d;
 mod scan_se_2d;
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
 mod sse;
+#[cfg(feature = "fft")]
+mod template_matching;
 
+#[cfg(feature = "fft")]
+pub use deconvolution::{richardson_lucy_deconvolution, wiener_deconvolution};
+#[cfg(feature = "fft")]
+pub use fft_mode::FftConvolutionMode;
+#[cfg(feature = "fft")]
+pub use fft_plan::FftConvolutionPlan;
 #[cfg(feature = "fft")]
 pub use fft_utils::fft_next_good_size;
 pub use filter_2d::{filter_2d, filter_2d_arbitrary};
@@ -70,3 +84,5 @@ pub use filter_2d_rgb_fft::{filter_2d_rgb_fft, filter_2d_rgb_fft_complex};
 pub use filter_2d_rgba::filter_2d_rgba;
 #[cfg(feature = "fft")]
 pub use filter_2d_rgba_fft::{filter_2d_rgba_fft, filter_2d_rgba_fft_complex};
+#[cfg(feature = "fft")]
+pub use template_matching::{TemplateMatchLocation, TemplateMatchMode, match_template};
@@ -28,10 +28,38 @@
  */
 use crate::filter1d::KernelShape;
 use crate::filter2d::scan_point_2d::ScanPoint2d;
+use crate::{BlurError, MismatchedSize};
 #[cfg(feature = "fft")]
 use num_complex::Complex;
 use num_traits::AsPrimitive;
 
+/// Checks that kernel is not empty and its length matches the shape,
+/// must be done before a kernel is scanned.
+pub(crate) fn check_kernel_size<V>(
+    kernel: &[V],
+    kernel_shape: KernelShape,
+) -> Result<(), BlurError> {
+    if kernel_shape.width == 0 {
+        return Err(BlurError::InvalidParameter {
+            name: "kernel_width",
+            value: 0.,
+        });
+    }
+    if kernel_shape.height == 0 {
+        return Err(BlurError::InvalidParameter {
+            name: "kernel_height",
+            value: 0.,
+        });
+    }
+    if kernel_shape.width.checked_mul(kernel_shape.height) != Some(kernel.len()) {
+        return Err(BlurError::KernelSizeMismatch(MismatchedSize {
+            expected: kernel_shape.width.saturating_mul(kernel_shape.height),
+            received: kernel.len(),
+        }));
+    }
+    Ok(())
+}
+
 pub(crate) fn scan_se_2d<F>(
     structuring_element: &[F],
     structuring_element_size: KernelShape,
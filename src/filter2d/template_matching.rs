@@ -0,0 +1,520 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::{ParallelZonedIterator, TbSliceMut, ThreadPool};
+use crate::filter2d::fft_utils::fft_next_good_size;
+use crate::{BlurError, BlurImage, BlurImageMut, FastBlurChannels, FftNumber, ThreadingPolicy};
+use num_complex::Complex;
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+use zaft::FftDirection;
+
+/// Score computed for every template placement in [match_template].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TemplateMatchMode {
+    /// Plain cross-correlation, sum of products of image and template.
+    #[default]
+    CrossCorrelation,
+    /// Cross-correlation with template mean subtracted,
+    /// which is insensitive to brightness offset of the image.
+    ZeroMeanCrossCorrelation,
+    /// Zero-mean cross-correlation divided by standard deviations of the template and
+    /// image window, insensitive to brightness and contrast, scores are in [-1, 1].
+    NormalizedCrossCorrelation,
+}
+
+/// Best template placement found by [match_template].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TemplateMatchLocation<F> {
+    /// Column of template top-left corner.
+    pub x: u32,
+    /// Row of template top-left corner.
+    pub y: u32,
+    /// Score at this placement.
+    pub score: F,
+}
+
+/// Matches template against an image, writes scores of every placement and returns the best one.
+///
+/// Correlation map in `dst` has `image.width - template.width + 1` columns and
+/// `image.height - template.height + 1` rows, value at (x, y) scores template
+/// with top-left corner placed at (x, y). Channels are summed together.
+/// Small templates are correlated directly, large templates through FFT,
+/// whichever is cheaper. Window statistics for normalized mode are taken from integral images.
+/// Best match is the placement with the highest score.
+///
+/// # Arguments
+///
+/// * `image`: Image to search in.
+/// * `template`: Template, must have the same channels layout and not exceed the image.
+/// * `dst`: Destination single plane correlation map.
+/// * `mode`: See [TemplateMatchMode] for more info.
+/// * `threading_policy`: Threading policy according to [ThreadingPolicy].
+/// * `F`: Correlation map type and FFT intermediate type, only `f32` and `f64` is supported.
+pub fn match_template<T, F>(
+    image: &BlurImage<T>,
+    template: &BlurImage<T>,
+    dst: &mut BlurImageMut<F>,
+    mode: TemplateMatchMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<TemplateMatchLocation<F>, BlurError>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<F> + AsPrimitive<f64> + Debug,
+    F: FftNumber + AsPrimitive<f64>,
+    f64: AsPrimitive<F>,
+{
+    image.check_layout()?;
+    template.check_layout()?;
+    if image.channels != template.channels {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    if template.width > image.width {
+        return Err(BlurError::InvalidParameter {
+            name: "template_width",
+            value: template.width as f64,
+        });
+    }
+    if template.height > image.height {
+        return Err(BlurError::InvalidParameter {
+            name: "template_height",
+            value: template.height as f64,
+        });
+    }
+
+    let out_width = (image.width - template.width + 1) as usize;
+    let out_height = (image.height - template.height + 1) as usize;
+    dst.check_layout_with_size(out_width as u32, out_height as u32, FastBlurChannels::Plane)?;
+
+    let cn = image.channels.channels();
+    let template_row_length = template.width as usize * cn;
+    let mut template_values: Vec<f64> =
+        Vec::with_capacity(template_row_length * template.height as usize);
+    for row in template
+        .data
+        .chunks(template.row_stride() as usize)
+        .take(template.height as usize)
+    {
+        template_values.extend(row[..template_row_length].iter().map(|&x| {
+            let v: f64 = x.as_();
+            v
+        }));
+    }
+
+    if mode != TemplateMatchMode::CrossCorrelation {
+        let mean = template_values.iter().sum::<f64>() / template_values.len() as f64;
+        template_values.iter_mut().for_each(|x| *x -= mean);
+    }
+
+    let thread_count = threading_policy.thread_count(image.width, image.height);
+    let pool = ThreadPool::new(thread_count);
+
+    let template_size = (template.width as usize, template.height as usize);
+    let mut scores = if prefers_fft(image, template_size, out_width, out_height) {
+        correlate_fft::<T, F>(image, &template_values, template_size, thread_count)?
+    } else {
+        correlate_direct(image, &template_values, template_size, &pool)
+    };
+
+    if mode == TemplateMatchMode::NormalizedCrossCorrelation {
+        let template_energy = template_values.iter().map(|&x| x * x).sum::<f64>();
+        normalize_scores(
+            image,
+            &mut scores,
+            template_size,
+            template_energy,
+            out_width,
+        );
+    }
+
+    let mut best = TemplateMatchLocation {
+        x: 0,
+        y: 0,
+        score: scores[0].as_(),
+    };
+    let mut best_score = scores[0];
+    let dst_stride = dst.row_stride() as usize;
+    for (y, (dst, src)) in dst
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(scores.chunks_exact(out_width))
+        .enumerate()
+    {
+        for (x, (dst, &src)) in dst.iter_mut().zip(src.iter()).enumerate() {
+            *dst = src.as_();
+            if src > best_score {
+                best_score = src;
+                best = TemplateMatchLocation {
+                    x: x as u32,
+                    y: y as u32,
+                    score: src.as_(),
+                };
+            }
+        }
+    }
+
+    Ok(best)
+}
+
+/// Compares rough operation counts of direct and FFT correlation.
+fn prefers_fft<T: Copy + Default + Debug>(
+    image: &BlurImage<T>,
+    template_size: (usize, usize),
+    out_width: usize,
+    out_height: usize,
+) -> bool {
+    let cn = image.channels.channels() as f64;
+    let direct_cost =
+        out_width as f64 * out_height as f64 * (template_size.0 * template_size.1) as f64 * cn;
+    let fft_area = fft_next_good_size(image.width as usize) as f64
+        * fft_next_good_size(image.height as usize) as f64;
+    // Two forward and one inverse transform per channel
+    let fft_cost = 3. * cn * fft_area * fft_area.log2() * 2.;
+    direct_cost > fft_cost
+}
+
+fn correlate_direct<T>(
+    image: &BlurImage<T>,
+    template: &[f64],
+    template_size: (usize, usize),
+    pool: &ThreadPool,
+) -> Vec<f64>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<f64> + Debug,
+{
+    let cn = image.channels.channels();
+    let out_width = image.width as usize - template_size.0 + 1;
+    let out_height = image.height as usize - template_size.1 + 1;
+    let template_row_length = template_size.0 * cn;
+    let image_stride = image.row_stride() as usize;
+    let image_data = image.data.as_ref();
+
+    let mut scores = vec![0f64; out_width * out_height];
+    scores
+        .tb_par_chunks_exact_mut(out_width)
+        .for_each_enumerated(pool, |y, dst| {
+            for (x, dst) in dst.iter_mut().enumerate() {
+                let mut sum = 0f64;
+                for (ty, template_row) in template.chunks_exact(template_row_length).enumerate() {
+                    let offset = (y + ty) * image_stride + x * cn;
+                    let image_row = &image_data[offset..offset + template_row_length];
+                    for (&i, &t) in image_row.iter().zip(template_row.iter()) {
+                        let i: f64 = i.as_();
+                        sum += i * t;
+                    }
+                }
+                *dst = sum;
+            }
+        });
+    scores
+}
+
+fn correlate_fft<T, F>(
+    image: &BlurImage<T>,
+    template: &[f64],
+    template_size: (usize, usize),
+    thread_count: usize,
+) -> Result<Vec<f64>, BlurError>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<F> + Debug,
+    F: FftNumber + AsPrimitive<f64>,
+    f64: AsPrimitive<F>,
+{
+    let cn = image.channels.channels();
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let out_width = width - template_size.0 + 1;
+    let out_height = height - template_size.1 + 1;
+    let image_stride = image.row_stride() as usize;
+    let template_row_length = template_size.0 * cn;
+
+    // Valid placements never wrap around, so padding to image size is enough
+    let fft_width = fft_next_good_size(width);
+    let fft_height = fft_next_good_size(height);
+
+    let fft_forward = F::make_fft(fft_width, fft_height, FftDirection::Forward, thread_count)?;
+    let fft_inverse = F::make_fft(fft_width, fft_height, FftDirection::Inverse, thread_count)?;
+    let mut scratch = vec![
+        Complex::<F>::default();
+        fft_forward
+            .scratch_length()
+            .max(fft_inverse.scratch_length())
+    ];
+
+    let mut image_spectrum = vec![Complex::<F>::default(); fft_width * fft_height];
+    let mut template_spectrum = vec![Complex::<F>::default(); fft_width * fft_height];
+    let mut scores = vec![0f64; out_width * out_height];
+
+    let norm_factor: F = (1f64 / (fft_width * fft_height) as f64).as_();
+
+    for c in 0..cn {
+        image_spectrum.fill(Complex::<F>::default());
+        for (dst, src) in image_spectrum
+            .chunks_exact_mut(fft_width)
+            .zip(image.data.chunks(image_stride).take(height))
+        {
+            for (dst, &src) in dst
+                .iter_mut()
+                .zip(src.iter().skip(c).step_by(cn).take(width))
+            {
+                dst.re = src.as_();
+            }
+        }
+
+        template_spectrum.fill(Complex::<F>::default());
+        for (dst, src) in template_spectrum
+            .chunks_exact_mut(fft_width)
+            .zip(template.chunks_exact(template_row_length))
+        {
+            for (dst, &src) in dst.iter_mut().zip(src.iter().skip(c).step_by(cn)) {
+                dst.re = src.as_();
+            }
+        }
+
+        fft_forward
+            .execute_with_scratch(&mut image_spectrum, &mut scratch)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+        fft_forward
+            .execute_with_scratch(&mut template_spectrum, &mut scratch)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+        // Correlation is multiplication by conjugated template spectrum
+        for v in template_spectrum.iter_mut() {
+            v.im = F::zero() - v.im;
+        }
+
+        F::mul_spectrum(
+            &mut image_spectrum,
+            &template_spectrum,
+            fft_width,
+            fft_height,
+            norm_factor,
+        );
+
+        fft_inverse
+            .execute_with_scratch(&mut image_spectrum, &mut scratch)
+            .map_err(|x| BlurError::FftError(x.to_string()))?;
+
+        for (dst, src) in scores
+            .chunks_exact_mut(out_width)
+            .zip(image_spectrum.chunks_exact(fft_width))
+        {
+            for (dst, src) in dst.iter_mut().zip(src.iter()) {
+                let v: f64 = src.re.as_();
+                *dst += v;
+            }
+        }
+    }
+
+    Ok(scores)
+}
+
+/// Divides zero-mean scores by standard deviations of template and image windows.
+fn normalize_scores<T>(
+    image: &BlurImage<T>,
+    scores: &mut [f64],
+    template_size: (usize, usize),
+    template_energy: f64,
+    out_width: usize,
+) where
+    T: Copy + Default + AsPrimitive<f64> + Debug,
+{
+    let cn = image.channels.channels();
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let image_stride = image.row_stride() as usize;
+    let integral_width = width + 1;
+
+    let mut sums = vec![0f64; integral_width * (height + 1)];
+    let mut squares = vec![0f64; integral_width * (height + 1)];
+    for (y, row) in image.data.chunks(image_stride).take(height).enumerate() {
+        let mut row_sum = 0f64;
+        let mut row_squares = 0f64;
+        for (x, px) in row[..width * cn].chunks_exact(cn).enumerate() {
+            for &v in px {
+                let v: f64 = v.as_();
+                row_sum += v;
+                row_squares += v * v;
+            }
+            let idx = (y + 1) * integral_width + x + 1;
+            sums[idx] = sums[idx - integral_width] + row_sum;
+            squares[idx] = squares[idx - integral_width] + row_squares;
+        }
+    }
+
+    let count = (template_size.0 * template_size.1 * cn) as f64;
+    let (tw, th) = template_size;
+    let window = |table: &[f64], x: usize, y: usize| -> f64 {
+        table[(y + th) * integral_width + x + tw]
+            - table[y * integral_width + x + tw]
+            - table[(y + th) * integral_width + x]
+            + table[y * integral_width + x]
+    };
+
+    for (y, row) in scores.chunks_exact_mut(out_width).enumerate() {
+        for (x, score) in row.iter_mut().enumerate() {
+            let sum = window(&sums, x, y);
+            let image_energy = (window(&squares, x, y) - sum * sum / count).max(0.);
+            let denominator = (image_energy * template_energy).sqrt();
+            *score = if denominator > f64::EPSILON * count {
+                (*score / denominator).clamp(-1., 1.)
+            } else {
+                0.
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_noise(width: usize, height: usize, cn: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491u32;
+        (0..width * height * cn)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    fn crop(
+        data: &[u8],
+        width: usize,
+        cn: usize,
+        x: usize,
+        y: usize,
+        crop_width: usize,
+        crop_height: usize,
+    ) -> Vec<u8> {
+        let mut out = Vec::with_capacity(crop_width * crop_height * cn);
+        for row in data.chunks_exact(width * cn).skip(y).take(crop_height) {
+            out.extend_from_slice(&row[x * cn..(x + crop_width) * cn]);
+        }
+        out
+    }
+
+    #[test]
+    fn test_match_template_finds_offset() {
+        let width = 120usize;
+        let height = 90usize;
+        let image_data = make_noise(width, height, 3);
+        let template_data = crop(&image_data, width, 3, 37, 21, 24, 18);
+        // Brightness and contrast changes must not affect normalized scores
+        let shifted: Vec<u8> = image_data.iter().map(|&x| x / 2 + 40).collect();
+
+        let template = BlurImage::borrow(&template_data, 24, 18, FastBlurChannels::Channels3);
+        for mode in [
+            TemplateMatchMode::ZeroMeanCrossCorrelation,
+            TemplateMatchMode::NormalizedCrossCorrelation,
+        ] {
+            for data in [&image_data, &shifted] {
+                let image = BlurImage::borrow(
+                    data,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                );
+                let mut dst = BlurImageMut::<f32>::default();
+                let best =
+                    match_template(&image, &template, &mut dst, mode, ThreadingPolicy::Adaptive)
+                        .unwrap();
+                assert_eq!(dst.width, (width - 24 + 1) as u32);
+                assert_eq!(dst.height, (height - 18 + 1) as u32);
+                assert_eq!((best.x, best.y), (37, 21), "Mode {mode:?}");
+                if mode == TemplateMatchMode::NormalizedCrossCorrelation {
+                    assert!((best.score - 1.).abs() < 1e-4, "Score {}", best.score);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlation_matches_direct() {
+        let width = 47usize;
+        let height = 33usize;
+        let image_data = make_noise(width, height, 2);
+        let image = BlurImage::borrow(
+            &image_data,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        let template: Vec<f64> = make_noise(9, 7, 2)
+            .iter()
+            .map(|&x| x as f64 - 127.5)
+            .collect();
+
+        let pool = ThreadPool::new(2);
+        let direct = correlate_direct(&image, &template, (9, 7), &pool);
+        let fft = correlate_fft::<u8, f64>(&image, &template, (9, 7), 1).unwrap();
+        assert_eq!(direct.len(), fft.len());
+        for (i, (&a, &b)) in direct.iter().zip(fft.iter()).enumerate() {
+            assert!((a - b).abs() < 1e-6 * a.abs().max(1.), "At {i}: {a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_match_template_rejects_bad_input() {
+        let image_data = make_noise(10, 10, 1);
+        let image = BlurImage::borrow(&image_data, 10, 10, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::<f64>::default();
+
+        let wide = make_noise(11, 2, 1);
+        let template = BlurImage::borrow(&wide, 11, 2, FastBlurChannels::Plane);
+        assert!(matches!(
+            match_template(
+                &image,
+                &template,
+                &mut dst,
+                TemplateMatchMode::CrossCorrelation,
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::InvalidParameter {
+                name: "template_width",
+                ..
+            })
+        ));
+
+        let rgb = make_noise(2, 2, 3);
+        let template = BlurImage::borrow(&rgb, 2, 2, FastBlurChannels::Channels3);
+        assert!(matches!(
+            match_template(
+                &image,
+                &template,
+                &mut dst,
+                TemplateMatchMode::CrossCorrelation,
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::ImagesMustMatch)
+        ));
+    }
+}
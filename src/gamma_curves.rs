@@ -293,7 +293,7 @@ pub(crate) fn hlg_from_linear(linear: f32) -> f32 {
 #[inline]
 /// Gamma transfer function for HLG
 pub(crate) fn trc_linear(v: f32) -> f32 {
-    v.min(1.).min(0.)
+    v.clamp(0., 1.)
 }
 
 #[repr(C)]
@@ -383,3 +383,18 @@ impl TransferFunction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_transfer_is_identity() {
+        for v in [0., 0.25, 0.5, 1.] {
+            assert_eq!(TransferFunction::Linear.linearize(v), v);
+            assert_eq!(TransferFunction::Linear.gamma(v), v);
+        }
+        assert_eq!(TransferFunction::Linear.linearize(-0.5), 0.);
+        assert_eq!(TransferFunction::Linear.gamma(1.5), 1.);
+    }
+}
@@ -25,13 +25,13 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::channels_configuration::FastBlurChannels;
 use crate::gaussian::gaussian_hint::IeeeBinaryConvolutionMode;
 use crate::gaussian::gaussian_kernel::gaussian_kernel_1d;
 use crate::gaussian::gaussian_util::{kernel_size as get_kernel_size, kernel_size_d};
+use crate::gaussian::plan::GaussianPlan;
 use crate::{
-    BlurError, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode2D, Scalar, ThreadingPolicy,
-    filter_1d_approx, filter_1d_exact, gaussian_kernel_1d_f64, sigma_size, sigma_size_d,
+    AlphaMode, BlurError, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode2D, ThreadingPolicy,
+    gaussian_kernel_1d_f64, sigma_size, sigma_size_d,
 };
 #[cfg(feature = "nightly_f16")]
 use core::f16;
@@ -68,6 +68,41 @@ fn round_to_nearest_odd(x: f64) -> i64 {
         }
     }
 }
+/// Largest supported kernel size, also applies to kernels computed from sigma
+const MAX_KERNEL_SIZE: u32 = 65535;
+
+fn check_sigma(name: &'static str, sigma: f64) -> Result<(), BlurError> {
+    if !sigma.is_finite() {
+        return Err(BlurError::InvalidParameter { name, value: sigma });
+    }
+    if sigma <= 0. {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    Ok(())
+}
+
+fn check_kernel(name: &'static str, kernel: f64) -> Result<(), BlurError> {
+    if !kernel.is_finite() || kernel <= 0. {
+        return Err(BlurError::InvalidParameter {
+            name,
+            value: kernel,
+        });
+    }
+    Ok(())
+}
+
+/// Rounds fractional kernel to odd size and derives its sigma.
+///
+/// Kernel that is not positive or not finite is carried over as sigma with unset size,
+/// so [GaussianBlurParams::validate] rejects it.
+fn kernel_and_sigma(kernel: f64) -> (u32, f64) {
+    if !kernel.is_finite() || kernel <= 0. {
+        return (0, kernel);
+    }
+    let size = round_to_nearest_odd(kernel).min(u32::MAX as i64) as u32;
+    (size, sigma_size_d(kernel))
+}
+
 impl GaussianBlurParams {
     /// Kernel expected to be odd.
     /// Sigma must be > 0.
@@ -81,33 +116,57 @@ impl GaussianBlurParams {
     }
 
     /// Sigma must be > 0 and not equal to `0.8`.
+    ///
+    /// Invalid sigma is reported by blur functions, see [GaussianBlurParams::try_new_from_sigma]
+    /// to validate it immediately.
     pub fn new_from_sigma(sigma: f64) -> GaussianBlurParams {
-        assert!(sigma > 0.);
         let kernel_size = kernel_size_d(sigma);
         Self::new(kernel_size, sigma)
     }
 
+    /// Sigma must be > 0 and finite.
+    pub fn try_new_from_sigma(sigma: f64) -> Result<GaussianBlurParams, BlurError> {
+        check_sigma("sigma", sigma)?;
+        Ok(Self::new_from_sigma(sigma))
+    }
+
     /// Kernel must be > 0.
     /// Kernel will be rounded to nearest odd, it is safe to pass any kernel here.
+    ///
+    /// Invalid kernel is reported by blur functions, see [GaussianBlurParams::try_new_from_kernel]
+    /// to validate it immediately.
     pub fn new_from_kernel(kernel: f64) -> GaussianBlurParams {
-        assert!(kernel > 0.);
-        let sigma = sigma_size_d(kernel);
-        Self::new(round_to_nearest_odd(kernel) as u32, sigma)
+        let (kernel, sigma) = kernel_and_sigma(kernel);
+        Self::new(kernel, sigma)
+    }
+
+    /// Kernel must be > 0 and finite.
+    /// Kernel will be rounded to nearest odd, it is safe to pass any kernel here.
+    pub fn try_new_from_kernel(kernel: f64) -> Result<GaussianBlurParams, BlurError> {
+        check_kernel("kernel", kernel)?;
+        Ok(Self::new_from_kernel(kernel))
     }
 
     /// Kernel must be > 0.
     /// Kernel will be rounded to nearest odd, it is safe to pass any kernel here.
+    ///
+    /// Invalid kernels are reported by blur functions,
+    /// see [GaussianBlurParams::try_new_asymmetric_from_kernels] to validate them immediately.
     pub fn new_asymmetric_from_kernels(x_kernel: f64, y_kernel: f64) -> GaussianBlurParams {
-        assert!(x_kernel > 0.);
-        assert!(y_kernel > 0.);
-        let x_sigma = sigma_size_d(x_kernel);
-        let y_sigma = sigma_size_d(y_kernel);
-        Self::new_asymmetric(
-            round_to_nearest_odd(x_kernel) as u32,
-            x_sigma,
-            round_to_nearest_odd(y_kernel) as u32,
-            y_sigma,
-        )
+        let (x_kernel, x_sigma) = kernel_and_sigma(x_kernel);
+        let (y_kernel, y_sigma) = kernel_and_sigma(y_kernel);
+        Self::new_asymmetric(x_kernel, x_sigma, y_kernel, y_sigma)
+    }
+
+    /// Kernels must be > 0 and finite.
+    /// Kernel will be rounded to nearest odd, it is safe to pass any kernel here.
+    pub fn try_new_asymmetric_from_kernels(
+        x_kernel: f64,
+        y_kernel: f64,
+    ) -> Result<GaussianBlurParams, BlurError> {
+        check_kernel("x_kernel", x_kernel)?;
+        check_kernel("y_kernel", y_kernel)?;
+        Ok(Self::new_asymmetric_from_kernels(x_kernel, y_kernel))
     }
 
     /// Kernel expected to be odd.
@@ -136,6 +195,16 @@ impl GaussianBlurParams {
         }
     }
 
+    /// Sigmas must be > 0 and finite.
+    pub fn try_new_asymmetric_from_sigma(
+        x_sigma: f64,
+        y_sigma: f64,
+    ) -> Result<GaussianBlurParams, BlurError> {
+        check_sigma("x_sigma", x_sigma)?;
+        check_sigma("y_sigma", y_sigma)?;
+        Ok(Self::new_asymmetric_from_sigma(x_sigma, y_sigma))
+    }
+
     fn make_f32_kernel(&self, kernel_size: u32, sigma: f32) -> Vec<f32> {
         assert!(
             kernel_size != 0 || sigma > 0.0,
@@ -178,19 +247,47 @@ impl GaussianBlurParams {
         gaussian_kernel_1d_f64(kernel_size, sigma)
     }
 
-    fn make_f32_kernels(&self) -> (Vec<f32>, Vec<f32>) {
+    pub(crate) fn make_f32_kernels(&self) -> (Vec<f32>, Vec<f32>) {
         let vx_kernel = self.make_f32_kernel(self.x_kernel, self.x_sigma as f32);
         let vy_kernel = self.make_f32_kernel(self.y_kernel, self.y_sigma as f32);
         (vx_kernel, vy_kernel)
     }
 
-    fn make_f64_kernels(&self) -> (Vec<f64>, Vec<f64>) {
+    pub(crate) fn make_f64_kernels(&self) -> (Vec<f64>, Vec<f64>) {
         let vx_kernel = self.make_f64_kernel(self.x_kernel, self.x_sigma);
         let vy_kernel = self.make_f64_kernel(self.y_kernel, self.y_sigma);
         (vx_kernel, vy_kernel)
     }
 
-    fn validate(&self) -> Result<(), BlurError> {
+    pub(crate) fn validate(&self) -> Result<(), BlurError> {
+        if !self.x_sigma.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name: "x_sigma",
+                value: self.x_sigma,
+            });
+        }
+        if !self.y_sigma.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name: "y_sigma",
+                value: self.y_sigma,
+            });
+        }
+        for (name, kernel, sigma) in [
+            ("x_kernel", self.x_kernel, self.x_sigma),
+            ("y_kernel", self.y_kernel, self.y_sigma),
+        ] {
+            let kernel = if kernel == 0 {
+                kernel_size_d(sigma)
+            } else {
+                kernel
+            };
+            if kernel > MAX_KERNEL_SIZE {
+                return Err(BlurError::InvalidParameter {
+                    name,
+                    value: kernel as f64,
+                });
+            }
+        }
         if self.x_sigma < 0. || self.y_sigma < 0. {
             return Err(BlurError::NegativeOrZeroSigma);
         }
@@ -225,10 +322,11 @@ impl GaussianBlurParams {
 /// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
 /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
 /// * `hint` - see [ConvolutionMode] for more info.
+/// * `alpha_mode` - Alpha layout of [crate::FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided.
-/// Panics if sigma = 0.8 and kernel size = 0.
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or if [GaussianBlurParams] are invalid.
 pub fn gaussian_blur(
     src: &BlurImage<u8>,
     dst: &mut BlurImageMut<u8>,
@@ -236,47 +334,21 @@ pub fn gaussian_blur(
     edge_modes: EdgeMode2D,
     threading_policy: ThreadingPolicy,
     hint: ConvolutionMode,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
-    params.validate()?;
-    let (x_kernel, y_kernel) = params.make_f32_kernels();
-    match hint {
-        ConvolutionMode::Exact => {
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_exact::<u8, f32, 1>,
-                FastBlurChannels::Channels3 => filter_1d_exact::<u8, f32, 3>,
-                FastBlurChannels::Channels4 => filter_1d_exact::<u8, f32, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )?;
-        }
-        ConvolutionMode::FixedPoint => {
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_approx::<u8, f32, i32, 1>,
-                FastBlurChannels::Channels3 => filter_1d_approx::<u8, f32, i32, 3>,
-                FastBlurChannels::Channels4 => filter_1d_approx::<u8, f32, i32, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )?;
-        }
-    }
-    Ok(())
+    GaussianPlan::<u8>::new(
+        src.size(),
+        src.channels,
+        params,
+        edge_modes,
+        threading_policy,
+        hint,
+        alpha_mode,
+    )?
+    .execute(src, dst)
 }
 
 /// Performs gaussian blur on the image.
@@ -294,13 +366,14 @@ pub fn gaussian_blur(
 /// * `edge_mode` - Rule to handle edge mode, sse [EdgeMode] for more info.
 /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
 /// * `hint` - see [ConvolutionMode] for more info.
+/// * `alpha_mode` - Alpha layout of [crate::FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
 /// This method always clamp into [0, 65535], if other bit-depth is used
 /// consider additional clamp into required range.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided.
-/// Panics if sigma = 0.8 and kernel size = 0.
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or if [GaussianBlurParams] are invalid.
 pub fn gaussian_blur_u16(
     src: &BlurImage<u16>,
     dst: &mut BlurImageMut<u16>,
@@ -308,47 +381,21 @@ pub fn gaussian_blur_u16(
     edge_modes: EdgeMode2D,
     threading_policy: ThreadingPolicy,
     hint: ConvolutionMode,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
-    params.validate()?;
-    let (x_kernel, y_kernel) = params.make_f32_kernels();
-    match hint {
-        ConvolutionMode::Exact => {
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_exact::<u16, f32, 1>,
-                FastBlurChannels::Channels3 => filter_1d_exact::<u16, f32, 3>,
-                FastBlurChannels::Channels4 => filter_1d_exact::<u16, f32, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )
-        }
-        ConvolutionMode::FixedPoint => {
-            use crate::filter1d::filter_1d_approx;
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_approx::<u16, f32, u32, 1>,
-                FastBlurChannels::Channels3 => filter_1d_approx::<u16, f32, u32, 3>,
-                FastBlurChannels::Channels4 => filter_1d_approx::<u16, f32, u32, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )
-        }
-    }
+    GaussianPlan::<u16>::new(
+        src.size(),
+        src.channels,
+        params,
+        edge_modes,
+        threading_policy,
+        hint,
+        alpha_mode,
+    )?
+    .execute(src, dst)
 }
 
 /// Performs gaussian blur on the image.
@@ -366,10 +413,11 @@ pub fn gaussian_blur_u16(
 /// * `edge_modes` - Rule to handle edge mode, sse [EdgeMode] and [EdgeMode2D] for more info.
 /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
 /// * `convolution_mode` - See [IeeeBinaryConvolutionMode] for more info.
+/// * `alpha_mode` - Alpha layout of [crate::FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided.
-/// Panics if sigma = 0.8 and kernel size = 0.
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or if [GaussianBlurParams] are invalid.
 pub fn gaussian_blur_f32(
     src: &BlurImage<f32>,
     dst: &mut BlurImageMut<f32>,
@@ -377,47 +425,21 @@ pub fn gaussian_blur_f32(
     edge_modes: EdgeMode2D,
     threading_policy: ThreadingPolicy,
     convolution_mode: IeeeBinaryConvolutionMode,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
-    params.validate()?;
-    match convolution_mode {
-        IeeeBinaryConvolutionMode::Normal => {
-            let (x_kernel, y_kernel) = params.make_f32_kernels();
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_exact::<f32, f32, 1>,
-                FastBlurChannels::Channels3 => filter_1d_exact::<f32, f32, 3>,
-                FastBlurChannels::Channels4 => filter_1d_exact::<f32, f32, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )
-        }
-        IeeeBinaryConvolutionMode::Zealous => {
-            let (x_kernel, y_kernel) = params.make_f64_kernels();
-            let _dispatcher = match src.channels {
-                FastBlurChannels::Plane => filter_1d_exact::<f32, f64, 1>,
-                FastBlurChannels::Channels3 => filter_1d_exact::<f32, f64, 3>,
-                FastBlurChannels::Channels4 => filter_1d_exact::<f32, f64, 4>,
-            };
-            _dispatcher(
-                src,
-                dst,
-                &x_kernel,
-                &y_kernel,
-                edge_modes,
-                Scalar::default(),
-                threading_policy,
-            )
-        }
-    }
+    GaussianPlan::<f32>::new(
+        src.size(),
+        src.channels,
+        params,
+        edge_modes,
+        threading_policy,
+        convolution_mode,
+        alpha_mode,
+    )?
+    .execute(src, dst)
 }
 
 /// Performs gaussian blur on the image.
@@ -435,9 +457,9 @@ pub fn gaussian_blur_f32(
 /// * `edge_modes` - Rule to handle edge mode, sse [EdgeMode] and [EdgeMode2D] for more info.
 /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
-/// Panics if sigma = 0.8 and kernel size = 0.
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or if [GaussianBlurParams] are invalid.
 #[cfg(feature = "nightly_f16")]
 #[cfg_attr(docsrs, doc(cfg(feature = "nightly_f16")))]
 pub fn gaussian_blur_f16(
@@ -450,29 +472,22 @@ pub fn gaussian_blur_f16(
     src.check_layout()?;
     dst.check_layout(Some(src))?;
     src.size_matches_mut(dst)?;
-    params.validate()?;
-    let (x_kernel, y_kernel) = params.make_f32_kernels();
-    let _dispatcher = match src.channels {
-        FastBlurChannels::Plane => filter_1d_exact::<f16, f32, 1>,
-        FastBlurChannels::Channels3 => filter_1d_exact::<f16, f32, 3>,
-        FastBlurChannels::Channels4 => filter_1d_exact::<f16, f32, 4>,
-    };
-    _dispatcher(
-        src,
-        dst,
-        &x_kernel,
-        &y_kernel,
+    GaussianPlan::<f16>::new(
+        src.size(),
+        src.channels,
+        params,
         edge_modes,
-        Scalar::default(),
         threading_policy,
-    )
+    )?
+    .execute(src, dst)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::EdgeMode;
-    use crate::{gaussian_kernel_1d_f64, sigma_size_d};
+    use crate::{
+        EdgeMode, FastBlurChannels, Scalar, filter_1d_exact, gaussian_kernel_1d_f64, sigma_size_d,
+    };
 
     macro_rules! compare_u8_stat {
         ($dst: expr) => {
@@ -520,6 +535,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst);
@@ -549,6 +565,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         println!("{}", dst.data.borrow_mut()[0]);
@@ -579,6 +596,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst);
@@ -608,6 +626,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst);
@@ -637,6 +656,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst);
@@ -666,6 +686,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u8_stat!(dst);
@@ -717,6 +738,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u16_stat!(dst);
@@ -746,6 +768,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_u16_stat!(dst);
@@ -797,6 +820,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             IeeeBinaryConvolutionMode::Normal,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_f32_stat!(dst);
@@ -808,6 +832,7 @@ mod tests {
             EdgeMode2D::new(EdgeMode::Clamp),
             ThreadingPolicy::Single,
             IeeeBinaryConvolutionMode::Zealous,
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         compare_f32_stat!(dst);
@@ -845,4 +870,29 @@ mod tests {
         .unwrap();
         compare_f32_stat!(dst);
     }
+
+    #[test]
+    fn test_gauss_invalid_kernel_reported_by_blur() {
+        let src = vec![0u8; 8 * 8];
+        let src_image = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Plane);
+        for params in [
+            GaussianBlurParams::new_from_kernel(0.),
+            GaussianBlurParams::new_from_kernel(-3.),
+            GaussianBlurParams::new_from_kernel(f64::NAN),
+            GaussianBlurParams::new_asymmetric_from_kernels(3., f64::INFINITY),
+            GaussianBlurParams::new_from_kernel(1e12),
+        ] {
+            let mut dst = BlurImageMut::default();
+            let result = gaussian_blur(
+                &src_image,
+                &mut dst,
+                params,
+                EdgeMode::Clamp.as_2d(),
+                ThreadingPolicy::Single,
+                ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
+            );
+            assert!(result.is_err(), "{params:?}");
+        }
+    }
 }
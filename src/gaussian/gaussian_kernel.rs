@@ -25,13 +25,20 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
 use num_complex::Complex;
 
 pub fn gaussian_kernel_1d(width: u32, sigma: f32) -> Vec<f32> {
-    let mut sum_norm: f32 = 0.;
     let mut kernel: Vec<f32> = vec![0.; width as usize];
+    fill_gaussian_kernel_1d(&mut kernel, sigma);
+    kernel
+}
+
+/// Writes normalized gaussian kernel of `kernel.len()` size into `kernel`
+pub(crate) fn fill_gaussian_kernel_1d(kernel: &mut [f32], sigma: f32) {
+    let mut sum_norm: f32 = 0.;
     let scale = 1f32 / (f32::sqrt(2. * std::f32::consts::PI) * sigma);
-    let mean = (width / 2) as f32;
+    let mean = (kernel.len() / 2) as f32;
 
     for (x, item) in kernel.iter_mut().enumerate() {
         let dx = (x as f32 - mean) / sigma;
@@ -46,8 +53,6 @@ pub fn gaussian_kernel_1d(width: u32, sigma: f32) -> Vec<f32> {
             *item *= sum_scale;
         }
     }
-
-    kernel
 }
 
 pub fn gaussian_kernel_1d_f64(width: u32, sigma: f64) -> Vec<f64> {
@@ -74,12 +79,25 @@ pub fn gaussian_kernel_1d_f64(width: u32, sigma: f64) -> Vec<f64> {
 }
 
 /// Regular gaussian kernel with phase shift.
-pub fn complex_gaussian_kernel(radius: f64, scale: f64, distortion: f64) -> Vec<Complex<f32>> {
-    if radius < 1. {
-        panic!("Radius cannot be less than 1");
+///
+/// Radius must be finite and at least 1.
+pub fn complex_gaussian_kernel(
+    radius: f64,
+    scale: f64,
+    distortion: f64,
+) -> Result<Vec<Complex<f32>>, BlurError> {
+    if !radius.is_finite() || radius < 1. {
+        return Err(BlurError::InvalidParameter {
+            name: "radius",
+            value: radius,
+        });
     }
     let kernel_radius = radius.ceil() as usize;
-    let mut kernel: Vec<Complex<f64>> = vec![Complex::new(0.0, 0.0); 1 + 2 * (kernel_radius)];
+    let kernel_size = kernel_radius
+        .checked_mul(2)
+        .and_then(|x| x.checked_add(1))
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let mut kernel: Vec<Complex<f64>> = vec![Complex::new(0.0, 0.0); kernel_size];
 
     for (x, dst) in kernel.iter_mut().enumerate() {
         let ax = (x as f64 - radius) * scale / radius;
@@ -93,7 +111,7 @@ pub fn complex_gaussian_kernel(radius: f64, scale: f64, distortion: f64) -> Vec<
     }
 
     let sum: f64 = kernel.iter().map(|z| z.norm_sqr()).sum::<f64>();
-    if sum != 0.0 {
+    Ok(if sum != 0.0 {
         kernel
             .iter()
             .map(|z| Complex {
@@ -109,5 +127,5 @@ pub fn complex_gaussian_kernel(radius: f64, scale: f64, distortion: f64) -> Vec<
                 im: x.im as f32,
             })
             .collect()
-    }
+    })
 }
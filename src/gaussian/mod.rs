@@ -29,10 +29,13 @@ mod declaration;
 mod gaussian_hint;
 mod gaussian_kernel;
 mod gaussian_util;
+mod plan;
 
 #[cfg(feature = "nightly_f16")]
 pub use declaration::gaussian_blur_f16;
 pub use declaration::{GaussianBlurParams, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16};
 pub use gaussian_hint::{ConvolutionMode, IeeeBinaryConvolutionMode};
+pub(crate) use gaussian_kernel::fill_gaussian_kernel_1d;
 pub use gaussian_kernel::{complex_gaussian_kernel, gaussian_kernel_1d, gaussian_kernel_1d_f64};
 pub use gaussian_util::{sigma_size, sigma_size_d};
+pub use plan::GaussianPlan;
@@ -0,0 +1,585 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::{ApproxHandlers, ApproxKernels, filter_1d_approx_prepared};
+use crate::gaussian::declaration::GaussianBlurParams;
+use crate::premultiply::{premultiply_into_workspace, unpremultiply_in_place};
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, BlurWorkspace, ConvolutionMode, EdgeMode2D,
+    FastBlurChannels, IeeeBinaryConvolutionMode, ImageSize, Scalar, ThreadingPolicy,
+    filter_1d_exact_with_workspace,
+};
+#[cfg(feature = "nightly_f16")]
+use core::f16;
+
+#[derive(Clone)]
+enum GaussianPlanKernels<T> {
+    Exact(Vec<f32>, Vec<f32>),
+    Zealous(Vec<f64>, Vec<f64>),
+    FixedPoint(ApproxKernels<i32>, ApproxHandlers<T>),
+    FixedPointU32(ApproxKernels<u32>, ApproxHandlers<T>),
+}
+
+macro_rules! approx_handlers {
+    ($channels:expr, $kernels:expr) => {{
+        match $channels {
+            FastBlurChannels::Plane => ApproxHandlers::new::<_, 1>($kernels),
+            FastBlurChannels::Channels2 => ApproxHandlers::new::<_, 2>($kernels),
+            FastBlurChannels::Channels3 => ApproxHandlers::new::<_, 3>($kernels),
+            FastBlurChannels::Channels4 => ApproxHandlers::new::<_, 4>($kernels),
+        }
+    }};
+}
+
+/// Reusable gaussian blur for a fixed image size and parameters.
+///
+/// Kernels, including fixed point weights and their row and column handlers, are computed
+/// once when plan is created, intermediate buffers are kept in [BlurWorkspace] owned by the plan.
+/// With [AlphaMode::Straight] premultiplied copy of the source is kept there as well.
+/// With [ThreadingPolicy::Single] executing the plan repeatedly, e.g. for every frame of a video,
+/// does not allocate after the first execution. Multithreaded executions reuse the same
+/// buffers, but dispatching work to threads may allocate, see [crate::with_executor].
+///
+/// # Examples
+///
+/// ```
+/// use libblur::{AlphaMode, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode, FastBlurChannels,
+///     GaussianBlurParams, GaussianPlan, ImageSize, ThreadingPolicy};
+/// let frame = vec![0u8; 64 * 48 * 3];
+/// let src = BlurImage::borrow(&frame, 64, 48, FastBlurChannels::Channels3);
+/// let mut dst = BlurImageMut::alloc(64, 48, FastBlurChannels::Channels3);
+/// let mut plan = GaussianPlan::<u8>::new(
+///     ImageSize::new(64, 48),
+///     FastBlurChannels::Channels3,
+///     GaussianBlurParams::new_from_sigma(2.),
+///     EdgeMode::Clamp.as_2d(),
+///     ThreadingPolicy::Single,
+///     ConvolutionMode::FixedPoint,
+///     AlphaMode::Premultiplied,
+/// )
+/// .unwrap();
+/// for _ in 0..3 {
+///     plan.execute(&src, &mut dst).unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct GaussianPlan<T> {
+    size: ImageSize,
+    channels: FastBlurChannels,
+    edge_modes: EdgeMode2D,
+    threading_policy: ThreadingPolicy,
+    kernels: GaussianPlanKernels<T>,
+    alpha_mode: AlphaMode,
+    workspace: BlurWorkspace<T>,
+    /// Premultiplied source for [AlphaMode::Straight]
+    premultiplied: BlurWorkspace<T>,
+}
+
+impl<T: Copy + Default> GaussianPlan<T> {
+    fn new_with_kernels(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        edge_modes: EdgeMode2D,
+        threading_policy: ThreadingPolicy,
+        kernels: GaussianPlanKernels<T>,
+        alpha_mode: AlphaMode,
+    ) -> Result<GaussianPlan<T>, BlurError> {
+        if size.width == 0 || size.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        Ok(GaussianPlan {
+            size,
+            channels,
+            edge_modes,
+            threading_policy,
+            kernels,
+            alpha_mode,
+            workspace: BlurWorkspace::new(),
+            premultiplied: BlurWorkspace::new(),
+        })
+    }
+
+    /// Image size this plan was created for.
+    pub fn size(&self) -> ImageSize {
+        self.size
+    }
+
+    /// Channels layout this plan was created for.
+    pub fn channels(&self) -> FastBlurChannels {
+        self.channels
+    }
+
+    fn check_images(&self, src: &BlurImage<T>, dst: &mut BlurImageMut<T>) -> Result<(), BlurError>
+    where
+        T: std::fmt::Debug,
+    {
+        src.check_layout()?;
+        dst.check_layout(Some(src))?;
+        src.size_matches_mut(dst)?;
+        if src.size() != self.size || src.channels != self.channels {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        Ok(())
+    }
+
+    fn is_straight(&self) -> bool {
+        self.alpha_mode == AlphaMode::Straight && self.channels == FastBlurChannels::Channels4
+    }
+}
+
+macro_rules! execute_exact {
+    ($src:expr, $dst:expr, $plan:expr, $t:ty, $f:ty, $x:expr, $y:expr) => {{
+        let _dispatcher = match $plan.channels {
+            FastBlurChannels::Plane => filter_1d_exact_with_workspace::<$t, $f, 1>,
+            FastBlurChannels::Channels2 => filter_1d_exact_with_workspace::<$t, $f, 2>,
+            FastBlurChannels::Channels3 => filter_1d_exact_with_workspace::<$t, $f, 3>,
+            FastBlurChannels::Channels4 => filter_1d_exact_with_workspace::<$t, $f, 4>,
+        };
+        _dispatcher(
+            $src,
+            $dst,
+            $x,
+            $y,
+            $plan.edge_modes,
+            Scalar::default(),
+            $plan.threading_policy,
+            &mut $plan.workspace,
+        )
+    }};
+}
+
+macro_rules! execute_fixed_point {
+    ($src:expr, $dst:expr, $plan:expr, $t:ty, $i:ty, $kernels:expr, $handlers:expr) => {{
+        let _dispatcher = match $plan.channels {
+            FastBlurChannels::Plane => filter_1d_approx_prepared::<$t, $i, 1>,
+            FastBlurChannels::Channels2 => filter_1d_approx_prepared::<$t, $i, 2>,
+            FastBlurChannels::Channels3 => filter_1d_approx_prepared::<$t, $i, 3>,
+            FastBlurChannels::Channels4 => filter_1d_approx_prepared::<$t, $i, 4>,
+        };
+        _dispatcher(
+            $src,
+            $dst,
+            $kernels,
+            $handlers,
+            $plan.edge_modes,
+            Scalar::default(),
+            $plan.threading_policy,
+            &mut $plan.workspace,
+        )
+    }};
+}
+
+impl GaussianPlan<u8> {
+    /// Creates gaussian blur plan for 8-bit images.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the images plan will be executed on.
+    /// * `channels` - Channels layout, see [FastBlurChannels].
+    /// * `params` - See [GaussianBlurParams] for more info.
+    /// * `edge_modes` - Rule to handle edge mode, sse [crate::EdgeMode] and [EdgeMode2D] for more info.
+    /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+    /// * `hint` - see [ConvolutionMode] for more info.
+    /// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+    ///
+    /// # Errors
+    /// Returns an error if [GaussianBlurParams] are invalid or size is zero.
+    pub fn new(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        params: GaussianBlurParams,
+        edge_modes: EdgeMode2D,
+        threading_policy: ThreadingPolicy,
+        hint: ConvolutionMode,
+        alpha_mode: AlphaMode,
+    ) -> Result<GaussianPlan<u8>, BlurError> {
+        params.validate()?;
+        let (x_kernel, y_kernel) = params.make_f32_kernels();
+        let kernels = match hint {
+            ConvolutionMode::Exact => GaussianPlanKernels::Exact(x_kernel, y_kernel),
+            ConvolutionMode::FixedPoint => {
+                let kernels = ApproxKernels::new(&x_kernel, &y_kernel);
+                let handlers = approx_handlers!(channels, &kernels);
+                GaussianPlanKernels::FixedPoint(kernels, handlers)
+            }
+        };
+        Self::new_with_kernels(
+            size,
+            channels,
+            edge_modes,
+            threading_policy,
+            kernels,
+            alpha_mode,
+        )
+    }
+
+    /// Blurs `src` into `dst` using precomputed kernels and owned scratch memory.
+    ///
+    /// # Errors
+    /// Returns an error if stride/width/height/channel configuration do not match
+    /// the plan.
+    pub fn execute(
+        &mut self,
+        src: &BlurImage<u8>,
+        dst: &mut BlurImageMut<u8>,
+    ) -> Result<(), BlurError> {
+        self.check_images(src, dst)?;
+        let straight = self.is_straight();
+        let premultiplied;
+        let src = if straight {
+            premultiplied = premultiply_into_workspace(src, &mut self.premultiplied)?;
+            &premultiplied
+        } else {
+            src
+        };
+        match &self.kernels {
+            GaussianPlanKernels::Exact(x, y) => execute_exact!(src, dst, self, u8, f32, x, y),
+            GaussianPlanKernels::FixedPoint(kernels, handlers) => {
+                execute_fixed_point!(src, dst, self, u8, i32, kernels, handlers)
+            }
+            _ => unreachable!("Plan for u8 is always built with f32 or i32 kernels"),
+        }?;
+        if straight {
+            unpremultiply_in_place(dst)?;
+        }
+        Ok(())
+    }
+}
+
+impl GaussianPlan<u16> {
+    /// Creates gaussian blur plan for 16-bit images.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the images plan will be executed on.
+    /// * `channels` - Channels layout, see [FastBlurChannels].
+    /// * `params` - See [GaussianBlurParams] for more info.
+    /// * `edge_modes` - Rule to handle edge mode, sse [crate::EdgeMode] and [EdgeMode2D] for more info.
+    /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+    /// * `hint` - see [ConvolutionMode] for more info.
+    /// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+    ///
+    /// # Errors
+    /// Returns an error if [GaussianBlurParams] are invalid or size is zero.
+    pub fn new(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        params: GaussianBlurParams,
+        edge_modes: EdgeMode2D,
+        threading_policy: ThreadingPolicy,
+        hint: ConvolutionMode,
+        alpha_mode: AlphaMode,
+    ) -> Result<GaussianPlan<u16>, BlurError> {
+        params.validate()?;
+        let (x_kernel, y_kernel) = params.make_f32_kernels();
+        let kernels = match hint {
+            ConvolutionMode::Exact => GaussianPlanKernels::Exact(x_kernel, y_kernel),
+            ConvolutionMode::FixedPoint => {
+                let kernels = ApproxKernels::new(&x_kernel, &y_kernel);
+                let handlers = approx_handlers!(channels, &kernels);
+                GaussianPlanKernels::FixedPointU32(kernels, handlers)
+            }
+        };
+        Self::new_with_kernels(
+            size,
+            channels,
+            edge_modes,
+            threading_policy,
+            kernels,
+            alpha_mode,
+        )
+    }
+
+    /// Blurs `src` into `dst` using precomputed kernels and owned scratch memory.
+    ///
+    /// This method always clamp into [0, 65535], if other bit-depth is used
+    /// consider additional clamp into required range.
+    ///
+    /// # Errors
+    /// Returns an error if stride/width/height/channel configuration do not match
+    /// the plan.
+    pub fn execute(
+        &mut self,
+        src: &BlurImage<u16>,
+        dst: &mut BlurImageMut<u16>,
+    ) -> Result<(), BlurError> {
+        self.check_images(src, dst)?;
+        let straight = self.is_straight();
+        let premultiplied;
+        let src = if straight {
+            premultiplied = premultiply_into_workspace(src, &mut self.premultiplied)?;
+            &premultiplied
+        } else {
+            src
+        };
+        match &self.kernels {
+            GaussianPlanKernels::Exact(x, y) => execute_exact!(src, dst, self, u16, f32, x, y),
+            GaussianPlanKernels::FixedPointU32(kernels, handlers) => {
+                execute_fixed_point!(src, dst, self, u16, u32, kernels, handlers)
+            }
+            _ => unreachable!("Plan for u16 is always built with f32 or u32 kernels"),
+        }?;
+        if straight {
+            unpremultiply_in_place(dst)?;
+        }
+        Ok(())
+    }
+}
+
+impl GaussianPlan<f32> {
+    /// Creates gaussian blur plan for `f32` images.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the images plan will be executed on.
+    /// * `channels` - Channels layout, see [FastBlurChannels].
+    /// * `params` - See [GaussianBlurParams] for more info.
+    /// * `edge_modes` - Rule to handle edge mode, sse [crate::EdgeMode] and [EdgeMode2D] for more info.
+    /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+    /// * `convolution_mode` - See [IeeeBinaryConvolutionMode] for more info.
+    /// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+    ///
+    /// # Errors
+    /// Returns an error if [GaussianBlurParams] are invalid or size is zero.
+    pub fn new(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        params: GaussianBlurParams,
+        edge_modes: EdgeMode2D,
+        threading_policy: ThreadingPolicy,
+        convolution_mode: IeeeBinaryConvolutionMode,
+        alpha_mode: AlphaMode,
+    ) -> Result<GaussianPlan<f32>, BlurError> {
+        params.validate()?;
+        let kernels = match convolution_mode {
+            IeeeBinaryConvolutionMode::Normal => {
+                let (x_kernel, y_kernel) = params.make_f32_kernels();
+                GaussianPlanKernels::Exact(x_kernel, y_kernel)
+            }
+            IeeeBinaryConvolutionMode::Zealous => {
+                let (x_kernel, y_kernel) = params.make_f64_kernels();
+                GaussianPlanKernels::Zealous(x_kernel, y_kernel)
+            }
+        };
+        Self::new_with_kernels(
+            size,
+            channels,
+            edge_modes,
+            threading_policy,
+            kernels,
+            alpha_mode,
+        )
+    }
+
+    /// Blurs `src` into `dst` using precomputed kernels and owned scratch memory.
+    ///
+    /// # Errors
+    /// Returns an error if stride/width/height/channel configuration do not match
+    /// the plan.
+    pub fn execute(
+        &mut self,
+        src: &BlurImage<f32>,
+        dst: &mut BlurImageMut<f32>,
+    ) -> Result<(), BlurError> {
+        self.check_images(src, dst)?;
+        let straight = self.is_straight();
+        let premultiplied;
+        let src = if straight {
+            premultiplied = premultiply_into_workspace(src, &mut self.premultiplied)?;
+            &premultiplied
+        } else {
+            src
+        };
+        match &self.kernels {
+            GaussianPlanKernels::Exact(x, y) => execute_exact!(src, dst, self, f32, f32, x, y),
+            GaussianPlanKernels::Zealous(x, y) => execute_exact!(src, dst, self, f32, f64, x, y),
+            _ => unreachable!("Plan for f32 is always built with f32 or f64 kernels"),
+        }?;
+        if straight {
+            unpremultiply_in_place(dst)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "nightly_f16")]
+#[cfg_attr(docsrs, doc(cfg(feature = "nightly_f16")))]
+impl GaussianPlan<f16> {
+    /// Creates gaussian blur plan for `f16` images.
+    ///
+    /// Alpha of [FastBlurChannels::Channels4] images is expected to be premultiplied.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size of the images plan will be executed on.
+    /// * `channels` - Channels layout, see [FastBlurChannels].
+    /// * `params` - See [GaussianBlurParams] for more info.
+    /// * `edge_modes` - Rule to handle edge mode, sse [crate::EdgeMode] and [EdgeMode2D] for more info.
+    /// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+    ///
+    /// # Errors
+    /// Returns an error if [GaussianBlurParams] are invalid or size is zero.
+    pub fn new(
+        size: ImageSize,
+        channels: FastBlurChannels,
+        params: GaussianBlurParams,
+        edge_modes: EdgeMode2D,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<GaussianPlan<f16>, BlurError> {
+        params.validate()?;
+        let (x_kernel, y_kernel) = params.make_f32_kernels();
+        Self::new_with_kernels(
+            size,
+            channels,
+            edge_modes,
+            threading_policy,
+            GaussianPlanKernels::Exact(x_kernel, y_kernel),
+            AlphaMode::Premultiplied,
+        )
+    }
+
+    /// Blurs `src` into `dst` using precomputed kernels and owned scratch memory.
+    ///
+    /// # Errors
+    /// Returns an error if stride/width/height/channel configuration do not match
+    /// the plan.
+    pub fn execute(
+        &mut self,
+        src: &BlurImage<f16>,
+        dst: &mut BlurImageMut<f16>,
+    ) -> Result<(), BlurError> {
+        self.check_images(src, dst)?;
+        match &self.kernels {
+            GaussianPlanKernels::Exact(x, y) => execute_exact!(src, dst, self, f16, f32, x, y),
+            _ => unreachable!("Plan for f16 is always built with f32 kernels"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdgeMode, gaussian_blur};
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_gaussian_plan_reuse_matches_gaussian_blur() {
+        let width = 67usize;
+        let height = 53usize;
+        let mut seed = 0x2545f491u32;
+        let src = (0..width * height * 3)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 24) as u8
+            })
+            .collect::<Vec<u8>>();
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for kernel in [5., 71.] {
+            for hint in [ConvolutionMode::Exact, ConvolutionMode::FixedPoint] {
+                for policy in [
+                    ThreadingPolicy::Single,
+                    ThreadingPolicy::Fixed(NonZeroUsize::new(3).unwrap()),
+                ] {
+                    let params = GaussianBlurParams::new_from_kernel(kernel);
+                    let edge_modes = EdgeMode::Reflect101.as_2d();
+                    let mut reference = BlurImageMut::default();
+                    gaussian_blur(
+                        &src_image,
+                        &mut reference,
+                        params,
+                        edge_modes,
+                        ThreadingPolicy::Single,
+                        hint,
+                        AlphaMode::Premultiplied,
+                    )
+                    .unwrap();
+                    let mut plan = GaussianPlan::<u8>::new(
+                        src_image.size(),
+                        FastBlurChannels::Channels3,
+                        params,
+                        edge_modes,
+                        policy,
+                        hint,
+                        AlphaMode::Premultiplied,
+                    )
+                    .unwrap();
+                    let mut dst = BlurImageMut::alloc(
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels3,
+                    );
+                    plan.execute(&src_image, &mut dst).unwrap();
+                    let capacity = plan.workspace.capacity();
+                    assert_ne!(capacity, 0);
+                    plan.execute(&src_image, &mut dst).unwrap();
+                    assert_eq!(plan.workspace.capacity(), capacity);
+                    assert_eq!(
+                        dst.data.borrow(),
+                        reference.data.borrow(),
+                        "Mismatch for kernel {kernel} and policy {policy}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gaussian_plan_rejects_other_size() {
+        let src = vec![0u8; 8 * 8];
+        let src_image = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::alloc(8, 8, FastBlurChannels::Plane);
+        let mut plan = GaussianPlan::<u8>::new(
+            ImageSize::new(8, 7),
+            FastBlurChannels::Plane,
+            GaussianBlurParams::new_from_sigma(1.),
+            EdgeMode::Clamp.as_2d(),
+            ThreadingPolicy::Single,
+            ConvolutionMode::FixedPoint,
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        assert!(plan.execute(&src_image, &mut dst).is_err());
+        assert!(
+            GaussianPlan::<f32>::new(
+                ImageSize::new(8, 8),
+                FastBlurChannels::Plane,
+                GaussianBlurParams::new(4, 0.),
+                EdgeMode::Clamp.as_2d(),
+                ThreadingPolicy::Single,
+                IeeeBinaryConvolutionMode::Normal,
+                AlphaMode::Premultiplied,
+            )
+            .is_err()
+        );
+    }
+}
@@ -27,9 +27,9 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::{
-    BlurImage, BlurImageMut, ConvolutionMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams,
-    IeeeBinaryConvolutionMode, ThreadingPolicy, gaussian_blur, gaussian_blur_f32,
-    gaussian_blur_u16,
+    AlphaMode, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode2D, FastBlurChannels,
+    GaussianBlurParams, IeeeBinaryConvolutionMode, ThreadingPolicy, gaussian_blur,
+    gaussian_blur_f32, gaussian_blur_u16,
 };
 use image::{
     DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage, RgbImage,
@@ -38,7 +38,8 @@ use image::{
 
 /// Performs clear gaussian blur on the image
 ///
-/// NOTE: Alpha must be associated if this image with alpha
+/// RGBA images are considered to have straight alpha, as the `image` crate stores them,
+/// they are premultiplied before the blur and unpremultiplied after it.
 ///
 /// # Arguments
 ///
@@ -70,6 +71,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
             let new_gray_image = GrayImage::from_raw(
@@ -80,84 +82,33 @@ pub fn gaussian_blur_image(
             Some(DynamicImage::ImageLuma8(new_gray_image))
         }
         DynamicImage::ImageLumaA8(luma_alpha_image) => {
-            let mut intensity_plane = BlurImageMut::alloc(
+            let gray_image = BlurImage::borrow(
+                &luma_alpha_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let mut alpha_plane = BlurImageMut::alloc(
+            let mut new_image = BlurImageMut::alloc(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let raw_buffer = luma_alpha_image.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .data
-                .borrow_mut()
-                .iter_mut()
-                .zip(alpha_plane.data.borrow_mut().iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let int = intensity_plane.to_immutable_ref();
-            let alp = alpha_plane.to_immutable_ref();
-
-            let mut new_intensity_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-            let mut new_alpha_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            gaussian_blur(
-                &int,
-                &mut new_intensity_plane,
-                params,
-                edge_modes,
-                threading_policy,
-                precise_level,
-            )
-            .ok()?;
 
             gaussian_blur(
-                &alp,
-                &mut new_alpha_plane,
+                &gray_image,
+                &mut new_image,
                 params,
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
 
-            let mut new_raw_buffer =
-                vec![
-                    0u8;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((intensity, alpha), raw_buffer) in new_intensity_plane
-                .data
-                .borrow()
-                .iter()
-                .zip(new_alpha_plane.data.borrow().iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
-
             let new_gray_image = GrayAlphaImage::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image.data.borrow().to_vec(),
             )?;
             Some(DynamicImage::ImageLumaA8(new_gray_image))
         }
@@ -174,6 +125,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
 
@@ -193,6 +145,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Straight,
             )
             .ok()?;
             let new_rgba_image =
@@ -212,6 +165,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
 
@@ -223,84 +177,33 @@ pub fn gaussian_blur_image(
             Some(DynamicImage::ImageLuma16(new_rgb_image))
         }
         DynamicImage::ImageLumaA16(luma_alpha_image) => {
-            let mut intensity_plane = BlurImageMut::alloc(
+            let gray_image = BlurImage::borrow(
+                &luma_alpha_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let mut alpha_plane = BlurImageMut::alloc(
+            let mut new_image = BlurImageMut::alloc(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-            let raw_buffer = luma_alpha_image.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .data
-                .borrow_mut()
-                .iter_mut()
-                .zip(alpha_plane.data.borrow_mut().iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let int = intensity_plane.to_immutable_ref();
-            let alp = alpha_plane.to_immutable_ref();
-
-            let mut new_intensity_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-            let mut new_alpha_plane = BlurImageMut::alloc(
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            gaussian_blur_u16(
-                &int,
-                &mut new_intensity_plane,
-                params,
-                edge_modes,
-                threading_policy,
-                precise_level,
-            )
-            .ok()?;
 
             gaussian_blur_u16(
-                &alp,
-                &mut new_alpha_plane,
+                &gray_image,
+                &mut new_image,
                 params,
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
 
-            let mut new_raw_buffer =
-                vec![
-                    0u16;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((intensity, alpha), raw_buffer) in new_intensity_plane
-                .data
-                .borrow()
-                .iter()
-                .zip(new_alpha_plane.data.borrow().iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = *intensity;
-                raw_buffer[1] = *alpha;
-            }
-
             let new_gray_image = ImageBuffer::<LumaA<u16>, Vec<u16>>::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image.data.borrow().to_vec(),
             )?;
             Some(DynamicImage::ImageLumaA16(new_gray_image))
         }
@@ -317,6 +220,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
 
@@ -339,6 +243,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 precise_level,
+                AlphaMode::Straight,
             )
             .ok()?;
 
@@ -361,6 +266,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 IeeeBinaryConvolutionMode::Normal,
+                AlphaMode::Premultiplied,
             )
             .ok()?;
             let new_rgb_image =
@@ -379,6 +285,7 @@ pub fn gaussian_blur_image(
                 edge_modes,
                 threading_policy,
                 IeeeBinaryConvolutionMode::Normal,
+                AlphaMode::Straight,
             )
             .ok()?;
             let new_rgb_image = Rgba32FImage::from_raw(
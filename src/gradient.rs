@@ -0,0 +1,714 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
+    filter_1d_exact,
+};
+use num_traits::AsPrimitive;
+use std::fmt::Debug;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares derivative kernel family
+pub enum GradientKernel {
+    /// Sobel operator, supports apertures 3, 5 and 7
+    #[default]
+    Sobel,
+    /// Scharr operator, aperture is always 3 and only first derivatives are supported
+    Scharr,
+}
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares norm used to compute gradient magnitude
+pub enum GradientNorm {
+    /// `|dx| + |dy|`
+    L1,
+    /// `sqrt(dx^2 + dy^2)`
+    #[default]
+    L2,
+}
+
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+/// Parameters of image derivative
+pub struct GradientParams {
+    /// Order of the derivative in horizontal direction
+    pub dx: u32,
+    /// Order of the derivative in vertical direction
+    pub dy: u32,
+    /// Aperture size, must be one of 3, 5, 7
+    pub aperture: u32,
+    /// See [GradientKernel] for more info
+    pub kernel: GradientKernel,
+    /// Scale factor applied to the computed derivative
+    pub scale: f32,
+}
+
+impl GradientParams {
+    /// Sobel derivative with aperture 3.
+    pub fn new(dx: u32, dy: u32) -> GradientParams {
+        GradientParams {
+            dx,
+            dy,
+            aperture: 3,
+            kernel: GradientKernel::Sobel,
+            scale: 1.,
+        }
+    }
+
+    /// Sobel derivative with provided aperture.
+    /// Aperture must be one of 3, 5, 7.
+    pub fn new_with_aperture(dx: u32, dy: u32, aperture: u32) -> GradientParams {
+        GradientParams {
+            dx,
+            dy,
+            aperture,
+            kernel: GradientKernel::Sobel,
+            scale: 1.,
+        }
+    }
+
+    /// Scharr derivative, `dx + dy` must be exactly 1.
+    pub fn scharr(dx: u32, dy: u32) -> GradientParams {
+        GradientParams {
+            dx,
+            dy,
+            aperture: 3,
+            kernel: GradientKernel::Scharr,
+            scale: 1.,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.dx + self.dy == 0 {
+            return Err(BlurError::InvalidArguments);
+        }
+        match self.kernel {
+            GradientKernel::Sobel => {
+                if !matches!(self.aperture, 3 | 5 | 7) {
+                    return Err(BlurError::InvalidArguments);
+                }
+                if self.dx >= self.aperture || self.dy >= self.aperture {
+                    return Err(BlurError::InvalidArguments);
+                }
+            }
+            GradientKernel::Scharr => {
+                if self.aperture != 3 || self.dx + self.dy != 1 {
+                    return Err(BlurError::InvalidArguments);
+                }
+            }
+        }
+        if !self.scale.is_finite() {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+fn convolve_polynomial(a: &[f32], b: &[f32]) -> Vec<f32> {
+    let mut result = vec![0f32; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+fn sobel_kernel_1d(order: u32, aperture: u32) -> Vec<f32> {
+    let mut kernel = vec![1f32];
+    for _ in 0..(aperture - order - 1) {
+        kernel = convolve_polynomial(&kernel, &[1., 1.]);
+    }
+    for _ in 0..order {
+        kernel = convolve_polynomial(&kernel, &[-1., 1.]);
+    }
+    kernel
+}
+
+fn scharr_kernel_1d(order: u32) -> Vec<f32> {
+    if order == 0 {
+        vec![3., 10., 3.]
+    } else {
+        vec![-1., 0., 1.]
+    }
+}
+
+/// Creates separable derivative kernels.
+///
+/// Scale from [GradientParams] is applied to the column kernel.
+///
+/// # Arguments
+///
+/// * `params`: See [GradientParams] for more info.
+///
+/// returns: Row and column kernels
+pub fn gradient_kernels(params: GradientParams) -> Result<(Vec<f32>, Vec<f32>), BlurError> {
+    params.validate()?;
+    let (row_kernel, mut column_kernel) = match params.kernel {
+        GradientKernel::Sobel => (
+            sobel_kernel_1d(params.dx, params.aperture),
+            sobel_kernel_1d(params.dy, params.aperture),
+        ),
+        GradientKernel::Scharr => (scharr_kernel_1d(params.dx), scharr_kernel_1d(params.dy)),
+    };
+    if params.scale != 1. {
+        column_kernel.iter_mut().for_each(|x| *x *= params.scale);
+    }
+    Ok((row_kernel, column_kernel))
+}
+
+fn make_intermediate_image<T, V>(image: &BlurImage<T>) -> BlurImage<'static, V>
+where
+    T: Copy + Default + Debug + AsPrimitive<V>,
+    V: Copy + Default + Debug + 'static,
+{
+    let cn = image.channels.channels();
+    let width = image.width as usize;
+    let mut data = vec![V::default(); width * image.height as usize * cn];
+    for (dst, src) in data
+        .chunks_exact_mut(width * cn)
+        .zip(image.data.chunks(image.row_stride() as usize))
+    {
+        for (dst, &src) in dst.iter_mut().zip(src[..width * cn].iter()) {
+            *dst = src.as_();
+        }
+    }
+    BlurImage {
+        data: std::borrow::Cow::Owned(data),
+        width: image.width,
+        height: image.height,
+        stride: image.width * cn as u32,
+        channels: image.channels,
+    }
+}
+
+/// Computes image derivative using Sobel or Scharr operator
+///
+/// Derivative is stored as signed 16-bit value and saturated into its range,
+/// use [GradientParams::scale] to prevent saturation on higher orders and large apertures.
+/// Only 8-bit images are supported, since 16-bit source does not fit into signed 16-bit
+/// intermediate, use [derivative_f32] for other bit depths.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `destination`: Destination image.
+/// * `params`: See [GradientParams] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn derivative_i16(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<i16>,
+    params: GradientParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    let (row_kernel, column_kernel) = gradient_kernels(params)?;
+    let intermediate = make_intermediate_image::<u8, i16>(image);
+    let _dispatcher = match image.channels {
+        FastBlurChannels::Plane => filter_1d_exact::<i16, f32, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<i16, f32, 2>,
+        FastBlurChannels::Channels3 => filter_1d_exact::<i16, f32, 3>,
+        FastBlurChannels::Channels4 => filter_1d_exact::<i16, f32, 4>,
+    };
+    _dispatcher(
+        &intermediate,
+        destination,
+        &row_kernel,
+        &column_kernel,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Computes image derivative using Sobel or Scharr operator
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `destination`: Destination image.
+/// * `params`: See [GradientParams] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn derivative_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    params: GradientParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    let (row_kernel, column_kernel) = gradient_kernels(params)?;
+    let _dispatcher = match image.channels {
+        FastBlurChannels::Plane => filter_1d_exact::<f32, f32, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<f32, f32, 2>,
+        FastBlurChannels::Channels3 => filter_1d_exact::<f32, f32, 3>,
+        FastBlurChannels::Channels4 => filter_1d_exact::<f32, f32, 4>,
+    };
+    _dispatcher(
+        image,
+        destination,
+        &row_kernel,
+        &column_kernel,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+/// Parameters of gradient magnitude and orientation
+pub struct GradientMagnitudeParams {
+    /// See [GradientKernel] for more info
+    pub kernel: GradientKernel,
+    /// Aperture size, must be one of 3, 5, 7 for [GradientKernel::Sobel], and 3 for [GradientKernel::Scharr]
+    pub aperture: u32,
+    /// See [GradientNorm] for more info
+    pub norm: GradientNorm,
+}
+
+impl GradientMagnitudeParams {
+    pub fn new(kernel: GradientKernel, aperture: u32, norm: GradientNorm) -> Self {
+        Self {
+            kernel,
+            aperture,
+            norm,
+        }
+    }
+
+    fn derivative_params(&self) -> (GradientParams, GradientParams) {
+        let make = |dx: u32, dy: u32| GradientParams {
+            dx,
+            dy,
+            aperture: self.aperture,
+            kernel: self.kernel,
+            scale: 1.,
+        };
+        (make(1, 0), make(0, 1))
+    }
+}
+
+/// Computes first order derivatives in both directions as `f32` planes of the same layout as `image`.
+pub(crate) fn first_derivatives<T>(
+    image: &BlurImage<T>,
+    kernel: GradientKernel,
+    aperture: u32,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(BlurImageMut<'static, f32>, BlurImageMut<'static, f32>), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    image.check_layout()?;
+    let (x_params, y_params) =
+        GradientMagnitudeParams::new(kernel, aperture, GradientNorm::L2).derivative_params();
+    let intermediate = make_intermediate_image::<T, f32>(image);
+    let mut gx = BlurImageMut::default();
+    let mut gy = BlurImageMut::default();
+    derivative_f32(
+        &intermediate,
+        &mut gx,
+        x_params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )?;
+    derivative_f32(
+        &intermediate,
+        &mut gy,
+        y_params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )?;
+    Ok((gx, gy))
+}
+
+fn gradient_magnitude_impl<T>(
+    image: &BlurImage<T>,
+    magnitude: &mut BlurImageMut<f32>,
+    mut orientation: Option<&mut BlurImageMut<f32>>,
+    params: GradientMagnitudeParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + AsPrimitive<f32>,
+{
+    image.check_layout()?;
+    magnitude.check_layout_with_size(image.width, image.height, image.channels)?;
+    if let Some(orientation) = orientation.as_deref_mut() {
+        orientation.check_layout_with_size(image.width, image.height, image.channels)?;
+    }
+
+    let (x_params, y_params) = params.derivative_params();
+    let intermediate = make_intermediate_image::<T, f32>(image);
+    // Horizontal derivative is staged in the magnitude and vertical one in the orientation
+    derivative_f32(
+        &intermediate,
+        magnitude,
+        x_params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )?;
+    let has_orientation = orientation.is_some();
+    let mut vertical = BlurImageMut::default();
+    let gy = orientation.unwrap_or(&mut vertical);
+    derivative_f32(
+        &intermediate,
+        gy,
+        y_params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )?;
+
+    let row_length = image.width as usize * image.channels.channels();
+    let magnitude_stride = magnitude.row_stride() as usize;
+    let gy_stride = gy.row_stride() as usize;
+    let unsafe_gy = UnsafeSlice::new(gy.data.borrow_mut());
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(image.width, image.height));
+    magnitude.data.borrow_mut()[..magnitude_stride * (image.height as usize - 1) + row_length]
+        .tb_par_chunks_mut(magnitude_stride)
+        .for_each_enumerated(&pool, |y, magnitude_row| {
+            for (x, dst) in magnitude_row[..row_length].iter_mut().enumerate() {
+                let dx = *dst;
+                let dy = unsafe_gy.get(y * gy_stride + x);
+                *dst = match params.norm {
+                    GradientNorm::L1 => dx.abs() + dy.abs(),
+                    GradientNorm::L2 => dx.hypot(*dy),
+                };
+                if has_orientation {
+                    *dy = dy.atan2(dx);
+                }
+            }
+        });
+    Ok(())
+}
+
+/// Computes gradient magnitude and optionally gradient orientation
+///
+/// Both derivatives are computed in `f32` so no saturation happens on any aperture.
+/// Orientation is stored in radians in range `[-PI, PI]` as `atan2(dy, dx)`.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `magnitude`: Destination gradient magnitude image.
+/// * `orientation`: Optional destination gradient orientation image.
+/// * `params`: See [GradientMagnitudeParams] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn gradient_magnitude(
+    image: &BlurImage<u8>,
+    magnitude: &mut BlurImageMut<f32>,
+    orientation: Option<&mut BlurImageMut<f32>>,
+    params: GradientMagnitudeParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gradient_magnitude_impl(
+        image,
+        magnitude,
+        orientation,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Computes gradient magnitude and optionally gradient orientation
+///
+/// Both derivatives are computed in `f32` so no saturation happens on any aperture.
+/// Orientation is stored in radians in range `[-PI, PI]` as `atan2(dy, dx)`.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `magnitude`: Destination gradient magnitude image.
+/// * `orientation`: Optional destination gradient orientation image.
+/// * `params`: See [GradientMagnitudeParams] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn gradient_magnitude_u16(
+    image: &BlurImage<u16>,
+    magnitude: &mut BlurImageMut<f32>,
+    orientation: Option<&mut BlurImageMut<f32>>,
+    params: GradientMagnitudeParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gradient_magnitude_impl(
+        image,
+        magnitude,
+        orientation,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Computes gradient magnitude and optionally gradient orientation
+///
+/// Orientation is stored in radians in range `[-PI, PI]` as `atan2(dy, dx)`.
+///
+/// # Arguments
+///
+/// * `image`: Source image.
+/// * `magnitude`: Destination gradient magnitude image.
+/// * `orientation`: Optional destination gradient orientation image.
+/// * `params`: See [GradientMagnitudeParams] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn gradient_magnitude_f32(
+    image: &BlurImage<f32>,
+    magnitude: &mut BlurImageMut<f32>,
+    orientation: Option<&mut BlurImageMut<f32>>,
+    params: GradientMagnitudeParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gradient_magnitude_impl(
+        image,
+        magnitude,
+        orientation,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_sobel_kernels() {
+        let (row, column) = gradient_kernels(GradientParams::new(1, 0)).unwrap();
+        assert_eq!(row, vec![-1., 0., 1.]);
+        assert_eq!(column, vec![1., 2., 1.]);
+        let (row, column) = gradient_kernels(GradientParams::new_with_aperture(2, 0, 5)).unwrap();
+        assert_eq!(row, vec![1., 0., -2., 0., 1.]);
+        assert_eq!(column, vec![1., 4., 6., 4., 1.]);
+        let (row, column) = gradient_kernels(GradientParams::scharr(0, 1)).unwrap();
+        assert_eq!(row, vec![3., 10., 3.]);
+        assert_eq!(column, vec![-1., 0., 1.]);
+        assert!(gradient_kernels(GradientParams::scharr(1, 1)).is_err());
+        assert!(gradient_kernels(GradientParams::new_with_aperture(1, 0, 4)).is_err());
+    }
+
+    #[test]
+    fn test_derivative_i16_ramp() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let mut src = vec![0u8; width * height];
+        for row in src.chunks_exact_mut(width) {
+            for (x, dst) in row.iter_mut().enumerate() {
+                *dst = (x * 2) as u8;
+            }
+        }
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        derivative_i16(
+            &src_image,
+            &mut dst,
+            GradientParams::new(1, 0),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        for row in dst.data.borrow().chunks_exact(width) {
+            for &v in row[1..width - 1].iter() {
+                assert_eq!(
+                    v, 16,
+                    "Derivative of ramp expected to be 16, but it was {v}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_gradient_magnitude_flat() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let src = vec![126u8; width * height * 3];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut magnitude = BlurImageMut::default();
+        let mut orientation = BlurImageMut::default();
+        gradient_magnitude(
+            &src_image,
+            &mut magnitude,
+            Some(&mut orientation),
+            GradientMagnitudeParams::new(GradientKernel::Scharr, 3, GradientNorm::L2),
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert_eq!(magnitude.data.borrow().len(), width * height * 3);
+        assert_eq!(orientation.data.borrow().len(), width * height * 3);
+        for &v in magnitude.data.borrow().iter() {
+            assert!(v.abs() < 1e-3, "Magnitude expected to be 0, but it was {v}");
+        }
+    }
+
+    #[test]
+    fn test_gradient_magnitude_matches_reference() {
+        let width: usize = 37;
+        let height: usize = 29;
+        let cn = 3usize;
+        let src: Vec<u8> = (0..width * height * cn)
+            .map(|i| ((i * 7919 + i / 5 * 31) % 251) as u8)
+            .collect();
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let border_constant = Scalar::new(15., 120., 240., 0.);
+        let reflect101 = |i: i64, n: i64| {
+            if i < 0 {
+                -i
+            } else if i >= n {
+                2 * n - i - 2
+            } else {
+                i
+            }
+        };
+        for (kernel, aperture) in [(GradientKernel::Sobel, 5), (GradientKernel::Scharr, 3)] {
+            for edge_mode in [EdgeMode::Clamp, EdgeMode::Reflect101, EdgeMode::Constant] {
+                let params = GradientMagnitudeParams::new(kernel, aperture, GradientNorm::L2);
+                let (x_params, y_params) = params.derivative_params();
+                let x_kernels = gradient_kernels(x_params).unwrap();
+                let y_kernels = gradient_kernels(y_params).unwrap();
+                let radius = aperture as i64 / 2;
+                let sample = |x: i64, y: i64, c: usize| -> f32 {
+                    let (x, y) = match edge_mode {
+                        EdgeMode::Clamp => {
+                            (x.clamp(0, width as i64 - 1), y.clamp(0, height as i64 - 1))
+                        }
+                        EdgeMode::Reflect101 => {
+                            (reflect101(x, width as i64), reflect101(y, height as i64))
+                        }
+                        _ => {
+                            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                                return border_constant[c] as f32;
+                            }
+                            (x, y)
+                        }
+                    };
+                    src[(y as usize * width + x as usize) * cn + c] as f32
+                };
+                let derivative = |kernels: &(Vec<f32>, Vec<f32>), x: i64, y: i64, c: usize| {
+                    let mut sum = 0f32;
+                    for (ky, &wy) in kernels.1.iter().enumerate() {
+                        for (kx, &wx) in kernels.0.iter().enumerate() {
+                            sum +=
+                                wx * wy * sample(x + kx as i64 - radius, y + ky as i64 - radius, c);
+                        }
+                    }
+                    sum
+                };
+
+                // Tiles of multithreaded filter start inside the image and have their own top rows
+                for threading_policy in [
+                    ThreadingPolicy::Single,
+                    ThreadingPolicy::Fixed(std::num::NonZeroUsize::new(4).unwrap()),
+                ] {
+                    let mut magnitude = BlurImageMut::default();
+                    gradient_magnitude(
+                        &src_image,
+                        &mut magnitude,
+                        None,
+                        params,
+                        edge_mode.as_2d(),
+                        border_constant,
+                        threading_policy,
+                    )
+                    .unwrap();
+                    for (i, &m) in magnitude.data.borrow().iter().enumerate() {
+                        let c = i % cn;
+                        let x = ((i / cn) % width) as i64;
+                        let y = (i / (cn * width)) as i64;
+                        let expected =
+                            derivative(&x_kernels, x, y, c).hypot(derivative(&y_kernels, x, y, c));
+                        assert!(
+                            (m - expected).abs() <= 1e-3 * expected.max(1.),
+                            "{kernel:?} {edge_mode:?} at {i}: {m} vs {expected}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
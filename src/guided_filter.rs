@@ -0,0 +1,642 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, BoxBlurParameters, BufferStore, EdgeMode,
+    FastBlurChannels, Scalar, ThreadingPolicy, box_blur_f32,
+};
+use std::fmt::Debug;
+
+/// Parameters of the guided filter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GuidedFilterParams {
+    /// Radius of the local window.
+    pub radius: u32,
+    /// Regularization, defines which variance is considered an edge.
+    /// Images are normalized into `0..1` before filtering, so typical values are `1e-4..1e-1`.
+    pub epsilon: f32,
+    /// Subsampling factor of the fast guided filter, 1 performs exact filter.
+    /// Coefficients are computed on the image downscaled by this factor and then upscaled back,
+    /// it is recommended to keep `radius / subsampling` not less than 1.
+    pub subsampling: u32,
+}
+
+impl GuidedFilterParams {
+    pub fn new(radius: u32, epsilon: f32) -> GuidedFilterParams {
+        GuidedFilterParams {
+            radius,
+            epsilon,
+            subsampling: 1,
+        }
+    }
+
+    /// Creates parameters for the fast subsampled guided filter
+    pub fn new_fast(radius: u32, epsilon: f32, subsampling: u32) -> GuidedFilterParams {
+        GuidedFilterParams {
+            radius,
+            epsilon,
+            subsampling,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        // Box window `radius * 2 + 1` must fit into u32
+        if self.radius == 0 || self.radius > (u32::MAX - 1) / 2 {
+            return Err(BlurError::InvalidParameter {
+                name: "radius",
+                value: self.radius as f64,
+            });
+        }
+        if self.subsampling == 0 {
+            return Err(BlurError::InvalidParameter {
+                name: "subsampling",
+                value: self.subsampling as f64,
+            });
+        }
+        if !self.epsilon.is_finite() || self.epsilon <= 0. {
+            return Err(BlurError::InvalidParameter {
+                name: "epsilon",
+                value: self.epsilon as f64,
+            });
+        }
+        Ok(())
+    }
+}
+
+trait GuidedSample: Copy + Clone + Default + Debug + Send + Sync + 'static {
+    const SCALE: f32;
+    fn to_f32(self) -> f32;
+    fn from_f32(v: f32) -> Self;
+}
+
+impl GuidedSample for u8 {
+    const SCALE: f32 = 255.;
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u8::MAX as f32) as u8
+    }
+}
+
+impl GuidedSample for u16 {
+    const SCALE: f32 = 65535.;
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u16::MAX as f32) as u16
+    }
+}
+
+impl GuidedSample for f32 {
+    const SCALE: f32 = 1.;
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+}
+
+/// Single f32 plane with tightly packed rows
+struct Plane {
+    data: Vec<f32>,
+    width: usize,
+    height: usize,
+}
+
+impl Plane {
+    fn new(width: usize, height: usize) -> Plane {
+        Plane {
+            data: vec![0f32; width * height],
+            width,
+            height,
+        }
+    }
+
+    fn extract<T: GuidedSample>(image: &BlurImage<T>, channel: usize) -> Plane {
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let cn = image.channels.channels();
+        let stride = image.row_stride() as usize;
+        let scale = 1. / T::SCALE;
+        let mut plane = Plane::new(width, height);
+        for (dst, src) in plane
+            .data
+            .chunks_exact_mut(width)
+            .zip(image.data.chunks(stride))
+        {
+            for (dst, src) in dst.iter_mut().zip(src[..width * cn].chunks_exact(cn)) {
+                *dst = src[channel].to_f32() * scale;
+            }
+        }
+        plane
+    }
+
+    fn product(&self, other: &Plane) -> Plane {
+        Plane {
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(&a, &b)| a * b)
+                .collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Box mean with the window `radius * 2 + 1`, made by [box_blur_f32]
+    fn mean(&self, radius: u32, threading_policy: ThreadingPolicy) -> Result<Plane, BlurError> {
+        let image = BlurImage::borrow(
+            &self.data,
+            self.width as u32,
+            self.height as u32,
+            FastBlurChannels::Plane,
+        );
+        let mut dst = BlurImageMut::default();
+        box_blur_f32(
+            &image,
+            &mut dst,
+            BoxBlurParameters::new(radius * 2 + 1),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            threading_policy,
+            AlphaMode::Premultiplied,
+        )?;
+        let data = match dst.data {
+            BufferStore::Owned(data) => data,
+            BufferStore::Borrowed(data) => data.to_vec(),
+        };
+        Ok(Plane {
+            data,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Downsamples the plane by averaging `factor x factor` blocks
+    fn downsample(&self, factor: usize) -> Plane {
+        let width = self.width.div_ceil(factor);
+        let height = self.height.div_ceil(factor);
+        let mut plane = Plane::new(width, height);
+        for (y, dst_row) in plane.data.chunks_exact_mut(width).enumerate() {
+            let y0 = y * factor;
+            let y1 = (y0 + factor).min(self.height);
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                let x0 = x * factor;
+                let x1 = (x0 + factor).min(self.width);
+                let mut sum = 0f32;
+                for row in self.data[y0 * self.width..y1 * self.width].chunks_exact(self.width) {
+                    sum += row[x0..x1].iter().sum::<f32>();
+                }
+                *dst = sum / ((x1 - x0) * (y1 - y0)) as f32;
+            }
+        }
+        plane
+    }
+
+    /// Bilinear upsampling with pixel centers alignment
+    fn upsample(&self, width: usize, height: usize, factor: usize) -> Plane {
+        let mut plane = Plane::new(width, height);
+        let scale = 1. / factor as f32;
+        let coordinate = |i: usize, len: usize| {
+            let v = ((i as f32 + 0.5) * scale - 0.5).max(0.);
+            let i0 = (v as usize).min(len - 1);
+            let i1 = (i0 + 1).min(len - 1);
+            (i0, i1, v - i0 as f32)
+        };
+        let xs: Vec<(usize, usize, f32)> = (0..width).map(|x| coordinate(x, self.width)).collect();
+        for (y, dst_row) in plane.data.chunks_exact_mut(width).enumerate() {
+            let (y0, y1, wy) = coordinate(y, self.height);
+            let row0 = &self.data[y0 * self.width..(y0 + 1) * self.width];
+            let row1 = &self.data[y1 * self.width..(y1 + 1) * self.width];
+            for (dst, &(x0, x1, wx)) in dst_row.iter_mut().zip(xs.iter()) {
+                let top = row0[x0] + (row0[x1] - row0[x0]) * wx;
+                let bottom = row1[x0] + (row1[x1] - row1[x0]) * wx;
+                *dst = top + (bottom - top) * wy;
+            }
+        }
+        plane
+    }
+}
+
+/// Solves `(sigma + eps * I) * a = cov` for symmetric 3x3 `sigma`
+#[inline(always)]
+fn solve_symmetric3(sigma: [f32; 6], cov: [f32; 3], eps: f32) -> [f32; 3] {
+    let [rr, rg, rb, gg, gb, bb] = sigma;
+    let (rr, gg, bb) = (rr + eps, gg + eps, bb + eps);
+    let i_rr = gg * bb - gb * gb;
+    let i_rg = gb * rb - rg * bb;
+    let i_rb = rg * gb - gg * rb;
+    let i_gg = rr * bb - rb * rb;
+    let i_gb = rb * rg - rr * gb;
+    let i_bb = rr * gg - rg * rg;
+    let det = rr * i_rr + rg * i_rg + rb * i_rb;
+    let recip = if det.abs() > f32::EPSILON * f32::EPSILON {
+        1. / det
+    } else {
+        0.
+    };
+    [
+        (i_rr * cov[0] + i_rg * cov[1] + i_rb * cov[2]) * recip,
+        (i_rg * cov[0] + i_gg * cov[1] + i_gb * cov[2]) * recip,
+        (i_rb * cov[0] + i_gb * cov[1] + i_bb * cov[2]) * recip,
+    ]
+}
+
+/// Computes linear coefficients `a` (one per guide channel) and `b` for one input channel.
+fn make_coefficients(
+    guide: &[Plane],
+    guide_means: &[Plane],
+    guide_sigma: &[Plane],
+    input: &Plane,
+    radius: u32,
+    epsilon: f32,
+    pool: &crate::executor::ThreadPool,
+    threading_policy: ThreadingPolicy,
+) -> Result<Vec<Plane>, BlurError> {
+    let width = input.width;
+    let height = input.height;
+    let mean_p = input.mean(radius, threading_policy)?;
+    let mean_ip = guide
+        .iter()
+        .map(|g| g.product(input).mean(radius, threading_policy))
+        .collect::<Result<Vec<Plane>, BlurError>>()?;
+
+    let k = guide.len();
+    // Interleaved `a0, .., ak-1, b`
+    let mut coefficients = vec![0f32; width * height * (k + 1)];
+    coefficients
+        .tb_par_chunks_exact_mut(width * (k + 1))
+        .for_each_enumerated(pool, |y, row| {
+            for (x, dst) in row.chunks_exact_mut(k + 1).enumerate() {
+                let i = y * width + x;
+                let mp = mean_p.data[i];
+                if k == 1 {
+                    let mi = guide_means[0].data[i];
+                    let var = guide_sigma[0].data[i] - mi * mi;
+                    let cov = mean_ip[0].data[i] - mi * mp;
+                    let a = cov / (var + epsilon);
+                    dst[0] = a;
+                    dst[1] = mp - a * mi;
+                } else {
+                    let m = [
+                        guide_means[0].data[i],
+                        guide_means[1].data[i],
+                        guide_means[2].data[i],
+                    ];
+                    let sigma = [
+                        guide_sigma[0].data[i] - m[0] * m[0],
+                        guide_sigma[1].data[i] - m[0] * m[1],
+                        guide_sigma[2].data[i] - m[0] * m[2],
+                        guide_sigma[3].data[i] - m[1] * m[1],
+                        guide_sigma[4].data[i] - m[1] * m[2],
+                        guide_sigma[5].data[i] - m[2] * m[2],
+                    ];
+                    let cov = [
+                        mean_ip[0].data[i] - m[0] * mp,
+                        mean_ip[1].data[i] - m[1] * mp,
+                        mean_ip[2].data[i] - m[2] * mp,
+                    ];
+                    let a = solve_symmetric3(sigma, cov, epsilon);
+                    dst[0] = a[0];
+                    dst[1] = a[1];
+                    dst[2] = a[2];
+                    dst[3] = mp - a[0] * m[0] - a[1] * m[1] - a[2] * m[2];
+                }
+            }
+        });
+
+    (0..k + 1)
+        .map(|c| {
+            let mut plane = Plane::new(width, height);
+            for (dst, src) in plane.data.iter_mut().zip(coefficients.chunks_exact(k + 1)) {
+                *dst = src[c];
+            }
+            plane.mean(radius, threading_policy)
+        })
+        .collect()
+}
+
+fn guided_filter_impl<T: GuidedSample>(
+    guide: &BlurImage<T>,
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    params: GuidedFilterParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    guide.check_layout()?;
+    image.check_layout()?;
+    destination.check_layout(Some(image))?;
+    image.size_matches_mut(destination)?;
+    params.validate()?;
+    if guide.width != image.width || guide.height != image.height {
+        return Err(BlurError::ImagesMustMatch);
+    }
+
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let factor = params.subsampling as usize;
+    let radius = if factor > 1 {
+        (params.radius as f32 / factor as f32).round().max(1.) as u32
+    } else {
+        params.radius
+    };
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(image.width, image.height));
+
+    // Only color part of luma-alpha and RGBA guides is used
+    let guide_channels = match guide.channels {
+        FastBlurChannels::Plane | FastBlurChannels::Channels2 => 1,
+        FastBlurChannels::Channels3 | FastBlurChannels::Channels4 => 3,
+    };
+    let full_guide: Vec<Plane> = (0..guide_channels)
+        .map(|c| Plane::extract(guide, c))
+        .collect();
+    let low = |plane: &Plane| {
+        if factor > 1 {
+            plane.downsample(factor)
+        } else {
+            Plane {
+                data: plane.data.clone(),
+                width: plane.width,
+                height: plane.height,
+            }
+        }
+    };
+    let low_guide: Vec<Plane> = full_guide.iter().map(low).collect();
+    let guide_means = low_guide
+        .iter()
+        .map(|g| g.mean(radius, threading_policy))
+        .collect::<Result<Vec<Plane>, BlurError>>()?;
+    let mut guide_sigma = Vec::with_capacity(6);
+    for i in 0..guide_channels {
+        for j in i..guide_channels {
+            guide_sigma.push(
+                low_guide[i]
+                    .product(&low_guide[j])
+                    .mean(radius, threading_policy)?,
+            );
+        }
+    }
+
+    let cn = image.channels.channels();
+    let dst_stride = destination.row_stride() as usize;
+    for channel in 0..cn {
+        let input = low(&Plane::extract(image, channel));
+        let mut coefficients = make_coefficients(
+            &low_guide,
+            &guide_means,
+            &guide_sigma,
+            &input,
+            radius,
+            params.epsilon,
+            &pool,
+            threading_policy,
+        )?;
+        if factor > 1 {
+            coefficients = coefficients
+                .iter()
+                .map(|x| x.upsample(width, height, factor))
+                .collect();
+        }
+        let (b, a) = coefficients.split_last().unwrap();
+        destination.data.borrow_mut()[..dst_stride * (height - 1) + width * cn]
+            .tb_par_chunks_mut(dst_stride)
+            .for_each_enumerated(&pool, |y, dst_row| {
+                for (x, dst) in dst_row[..width * cn].chunks_exact_mut(cn).enumerate() {
+                    let i = y * width + x;
+                    let mut q = b.data[i];
+                    for (a, g) in a.iter().zip(full_guide.iter()) {
+                        q += a.data[i] * g.data[i];
+                    }
+                    dst[channel] = T::from_f32(q * T::SCALE);
+                }
+            });
+    }
+    Ok(())
+}
+
+/// Performs guided filter (He et al.), edge-aware smoothing with edges taken from the guide.
+///
+/// Guide may be grayscale or RGB, in RGBA guide alpha is ignored.
+/// Each channel of the input is filtered independently, input and guide may be different images,
+/// e.g. an alpha matte refined by the RGB image.
+/// Means are computed with [box_blur_f32], so filter is O(1) with respect to radius.
+///
+/// # Arguments
+///
+/// * `guide`: Guide image, see [BlurImage] for more info.
+/// * `image`: Input image, must have the same size as the guide.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [GuidedFilterParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn guided_filter(
+    guide: &BlurImage<u8>,
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: GuidedFilterParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    guided_filter_impl(guide, image, destination, params, threading_policy)
+}
+
+/// Performs guided filter (He et al.), edge-aware smoothing with edges taken from the guide.
+///
+/// See [guided_filter] for more info.
+///
+/// # Arguments
+///
+/// * `guide`: Guide image, see [BlurImage] for more info.
+/// * `image`: Input image, must have the same size as the guide.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [GuidedFilterParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn guided_filter_u16(
+    guide: &BlurImage<u16>,
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    params: GuidedFilterParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    guided_filter_impl(guide, image, destination, params, threading_policy)
+}
+
+/// Performs guided filter (He et al.), edge-aware smoothing with edges taken from the guide.
+///
+/// See [guided_filter] for more info, image is expected to be in `0..1` range.
+///
+/// # Arguments
+///
+/// * `guide`: Guide image, see [BlurImage] for more info.
+/// * `image`: Input image, must have the same size as the guide.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [GuidedFilterParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn guided_filter_f32(
+    guide: &BlurImage<f32>,
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    params: GuidedFilterParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    guided_filter_impl(guide, image, destination, params, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guided_filter_preserves_edge() {
+        let width = 40usize;
+        let height = 24usize;
+        let mut src = vec![0u8; width * height * 3];
+        for row in src.chunks_exact_mut(width * 3) {
+            for (x, px) in row.chunks_exact_mut(3).enumerate() {
+                let base: u8 = if x < width / 2 { 40 } else { 210 };
+                px.fill(base.wrapping_add((x % 3) as u8));
+            }
+        }
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for params in [
+            GuidedFilterParams::new(4, 1e-2),
+            GuidedFilterParams::new_fast(4, 1e-2, 2),
+        ] {
+            let mut dst = BlurImageMut::default();
+            guided_filter(&image, &image, &mut dst, params, ThreadingPolicy::Adaptive).unwrap();
+            let dst = dst.data.borrow();
+            for y in 0..height {
+                let left = dst[y * width * 3 + (width / 2 - 1) * 3];
+                let right = dst[y * width * 3 + (width / 2) * 3];
+                assert!(left < 60, "{params:?} left {left}");
+                assert!(right > 190, "{params:?} right {right}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_guided_filter_matte() {
+        let width = 32usize;
+        let height = 32usize;
+        let guide: Vec<f32> = (0..width * height)
+            .map(|i| if i % width < width / 2 { 0.1 } else { 0.9 })
+            .collect();
+        // Noisy matte, refined matte must follow the guide edge
+        let matte: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let base = if i % width < width / 2 { 0. } else { 1. };
+                base + if i % 5 == 0 { 0.1 } else { -0.05 }
+            })
+            .collect();
+        let guide_image =
+            BlurImage::borrow(&guide, width as u32, height as u32, FastBlurChannels::Plane);
+        let matte_image =
+            BlurImage::borrow(&matte, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        guided_filter_f32(
+            &guide_image,
+            &matte_image,
+            &mut dst,
+            GuidedFilterParams::new(3, 1e-3),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        for y in 4..height - 4 {
+            assert!(dst[y * width + width / 2 - 1] < 0.2);
+            assert!(dst[y * width + width / 2] > 0.8);
+        }
+    }
+
+    #[test]
+    fn test_guided_filter_rejects_huge_radius() {
+        let src = vec![0u8; 4 * 4];
+        let image = BlurImage::borrow(&src, 4, 4, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let result = guided_filter(
+            &image,
+            &image,
+            &mut dst,
+            GuidedFilterParams::new(u32::MAX / 2 + 1, 1e-2),
+            ThreadingPolicy::Single,
+        );
+        assert!(matches!(
+            result,
+            Err(BlurError::InvalidParameter { name: "radius", .. })
+        ));
+    }
+
+    #[test]
+    fn test_guided_filter_reports_invalid_parameter() {
+        let src = vec![0u8; 4 * 4];
+        let image = BlurImage::borrow(&src, 4, 4, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let cases = [
+            (GuidedFilterParams::new(0, 1e-2), "radius"),
+            (GuidedFilterParams::new(2, 0.), "epsilon"),
+            (GuidedFilterParams::new_fast(2, 1e-2, 0), "subsampling"),
+        ];
+        for (params, expected) in cases {
+            let result = guided_filter(&image, &image, &mut dst, params, ThreadingPolicy::Single);
+            assert!(matches!(
+                result,
+                Err(BlurError::InvalidParameter { name, .. }) if name == expected
+            ));
+        }
+    }
+}
@@ -458,6 +458,35 @@ impl<'a, T: Clone + Copy + Default + Debug> BlurImageMut<'a, T> {
         Ok(())
     }
 
+    /// Checks if layout matches provided dimensions, this is useful when source image
+    /// has different pixel type.
+    /// If it owns vector it will be resized to provided dimensions.
+    pub(crate) fn check_layout_with_size(
+        &mut self,
+        width: u32,
+        height: u32,
+        channels: FastBlurChannels,
+    ) -> Result<(), BlurError> {
+        if matches!(self.data, BufferStore::Owned(_)) {
+            if check_image_size_overflow_with_stride(
+                width,
+                height,
+                width.saturating_mul(channels.channels() as u32),
+                channels.channels(),
+                size_of::<T>() as isize,
+            ) {
+                return Err(BlurError::ExceedingPointerSize);
+            }
+            self.resize(width, height, channels);
+            return Ok(());
+        }
+        self.check_layout(None)?;
+        if self.width != width || self.height != height || self.channels != channels {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        Ok(())
+    }
+
     /// Checks if it is matches the size of the other image
     #[inline]
     pub fn size_matches(&self, other: &BlurImage<'_, T>) -> Result<(), BlurError> {
@@ -515,6 +544,8 @@ impl<'a, T: Clone + Copy + Default + Debug> BlurImageMut<'a, T> {
             FastBlurChannels::Channels4
         } else if cn == 3 {
             FastBlurChannels::Channels3
+        } else if cn == 2 {
+            FastBlurChannels::Channels2
         } else {
             FastBlurChannels::Plane
         };
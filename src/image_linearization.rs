@@ -260,7 +260,12 @@ fn linearize8<Z, F: FinalImageFactory<Z, u16>>(
     let mut new_image = vec![0u16; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
     let linearization = make_linearization(transfer_function);
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -274,7 +279,8 @@ fn linearize8<Z, F: FinalImageFactory<Z, u16>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -282,16 +288,11 @@ fn linearize8<Z, F: FinalImageFactory<Z, u16>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = linearization.linearization[src[0] as usize];
-                dst[1] = linearization.linearization[src[1] as usize];
-                dst[2] = linearization.linearization[src[2] as usize];
-                dst[3] = u16::from_ne_bytes([src[3], src[3]]);
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = linearization.linearization[src as usize];
+                }
+                dst[cn - 1] = u16::from_ne_bytes([src[cn - 1], src[cn - 1]]);
             }
         }
     }
@@ -321,7 +322,12 @@ fn gen_gamma8<Z, F: FinalImageFactory<Z, u8>>(
     let mut new_image = vec![0u8; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
     let gamma = make_gamma(transfer_function);
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -334,7 +340,8 @@ fn gen_gamma8<Z, F: FinalImageFactory<Z, u8>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -342,16 +349,11 @@ fn gen_gamma8<Z, F: FinalImageFactory<Z, u8>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = gamma.gamma[src[0] as usize];
-                dst[1] = gamma.gamma[src[1] as usize];
-                dst[2] = gamma.gamma[src[2] as usize];
-                dst[3] = (src[3] >> 8) as u8;
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = gamma.gamma[src as usize];
+                }
+                dst[cn - 1] = (src[cn - 1] >> 8) as u8;
             }
         }
     }
@@ -381,7 +383,12 @@ fn linearize16<Z, F: FinalImageFactory<Z, u16>>(
     let mut new_image = vec![0u16; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
     let linearization = make_linearization16(transfer_function);
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -394,7 +401,8 @@ fn linearize16<Z, F: FinalImageFactory<Z, u16>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -402,16 +410,11 @@ fn linearize16<Z, F: FinalImageFactory<Z, u16>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = linearization.linearization[src[0] as usize];
-                dst[1] = linearization.linearization[src[1] as usize];
-                dst[2] = linearization.linearization[src[2] as usize];
-                dst[3] = src[3];
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = linearization.linearization[src as usize];
+                }
+                dst[cn - 1] = src[cn - 1];
             }
         }
     }
@@ -441,7 +444,12 @@ fn gen_gamma16<Z, F: FinalImageFactory<Z, u16>>(
     let mut new_image = vec![0u16; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
     let gamma = make_gamma16(transfer_function);
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -454,7 +462,8 @@ fn gen_gamma16<Z, F: FinalImageFactory<Z, u16>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -462,16 +471,11 @@ fn gen_gamma16<Z, F: FinalImageFactory<Z, u16>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = gamma.gamma[src[0] as usize];
-                dst[1] = gamma.gamma[src[1] as usize];
-                dst[2] = gamma.gamma[src[2] as usize];
-                dst[3] = src[3];
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = gamma.gamma[src as usize];
+                }
+                dst[cn - 1] = src[cn - 1];
             }
         }
     }
@@ -500,7 +504,12 @@ fn linearize_f32<Z, F: FinalImageFactory<Z, f32>>(
     src_ref.check_layout()?;
     let mut new_image = vec![0.; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -513,7 +522,8 @@ fn linearize_f32<Z, F: FinalImageFactory<Z, f32>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -521,16 +531,11 @@ fn linearize_f32<Z, F: FinalImageFactory<Z, f32>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = transfer_function.linearize(src[0]);
-                dst[1] = transfer_function.linearize(src[1]);
-                dst[2] = transfer_function.linearize(src[2]);
-                dst[3] = src[3];
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = transfer_function.linearize(src);
+                }
+                dst[cn - 1] = src[cn - 1];
             }
         }
     }
@@ -559,7 +564,12 @@ fn gamma_f32<Z, F: FinalImageFactory<Z, f32>>(
     src_ref.check_layout()?;
     let mut new_image = vec![0.; src_ref.row_stride() as usize * src_ref.height as usize];
     let row_stride = src_ref.row_stride() as usize;
-    if !may_have_alpha || (src_ref.channels != FastBlurChannels::Channels4) {
+    if !may_have_alpha
+        || !matches!(
+            src_ref.channels,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4
+        )
+    {
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -572,7 +582,8 @@ fn gamma_f32<Z, F: FinalImageFactory<Z, f32>>(
             }
         }
     } else {
-        // ATM only 4 channels here possible, so hardcoded 4
+        // Alpha is the last channel of luma-alpha and RGBA images
+        let cn = src_ref.channels.channels();
         let src_data = src_ref.projected();
         for (dst, src) in new_image
             .chunks_mut(row_stride)
@@ -580,16 +591,11 @@ fn gamma_f32<Z, F: FinalImageFactory<Z, f32>>(
         {
             let src = &src[..src_ref.width as usize * src_ref.channels.channels()];
             let dst = &mut dst[..src_ref.width as usize * src_ref.channels.channels()];
-            for (dst, src) in dst
-                .as_chunks_mut::<4>()
-                .0
-                .iter_mut()
-                .zip(src.as_chunks::<4>().0.iter())
-            {
-                dst[0] = transfer_function.gamma(src[0]);
-                dst[1] = transfer_function.gamma(src[1]);
-                dst[2] = transfer_function.gamma(src[2]);
-                dst[3] = src[3];
+            for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(cn)) {
+                for (dst, &src) in dst[..cn - 1].iter_mut().zip(src[..cn - 1].iter()) {
+                    *dst = transfer_function.gamma(src);
+                }
+                dst[cn - 1] = src[cn - 1];
             }
         }
     }
@@ -32,9 +32,10 @@ use crate::{
     filter_2d, sigma_size,
 };
 
-pub fn laplacian_kernel(size: usize) -> Vec<f32> {
+/// Creates laplacian of gaussian kernel of `size * size`, size must be odd.
+pub fn laplacian_kernel(size: usize) -> Result<Vec<f32>, BlurError> {
     if size & 1 == 0 {
-        panic!("Kernel size must be odd in 'get_laplacian_kernel'!");
+        return Err(BlurError::OddKernel(size));
     }
     let center_x = (size / 2) as f32;
     let center_y = (size / 2) as f32;
@@ -66,7 +67,7 @@ pub fn laplacian_kernel(size: usize) -> Vec<f32> {
             *item *= scale;
         }
     }
-    kernel
+    Ok(kernel)
 }
 
 /// Performs laplacian of gaussian on the image
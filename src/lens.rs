@@ -52,7 +52,12 @@ pub fn lens_kernel(
     if shape.height.is_multiple_of(2) {
         return Err(BlurError::OddKernel(shape.height));
     }
-    assert!(k.abs() <= 1., "Roundness contract is not satisfied");
+    if k.is_nan() || k.abs() > 1. {
+        return Err(BlurError::InvalidParameter {
+            name: "k",
+            value: k as f64,
+        });
+    }
     let eps = 1f32 / shape.width as f32;
     let radius = (shape.width as f32 - 1.) / 2. - 1.;
     let mut new_buffer = vec![0f32; shape.width * shape.height];
@@ -0,0 +1,480 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::gamma_curves::TransferFunction;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, FftConvolutionMode,
+    KernelShape, Scalar, ThreadingPolicy, filter_2d, filter_2d_rgb_fft, filter_2d_rgba_fft,
+    lens_kernel,
+};
+
+/// Kernels with fewer non-zero taps than this are convolved directly, larger ones through FFT
+const DIRECT_CONVOLUTION_TAPS: usize = 32;
+
+/// Kernel of the maximum radius takes 16 MiB
+const LENS_MAX_RADIUS: u32 = 1024;
+
+/// Parameters of the lens blur
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LensBlurParams {
+    /// Radius of the bokeh disc up to 1024, kernel size is `radius * 2 + 1`.
+    pub radius: u32,
+    /// Number of diaphragm blades, at least 3.
+    pub blades: u32,
+    /// Roundness of the diaphragm in `0..=1`, 0 is a polygon with straight blades, 1 is a circle.
+    pub roundness: f32,
+    /// Rotation of the diaphragm in radians.
+    pub rotation: f32,
+    /// Linear intensity in `0..=1` from which highlights are boosted.
+    pub highlight_threshold: f32,
+    /// Gain of the brightest highlights, 0 disables highlight boost.
+    pub highlight_boost: f32,
+    /// Transfer function of the image, blur is always performed in linear light,
+    /// see [TransferFunction] for more info.
+    pub transfer_function: TransferFunction,
+    /// Rule to handle edge mode, see [crate::EdgeMode] for more info.
+    pub edge_modes: EdgeMode2D,
+}
+
+impl LensBlurParams {
+    pub fn new(radius: u32) -> LensBlurParams {
+        LensBlurParams {
+            radius,
+            blades: 6,
+            roundness: 0.5,
+            rotation: 0.,
+            highlight_threshold: 0.8,
+            highlight_boost: 3.,
+            transfer_function: TransferFunction::Srgb,
+            edge_modes: EdgeMode2D::default(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.radius > LENS_MAX_RADIUS {
+            return Err(BlurError::InvalidParameter {
+                name: "radius",
+                value: self.radius as f64,
+            });
+        }
+        if self.blades < 3 {
+            return Err(BlurError::InvalidParameter {
+                name: "blades",
+                value: self.blades as f64,
+            });
+        }
+        if !(0. ..=1.).contains(&self.roundness) {
+            return Err(BlurError::InvalidParameter {
+                name: "roundness",
+                value: self.roundness as f64,
+            });
+        }
+        if !self.rotation.is_finite() {
+            return Err(BlurError::InvalidParameter {
+                name: "rotation",
+                value: self.rotation as f64,
+            });
+        }
+        if !(0. ..=1.).contains(&self.highlight_threshold) {
+            return Err(BlurError::InvalidParameter {
+                name: "highlight_threshold",
+                value: self.highlight_threshold as f64,
+            });
+        }
+        if !self.highlight_boost.is_finite() || self.highlight_boost < 0. {
+            return Err(BlurError::InvalidParameter {
+                name: "highlight_boost",
+                value: self.highlight_boost as f64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Builds diaphragm kernel of `radius * 2 + 1` size.
+    fn kernel(&self) -> Result<Vec<f32>, BlurError> {
+        let size = self.radius as usize * 2 + 1;
+        // Lens kernel leaves almost two empty pixels on each side of the diaphragm
+        let full_size = size + 4;
+        let full = lens_kernel(
+            KernelShape::new(full_size, full_size),
+            self.blades as f32,
+            0.,
+            1. - self.roundness,
+            self.rotation,
+        )?;
+        Ok(full
+            .chunks_exact(full_size)
+            .skip(2)
+            .take(size)
+            .flat_map(|row| row[2..2 + size].iter().copied())
+            .collect())
+    }
+}
+
+/// Conversions between the image and linear light working image
+struct LensOps<T, Linearize, Store, Load, Save>
+where
+    Linearize: Fn(T) -> f32,
+    Store: Fn(f32) -> T,
+    Load: Fn(T) -> f32,
+    Save: Fn(f32) -> T,
+{
+    linearize: Linearize,
+    store: Store,
+    /// Alpha is not linearized, only scaled into `0..1`
+    load_alpha: Load,
+    save_alpha: Save,
+}
+
+fn lens_blur_impl<T, Linearize, Store, Load, Save>(
+    image: &BlurImage<T>,
+    destination: &mut BlurImageMut<T>,
+    params: LensBlurParams,
+    threading_policy: ThreadingPolicy,
+    ops: LensOps<T, Linearize, Store, Load, Save>,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + std::fmt::Debug,
+    Linearize: Fn(T) -> f32,
+    Store: Fn(f32) -> T,
+    Load: Fn(T) -> f32,
+    Save: Fn(f32) -> T,
+{
+    image.check_layout()?;
+    destination.check_layout(Some(image))?;
+    image.size_matches_mut(destination)?;
+    params.validate()?;
+    let channels = image.channels;
+    if !matches!(
+        channels,
+        FastBlurChannels::Channels3 | FastBlurChannels::Channels4
+    ) {
+        return Err(BlurError::InvalidChannels(channels.channels()));
+    }
+
+    let cn = channels.channels();
+    let row_size = image.width as usize * cn;
+    let mut linear = BlurImageMut::<f32>::alloc(image.width, image.height, channels);
+    let linear_stride = linear.row_stride() as usize;
+
+    // Boost grows quadratically from threshold, so bright points bloom into visible discs
+    let boost_range = (1. - params.highlight_threshold).max(f32::EPSILON);
+    for (dst, src) in linear
+        .data
+        .borrow_mut()
+        .chunks_exact_mut(linear_stride)
+        .zip(image.data.chunks(image.row_stride() as usize))
+    {
+        for (dst, src) in dst[..row_size]
+            .chunks_exact_mut(cn)
+            .zip(src[..row_size].chunks_exact(cn))
+        {
+            let r = (ops.linearize)(src[0]);
+            let g = (ops.linearize)(src[1]);
+            let b = (ops.linearize)(src[2]);
+            let t = ((r.max(g).max(b) - params.highlight_threshold) / boost_range).clamp(0., 1.);
+            let gain = 1. + params.highlight_boost * t * t;
+            dst[0] = r * gain;
+            dst[1] = g * gain;
+            dst[2] = b * gain;
+            if cn == 4 {
+                dst[3] = (ops.load_alpha)(src[3]);
+            }
+        }
+    }
+
+    let kernel = params.kernel()?;
+    let kernel_size = params.radius as usize * 2 + 1;
+    let kernel_shape = KernelShape::new(kernel_size, kernel_size);
+    let linear = linear.to_immutable_ref();
+    let mut blurred = BlurImageMut::<f32>::alloc(image.width, image.height, channels);
+
+    if kernel.iter().filter(|&&x| x != 0.).count() <= DIRECT_CONVOLUTION_TAPS {
+        // Direct filter correlates, so kernel is flipped to match FFT convolution
+        let flipped = kernel.iter().rev().copied().collect::<Vec<f32>>();
+        filter_2d::<f32, f32>(
+            &linear,
+            &mut blurred,
+            &flipped,
+            kernel_shape,
+            params.edge_modes,
+            Scalar::default(),
+            threading_policy,
+        )?;
+    } else if channels == FastBlurChannels::Channels3 {
+        filter_2d_rgb_fft::<f32, f32>(
+            &linear,
+            &mut blurred,
+            &kernel,
+            kernel_shape,
+            params.edge_modes,
+            Scalar::default(),
+            threading_policy,
+            FftConvolutionMode::Auto,
+        )?;
+    } else {
+        filter_2d_rgba_fft::<f32, f32>(
+            &linear,
+            &mut blurred,
+            &kernel,
+            kernel_shape,
+            params.edge_modes,
+            Scalar::default(),
+            threading_policy,
+            FftConvolutionMode::Auto,
+        )?;
+    }
+
+    let blurred_stride = blurred.row_stride() as usize;
+    let dst_stride = destination.row_stride() as usize;
+    for (dst, src) in destination
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(blurred.data.borrow().chunks_exact(blurred_stride))
+    {
+        for (dst, src) in dst[..row_size]
+            .chunks_exact_mut(cn)
+            .zip(src[..row_size].chunks_exact(cn))
+        {
+            dst[0] = (ops.store)(src[0]);
+            dst[1] = (ops.store)(src[1]);
+            dst[2] = (ops.store)(src[2]);
+            if cn == 4 {
+                dst[3] = (ops.save_alpha)(src[3]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds a table from linear value in `0..65535` into gamma corrected integral value
+fn make_gamma_table<const MAX: u32>(transfer_function: TransferFunction) -> Vec<f32> {
+    (0..65536)
+        .map(|i| {
+            (transfer_function.gamma(i as f32 / 65535.) * MAX as f32)
+                .round()
+                .min(MAX as f32)
+        })
+        .collect()
+}
+
+/// Performs lens blur, simulates out of focus camera lens with a diaphragm of
+/// the given blade count, roundness and rotation.
+///
+/// Image is linearized, highlights brighter than threshold are boosted so bright points
+/// bloom into bokeh discs, then image is convolved with the diaphragm shaped kernel and
+/// converted back into gamma.
+/// Small kernels are convolved directly, large ones through FFT.
+/// Only RGB and RGBA images are supported, alpha is blurred as is, without boost and linearization.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [LensBlurParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn lens_blur(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: LensBlurParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let transfer_function = params.transfer_function;
+    let linearization: Vec<f32> = (0..256)
+        .map(|i| transfer_function.linearize(i as f32 / 255.))
+        .collect();
+    let gamma = make_gamma_table::<255>(transfer_function);
+    let ops = LensOps {
+        linearize: |v: u8| linearization[v as usize],
+        store: |v: f32| gamma[(v.clamp(0., 1.) * 65535.).round() as usize] as u8,
+        load_alpha: |v: u8| v as f32 * (1. / 255.),
+        save_alpha: |v: f32| (v * 255.).round().clamp(0., 255.) as u8,
+    };
+    lens_blur_impl(image, destination, params, threading_policy, ops)
+}
+
+/// Performs lens blur, simulates out of focus camera lens.
+///
+/// See [lens_blur] for more info.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [LensBlurParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn lens_blur_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    params: LensBlurParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let transfer_function = params.transfer_function;
+    let linearization: Vec<f32> = (0..65536)
+        .map(|i| transfer_function.linearize(i as f32 / 65535.))
+        .collect();
+    let gamma = make_gamma_table::<65535>(transfer_function);
+    let ops = LensOps {
+        linearize: |v: u16| linearization[v as usize],
+        store: |v: f32| gamma[(v.clamp(0., 1.) * 65535.).round() as usize] as u16,
+        load_alpha: |v: u16| v as f32 * (1. / 65535.),
+        save_alpha: |v: f32| (v * 65535.).round().clamp(0., 65535.) as u16,
+    };
+    lens_blur_impl(image, destination, params, threading_policy, ops)
+}
+
+/// Performs lens blur, simulates out of focus camera lens.
+///
+/// See [lens_blur] for more info.
+/// Image is expected to be in `0..1` range, boosted highlights are not clamped from above.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [LensBlurParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn lens_blur_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    params: LensBlurParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let transfer_function = params.transfer_function;
+    let ops = LensOps {
+        linearize: |v: f32| transfer_function.linearize(v),
+        store: |v: f32| transfer_function.gamma(v.max(0.)),
+        load_alpha: |v: f32| v,
+        save_alpha: |v: f32| v,
+    };
+    lens_blur_impl(image, destination, params, threading_policy, ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lens_blur_keeps_flat_image() {
+        let width = 40usize;
+        let height = 30usize;
+        let src = vec![137u8; width * height * 4];
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        // Radius 2 is convolved directly and radius 9 through FFT
+        for radius in [2, 9] {
+            let mut dst = BlurImageMut::default();
+            lens_blur(
+                &image,
+                &mut dst,
+                LensBlurParams::new(radius),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            for &v in dst.data.borrow().iter() {
+                assert!((v as i32 - 137).abs() <= 1, "Radius {radius}, value {v}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_lens_blur_highlight_blooms() {
+        let size = 41usize;
+        let center = size / 2;
+        let mut src = vec![0u16; size * size * 3];
+        src[(center * size + center) * 3..(center * size + center + 1) * 3].fill(65535);
+        let image = BlurImage::borrow(&src, size as u32, size as u32, FastBlurChannels::Channels3);
+
+        let radius = 8usize;
+        let mut params = LensBlurParams::new(radius as u32);
+        params.roundness = 1.;
+        let mut boosted = BlurImageMut::default();
+        lens_blur_u16(&image, &mut boosted, params, ThreadingPolicy::Single).unwrap();
+        params.highlight_boost = 0.;
+        let mut plain = BlurImageMut::default();
+        lens_blur_u16(&image, &mut plain, params, ThreadingPolicy::Single).unwrap();
+
+        let at =
+            |img: &BlurImageMut<u16>, x: usize, y: usize| img.data.borrow()[(y * size + x) * 3];
+        // Disc is flat inside and dark outside
+        let inside = at(&boosted, center, center);
+        assert!(inside > 0);
+        assert_eq!(at(&boosted, center + radius - 1, center), inside);
+        assert_eq!(at(&boosted, center, center - radius + 1), inside);
+        assert_eq!(at(&boosted, center + radius + 1, center), 0);
+        assert_eq!(at(&boosted, center + radius, center + radius), 0);
+        assert!(inside > at(&plain, center, center));
+    }
+
+    #[test]
+    fn test_lens_blur_rejects_bad_input() {
+        let src = vec![0f32; 8 * 8 * 3];
+        let image = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Channels3);
+        let mut dst = BlurImageMut::default();
+        let mut params = LensBlurParams::new(2);
+        params.blades = 2;
+        assert!(matches!(
+            lens_blur_f32(&image, &mut dst, params, ThreadingPolicy::Single),
+            Err(BlurError::InvalidParameter { name: "blades", .. })
+        ));
+        assert!(matches!(
+            lens_blur_f32(
+                &image,
+                &mut dst,
+                LensBlurParams::new(u32::MAX),
+                ThreadingPolicy::Single
+            ),
+            Err(BlurError::InvalidParameter { name: "radius", .. })
+        ));
+
+        let plane = BlurImage::borrow(&src, 24, 8, FastBlurChannels::Plane);
+        assert!(matches!(
+            lens_blur_f32(
+                &plane,
+                &mut dst,
+                LensBlurParams::new(2),
+                ThreadingPolicy::Single
+            ),
+            Err(BlurError::InvalidChannels(1))
+        ));
+    }
+}
@@ -70,8 +70,13 @@ mod adaptive_blur;
 mod avx;
 mod bilateral;
 mod box_filter;
+mod canny;
+#[cfg(feature = "capi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub mod capi;
 mod channels_configuration;
 mod edge_mode;
+mod executor;
 mod fast_bilateral_filter;
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
@@ -92,17 +97,33 @@ mod gaussian;
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 mod gaussian_blur_image;
+mod gradient;
+mod guided_filter;
 mod image;
 mod image_linearization;
 mod img_size;
 mod laplacian;
 mod lens;
+#[cfg(feature = "fft")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
+mod lens_blur;
 mod median_blur;
+mod median_blur_ctmf;
+mod median_blur_f32;
+mod median_blur_u16;
 mod mlaf;
+mod morphology;
 mod motion_blur;
+mod multichannel;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 mod neon;
+mod padded_blur;
+mod planar;
+mod premultiply;
 mod primitives;
+mod pyramid;
+mod recursive_gaussian;
+mod roi;
 mod safe_math;
 mod sobel;
 #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
@@ -116,22 +137,31 @@ mod sve;
 mod threading_policy;
 mod to_storage;
 mod unsafe_slice;
+mod unsharp_mask;
 mod util;
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 mod wasm32;
+mod workspace;
 
 #[cfg(feature = "fft")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
 pub use adaptive_blur::adaptive_blur;
-pub use bilateral::{BilateralBlurParams, bilateral_filter};
+pub use bilateral::{
+    BilateralBlurParams, bilateral_filter, joint_bilateral_filter, joint_bilateral_filter_f32,
+    joint_bilateral_filter_u16,
+};
 pub use box_filter::{
-    BoxBlurParameters, CLTParameters, box_blur, box_blur_f32, box_blur_u16, gaussian_box_blur,
-    gaussian_box_blur_f32, gaussian_box_blur_u16, tent_blur, tent_blur_f32, tent_blur_u16,
+    BlurMap, BoxBlurParameters, CLTParameters, VariableBlurParameters, box_blur, box_blur_f32,
+    box_blur_u16, gaussian_box_blur, gaussian_box_blur_f32, gaussian_box_blur_u16, tent_blur,
+    tent_blur_f32, tent_blur_u16, variable_blur, variable_blur_f32, variable_blur_u16,
 };
+pub use canny::{CannyParams, canny, canny_u16};
 pub use channels_configuration::FastBlurChannels;
 pub use edge_mode::{BorderHandle, EdgeMode, EdgeMode2D, Scalar};
+pub use executor::{BlurExecutor, with_executor};
 pub use fast_bilateral_filter::{
     fast_bilateral_filter, fast_bilateral_filter_f32, fast_bilateral_filter_u16,
+    fast_bilateral_filter_with_workspace,
 };
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
@@ -149,44 +179,86 @@ pub use fast_gaussian_image_next::fast_gaussian_next_blur_image;
 pub use fast_gaussian_next::fast_gaussian_next_f16;
 pub use fast_gaussian_next::{fast_gaussian_next, fast_gaussian_next_f32, fast_gaussian_next_u16};
 pub use filter1d::{
-    KernelShape, filter_1d_approx, filter_1d_complex, filter_1d_complex_fixed_point,
-    filter_1d_exact,
+    KernelShape, filter_1d_approx, filter_1d_approx_with_workspace, filter_1d_complex,
+    filter_1d_complex_fixed_point, filter_1d_exact, filter_1d_exact_with_workspace,
 };
 #[cfg(feature = "fft")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
 pub use filter2d::{
-    FftNumber, fft_next_good_size, filter_2d_fft, filter_2d_fft_complex, filter_2d_rgb_fft,
-    filter_2d_rgb_fft_complex, filter_2d_rgba_fft, filter_2d_rgba_fft_complex,
+    FftConvolutionMode, FftConvolutionPlan, FftNumber, TemplateMatchLocation, TemplateMatchMode,
+    fft_next_good_size, filter_2d_fft, filter_2d_fft_complex, filter_2d_rgb_fft,
+    filter_2d_rgb_fft_complex, filter_2d_rgba_fft, filter_2d_rgba_fft_complex, match_template,
+    richardson_lucy_deconvolution, wiener_deconvolution,
 };
 pub use filter2d::{filter_2d, filter_2d_arbitrary, filter_2d_rgb, filter_2d_rgba};
 pub use gamma_curves::TransferFunction;
 #[cfg(feature = "nightly_f16")]
 pub use gaussian::gaussian_blur_f16;
 pub use gaussian::{
-    ConvolutionMode, GaussianBlurParams, IeeeBinaryConvolutionMode, complex_gaussian_kernel,
-    gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, gaussian_kernel_1d,
-    gaussian_kernel_1d_f64, sigma_size, sigma_size_d,
+    ConvolutionMode, GaussianBlurParams, GaussianPlan, IeeeBinaryConvolutionMode,
+    complex_gaussian_kernel, gaussian_blur, gaussian_blur_f32, gaussian_blur_u16,
+    gaussian_kernel_1d, gaussian_kernel_1d_f64, sigma_size, sigma_size_d,
 };
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 pub use gaussian_blur_image::gaussian_blur_image;
+pub use gradient::{
+    GradientKernel, GradientMagnitudeParams, GradientNorm, GradientParams, derivative_f32,
+    derivative_i16, gradient_kernels, gradient_magnitude, gradient_magnitude_f32,
+    gradient_magnitude_u16,
+};
+pub use guided_filter::{GuidedFilterParams, guided_filter, guided_filter_f32, guided_filter_u16};
 pub use image::{BlurImage, BlurImageMut, BufferStore};
 pub use img_size::ImageSize;
 pub use laplacian::{laplacian, laplacian_kernel};
 pub use lens::lens_kernel;
+#[cfg(feature = "fft")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fft")))]
+pub use lens_blur::{LensBlurParams, lens_blur, lens_blur_f32, lens_blur_u16};
 pub use median_blur::median_blur;
+pub use median_blur_ctmf::median_blur_constant_time;
+pub use median_blur_f32::median_blur_f32;
+pub use median_blur_u16::median_blur_u16;
+pub use morphology::{MorphOp, StructuringElement, morphology, morphology_f32, morphology_u16};
 pub use motion_blur::{generate_motion_kernel, motion_blur};
+pub use multichannel::{
+    ChannelsLayout, MultiChannelImage, MultiChannelImageMut, blur_channels, blur_channels_in_place,
+};
+pub use planar::{
+    BlurPlane, BlurPlaneMut, PlanarImage, PlanarImageMut, PlaneContext, PlaneRadiusMode,
+    Subsampling, blur_planes, blur_planes_in_place, box_blur_planar, fast_gaussian_planar,
+    gaussian_blur_planar, stack_blur_planar,
+};
+pub use premultiply::{
+    AlphaMode, AlphaPremultiply, blur_in_place_with_alpha_mode, blur_with_alpha_mode,
+};
+pub use pyramid::{
+    LaplacianPyramid, PyramidSample, gaussian_pyramid, pyr_down, pyr_down_f32, pyr_down_u16,
+    pyr_up, pyr_up_f32, pyr_up_u16,
+};
+#[cfg(feature = "nightly_f16")]
+pub use recursive_gaussian::recursive_gaussian_f16;
+pub use recursive_gaussian::{
+    GaussianDerivative, RecursiveGaussianParams, recursive_gaussian, recursive_gaussian_f32,
+    recursive_gaussian_u16,
+};
+pub use roi::{Roi, blur_roi, box_blur_roi, fast_gaussian_roi, gaussian_blur_roi, stack_blur_roi};
 pub use sobel::sobel;
 #[cfg(feature = "image")]
 #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 pub use stack_blur_image::stack_blur_image;
-pub use stackblur::stack_blur::stack_blur;
+pub use stackblur::stack_blur::{stack_blur, stack_blur_with_workspace};
 #[cfg(feature = "nightly_f16")]
 pub use stackblur::stack_blur_f16::stack_blur_f16;
 pub use stackblur::stack_blur_f32::stack_blur_f32;
 pub use stackblur::stack_blur_u16;
 pub use threading_policy::ThreadingPolicy;
+pub use unsharp_mask::{
+    UnsharpBlur, UnsharpMaskParams, unsharp_mask, unsharp_mask_f32, unsharp_mask_u16,
+    unsharp_mask_with_workspace,
+};
 pub use util::{BlurError, MismatchedSize};
+pub use workspace::BlurWorkspace;
 
 /// Asymmetric radius container
 #[derive(Copy, Clone, Default, PartialOrd, PartialEq, Debug)]
@@ -223,8 +295,29 @@ impl AnisotropicRadius {
             y_axis: self.y_axis.max(max),
         }
     }
+
+    /// Returns an error if radius on any axis exceeds `max`
+    pub(crate) fn check_max(&self, max: u32) -> Result<(), BlurError> {
+        if self.x_axis > max {
+            return Err(BlurError::InvalidParameter {
+                name: "x_radius",
+                value: self.x_axis as f64,
+            });
+        }
+        if self.y_axis > max {
+            return Err(BlurError::InvalidParameter {
+                name: "y_radius",
+                value: self.y_axis as f64,
+            });
+        }
+        Ok(())
+    }
 }
 
+/// Largest radius accepted by integer O(1) blurs, bounds accumulators,
+/// stacks and padded copies for any radius a caller may pass.
+pub(crate) const MAX_INTEGER_RADIUS: u32 = 65535;
+
 #[cfg(test)]
 mod tests {
 
@@ -285,6 +378,7 @@ mod tests {
             EdgeMode::Clamp.as_2d(),
             Scalar::default(),
             ThreadingPolicy::Single,
+            FftConvolutionMode::Auto,
         )
         .unwrap();
         for (i, &cn) in dst.data.borrow_mut().iter().enumerate() {
@@ -321,7 +321,7 @@ fn push_hist<const CN: usize>(
 ///
 /// This performs a median kernel filter on the image producing edge preserving blur result.
 /// Preferred if you need to save edges.
-/// O(R) complexity.
+/// O(R) complexity, for large radii consider [crate::median_blur_constant_time].
 ///
 /// # Arguments
 ///
@@ -330,8 +330,8 @@ fn push_hist<const CN: usize>(
 /// * `radius` - Radius of kernel
 /// * `channels` - Count of channels in the image, see [FastBlurChannels] for more info
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided.
 #[allow(clippy::too_many_arguments)]
 pub fn median_blur(
     src_image: &BlurImage<u8>,
@@ -343,8 +343,11 @@ pub fn median_blur(
     dst_image.check_layout(Some(src_image))?;
     src_image.size_matches_mut(dst_image)?;
 
+    // Vectorized kernels expect at least one full window in a row
+    let _fits_simd_window = src_image.width as usize > 2 * radius as usize;
+
     #[cfg(all(target_arch = "aarch64", feature = "neon"))]
-    {
+    if _fits_simd_window {
         if radius == 1 {
             use crate::neon::median_blur_3x3;
             median_blur_3x3(
@@ -376,7 +379,7 @@ pub fn median_blur(
     }
     #[cfg(all(target_arch = "x86_64", feature = "avx"))]
     {
-        if std::arch::is_x86_feature_detected!("avx2") {
+        if _fits_simd_window && std::arch::is_x86_feature_detected!("avx2") {
             if radius == 1 {
                 use crate::avx::avx_median_blur_3x3;
                 avx_median_blur_3x3(
@@ -410,6 +413,7 @@ pub fn median_blur(
 
     let _dispatcher = match src_image.channels {
         FastBlurChannels::Plane => median_blur_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_impl::<2>,
         FastBlurChannels::Channels3 => median_blur_impl::<3>,
         FastBlurChannels::Channels4 => median_blur_impl::<4>,
     };
@@ -420,7 +424,7 @@ pub fn median_blur(
 
     let thread_count = threading_policy.thread_count(width, height) as u32;
 
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
 
     let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
 
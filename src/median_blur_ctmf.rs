@@ -0,0 +1,428 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::{BorderedSource, make_edge_lookup};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
+};
+
+const MAX_CTMF_RADIUS: u32 = 32766;
+/// Minimal count of output columns processed against one set of column histograms.
+/// Image is split into vertical strips so histogram memory depends on radius but not on width.
+const CTMF_MIN_STRIP_WIDTH: usize = 512;
+
+struct CtmfBand<'a, const CN: usize> {
+    source: BorderedSource<'a, u8, CN>,
+    width: usize,
+    radius: usize,
+}
+
+impl<const CN: usize> CtmfBand<'_, CN> {
+    #[inline(always)]
+    fn strip_width(&self) -> usize {
+        CTMF_MIN_STRIP_WIDTH
+            .max(2 * self.radius + 1)
+            .min(self.width)
+    }
+
+    /// Count of `u16` histogram bins one worker allocates.
+    #[inline(always)]
+    fn columns_size(&self) -> usize {
+        (self.strip_width() + 2 * self.radius) * CN * 256
+    }
+
+    #[inline(always)]
+    fn update_columns(&self, columns: &mut [u16], strip_x: usize, padded_y: usize, add: bool) {
+        for (i, column) in columns.chunks_exact_mut(CN * 256).enumerate() {
+            for c in 0..CN {
+                let v = self.source.fetch(strip_x + i, padded_y, c) as usize;
+                let bin = unsafe { column.get_unchecked_mut(c * 256 + v) };
+                if add {
+                    *bin += 1;
+                } else {
+                    *bin -= 1;
+                }
+            }
+        }
+    }
+
+    fn process(
+        &self,
+        dst: &UnsafeSlice<u8>,
+        dst_stride: usize,
+        start_y: usize,
+        end_y: usize,
+        columns: &mut [u16],
+    ) {
+        let strip_width = self.strip_width();
+        for strip_x in (0..self.width).step_by(strip_width) {
+            let strip_end = (strip_x + strip_width).min(self.width);
+            let strip_columns = &mut columns[..(strip_end - strip_x + 2 * self.radius) * CN * 256];
+            strip_columns.fill(0);
+            self.process_strip(
+                dst,
+                dst_stride,
+                start_y,
+                end_y,
+                strip_x,
+                strip_end,
+                strip_columns,
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_strip(
+        &self,
+        dst: &UnsafeSlice<u8>,
+        dst_stride: usize,
+        start_y: usize,
+        end_y: usize,
+        strip_x: usize,
+        strip_end: usize,
+        columns: &mut [u16],
+    ) {
+        let kernel_size = 2 * self.radius + 1;
+        let threshold = ((kernel_size * kernel_size) / 2 + 1) as u32;
+
+        for padded_y in start_y..start_y + kernel_size {
+            self.update_columns(columns, strip_x, padded_y, true);
+        }
+
+        let mut fine = [[0u32; 256]; CN];
+        let mut coarse = [[0u32; 16]; CN];
+
+        for y in start_y..end_y {
+            if y != start_y {
+                self.update_columns(columns, strip_x, y - 1, false);
+                self.update_columns(columns, strip_x, y + 2 * self.radius, true);
+            }
+
+            for c in 0..CN {
+                fine[c] = [0u32; 256];
+                coarse[c] = [0u32; 16];
+            }
+            for column in columns.chunks_exact(CN * 256).take(kernel_size) {
+                for c in 0..CN {
+                    let column = &column[c * 256..(c + 1) * 256];
+                    for (i, (dst, &src)) in fine[c].iter_mut().zip(column.iter()).enumerate() {
+                        *dst += src as u32;
+                        coarse[c][i >> 4] += src as u32;
+                    }
+                }
+            }
+
+            let dst_offset = y * dst_stride;
+
+            for x in 0..strip_end - strip_x {
+                if x != 0 {
+                    let removed = &columns[(x - 1) * CN * 256..x * CN * 256];
+                    let added = &columns
+                        [(x + 2 * self.radius) * CN * 256..(x + 2 * self.radius + 1) * CN * 256];
+                    for c in 0..CN {
+                        let removed = &removed[c * 256..(c + 1) * 256];
+                        let added = &added[c * 256..(c + 1) * 256];
+                        for (i, ((dst, &r), &a)) in fine[c]
+                            .iter_mut()
+                            .zip(removed.iter())
+                            .zip(added.iter())
+                            .enumerate()
+                        {
+                            *dst = *dst + a as u32 - r as u32;
+                            coarse[c][i >> 4] = coarse[c][i >> 4] + a as u32 - r as u32;
+                        }
+                    }
+                }
+
+                for c in 0..CN {
+                    let median = find_median(&fine[c], &coarse[c], threshold);
+                    unsafe {
+                        dst.write(dst_offset + (strip_x + x) * CN + c, median);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn find_median(fine: &[u32; 256], coarse: &[u32; 16], threshold: u32) -> u8 {
+    let mut remaining = threshold;
+    let mut group = 0usize;
+    while group < 15 {
+        if coarse[group] >= remaining {
+            break;
+        }
+        remaining -= coarse[group];
+        group += 1;
+    }
+    let mut i = group * 16;
+    let end = i + 16;
+    while i < end - 1 {
+        let bin = fine[i];
+        if bin >= remaining {
+            break;
+        }
+        remaining -= bin;
+        i += 1;
+    }
+    i as u8
+}
+
+/// Performs median blur on the image using constant time median filtering.
+///
+/// This implements Perreault–Hébert algorithm that keeps histograms per column,
+/// so cost per pixel do not depend on radius. Preferred for large radii, for small radii
+/// [crate::median_blur] is usually faster.
+/// O(1) complexity.
+///
+/// Unlike [crate::median_blur] window is always full and pixels out of bounds are
+/// taken according to provided edge mode.
+///
+/// Image is processed in vertical strips, every worker keeps column histograms
+/// of `(max(512, 2 * radius + 1) + 2 * radius) * channels * 512` bytes.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel, must be less than 32767
+/// * `edge_modes` - See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - see [ThreadingPolicy] for more info.
+pub fn median_blur_constant_time(
+    src_image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    radius: u32,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src_image.check_layout()?;
+    dst_image.check_layout(Some(src_image))?;
+    src_image.size_matches_mut(dst_image)?;
+    if radius > MAX_CTMF_RADIUS {
+        return Err(BlurError::InvalidParameter {
+            name: "radius",
+            value: radius as f64,
+        });
+    }
+    if radius == 0 {
+        return src_image.copy_to_mut(dst_image);
+    }
+
+    let _dispatcher = match src_image.channels {
+        FastBlurChannels::Plane => median_blur_ctmf_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_ctmf_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_ctmf_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_ctmf_impl::<4>,
+    };
+    _dispatcher(
+        src_image,
+        dst_image,
+        radius as usize,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    );
+    Ok(())
+}
+
+fn median_blur_ctmf_impl<const CN: usize>(
+    src_image: &BlurImage<u8>,
+    dst_image: &mut BlurImageMut<u8>,
+    radius: usize,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) {
+    let width = src_image.width as usize;
+    let height = src_image.height as usize;
+    let x_lookup = make_edge_lookup(edge_modes.horizontal, width, radius);
+    let y_lookup = make_edge_lookup(edge_modes.vertical, height, radius);
+    let constant: [u8; 4] =
+        std::array::from_fn(|i| border_constant[i].round().clamp(0., 255.) as u8);
+
+    let band = CtmfBand::<CN> {
+        source: BorderedSource {
+            src: src_image.data.as_ref(),
+            src_stride: src_image.row_stride() as usize,
+            x_lookup: &x_lookup,
+            y_lookup: &y_lookup,
+            constant,
+        },
+        width,
+        radius,
+    };
+
+    let thread_count = threading_policy.thread_count(src_image.width, src_image.height);
+    let pool = crate::executor::ThreadPool::new(thread_count);
+
+    let dst_stride = dst_image.row_stride() as usize;
+    let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
+
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index * segment_size;
+        let end_y = if thread_index == thread_count - 1 {
+            height
+        } else {
+            (thread_index + 1) * segment_size
+        };
+        if start_y < end_y {
+            let mut columns = vec![0u16; band.columns_size()];
+            band.process(&unsafe_dst, dst_stride, start_y, end_y, &mut columns);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    fn reference_median(src: &[u8], width: usize, height: usize, radius: usize) -> Vec<u8> {
+        let x_lookup = make_edge_lookup(EdgeMode::Reflect101, width, radius);
+        let y_lookup = make_edge_lookup(EdgeMode::Reflect101, height, radius);
+        let mut dst = vec![0u8; width * height];
+        let mut window = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                window.clear();
+                for ky in y..y + 2 * radius + 1 {
+                    for kx in x..x + 2 * radius + 1 {
+                        window.push(src[y_lookup[ky].unwrap() * width + x_lookup[kx].unwrap()]);
+                    }
+                }
+                window.sort_unstable();
+                dst[y * width + x] = window[window.len() / 2];
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn test_median_ctmf_matches_reference() {
+        let width: usize = 37;
+        let height: usize = 29;
+        let mut state = 0x2545u32;
+        let src = (0..width * height)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect::<Vec<u8>>();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for radius in [1usize, 4, 9] {
+            let mut dst = BlurImageMut::default();
+            median_blur_constant_time(
+                &src_image,
+                &mut dst,
+                radius as u32,
+                EdgeMode::Reflect101.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            let reference = reference_median(&src, width, height, radius);
+            assert_eq!(dst.data.borrow(), reference.as_slice(), "Radius {radius}");
+        }
+    }
+
+    #[test]
+    fn test_median_ctmf_constant_rgba() {
+        let width: usize = 64;
+        let height: usize = 48;
+        let src = vec![126u8; width * height * 4];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let mut dst = BlurImageMut::default();
+        median_blur_constant_time(
+            &src_image,
+            &mut dst,
+            35,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&x| x == 126));
+    }
+
+    #[test]
+    fn test_median_ctmf_strips_match_reference() {
+        let width: usize = CTMF_MIN_STRIP_WIDTH * 2 + 37;
+        let height: usize = 9;
+        let mut state = 0x1f3du32;
+        let src = (0..width * height)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect::<Vec<u8>>();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        median_blur_constant_time(
+            &src_image,
+            &mut dst,
+            3,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        let reference = reference_median(&src, width, height, 3);
+        assert_eq!(dst.data.borrow(), reference.as_slice());
+    }
+
+    #[test]
+    fn test_median_ctmf_rejects_huge_radius() {
+        let src = vec![0u8; 4 * 4];
+        let src_image = BlurImage::borrow(&src, 4, 4, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let result = median_blur_constant_time(
+            &src_image,
+            &mut dst,
+            MAX_CTMF_RADIUS + 1,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        );
+        assert!(matches!(
+            result,
+            Err(BlurError::InvalidParameter { name: "radius", .. })
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::{BorderedSource, make_edge_lookup};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
+};
+
+fn median_blur_f32_impl<const CN: usize>(
+    src_image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    radius: usize,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) {
+    let width = src_image.width as usize;
+    let height = src_image.height as usize;
+    let x_lookup = make_edge_lookup(edge_modes.horizontal, width, radius);
+    let y_lookup = make_edge_lookup(edge_modes.vertical, height, radius);
+    let source = BorderedSource::<f32, CN> {
+        src: src_image.data.as_ref(),
+        src_stride: src_image.row_stride() as usize,
+        x_lookup: &x_lookup,
+        y_lookup: &y_lookup,
+        constant: std::array::from_fn(|i| border_constant[i] as f32),
+    };
+
+    let kernel_size = 2 * radius + 1;
+    let median_index = (kernel_size * kernel_size) / 2;
+
+    let thread_count = threading_policy.thread_count(src_image.width, src_image.height);
+    let pool = crate::executor::ThreadPool::new(thread_count);
+
+    let dst_stride = dst_image.row_stride() as usize;
+    let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
+
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index * segment_size;
+        let end_y = if thread_index == thread_count - 1 {
+            height
+        } else {
+            (thread_index + 1) * segment_size
+        };
+        let mut window = vec![0f32; kernel_size * kernel_size];
+        for y in start_y..end_y {
+            for x in 0..width {
+                for c in 0..CN {
+                    let mut dst = window.iter_mut();
+                    for ky in y..y + kernel_size {
+                        for kx in x..x + kernel_size {
+                            if let Some(dst) = dst.next() {
+                                *dst = source.fetch(kx, ky, c);
+                            }
+                        }
+                    }
+                    let (_, median, _) =
+                        window.select_nth_unstable_by(median_index, f32::total_cmp);
+                    unsafe {
+                        unsafe_dst.write(y * dst_stride + x * CN + c, *median);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Window of the maximum radius takes 16 MiB per thread
+const MEDIAN_F32_MAX_RADIUS: u32 = 1024;
+
+/// Performs median blur on the floating point image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Median is selected from the full window, NaN values are ordered by [f32::total_cmp].
+/// O(R^2) complexity, every thread keeps the whole window, so radius is limited to 1024.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel, must not exceed 1024
+/// * `edge_modes` - See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - see [ThreadingPolicy] for more info.
+pub fn median_blur_f32(
+    src_image: &BlurImage<f32>,
+    dst_image: &mut BlurImageMut<f32>,
+    radius: u32,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src_image.check_layout()?;
+    dst_image.check_layout(Some(src_image))?;
+    src_image.size_matches_mut(dst_image)?;
+    if radius > MEDIAN_F32_MAX_RADIUS {
+        return Err(BlurError::InvalidParameter {
+            name: "radius",
+            value: radius as f64,
+        });
+    }
+    if radius == 0 {
+        return src_image.copy_to_mut(dst_image);
+    }
+    let _dispatcher = match src_image.channels {
+        FastBlurChannels::Plane => median_blur_f32_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_f32_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_f32_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_f32_impl::<4>,
+    };
+    _dispatcher(
+        src_image,
+        dst_image,
+        radius as usize,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_median_f32_constant_border() {
+        let width: usize = 16;
+        let height: usize = 12;
+        let src = vec![0.5f32; width * height];
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        median_blur_f32(
+            &src_image,
+            &mut dst,
+            3,
+            EdgeMode::Constant.as_2d(),
+            Scalar::dup(1.),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.data.borrow();
+        // Corner window takes 33 out of 49 values from the border
+        assert_eq!(dst[0], 1.);
+        assert_eq!(dst[width * height / 2 + width / 2], 0.5);
+    }
+
+    #[test]
+    fn test_median_f32_rejects_huge_radius() {
+        let src = vec![0.5f32; 4 * 4];
+        let src_image = BlurImage::borrow(&src, 4, 4, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let result = median_blur_f32(
+            &src_image,
+            &mut dst,
+            MEDIAN_F32_MAX_RADIUS + 1,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        );
+        assert!(matches!(
+            result,
+            Err(BlurError::InvalidParameter { name: "radius", .. })
+        ));
+    }
+}
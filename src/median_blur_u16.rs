@@ -0,0 +1,250 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::{BorderedSource, make_edge_lookup};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar, ThreadingPolicy,
+};
+
+struct MedianHistogram16<const CN: usize> {
+    fine: Vec<u32>,
+    coarse: Vec<u32>,
+}
+
+impl<const CN: usize> MedianHistogram16<CN> {
+    fn new() -> Self {
+        Self {
+            fine: vec![0u32; CN * 65536],
+            coarse: vec![0u32; CN * 256],
+        }
+    }
+
+    #[inline(always)]
+    fn update_column(
+        &mut self,
+        source: &BorderedSource<u16, CN>,
+        padded_x: usize,
+        padded_y: usize,
+        kernel_size: usize,
+        add: bool,
+    ) {
+        for y in padded_y..padded_y + kernel_size {
+            for c in 0..CN {
+                let v = source.fetch(padded_x, y, c) as usize;
+                let fine = unsafe { self.fine.get_unchecked_mut(c * 65536 + v) };
+                let coarse = unsafe { self.coarse.get_unchecked_mut(c * 256 + (v >> 8)) };
+                if add {
+                    *fine += 1;
+                    *coarse += 1;
+                } else {
+                    *fine -= 1;
+                    *coarse -= 1;
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn median(&self, c: usize, threshold: u32) -> u16 {
+        let coarse = &self.coarse[c * 256..(c + 1) * 256];
+        let mut remaining = threshold;
+        let mut group = 0usize;
+        while group < 255 {
+            if coarse[group] >= remaining {
+                break;
+            }
+            remaining -= coarse[group];
+            group += 1;
+        }
+        let fine = &self.fine[c * 65536 + group * 256..c * 65536 + (group + 1) * 256];
+        let mut i = 0usize;
+        while i < 255 {
+            if fine[i] >= remaining {
+                break;
+            }
+            remaining -= fine[i];
+            i += 1;
+        }
+        (group * 256 + i) as u16
+    }
+}
+
+fn median_blur_u16_impl<const CN: usize>(
+    src_image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    radius: usize,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) {
+    let width = src_image.width as usize;
+    let height = src_image.height as usize;
+    let x_lookup = make_edge_lookup(edge_modes.horizontal, width, radius);
+    let y_lookup = make_edge_lookup(edge_modes.vertical, height, radius);
+    let source = BorderedSource::<u16, CN> {
+        src: src_image.data.as_ref(),
+        src_stride: src_image.row_stride() as usize,
+        x_lookup: &x_lookup,
+        y_lookup: &y_lookup,
+        constant: std::array::from_fn(|i| border_constant[i].round().clamp(0., 65535.) as u16),
+    };
+
+    let kernel_size = 2 * radius + 1;
+    let threshold = ((kernel_size * kernel_size) / 2 + 1) as u32;
+
+    let thread_count = threading_policy.thread_count(src_image.width, src_image.height);
+    let pool = crate::executor::ThreadPool::new(thread_count);
+
+    let dst_stride = dst_image.row_stride() as usize;
+    let unsafe_dst = UnsafeSlice::new(dst_image.data.borrow_mut());
+
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index * segment_size;
+        let end_y = if thread_index == thread_count - 1 {
+            height
+        } else {
+            (thread_index + 1) * segment_size
+        };
+        if start_y >= end_y {
+            return;
+        }
+        let mut histogram = MedianHistogram16::<CN>::new();
+        for y in start_y..end_y {
+            for padded_x in 0..kernel_size {
+                histogram.update_column(&source, padded_x, y, kernel_size, true);
+            }
+            for x in 0..width {
+                if x != 0 {
+                    histogram.update_column(&source, x - 1, y, kernel_size, false);
+                    histogram.update_column(&source, x + 2 * radius, y, kernel_size, true);
+                }
+                for c in 0..CN {
+                    unsafe {
+                        unsafe_dst
+                            .write(y * dst_stride + x * CN + c, histogram.median(c, threshold));
+                    }
+                }
+            }
+            // Drain the last window so histogram is zeroed without touching all bins
+            for padded_x in width - 1..width + 2 * radius {
+                histogram.update_column(&source, padded_x, y, kernel_size, false);
+            }
+        }
+    });
+}
+
+/// Performs median blur on the 16-bit image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Uses two-level sliding histogram, so it works for any bit-depth up to 16.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_image` - Src image, see [BlurImage] for more info
+/// * `dst_image` - Destination image, see [BlurImageMut] for more info
+/// * `radius` - Radius of kernel
+/// * `edge_modes` - See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - see [ThreadingPolicy] for more info.
+pub fn median_blur_u16(
+    src_image: &BlurImage<u16>,
+    dst_image: &mut BlurImageMut<u16>,
+    radius: u32,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src_image.check_layout()?;
+    dst_image.check_layout(Some(src_image))?;
+    src_image.size_matches_mut(dst_image)?;
+    // Window area must fit into histogram counters
+    if radius > 32766 {
+        return Err(BlurError::InvalidParameter {
+            name: "radius",
+            value: radius as f64,
+        });
+    }
+    if radius == 0 {
+        return src_image.copy_to_mut(dst_image);
+    }
+    let _dispatcher = match src_image.channels {
+        FastBlurChannels::Plane => median_blur_u16_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_u16_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_u16_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_u16_impl::<4>,
+    };
+    _dispatcher(
+        src_image,
+        dst_image,
+        radius as usize,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_median_u16_removes_impulses() {
+        let width: usize = 48;
+        let height: usize = 32;
+        let mut src = vec![17234u16; width * height * 3];
+        for (i, px) in src.chunks_exact_mut(3).enumerate() {
+            if i % 7 == 0 {
+                px[0] = 65535;
+                px[1] = 0;
+                px[2] = 1;
+            }
+        }
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        median_blur_u16(
+            &src_image,
+            &mut dst,
+            2,
+            EdgeMode::Reflect.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&x| x == 17234));
+    }
+}
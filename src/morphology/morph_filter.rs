@@ -0,0 +1,703 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::make_edge_lookup;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::morphology::morph_pixel::{MorphPixel, morph_reduce};
+use crate::morphology::structuring_element::{MaskRun, StructuringElement, mask_runs};
+use crate::morphology::van_herk::van_herk_1d;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode2D, FastBlurChannels, KernelShape, Scalar,
+    ThreadingPolicy,
+};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+/// Declares morphological operation
+pub enum MorphOp {
+    /// Minimum over structuring element
+    Erode,
+    /// Maximum over structuring element
+    Dilate,
+    /// Erosion followed by dilation
+    Open,
+    /// Dilation followed by erosion
+    Close,
+    /// Difference between dilation and erosion
+    Gradient,
+    /// Difference between source and its opening
+    TopHat,
+    /// Difference between closing and source
+    BlackHat,
+}
+
+enum PreparedElement {
+    Rect(KernelShape),
+    Runs {
+        shape: KernelShape,
+        runs: Vec<MaskRun>,
+        lengths: Vec<usize>,
+    },
+}
+
+impl PreparedElement {
+    fn new(element: &StructuringElement) -> Result<PreparedElement, BlurError> {
+        let mask = element.mask()?;
+        let shape = element.shape();
+        if mask.iter().all(|&x| x != 0) {
+            return Ok(PreparedElement::Rect(shape));
+        }
+        let (runs, lengths) = mask_runs(&mask, shape);
+        Ok(PreparedElement::Runs {
+            shape,
+            runs,
+            lengths,
+        })
+    }
+
+    fn shape(&self) -> KernelShape {
+        match self {
+            PreparedElement::Rect(shape) => *shape,
+            PreparedElement::Runs { shape, .. } => *shape,
+        }
+    }
+}
+
+struct MorphSource<'a, T: MorphPixel, const CN: usize> {
+    src: &'a [T],
+    src_stride: usize,
+    x_lookup: Vec<Option<usize>>,
+    y_lookup: Vec<Option<usize>>,
+    constant: [T; 4],
+}
+
+impl<'a, T: MorphPixel, const CN: usize> MorphSource<'a, T, CN> {
+    fn new(
+        src: &'a BlurImage<T>,
+        shape: KernelShape,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+    ) -> Self {
+        MorphSource {
+            src: src.data.as_ref(),
+            src_stride: src.row_stride() as usize,
+            x_lookup: make_edge_lookup(edge_modes.horizontal, src.width as usize, shape.width / 2),
+            y_lookup: make_edge_lookup(edge_modes.vertical, src.height as usize, shape.height / 2),
+            constant: std::array::from_fn(|i| T::from_scalar(border_constant[i])),
+        }
+    }
+
+    /// Fills padded row with `width + kernel_width - 1` pixels
+    fn padded_row(&self, padded_y: usize, dst: &mut [T]) {
+        match self.y_lookup[padded_y] {
+            Some(y) => {
+                let src_row = &self.src[y * self.src_stride..];
+                for (dst, &lookup) in dst.chunks_exact_mut(CN).zip(self.x_lookup.iter()) {
+                    match lookup {
+                        Some(x) => dst.copy_from_slice(&src_row[x * CN..(x + 1) * CN]),
+                        None => dst.copy_from_slice(&self.constant[..CN]),
+                    }
+                }
+            }
+            None => {
+                for dst in dst.chunks_exact_mut(CN) {
+                    dst.copy_from_slice(&self.constant[..CN]);
+                }
+            }
+        }
+    }
+}
+
+fn morph_rect<T: MorphPixel, const CN: usize, const ERODE: bool>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    shape: KernelShape,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    pool: &crate::executor::ThreadPool,
+) {
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let source = MorphSource::<T, CN>::new(src, shape, edge_modes, border_constant);
+
+    // Horizontal pass into transient image, so vertical pass sees horizontal extremes
+    let mut transient = vec![T::default(); width * height * CN];
+    let padded_width = width + shape.width - 1;
+    transient
+        .tb_par_chunks_exact_mut(width * CN)
+        .for_each_enumerated(pool, |y, dst_row| {
+            let mut padded = vec![T::default(); padded_width * CN];
+            let mut prefix = vec![T::default(); padded_width * CN];
+            let mut suffix = vec![T::default(); padded_width * CN];
+            source.padded_row(y + shape.height / 2, &mut padded);
+            van_herk_1d::<T, CN, ERODE>(&padded, dst_row, shape.width, &mut prefix, &mut suffix);
+        });
+
+    let padded_height = height + shape.height - 1;
+    let dst_stride = dst.row_stride() as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data.borrow_mut());
+    let transient = transient.as_slice();
+    let thread_count = pool.thread_count();
+
+    pool.parallel_for(|thread_index| {
+        let segment_size = width / thread_count;
+        let start_x = thread_index * segment_size;
+        let end_x = if thread_index == thread_count - 1 {
+            width
+        } else {
+            (thread_index + 1) * segment_size
+        };
+        let mut padded = vec![T::default(); padded_height * CN];
+        let mut prefix = vec![T::default(); padded_height * CN];
+        let mut suffix = vec![T::default(); padded_height * CN];
+        let mut column = vec![T::default(); height * CN];
+        for x in start_x..end_x {
+            for (dst, &lookup) in padded.chunks_exact_mut(CN).zip(source.y_lookup.iter()) {
+                match lookup {
+                    Some(y) => dst.copy_from_slice(
+                        &transient[y * width * CN + x * CN..y * width * CN + (x + 1) * CN],
+                    ),
+                    None => dst.copy_from_slice(&source.constant[..CN]),
+                }
+            }
+            van_herk_1d::<T, CN, ERODE>(
+                &padded,
+                &mut column,
+                shape.height,
+                &mut prefix,
+                &mut suffix,
+            );
+            for (y, src) in column.chunks_exact(CN).enumerate() {
+                for (c, &v) in src.iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(y * dst_stride + x * CN + c, v);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn morph_runs<T: MorphPixel, const CN: usize, const ERODE: bool>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    shape: KernelShape,
+    runs: &[MaskRun],
+    lengths: &[usize],
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    pool: &crate::executor::ThreadPool,
+) {
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let source = MorphSource::<T, CN>::new(src, shape, edge_modes, border_constant);
+    let padded_width = width + shape.width - 1;
+
+    // Runs grouped by element row and then by run length, every group shares one 1D pass
+    let mut row_groups: Vec<Vec<(usize, Vec<usize>)>> = vec![Vec::new(); shape.height];
+    for run in runs.iter() {
+        let length = lengths[run.length_index];
+        let groups = &mut row_groups[run.row];
+        match groups.iter_mut().find(|(l, _)| *l == length) {
+            Some((_, starts)) => starts.push(run.start),
+            None => groups.push((length, vec![run.start])),
+        }
+    }
+
+    let dst_stride = dst.row_stride() as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data.borrow_mut());
+    let thread_count = pool.thread_count();
+
+    pool.parallel_for(|thread_index| {
+        let segment_size = height / thread_count;
+        let start_y = thread_index * segment_size;
+        let end_y = if thread_index == thread_count - 1 {
+            height
+        } else {
+            (thread_index + 1) * segment_size
+        };
+
+        let mut padded = vec![T::default(); padded_width * CN];
+        let mut prefix = vec![T::default(); padded_width * CN];
+        let mut suffix = vec![T::default(); padded_width * CN];
+        // Extremes of one run length over one padded row, reused for every length and row
+        let mut extremes = vec![T::default(); padded_width * CN];
+        let mut acc = vec![T::default(); width * CN];
+
+        for y in start_y..end_y {
+            let mut initialized = false;
+            for (j, groups) in row_groups.iter().enumerate() {
+                if groups.is_empty() {
+                    continue;
+                }
+                source.padded_row(y + j, &mut padded);
+                for (length, starts) in groups.iter() {
+                    let extremes = &mut extremes[..(padded_width - length + 1) * CN];
+                    van_herk_1d::<T, CN, ERODE>(
+                        &padded,
+                        extremes,
+                        *length,
+                        &mut prefix,
+                        &mut suffix,
+                    );
+                    for &start in starts.iter() {
+                        let src = &extremes[start * CN..(start + width) * CN];
+                        if initialized {
+                            for (acc, &v) in acc.iter_mut().zip(src.iter()) {
+                                *acc = morph_reduce::<T, ERODE>(*acc, v);
+                            }
+                        } else {
+                            acc.copy_from_slice(src);
+                            initialized = true;
+                        }
+                    }
+                }
+            }
+
+            for (x, &v) in acc.iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(y * dst_stride + x, v);
+                }
+            }
+        }
+    });
+}
+
+fn morph_basic<T: MorphPixel, const ERODE: bool>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    element: &PreparedElement,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    let thread_count = threading_policy.thread_count(src.width, src.height);
+    let pool = crate::executor::ThreadPool::new(thread_count);
+    match element {
+        PreparedElement::Rect(shape) => {
+            let _dispatcher = match src.channels {
+                FastBlurChannels::Plane => morph_rect::<T, 1, ERODE>,
+                FastBlurChannels::Channels2 => morph_rect::<T, 2, ERODE>,
+                FastBlurChannels::Channels3 => morph_rect::<T, 3, ERODE>,
+                FastBlurChannels::Channels4 => morph_rect::<T, 4, ERODE>,
+            };
+            _dispatcher(src, dst, *shape, edge_modes, border_constant, &pool);
+        }
+        PreparedElement::Runs {
+            shape,
+            runs,
+            lengths,
+        } => {
+            let _dispatcher = match src.channels {
+                FastBlurChannels::Plane => morph_runs::<T, 1, ERODE>,
+                FastBlurChannels::Channels2 => morph_runs::<T, 2, ERODE>,
+                FastBlurChannels::Channels3 => morph_runs::<T, 3, ERODE>,
+                FastBlurChannels::Channels4 => morph_runs::<T, 4, ERODE>,
+            };
+            _dispatcher(
+                src,
+                dst,
+                *shape,
+                runs,
+                lengths,
+                edge_modes,
+                border_constant,
+                &pool,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Stores `minuend - subtrahend` into `dst`, images must have same layout.
+fn morph_difference<T: MorphPixel>(
+    minuend: &BlurImage<T>,
+    subtrahend: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+) {
+    let width = minuend.width as usize * minuend.channels.channels();
+    let minuend_stride = minuend.row_stride() as usize;
+    let subtrahend_stride = subtrahend.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    for ((dst, a), b) in dst
+        .projected()
+        .chunks_mut(dst_stride)
+        .zip(minuend.projected().chunks(minuend_stride))
+        .zip(subtrahend.projected().chunks(subtrahend_stride))
+    {
+        for ((dst, &a), &b) in dst[..width]
+            .iter_mut()
+            .zip(a[..width].iter())
+            .zip(b[..width].iter())
+        {
+            *dst = a.morph_diff(b);
+        }
+    }
+}
+
+fn morphology_impl<T: MorphPixel>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    op: MorphOp,
+    element: &StructuringElement,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    let element = PreparedElement::new(element)?;
+    let shape = element.shape();
+    _ = (src.width as usize)
+        .checked_add(shape.width)
+        .and_then(|_| (src.height as usize).checked_add(shape.height))
+        .ok_or(BlurError::ExceedingPointerSize)?;
+
+    match op {
+        MorphOp::Erode => morph_basic::<T, true>(
+            src,
+            dst,
+            &element,
+            edge_modes,
+            border_constant,
+            threading_policy,
+        ),
+        MorphOp::Dilate => morph_basic::<T, false>(
+            src,
+            dst,
+            &element,
+            edge_modes,
+            border_constant,
+            threading_policy,
+        ),
+        MorphOp::Open | MorphOp::TopHat => {
+            let mut eroded = BlurImageMut::default();
+            morph_basic::<T, true>(
+                src,
+                &mut eroded,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            let eroded = eroded.to_immutable_ref();
+            if op == MorphOp::Open {
+                return morph_basic::<T, false>(
+                    &eroded,
+                    dst,
+                    &element,
+                    edge_modes,
+                    border_constant,
+                    threading_policy,
+                );
+            }
+            let mut opened = BlurImageMut::default();
+            morph_basic::<T, false>(
+                &eroded,
+                &mut opened,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            morph_difference(src, &opened.to_immutable_ref(), dst);
+            Ok(())
+        }
+        MorphOp::Close | MorphOp::BlackHat => {
+            let mut dilated = BlurImageMut::default();
+            morph_basic::<T, false>(
+                src,
+                &mut dilated,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            let dilated = dilated.to_immutable_ref();
+            if op == MorphOp::Close {
+                return morph_basic::<T, true>(
+                    &dilated,
+                    dst,
+                    &element,
+                    edge_modes,
+                    border_constant,
+                    threading_policy,
+                );
+            }
+            let mut closed = BlurImageMut::default();
+            morph_basic::<T, true>(
+                &dilated,
+                &mut closed,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            morph_difference(&closed.to_immutable_ref(), src, dst);
+            Ok(())
+        }
+        MorphOp::Gradient => {
+            let mut dilated = BlurImageMut::default();
+            let mut eroded = BlurImageMut::default();
+            morph_basic::<T, false>(
+                src,
+                &mut dilated,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            morph_basic::<T, true>(
+                src,
+                &mut eroded,
+                &element,
+                edge_modes,
+                border_constant,
+                threading_policy,
+            )?;
+            morph_difference(&dilated.to_immutable_ref(), &eroded.to_immutable_ref(), dst);
+            Ok(())
+        }
+    }
+}
+
+/// Performs morphological operation on the image
+///
+/// Rectangular elements are processed by van Herk/Gil–Werman algorithm in O(1) per pixel,
+/// other elements are decomposed into horizontal runs, which costs O(element height) per pixel.
+/// Compound operations saturate differences at zero.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `op`: See [MorphOp] for more info.
+/// * `element`: See [StructuringElement] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn morphology(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    op: MorphOp,
+    element: &StructuringElement,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        element,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs morphological operation on the 16-bit image
+///
+/// Rectangular elements are processed by van Herk/Gil–Werman algorithm in O(1) per pixel,
+/// other elements are decomposed into horizontal runs, which costs O(element height) per pixel.
+/// Compound operations saturate differences at zero.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `op`: See [MorphOp] for more info.
+/// * `element`: See [StructuringElement] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn morphology_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    op: MorphOp,
+    element: &StructuringElement,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        element,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs morphological operation on the floating point image
+///
+/// Rectangular elements are processed by van Herk/Gil–Werman algorithm in O(1) per pixel,
+/// other elements are decomposed into horizontal runs, which costs O(element height) per pixel.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `op`: See [MorphOp] for more info.
+/// * `element`: See [StructuringElement] for more info.
+/// * `edge_modes`: See [EdgeMode] and [EdgeMode2D] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn morphology_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    op: MorphOp,
+    element: &StructuringElement,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    morphology_impl(
+        src,
+        dst,
+        op,
+        element,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    fn reference_erode(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        mask: &[u8],
+        shape: KernelShape,
+    ) -> Vec<u8> {
+        let x_lookup = make_edge_lookup(EdgeMode::Reflect, width, shape.width / 2);
+        let y_lookup = make_edge_lookup(EdgeMode::Reflect, height, shape.height / 2);
+        let mut dst = vec![0u8; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let mut v = u8::MAX;
+                for ky in 0..shape.height {
+                    for kx in 0..shape.width {
+                        if mask[ky * shape.width + kx] != 0 {
+                            let sy = y_lookup[y + ky].unwrap();
+                            let sx = x_lookup[x + kx].unwrap();
+                            v = v.min(src[sy * width + sx]);
+                        }
+                    }
+                }
+                dst[y * width + x] = v;
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn test_erode_matches_reference() {
+        let width: usize = 41;
+        let height: usize = 33;
+        let src = (0..width * height)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect::<Vec<u8>>();
+        let src_image =
+            BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for element in [
+            StructuringElement::Rect(KernelShape::new(7, 3)),
+            StructuringElement::Cross(KernelShape::new(5, 5)),
+            StructuringElement::Ellipse(KernelShape::new(9, 7)),
+            StructuringElement::Ellipse(KernelShape::new(15, 11)),
+        ] {
+            let mut dst = BlurImageMut::default();
+            morphology(
+                &src_image,
+                &mut dst,
+                MorphOp::Erode,
+                &element,
+                EdgeMode::Reflect.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            let reference = reference_erode(
+                &src,
+                width,
+                height,
+                &element.mask().unwrap(),
+                element.shape(),
+            );
+            assert_eq!(
+                dst.data.borrow(),
+                reference.as_slice(),
+                "Mismatch for {element:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_top_hat_flat_rgb() {
+        let width: usize = 32;
+        let height: usize = 24;
+        let src = vec![0.25f32; width * height * 3];
+        let src_image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        morphology_f32(
+            &src_image,
+            &mut dst,
+            MorphOp::TopHat,
+            &StructuringElement::Rect(KernelShape::new(5, 5)),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert!(dst.data.borrow().iter().all(|&x| x == 0.));
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use std::fmt::Debug;
+
+pub(crate) trait MorphPixel: Copy + Default + Debug + Send + Sync + 'static {
+    fn morph_min(self, other: Self) -> Self;
+    fn morph_max(self, other: Self) -> Self;
+    /// Difference `self - other`, saturated for integral types
+    fn morph_diff(self, other: Self) -> Self;
+    fn from_scalar(v: f64) -> Self;
+}
+
+#[inline(always)]
+pub(crate) fn morph_reduce<T: MorphPixel, const ERODE: bool>(a: T, b: T) -> T {
+    if ERODE {
+        a.morph_min(b)
+    } else {
+        a.morph_max(b)
+    }
+}
+
+macro_rules! impl_morph_pixel_integral {
+    ($t: ty) => {
+        impl MorphPixel for $t {
+            #[inline(always)]
+            fn morph_min(self, other: Self) -> Self {
+                self.min(other)
+            }
+
+            #[inline(always)]
+            fn morph_max(self, other: Self) -> Self {
+                self.max(other)
+            }
+
+            #[inline(always)]
+            fn morph_diff(self, other: Self) -> Self {
+                self.saturating_sub(other)
+            }
+
+            #[inline(always)]
+            fn from_scalar(v: f64) -> Self {
+                v.round().clamp(<$t>::MIN as f64, <$t>::MAX as f64) as $t
+            }
+        }
+    };
+}
+
+impl_morph_pixel_integral!(u8);
+impl_morph_pixel_integral!(u16);
+
+impl MorphPixel for f32 {
+    #[inline(always)]
+    fn morph_min(self, other: Self) -> Self {
+        self.min(other)
+    }
+
+    #[inline(always)]
+    fn morph_max(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    #[inline(always)]
+    fn morph_diff(self, other: Self) -> Self {
+        self - other
+    }
+
+    #[inline(always)]
+    fn from_scalar(v: f64) -> Self {
+        v as f32
+    }
+}
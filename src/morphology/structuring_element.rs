@@ -0,0 +1,165 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, KernelShape, MismatchedSize};
+
+/// Declares structuring element for morphological operations.
+///
+/// Anchor is always at the center, so element size must be odd.
+#[derive(Debug, Clone)]
+pub enum StructuringElement {
+    /// Filled rectangle, processed in O(1) per pixel regardless of size
+    Rect(KernelShape),
+    /// Cross made of the center row and the center column
+    Cross(KernelShape),
+    /// Filled ellipse inscribed into the element bounds
+    Ellipse(KernelShape),
+    /// Arbitrary mask, non-zero values are included into the element
+    Custom {
+        /// Mask of `shape.width * shape.height` size
+        mask: Vec<u8>,
+        shape: KernelShape,
+    },
+}
+
+impl StructuringElement {
+    /// Returns bounding shape of the element
+    pub fn shape(&self) -> KernelShape {
+        match self {
+            StructuringElement::Rect(shape)
+            | StructuringElement::Cross(shape)
+            | StructuringElement::Ellipse(shape) => *shape,
+            StructuringElement::Custom { shape, .. } => *shape,
+        }
+    }
+
+    /// Creates flat mask of the element where included items are 1 and excluded are 0
+    pub fn mask(&self) -> Result<Vec<u8>, BlurError> {
+        let shape = self.shape();
+        if shape.width == 0 || shape.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        if shape.width.is_multiple_of(2) {
+            return Err(BlurError::OddKernel(shape.width));
+        }
+        if shape.height.is_multiple_of(2) {
+            return Err(BlurError::OddKernel(shape.height));
+        }
+        let anchor_x = shape.width / 2;
+        let anchor_y = shape.height / 2;
+        let mask = match self {
+            StructuringElement::Rect(_) => vec![1u8; shape.width * shape.height],
+            StructuringElement::Cross(_) => {
+                let mut mask = vec![0u8; shape.width * shape.height];
+                for (y, row) in mask.chunks_exact_mut(shape.width).enumerate() {
+                    if y == anchor_y {
+                        row.fill(1);
+                    } else {
+                        row[anchor_x] = 1;
+                    }
+                }
+                mask
+            }
+            StructuringElement::Ellipse(_) => {
+                let mut mask = vec![0u8; shape.width * shape.height];
+                let rx = anchor_x as f64 + 0.5;
+                let ry = anchor_y as f64 + 0.5;
+                for (y, row) in mask.chunks_exact_mut(shape.width).enumerate() {
+                    let dy = (y as f64 - anchor_y as f64) / ry;
+                    for (x, dst) in row.iter_mut().enumerate() {
+                        let dx = (x as f64 - anchor_x as f64) / rx;
+                        if dx * dx + dy * dy <= 1. {
+                            *dst = 1;
+                        }
+                    }
+                }
+                mask
+            }
+            StructuringElement::Custom { mask, .. } => {
+                if mask.len() != shape.width * shape.height {
+                    return Err(BlurError::KernelSizeMismatch(MismatchedSize {
+                        expected: shape.width * shape.height,
+                        received: mask.len(),
+                    }));
+                }
+                let mask = mask.iter().map(|&x| (x != 0) as u8).collect::<Vec<u8>>();
+                if mask.iter().all(|&x| x == 0) {
+                    return Err(BlurError::InvalidArguments);
+                }
+                mask
+            }
+        };
+        Ok(mask)
+    }
+}
+
+/// Horizontal run of included mask items
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct MaskRun {
+    /// Row of the element
+    pub(crate) row: usize,
+    /// Start column of the element
+    pub(crate) start: usize,
+    /// Index of the run length in the unique lengths list
+    pub(crate) length_index: usize,
+}
+
+/// Decomposes mask into horizontal runs.
+///
+/// returns: Runs and unique run lengths
+pub(crate) fn mask_runs(mask: &[u8], shape: KernelShape) -> (Vec<MaskRun>, Vec<usize>) {
+    let mut runs = Vec::new();
+    let mut lengths: Vec<usize> = Vec::new();
+    for (y, row) in mask.chunks_exact(shape.width).enumerate() {
+        let mut x = 0usize;
+        while x < shape.width {
+            if row[x] == 0 {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < shape.width && row[x] != 0 {
+                x += 1;
+            }
+            let length = x - start;
+            let length_index = match lengths.iter().position(|&l| l == length) {
+                Some(index) => index,
+                None => {
+                    lengths.push(length);
+                    lengths.len() - 1
+                }
+            };
+            runs.push(MaskRun {
+                row: y,
+                start,
+                length_index,
+            });
+        }
+    }
+    (runs, lengths)
+}
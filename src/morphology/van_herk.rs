@@ -0,0 +1,94 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::morphology::morph_pixel::{MorphPixel, morph_reduce};
+
+/// Computes running minimum or maximum over `window` items using van Herk/Gil–Werman algorithm.
+///
+/// `src` holds interleaved pixels with `CN` channels and must have at least
+/// `dst.len() / CN + window - 1` pixels.
+/// `prefix` and `suffix` are scratch buffers of `src.len()` size.
+/// Cost is three comparisons per pixel regardless of window size.
+pub(crate) fn van_herk_1d<T: MorphPixel, const CN: usize, const ERODE: bool>(
+    src: &[T],
+    dst: &mut [T],
+    window: usize,
+    prefix: &mut [T],
+    suffix: &mut [T],
+) {
+    let dst_len = dst.len() / CN;
+    let len = dst_len + window - 1;
+    assert!(src.len() >= len * CN);
+    assert!(prefix.len() >= len * CN && suffix.len() >= len * CN);
+
+    let mut block_start = 0usize;
+    while block_start < len {
+        let block_end = (block_start + window).min(len);
+        for c in 0..CN {
+            prefix[block_start * CN + c] = src[block_start * CN + c];
+            for i in block_start + 1..block_end {
+                prefix[i * CN + c] =
+                    morph_reduce::<T, ERODE>(prefix[(i - 1) * CN + c], src[i * CN + c]);
+            }
+            suffix[(block_end - 1) * CN + c] = src[(block_end - 1) * CN + c];
+            for i in (block_start..block_end - 1).rev() {
+                suffix[i * CN + c] =
+                    morph_reduce::<T, ERODE>(suffix[(i + 1) * CN + c], src[i * CN + c]);
+            }
+        }
+        block_start = block_end;
+    }
+
+    for (x, dst) in dst.chunks_exact_mut(CN).enumerate() {
+        for (c, dst) in dst.iter_mut().enumerate() {
+            *dst = morph_reduce::<T, ERODE>(suffix[x * CN + c], prefix[(x + window - 1) * CN + c]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_van_herk_matches_naive() {
+        let src = (0..53u32)
+            .map(|x| ((x * 7919) % 101) as u8)
+            .collect::<Vec<u8>>();
+        for window in [1usize, 2, 3, 5, 8, 13] {
+            let mut dst = vec![0u8; src.len() - window + 1];
+            let mut prefix = vec![0u8; src.len()];
+            let mut suffix = vec![0u8; src.len()];
+            van_herk_1d::<u8, 1, true>(&src, &mut dst, window, &mut prefix, &mut suffix);
+            for (x, &v) in dst.iter().enumerate() {
+                let naive = *src[x..x + window].iter().min().unwrap();
+                assert_eq!(v, naive, "Window {window} at {x}");
+            }
+        }
+    }
+}
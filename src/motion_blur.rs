@@ -85,8 +85,18 @@ fn draw_line_bresenham(
     result
 }
 
-pub fn generate_motion_kernel(size: usize, angle_deg: f32) -> Vec<f32> {
-    let mut kernel = vec![0.0; size * size];
+/// Generates normalized `size * size` line kernel rotated by `angle_deg`.
+pub fn generate_motion_kernel(size: usize, angle_deg: f32) -> Result<Vec<f32>, BlurError> {
+    if !angle_deg.is_finite() {
+        return Err(BlurError::InvalidParameter {
+            name: "angle",
+            value: angle_deg as f64,
+        });
+    }
+    let kernel_len = size
+        .checked_mul(size)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let mut kernel = vec![0.0; kernel_len];
 
     // Convert the angle to radians
     let angle_rad = angle_deg * std::f32::consts::PI / 180.0;
@@ -116,11 +126,13 @@ pub fn generate_motion_kernel(size: usize, angle_deg: f32) -> Vec<f32> {
         sum += 1.;
     }
 
-    for item in kernel.iter_mut() {
-        *item /= sum;
+    if sum != 0. {
+        for item in kernel.iter_mut() {
+            *item /= sum;
+        }
     }
 
-    kernel
+    Ok(kernel)
 }
 
 /// Performs motion blur on the image
@@ -152,7 +164,7 @@ pub fn motion_blur(
     if kernel_size & 1 == 0 {
         return Err(BlurError::OddKernel(kernel_size));
     }
-    let kernel = generate_motion_kernel(kernel_size, angle);
+    let kernel = generate_motion_kernel(kernel_size, angle)?;
     filter_2d::<u8, f32>(
         image,
         destination,
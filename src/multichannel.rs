@@ -0,0 +1,892 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, BlurImage, BlurImageMut, BufferStore, FastBlurChannels, MismatchedSize};
+use std::fmt::Debug;
+
+/// Memory layout of [MultiChannelImage]
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ChannelsLayout {
+    /// Channels of each pixel are stored next to each other, stride is a row stride of the image
+    #[default]
+    Interleaved,
+    /// Each channel is stored as a separate plane, planes are stored one after another,
+    /// stride is a row stride of a single plane
+    Planar,
+}
+
+/// Immutable image with an arbitrary channels count
+///
+/// Used for multispectral images, feature maps etc. that doesn't fit into [FastBlurChannels].
+#[derive(Debug, Clone)]
+pub struct MultiChannelImage<'a, T: Clone + Copy + Default + Debug> {
+    pub data: std::borrow::Cow<'a, [T]>,
+    pub width: u32,
+    pub height: u32,
+    /// Row stride in elements, for [ChannelsLayout::Planar] it is a row stride of a single plane
+    pub stride: u32,
+    pub channels: usize,
+    pub layout: ChannelsLayout,
+}
+
+/// Mutable image with an arbitrary channels count
+#[derive(Debug)]
+pub struct MultiChannelImageMut<'a, T: Clone + Copy + Default + Debug> {
+    pub data: BufferStore<'a, T>,
+    pub width: u32,
+    pub height: u32,
+    /// Row stride in elements, for [ChannelsLayout::Planar] it is a row stride of a single plane
+    pub stride: u32,
+    pub channels: usize,
+    pub layout: ChannelsLayout,
+}
+
+fn default_stride(width: u32, channels: usize, layout: ChannelsLayout) -> u32 {
+    match layout {
+        ChannelsLayout::Interleaved => width.saturating_mul(channels as u32),
+        ChannelsLayout::Planar => width,
+    }
+}
+
+fn packed_size(width: u32, height: u32, channels: usize) -> Result<usize, BlurError> {
+    let row_size = (width as usize)
+        .checked_mul(channels)
+        .filter(|&x| x <= u32::MAX as usize)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    row_size
+        .checked_mul(height as usize)
+        .ok_or(BlurError::ExceedingPointerSize)
+}
+
+fn check_multichannel_layout(
+    data_len: usize,
+    width: u32,
+    height: u32,
+    stride: usize,
+    channels: usize,
+    layout: ChannelsLayout,
+) -> Result<(), BlurError> {
+    if width == 0 || height == 0 {
+        return Err(BlurError::ZeroBaseSize);
+    }
+    if channels == 0 {
+        return Err(BlurError::InvalidArguments);
+    }
+    let row_size = match layout {
+        ChannelsLayout::Interleaved => (width as usize)
+            .checked_mul(channels)
+            .ok_or(BlurError::ExceedingPointerSize)?,
+        ChannelsLayout::Planar => width as usize,
+    };
+    if stride < row_size {
+        return Err(BlurError::MinimumStrideSizeMismatch(MismatchedSize {
+            expected: row_size,
+            received: stride,
+        }));
+    }
+    let plane_size = stride
+        .checked_mul(height as usize)
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    let planes = match layout {
+        ChannelsLayout::Interleaved => 1,
+        ChannelsLayout::Planar => channels,
+    };
+    let required = plane_size
+        .checked_mul(planes - 1)
+        .and_then(|x| x.checked_add(stride * (height as usize - 1) + row_size))
+        .ok_or(BlurError::ExceedingPointerSize)?;
+    if data_len < required {
+        return Err(BlurError::MinimumSliceSizeMismatch(MismatchedSize {
+            expected: required,
+            received: data_len,
+        }));
+    }
+    Ok(())
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> MultiChannelImage<'a, T> {
+    /// Borrows existing data
+    /// Stride will be default `width * channels` for interleaved and `width` for planar layout
+    pub fn borrow(
+        arr: &'a [T],
+        width: u32,
+        height: u32,
+        channels: usize,
+        layout: ChannelsLayout,
+    ) -> Self {
+        Self {
+            data: std::borrow::Cow::Borrowed(arr),
+            width,
+            height,
+            stride: default_stride(width, channels, layout),
+            channels,
+            layout,
+        }
+    }
+
+    /// Returns row stride
+    #[inline]
+    pub fn row_stride(&self) -> u32 {
+        if self.stride == 0 {
+            default_stride(self.width, self.channels, self.layout)
+        } else {
+            self.stride
+        }
+    }
+
+    /// Checks if layout matches necessary requirements
+    pub fn check_layout(&self) -> Result<(), BlurError> {
+        check_multichannel_layout(
+            self.data.len(),
+            self.width,
+            self.height,
+            self.row_stride() as usize,
+            self.channels,
+            self.layout,
+        )
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> MultiChannelImageMut<'a, T> {
+    /// Allocates default image layout
+    pub fn alloc(
+        width: u32,
+        height: u32,
+        channels: usize,
+        layout: ChannelsLayout,
+    ) -> Result<Self, BlurError> {
+        let size = packed_size(width, height, channels)?;
+        Ok(Self {
+            data: BufferStore::Owned(vec![T::default(); size]),
+            width,
+            height,
+            stride: default_stride(width, channels, layout),
+            channels,
+            layout,
+        })
+    }
+
+    /// Mutable borrows existing data
+    /// Stride will be default `width * channels` for interleaved and `width` for planar layout
+    pub fn borrow(
+        arr: &'a mut [T],
+        width: u32,
+        height: u32,
+        channels: usize,
+        layout: ChannelsLayout,
+    ) -> Self {
+        Self {
+            data: BufferStore::Borrowed(arr),
+            width,
+            height,
+            stride: default_stride(width, channels, layout),
+            channels,
+            layout,
+        }
+    }
+
+    /// Returns row stride
+    #[inline]
+    pub fn row_stride(&self) -> u32 {
+        if self.stride == 0 {
+            default_stride(self.width, self.channels, self.layout)
+        } else {
+            self.stride
+        }
+    }
+
+    /// Checks if layout matches necessary requirements,
+    /// owned image will be resized to match the `other` one
+    pub fn check_layout(
+        &mut self,
+        other: Option<&MultiChannelImage<'_, T>>,
+    ) -> Result<(), BlurError> {
+        if let Some(other) = other
+            && let BufferStore::Owned(data) = &mut self.data
+        {
+            let size = packed_size(other.width, other.height, other.channels)?;
+            self.width = other.width;
+            self.height = other.height;
+            self.channels = other.channels;
+            self.layout = other.layout;
+            self.stride = default_stride(other.width, other.channels, other.layout);
+            data.resize(size, T::default());
+            return Ok(());
+        }
+        check_multichannel_layout(
+            self.data.borrow().len(),
+            self.width,
+            self.height,
+            self.row_stride() as usize,
+            self.channels,
+            self.layout,
+        )
+    }
+
+    #[inline]
+    pub fn to_immutable_ref(&self) -> MultiChannelImage<'_, T> {
+        MultiChannelImage {
+            data: std::borrow::Cow::Borrowed(self.data.borrow()),
+            width: self.width,
+            height: self.height,
+            stride: self.row_stride(),
+            channels: self.channels,
+            layout: self.layout,
+        }
+    }
+}
+
+fn group_channels(cn: usize) -> FastBlurChannels {
+    match cn {
+        1 => FastBlurChannels::Plane,
+        2 => FastBlurChannels::Channels2,
+        3 => FastBlurChannels::Channels3,
+        _ => FastBlurChannels::Channels4,
+    }
+}
+
+/// Gathers `cn` channels starting from `start` out of interleaved image into a new image
+#[allow(clippy::too_many_arguments)]
+fn gather<'f, T: Copy + Default + Debug>(
+    data: &[T],
+    stride: usize,
+    width: u32,
+    height: u32,
+    channels: usize,
+    start: usize,
+    cn: usize,
+) -> BlurImageMut<'f, T> {
+    let mut group = BlurImageMut::alloc(width, height, group_channels(cn));
+    let row_size = width as usize * cn;
+    for (dst, src) in group
+        .data
+        .borrow_mut()
+        .chunks_exact_mut(row_size)
+        .zip(data.chunks(stride))
+    {
+        for (dst, src) in dst.chunks_exact_mut(cn).zip(src.chunks_exact(channels)) {
+            dst.copy_from_slice(&src[start..start + cn]);
+        }
+    }
+    group
+}
+
+/// Writes channels of the `group` back to interleaved image starting from `start`
+fn scatter<T: Copy + Default + Debug>(
+    group: &BlurImageMut<T>,
+    data: &mut [T],
+    stride: usize,
+    channels: usize,
+    start: usize,
+) {
+    let cn = group.channels.channels();
+    let group_stride = group.row_stride() as usize;
+    let row_size = group.width as usize * cn;
+    for (src, dst) in group
+        .data
+        .borrow()
+        .chunks(group_stride)
+        .zip(data.chunks_mut(stride))
+    {
+        for (src, dst) in src[..row_size]
+            .chunks_exact(cn)
+            .zip(dst.chunks_exact_mut(channels))
+        {
+            dst[start..start + cn].copy_from_slice(src);
+        }
+    }
+}
+
+/// Blurs interleaved image by splitting its channels into groups of at most `group` channels,
+/// each group is blurred as a separate interleaved image.
+#[allow(clippy::too_many_arguments)]
+fn blur_grouped<T, F>(
+    src: &[T],
+    src_stride: usize,
+    dst: &mut [T],
+    dst_stride: usize,
+    width: u32,
+    height: u32,
+    channels: usize,
+    group: usize,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+    F: Fn(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    let mut start = 0usize;
+    while start < channels {
+        let cn = group.min(channels - start);
+        let gathered = gather(src, src_stride, width, height, channels, start, cn);
+        let gathered_ref = gathered.to_immutable_ref();
+        let mut blurred = BlurImageMut::alloc(width, height, gathered.channels);
+        blur(&gathered_ref, &mut blurred)?;
+        scatter(&blurred, dst, dst_stride, channels, start);
+        start += cn;
+    }
+    Ok(())
+}
+
+/// Blurs each channel of an interleaved image as a separate plane,
+/// used where native kernel for the layout is not available
+pub(crate) fn blur_split_planes<T, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+    F: Fn(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    let src_stride = src.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    blur_grouped(
+        src.data.as_ref(),
+        src_stride,
+        dst.data.borrow_mut(),
+        dst_stride,
+        src.width,
+        src.height,
+        src.channels.channels(),
+        1,
+        blur,
+    )
+}
+
+/// Blurs an image with an arbitrary channels count
+///
+/// Interleaved images up to 4 channels are blurred natively, images with more channels
+/// are split into groups of 4 channels, each group is blurred as RGBA image.
+/// Planes of planar images are blurred one by one as [FastBlurChannels::Plane] images.
+///
+/// This works with any blur of the crate taking a source and a destination,
+/// e.g. [crate::gaussian_blur], [crate::box_blur] or [crate::filter_1d_exact].
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image, must have the same size, channels count and layout.
+/// * `blur`: Blur to perform on each group of channels.
+///
+/// # Example
+///
+/// ```
+/// use libblur::{AlphaMode, ChannelsLayout, ConvolutionMode, EdgeMode, GaussianBlurParams, MultiChannelImage, MultiChannelImageMut, ThreadingPolicy};
+/// let data = vec![0u8; 16 * 16 * 6];
+/// let src = MultiChannelImage::borrow(&data, 16, 16, 6, ChannelsLayout::Interleaved);
+/// let mut dst = MultiChannelImageMut::alloc(16, 16, 6, ChannelsLayout::Interleaved).unwrap();
+/// libblur::blur_channels(&src, &mut dst, |src, dst| {
+///     libblur::gaussian_blur(
+///         src,
+///         dst,
+///         GaussianBlurParams::try_new_from_kernel(5.)?,
+///         EdgeMode::Clamp.as_2d(),
+///         ThreadingPolicy::Single,
+///         ConvolutionMode::Exact,
+///         AlphaMode::Premultiplied,
+///     )
+/// })
+/// .unwrap();
+/// ```
+pub fn blur_channels<T, F>(
+    src: &MultiChannelImage<T>,
+    dst: &mut MultiChannelImageMut<T>,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+    F: Fn(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    if src.width != dst.width
+        || src.height != dst.height
+        || src.channels != dst.channels
+        || src.layout != dst.layout
+    {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    let src_stride = src.row_stride();
+    let dst_stride = dst.row_stride();
+    match src.layout {
+        ChannelsLayout::Interleaved if src.channels <= 4 => {
+            let channels = group_channels(src.channels);
+            let src_image = BlurImage {
+                data: std::borrow::Cow::Borrowed(src.data.as_ref()),
+                width: src.width,
+                height: src.height,
+                stride: src_stride,
+                channels,
+            };
+            let mut dst_image = BlurImageMut {
+                data: BufferStore::Borrowed(dst.data.borrow_mut()),
+                width: src.width,
+                height: src.height,
+                stride: dst_stride,
+                channels,
+            };
+            blur(&src_image, &mut dst_image)
+        }
+        ChannelsLayout::Interleaved => blur_grouped(
+            src.data.as_ref(),
+            src_stride as usize,
+            dst.data.borrow_mut(),
+            dst_stride as usize,
+            src.width,
+            src.height,
+            src.channels,
+            4,
+            blur,
+        ),
+        ChannelsLayout::Planar => {
+            let src_plane = src_stride as usize * src.height as usize;
+            let dst_plane = dst_stride as usize * dst.height as usize;
+            for (src_data, dst_data) in src
+                .data
+                .chunks(src_plane)
+                .zip(dst.data.borrow_mut().chunks_mut(dst_plane))
+                .take(src.channels)
+            {
+                let src_image = BlurImage {
+                    data: std::borrow::Cow::Borrowed(src_data),
+                    width: src.width,
+                    height: src.height,
+                    stride: src_stride,
+                    channels: FastBlurChannels::Plane,
+                };
+                let mut dst_image = BlurImageMut {
+                    data: BufferStore::Borrowed(dst_data),
+                    width: src.width,
+                    height: src.height,
+                    stride: dst_stride,
+                    channels: FastBlurChannels::Plane,
+                };
+                blur(&src_image, &mut dst_image)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Blurs an image with an arbitrary channels count in place
+///
+/// See [blur_channels] for more info, this one works with in-place blurs,
+/// e.g. [crate::stack_blur] or [crate::fast_gaussian].
+///
+/// # Arguments
+///
+/// * `image`: Image that will be used as a source and destination.
+/// * `blur`: Blur to perform on each group of channels.
+pub fn blur_channels_in_place<T, F>(
+    image: &mut MultiChannelImageMut<T>,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug,
+    F: Fn(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    image.check_layout(None)?;
+    let stride = image.row_stride();
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+    match image.layout {
+        ChannelsLayout::Interleaved if channels <= 4 => {
+            let mut view = BlurImageMut {
+                data: BufferStore::Borrowed(image.data.borrow_mut()),
+                width,
+                height,
+                stride,
+                channels: group_channels(channels),
+            };
+            blur(&mut view)
+        }
+        ChannelsLayout::Interleaved => {
+            let mut start = 0usize;
+            while start < channels {
+                let cn = 4.min(channels - start);
+                let mut gathered = gather(
+                    image.data.borrow(),
+                    stride as usize,
+                    width,
+                    height,
+                    channels,
+                    start,
+                    cn,
+                );
+                blur(&mut gathered)?;
+                scatter(
+                    &gathered,
+                    image.data.borrow_mut(),
+                    stride as usize,
+                    channels,
+                    start,
+                );
+                start += cn;
+            }
+            Ok(())
+        }
+        ChannelsLayout::Planar => {
+            let plane = stride as usize * height as usize;
+            for data in image.data.borrow_mut().chunks_mut(plane).take(channels) {
+                let mut view = BlurImageMut {
+                    data: BufferStore::Borrowed(data),
+                    width,
+                    height,
+                    stride,
+                    channels: FastBlurChannels::Plane,
+                };
+                blur(&mut view)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AlphaMode, AnisotropicRadius, BoxBlurParameters, ConvolutionMode, EdgeMode,
+        GaussianBlurParams, IeeeBinaryConvolutionMode, Scalar, ThreadingPolicy, box_blur,
+        fast_gaussian, fast_gaussian_next, gaussian_blur, gaussian_blur_f32, median_blur,
+        stack_blur, stack_blur_f32, stack_blur_u16,
+    };
+
+    fn pattern<T: Copy>(len: usize, cast: impl Fn(usize) -> T) -> Vec<T> {
+        (0..len)
+            .map(|i| cast((i * 37 + (i / 7) * 11) % 256))
+            .collect()
+    }
+
+    fn assert_close_u8(a: &[u8], b: &[u8], tolerance: u8, method: &str) {
+        for (i, (&a, &b)) in a.iter().zip(b.iter()).enumerate() {
+            assert!(
+                a.abs_diff(b) <= tolerance,
+                "{method}: difference at {i}, {a} != {b}"
+            );
+        }
+    }
+
+    fn check_two_channels_u8<F>(method: &str, tolerance: u8, blur: F)
+    where
+        F: Fn(&BlurImage<u8>, &mut BlurImageMut<u8>) -> Result<(), BlurError>,
+    {
+        let (width, height) = (43u32, 29u32);
+        let data = pattern(width as usize * height as usize * 2, |x| x as u8);
+        let src = BlurImage::borrow(&data, width, height, FastBlurChannels::Channels2);
+        let mut native = BlurImageMut::default();
+        blur(&src, &mut native).unwrap();
+        let mut reference = BlurImageMut::default();
+        blur_split_planes(&src, &mut reference, &blur).unwrap();
+        assert_close_u8(
+            native.data.borrow(),
+            reference.data.borrow(),
+            tolerance,
+            method,
+        );
+    }
+
+    fn in_place_u8(
+        blur: impl Fn(&mut BlurImageMut<u8>) -> Result<(), BlurError>,
+    ) -> impl Fn(&BlurImage<u8>, &mut BlurImageMut<u8>) -> Result<(), BlurError> {
+        move |src, dst| {
+            src.copy_to_mut(dst)?;
+            blur(dst)
+        }
+    }
+
+    #[test]
+    fn test_two_channels_u8_matches_planes() {
+        let edges = EdgeMode::Clamp.as_2d();
+        check_two_channels_u8("gaussian exact", 0, |src, dst| {
+            gaussian_blur(
+                src,
+                dst,
+                GaussianBlurParams::new_from_kernel(7.),
+                edges,
+                ThreadingPolicy::Single,
+                ConvolutionMode::Exact,
+                AlphaMode::Premultiplied,
+            )
+        });
+        check_two_channels_u8("gaussian fixed point", 1, |src, dst| {
+            gaussian_blur(
+                src,
+                dst,
+                GaussianBlurParams::new_from_kernel(7.),
+                edges,
+                ThreadingPolicy::Single,
+                ConvolutionMode::FixedPoint,
+                AlphaMode::Premultiplied,
+            )
+        });
+        check_two_channels_u8("box", 0, |src, dst| {
+            box_blur(
+                src,
+                dst,
+                BoxBlurParameters::new(5),
+                edges,
+                Scalar::default(),
+                ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
+            )
+        });
+        for radius in 1..5 {
+            check_two_channels_u8("median", 0, |src, dst| {
+                median_blur(src, dst, radius, ThreadingPolicy::Single)
+            });
+        }
+        check_two_channels_u8(
+            "stack",
+            0,
+            in_place_u8(|image| {
+                stack_blur(
+                    image,
+                    AnisotropicRadius::new(6),
+                    ThreadingPolicy::Single,
+                    edges,
+                    Scalar::default(),
+                    AlphaMode::Premultiplied,
+                )
+            }),
+        );
+        check_two_channels_u8(
+            "fast gaussian",
+            0,
+            in_place_u8(|image| {
+                fast_gaussian(
+                    image,
+                    AnisotropicRadius::new(6),
+                    ThreadingPolicy::Single,
+                    edges,
+                    Scalar::default(),
+                    AlphaMode::Premultiplied,
+                )
+            }),
+        );
+        check_two_channels_u8(
+            "fast gaussian next",
+            0,
+            in_place_u8(|image| {
+                fast_gaussian_next(
+                    image,
+                    AnisotropicRadius::new(6),
+                    ThreadingPolicy::Single,
+                    edges,
+                    Scalar::default(),
+                    AlphaMode::Premultiplied,
+                )
+            }),
+        );
+    }
+
+    #[test]
+    fn test_two_channels_u16_f32_matches_planes() {
+        let (width, height) = (31u32, 23u32);
+        let edges = EdgeMode::Reflect.as_2d();
+
+        let data = pattern(width as usize * height as usize * 2, |x| (x * 255) as u16);
+        let mut native = data.clone();
+        let mut image =
+            BlurImageMut::borrow(&mut native, width, height, FastBlurChannels::Channels2);
+        let stack_u16 = |image: &mut BlurImageMut<u16>| {
+            stack_blur_u16(
+                image,
+                AnisotropicRadius::new(4),
+                ThreadingPolicy::Single,
+                edges,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+        };
+        stack_u16(&mut image).unwrap();
+        let src = BlurImage::borrow(&data, width, height, FastBlurChannels::Channels2);
+        let mut reference = BlurImageMut::default();
+        blur_split_planes(&src, &mut reference, |src, dst| {
+            src.copy_to_mut(dst)?;
+            stack_u16(dst)
+        })
+        .unwrap();
+        assert_eq!(native, reference.data.borrow());
+
+        let data = pattern(width as usize * height as usize * 2, |x| x as f32 / 255.);
+        let src = BlurImage::borrow(&data, width, height, FastBlurChannels::Channels2);
+        let gaussian_f32 = |src: &BlurImage<f32>, dst: &mut BlurImageMut<f32>| {
+            gaussian_blur_f32(
+                src,
+                dst,
+                GaussianBlurParams::new_from_sigma(2.),
+                edges,
+                ThreadingPolicy::Single,
+                IeeeBinaryConvolutionMode::Normal,
+                AlphaMode::Premultiplied,
+            )
+        };
+        let stack_f32 = |src: &BlurImage<f32>, dst: &mut BlurImageMut<f32>| {
+            src.copy_to_mut(dst)?;
+            stack_blur_f32(
+                dst,
+                AnisotropicRadius::new(5),
+                ThreadingPolicy::Single,
+                edges,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+        };
+        let mut native = BlurImageMut::default();
+        let mut reference = BlurImageMut::default();
+        gaussian_f32(&src, &mut native).unwrap();
+        blur_split_planes(&src, &mut reference, gaussian_f32).unwrap();
+        for (&a, &b) in native.data.borrow().iter().zip(reference.data.borrow()) {
+            assert!((a - b).abs() < 1e-4, "gaussian f32: {a} != {b}");
+        }
+        stack_f32(&src, &mut native).unwrap();
+        blur_split_planes(&src, &mut reference, stack_f32).unwrap();
+        for (&a, &b) in native.data.borrow().iter().zip(reference.data.borrow()) {
+            assert!((a - b).abs() < 1e-4, "stack f32: {a} != {b}");
+        }
+    }
+
+    #[test]
+    fn test_blur_channels_interleaved_and_planar() {
+        let (width, height, channels) = (27u32, 19u32, 6usize);
+        let plane_size = width as usize * height as usize;
+        let interleaved = pattern(plane_size * channels, |x| x as u8);
+        let mut planar = vec![0u8; plane_size * channels];
+        for (i, px) in interleaved.chunks_exact(channels).enumerate() {
+            for (c, &v) in px.iter().enumerate() {
+                planar[c * plane_size + i] = v;
+            }
+        }
+        let blur = |src: &BlurImage<u8>, dst: &mut BlurImageMut<u8>| {
+            box_blur(
+                src,
+                dst,
+                BoxBlurParameters::new(3),
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+                AlphaMode::Premultiplied,
+            )
+        };
+
+        let src = MultiChannelImage::borrow(
+            &interleaved,
+            width,
+            height,
+            channels,
+            ChannelsLayout::Interleaved,
+        );
+        let mut interleaved_dst =
+            MultiChannelImageMut::alloc(width, height, channels, ChannelsLayout::Interleaved)
+                .unwrap();
+        blur_channels(&src, &mut interleaved_dst, blur).unwrap();
+
+        let src =
+            MultiChannelImage::borrow(&planar, width, height, channels, ChannelsLayout::Planar);
+        let mut planar_dst =
+            MultiChannelImageMut::alloc(width, height, channels, ChannelsLayout::Planar).unwrap();
+        blur_channels(&src, &mut planar_dst, blur).unwrap();
+
+        let mut planar_in_place = planar.clone();
+        let mut image = MultiChannelImageMut::borrow(
+            &mut planar_in_place,
+            width,
+            height,
+            channels,
+            ChannelsLayout::Planar,
+        );
+        blur_channels_in_place(&mut image, |image| {
+            stack_blur(
+                image,
+                AnisotropicRadius::new(1),
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+        })
+        .unwrap();
+
+        let mut first_plane = planar[..plane_size].to_vec();
+        let mut first_plane_image =
+            BlurImageMut::borrow(&mut first_plane, width, height, FastBlurChannels::Plane);
+        stack_blur(
+            &mut first_plane_image,
+            AnisotropicRadius::new(1),
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        assert_eq!(first_plane, planar_in_place[..plane_size]);
+
+        let interleaved_dst = interleaved_dst.data.borrow();
+        let planar_dst = planar_dst.data.borrow();
+        for (i, px) in interleaved_dst.chunks_exact(channels).enumerate() {
+            for (c, &v) in px.iter().enumerate() {
+                assert_eq!(v, planar_dst[c * plane_size + i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blur_channels_rejects_mismatch() {
+        let data = vec![0u8; 4 * 4 * 5];
+        let src = MultiChannelImage::borrow(&data, 4, 4, 5, ChannelsLayout::Interleaved);
+        let mut dst_data = vec![0u8; 4 * 4 * 5];
+        let mut dst = MultiChannelImageMut::borrow(&mut dst_data, 4, 4, 5, ChannelsLayout::Planar);
+        assert!(blur_channels(&src, &mut dst, |_, _| Ok(())).is_err());
+        let short = MultiChannelImage::borrow(&data[..10], 4, 4, 5, ChannelsLayout::Interleaved);
+        assert!(short.check_layout().is_err());
+    }
+
+    #[test]
+    fn test_multichannel_rejects_overflowing_size() {
+        assert!(matches!(
+            MultiChannelImageMut::<u8>::alloc(u32::MAX, 2, 4, ChannelsLayout::Interleaved),
+            Err(BlurError::ExceedingPointerSize)
+        ));
+        assert!(matches!(
+            MultiChannelImageMut::<u8>::alloc(4, 4, usize::MAX, ChannelsLayout::Planar),
+            Err(BlurError::ExceedingPointerSize)
+        ));
+        let src = MultiChannelImage::<u8>::borrow(&[], u32::MAX, 2, 8, ChannelsLayout::Interleaved);
+        let mut dst = MultiChannelImageMut::alloc(1, 1, 1, ChannelsLayout::Interleaved).unwrap();
+        assert!(matches!(
+            dst.check_layout(Some(&src)),
+            Err(BlurError::ExceedingPointerSize)
+        ));
+    }
+}
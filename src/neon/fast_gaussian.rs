@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::neon::{load_u8_s32_fast, store_u8_s32_x4, store_u8x8_m4, vmulq_s32_f32};
 use crate::unsafe_slice::UnsafeSlice;
 use crate::util::ScratchBuffer;
@@ -47,7 +48,9 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -104,8 +107,8 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
                         int32x4x4_t(prepared_px0, prepared_px1, prepared_px2, prepared_px3),
                     );
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -137,7 +140,7 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
                         vsubq_s32(a_stored3, vaddq_s32(d_stored3, d_stored3)),
                     );
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
                     let mut stored1 = vld1q_s32(da[1..].as_ptr().cast());
@@ -168,7 +171,7 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
                 let pixel_color2 = load_u8_s32_fast::<CN>(s_ptr2);
                 let pixel_color3 = load_u8_s32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 vst1q_s32(da.as_mut_ptr().cast(), pixel_color0);
@@ -209,8 +212,8 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u8x8_m4::<CN>(dst_ptr, prepared_u8);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_s32(d_buf_ptr.as_ptr().cast());
@@ -220,7 +223,7 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vsubq_s32(a_stored, vaddq_s32(d_stored, d_stored)));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = vld1q_s32(buf_ptr.as_ptr().cast());
                     stored = vshlq_n_s32::<1>(stored);
@@ -233,7 +236,7 @@ pub(crate) fn fg_horizontal_pass_neon_u8<const CN: usize>(
                 let s_ptr = bytes.get_ptr(current_y + next_row_px);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_s32(diffs, pixel_color);
@@ -255,7 +258,9 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -314,8 +319,8 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
                         int32x4x4_t(prepared_px0, prepared_px1, prepared_px2, prepared_px3),
                     );
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -347,7 +352,7 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
                         vsubq_s32(a_stored3, vaddq_s32(d_stored3, d_stored3)),
                     );
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
                     let mut stored1 = vld1q_s32(da[1..].as_ptr().cast());
@@ -378,7 +383,7 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
                 let pixel_color2 = load_u8_s32_fast::<CN>(s_ptr2);
                 let pixel_color3 = load_u8_s32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 diffs0 = vaddq_s32(diffs0, pixel_color0);
                 diffs1 = vaddq_s32(diffs1, pixel_color1);
@@ -421,8 +426,8 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u8x8_m4::<CN>(dst_ptr, prepared_u8);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_s32(d_buf_ptr.as_ptr().cast());
@@ -432,7 +437,7 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vsubq_s32(a_stored, vaddq_s32(d_stored, d_stored)));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = vld1q_s32(buf_ptr.as_ptr().cast());
                     diffs = vsubq_s32(diffs, vaddq_s32(stored, stored));
@@ -445,7 +450,7 @@ pub(crate) fn fg_vertical_pass_neon_u8<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_s32(diffs, pixel_color);
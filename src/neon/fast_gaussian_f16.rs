@@ -27,8 +27,10 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::neon::{load_f32_f16, store_f32_f16};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use core::f16;
 use std::arch::aarch64::*;
 
@@ -43,7 +45,10 @@ pub(crate) fn fg_vertical_pass_neon_f16<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = [[0f32; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[f32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
 
         let height_wide = height as i64;
 
@@ -66,8 +71,8 @@ pub(crate) fn fg_vertical_pass_neon_f16<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(current_y + current_px);
                     store_f32_f16::<CN>(dst_ptr, prepared_px);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = vld1q_f32(d_buf_ptr);
@@ -78,7 +83,7 @@ pub(crate) fn fg_vertical_pass_neon_f16<const CN: usize>(
 
                     diffs = vaddq_f32(diffs, vsubq_f32(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = vld1q_f32(buf_ptr);
                     stored = vmulq_n_f32(stored, 2f32);
@@ -92,7 +97,7 @@ pub(crate) fn fg_vertical_pass_neon_f16<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_f32_f16::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = vaddq_f32(diffs, pixel_color);
@@ -114,7 +119,10 @@ pub(crate) fn fg_horizontal_pass_neon_f16<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer: [[f32; 4]; 1024] = [[0f32; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[f32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
         let radius_64 = radius as i64;
         let width_wide = width as i64;
         let weight = 1.0f32 / ((radius as f32) * (radius as f32));
@@ -135,8 +143,8 @@ pub(crate) fn fg_horizontal_pass_neon_f16<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(current_y + current_px);
                     store_f32_f16::<CN>(dst_ptr, prepared_px);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = vld1q_f32(d_buf_ptr);
@@ -147,7 +155,7 @@ pub(crate) fn fg_horizontal_pass_neon_f16<const CN: usize>(
 
                     diffs = vaddq_f32(diffs, vsubq_f32(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = vld1q_f32(buf_ptr);
                     stored = vmulq_n_f32(stored, 2f32);
@@ -161,7 +169,7 @@ pub(crate) fn fg_horizontal_pass_neon_f16<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_f32_f16::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = vaddq_f32(diffs, pixel_color);
@@ -29,6 +29,7 @@ use std::arch::aarch64::*;
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::neon::fast_gaussian_next_f32::NeonF32x4;
 use crate::neon::{load_f32_fast, store_f32};
 use crate::unsafe_slice::UnsafeSlice;
@@ -45,7 +46,9 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonF32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonF32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let height_wide = height as i64;
@@ -92,8 +95,8 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
                     store_f32::<CN>(dst_ptr2, prepared_px2);
                     store_f32::<CN>(dst_ptr3, prepared_px3);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -113,7 +116,7 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
                     diffs2 = vaddq_f32(diffs2, vfmaq_n_f32(a_s2, d_s2, -2f32));
                     diffs3 = vaddq_f32(diffs3, vfmaq_n_f32(a_s3, d_s3, -2f32));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let s0 = vld1q_f32(da.as_ptr().cast());
                     let s1 = vld1q_f32(da[1..].as_ptr().cast());
@@ -139,7 +142,7 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
                 let px2 = load_f32_fast::<CN>(s_ptr2);
                 let px3 = load_f32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 let da = buffer.get_unchecked_mut(arr_index);
                 vst1q_f32(da.as_mut_ptr().cast(), px0);
@@ -176,8 +179,8 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(current_y + current_px);
                     store_f32::<CN>(dst_ptr, prepared_px);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_f32(d_buf_ptr.as_ptr().cast());
@@ -187,7 +190,7 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
 
                     diffs = vaddq_f32(diffs, vfmaq_n_f32(a_stored, d_stored, -2f32));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = vld1q_f32(buf_ptr.as_ptr().cast());
                     diffs = vfmaq_f32(diffs, stored, vdupq_n_f32(-2f32));
@@ -200,7 +203,7 @@ pub(crate) fn fg_vertical_pass_neon_f32<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_f32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_f32(diffs, pixel_color);
@@ -222,7 +225,9 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonF32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonF32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let radius_64 = radius as i64;
@@ -268,8 +273,8 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
                     store_f32::<CN>(dst_ptr2, prepared_px2);
                     store_f32::<CN>(dst_ptr3, prepared_px3);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -288,7 +293,7 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
                     diffs2 = vaddq_f32(diffs2, vfmaq_n_f32(a_s2, d_s2, -2f32));
                     diffs3 = vaddq_f32(diffs3, vfmaq_n_f32(a_s3, d_s3, -2f32));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let s0 = vld1q_f32(da.as_ptr().cast());
                     let s1 = vld1q_f32(da[1..].as_ptr().cast());
@@ -314,7 +319,7 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
                 let px2 = load_f32_fast::<CN>(s_ptr2);
                 let px3 = load_f32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
                 vst1q_f32(da.as_mut_ptr().cast(), px0);
                 vst1q_f32(da[1..].as_mut_ptr().cast(), px1);
@@ -350,8 +355,8 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
 
                     store_f32::<CN>(bytes.get_ptr(current_y + current_px), prepared_px);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_f32(d_buf_ptr.as_ptr().cast());
@@ -361,7 +366,7 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
 
                     diffs = vaddq_f32(diffs, vfmaq_n_f32(a_stored, d_stored, -2f32));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = vld1q_f32(buf_ptr.as_ptr().cast());
                     diffs = vfmaq_n_f32(diffs, stored, -2f32);
@@ -374,7 +379,7 @@ pub(crate) fn fg_horizontal_pass_neon_f32<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_f32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_f32(diffs, pixel_color);
@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::neon::fast_gaussian::NeonI32x4;
 use crate::neon::{load_u8_s32_fast, store_u8_s32_x5, store_u8x8_m4};
 use crate::unsafe_slice::UnsafeSlice;
@@ -62,7 +63,9 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -118,8 +121,8 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
                         prepared_px4,
                     );
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -157,7 +160,7 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
                         vsubq_s32(a_stored4, vaddq_s32(d_stored4, d_stored4)),
                     );
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
                     let mut stored1 = vld1q_s32(da[1..].as_ptr().cast());
@@ -193,7 +196,7 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
                 let pixel_color3 = load_u8_s32_fast::<CN>(s_ptr3);
                 let pixel_color4 = load_u8_s32_fast::<CN>(s_ptr4);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 vst1q_s32(da.as_mut_ptr().cast(), pixel_color0);
@@ -236,8 +239,8 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
                     let bytes_offset = current_y + current_px;
                     store_u8x8_m4::<CN>(bytes.get_ptr(bytes_offset), prepared_u8);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_s32(d_buf_ptr.as_ptr().cast());
@@ -247,7 +250,7 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vsubq_s32(a_stored, vaddq_s32(d_stored, d_stored)));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = vld1q_s32(buf_ptr.as_ptr().cast());
                     stored = vshlq_n_s32::<1>(stored);
@@ -260,7 +263,7 @@ fn fg_horizontal_pass_neon_rdm<const CN: usize>(
                 let s_ptr = bytes.get_ptr(current_y + next_row_px);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_s32(diffs, pixel_color);
@@ -300,7 +303,9 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -358,8 +363,8 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
                         prepared_px4,
                     );
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -397,7 +402,7 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
                         vsubq_s32(a_stored4, vaddq_s32(d_stored4, d_stored4)),
                     );
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
 
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
@@ -434,7 +439,7 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
                 let pixel_color3 = load_u8_s32_fast::<CN>(s_ptr3);
                 let pixel_color4 = load_u8_s32_fast::<CN>(s_ptr4);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 diffs0 = vaddq_s32(diffs0, pixel_color0);
                 diffs1 = vaddq_s32(diffs1, pixel_color1);
@@ -479,8 +484,8 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
 
                     store_u8x8_m4::<CN>(bytes.get_ptr(bytes_offset), prepared_u8);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let d_stored = vld1q_s32(d_buf_ptr.cast());
@@ -490,7 +495,7 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vsubq_s32(a_stored, vaddq_s32(d_stored, d_stored)));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let stored = vld1q_s32(buf_ptr.cast());
                     diffs = vsubq_s32(diffs, vaddq_s32(stored, stored));
@@ -503,7 +508,7 @@ fn fg_vertical_pass_neon_rdm<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = vaddq_s32(diffs, pixel_color);
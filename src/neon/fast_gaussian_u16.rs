@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::neon::fast_gaussian::NeonI32x4;
 use crate::neon::{load_u16_s32_fast, store_u16_s32_x5, store_u16x4, vmulq_s32_f32};
 use crate::unsafe_slice::UnsafeSlice;
@@ -44,7 +45,9 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -109,8 +112,8 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
                         prepared_px4,
                     );
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -148,7 +151,7 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
                         vsubq_s32(a_stored4, vaddq_s32(d_stored4, d_stored4)),
                     );
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
                     let mut stored1 = vld1q_s32(da[1..].as_ptr().cast());
@@ -184,7 +187,7 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
                 let pixel_color3 = load_u16_s32_fast::<CN>(s_ptr3);
                 let pixel_color4 = load_u16_s32_fast::<CN>(s_ptr4);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 vst1q_s32(da.as_mut_ptr().cast(), pixel_color0);
@@ -226,8 +229,8 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
                     let bytes_offset = current_y + current_px;
                     store_u16x4::<CN>(bytes.get_ptr(bytes_offset), prepared_u16);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_s32(d_buf_ptr.as_ptr().cast());
@@ -237,7 +240,7 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vmlaq_n_s32(a_stored, d_stored, -2));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = vld1q_s32(buf_ptr.as_ptr().cast());
                     stored = vshlq_n_s32::<1>(stored);
@@ -250,7 +253,7 @@ pub(crate) fn fg_horizontal_pass_neon_u16<const CN: usize>(
                 let s_ptr = bytes.get_ptr(current_y + next_row_px);
                 let pixel_color = load_u16_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_s32(diffs, pixel_color);
@@ -272,7 +275,9 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[NeonI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -339,8 +344,8 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
                         prepared_px4,
                     );
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -377,7 +382,7 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
                         vsubq_s32(a_stored4, vaddq_s32(d_stored4, d_stored4)),
                     );
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = vld1q_s32(da.as_ptr().cast());
                     let mut stored1 = vld1q_s32(da[1..].as_ptr().cast());
@@ -413,7 +418,7 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
                 let pixel_color3 = load_u16_s32_fast::<CN>(s_ptr3);
                 let pixel_color4 = load_u16_s32_fast::<CN>(s_ptr4);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 diffs0 = vaddq_s32(diffs0, pixel_color0);
                 diffs1 = vaddq_s32(diffs1, pixel_color1);
@@ -458,8 +463,8 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u16x4::<CN>(dst_ptr, prepared_u16);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = vld1q_s32(d_buf_ptr.as_ptr().cast());
@@ -469,7 +474,7 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
 
                     diffs = vaddq_s32(diffs, vmlaq_n_s32(a_stored, d_stored, -2));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = vld1q_s32(buf_ptr.as_ptr().cast());
                     diffs = vsubq_s32(diffs, vaddq_s32(stored, stored));
@@ -482,7 +487,7 @@ pub(crate) fn fg_vertical_pass_neon_u16<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u16_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = vaddq_s32(diffs, pixel_color);
@@ -28,8 +28,8 @@
  */
 #![allow(clippy::needless_range_loop)]
 
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::{BlurImage, BlurImageMut, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use std::arch::aarch64::*;
 
 pub(crate) trait SimdU8: Copy {
@@ -181,7 +181,7 @@ pub(crate) fn median_blur_3x3(
     threading_policy: ThreadingPolicy,
 ) {
     let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let dst_stride = dst.row_stride() as usize;
     let height = src.height as usize;
     let src_stride = src.row_stride() as usize;
@@ -229,6 +229,11 @@ fn median_blur_3x3_impl_16(
             let px = load_scalar_3x3::<1>(r0, r1, r2, 0, width);
             dst[0] = px[0];
         }
+        2 => {
+            let px = load_scalar_3x3::<2>(r0, r1, r2, 0, width);
+            dst[0] = px[0];
+            dst[1] = px[1];
+        }
         3 => {
             let px = load_scalar_3x3::<3>(r0, r1, r2, 0, width);
             dst[0] = px[0];
@@ -291,6 +296,11 @@ fn median_blur_3x3_impl_16(
                 let px = load_scalar_3x3::<1>(r0, r1, r2, x, width);
                 dst[x] = px[0];
             }
+            2 => {
+                let px = load_scalar_3x3::<2>(r0, r1, r2, x, width);
+                dst[x] = px[0];
+                dst[x + 1] = px[1];
+            }
             3 => {
                 let px = load_scalar_3x3::<3>(r0, r1, r2, x, width);
                 dst[x] = px[0];
@@ -315,6 +325,11 @@ fn median_blur_3x3_impl_16(
             let px = load_scalar_3x3::<1>(r0, r1, r2, x, width);
             dst[x] = px[0];
         }
+        2 => {
+            let px = load_scalar_3x3::<2>(r0, r1, r2, x, width);
+            dst[x] = px[0];
+            dst[x + 1] = px[1];
+        }
         3 => {
             let px = load_scalar_3x3::<3>(r0, r1, r2, x, width);
             dst[x] = px[0];
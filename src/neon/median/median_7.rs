@@ -27,9 +27,9 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 #![allow(clippy::needless_range_loop)]
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
 use crate::neon::median::median_3::{SimdU8, load, load8};
 use crate::{BlurImage, BlurImageMut, ThreadingPolicy};
-use novtb::{ParallelZonedIterator, TbSliceMut};
 use std::arch::aarch64::*;
 
 fn load_scalar_7x7<const CN: usize>(rows: [&[u8]; 7], x: usize, width: usize) -> [u8; CN] {
@@ -181,7 +181,7 @@ pub(crate) fn median_blur_7x7(
     threading_policy: ThreadingPolicy,
 ) {
     let thread_count = threading_policy.thread_count(src.width, src.height) as u32;
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let dst_stride = dst.row_stride() as usize;
     let height = src.height as usize;
     let src_stride = src.row_stride() as usize;
@@ -211,6 +211,11 @@ fn write_scalar_7x7(dst: &mut [u8], rows: [&[u8]; 7], x: usize, width: usize, ch
             let px = load_scalar_7x7::<1>(rows, x, width);
             dst[x] = px[0];
         }
+        2 => {
+            let px = load_scalar_7x7::<2>(rows, x, width);
+            dst[x] = px[0];
+            dst[x + 1] = px[1];
+        }
         3 => {
             let px = load_scalar_7x7::<3>(rows, x, width);
             dst[x] = px[0];
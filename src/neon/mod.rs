@@ -45,6 +45,7 @@ mod fast_gaussian_next_u16_q0_31;
 mod fast_gaussian_q0_31;
 mod fast_gaussian_u16;
 mod median;
+mod premultiply;
 mod utils;
 
 pub(crate) use fast_gaussian::{fg_horizontal_pass_neon_u8, fg_vertical_pass_neon_u8};
@@ -73,4 +74,8 @@ pub(crate) use fast_gaussian_q0_31::{
 };
 pub(crate) use fast_gaussian_u16::{fg_horizontal_pass_neon_u16, fg_vertical_pass_neon_u16};
 pub(crate) use median::{median_blur_3x3, median_blur_5x5, median_blur_7x7};
+pub(crate) use premultiply::{
+    premultiply_row_neon_f32, premultiply_row_neon_u8, premultiply_row_neon_u16,
+    unpremultiply_row_neon_f32, unpremultiply_row_neon_u8, unpremultiply_row_neon_u16,
+};
 pub(crate) use utils::*;
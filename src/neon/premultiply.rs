@@ -0,0 +1,196 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::premultiply::{
+    PREMULTIPLY_U16_INV_MAX, premultiply_row_f32, premultiply_row_u8, premultiply_row_u16,
+    unpremultiply_row_f32, unpremultiply_row_u8, unpremultiply_row_u16,
+};
+use std::arch::aarch64::*;
+
+#[inline(always)]
+unsafe fn mul_div255_u8(v: uint8x16_t, a: uint8x16_t) -> uint8x16_t {
+    unsafe {
+        let lo = vmull_u8(vget_low_u8(v), vget_low_u8(a));
+        let hi = vmull_high_u8(v, a);
+        vcombine_u8(
+            vraddhn_u16(lo, vrshrq_n_u16::<8>(lo)),
+            vraddhn_u16(hi, vrshrq_n_u16::<8>(hi)),
+        )
+    }
+}
+
+/// Computes `max / alpha` with zero for fully transparent pixels.
+#[inline(always)]
+unsafe fn unpremultiply_scale(a: float32x4_t, max: float32x4_t) -> float32x4_t {
+    unsafe { vbslq_f32(vceqzq_f32(a), vdupq_n_f32(0.), vdivq_f32(max, a)) }
+}
+
+#[inline(always)]
+unsafe fn scale_u32(v: uint32x4_t, scale: float32x4_t) -> uint32x4_t {
+    unsafe {
+        let r = vaddq_f32(vmulq_f32(vcvtq_f32_u32(v), scale), vdupq_n_f32(0.5));
+        vcvtq_u32_f32(r)
+    }
+}
+
+#[inline(always)]
+unsafe fn scale_u16(v: uint16x8_t, scale_lo: float32x4_t, scale_hi: float32x4_t) -> uint16x8_t {
+    unsafe {
+        let lo = scale_u32(vmovl_u16(vget_low_u16(v)), scale_lo);
+        let hi = scale_u32(vmovl_high_u16(v), scale_hi);
+        vcombine_u16(vqmovn_u32(lo), vqmovn_u32(hi))
+    }
+}
+
+#[inline(always)]
+unsafe fn scale_u8(v: uint8x16_t, scales: [float32x4_t; 4]) -> uint8x16_t {
+    unsafe {
+        let lo = scale_u16(vmovl_u8(vget_low_u8(v)), scales[0], scales[1]);
+        let hi = scale_u16(vmovl_high_u8(v), scales[2], scales[3]);
+        vcombine_u8(vqmovn_u16(lo), vqmovn_u16(hi))
+    }
+}
+
+pub(crate) fn premultiply_row_neon_u8(row: &mut [u8]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(64);
+        for chunk in &mut chunks {
+            let v = vld4q_u8(chunk.as_ptr());
+            let r = uint8x16x4_t(
+                mul_div255_u8(v.0, v.3),
+                mul_div255_u8(v.1, v.3),
+                mul_div255_u8(v.2, v.3),
+                v.3,
+            );
+            vst4q_u8(chunk.as_mut_ptr(), r);
+        }
+        premultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_neon_u8(row: &mut [u8]) {
+    unsafe {
+        let max = vdupq_n_f32(255.);
+        let mut chunks = row.chunks_exact_mut(64);
+        for chunk in &mut chunks {
+            let v = vld4q_u8(chunk.as_ptr());
+            let a_lo = vmovl_u8(vget_low_u8(v.3));
+            let a_hi = vmovl_high_u8(v.3);
+            let scales = [
+                unpremultiply_scale(vcvtq_f32_u32(vmovl_u16(vget_low_u16(a_lo))), max),
+                unpremultiply_scale(vcvtq_f32_u32(vmovl_high_u16(a_lo)), max),
+                unpremultiply_scale(vcvtq_f32_u32(vmovl_u16(vget_low_u16(a_hi))), max),
+                unpremultiply_scale(vcvtq_f32_u32(vmovl_high_u16(a_hi)), max),
+            ];
+            let r = uint8x16x4_t(
+                scale_u8(v.0, scales),
+                scale_u8(v.1, scales),
+                scale_u8(v.2, scales),
+                v.3,
+            );
+            vst4q_u8(chunk.as_mut_ptr(), r);
+        }
+        unpremultiply_row_u8(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn premultiply_row_neon_u16(row: &mut [u16]) {
+    unsafe {
+        let inv_max = vdupq_n_f32(PREMULTIPLY_U16_INV_MAX);
+        let mut chunks = row.chunks_exact_mut(32);
+        for chunk in &mut chunks {
+            let v = vld4q_u16(chunk.as_ptr());
+            let scale_lo = vmulq_f32(vcvtq_f32_u32(vmovl_u16(vget_low_u16(v.3))), inv_max);
+            let scale_hi = vmulq_f32(vcvtq_f32_u32(vmovl_high_u16(v.3)), inv_max);
+            let r = uint16x8x4_t(
+                scale_u16(v.0, scale_lo, scale_hi),
+                scale_u16(v.1, scale_lo, scale_hi),
+                scale_u16(v.2, scale_lo, scale_hi),
+                v.3,
+            );
+            vst4q_u16(chunk.as_mut_ptr(), r);
+        }
+        premultiply_row_u16(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_neon_u16(row: &mut [u16]) {
+    unsafe {
+        let max = vdupq_n_f32(65535.);
+        let mut chunks = row.chunks_exact_mut(32);
+        for chunk in &mut chunks {
+            let v = vld4q_u16(chunk.as_ptr());
+            let scale_lo = unpremultiply_scale(vcvtq_f32_u32(vmovl_u16(vget_low_u16(v.3))), max);
+            let scale_hi = unpremultiply_scale(vcvtq_f32_u32(vmovl_high_u16(v.3)), max);
+            let r = uint16x8x4_t(
+                scale_u16(v.0, scale_lo, scale_hi),
+                scale_u16(v.1, scale_lo, scale_hi),
+                scale_u16(v.2, scale_lo, scale_hi),
+                v.3,
+            );
+            vst4q_u16(chunk.as_mut_ptr(), r);
+        }
+        unpremultiply_row_u16(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn premultiply_row_neon_f32(row: &mut [f32]) {
+    unsafe {
+        let mut chunks = row.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let v = vld4q_f32(chunk.as_ptr());
+            let r = float32x4x4_t(
+                vmulq_f32(v.0, v.3),
+                vmulq_f32(v.1, v.3),
+                vmulq_f32(v.2, v.3),
+                v.3,
+            );
+            vst4q_f32(chunk.as_mut_ptr(), r);
+        }
+        premultiply_row_f32(chunks.into_remainder());
+    }
+}
+
+pub(crate) fn unpremultiply_row_neon_f32(row: &mut [f32]) {
+    unsafe {
+        let zeros = vdupq_n_f32(0.);
+        let mut chunks = row.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let v = vld4q_f32(chunk.as_ptr());
+            let is_zero = vceqzq_f32(v.3);
+            let r = float32x4x4_t(
+                vbslq_f32(is_zero, zeros, vdivq_f32(v.0, v.3)),
+                vbslq_f32(is_zero, zeros, vdivq_f32(v.1, v.3)),
+                vbslq_f32(is_zero, zeros, vdivq_f32(v.2, v.3)),
+                v.3,
+            );
+            vst4q_f32(chunk.as_mut_ptr(), r);
+        }
+        unpremultiply_row_f32(chunks.into_remainder());
+    }
+}
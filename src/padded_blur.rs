@@ -0,0 +1,285 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::edge_index;
+use crate::filter1d::copy_roi;
+use crate::primitives::PrimitiveCast;
+use crate::safe_math::{SafeAdd, SafeMul};
+use crate::{
+    AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BlurWorkspace, EdgeMode, EdgeMode2D,
+    Scalar,
+};
+use std::fmt::Debug;
+
+/// Blurs which handle only clamp border natively are executed on an image
+/// padded according to the requested edge modes.
+/// Each axis with non clamp border is padded by full blur support, so clamping inside
+/// the padded image never reaches pixels which are written back.
+fn padding(
+    radius: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
+    native: impl Fn(EdgeMode) -> bool,
+) -> (usize, usize, EdgeMode2D) {
+    let pad_x = if native(edge_modes.horizontal) {
+        0
+    } else {
+        radius.x_axis as usize
+    };
+    let pad_y = if native(edge_modes.vertical) {
+        0
+    } else {
+        radius.y_axis as usize
+    };
+    let inner_mode = |mode: EdgeMode| {
+        if native(mode) { mode } else { EdgeMode::Clamp }
+    };
+    (
+        pad_x,
+        pad_y,
+        EdgeMode2D::anisotropy(
+            inner_mode(edge_modes.horizontal),
+            inner_mode(edge_modes.vertical),
+        ),
+    )
+}
+
+/// Checks if blur supporting only [EdgeMode::Clamp] needs padding
+pub(crate) fn needs_padding(edge_modes: EdgeMode2D) -> bool {
+    edge_modes.horizontal != EdgeMode::Clamp || edge_modes.vertical != EdgeMode::Clamp
+}
+
+/// Checks if blur supporting every [EdgeMode] except [EdgeMode::Constant] needs padding
+pub(crate) fn needs_constant_padding(edge_modes: EdgeMode2D) -> bool {
+    edge_modes.horizontal == EdgeMode::Constant || edge_modes.vertical == EdgeMode::Constant
+}
+
+/// Padded copy of an image, padding is the same on both sides of each axis
+struct Padded<T> {
+    data: Vec<T>,
+    layout: PaddedLayout,
+}
+
+/// Size of the padded image
+#[derive(Clone, Copy)]
+struct PaddedLayout {
+    width: usize,
+    height: usize,
+    pad_x: usize,
+    pad_y: usize,
+}
+
+impl PaddedLayout {
+    fn new<T>(image: &BlurImage<T>, pad_x: usize, pad_y: usize) -> Result<PaddedLayout, BlurError>
+    where
+        T: Copy + Default + Debug,
+    {
+        let width = (image.width as usize).safe_add(pad_x.safe_mul(2)?)?;
+        let height = (image.height as usize).safe_add(pad_y.safe_mul(2)?)?;
+        if width > u32::MAX as usize || height > u32::MAX as usize {
+            return Err(BlurError::ExceedingPointerSize);
+        }
+        width
+            .safe_mul(height)?
+            .safe_mul(image.channels.channels())?;
+        Ok(PaddedLayout {
+            width,
+            height,
+            pad_x,
+            pad_y,
+        })
+    }
+
+    fn len(&self, cn: usize) -> usize {
+        self.width * self.height * cn
+    }
+}
+
+fn write_padded<T>(
+    image: &BlurImage<T>,
+    layout: PaddedLayout,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    data: &mut [T],
+) where
+    T: Copy + Default + Debug + 'static,
+    f64: PrimitiveCast<T>,
+{
+    let cn = image.channels.channels();
+    let src = image.data.as_ref();
+    let src_stride = image.row_stride() as usize;
+    let constant: [T; 4] = std::array::from_fn(|c| border_constant[c].cast_());
+    for (row, y) in data
+        .chunks_exact_mut(layout.width * cn)
+        .zip(-(layout.pad_y as i64)..)
+    {
+        let y = edge_index(edge_modes.vertical, y, image.height as i64);
+        for (dst, x) in row.chunks_exact_mut(cn).zip(-(layout.pad_x as i64)..) {
+            match (y, edge_index(edge_modes.horizontal, x, image.width as i64)) {
+                (Some(y), Some(x)) => {
+                    let offset = y * src_stride + x * cn;
+                    dst.copy_from_slice(&src[offset..offset + cn]);
+                }
+                _ => dst.copy_from_slice(&constant[..cn]),
+            }
+        }
+    }
+}
+
+fn make_padded<T>(
+    image: &BlurImage<T>,
+    pad_x: usize,
+    pad_y: usize,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+) -> Result<Padded<T>, BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    f64: PrimitiveCast<T>,
+{
+    image.check_layout()?;
+    let layout = PaddedLayout::new(image, pad_x, pad_y)?;
+    let padded_length = layout.len(image.channels.channels());
+    let mut data = Vec::new();
+    data.try_reserve_exact(padded_length)
+        .map_err(|_| BlurError::ExceedingPointerSize)?;
+    data.resize(padded_length, T::default());
+    write_padded(image, layout, edge_modes, border_constant, &mut data);
+    Ok(Padded { data, layout })
+}
+
+fn copy_central<T: Copy + Default + Debug>(
+    padded: &[T],
+    layout: PaddedLayout,
+    dst: &mut BlurImageMut<T>,
+) {
+    let cn = dst.channels.channels();
+    let padded_stride = layout.width * cn;
+    let dst_stride = dst.row_stride() as usize;
+    let row_size = dst.width as usize * cn;
+    let start = layout.pad_y * padded_stride + layout.pad_x * cn;
+    let end = start + (dst.height as usize - 1) * padded_stride + row_size;
+    copy_roi(
+        dst.data.borrow_mut(),
+        &padded[start..end],
+        dst_stride,
+        padded_stride,
+        row_size,
+    );
+}
+
+/// Executes blur from source into destination on the padded image.
+///
+/// `blur` receives edge modes that should be used on the padded image,
+/// axes which were not natively supported are replaced with [EdgeMode::Clamp].
+pub(crate) fn blur_padded<T, F>(
+    image: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    support: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    native: impl Fn(EdgeMode) -> bool,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    f64: PrimitiveCast<T>,
+    F: FnOnce(&BlurImage<T>, &mut BlurImageMut<T>, EdgeMode2D) -> Result<(), BlurError>,
+{
+    image.check_layout()?;
+    dst.check_layout(Some(image))?;
+    image.size_matches_mut(dst)?;
+    let (pad_x, pad_y, inner_modes) = padding(support, edge_modes, native);
+    let padded = make_padded(image, pad_x, pad_y, edge_modes, border_constant)?;
+    let padded_image = BlurImage::borrow(
+        &padded.data,
+        padded.layout.width as u32,
+        padded.layout.height as u32,
+        image.channels,
+    );
+    let mut padded_dst = BlurImageMut::default();
+    blur(&padded_image, &mut padded_dst, inner_modes)?;
+    copy_central(padded_dst.data.borrow(), padded.layout, dst);
+    Ok(())
+}
+
+/// Executes in place blur on the padded image, see [blur_padded] for more info.
+pub(crate) fn blur_in_place_padded<T, F>(
+    image: &mut BlurImageMut<T>,
+    support: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    native: impl Fn(EdgeMode) -> bool,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    f64: PrimitiveCast<T>,
+    F: FnOnce(&mut BlurImageMut<T>, EdgeMode2D) -> Result<(), BlurError>,
+{
+    blur_in_place_padded_with_workspace(
+        image,
+        support,
+        edge_modes,
+        border_constant,
+        native,
+        &mut BlurWorkspace::new(),
+        blur,
+    )
+}
+
+/// Same as [blur_in_place_padded], padded image is taken from the workspace.
+pub(crate) fn blur_in_place_padded_with_workspace<T, F>(
+    image: &mut BlurImageMut<T>,
+    support: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    native: impl Fn(EdgeMode) -> bool,
+    workspace: &mut BlurWorkspace<T>,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + 'static,
+    f64: PrimitiveCast<T>,
+    F: FnOnce(&mut BlurImageMut<T>, EdgeMode2D) -> Result<(), BlurError>,
+{
+    image.check_layout(None)?;
+    let (pad_x, pad_y, inner_modes) = padding(support, edge_modes, native);
+    let source = image.to_immutable_ref();
+    let layout = PaddedLayout::new(&source, pad_x, pad_y)?;
+    let padded = workspace.take(layout.len(image.channels.channels()))?;
+    write_padded(&source, layout, edge_modes, border_constant, padded);
+    let mut padded_image = BlurImageMut::borrow(
+        padded,
+        layout.width as u32,
+        layout.height as u32,
+        image.channels,
+    );
+    blur(&mut padded_image, inner_modes)?;
+    copy_central(padded, layout, image);
+    Ok(())
+}
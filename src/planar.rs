@@ -0,0 +1,943 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::ThreadPool;
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BoxBlurParameters,
+    BufferStore, ConvolutionMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams, Scalar,
+    ThreadingPolicy, box_blur, fast_gaussian, gaussian_blur, stack_blur,
+};
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// Subsampling of an image plane relative to the full image size.
+///
+/// Plane size is the image size divided by the factor and rounded up,
+/// e.g. chroma of odd-sized YUV 4:2:0 image is `(width + 1) / 2`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Subsampling {
+    pub horizontal: u32,
+    pub vertical: u32,
+}
+
+impl Default for Subsampling {
+    fn default() -> Self {
+        Subsampling::NONE
+    }
+}
+
+impl Subsampling {
+    /// Full resolution plane, luma or 4:4:4 chroma
+    pub const NONE: Subsampling = Subsampling::new(1, 1);
+    /// 4:2:2 chroma
+    pub const HALF_HORIZONTAL: Subsampling = Subsampling::new(2, 1);
+    /// 4:2:0 chroma
+    pub const HALF: Subsampling = Subsampling::new(2, 2);
+
+    pub const fn new(horizontal: u32, vertical: u32) -> Subsampling {
+        Subsampling {
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// Returns size of the plane for the image of the given size
+    pub fn plane_size(&self, width: u32, height: u32) -> (u32, u32) {
+        (
+            width.div_ceil(self.horizontal.max(1)),
+            height.div_ceil(self.vertical.max(1)),
+        )
+    }
+
+    /// Scales radius to the plane resolution, radius is never less than 1
+    pub fn scale_radius(&self, radius: AnisotropicRadius) -> AnisotropicRadius {
+        AnisotropicRadius::create(
+            (radius.x_axis / self.horizontal.max(1)).max(1),
+            (radius.y_axis / self.vertical.max(1)).max(1),
+        )
+    }
+
+    /// Scales gaussian kernel and sigma to the plane resolution, kernel is kept odd
+    pub fn scale_gaussian(&self, params: GaussianBlurParams) -> GaussianBlurParams {
+        let horizontal = self.horizontal.max(1);
+        let vertical = self.vertical.max(1);
+        GaussianBlurParams::new_asymmetric(
+            scale_kernel(params.x_kernel, horizontal),
+            params.x_sigma / horizontal as f64,
+            scale_kernel(params.y_kernel, vertical),
+            params.y_sigma / vertical as f64,
+        )
+    }
+
+    /// Scales box kernel to the plane resolution, kernel is kept odd
+    pub fn scale_box(&self, params: BoxBlurParameters) -> BoxBlurParameters {
+        BoxBlurParameters {
+            x_axis_kernel: scale_kernel(params.x_axis_kernel, self.horizontal.max(1)),
+            y_axis_kernel: scale_kernel(params.y_axis_kernel, self.vertical.max(1)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.horizontal == 0 || self.vertical == 0 {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+/// Zero kernel means kernel derived from sigma and is kept as is
+fn scale_kernel(kernel: u32, factor: u32) -> u32 {
+    if kernel == 0 {
+        return 0;
+    }
+    (kernel / factor) | 1
+}
+
+/// How blur radius is applied to subsampled planes
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum PlaneRadiusMode {
+    /// Each plane is blurred with the same radius in its own pixels
+    #[default]
+    Same,
+    /// Radius is divided by the plane subsampling, so chroma is blurred
+    /// over the same area of the picture as luma
+    Subsampled,
+}
+
+/// Single plane of [PlanarImage]
+pub struct BlurPlane<'a, T: Clone + Copy + Default + Debug> {
+    pub data: std::borrow::Cow<'a, [T]>,
+    /// Plane stride, items per row, might be 0
+    pub stride: u32,
+    pub channels: FastBlurChannels,
+    pub subsampling: Subsampling,
+}
+
+/// Single plane of [PlanarImageMut]
+pub struct BlurPlaneMut<'a, T: Clone + Copy + Default + Debug> {
+    pub data: BufferStore<'a, T>,
+    /// Plane stride, items per row, might be 0
+    pub stride: u32,
+    pub channels: FastBlurChannels,
+    pub subsampling: Subsampling,
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> BlurPlane<'a, T> {
+    /// Borrows existing data, stride will be default `plane width * channels.channels()`
+    pub fn borrow(arr: &'a [T], channels: FastBlurChannels, subsampling: Subsampling) -> Self {
+        Self {
+            data: std::borrow::Cow::Borrowed(arr),
+            stride: 0,
+            channels,
+            subsampling,
+        }
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> BlurPlaneMut<'a, T> {
+    /// Mutable borrows existing data, stride will be default `plane width * channels.channels()`
+    pub fn borrow(arr: &'a mut [T], channels: FastBlurChannels, subsampling: Subsampling) -> Self {
+        Self {
+            data: BufferStore::Borrowed(arr),
+            stride: 0,
+            channels,
+            subsampling,
+        }
+    }
+}
+
+/// Immutable planar image store, e.g. YUV 4:2:0, NV12 or planar RGB.
+///
+/// Each plane has its own stride, channels and subsampling relative to `width` and `height`.
+pub struct PlanarImage<'a, T: Clone + Copy + Default + Debug> {
+    pub width: u32,
+    pub height: u32,
+    pub planes: Vec<BlurPlane<'a, T>>,
+}
+
+/// Mutable planar image store.
+/// If it has no planes it allocates them on methods that working out-of-place.
+pub struct PlanarImageMut<'a, T: Clone + Copy + Default + Debug> {
+    pub width: u32,
+    pub height: u32,
+    pub planes: Vec<BlurPlaneMut<'a, T>>,
+}
+
+impl<T: Clone + Copy + Default + Debug> Default for PlanarImageMut<'_, T> {
+    fn default() -> Self {
+        PlanarImageMut {
+            width: 0,
+            height: 0,
+            planes: Vec::new(),
+        }
+    }
+}
+
+fn plane_view<'a, T: Clone + Copy + Default + Debug>(
+    plane: &'a BlurPlane<'_, T>,
+    width: u32,
+    height: u32,
+) -> BlurImage<'a, T> {
+    let (plane_width, plane_height) = plane.subsampling.plane_size(width, height);
+    BlurImage {
+        data: std::borrow::Cow::Borrowed(plane.data.as_ref()),
+        width: plane_width,
+        height: plane_height,
+        stride: plane.stride,
+        channels: plane.channels,
+    }
+}
+
+fn plane_view_mut<'a, T: Clone + Copy + Default + Debug>(
+    plane: &'a mut BlurPlaneMut<'_, T>,
+    width: u32,
+    height: u32,
+) -> BlurImageMut<'a, T> {
+    let (plane_width, plane_height) = plane.subsampling.plane_size(width, height);
+    BlurImageMut {
+        data: BufferStore::Borrowed(plane.data.borrow_mut()),
+        width: plane_width,
+        height: plane_height,
+        stride: plane.stride,
+        channels: plane.channels,
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> PlanarImage<'a, T> {
+    pub fn new(width: u32, height: u32, planes: Vec<BlurPlane<'a, T>>) -> Self {
+        Self {
+            width,
+            height,
+            planes,
+        }
+    }
+
+    /// Borrows three planes image, Y plane is full resolution, U and V planes are subsampled.
+    ///
+    /// Use [Subsampling::HALF] for 4:2:0, [Subsampling::HALF_HORIZONTAL] for 4:2:2
+    /// and [Subsampling::NONE] for 4:4:4 or planar RGB.
+    pub fn yuv(
+        y: &'a [T],
+        u: &'a [T],
+        v: &'a [T],
+        width: u32,
+        height: u32,
+        subsampling: Subsampling,
+    ) -> Self {
+        Self::new(
+            width,
+            height,
+            vec![
+                BlurPlane::borrow(y, FastBlurChannels::Plane, Subsampling::NONE),
+                BlurPlane::borrow(u, FastBlurChannels::Plane, subsampling),
+                BlurPlane::borrow(v, FastBlurChannels::Plane, subsampling),
+            ],
+        )
+    }
+
+    /// Borrows NV12 or NV21 image, Y plane and interleaved 4:2:0 chroma plane
+    pub fn nv12(y: &'a [T], uv: &'a [T], width: u32, height: u32) -> Self {
+        Self::new(
+            width,
+            height,
+            vec![
+                BlurPlane::borrow(y, FastBlurChannels::Plane, Subsampling::NONE),
+                BlurPlane::borrow(uv, FastBlurChannels::Channels2, Subsampling::HALF),
+            ],
+        )
+    }
+
+    /// Returns plane at `index` as an image
+    pub fn plane(&self, index: usize) -> Option<BlurImage<'_, T>> {
+        self.planes
+            .get(index)
+            .map(|plane| plane_view(plane, self.width, self.height))
+    }
+
+    /// Checks if layout of every plane matches necessary requirements
+    pub fn check_layout(&self) -> Result<(), BlurError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        if self.planes.is_empty() {
+            return Err(BlurError::InvalidArguments);
+        }
+        for plane in self.planes.iter() {
+            plane.subsampling.validate()?;
+            plane_view(plane, self.width, self.height).check_layout()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Clone + Copy + Default + Debug> PlanarImageMut<'a, T> {
+    pub fn new(width: u32, height: u32, planes: Vec<BlurPlaneMut<'a, T>>) -> Self {
+        Self {
+            width,
+            height,
+            planes,
+        }
+    }
+
+    /// Mutable borrows three planes image, see [PlanarImage::yuv] for more info
+    pub fn yuv(
+        y: &'a mut [T],
+        u: &'a mut [T],
+        v: &'a mut [T],
+        width: u32,
+        height: u32,
+        subsampling: Subsampling,
+    ) -> Self {
+        Self::new(
+            width,
+            height,
+            vec![
+                BlurPlaneMut::borrow(y, FastBlurChannels::Plane, Subsampling::NONE),
+                BlurPlaneMut::borrow(u, FastBlurChannels::Plane, subsampling),
+                BlurPlaneMut::borrow(v, FastBlurChannels::Plane, subsampling),
+            ],
+        )
+    }
+
+    /// Mutable borrows NV12 or NV21 image, see [PlanarImage::nv12] for more info
+    pub fn nv12(y: &'a mut [T], uv: &'a mut [T], width: u32, height: u32) -> Self {
+        Self::new(
+            width,
+            height,
+            vec![
+                BlurPlaneMut::borrow(y, FastBlurChannels::Plane, Subsampling::NONE),
+                BlurPlaneMut::borrow(uv, FastBlurChannels::Channels2, Subsampling::HALF),
+            ],
+        )
+    }
+
+    /// Returns plane at `index` as a mutable image
+    pub fn plane_mut(&mut self, index: usize) -> Option<BlurImageMut<'_, T>> {
+        let (width, height) = (self.width, self.height);
+        self.planes
+            .get_mut(index)
+            .map(|plane| plane_view_mut(plane, width, height))
+    }
+
+    /// Checks if layout of every plane matches necessary requirements.
+    ///
+    /// If `other` is provided, image without planes receives owned planes with the same layout,
+    /// and owned planes are resized to match the `other` one.
+    pub fn check_layout(&mut self, other: Option<&PlanarImage<'_, T>>) -> Result<(), BlurError> {
+        if let Some(other) = other {
+            if self.planes.is_empty() {
+                self.planes = other
+                    .planes
+                    .iter()
+                    .map(|plane| BlurPlaneMut {
+                        data: BufferStore::Owned(Vec::new()),
+                        stride: 0,
+                        channels: plane.channels,
+                        subsampling: plane.subsampling,
+                    })
+                    .collect();
+                self.width = other.width;
+                self.height = other.height;
+            }
+            if self.planes.len() != other.planes.len()
+                || self.width != other.width
+                || self.height != other.height
+            {
+                return Err(BlurError::ImagesMustMatch);
+            }
+            for (plane, other_plane) in self.planes.iter_mut().zip(other.planes.iter()) {
+                if let BufferStore::Owned(data) = &mut plane.data {
+                    plane.channels = other_plane.channels;
+                    plane.subsampling = other_plane.subsampling;
+                    plane.stride = 0;
+                    let (plane_width, plane_height) = other_plane
+                        .subsampling
+                        .plane_size(other.width, other.height);
+                    data.resize(
+                        plane_width as usize
+                            * plane_height as usize
+                            * other_plane.channels.channels(),
+                        T::default(),
+                    );
+                } else if plane.channels != other_plane.channels
+                    || plane.subsampling != other_plane.subsampling
+                {
+                    return Err(BlurError::ImagesMustMatch);
+                }
+            }
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        if self.planes.is_empty() {
+            return Err(BlurError::InvalidArguments);
+        }
+        let (width, height) = (self.width, self.height);
+        for plane in self.planes.iter_mut() {
+            plane.subsampling.validate()?;
+            plane_view_mut(plane, width, height).check_layout(None)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn to_immutable_ref(&self) -> PlanarImage<'_, T> {
+        PlanarImage {
+            width: self.width,
+            height: self.height,
+            planes: self
+                .planes
+                .iter()
+                .map(|plane| BlurPlane {
+                    data: std::borrow::Cow::Borrowed(plane.data.borrow()),
+                    stride: plane.stride,
+                    channels: plane.channels,
+                    subsampling: plane.subsampling,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Plane being blurred by [blur_planes] or [blur_planes_in_place]
+#[derive(Debug, Copy, Clone)]
+pub struct PlaneContext {
+    /// Index of the plane in the image
+    pub index: usize,
+    pub subsampling: Subsampling,
+    /// Share of the image threading budget given to this plane
+    pub threading_policy: ThreadingPolicy,
+}
+
+impl PlaneContext {
+    fn radius(&self, radius: AnisotropicRadius, mode: PlaneRadiusMode) -> AnisotropicRadius {
+        match mode {
+            PlaneRadiusMode::Same => radius,
+            PlaneRadiusMode::Subsampled => self.subsampling.scale_radius(radius),
+        }
+    }
+
+    fn gaussian(&self, params: GaussianBlurParams, mode: PlaneRadiusMode) -> GaussianBlurParams {
+        match mode {
+            PlaneRadiusMode::Same => params,
+            PlaneRadiusMode::Subsampled => self.subsampling.scale_gaussian(params),
+        }
+    }
+
+    fn box_kernel(&self, params: BoxBlurParameters, mode: PlaneRadiusMode) -> BoxBlurParameters {
+        match mode {
+            PlaneRadiusMode::Same => params,
+            PlaneRadiusMode::Subsampled => self.subsampling.scale_box(params),
+        }
+    }
+}
+
+/// Splits threads budget of the whole image between planes proportionally to their area.
+///
+/// Returns `false` if planes must be processed one by one, in that case each
+/// plane receives the whole budget.
+fn split_threads(
+    threading_policy: ThreadingPolicy,
+    width: u32,
+    height: u32,
+    areas: &[usize],
+) -> (bool, Vec<ThreadingPolicy>) {
+    let budget = threading_policy.thread_count(width, height).max(1);
+    if budget < 2 || budget < areas.len() || areas.len() < 2 {
+        let policy = match NonZeroUsize::new(budget) {
+            Some(budget) if budget.get() > 1 => ThreadingPolicy::Fixed(budget),
+            _ => ThreadingPolicy::Single,
+        };
+        return (false, vec![policy; areas.len()]);
+    }
+    let total: usize = areas.iter().sum::<usize>().max(1);
+    let spare = budget - areas.len();
+    let mut shares = areas
+        .iter()
+        .map(|&area| 1 + spare * area / total)
+        .collect::<Vec<_>>();
+    let assigned: usize = shares.iter().sum();
+    if let Some(largest) = areas
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, area)| *area)
+        .map(|(index, _)| index)
+    {
+        shares[largest] += budget - assigned;
+    }
+    (
+        true,
+        shares
+            .into_iter()
+            .map(|share| match NonZeroUsize::new(share) {
+                Some(share) if share.get() > 1 => ThreadingPolicy::Fixed(share),
+                _ => ThreadingPolicy::Single,
+            })
+            .collect(),
+    )
+}
+
+/// Runs jobs on the current thread one by one or concurrently on the blur executor
+fn run_planes<J: Send, F>(jobs: Vec<J>, concurrent: bool, job: F) -> Result<(), BlurError>
+where
+    F: Fn(J) -> Result<(), BlurError> + Sync,
+{
+    if !concurrent {
+        return jobs.into_iter().try_for_each(job);
+    }
+    let slots = jobs
+        .into_iter()
+        .map(|j| Mutex::new((Some(j), Ok(()))))
+        .collect::<Vec<_>>();
+    ThreadPool::new(slots.len()).parallel_for(|i| {
+        let Some(slot) = slots.get(i) else {
+            return;
+        };
+        let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(j) = slot.0.take() {
+            slot.1 = job(j);
+        }
+    });
+    slots
+        .into_iter()
+        .try_for_each(|slot| slot.into_inner().unwrap_or_else(|e| e.into_inner()).1)
+}
+
+/// Blurs every plane of a planar image in one call.
+///
+/// Threads budget is computed by `threading_policy` for the full image size and is split
+/// between planes proportionally to their area, planes are blurred concurrently
+/// when the budget allows it. Each plane receives its share in [PlaneContext].
+///
+/// # Arguments
+///
+/// * `src`: Source image, see [PlanarImage] for more info.
+/// * `dst`: Destination image, must have the same planes layout or have no planes at all.
+/// * `threading_policy`: Threading policy for the whole image, see [ThreadingPolicy] for more info.
+/// * `blur`: Blur to perform on each plane.
+///
+/// # Example
+///
+/// ```
+/// use libblur::{AlphaMode, ConvolutionMode, EdgeMode, GaussianBlurParams, PlanarImage, PlanarImageMut, Subsampling, ThreadingPolicy};
+/// let (y, u, v) = (vec![0u8; 16 * 16], vec![0u8; 8 * 8], vec![0u8; 8 * 8]);
+/// let src = PlanarImage::yuv(&y, &u, &v, 16, 16, Subsampling::HALF);
+/// let mut dst = PlanarImageMut::default();
+/// libblur::blur_planes(&src, &mut dst, ThreadingPolicy::Adaptive, |src, dst, plane| {
+///     libblur::gaussian_blur(
+///         src,
+///         dst,
+///         plane.subsampling.scale_gaussian(GaussianBlurParams::new_from_kernel(5.)),
+///         EdgeMode::Clamp.as_2d(),
+///         plane.threading_policy,
+///         ConvolutionMode::Exact,
+///         AlphaMode::Premultiplied,
+///     )
+/// })
+/// .unwrap();
+/// ```
+pub fn blur_planes<T, F>(
+    src: &PlanarImage<T>,
+    dst: &mut PlanarImageMut<T>,
+    threading_policy: ThreadingPolicy,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync,
+    F: Fn(&BlurImage<T>, &mut BlurImageMut<T>, PlaneContext) -> Result<(), BlurError> + Sync,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    let (width, height) = (src.width, src.height);
+    let areas = src
+        .planes
+        .iter()
+        .map(|plane| {
+            let (w, h) = plane.subsampling.plane_size(width, height);
+            w as usize * h as usize
+        })
+        .collect::<Vec<_>>();
+    let (concurrent, policies) = split_threads(threading_policy, width, height, &areas);
+    let jobs = src
+        .planes
+        .iter()
+        .zip(dst.planes.iter_mut())
+        .zip(policies)
+        .enumerate()
+        .map(|(index, ((src_plane, dst_plane), threading_policy))| {
+            let context = PlaneContext {
+                index,
+                subsampling: src_plane.subsampling,
+                threading_policy,
+            };
+            (
+                plane_view(src_plane, width, height),
+                plane_view_mut(dst_plane, width, height),
+                context,
+            )
+        })
+        .collect::<Vec<_>>();
+    run_planes(jobs, concurrent, |(src, mut dst, context)| {
+        blur(&src, &mut dst, context)
+    })
+}
+
+/// Blurs every plane of a planar image in place in one call.
+///
+/// See [blur_planes] for more info, this one works with in-place blurs,
+/// e.g. [crate::stack_blur] or [crate::fast_gaussian].
+///
+/// # Arguments
+///
+/// * `image`: Image that will be used as a source and destination.
+/// * `threading_policy`: Threading policy for the whole image, see [ThreadingPolicy] for more info.
+/// * `blur`: Blur to perform on each plane.
+pub fn blur_planes_in_place<T, F>(
+    image: &mut PlanarImageMut<T>,
+    threading_policy: ThreadingPolicy,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Debug + Send + Sync,
+    F: Fn(&mut BlurImageMut<T>, PlaneContext) -> Result<(), BlurError> + Sync,
+{
+    image.check_layout(None)?;
+    let (width, height) = (image.width, image.height);
+    let areas = image
+        .planes
+        .iter()
+        .map(|plane| {
+            let (w, h) = plane.subsampling.plane_size(width, height);
+            w as usize * h as usize
+        })
+        .collect::<Vec<_>>();
+    let (concurrent, policies) = split_threads(threading_policy, width, height, &areas);
+    let jobs = image
+        .planes
+        .iter_mut()
+        .zip(policies)
+        .enumerate()
+        .map(|(index, (plane, threading_policy))| {
+            let context = PlaneContext {
+                index,
+                subsampling: plane.subsampling,
+                threading_policy,
+            };
+            (plane_view_mut(plane, width, height), context)
+        })
+        .collect::<Vec<_>>();
+    run_planes(jobs, concurrent, |(mut plane, context)| {
+        blur(&mut plane, context)
+    })
+}
+
+/// Performs gaussian blur on every plane of a planar image.
+///
+/// See [crate::gaussian_blur] and [blur_planes] for more info.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [PlanarImage] for more info.
+/// * `dst` - Destination image, must have the same planes layout or have no planes at all.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `radius_mode` - How kernel is applied to subsampled planes, see [PlaneRadiusMode] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [crate::EdgeMode] for more info.
+/// * `threading_policy` - Threading policy for the whole image according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_planar(
+    src: &PlanarImage<u8>,
+    dst: &mut PlanarImageMut<u8>,
+    params: GaussianBlurParams,
+    radius_mode: PlaneRadiusMode,
+    edge_modes: EdgeMode2D,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    blur_planes(src, dst, threading_policy, |src, dst, plane| {
+        gaussian_blur(
+            src,
+            dst,
+            plane.gaussian(params, radius_mode),
+            edge_modes,
+            plane.threading_policy,
+            hint,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+/// Performs box blur on every plane of a planar image.
+///
+/// See [crate::box_blur] and [blur_planes] for more info.
+///
+/// # Arguments
+///
+/// * `src` - Source image, see [PlanarImage] for more info.
+/// * `dst` - Destination image, must have the same planes layout or have no planes at all.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `radius_mode` - How kernel is applied to subsampled planes, see [PlaneRadiusMode] for more info.
+/// * `edge_modes` - Rule to handle edge mode, see [crate::EdgeMode] for more info.
+/// * `border_constant` - If [crate::EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+/// * `threading_policy` - Threading policy for the whole image according to [ThreadingPolicy].
+pub fn box_blur_planar(
+    src: &PlanarImage<u8>,
+    dst: &mut PlanarImageMut<u8>,
+    parameters: BoxBlurParameters,
+    radius_mode: PlaneRadiusMode,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    blur_planes(src, dst, threading_policy, |src, dst, plane| {
+        box_blur(
+            src,
+            dst,
+            plane.box_kernel(parameters, radius_mode),
+            edge_modes,
+            border_constant,
+            plane.threading_policy,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+/// Performs stack blur in place on every plane of a planar image.
+///
+/// See [crate::stack_blur] and [blur_planes_in_place] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place, see [PlanarImageMut] for more info.
+/// * `radius` - Radius more than ~150 is not supported.
+/// * `radius_mode` - How radius is applied to subsampled planes, see [PlaneRadiusMode] for more info.
+/// * `threading_policy` - Threading policy for the whole image according to [ThreadingPolicy].
+/// * `edge_modes` - Rule to handle edge mode, see [crate::EdgeMode] for more info.
+/// * `border_constant` - If [crate::EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+pub fn stack_blur_planar(
+    image: &mut PlanarImageMut<u8>,
+    radius: AnisotropicRadius,
+    radius_mode: PlaneRadiusMode,
+    threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+) -> Result<(), BlurError> {
+    blur_planes_in_place(image, threading_policy, |image, plane| {
+        stack_blur(
+            image,
+            plane.radius(radius, radius_mode),
+            plane.threading_policy,
+            edge_modes,
+            border_constant,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+/// Performs fast gaussian blur in place on every plane of a planar image.
+///
+/// See [crate::fast_gaussian] and [blur_planes_in_place] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place, see [PlanarImageMut] for more info.
+/// * `radius` - Radius of the blur.
+/// * `radius_mode` - How radius is applied to subsampled planes, see [PlaneRadiusMode] for more info.
+/// * `threading_policy` - Threading policy for the whole image according to [ThreadingPolicy].
+/// * `edge_modes` - Rule to handle edge mode, see [crate::EdgeMode] for more info.
+/// * `border_constant` - If [crate::EdgeMode::Constant] border will be replaced with this provided [Scalar] value.
+pub fn fast_gaussian_planar(
+    image: &mut PlanarImageMut<u8>,
+    radius: AnisotropicRadius,
+    radius_mode: PlaneRadiusMode,
+    threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+) -> Result<(), BlurError> {
+    blur_planes_in_place(image, threading_policy, |image, plane| {
+        fast_gaussian(
+            image,
+            plane.radius(radius, radius_mode),
+            plane.threading_policy,
+            edge_modes,
+            border_constant,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    fn make_pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|x| ((x * 7919) % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_gaussian_yuv420_matches_planes() {
+        let (width, height) = (37u32, 23u32);
+        let (chroma_width, chroma_height) = Subsampling::HALF.plane_size(width, height);
+        assert_eq!((chroma_width, chroma_height), (19, 12));
+        let y = make_pattern(width as usize * height as usize);
+        let u = make_pattern(chroma_width as usize * chroma_height as usize);
+        let v = u.iter().map(|&x| 255 - x).collect::<Vec<_>>();
+        let src = PlanarImage::yuv(&y, &u, &v, width, height, Subsampling::HALF);
+        let params = GaussianBlurParams::new_from_kernel(9.);
+
+        for threading_policy in [
+            ThreadingPolicy::Single,
+            ThreadingPolicy::Fixed(NonZeroUsize::new(5).unwrap()),
+        ] {
+            let mut dst = PlanarImageMut::default();
+            gaussian_blur_planar(
+                &src,
+                &mut dst,
+                params,
+                PlaneRadiusMode::Subsampled,
+                EdgeMode::Clamp.as_2d(),
+                threading_policy,
+                ConvolutionMode::Exact,
+            )
+            .unwrap();
+            assert_eq!(dst.planes.len(), 3);
+
+            for (index, plane) in src.planes.iter().enumerate() {
+                let plane_image = src.plane(index).unwrap();
+                let mut reference = BlurImageMut::default();
+                gaussian_blur(
+                    &plane_image,
+                    &mut reference,
+                    plane.subsampling.scale_gaussian(params),
+                    EdgeMode::Clamp.as_2d(),
+                    ThreadingPolicy::Single,
+                    ConvolutionMode::Exact,
+                    AlphaMode::Premultiplied,
+                )
+                .unwrap();
+                let blurred = dst.plane_mut(index).unwrap();
+                assert_eq!(blurred.width, plane_image.width);
+                assert_eq!(blurred.height, plane_image.height);
+                assert_eq!(blurred.data.borrow(), reference.data.borrow());
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_nv12_matches_planes() {
+        let (width, height) = (30u32, 18u32);
+        let mut y = make_pattern(width as usize * height as usize);
+        let mut uv = make_pattern(15 * 9 * 2);
+        let mut expected_y = y.clone();
+        let mut expected_uv = uv.clone();
+        let radius = AnisotropicRadius::new(6);
+
+        let threading_policy = ThreadingPolicy::Fixed(NonZeroUsize::new(3).unwrap());
+        let mut image = PlanarImageMut::nv12(&mut y, &mut uv, width, height);
+        stack_blur_planar(
+            &mut image,
+            radius,
+            PlaneRadiusMode::Subsampled,
+            threading_policy,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+        )
+        .unwrap();
+        // Stack blur output depends on threads count, so references use the same shares
+        let (concurrent, policies) =
+            split_threads(threading_policy, width, height, &[30 * 18, 15 * 9]);
+        assert!(concurrent);
+
+        let mut luma =
+            BlurImageMut::borrow(&mut expected_y, width, height, FastBlurChannels::Plane);
+        stack_blur(
+            &mut luma,
+            radius,
+            policies[0],
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        let mut chroma = BlurImageMut::borrow(&mut expected_uv, 15, 9, FastBlurChannels::Channels2);
+        stack_blur(
+            &mut chroma,
+            AnisotropicRadius::new(3),
+            policies[1],
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+
+        assert_eq!(y, expected_y);
+        assert_eq!(uv, expected_uv);
+    }
+
+    #[test]
+    fn test_split_threads() {
+        let policy = ThreadingPolicy::Fixed(NonZeroUsize::new(8).unwrap());
+        let (concurrent, policies) = split_threads(policy, 64, 64, &[4096, 1024, 1024]);
+        assert!(concurrent);
+        let total: usize = policies.iter().map(|x| x.thread_count(64, 64)).sum();
+        assert_eq!(total, 8);
+        assert!(policies[0].thread_count(64, 64) > policies[1].thread_count(64, 64));
+
+        let policy = ThreadingPolicy::Fixed(NonZeroUsize::new(2).unwrap());
+        let (concurrent, policies) = split_threads(policy, 64, 64, &[4096, 1024, 1024]);
+        assert!(!concurrent);
+        assert!(policies.iter().all(|&x| x == policy));
+
+        let (concurrent, policies) =
+            split_threads(ThreadingPolicy::Single, 64, 64, &[4096, 1024, 1024]);
+        assert!(!concurrent);
+        assert!(policies.iter().all(|&x| x == ThreadingPolicy::Single));
+    }
+
+    #[test]
+    fn test_planar_layout_errors() {
+        let y = vec![0u8; 16 * 16];
+        let u = vec![0u8; 8 * 7];
+        let v = vec![0u8; 8 * 8];
+        let src = PlanarImage::yuv(&y, &u, &v, 16, 16, Subsampling::HALF);
+        assert!(src.check_layout().is_err());
+
+        let src = PlanarImage::yuv(&y, &v, &v, 16, 16, Subsampling::new(0, 2));
+        assert!(src.check_layout().is_err());
+
+        let src = PlanarImage::yuv(&y, &v, &v, 16, 16, Subsampling::HALF);
+        let mut dst_y = vec![0u8; 16 * 16];
+        let mut dst_uv = vec![0u8; 8 * 8 * 2];
+        let mut dst = PlanarImageMut::nv12(&mut dst_y, &mut dst_uv, 16, 16);
+        assert!(matches!(
+            blur_planes(&src, &mut dst, ThreadingPolicy::Single, |_, _, _| Ok(())),
+            Err(BlurError::ImagesMustMatch)
+        ));
+    }
+}
@@ -0,0 +1,814 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, BlurImage, BlurImageMut, BlurWorkspace, FastBlurChannels, Scalar};
+
+/// Declares how color channels relate to alpha in [FastBlurChannels::Channels4] images.
+///
+/// Alpha is always expected to be the last channel.
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum AlphaMode {
+    /// Color channels are already multiplied by alpha, image is blurred as is.
+    #[default]
+    Premultiplied,
+    /// Color channels are not multiplied by alpha, image will be premultiplied
+    /// before the blur and unpremultiplied after it.
+    Straight,
+}
+
+pub(crate) const PREMULTIPLY_U16_INV_MAX: f32 = 1. / 65535.;
+
+#[inline(always)]
+pub(crate) fn premultiply_row_u8(row: &mut [u8]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let a = px[3] as u16;
+        for v in px[..3].iter_mut() {
+            let t = *v as u16 * a + 128;
+            *v = ((t + (t >> 8)) >> 8) as u8;
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn unpremultiply_row_u8(row: &mut [u8]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let a = px[3];
+        if a == 0 {
+            px[0] = 0;
+            px[1] = 0;
+            px[2] = 0;
+            continue;
+        }
+        let scale = 255. / a as f32;
+        for v in px[..3].iter_mut() {
+            *v = (*v as f32 * scale + 0.5).min(255.) as u8;
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn premultiply_row_u16(row: &mut [u16]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let scale = px[3] as f32 * PREMULTIPLY_U16_INV_MAX;
+        for v in px[..3].iter_mut() {
+            *v = (*v as f32 * scale + 0.5) as u16;
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn unpremultiply_row_u16(row: &mut [u16]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let a = px[3];
+        if a == 0 {
+            px[0] = 0;
+            px[1] = 0;
+            px[2] = 0;
+            continue;
+        }
+        let scale = 65535. / a as f32;
+        for v in px[..3].iter_mut() {
+            *v = (*v as f32 * scale + 0.5).min(65535.) as u16;
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn premultiply_row_f32(row: &mut [f32]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let a = px[3];
+        px[0] *= a;
+        px[1] *= a;
+        px[2] *= a;
+    }
+}
+
+#[inline(always)]
+pub(crate) fn unpremultiply_row_f32(row: &mut [f32]) {
+    for px in row.as_chunks_mut::<4>().0.iter_mut() {
+        let a = px[3];
+        if a == 0. {
+            px[0] = 0.;
+            px[1] = 0.;
+            px[2] = 0.;
+            continue;
+        }
+        px[0] /= a;
+        px[1] /= a;
+        px[2] /= a;
+    }
+}
+
+mod sealed {
+    use std::fmt::Debug;
+
+    /// Row handlers are selected per storage type, trait is not reachable outside the crate.
+    pub trait AlphaRows: Copy + Default + Debug {
+        /// Value of fully opaque alpha
+        const ALPHA_MAX: f64;
+        fn premultiply_handler() -> fn(&mut [Self]);
+        fn unpremultiply_handler() -> fn(&mut [Self]);
+    }
+}
+
+use sealed::AlphaRows;
+
+/// Storage type that supports alpha premultiplication.
+///
+/// Implemented for `u8`, `u16` and `f32`, `u16` is expected to use full 16-bit range.
+/// This trait is sealed and cannot be implemented outside the crate.
+pub trait AlphaPremultiply: AlphaRows {}
+
+impl AlphaPremultiply for u8 {}
+impl AlphaPremultiply for u16 {}
+impl AlphaPremultiply for f32 {}
+
+impl AlphaRows for u8 {
+    const ALPHA_MAX: f64 = 255.;
+
+    fn premultiply_handler() -> fn(&mut [u8]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::premultiply_row_neon_u8;
+            return premultiply_row_neon_u8;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::premultiply_row_avx_u8;
+                return premultiply_row_avx_u8;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::premultiply_row_sse_u8;
+                return premultiply_row_sse_u8;
+            }
+        }
+        #[allow(unreachable_code)]
+        premultiply_row_u8
+    }
+
+    fn unpremultiply_handler() -> fn(&mut [u8]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::unpremultiply_row_neon_u8;
+            return unpremultiply_row_neon_u8;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::unpremultiply_row_avx_u8;
+                return unpremultiply_row_avx_u8;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::unpremultiply_row_sse_u8;
+                return unpremultiply_row_sse_u8;
+            }
+        }
+        #[allow(unreachable_code)]
+        unpremultiply_row_u8
+    }
+}
+
+impl AlphaRows for u16 {
+    const ALPHA_MAX: f64 = 65535.;
+
+    fn premultiply_handler() -> fn(&mut [u16]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::premultiply_row_neon_u16;
+            return premultiply_row_neon_u16;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::premultiply_row_avx_u16;
+                return premultiply_row_avx_u16;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::premultiply_row_sse_u16;
+                return premultiply_row_sse_u16;
+            }
+        }
+        #[allow(unreachable_code)]
+        premultiply_row_u16
+    }
+
+    fn unpremultiply_handler() -> fn(&mut [u16]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::unpremultiply_row_neon_u16;
+            return unpremultiply_row_neon_u16;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::unpremultiply_row_avx_u16;
+                return unpremultiply_row_avx_u16;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::unpremultiply_row_sse_u16;
+                return unpremultiply_row_sse_u16;
+            }
+        }
+        #[allow(unreachable_code)]
+        unpremultiply_row_u16
+    }
+}
+
+impl AlphaRows for f32 {
+    const ALPHA_MAX: f64 = 1.;
+
+    fn premultiply_handler() -> fn(&mut [f32]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::premultiply_row_neon_f32;
+            return premultiply_row_neon_f32;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::premultiply_row_avx_f32;
+                return premultiply_row_avx_f32;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::premultiply_row_sse_f32;
+                return premultiply_row_sse_f32;
+            }
+        }
+        #[allow(unreachable_code)]
+        premultiply_row_f32
+    }
+
+    fn unpremultiply_handler() -> fn(&mut [f32]) {
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            use crate::neon::unpremultiply_row_neon_f32;
+            return unpremultiply_row_neon_f32;
+        }
+        #[cfg(all(target_arch = "x86_64", feature = "avx"))]
+        {
+            if std::arch::is_x86_feature_detected!("avx2") {
+                use crate::avx::unpremultiply_row_avx_f32;
+                return unpremultiply_row_avx_f32;
+            }
+        }
+        #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.1") {
+                use crate::sse::unpremultiply_row_sse_f32;
+                return unpremultiply_row_sse_f32;
+            }
+        }
+        #[allow(unreachable_code)]
+        unpremultiply_row_f32
+    }
+}
+
+fn apply_rows<T: AlphaPremultiply>(
+    image: &mut BlurImageMut<T>,
+    handler: fn(&mut [T]),
+) -> Result<(), BlurError> {
+    image.check_layout(None)?;
+    if image.channels != FastBlurChannels::Channels4 {
+        return Err(BlurError::InvalidChannels(image.channels.channels()));
+    }
+    let width = image.width as usize * 4;
+    let stride = image.row_stride() as usize;
+    for row in image.data.borrow_mut().chunks_mut(stride) {
+        handler(&mut row[..width]);
+    }
+    Ok(())
+}
+
+fn premultiplied_copy<'f, T: AlphaPremultiply>(
+    image: &BlurImage<T>,
+    handler: fn(&mut [T]),
+) -> Result<BlurImage<'f, T>, BlurError> {
+    image.check_layout()?;
+    let mut target = BlurImageMut::alloc(image.width, image.height, image.channels);
+    image.copy_to_mut(&mut target)?;
+    apply_rows(&mut target, handler)?;
+    let data = match target.data {
+        crate::BufferStore::Owned(data) => data,
+        crate::BufferStore::Borrowed(data) => data.to_vec(),
+    };
+    Ok(BlurImage {
+        data: std::borrow::Cow::Owned(data),
+        width: target.width,
+        height: target.height,
+        stride: target.stride,
+        channels: target.channels,
+    })
+}
+
+/// Multiplies color channels of the border constant by its alpha
+fn premultiplied_border<T: AlphaPremultiply>(border_constant: Scalar) -> Scalar {
+    let scale = border_constant.v3 / T::ALPHA_MAX;
+    Scalar::new(
+        border_constant.v0 * scale,
+        border_constant.v1 * scale,
+        border_constant.v2 * scale,
+        border_constant.v3,
+    )
+}
+
+/// Runs in-place `blur` on premultiplied image, then unpremultiplies it
+///
+/// `blur` receives border constant premultiplied the same way as the image.
+pub(crate) fn blur_straight_in_place<T: AlphaPremultiply, F>(
+    image: &mut BlurImageMut<T>,
+    border_constant: Scalar,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    F: FnOnce(&mut BlurImageMut<T>, Scalar) -> Result<(), BlurError>,
+{
+    apply_rows(image, T::premultiply_handler())?;
+    blur(image, premultiplied_border::<T>(border_constant))?;
+    apply_rows(image, T::unpremultiply_handler())
+}
+
+/// Stages premultiplied copy of `src` in `dst`, runs in-place `blur` on it,
+/// then unpremultiplies the result
+pub(crate) fn blur_straight_staged<T: AlphaPremultiply, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    border_constant: Scalar,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    F: FnOnce(&mut BlurImageMut<T>, Scalar) -> Result<(), BlurError>,
+{
+    src.copy_to_mut(dst)?;
+    blur_straight_in_place(dst, border_constant, blur)
+}
+
+/// Writes premultiplied copy of `src` into `workspace`, returns image borrowing it
+pub(crate) fn premultiply_into_workspace<'a, T: AlphaPremultiply>(
+    src: &BlurImage<T>,
+    workspace: &'a mut BlurWorkspace<T>,
+) -> Result<BlurImage<'a, T>, BlurError> {
+    src.check_layout()?;
+    let row_size = src.width as usize * src.channels.channels();
+    let target = workspace.take(row_size * src.height as usize)?;
+    let handler = T::premultiply_handler();
+    for (dst, src) in target
+        .chunks_exact_mut(row_size)
+        .zip(src.data.chunks(src.row_stride() as usize))
+    {
+        dst.copy_from_slice(&src[..row_size]);
+        handler(dst);
+    }
+    Ok(BlurImage::borrow(
+        target,
+        src.width,
+        src.height,
+        src.channels,
+    ))
+}
+
+/// Divides color channels by alpha in place
+pub(crate) fn unpremultiply_in_place<T: AlphaPremultiply>(
+    image: &mut BlurImageMut<T>,
+) -> Result<(), BlurError> {
+    apply_rows(image, T::unpremultiply_handler())
+}
+
+macro_rules! impl_premultiply {
+    ($t: ty) => {
+        impl BlurImage<'_, $t> {
+            /// Returns a copy of the image with color channels multiplied by alpha
+            ///
+            /// Image must have [FastBlurChannels::Channels4] layout with alpha as the last channel.
+            pub fn premultiply_alpha<'f>(&self) -> Result<BlurImage<'f, $t>, BlurError> {
+                premultiplied_copy(self, <$t>::premultiply_handler())
+            }
+
+            /// Returns a copy of the image with color channels divided by alpha
+            ///
+            /// Image must have [FastBlurChannels::Channels4] layout with alpha as the last channel,
+            /// fully transparent pixels become zero.
+            pub fn unpremultiply_alpha<'f>(&self) -> Result<BlurImage<'f, $t>, BlurError> {
+                premultiplied_copy(self, <$t>::unpremultiply_handler())
+            }
+        }
+
+        impl BlurImageMut<'_, $t> {
+            /// Multiplies color channels by alpha in place
+            ///
+            /// Image must have [FastBlurChannels::Channels4] layout with alpha as the last channel.
+            pub fn premultiply_alpha(&mut self) -> Result<(), BlurError> {
+                apply_rows(self, <$t>::premultiply_handler())
+            }
+
+            /// Divides color channels by alpha in place
+            ///
+            /// Image must have [FastBlurChannels::Channels4] layout with alpha as the last channel,
+            /// fully transparent pixels become zero.
+            pub fn unpremultiply_alpha(&mut self) -> Result<(), BlurError> {
+                apply_rows(self, <$t>::unpremultiply_handler())
+            }
+        }
+    };
+}
+
+impl_premultiply!(u8);
+impl_premultiply!(u16);
+impl_premultiply!(f32);
+
+/// Runs a blur honoring [AlphaMode]
+///
+/// When `alpha_mode` is [AlphaMode::Straight] and image has [FastBlurChannels::Channels4] layout,
+/// source is premultiplied before the blur, and destination is unpremultiplied after it.
+/// Otherwise, `blur` is called as is.
+///
+/// Blurs of the crate accept [AlphaMode] directly and should be preferred,
+/// this wrapper is meant for filters that do not, e.g. [crate::filter_2d].
+/// Straight source is premultiplied into a temporary copy on every call.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `alpha_mode`: See [AlphaMode] for more info.
+/// * `blur`: Blur to perform, must expect premultiplied alpha.
+///
+/// # Example
+///
+/// ```
+/// use libblur::{AlphaMode, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, KernelShape, Scalar, ThreadingPolicy};
+/// let data = vec![255u8; 16 * 16 * 4];
+/// let src = BlurImage::borrow(&data, 16, 16, FastBlurChannels::Channels4);
+/// let mut dst = BlurImageMut::default();
+/// libblur::blur_with_alpha_mode(&src, &mut dst, AlphaMode::Straight, |src, dst| {
+///     libblur::filter_2d::<u8, f32>(
+///         src,
+///         dst,
+///         &[1. / 9.; 9],
+///         KernelShape::new(3, 3),
+///         EdgeMode::Clamp.as_2d(),
+///         Scalar::default(),
+///         ThreadingPolicy::Single,
+///     )
+/// })
+/// .unwrap();
+/// ```
+pub fn blur_with_alpha_mode<T: AlphaPremultiply, F>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    F: FnOnce(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    if alpha_mode == AlphaMode::Premultiplied || src.channels != FastBlurChannels::Channels4 {
+        return blur(src, dst);
+    }
+    let premultiplied = premultiplied_copy(src, T::premultiply_handler())?;
+    blur(&premultiplied, dst)?;
+    apply_rows(dst, T::unpremultiply_handler())
+}
+
+/// Runs an in-place blur honoring [AlphaMode]
+///
+/// When `alpha_mode` is [AlphaMode::Straight] and image has [FastBlurChannels::Channels4] layout,
+/// image is premultiplied before the blur, and unpremultiplied after it.
+/// Otherwise, `blur` is called as is.
+///
+/// Blurs of the crate accept [AlphaMode] directly and should be preferred,
+/// this wrapper is meant for in-place filters that do not.
+///
+/// # Arguments
+///
+/// * `image`: Image that will be used as a source and destination.
+/// * `alpha_mode`: See [AlphaMode] for more info.
+/// * `blur`: Blur to perform, must expect premultiplied alpha.
+pub fn blur_in_place_with_alpha_mode<T: AlphaPremultiply, F>(
+    image: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    F: FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    if alpha_mode == AlphaMode::Premultiplied || image.channels != FastBlurChannels::Channels4 {
+        return blur(image);
+    }
+    apply_rows(image, T::premultiply_handler())?;
+    blur(image)?;
+    apply_rows(image, T::unpremultiply_handler())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AnisotropicRadius, BoxBlurParameters, CLTParameters, ConvolutionMode, EdgeMode,
+        GaussianBlurParams, ThreadingPolicy, box_blur, fast_gaussian, fast_gaussian_next,
+        gaussian_blur, gaussian_box_blur, stack_blur, tent_blur,
+    };
+
+    #[test]
+    fn test_premultiply_u8_matches_scalar() {
+        let mut data = Vec::with_capacity(37 * 256 * 4);
+        for a in 0..=255u8 {
+            for c in 0..37u8 {
+                data.extend_from_slice(&[c.wrapping_mul(7), c.wrapping_mul(13), 255 - c, a]);
+            }
+        }
+        let mut reference = data.clone();
+        premultiply_row_u8(&mut reference);
+        let mut working = data.clone();
+        let mut image = BlurImageMut::borrow(&mut working, 37, 256, FastBlurChannels::Channels4);
+        image.premultiply_alpha().unwrap();
+        assert_eq!(working, reference);
+
+        unpremultiply_row_u8(&mut reference);
+        let mut image = BlurImageMut::borrow(&mut working, 37, 256, FastBlurChannels::Channels4);
+        image.unpremultiply_alpha().unwrap();
+        assert_eq!(working, reference);
+
+        for (src, restored) in data
+            .as_chunks::<4>()
+            .0
+            .iter()
+            .zip(reference.as_chunks::<4>().0.iter())
+        {
+            assert_eq!(src[3], restored[3]);
+            if src[3] == 255 {
+                assert_eq!(src, restored);
+            }
+        }
+    }
+
+    #[test]
+    fn test_premultiply_u16_matches_scalar() {
+        let mut data = Vec::with_capacity(13 * 64 * 4);
+        for y in 0..64u16 {
+            for x in 0..13u16 {
+                let a = if y == 0 { 0 } else { y * 1040 + x };
+                data.extend_from_slice(&[x * 5000, 65535 - x * 311, y * 1000, a]);
+            }
+        }
+        let mut reference = data.clone();
+        premultiply_row_u16(&mut reference);
+        let src = BlurImage::borrow(&data, 13, 64, FastBlurChannels::Channels4);
+        let premultiplied = src.premultiply_alpha().unwrap();
+        assert_eq!(premultiplied.data.as_ref(), reference.as_slice());
+
+        unpremultiply_row_u16(&mut reference);
+        let restored = premultiplied.unpremultiply_alpha().unwrap();
+        assert_eq!(restored.data.as_ref(), reference.as_slice());
+    }
+
+    #[test]
+    fn test_premultiply_f32_matches_scalar() {
+        let mut data = Vec::with_capacity(11 * 9 * 4);
+        for y in 0..9 {
+            for x in 0..11 {
+                let a = y as f32 / 8.;
+                data.extend_from_slice(&[x as f32 / 10., 0.3, 1. - x as f32 / 10., a]);
+            }
+        }
+        let mut reference = data.clone();
+        premultiply_row_f32(&mut reference);
+        let mut working = data.clone();
+        let mut image = BlurImageMut::borrow(&mut working, 11, 9, FastBlurChannels::Channels4);
+        image.premultiply_alpha().unwrap();
+        assert_eq!(working, reference);
+
+        unpremultiply_row_f32(&mut reference);
+        let mut image = BlurImageMut::borrow(&mut working, 11, 9, FastBlurChannels::Channels4);
+        image.unpremultiply_alpha().unwrap();
+        assert_eq!(working, reference);
+    }
+
+    #[test]
+    fn test_straight_alpha_does_not_bleed_transparent_color() {
+        let width = 32usize;
+        let height = 8usize;
+        let mut data = vec![0u8; width * height * 4];
+        for row in data.chunks_exact_mut(width * 4) {
+            for (x, px) in row.as_chunks_mut::<4>().0.iter_mut().enumerate() {
+                if x < width / 2 {
+                    px.copy_from_slice(&[255, 0, 0, 255]);
+                } else {
+                    px.copy_from_slice(&[0, 255, 0, 0]);
+                }
+            }
+        }
+        let mut image = BlurImageMut::borrow(
+            &mut data,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        stack_blur(
+            &mut image,
+            AnisotropicRadius::new(5),
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Straight,
+        )
+        .unwrap();
+        for px in data.as_chunks::<4>().0.iter() {
+            if px[3] != 0 {
+                assert!(px[0] >= 250, "Red must be kept, but pixel was {px:?}");
+                assert!(px[1] <= 2, "Green must not bleed, but pixel was {px:?}");
+            }
+        }
+    }
+
+    type OutOfPlaceBlur<'a> = dyn Fn(&BlurImage<u8>, &mut BlurImageMut<u8>, AlphaMode, Scalar) -> Result<(), BlurError>
+        + 'a;
+    type InPlaceBlur<'a> =
+        dyn Fn(&mut BlurImageMut<u8>, AlphaMode, Scalar) -> Result<(), BlurError> + 'a;
+
+    #[test]
+    fn test_straight_alpha_entry_points_match_wrappers() {
+        let width = 37u32;
+        let height = 29u32;
+        let mut seed = 0x2545f491u32;
+        let src = (0..width * height * 4)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 24) as u8
+            })
+            .collect::<Vec<u8>>();
+        let src_image = BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4);
+        // Transparent border must not bleed its color either
+        for (edge_mode, border) in [
+            (EdgeMode::Clamp, Scalar::default()),
+            (EdgeMode::Reflect101, Scalar::default()),
+            (EdgeMode::Constant, Scalar::new(255., 0., 0., 0.)),
+        ] {
+            let edge_modes = edge_mode.as_2d();
+            let out_of_place: [(&str, &OutOfPlaceBlur<'_>); 4] = [
+                ("gaussian_blur", &|src, dst, alpha_mode, _| {
+                    gaussian_blur(
+                        src,
+                        dst,
+                        GaussianBlurParams::new_from_kernel(9.),
+                        edge_modes,
+                        ThreadingPolicy::Single,
+                        ConvolutionMode::FixedPoint,
+                        alpha_mode,
+                    )
+                }),
+                ("box_blur", &|src, dst, alpha_mode, border| {
+                    box_blur(
+                        src,
+                        dst,
+                        BoxBlurParameters::new(7),
+                        edge_modes,
+                        border,
+                        ThreadingPolicy::Single,
+                        alpha_mode,
+                    )
+                }),
+                ("tent_blur", &|src, dst, alpha_mode, border| {
+                    tent_blur(
+                        src,
+                        dst,
+                        CLTParameters::new(3.),
+                        edge_modes,
+                        border,
+                        ThreadingPolicy::Single,
+                        alpha_mode,
+                    )
+                }),
+                ("gaussian_box_blur", &|src, dst, alpha_mode, border| {
+                    gaussian_box_blur(
+                        src,
+                        dst,
+                        CLTParameters::new(3.),
+                        edge_modes,
+                        border,
+                        ThreadingPolicy::Single,
+                        alpha_mode,
+                    )
+                }),
+            ];
+            for (name, blur) in out_of_place {
+                let mut reference = BlurImageMut::default();
+                blur_with_alpha_mode(
+                    &src_image,
+                    &mut reference,
+                    AlphaMode::Straight,
+                    |src, dst| blur(src, dst, AlphaMode::Premultiplied, Scalar::default()),
+                )
+                .unwrap();
+                let mut dst = BlurImageMut::default();
+                blur(&src_image, &mut dst, AlphaMode::Straight, border).unwrap();
+                assert_eq!(
+                    dst.data.borrow(),
+                    reference.data.borrow(),
+                    "{name} mismatch for {edge_mode:?}"
+                );
+            }
+
+            let in_place: [(&str, &InPlaceBlur<'_>); 3] = [
+                ("stack_blur", &|image, alpha_mode, border| {
+                    stack_blur(
+                        image,
+                        AnisotropicRadius::new(4),
+                        ThreadingPolicy::Single,
+                        edge_modes,
+                        border,
+                        alpha_mode,
+                    )
+                }),
+                ("fast_gaussian", &|image, alpha_mode, border| {
+                    fast_gaussian(
+                        image,
+                        AnisotropicRadius::new(4),
+                        ThreadingPolicy::Single,
+                        edge_modes,
+                        border,
+                        alpha_mode,
+                    )
+                }),
+                ("fast_gaussian_next", &|image, alpha_mode, border| {
+                    fast_gaussian_next(
+                        image,
+                        AnisotropicRadius::new(4),
+                        ThreadingPolicy::Single,
+                        edge_modes,
+                        border,
+                        alpha_mode,
+                    )
+                }),
+            ];
+            for (name, blur) in in_place {
+                let mut reference = src.clone();
+                let mut image = BlurImageMut::borrow(
+                    &mut reference,
+                    width,
+                    height,
+                    FastBlurChannels::Channels4,
+                );
+                blur_in_place_with_alpha_mode(&mut image, AlphaMode::Straight, |image| {
+                    blur(image, AlphaMode::Premultiplied, Scalar::default())
+                })
+                .unwrap();
+                let mut data = src.clone();
+                let mut image =
+                    BlurImageMut::borrow(&mut data, width, height, FastBlurChannels::Channels4);
+                blur(&mut image, AlphaMode::Straight, border).unwrap();
+                assert_eq!(data, reference, "{name} mismatch for {edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_premultiply_requires_rgba() {
+        let mut data = vec![0u8; 4 * 4 * 3];
+        let mut image = BlurImageMut::borrow(&mut data, 4, 4, FastBlurChannels::Channels3);
+        assert!(matches!(
+            image.premultiply_alpha(),
+            Err(BlurError::InvalidChannels(3))
+        ));
+    }
+}
@@ -0,0 +1,807 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::make_edge_lookup;
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::{
+    BlurError, BlurImage, BlurImageMut, BufferStore, EdgeMode, EdgeMode2D, FastBlurChannels,
+    ImageSize, Scalar, ThreadingPolicy,
+};
+use std::fmt::Debug;
+
+/// Sample type that can be stored in an image pyramid
+pub trait PyramidSample: Copy + Clone + Default + Debug + Send + Sync + 'static {
+    fn to_f32(self) -> f32;
+    fn from_f32(v: f32) -> Self;
+}
+
+impl PyramidSample for u8 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u8::MAX as f32) as u8
+    }
+}
+
+impl PyramidSample for u16 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v.round().max(0.).min(u16::MAX as f32) as u16
+    }
+}
+
+impl PyramidSample for f32 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+}
+
+/// 5-tap binomial kernel `[1, 4, 6, 4, 1] / 16`
+const BINOMIAL: [f32; 5] = [1. / 16., 4. / 16., 6. / 16., 4. / 16., 1. / 16.];
+const PAD: usize = 2;
+
+type Taps = [(Option<usize>, f32); 5];
+
+/// Computes source taps for each output sample on one axis.
+fn make_taps(in_len: usize, out_len: usize, upsample: bool, edge_mode: EdgeMode) -> Vec<Taps> {
+    let lookup = make_edge_lookup(edge_mode, in_len, PAD + 1);
+    let fetch = |i: isize| lookup[(i + PAD as isize + 1) as usize];
+    (0..out_len)
+        .map(|o| {
+            let mut taps: Taps = [(None, 0.); 5];
+            if upsample {
+                // Zero insertion followed by the binomial kernel scaled by 2,
+                // only taps hitting even positions are non-zero.
+                let o = o as isize;
+                for (k, (tap, &weight)) in taps.iter_mut().zip(BINOMIAL.iter()).enumerate() {
+                    let position = o + k as isize - PAD as isize;
+                    if position.rem_euclid(2) == 0 {
+                        *tap = (fetch(position.div_euclid(2)), weight * 2.);
+                    }
+                }
+            } else {
+                for (k, (tap, &weight)) in taps.iter_mut().zip(BINOMIAL.iter()).enumerate() {
+                    *tap = (fetch(2 * o as isize + k as isize - PAD as isize), weight);
+                }
+            }
+            taps
+        })
+        .collect()
+}
+
+/// Resamples f32 interleaved image by factor 2 in both directions.
+#[allow(clippy::too_many_arguments)]
+fn resample_impl<const CN: usize>(
+    src: &[f32],
+    src_width: usize,
+    src_height: usize,
+    dst: &mut [f32],
+    dst_width: usize,
+    dst_height: usize,
+    upsample: bool,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    pool: &crate::executor::ThreadPool,
+) {
+    let x_taps = make_taps(src_width, dst_width, upsample, edge_modes.horizontal);
+    let y_taps = make_taps(src_height, dst_height, upsample, edge_modes.vertical);
+    let mut constant = [0f32; CN];
+    for (c, dst) in constant.iter_mut().enumerate() {
+        *dst = border_constant[c] as f32;
+    }
+
+    let mut transient = vec![0f32; dst_width * CN * src_height];
+    transient
+        .tb_par_chunks_exact_mut(dst_width * CN)
+        .for_each_enumerated(pool, |y, row| {
+            let src_row = &src[y * src_width * CN..(y + 1) * src_width * CN];
+            for (dst, taps) in row.chunks_exact_mut(CN).zip(x_taps.iter()) {
+                for c in 0..CN {
+                    let mut sum = 0f32;
+                    for &(index, weight) in taps.iter() {
+                        let v = match index {
+                            Some(x) => src_row[x * CN + c],
+                            None => constant[c],
+                        };
+                        sum += v * weight;
+                    }
+                    dst[c] = sum;
+                }
+            }
+        });
+
+    let row_size = dst_width * CN;
+    dst[..row_size * dst_height]
+        .tb_par_chunks_exact_mut(row_size)
+        .for_each_enumerated(pool, |y, row| {
+            row.fill(0.);
+            for &(index, weight) in y_taps[y].iter() {
+                if weight == 0. {
+                    continue;
+                }
+                match index {
+                    Some(src_y) => {
+                        let src_row = &transient[src_y * row_size..(src_y + 1) * row_size];
+                        for (dst, &src) in row.iter_mut().zip(src_row.iter()) {
+                            *dst += src * weight;
+                        }
+                    }
+                    None => {
+                        for dst in row.chunks_exact_mut(CN) {
+                            for c in 0..CN {
+                                dst[c] += constant[c] * weight;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+}
+
+/// Working f32 level with tightly packed rows
+struct Level {
+    data: Vec<f32>,
+    width: usize,
+    height: usize,
+}
+
+impl Level {
+    fn from_image<T: PyramidSample>(image: &BlurImage<T>) -> Level {
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let row_size = width * image.channels.channels();
+        let mut data = vec![0f32; row_size * height];
+        for (dst, src) in data
+            .chunks_exact_mut(row_size)
+            .zip(image.data.chunks(image.row_stride() as usize))
+        {
+            for (dst, &src) in dst.iter_mut().zip(src[..row_size].iter()) {
+                *dst = src.to_f32();
+            }
+        }
+        Level {
+            data,
+            width,
+            height,
+        }
+    }
+
+    fn store<T: PyramidSample>(&self, dst: &mut BlurImageMut<T>) {
+        let row_size = self.width * dst.channels.channels();
+        let dst_stride = dst.row_stride() as usize;
+        for (dst, src) in dst
+            .data
+            .borrow_mut()
+            .chunks_mut(dst_stride)
+            .zip(self.data.chunks_exact(row_size))
+        {
+            for (dst, &src) in dst[..row_size].iter_mut().zip(src.iter()) {
+                *dst = T::from_f32(src);
+            }
+        }
+    }
+
+    fn into_image(self, channels: FastBlurChannels) -> BlurImageMut<'static, f32> {
+        BlurImageMut {
+            data: BufferStore::Owned(self.data),
+            width: self.width as u32,
+            height: self.height as u32,
+            stride: (self.width * channels.channels()) as u32,
+            channels,
+        }
+    }
+
+    fn resample(
+        &self,
+        dst_width: usize,
+        dst_height: usize,
+        upsample: bool,
+        channels: FastBlurChannels,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+        pool: &crate::executor::ThreadPool,
+    ) -> Level {
+        let mut data = vec![0f32; dst_width * dst_height * channels.channels()];
+        let _dispatcher = match channels {
+            FastBlurChannels::Plane => resample_impl::<1>,
+            FastBlurChannels::Channels2 => resample_impl::<2>,
+            FastBlurChannels::Channels3 => resample_impl::<3>,
+            FastBlurChannels::Channels4 => resample_impl::<4>,
+        };
+        _dispatcher(
+            &self.data,
+            self.width,
+            self.height,
+            &mut data,
+            dst_width,
+            dst_height,
+            upsample,
+            edge_modes,
+            border_constant,
+            pool,
+        );
+        Level {
+            data,
+            width: dst_width,
+            height: dst_height,
+        }
+    }
+}
+
+#[inline]
+fn down_size(v: usize) -> usize {
+    v.div_ceil(2)
+}
+
+fn pyr_down_impl<T: PyramidSample>(
+    image: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    let dst_width = down_size(image.width as usize);
+    let dst_height = down_size(image.height as usize);
+    dst.check_layout_with_size(dst_width as u32, dst_height as u32, image.channels)?;
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(image.width, image.height));
+    let level = Level::from_image(image);
+    let resampled = level.resample(
+        dst_width,
+        dst_height,
+        false,
+        image.channels,
+        edge_modes,
+        border_constant,
+        &pool,
+    );
+    resampled.store(dst);
+    Ok(())
+}
+
+#[inline]
+fn up_size_matches(src: u32, dst: u32) -> bool {
+    let twice = src as u64 * 2;
+    let dst = dst as u64;
+    dst == twice || (dst % 2 == 1 && dst.abs_diff(twice) == 1)
+}
+
+fn pyr_up_impl<T: PyramidSample>(
+    image: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    dst_size: Option<ImageSize>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    let (dst_width, dst_height) = match dst_size {
+        Some(size) => (
+            u32::try_from(size.width).map_err(|_| BlurError::ExceedingPointerSize)?,
+            u32::try_from(size.height).map_err(|_| BlurError::ExceedingPointerSize)?,
+        ),
+        None => (
+            image
+                .width
+                .checked_mul(2)
+                .ok_or(BlurError::ExceedingPointerSize)?,
+            image
+                .height
+                .checked_mul(2)
+                .ok_or(BlurError::ExceedingPointerSize)?,
+        ),
+    };
+    if !up_size_matches(image.width, dst_width) || !up_size_matches(image.height, dst_height) {
+        return Err(BlurError::ImagesMustMatch);
+    }
+    dst.check_layout_with_size(dst_width, dst_height, image.channels)?;
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(dst_width, dst_height));
+    let level = Level::from_image(image);
+    let resampled = level.resample(
+        dst_width as usize,
+        dst_height as usize,
+        true,
+        image.channels,
+        edge_modes,
+        border_constant,
+        &pool,
+    );
+    resampled.store(dst);
+    Ok(())
+}
+
+/// Blurs the image with 5-tap binomial kernel and downsamples it by 2.
+///
+/// Destination has size `((width + 1) / 2, (height + 1) / 2)`.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, owned buffer will be resized, see [BlurImageMut] for more info.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn pyr_down(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_down_impl(
+        image,
+        destination,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Blurs the image with 5-tap binomial kernel and downsamples it by 2.
+///
+/// See [pyr_down] for more info.
+pub fn pyr_down_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_down_impl(
+        image,
+        destination,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Blurs the image with 5-tap binomial kernel and downsamples it by 2.
+///
+/// See [pyr_down] for more info.
+pub fn pyr_down_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_down_impl(
+        image,
+        destination,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Upsamples the image by 2 and blurs it with 5-tap binomial kernel.
+///
+/// Destination has size `(width * 2, height * 2)` unless `destination_size` is set,
+/// explicit size may also be odd `width * 2 ± 1`,
+/// this allows restoring odd sized levels made by [pyr_down].
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, owned buffer will be resized, see [BlurImageMut] for more info.
+/// * `destination_size`: Size of the destination, `None` to double the source size.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn pyr_up(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    destination_size: Option<ImageSize>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_up_impl(
+        image,
+        destination,
+        destination_size,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Upsamples the image by 2 and blurs it with 5-tap binomial kernel.
+///
+/// See [pyr_up] for more info.
+pub fn pyr_up_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    destination_size: Option<ImageSize>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_up_impl(
+        image,
+        destination,
+        destination_size,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Upsamples the image by 2 and blurs it with 5-tap binomial kernel.
+///
+/// See [pyr_up] for more info.
+pub fn pyr_up_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    destination_size: Option<ImageSize>,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    pyr_up_impl(
+        image,
+        destination,
+        destination_size,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Builds Gaussian pyramid, where level 0 is a copy of the source image
+/// and each next level is made by [pyr_down] from the previous one.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `levels`: Count of downsampled levels, result contains `levels + 1` images.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+pub fn gaussian_pyramid<T: PyramidSample>(
+    image: &BlurImage<T>,
+    levels: usize,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<Vec<BlurImageMut<'static, T>>, BlurError> {
+    image.check_layout()?;
+    let mut pyramid = Vec::with_capacity(levels + 1);
+    let mut first = BlurImageMut::default();
+    image.copy_to_mut(&mut first)?;
+    pyramid.push(first);
+    for _ in 0..levels {
+        let mut next = BlurImageMut::default();
+        let previous = pyramid.last().unwrap();
+        pyr_down_impl(
+            &previous.to_immutable_ref(),
+            &mut next,
+            edge_modes,
+            border_constant,
+            threading_policy,
+        )?;
+        pyramid.push(next);
+    }
+    Ok(pyramid)
+}
+
+/// Laplacian pyramid, stored in f32 so reconstruction is exact
+/// and levels may be freely combined, e.g. for multi-band blending.
+pub struct LaplacianPyramid {
+    /// Band-pass levels from the finest to the coarsest
+    pub levels: Vec<BlurImageMut<'static, f32>>,
+    /// Low-pass residual at the top of the pyramid
+    pub residual: BlurImageMut<'static, f32>,
+    /// Edge modes used to build the pyramid, reused on reconstruction
+    pub edge_modes: EdgeMode2D,
+    /// Border constant used to build the pyramid, reused on reconstruction
+    pub border_constant: Scalar,
+}
+
+impl LaplacianPyramid {
+    /// Builds Laplacian pyramid with `levels` band-pass levels
+    ///
+    /// # Arguments
+    ///
+    /// * `image`: Source image, see [BlurImage] for more info.
+    /// * `levels`: Count of band-pass levels.
+    /// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+    /// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+    /// * `threading_policy`: see [ThreadingPolicy] for more info.
+    ///
+    pub fn new<T: PyramidSample>(
+        image: &BlurImage<T>,
+        levels: usize,
+        edge_modes: EdgeMode2D,
+        border_constant: Scalar,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<LaplacianPyramid, BlurError> {
+        image.check_layout()?;
+        let channels = image.channels;
+        let pool = crate::executor::ThreadPool::new(
+            threading_policy.thread_count(image.width, image.height),
+        );
+        let mut current = Level::from_image(image);
+        let mut band_pass = Vec::with_capacity(levels);
+        for _ in 0..levels {
+            let down = current.resample(
+                down_size(current.width),
+                down_size(current.height),
+                false,
+                channels,
+                edge_modes,
+                border_constant,
+                &pool,
+            );
+            let up = down.resample(
+                current.width,
+                current.height,
+                true,
+                channels,
+                edge_modes,
+                border_constant,
+                &pool,
+            );
+            for (dst, &src) in current.data.iter_mut().zip(up.data.iter()) {
+                *dst -= src;
+            }
+            band_pass.push(current.into_image(channels));
+            current = down;
+        }
+        Ok(LaplacianPyramid {
+            levels: band_pass,
+            residual: current.into_image(channels),
+            edge_modes,
+            border_constant,
+        })
+    }
+
+    /// Collapses the pyramid back into the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `destination`: Destination image, owned buffer will be resized, see [BlurImageMut] for more info.
+    /// * `threading_policy`: see [ThreadingPolicy] for more info.
+    ///
+    pub fn reconstruct<T: PyramidSample>(
+        &self,
+        destination: &mut BlurImageMut<T>,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<(), BlurError> {
+        let channels = self.residual.channels;
+        let (width, height) = self
+            .levels
+            .first()
+            .map(|x| (x.width, x.height))
+            .unwrap_or((self.residual.width, self.residual.height));
+        destination.check_layout_with_size(width, height, channels)?;
+        let pool = crate::executor::ThreadPool::new(threading_policy.thread_count(width, height));
+        let mut current = Level::from_image(&self.residual.to_immutable_ref());
+        for level in self.levels.iter().rev() {
+            if level.channels != channels
+                || down_size(level.width as usize) != current.width
+                || down_size(level.height as usize) != current.height
+            {
+                return Err(BlurError::ImagesMustMatch);
+            }
+            let mut up = current.resample(
+                level.width as usize,
+                level.height as usize,
+                true,
+                channels,
+                self.edge_modes,
+                self.border_constant,
+                &pool,
+            );
+            let level_stride = level.row_stride() as usize;
+            let row_size = level.width as usize * channels.channels();
+            for (dst, src) in up
+                .data
+                .chunks_exact_mut(row_size)
+                .zip(level.data.borrow().chunks(level_stride))
+            {
+                for (dst, &src) in dst.iter_mut().zip(src[..row_size].iter()) {
+                    *dst += src;
+                }
+            }
+            current = up;
+        }
+        current.store(destination);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_pattern(width: usize, height: usize, cn: usize) -> Vec<u8> {
+        (0..width * height * cn)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_pyr_down_constant() {
+        let width = 37usize;
+        let height = 21usize;
+        let src = vec![117u8; width * height * 3];
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut dst = BlurImageMut::default();
+        pyr_down(
+            &image,
+            &mut dst,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(dst.width, 19);
+        assert_eq!(dst.height, 11);
+        assert!(dst.data.borrow().iter().all(|&x| x == 117));
+        let mut up = BlurImageMut::default();
+        pyr_up(
+            &dst.to_immutable_ref(),
+            &mut up,
+            None,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(up.width, 38);
+        assert_eq!(up.height, 22);
+        assert!(up.data.borrow().iter().all(|&x| x == 117));
+    }
+
+    #[test]
+    fn test_pyr_up_explicit_size() {
+        let width = 19usize;
+        let height = 11usize;
+        let src = make_pattern(width, height, 2);
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels2,
+        );
+        let size = ImageSize::new(37, 21);
+        let mut owned = BlurImageMut::default();
+        pyr_up(
+            &image,
+            &mut owned,
+            Some(size),
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(owned.width, 37);
+        assert_eq!(owned.height, 21);
+        // Borrowed destination gets exactly the same result for the same size
+        let mut target = vec![0u8; size.width * size.height * 2];
+        let mut borrowed = BlurImageMut::borrow(
+            &mut target,
+            size.width as u32,
+            size.height as u32,
+            FastBlurChannels::Channels2,
+        );
+        pyr_up(
+            &image,
+            &mut borrowed,
+            Some(size),
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(owned.data.borrow(), borrowed.data.borrow());
+        // Borrowed destination of odd size requires the explicit size
+        assert!(
+            pyr_up(
+                &image,
+                &mut borrowed,
+                None,
+                EdgeMode::Reflect101.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .is_err()
+        );
+        assert!(
+            pyr_up(
+                &image,
+                &mut BlurImageMut::default(),
+                Some(ImageSize::new(40, 21)),
+                EdgeMode::Reflect101.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_laplacian_reconstruction() {
+        let width = 53usize;
+        let height = 31usize;
+        let src = make_pattern(width, height, 4);
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let pyramid = LaplacianPyramid::new(
+            &image,
+            4,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Adaptive,
+        )
+        .unwrap();
+        assert_eq!(pyramid.levels.len(), 4);
+        assert_eq!(pyramid.residual.width, 4);
+        assert_eq!(pyramid.residual.height, 2);
+        let mut dst = BlurImageMut::<u8>::default();
+        pyramid
+            .reconstruct(&mut dst, ThreadingPolicy::Adaptive)
+            .unwrap();
+        assert_eq!(dst.data.borrow(), src.as_slice());
+    }
+}
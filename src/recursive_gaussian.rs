@@ -0,0 +1,924 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::edge_mode::{reflect_index, reflect_index_101};
+use crate::executor::{ParallelZonedIterator, TbSliceMut, ThreadPool, parallel_range};
+use crate::primitives::PrimitiveCast;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    BlurError, BlurImage, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy,
+};
+#[cfg(feature = "nightly_f16")]
+use core::f16;
+use num_complex::Complex;
+use std::fmt::Debug;
+
+/// Order of the Gaussian derivative computed by [recursive_gaussian] along an axis
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GaussianDerivative {
+    /// Plain Gaussian smoothing
+    #[default]
+    Smooth = 0,
+    /// First derivative of the Gaussian, positive on increasing intensity
+    First = 1,
+    /// Second derivative of the Gaussian
+    Second = 2,
+}
+
+/// Parameters of the recursive Gaussian filter
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct RecursiveGaussianParams {
+    /// X-axis sigma
+    pub x_sigma: f64,
+    /// Y-axis sigma
+    pub y_sigma: f64,
+    /// Derivative order computed along X-axis
+    pub x_derivative: GaussianDerivative,
+    /// Derivative order computed along Y-axis
+    pub y_derivative: GaussianDerivative,
+}
+
+/// Smallest sigma for derivatives, sampled derivatives of a narrower Gaussian are meaningless
+const MIN_DERIVATIVE_SIGMA: f64 = 0.5;
+
+impl RecursiveGaussianParams {
+    /// Sigma must be > 0.
+    pub fn new(sigma: f64) -> RecursiveGaussianParams {
+        Self::new_asymmetric(sigma, sigma)
+    }
+
+    /// Sigmas must be > 0.
+    pub fn new_asymmetric(x_sigma: f64, y_sigma: f64) -> RecursiveGaussianParams {
+        RecursiveGaussianParams {
+            x_sigma,
+            y_sigma,
+            x_derivative: GaussianDerivative::Smooth,
+            y_derivative: GaussianDerivative::Smooth,
+        }
+    }
+
+    /// Sets derivative orders along each axis.
+    /// Sigma of an axis with a derivative must be at least `0.5`.
+    pub fn with_derivatives(
+        self,
+        x_derivative: GaussianDerivative,
+        y_derivative: GaussianDerivative,
+    ) -> RecursiveGaussianParams {
+        RecursiveGaussianParams {
+            x_derivative,
+            y_derivative,
+            ..self
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        for (name, sigma, derivative) in [
+            ("x_sigma", self.x_sigma, self.x_derivative),
+            ("y_sigma", self.y_sigma, self.y_derivative),
+        ] {
+            if !sigma.is_finite() {
+                return Err(BlurError::InvalidParameter { name, value: sigma });
+            }
+            if sigma <= 0. {
+                return Err(BlurError::NegativeOrZeroSigma);
+            }
+            if derivative != GaussianDerivative::Smooth && sigma < MIN_DERIVATIVE_SIGMA {
+                return Err(BlurError::InvalidParameter { name, value: sigma });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Three damped oscillations `(a, b, w, l)` approximating the right half of the target
+/// as `sum((a * cos(w * t) + b * sin(w * t)) * exp(-l * t))` with `t = x / sigma`.
+///
+/// Fitted by least squares on `t` in `0..16` with zeroth, first and second moments
+/// constrained to the ones of the target, maximum absolute errors are about
+/// `1e-5`, `1e-4` and `5e-4` against unit peak targets.
+const SMOOTH_TERMS: [[f64; 4]; 3] = [
+    [
+        2.444038405855468,
+        3.5987408369143434,
+        0.5071283858527358,
+        1.899294608354907,
+    ],
+    [
+        -1.4922568323971528,
+        0.2647625036297747,
+        1.563446984907269,
+        1.8764496184289137,
+    ],
+    [
+        0.04822837138045581,
+        -0.11146568538945814,
+        2.7797154678340243,
+        1.82041386698219,
+    ],
+];
+
+/// Fit of `-t * exp(-t^2 / 2)`, see [SMOOTH_TERMS].
+const FIRST_DERIVATIVE_TERMS: [[f64; 4]; 3] = [
+    [
+        -1.3268703712899825,
+        -14.99819320793587,
+        0.5966081393928423,
+        2.0797732997834255,
+    ],
+    [
+        1.5884983419963927,
+        5.774753069084383,
+        1.7939160143175488,
+        2.200855083847162,
+    ],
+    [
+        -0.26162797054128606,
+        -0.8096187053024548,
+        2.818201332479301,
+        2.302281633599891,
+    ],
+];
+
+/// Fit of `(t^2 - 1) * exp(-t^2 / 2)`, see [SMOOTH_TERMS].
+const SECOND_DERIVATIVE_TERMS: [[f64; 4]; 3] = [
+    [
+        0.6961560044644003,
+        0.05456480415400154,
+        2.7924198628636043,
+        1.5031494224151147,
+    ],
+    [
+        0.9484279549974973,
+        4.207079404517252,
+        0.5032198127044876,
+        1.4072989295405889,
+    ],
+    [
+        -2.6441146121644503,
+        -2.427795610704229,
+        1.5668312814470984,
+        1.4733779920244925,
+    ],
+];
+
+type FilterState = [Complex<f64>; 3];
+
+const ZERO_STATE: FilterState = [Complex::new(0., 0.); 3];
+
+/// Sixth-order recursive filter in parallel form, each conjugate pole pair
+/// runs as a single complex first-order recursion, what keeps it stable at any sigma.
+///
+/// Causal part covers kernel taps `0..`, anticausal part covers taps `..0`.
+#[derive(Copy, Clone, Debug)]
+struct RecursiveFilter {
+    poles: [Complex<f64>; 3],
+    causal: [Complex<f64>; 3],
+    anticausal: [Complex<f64>; 3],
+    direct: f64,
+    /// Count of samples after what impulse response drops below [SETTLE_TOLERANCE]
+    settle: usize,
+}
+
+const SETTLE_TOLERANCE: f64 = 1e-9;
+
+impl RecursiveFilter {
+    fn new(sigma: f64, derivative: GaussianDerivative) -> RecursiveFilter {
+        let terms = match derivative {
+            GaussianDerivative::Smooth => &SMOOTH_TERMS,
+            GaussianDerivative::First => &FIRST_DERIVATIVE_TERMS,
+            GaussianDerivative::Second => &SECOND_DERIVATIVE_TERMS,
+        };
+        let weights = terms.map(|[a, b, _, _]| Complex::new(a, -b));
+        let poles = terms.map(|[_, _, w, l]| (Complex::new(-l, w) / sigma).exp());
+
+        // Sums over taps `m >= 1` of h(m), m * h(m) and m^2 * h(m) in closed form,
+        // they are used to normalize sampled kernel exactly.
+        let sum = |f: &dyn Fn(Complex<f64>) -> Complex<f64>| -> f64 {
+            weights
+                .iter()
+                .zip(poles.iter())
+                .map(|(&c, &r)| (c * f(r)).re)
+                .sum()
+        };
+        let center = sum(&|_| Complex::new(1., 0.));
+        let tail = sum(&|r| r / (1. - r));
+        let first_moment = sum(&|r| r / ((1. - r) * (1. - r)));
+        let second_moment = sum(&|r| r * (1. + r) / ((1. - r) * (1. - r) * (1. - r)));
+
+        // Kernel is `h(m)` on the right and `sign * h(-m)` on the left, center tap is `h(0) + direct`.
+        let (sign, direct, scale) = match derivative {
+            // Sum of taps is 1
+            GaussianDerivative::Smooth => (1., 0., 1. / (center + 2. * tail)),
+            // Odd kernel with zero center, response to `x` is 1
+            GaussianDerivative::First => (-1., -center, -1. / (2. * first_moment)),
+            // Sum of taps is 0, response to `x^2 / 2` is 1
+            GaussianDerivative::Second => (1., -(center + 2. * tail), 1. / second_moment),
+        };
+
+        let slowest = poles.iter().map(|r| r.norm()).fold(0., f64::max);
+        let settle = if slowest > 0. {
+            (SETTLE_TOLERANCE.ln() / slowest.ln())
+                .ceil()
+                .min(usize::MAX as f64) as usize
+        } else {
+            1
+        };
+
+        RecursiveFilter {
+            poles,
+            causal: weights.map(|c| c * scale),
+            anticausal: weights.map(|c| c * (scale * sign)),
+            direct: direct * scale,
+            settle,
+        }
+    }
+
+    /// Advances accumulator `state = x + pole * state` without producing output
+    #[inline(always)]
+    fn push(&self, state: &mut FilterState, x: f64) {
+        for (s, &r) in state.iter_mut().zip(self.poles.iter()) {
+            *s = r * *s + x;
+        }
+    }
+
+    /// Causal output at `n` from the state at `n - 1` and input `x(n)`
+    #[inline(always)]
+    fn causal_step(&self, state: &mut FilterState, x: f64) -> f64 {
+        self.push(state, x);
+        let mut acc = self.direct * x;
+        for (s, c) in state.iter().zip(self.causal.iter()) {
+            acc += (c * s).re;
+        }
+        acc
+    }
+
+    /// Anticausal output of the state at `n`
+    #[inline(always)]
+    fn anticausal_output(&self, state: &FilterState) -> f64 {
+        state
+            .iter()
+            .zip(self.anticausal.iter())
+            .map(|(s, c)| (c * s).re)
+            .sum()
+    }
+
+    /// Anticausal output at `n` from the state at `n + 1` and input `x(n + 1)`
+    #[inline(always)]
+    fn anticausal_step(&self, state: &mut FilterState, x_next: f64) -> f64 {
+        for (s, &r) in state.iter_mut().zip(self.poles.iter()) {
+            *s = r * (*s + x_next);
+        }
+        self.anticausal_output(state)
+    }
+
+    /// Causal state at `-1` for the signal equal to `v` on the left
+    fn steady_causal(&self, v: f64) -> FilterState {
+        self.poles.map(|r| v / (1. - r))
+    }
+
+    /// Anticausal state at `n - 1` for the signal equal to `v` on the right
+    fn steady_anticausal(&self, v: f64) -> FilterState {
+        self.poles.map(|r| r * v / (1. - r))
+    }
+
+    /// Turns accumulator pushed with `count` samples of the periodic signal on the left
+    /// into the causal state at `-1`, full period is summed up to infinity
+    fn periodic_causal(
+        &self,
+        accumulated: FilterState,
+        period: usize,
+        count: usize,
+    ) -> FilterState {
+        let mut state = accumulated;
+        if count == period {
+            for (s, &r) in state.iter_mut().zip(self.poles.iter()) {
+                *s /= 1. - r.powf(period as f64);
+            }
+        }
+        state
+    }
+
+    /// Turns accumulator pushed in reverse with `count` samples of the periodic signal on the right
+    /// into the anticausal state at `n - 1`, full period is summed up to infinity
+    fn periodic_anticausal(
+        &self,
+        accumulated: FilterState,
+        period: usize,
+        count: usize,
+    ) -> FilterState {
+        let mut state = accumulated;
+        for (s, &r) in state.iter_mut().zip(self.poles.iter()) {
+            *s *= r;
+            if count == period {
+                *s /= 1. - r.powf(period as f64);
+            }
+        }
+        state
+    }
+}
+
+/// Period of the signal extended by periodic edge mode, `None` for steady edges
+fn edge_period(edge_mode: EdgeMode, len: usize) -> Option<usize> {
+    match edge_mode {
+        EdgeMode::Clamp | EdgeMode::Constant => None,
+        EdgeMode::Wrap => Some(len),
+        EdgeMode::Reflect => Some(2 * len),
+        EdgeMode::Reflect101 => Some((2 * len).saturating_sub(2).max(1)),
+    }
+}
+
+/// Maps any coordinate of periodic extension into `0..len`
+#[inline(always)]
+fn periodic_index(edge_mode: EdgeMode, i: isize, len: usize) -> usize {
+    let len = len as isize;
+    match edge_mode {
+        EdgeMode::Wrap => i.rem_euclid(len) as usize,
+        EdgeMode::Reflect => {
+            let j = i.rem_euclid(2 * len);
+            if j < len {
+                j as usize
+            } else {
+                reflect_index(j, len)
+            }
+        }
+        _ => {
+            if len == 1 {
+                return 0;
+            }
+            let j = i.rem_euclid(2 * len - 2);
+            if j < len {
+                j as usize
+            } else {
+                reflect_index_101(j, len)
+            }
+        }
+    }
+}
+
+/// Computes the state before the first sample of the line, `fetch` reads the line at index
+#[inline(always)]
+fn causal_border(
+    filter: &RecursiveFilter,
+    edge_mode: EdgeMode,
+    len: usize,
+    constant: f64,
+    fetch: impl Fn(usize) -> f64,
+) -> FilterState {
+    match edge_period(edge_mode, len) {
+        None if edge_mode == EdgeMode::Constant => filter.steady_causal(constant),
+        None => filter.steady_causal(fetch(0)),
+        Some(period) => {
+            // Far periods are not noticeable when response settles faster
+            let count = period.min(filter.settle);
+            let mut state = ZERO_STATE;
+            for i in -(count as isize)..0 {
+                filter.push(&mut state, fetch(periodic_index(edge_mode, i, len)));
+            }
+            filter.periodic_causal(state, period, count)
+        }
+    }
+}
+
+/// Computes the anticausal state at the last sample of the line
+#[inline(always)]
+fn anticausal_border(
+    filter: &RecursiveFilter,
+    edge_mode: EdgeMode,
+    len: usize,
+    constant: f64,
+    fetch: impl Fn(usize) -> f64,
+) -> FilterState {
+    match edge_period(edge_mode, len) {
+        None if edge_mode == EdgeMode::Constant => filter.steady_anticausal(constant),
+        None => filter.steady_anticausal(fetch(len - 1)),
+        Some(period) => {
+            let count = period.min(filter.settle);
+            let mut state = ZERO_STATE;
+            for i in (len as isize..(len + count) as isize).rev() {
+                filter.push(&mut state, fetch(periodic_index(edge_mode, i, len)));
+            }
+            filter.periodic_anticausal(state, period, count)
+        }
+    }
+}
+
+fn horizontal_pass<T: Copy + PrimitiveCast<f64>, const CN: usize>(
+    src: &[T],
+    dst: &mut [f32],
+    width: usize,
+    filter: &RecursiveFilter,
+    edge_mode: EdgeMode,
+    constant: &[f64; 4],
+) {
+    let fetch = |x: usize, c: usize| -> f64 { src[x * CN + c].cast_() };
+    let mut states = [ZERO_STATE; CN];
+    for (c, state) in states.iter_mut().enumerate() {
+        *state = causal_border(filter, edge_mode, width, constant[c], |x| fetch(x, c));
+    }
+    for (x, dst) in dst.chunks_exact_mut(CN).take(width).enumerate() {
+        for (c, (dst, state)) in dst.iter_mut().zip(states.iter_mut()).enumerate() {
+            *dst = filter.causal_step(state, fetch(x, c)) as f32;
+        }
+    }
+    for (c, state) in states.iter_mut().enumerate() {
+        *state = anticausal_border(filter, edge_mode, width, constant[c], |x| fetch(x, c));
+        dst[(width - 1) * CN + c] += filter.anticausal_output(state) as f32;
+    }
+    for x in (0..width - 1).rev() {
+        for (c, state) in states.iter_mut().enumerate() {
+            dst[x * CN + c] += filter.anticausal_step(state, fetch(x + 1, c)) as f32;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn vertical_pass<T: Copy + Send + Sync + 'static>(
+    src: &[f32],
+    src_stride: usize,
+    dst: &UnsafeSlice<T>,
+    dst_stride: usize,
+    lanes: std::ops::Range<usize>,
+    height: usize,
+    cn: usize,
+    filter: &RecursiveFilter,
+    edge_mode: EdgeMode,
+    constant: &[f64; 4],
+) where
+    f64: ToStorage<T>,
+{
+    let band = lanes.len();
+    let fetch = |y: usize, lane: usize| -> f64 { src[y * src_stride + lane] as f64 };
+    let mut states = vec![ZERO_STATE; band];
+    let mut causal = vec![0f32; band * height];
+
+    for (state, lane) in states.iter_mut().zip(lanes.clone()) {
+        *state = causal_border(filter, edge_mode, height, constant[lane % cn], |y| {
+            fetch(y, lane)
+        });
+    }
+    for (y, causal) in causal.chunks_exact_mut(band).enumerate() {
+        for ((dst, state), lane) in causal.iter_mut().zip(states.iter_mut()).zip(lanes.clone()) {
+            *dst = filter.causal_step(state, fetch(y, lane)) as f32;
+        }
+    }
+
+    for ((state, lane), causal) in states
+        .iter_mut()
+        .zip(lanes.clone())
+        .zip(causal[(height - 1) * band..].iter())
+    {
+        *state = anticausal_border(filter, edge_mode, height, constant[lane % cn], |y| {
+            fetch(y, lane)
+        });
+        let v = *causal as f64 + filter.anticausal_output(state);
+        unsafe {
+            dst.write((height - 1) * dst_stride + lane, v.to_());
+        }
+    }
+    for y in (0..height - 1).rev() {
+        for ((state, lane), causal) in states
+            .iter_mut()
+            .zip(lanes.clone())
+            .zip(causal[y * band..(y + 1) * band].iter())
+        {
+            let v = *causal as f64 + filter.anticausal_step(state, fetch(y + 1, lane));
+            unsafe {
+                dst.write(y * dst_stride + lane, v.to_());
+            }
+        }
+    }
+}
+
+fn recursive_gaussian_impl<T: Copy + Default + Debug + Send + Sync + PrimitiveCast<f64> + 'static>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: RecursiveGaussianParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    f64: ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout(Some(src))?;
+    src.size_matches_mut(dst)?;
+    params.validate()?;
+
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let cn = src.channels.channels();
+    let row_len = width * cn;
+    let constant = [
+        border_constant[0],
+        border_constant[1],
+        border_constant[2],
+        border_constant[3],
+    ];
+
+    let horizontal = RecursiveFilter::new(params.x_sigma, params.x_derivative);
+    let vertical = RecursiveFilter::new(params.y_sigma, params.y_derivative);
+
+    let pool = ThreadPool::new(threading_policy.thread_count(src.width, src.height));
+
+    let mut transient = vec![0f32; row_len * height];
+    let src_stride = src.row_stride() as usize;
+    let src_data = src.data.as_ref();
+    let _dispatcher = match src.channels {
+        FastBlurChannels::Plane => horizontal_pass::<T, 1>,
+        FastBlurChannels::Channels2 => horizontal_pass::<T, 2>,
+        FastBlurChannels::Channels3 => horizontal_pass::<T, 3>,
+        FastBlurChannels::Channels4 => horizontal_pass::<T, 4>,
+    };
+    transient
+        .tb_par_chunks_exact_mut(row_len)
+        .for_each_enumerated(&pool, |y, row| {
+            let src_row = &src_data[y * src_stride..y * src_stride + row_len];
+            _dispatcher(
+                src_row,
+                row,
+                width,
+                &horizontal,
+                edge_modes.horizontal,
+                &constant,
+            );
+        });
+
+    // Constant rows outside of the image are already filtered horizontally
+    let vertical_constant = match params.x_derivative {
+        GaussianDerivative::Smooth => constant,
+        _ => [0.; 4],
+    };
+    let dst_stride = dst.row_stride() as usize;
+    let dst_data = UnsafeSlice::new(dst.data.borrow_mut());
+    let band = row_len.div_ceil(pool.thread_count());
+    parallel_range(&pool, row_len.div_ceil(band), |i| {
+        let lanes = i * band..((i + 1) * band).min(row_len);
+        vertical_pass(
+            &transient,
+            row_len,
+            &dst_data,
+            dst_stride,
+            lanes,
+            height,
+            cn,
+            &vertical,
+            edge_modes.vertical,
+            &vertical_constant,
+        );
+    });
+
+    Ok(())
+}
+
+/// Performs Gaussian blur or computes Gaussian derivatives with recursive filter.
+///
+/// Sixth-order recursive approximation of the Gaussian with an arbitrary real sigma,
+/// cost per pixel does not depend on sigma, there is no radius limit.
+/// Result stays within about `1e-5` of the exact sampled Gaussian, so it may replace
+/// [crate::gaussian_blur] with a large sigma.
+/// Derivatives are normalized to respond with `1` to a unit slope for the first order and
+/// to a unit curvature for the second one, negative values are saturated to zero in integral images,
+/// use [recursive_gaussian_f32] to receive signed derivatives.
+///
+/// Borders are initialized as if the line continues infinitely: [EdgeMode::Clamp] and [EdgeMode::Constant]
+/// repeat the edge or the constant, [EdgeMode::Wrap], [EdgeMode::Reflect] and [EdgeMode::Reflect101]
+/// continue the line periodically.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `params`: See [RecursiveGaussianParams] for more info.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: Threads usage policy, see [ThreadingPolicy] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or sigma is invalid.
+pub fn recursive_gaussian(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: RecursiveGaussianParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    recursive_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs Gaussian blur or computes Gaussian derivatives with recursive filter on the up to 16-bit image.
+///
+/// See [recursive_gaussian] for more info.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `params`: See [RecursiveGaussianParams] for more info.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: Threads usage policy, see [ThreadingPolicy] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or sigma is invalid.
+pub fn recursive_gaussian_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: RecursiveGaussianParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    recursive_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs Gaussian blur or computes Gaussian derivatives with recursive filter on the f32 image.
+///
+/// See [recursive_gaussian] for more info, derivatives keep their sign.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `params`: See [RecursiveGaussianParams] for more info.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: Threads usage policy, see [ThreadingPolicy] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or sigma is invalid.
+pub fn recursive_gaussian_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: RecursiveGaussianParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    recursive_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+/// Performs Gaussian blur or computes Gaussian derivatives with recursive filter on the f16 image.
+///
+/// See [recursive_gaussian] for more info, derivatives keep their sign.
+///
+/// # Arguments
+///
+/// * `src`: Source image.
+/// * `dst`: Destination image.
+/// * `params`: See [RecursiveGaussianParams] for more info.
+/// * `edge_modes`: Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant`: If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy`: Threads usage policy, see [ThreadingPolicy] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided
+/// or sigma is invalid.
+#[cfg(feature = "nightly_f16")]
+pub fn recursive_gaussian_f16(
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
+    params: RecursiveGaussianParams,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    recursive_gaussian_impl(
+        src,
+        dst,
+        params,
+        edge_modes,
+        border_constant,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Separable convolution with sampled Gaussian over explicitly extended borders
+    fn reference_gaussian(
+        src: &[f32],
+        width: usize,
+        height: usize,
+        cn: usize,
+        sigma: f64,
+        edge_mode: EdgeMode,
+        constant: f64,
+    ) -> Vec<f64> {
+        let radius = (sigma * 8.).ceil() as isize;
+        let weights = (-radius..=radius)
+            .map(|x| (-(x * x) as f64 / (2. * sigma * sigma)).exp())
+            .collect::<Vec<_>>();
+        let norm = weights.iter().sum::<f64>();
+        let convolve = |len: usize, fetch: &dyn Fn(usize) -> f64, i: usize| -> f64 {
+            let mut acc = 0.;
+            for (k, w) in (-radius..=radius).zip(weights.iter()) {
+                let j = i as isize + k;
+                let v = match edge_mode {
+                    EdgeMode::Constant if j < 0 || j >= len as isize => constant,
+                    EdgeMode::Clamp | EdgeMode::Constant => {
+                        fetch(j.clamp(0, len as isize - 1) as usize)
+                    }
+                    _ => fetch(periodic_index(edge_mode, j, len)),
+                };
+                acc += v * w;
+            }
+            acc / norm
+        };
+        let row_len = width * cn;
+        let mut horizontal = vec![0f64; row_len * height];
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..cn {
+                    horizontal[y * row_len + x * cn + c] =
+                        convolve(width, &|x| src[y * row_len + x * cn + c] as f64, x);
+                }
+            }
+        }
+        let mut dst = vec![0f64; row_len * height];
+        for y in 0..height {
+            for lane in 0..row_len {
+                dst[y * row_len + lane] = convolve(height, &|y| horizontal[y * row_len + lane], y);
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn test_recursive_gaussian_matches_exact_gaussian() {
+        let width = 71usize;
+        let height = 53usize;
+        let mut src = vec![0f32; width * height * 3];
+        for (i, v) in src.iter_mut().enumerate() {
+            *v = ((i * 7919 + (i / 5) * 104729) % 256) as f32 / 255.;
+        }
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for (sigma, edge_mode) in [0.5f64, 1., 2.5, 50.].into_iter().flat_map(|sigma| {
+            [
+                EdgeMode::Clamp,
+                EdgeMode::Wrap,
+                EdgeMode::Reflect,
+                EdgeMode::Reflect101,
+                EdgeMode::Constant,
+            ]
+            .map(|edge_mode| (sigma, edge_mode))
+        }) {
+            let expected = reference_gaussian(&src, width, height, 3, sigma, edge_mode, 0.75);
+            let mut dst = BlurImageMut::default();
+            recursive_gaussian_f32(
+                &image,
+                &mut dst,
+                RecursiveGaussianParams::new(sigma),
+                edge_mode.as_2d(),
+                Scalar::dup(0.75),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            for (i, (&a, &b)) in dst.data.borrow().iter().zip(expected.iter()).enumerate() {
+                assert!(
+                    (a as f64 - b).abs() < 2e-5,
+                    "{edge_mode:?} sigma {sigma} at {i}: recursive {a}, exact {b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_recursive_gaussian_derivatives() {
+        let width = 96usize;
+        let height = 96usize;
+        let mut src = vec![0f32; width * height];
+        for (y, row) in src.chunks_exact_mut(width).enumerate() {
+            for (x, v) in row.iter_mut().enumerate() {
+                *v = 0.5 * x as f32 + 0.25 * (y as f32 - 48.) * (y as f32 - 48.);
+            }
+        }
+        let image = BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        for (x_derivative, y_derivative, expected) in [
+            (
+                GaussianDerivative::First,
+                GaussianDerivative::Smooth,
+                0.5f32,
+            ),
+            (
+                GaussianDerivative::Smooth,
+                GaussianDerivative::Second,
+                0.5f32,
+            ),
+            (GaussianDerivative::Second, GaussianDerivative::Smooth, 0f32),
+        ] {
+            let mut dst = BlurImageMut::default();
+            recursive_gaussian_f32(
+                &image,
+                &mut dst,
+                RecursiveGaussianParams::new(3.).with_derivatives(x_derivative, y_derivative),
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let dst = dst.data.borrow();
+            for y in 30..66 {
+                for x in 30..66 {
+                    let v = dst[y * width + x];
+                    assert!(
+                        (v - expected).abs() < 2e-3,
+                        "{x_derivative:?} {y_derivative:?} at ({x}, {y}): {v}, expected {expected}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_recursive_gaussian_large_sigma() {
+        let src = vec![117u8; 37 * 29 * 4];
+        let image = BlurImage::borrow(&src, 37, 29, FastBlurChannels::Channels4);
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::Reflect, EdgeMode::Wrap] {
+            let mut dst = BlurImageMut::default();
+            recursive_gaussian(
+                &image,
+                &mut dst,
+                RecursiveGaussianParams::new(2500.),
+                edge_mode.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Adaptive,
+            )
+            .unwrap();
+            assert!(dst.data.borrow().iter().all(|&v| v == 117), "{edge_mode:?}");
+        }
+        let mut dst = BlurImageMut::default();
+        assert!(matches!(
+            recursive_gaussian(
+                &image,
+                &mut dst,
+                RecursiveGaussianParams::new(0.),
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                ThreadingPolicy::Single,
+            ),
+            Err(BlurError::NegativeOrZeroSigma)
+        ));
+    }
+}
@@ -0,0 +1,449 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::filter1d::copy_roi;
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImageMut, BoxBlurParameters, ConvolutionMode,
+    EdgeMode2D, GaussianBlurParams, Scalar, ThreadingPolicy, box_blur, fast_gaussian,
+    gaussian_blur, stack_blur,
+};
+use std::fmt::Debug;
+
+/// Region of interest rectangle
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub struct Roi {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Roi {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Roi {
+        Roi {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Checks that ROI is not empty and lies inside image bounds
+    pub fn check_bounds(&self, width: u32, height: u32) -> Result<(), BlurError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BlurError::ZeroBaseSize);
+        }
+        let fits_x = self
+            .x
+            .checked_add(self.width)
+            .map(|x| x <= width)
+            .unwrap_or(false);
+        let fits_y = self
+            .y
+            .checked_add(self.height)
+            .map(|y| y <= height)
+            .unwrap_or(false);
+        if !fits_x || !fits_y {
+            return Err(BlurError::RoiOutOfBounds);
+        }
+        Ok(())
+    }
+
+    /// Expands ROI by margins and clips it to the image bounds
+    fn expand(&self, margin: AnisotropicRadius, width: u32, height: u32) -> Roi {
+        let x0 = self.x.saturating_sub(margin.x_axis);
+        let y0 = self.y.saturating_sub(margin.y_axis);
+        let x1 = (self.x + self.width)
+            .saturating_add(margin.x_axis)
+            .min(width);
+        let y1 = (self.y + self.height)
+            .saturating_add(margin.y_axis)
+            .min(height);
+        Roi::new(x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+/// Applies blur only inside the region of interest.
+///
+/// ROI is expanded by `margin` and this context is cropped from the image, so pixels around
+/// the ROI are read as real content and edge handling happens only on real image borders.
+/// Blur is performed on the crop, then only pixels inside the ROI are written back.
+/// Margin should be at least a radius of the blur, otherwise result inside the ROI
+/// will differ from blurring the whole image.
+///
+/// # Arguments
+///
+/// * `image`: Image to blur in place.
+/// * `roi`: Region to blur, see [Roi] for more info.
+/// * `margin`: Context to read around ROI.
+/// * `blur`: Blur that will be performed in place on the cropped context.
+///
+/// returns: ()
+///
+pub fn blur_roi<T, F>(
+    image: &mut BlurImageMut<T>,
+    roi: Roi,
+    margin: AnisotropicRadius,
+    blur: F,
+) -> Result<(), BlurError>
+where
+    T: Copy + Clone + Default + Debug,
+    F: FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+{
+    image.check_layout(None)?;
+    roi.check_bounds(image.width, image.height)?;
+    let context = roi.expand(margin, image.width, image.height);
+    let cn = image.channels.channels();
+    let image_stride = image.row_stride() as usize;
+
+    let mut crop = BlurImageMut::alloc(context.width, context.height, image.channels);
+    let crop_stride = crop.row_stride() as usize;
+    let context_offset = context.y as usize * image_stride + context.x as usize * cn;
+    copy_roi(
+        crop.data.borrow_mut(),
+        &image.data.borrow()[context_offset..],
+        crop_stride,
+        image_stride,
+        context.width as usize * cn,
+    );
+
+    blur(&mut crop)?;
+    if crop.width != context.width
+        || crop.height != context.height
+        || crop.channels != image.channels
+    {
+        return Err(BlurError::ImagesMustMatch);
+    }
+
+    let crop_stride = crop.row_stride() as usize;
+    let crop_offset =
+        (roi.y - context.y) as usize * crop_stride + (roi.x - context.x) as usize * cn;
+    let roi_offset = roi.y as usize * image_stride + roi.x as usize * cn;
+    let row_size = roi.width as usize * cn;
+    let crop_end = crop_offset + (roi.height as usize - 1) * crop_stride + row_size;
+    copy_roi(
+        &mut image.data.borrow_mut()[roi_offset..],
+        &crop.data.borrow()[crop_offset..crop_end],
+        image_stride,
+        crop_stride,
+        row_size,
+    );
+    Ok(())
+}
+
+/// Performs gaussian blur only inside the region of interest.
+///
+/// See [crate::gaussian_blur] and [blur_roi] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place.
+/// * `roi` - Region to blur, see [Roi] for more info.
+/// * `params` - See [GaussianBlurParams] for more info.
+/// * `edge_modes` - Rule to handle edge mode, only applied on real image borders, see [EdgeMode] for more info.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `hint` - see [ConvolutionMode] for more info.
+pub fn gaussian_blur_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Roi,
+    params: GaussianBlurParams,
+    edge_modes: EdgeMode2D,
+    threading_policy: ThreadingPolicy,
+    hint: ConvolutionMode,
+) -> Result<(), BlurError> {
+    let margin = AnisotropicRadius::create(
+        params.x_kernel.max(1) / 2 + 1,
+        params.y_kernel.max(1) / 2 + 1,
+    );
+    let margin = if params.x_kernel == 0 || params.y_kernel == 0 {
+        let automatic = GaussianBlurParams::new_asymmetric_from_sigma(
+            params.x_sigma.max(f64::EPSILON),
+            params.y_sigma.max(f64::EPSILON),
+        );
+        AnisotropicRadius::create(
+            margin.x_axis.max(automatic.x_kernel / 2 + 1),
+            margin.y_axis.max(automatic.y_kernel / 2 + 1),
+        )
+    } else {
+        margin
+    };
+    blur_roi(image, roi, margin, |crop| {
+        let mut dst = BlurImageMut::default();
+        gaussian_blur(
+            &crop.to_immutable_ref(),
+            &mut dst,
+            params,
+            edge_modes,
+            threading_policy,
+            hint,
+            AlphaMode::Premultiplied,
+        )?;
+        *crop = dst;
+        Ok(())
+    })
+}
+
+/// Performs stack blur only inside the region of interest.
+///
+/// See [crate::stack_blur] and [blur_roi] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place.
+/// * `roi` - Region to blur, see [Roi] for more info.
+/// * `radius` - Radius of stack blur.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_modes` - Rule to handle edge mode, only applied on real image borders, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+pub fn stack_blur_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Roi,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+) -> Result<(), BlurError> {
+    let margin = AnisotropicRadius::create(radius.x_axis + 1, radius.y_axis + 1);
+    blur_roi(image, roi, margin, |crop| {
+        stack_blur(
+            crop,
+            radius,
+            threading_policy,
+            edge_modes,
+            border_constant,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+/// Performs fast gaussian blur only inside the region of interest.
+///
+/// See [crate::fast_gaussian] and [blur_roi] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place.
+/// * `roi` - Region to blur, see [Roi] for more info.
+/// * `radius` - Radius of fast gaussian.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+/// * `edge_modes` - Rule to handle edge mode, only applied on real image borders, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+pub fn fast_gaussian_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Roi,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+) -> Result<(), BlurError> {
+    let margin = AnisotropicRadius::create(radius.x_axis * 2 + 1, radius.y_axis * 2 + 1);
+    blur_roi(image, roi, margin, |crop| {
+        fast_gaussian(
+            crop,
+            radius,
+            threading_policy,
+            edge_modes,
+            border_constant,
+            AlphaMode::Premultiplied,
+        )
+    })
+}
+
+/// Performs box blur only inside the region of interest.
+///
+/// See [crate::box_blur] and [blur_roi] for more info.
+///
+/// # Arguments
+///
+/// * `image` - Image to blur in place.
+/// * `roi` - Region to blur, see [Roi] for more info.
+/// * `parameters` - See [BoxBlurParameters] for more info.
+/// * `edge_modes` - Rule to handle edge mode, only applied on real image borders, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `threading_policy` - Threading policy according to [ThreadingPolicy].
+pub fn box_blur_roi(
+    image: &mut BlurImageMut<u8>,
+    roi: Roi,
+    parameters: BoxBlurParameters,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    let margin = AnisotropicRadius::create(
+        parameters.x_axis_kernel / 2 + 1,
+        parameters.y_axis_kernel / 2 + 1,
+    );
+    blur_roi(image, roi, margin, |crop| {
+        let mut dst = BlurImageMut::default();
+        box_blur(
+            &crop.to_immutable_ref(),
+            &mut dst,
+            parameters,
+            edge_modes,
+            border_constant,
+            threading_policy,
+            AlphaMode::Premultiplied,
+        )?;
+        *crop = dst;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdgeMode, FastBlurChannels};
+
+    fn make_pattern(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height * 3)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_gaussian_roi_matches_full() {
+        let width: usize = 96;
+        let height: usize = 80;
+        let src = make_pattern(width, height);
+        let params = GaussianBlurParams::new_from_kernel(9.);
+        let mut full_src = src.clone();
+        let full = BlurImageMut::borrow(
+            &mut full_src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let mut full_dst = BlurImageMut::default();
+        gaussian_blur(
+            &full.to_immutable_ref(),
+            &mut full_dst,
+            params,
+            EdgeMode::Clamp.as_2d(),
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+
+        let mut roi_src = src.clone();
+        let mut image = BlurImageMut::borrow(
+            &mut roi_src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let roi = Roi::new(20, 10, 30, 25);
+        gaussian_blur_roi(
+            &mut image,
+            roi,
+            params,
+            EdgeMode::Clamp.as_2d(),
+            ThreadingPolicy::Single,
+            ConvolutionMode::Exact,
+        )
+        .unwrap();
+        let full_dst = full_dst.data.borrow();
+        for y in 0..height {
+            for x in 0..width * 3 {
+                let inside = y >= roi.y as usize
+                    && y < (roi.y + roi.height) as usize
+                    && x / 3 >= roi.x as usize
+                    && x / 3 < (roi.x + roi.width) as usize;
+                let expected = if inside {
+                    full_dst[y * width * 3 + x]
+                } else {
+                    src[y * width * 3 + x]
+                };
+                assert_eq!(roi_src[y * width * 3 + x], expected, "At {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_blur_roi_matches_full() {
+        let width: usize = 64;
+        let height: usize = 64;
+        let src = make_pattern(width, height);
+        let radius = AnisotropicRadius::new(5);
+        let mut full = src.clone();
+        let mut full_image = BlurImageMut::borrow(
+            &mut full,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        stack_blur(
+            &mut full_image,
+            radius,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+
+        let mut roi_src = src.clone();
+        let mut image = BlurImageMut::borrow(
+            &mut roi_src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let roi = Roi::new(0, 30, 64, 20);
+        stack_blur_roi(
+            &mut image,
+            roi,
+            radius,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            &roi_src[30 * width * 3..50 * width * 3],
+            &full[30 * width * 3..50 * width * 3]
+        );
+        assert_eq!(&roi_src[..30 * width * 3], &src[..30 * width * 3]);
+    }
+
+    #[test]
+    fn test_roi_out_of_bounds() {
+        let mut data = vec![0u8; 16 * 16];
+        let mut image = BlurImageMut::borrow(&mut data, 16, 16, FastBlurChannels::Plane);
+        let result = box_blur_roi(
+            &mut image,
+            Roi::new(10, 10, 7, 2),
+            BoxBlurParameters::new(3),
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        );
+        assert!(matches!(result, Err(BlurError::RoiOutOfBounds)));
+    }
+}
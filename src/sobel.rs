@@ -33,6 +33,10 @@ use crate::{
 
 /// Performs sobel operator on the image
 ///
+/// Computes horizontal derivative clamped into `u8`.
+/// For arbitrary derivative orders, apertures and signed output see [crate::derivative_i16],
+/// [crate::derivative_f32] and [crate::gradient_magnitude].
+///
 /// # Arguments
 ///
 /// * `image`: Source image.
@@ -57,6 +61,7 @@ pub fn sobel(
     let sobel_vertical: [i16; 3] = [1, 2, 1];
     let _dispatcher = match image.channels {
         FastBlurChannels::Plane => filter_1d_exact::<u8, i16, 1>,
+        FastBlurChannels::Channels2 => filter_1d_exact::<u8, i16, 2>,
         FastBlurChannels::Channels3 => filter_1d_exact::<u8, i16, 3>,
         FastBlurChannels::Channels4 => filter_1d_exact::<u8, i16, 4>,
     };
@@ -70,3 +75,46 @@ pub fn sobel(
         threading_policy,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeMode;
+
+    #[test]
+    fn test_sobel_plane_matches_rgb() {
+        let width = 53u32;
+        let height = 11u32;
+        let plane = (0..width as usize * height as usize)
+            .map(|x| ((x * 7919) % 251) as u8)
+            .collect::<Vec<_>>();
+        let rgb = plane.iter().flat_map(|&x| [x, x, x]).collect::<Vec<_>>();
+        let mut plane_dst = BlurImageMut::default();
+        sobel(
+            &BlurImage::borrow(&plane, width, height, FastBlurChannels::Plane),
+            &mut plane_dst,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let mut rgb_dst = BlurImageMut::default();
+        sobel(
+            &BlurImage::borrow(&rgb, width, height, FastBlurChannels::Channels3),
+            &mut rgb_dst,
+            EdgeMode::Reflect101.as_2d(),
+            Scalar::default(),
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        for (i, (&a, b)) in plane_dst
+            .data
+            .borrow()
+            .iter()
+            .zip(rgb_dst.data.borrow().chunks_exact(3))
+            .enumerate()
+        {
+            assert_eq!(a, b[0], "Mismatch at {i}");
+        }
+    }
+}
@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::store_u8_u32;
 use crate::sse::utils::load_u8_s32_fast;
 use crate::unsafe_slice::UnsafeSlice;
@@ -85,7 +86,9 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -138,8 +141,8 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
                     store_u8_u32::<CN>(bytes.get_ptr(current_y2 + current_px), prepared_px2);
                     store_u8_u32::<CN>(bytes.get_ptr(current_y3 + current_px), prepared_px3);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -163,7 +166,7 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
                     diffs2 = _mm_add_epi32(diffs2, _mm_sub_epi32(a_stored2, d_stored2));
                     diffs3 = _mm_add_epi32(diffs3, _mm_sub_epi32(a_stored3, d_stored3));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm_load_si128(da.as_ptr().cast());
                     let mut stored1 = _mm_load_si128(da[1..].as_ptr().cast());
@@ -194,7 +197,7 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
                 let pixel_color2 = load_u8_s32_fast::<CN>(s_ptr2);
                 let pixel_color3 = load_u8_s32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 _mm_store_si128(da.as_mut_ptr().cast(), pixel_color0);
@@ -234,8 +237,8 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
 
                     store_u8_u32::<CN>(bytes.get_ptr(bytes_offset), pixel_u32);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_load_si128(d_buf_ptr.cast());
@@ -246,7 +249,7 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_load_si128(buf_ptr.cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -260,7 +263,7 @@ fn fg_horizontal_pass_sse_u8_impl<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -301,7 +304,9 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -361,8 +366,8 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
                     store_u8_u32::<CN>(dst_ptr2, prepared_px2);
                     store_u8_u32::<CN>(dst_ptr3, prepared_px3);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -387,7 +392,7 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
                     diffs2 = _mm_add_epi32(diffs2, _mm_sub_epi32(a_stored2, d_stored2));
                     diffs3 = _mm_add_epi32(diffs3, _mm_sub_epi32(a_stored3, d_stored3));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm_load_si128(da.as_ptr().cast());
                     let mut stored1 = _mm_load_si128(da[1..].as_ptr().cast());
@@ -413,7 +418,7 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
                 let pixel_color2 = load_u8_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px2));
                 let pixel_color3 = load_u8_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px3));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 diffs0 = _mm_add_epi32(diffs0, pixel_color0);
                 diffs1 = _mm_add_epi32(diffs1, pixel_color1);
@@ -456,8 +461,8 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_u8_u32::<CN>(dst_ptr, pixel_u32);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_load_si128(d_buf_ptr.cast());
@@ -468,7 +473,7 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_load_si128(buf_ptr.cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -482,7 +487,7 @@ fn fg_vertical_pass_sse_u8_def<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
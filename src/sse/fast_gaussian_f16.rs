@@ -32,8 +32,10 @@ use std::arch::x86_64::*;
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::{load_f32_f16, store_f32_f16};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use core::f16;
 
 pub(crate) fn fg_horizontal_pass_sse_f16<const CN: usize>(
@@ -73,7 +75,10 @@ fn fast_gaussian_horizontal_pass_sse_f16_impl<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer: [[f32; 4]; 1024] = [[0.; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[f32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
 
         let radius_64 = radius as i64;
         let width_wide = width as i64;
@@ -99,8 +104,8 @@ fn fast_gaussian_horizontal_pass_sse_f16_impl<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_f32_f16::<CN>(dst_ptr, pixel);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_loadu_ps(d_buf_ptr);
@@ -111,7 +116,7 @@ fn fast_gaussian_horizontal_pass_sse_f16_impl<const CN: usize>(
 
                     diffs = _mm_add_ps(diffs, _mm_sub_ps(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_loadu_ps(buf_ptr);
                     stored = _mm_mul_ps(stored, v_half);
@@ -125,7 +130,7 @@ fn fast_gaussian_horizontal_pass_sse_f16_impl<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_f32_f16::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_ps(diffs, pixel_color);
@@ -174,7 +179,10 @@ fn fast_gaussian_vertical_pass_sse_f16_impl<T, const CN: usize>(
 ) {
     unsafe {
         let bytes: &UnsafeSlice<'_, f16> = std::mem::transmute(undefined_slice);
-        let mut buffer: [[f32; 4]; 1024] = [[0.; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[f32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
 
         let v_half = _mm_set1_ps(2.);
         let v_weight = _mm_set1_ps(1f32 / (radius as f32 * radius as f32));
@@ -199,8 +207,8 @@ fn fast_gaussian_vertical_pass_sse_f16_impl<T, const CN: usize>(
 
                     store_f32_f16::<CN>(bytes.get_ptr(bytes_offset), pixel);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let mut d_stored = _mm_loadu_ps(d_buf_ptr);
@@ -211,7 +219,7 @@ fn fast_gaussian_vertical_pass_sse_f16_impl<T, const CN: usize>(
 
                     diffs = _mm_add_ps(diffs, _mm_sub_ps(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = _mm_loadu_ps(buf_ptr);
                     stored = _mm_mul_ps(stored, v_half);
@@ -225,7 +233,7 @@ fn fast_gaussian_vertical_pass_sse_f16_impl<T, const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_f32_f16::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_ps(diffs, pixel_color);
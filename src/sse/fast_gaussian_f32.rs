@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::{_mm_opt_fnmlaf_ps, load_f32, store_f32};
 use crate::unsafe_slice::UnsafeSlice;
 use crate::util::ScratchBuffer;
@@ -106,7 +107,9 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseF32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseF32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let radius_64 = radius as i64;
@@ -148,8 +151,8 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     store_f32::<CN>(bytes.get_ptr(current_y2 + current_px), prepared_px2);
                     store_f32::<CN>(bytes.get_ptr(current_y3 + current_px), prepared_px3);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -168,7 +171,7 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     diffs2 = _mm_add_ps(diffs2, _mm_opt_fnmlaf_ps::<FMA>(a_s2, d_s2, v_double));
                     diffs3 = _mm_add_ps(diffs3, _mm_opt_fnmlaf_ps::<FMA>(a_s3, d_s3, v_double));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let s0 = _mm_loadu_ps(da.as_ptr().cast());
                     let s1 = _mm_loadu_ps(da[1..].as_ptr().cast());
@@ -189,7 +192,7 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                 let px2 = load_f32::<CN>(bytes.get_ptr(current_y2 + next_row_px));
                 let px3 = load_f32::<CN>(bytes.get_ptr(current_y3 + next_row_px));
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 _mm_store_ps(da.as_mut_ptr().cast(), px0);
@@ -229,8 +232,8 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_f32::<CN>(dst_ptr, pixel);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = _mm_load_ps(d_buf_ptr.as_ptr().cast());
@@ -243,7 +246,7 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                         _mm_opt_fnmlaf_ps::<FMA>(a_stored, d_stored, v_double),
                     );
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = _mm_load_ps(buf_ptr.as_ptr().cast());
                     diffs = _mm_opt_fnmlaf_ps::<FMA>(diffs, stored, v_double);
@@ -256,7 +259,7 @@ fn fg_horizontal_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_f32::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = _mm_add_ps(diffs, pixel_color);
@@ -334,7 +337,9 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseF32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseF32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let v_double = _mm_set1_ps(2.);
@@ -377,8 +382,8 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     store_f32::<CN>(bytes.get_ptr(current_y + current_px2), prepared_px2);
                     store_f32::<CN>(bytes.get_ptr(current_y + current_px3), prepared_px3);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -397,7 +402,7 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     diffs2 = _mm_add_ps(diffs2, _mm_opt_fnmlaf_ps::<FMA>(a_s2, d_s2, v_double));
                     diffs3 = _mm_add_ps(diffs3, _mm_opt_fnmlaf_ps::<FMA>(a_s3, d_s3, v_double));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let s0 = _mm_loadu_ps(da.as_ptr().cast());
                     let s1 = _mm_loadu_ps(da[1..].as_ptr().cast());
@@ -418,7 +423,7 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                 let px2 = load_f32::<CN>(bytes.get_ptr(next_row_y + current_px2));
                 let px3 = load_f32::<CN>(bytes.get_ptr(next_row_y + current_px3));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 _mm_store_ps(da.as_mut_ptr().cast(), px0);
@@ -459,8 +464,8 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     store_f32::<CN>(dst_ptr, pixel);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let d_stored = _mm_load_ps(d_buf_ptr.as_ptr().cast());
@@ -473,7 +478,7 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                         _mm_opt_fnmlaf_ps::<FMA>(a_stored, d_stored, v_double),
                     );
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let stored = _mm_loadu_ps(buf_ptr.as_ptr().cast());
                     diffs = _mm_opt_fnmlaf_ps::<FMA>(diffs, stored, v_double);
@@ -486,7 +491,7 @@ fn fg_vertical_pass_sse_f32_impl<const CN: usize, const FMA: bool>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_f32::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = _mm_add_ps(diffs, pixel_color);
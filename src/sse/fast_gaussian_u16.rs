@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::sse::fast_gaussian::SseI32x4;
 use crate::sse::store_u16_u32;
 use crate::sse::utils::load_u16_s32_fast;
@@ -82,7 +83,9 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -135,8 +138,8 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
                     store_u16_u32::<CN>(bytes.get_ptr(current_y2 + current_px), prepared_px2);
                     store_u16_u32::<CN>(bytes.get_ptr(current_y3 + current_px), prepared_px3);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
 
@@ -160,7 +163,7 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
                     diffs2 = _mm_add_epi32(diffs2, _mm_sub_epi32(a_stored2, d_stored2));
                     diffs3 = _mm_add_epi32(diffs3, _mm_sub_epi32(a_stored3, d_stored3));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm_load_si128(da.as_ptr().cast());
                     let mut stored1 = _mm_load_si128(da[1..].as_ptr().cast());
@@ -191,7 +194,7 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
                 let pixel_color2 = load_u16_s32_fast::<CN>(s_ptr2);
                 let pixel_color3 = load_u16_s32_fast::<CN>(s_ptr3);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 _mm_store_si128(da.as_mut_ptr().cast(), pixel_color0);
@@ -231,8 +234,8 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
 
                     store_u16_u32::<CN>(bytes.get_ptr(bytes_offset), pixel_u32);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let mut d_stored = _mm_load_si128(d_buf_ptr.as_ptr().cast());
@@ -243,7 +246,7 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = _mm_load_si128(buf_ptr.as_ptr().cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -257,7 +260,7 @@ fn fg_horizontal_pass_sse_u16_impl<const CN: usize, const FMA: bool>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_u16_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index);
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -314,7 +317,9 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SseI32x4; 4], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -369,8 +374,8 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
                     store_u16_u32::<CN>(bytes.get_ptr(current_y + current_px2), prepared_px2);
                     store_u16_u32::<CN>(bytes.get_ptr(current_y + current_px3), prepared_px3);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -395,7 +400,7 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
                     diffs2 = _mm_add_epi32(diffs2, _mm_sub_epi32(a_stored2, d_stored2));
                     diffs3 = _mm_add_epi32(diffs3, _mm_sub_epi32(a_stored3, d_stored3));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = _mm_load_si128(da.as_ptr().cast());
                     let mut stored1 = _mm_load_si128(da[1..].as_ptr().cast());
@@ -421,7 +426,7 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
                 let pixel_color2 = load_u16_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px2));
                 let pixel_color3 = load_u16_s32_fast::<CN>(bytes.get_ptr(next_row_y + current_px3));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 diffs0 = _mm_add_epi32(diffs0, pixel_color0);
                 diffs1 = _mm_add_epi32(diffs1, pixel_color1);
@@ -463,8 +468,8 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
 
                     store_u16_u32::<CN>(bytes.get_ptr(bytes_offset), pixel_u32);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let mut d_stored = _mm_load_si128(d_buf_ptr.as_ptr().cast());
@@ -475,7 +480,7 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
 
                     diffs = _mm_add_epi32(diffs, _mm_sub_epi32(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = _mm_load_si128(buf_ptr.as_ptr().cast());
                     stored = _mm_slli_epi32::<1>(stored);
@@ -488,7 +493,7 @@ fn fg_vertical_pass_sse_u16_impl<const CN: usize>(
 
                 let pixel_color = load_u16_s32_fast::<CN>(bytes.get_ptr(next_row_y + next_row_x));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = _mm_add_epi32(diffs, pixel_color);
@@ -42,6 +42,8 @@ mod fast_gaussian_next_u16;
 #[cfg(feature = "sse")]
 mod fast_gaussian_u16;
 mod packing;
+#[cfg(feature = "sse")]
+mod premultiply;
 pub(crate) mod utils;
 mod v_load_store;
 
@@ -64,6 +66,11 @@ pub(crate) use fast_gaussian_next_u16::{fgn_horizontal_pass_sse_u16, fgn_vertica
 #[cfg(feature = "sse")]
 pub(crate) use fast_gaussian_u16::{fg_horizontal_pass_sse_u16, fg_vertical_pass_sse_u16};
 pub(crate) use packing::*;
+#[cfg(feature = "sse")]
+pub(crate) use premultiply::{
+    premultiply_row_sse_f32, premultiply_row_sse_u8, premultiply_row_sse_u16,
+    unpremultiply_row_sse_f32, unpremultiply_row_sse_u8, unpremultiply_row_sse_u16,
+};
 pub(crate) use utils::*;
 #[allow(unused_imports)]
 pub(crate) use v_load_store::*;
@@ -27,8 +27,8 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::{
-    AnisotropicRadius, BlurImageMut, FastBlurChannels, ThreadingPolicy, stack_blur, stack_blur_f32,
-    stack_blur_u16,
+    AlphaMode, AnisotropicRadius, BlurImageMut, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy, stack_blur, stack_blur_f32, stack_blur_u16,
 };
 use image::{
     DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage, Rgba,
@@ -42,13 +42,15 @@ use image::{
 /// # Arguments
 ///
 /// * `image`: Dynamic image provided by image crate
-/// * `radius`: radius limited into 2..1449
+/// * `radius`: radius up to 65535, larger radius returns `None`
+/// * `edge_modes` - Rule to handle edge mode, [crate::EdgeMode::Constant] uses zero border.
 /// * `threading_policy` - Threads usage policy
 ///
 #[must_use]
 pub fn stack_blur_image(
     image: DynamicImage,
     radius: AnisotropicRadius,
+    edge_modes: EdgeMode2D,
     threading_policy: ThreadingPolicy,
 ) -> Option<DynamicImage> {
     match image {
@@ -60,63 +62,39 @@ pub fn stack_blur_image(
                 gray.height(),
                 FastBlurChannels::Plane,
             );
-            stack_blur(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_gray_image = GrayImage::from_raw(gray.width(), gray.height(), new_image)?;
             Some(DynamicImage::ImageLuma8(new_gray_image))
         }
         DynamicImage::ImageLumaA8(luma_alpha_image) => {
-            let mut intensity_plane =
-                vec![0u8; luma_alpha_image.width() as usize * luma_alpha_image.height() as usize];
-            let mut alpha_plane =
-                vec![0u8; luma_alpha_image.width() as usize * luma_alpha_image.height() as usize];
-            let raw_buffer = luma_alpha_image.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter_mut()
-                .zip(alpha_plane.iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
-
-            let mut intensity_image = BlurImageMut::borrow(
-                &mut intensity_plane,
-                luma_alpha_image.width(),
-                luma_alpha_image.height(),
-                FastBlurChannels::Plane,
-            );
-
-            stack_blur(&mut intensity_image, radius, threading_policy).unwrap();
-
-            let mut alpha_image = BlurImageMut::borrow(
-                &mut alpha_plane,
+            let mut new_image = luma_alpha_image.as_raw().to_vec();
+            let mut blur_image = BlurImageMut::borrow(
+                &mut new_image,
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                FastBlurChannels::Plane,
+                FastBlurChannels::Channels2,
             );
-
-            stack_blur(&mut alpha_image, radius, threading_policy).unwrap();
-
-            let mut new_raw_buffer =
-                vec![
-                    0u8;
-                    luma_alpha_image.width() as usize * luma_alpha_image.height() as usize * 2
-                ];
-
-            for ((&intensity, &alpha), raw_buffer) in intensity_plane
-                .iter()
-                .zip(alpha_plane.iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = intensity;
-                raw_buffer[1] = alpha;
-            }
-
+            stack_blur(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_gray_image = GrayAlphaImage::from_raw(
                 luma_alpha_image.width(),
                 luma_alpha_image.height(),
-                new_raw_buffer,
+                new_image,
             )?;
             Some(DynamicImage::ImageLumaA8(new_gray_image))
         }
@@ -128,7 +106,15 @@ pub fn stack_blur_image(
                 rgb_image.height(),
                 FastBlurChannels::Channels3,
             );
-            stack_blur(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_rgb_image =
                 RgbImage::from_raw(rgb_image.width(), rgb_image.height(), new_image)?;
             Some(DynamicImage::ImageRgb8(new_rgb_image))
@@ -141,7 +127,15 @@ pub fn stack_blur_image(
                 rgba_image.height(),
                 FastBlurChannels::Channels4,
             );
-            stack_blur(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
             let new_rgba_image =
                 RgbaImage::from_raw(rgba_image.width(), rgba_image.height(), new_image)?;
             Some(DynamicImage::ImageRgba8(new_rgba_image))
@@ -156,7 +150,15 @@ pub fn stack_blur_image(
                 FastBlurChannels::Plane,
             );
 
-            stack_blur_u16(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur_u16(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(
                 luma_16.width(),
@@ -165,56 +167,30 @@ pub fn stack_blur_image(
             )?;
             Some(DynamicImage::ImageLuma16(new_rgb_image))
         }
-        DynamicImage::ImageLumaA16(gray_alpha_16) => {
-            let mut intensity_plane =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize];
-            let mut alpha_plane =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize];
-            let raw_buffer = gray_alpha_16.as_raw();
-
-            for ((intensity, alpha), raw_buffer) in intensity_plane
-                .iter_mut()
-                .zip(alpha_plane.iter_mut())
-                .zip(raw_buffer.chunks_exact(2))
-            {
-                *intensity = raw_buffer[0];
-                *alpha = raw_buffer[1];
-            }
+        DynamicImage::ImageLumaA16(luma_alpha_image) => {
+            let mut new_image = luma_alpha_image.to_vec();
 
             let mut blur_image = BlurImageMut::borrow(
-                &mut intensity_plane,
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                FastBlurChannels::Plane,
-            );
-
-            let mut blur_image1 = BlurImageMut::borrow(
-                &mut alpha_plane,
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                FastBlurChannels::Plane,
+                &mut new_image,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                FastBlurChannels::Channels2,
             );
 
-            stack_blur_u16(&mut blur_image, radius, threading_policy).unwrap();
-
-            stack_blur_u16(&mut blur_image1, radius, threading_policy).unwrap();
-
-            let mut new_raw_buffer =
-                vec![0u16; gray_alpha_16.width() as usize * gray_alpha_16.height() as usize * 2];
-
-            for ((&intensity, &alpha), raw_buffer) in intensity_plane
-                .iter()
-                .zip(alpha_plane.iter())
-                .zip(new_raw_buffer.chunks_exact_mut(2))
-            {
-                raw_buffer[0] = intensity;
-                raw_buffer[1] = alpha;
-            }
+            stack_blur_u16(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_gray_image = ImageBuffer::<LumaA<u16>, Vec<u16>>::from_raw(
-                gray_alpha_16.width(),
-                gray_alpha_16.height(),
-                new_raw_buffer,
+                luma_alpha_image.width(),
+                luma_alpha_image.height(),
+                new_image,
             )?;
             Some(DynamicImage::ImageLumaA16(new_gray_image))
         }
@@ -228,7 +204,15 @@ pub fn stack_blur_image(
                 FastBlurChannels::Channels3,
             );
 
-            stack_blur_u16(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur_u16(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw(
                 rgb_16_image.width(),
@@ -247,7 +231,15 @@ pub fn stack_blur_image(
                 FastBlurChannels::Channels4,
             );
 
-            stack_blur_u16(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur_u16(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgba_image = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_raw(
                 rgba_16_image.width(),
@@ -266,7 +258,15 @@ pub fn stack_blur_image(
                 FastBlurChannels::Channels3,
             );
 
-            stack_blur_f32(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur_f32(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Rgb<f32>, Vec<f32>>::from_raw(
                 rgb_image_f32.width(),
@@ -285,7 +285,15 @@ pub fn stack_blur_image(
                 FastBlurChannels::Channels4,
             );
 
-            stack_blur_f32(&mut blur_image, radius, threading_policy).unwrap();
+            stack_blur_f32(
+                &mut blur_image,
+                radius,
+                threading_policy,
+                edge_modes,
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .ok()?;
 
             let new_rgb_image = ImageBuffer::<Rgba<f32>, Vec<f32>>::from_raw(
                 rgba_image_f32.width(),
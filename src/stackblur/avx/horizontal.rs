@@ -115,6 +115,8 @@ fn avx_horiz_pass_impl<const CN: usize>(
                 if i <= wm {
                     src_ptr0 += CN;
                     src_ptr1 += CN;
+                    src_ptr2 += CN;
+                    src_ptr3 += CN;
                 }
                 let stack_ptr = stacks.get_unchecked_mut((i + radius) as usize * 2..);
 
@@ -59,7 +59,7 @@ fn sse_horiz_pass_impl<const CN: usize>(
         let mut xp;
         let mut sp;
         let mut stack_start;
-        let mut scratch_buffer = ScratchBuffer::<i32, 1024>::new(4 * div * 2);
+        let mut scratch_buffer = ScratchBuffer::<i32, 2048>::new(4 * div * 2);
         let stacks = scratch_buffer.as_mut_slice();
 
         let v_mul_value = _mm_set1_ps(1. / ((radius as f32 + 1.) * (radius as f32 + 1.)));
@@ -60,7 +60,7 @@ impl<const CN: usize> VerticalSseStackBlurPass<CN> {
             let mut yp;
             let mut sp;
             let mut stack_start;
-            let mut scratch_buffer = ScratchBuffer::<i32, 1024>::new(4 * div * 4);
+            let mut scratch_buffer = ScratchBuffer::<i32, 4096>::new(4 * div * 4);
             let stacks = scratch_buffer.as_mut_slice();
 
             let v_mul_value = _mm_set1_ps(1. / ((radius as f32 + 1.) * (radius as f32 + 1.)));
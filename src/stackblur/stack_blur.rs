@@ -25,6 +25,8 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::padded_blur::{blur_in_place_padded_with_workspace, needs_padding};
+use crate::premultiply::blur_straight_in_place;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::stackblur::neon::{HorizontalNeonStackBlurPass, VerticalNeonStackBlurPass};
 #[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), feature = "sse"))]
@@ -33,7 +35,17 @@ use crate::stackblur::sse::{HorizontalSseStackBlurPass, VerticalSseStackBlurPass
 use crate::stackblur::wasm::{HorizontalWasmStackBlurPass, VerticalWasmStackBlurPass};
 use crate::stackblur::*;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{AnisotropicRadius, BlurError, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImageMut, BlurWorkspace, EdgeMode, EdgeMode2D,
+    FastBlurChannels, MAX_INTEGER_RADIUS, Scalar, ThreadingPolicy,
+};
+
+/// Stack sum of u8 values fits i32 while 255 * (radius + 1)^2 < 2^31,
+/// larger radius is blurred with i64 accumulator.
+const I32_RADIUS_CUTOFF: u32 = 2900;
+/// Q0.31 reciprocal of (radius + 1)^2 loses precision on larger radius.
+#[cfg(target_arch = "aarch64")]
+const Q0_31_RADIUS_CUTOFF: u32 = 1450;
 
 fn stack_blur_worker_horizontal(
     slice: &UnsafeSlice<u8>,
@@ -55,7 +67,10 @@ fn stack_blur_worker_horizontal(
         thread_count: usize,
     ) {
         #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-        fn select_blur_pass<const N: usize>() -> Box<dyn StackBlurWorkingPass<u8, N>> {
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(HorizontalStackBlurPass::<u8, i64, f64, N>::default());
+            }
             #[cfg(all(target_arch = "x86_64", feature = "avx"))]
             {
                 if std::arch::is_x86_feature_detected!("avx2") {
@@ -74,10 +89,13 @@ fn stack_blur_worker_horizontal(
         }
 
         #[cfg(target_arch = "aarch64")]
-        fn select_blur_pass<const N: usize>() -> Box<dyn StackBlurWorkingPass<u8, N>> {
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(HorizontalStackBlurPass::<u8, i64, f64, N>::default());
+            }
             #[cfg(feature = "sve")]
             {
-                if std::arch::is_aarch64_feature_detected!("sve2") {
+                if radius < Q0_31_RADIUS_CUTOFF && std::arch::is_aarch64_feature_detected!("sve2") {
                     use crate::stackblur::sve::HorizontalSveStackBlurPassQ0_31;
                     return Box::new(HorizontalSveStackBlurPassQ0_31::<N>::default());
                 }
@@ -85,7 +103,7 @@ fn stack_blur_worker_horizontal(
             #[cfg(feature = "neon")]
             {
                 #[cfg(feature = "rdm")]
-                if std::arch::is_aarch64_feature_detected!("rdm") {
+                if radius < Q0_31_RADIUS_CUTOFF && std::arch::is_aarch64_feature_detected!("rdm") {
                     use crate::stackblur::neon::HorizontalNeonStackBlurPassQ0_31;
                     return Box::new(HorizontalNeonStackBlurPassQ0_31::<N>::default());
                 }
@@ -98,8 +116,11 @@ fn stack_blur_worker_horizontal(
         }
 
         #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
-        fn select_blur_pass<const N: usize>() -> impl StackBlurWorkingPass<u8, N> {
-            HorizontalWasmStackBlurPass::<N>::default()
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(HorizontalStackBlurPass::<u8, i64, f64, N>::default());
+            }
+            Box::new(HorizontalWasmStackBlurPass::<N>::default())
         }
 
         #[cfg(not(any(
@@ -108,17 +129,23 @@ fn stack_blur_worker_horizontal(
             target_arch = "x86_64",
             target_arch = "x86"
         )))]
-        fn select_blur_pass<const N: usize>() -> impl StackBlurWorkingPass<u8, N> {
-            HorizontalStackBlurPass::<u8, i32, f32, N>::default()
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(HorizontalStackBlurPass::<u8, i64, f64, N>::default());
+            }
+            Box::new(HorizontalStackBlurPass::<u8, i32, f32, N>::default())
         }
 
-        let executor = select_blur_pass::<N>();
+        let executor = select_blur_pass::<N>(radius);
         executor.pass(slice, stride, width, height, radius, thread, thread_count);
     }
     match channels {
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -149,7 +176,10 @@ fn stack_blur_worker_vertical(
         thread_count: usize,
     ) {
         #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-        fn select_blur_pass<const N: usize>() -> Box<dyn StackBlurWorkingPass<u8, N>> {
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(VerticalStackBlurPass::<u8, i64, f64, N>::default());
+            }
             #[cfg(all(target_arch = "x86_64", feature = "avx"))]
             if std::arch::is_x86_feature_detected!("avx2") {
                 use crate::stackblur::avx::VerticalAvxStackBlurPass;
@@ -166,9 +196,12 @@ fn stack_blur_worker_vertical(
         }
 
         #[cfg(all(target_arch = "aarch64", feature = "neon"))]
-        fn select_blur_pass<const N: usize>() -> Box<dyn StackBlurWorkingPass<u8, N>> {
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(VerticalStackBlurPass::<u8, i64, f64, N>::default());
+            }
             #[cfg(feature = "rdm")]
-            if std::arch::is_aarch64_feature_detected!("rdm") {
+            if radius < Q0_31_RADIUS_CUTOFF && std::arch::is_aarch64_feature_detected!("rdm") {
                 use crate::stackblur::neon::VerticalNeonStackBlurPassQ0_31;
                 return Box::new(VerticalNeonStackBlurPassQ0_31::<N>::default());
             }
@@ -176,8 +209,11 @@ fn stack_blur_worker_vertical(
         }
 
         #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
-        fn select_blur_pass<const N: usize>() -> impl StackBlurWorkingPass<u8, N> {
-            VerticalWasmStackBlurPass::<N>::default()
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(VerticalStackBlurPass::<u8, i64, f64, N>::default());
+            }
+            Box::new(VerticalWasmStackBlurPass::<N>::default())
         }
 
         #[cfg(not(any(
@@ -186,17 +222,23 @@ fn stack_blur_worker_vertical(
             target_arch = "x86_64",
             target_arch = "x86"
         )))]
-        fn select_blur_pass<const N: usize>() -> impl StackBlurWorkingPass<u8, N> {
-            VerticalStackBlurPass::<u8, i32, f32, N>::default()
+        fn select_blur_pass<const N: usize>(radius: u32) -> Box<dyn StackBlurWorkingPass<u8, N>> {
+            if radius >= I32_RADIUS_CUTOFF {
+                return Box::new(VerticalStackBlurPass::<u8, i64, f64, N>::default());
+            }
+            Box::new(VerticalStackBlurPass::<u8, i32, f32, N>::default())
         }
 
-        let executor = select_blur_pass::<N>();
+        let executor = select_blur_pass::<N>(radius);
         executor.pass(slice, stride, width, height, radius, thread, thread_count);
     }
     match channels {
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -212,8 +254,16 @@ fn stack_blur_worker_vertical(
 ///
 /// # Arguments
 /// * `image` - mutable buffer contains image data that will be used as a source and destination.
-/// * `radius` - radius almost is limited to 1449, minimum is one.
+/// * `radius` - radius up to 65535, minimum is one. Radius above 2899 uses i64 accumulator
+///   which has no SIMD implementation and is several times slower.
 /// * `threading_policy` - Threads usage policy.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided,
+/// or radius exceeds 65535.
 ///
 /// # Complexity
 /// O(1) complexity.
@@ -221,9 +271,85 @@ pub fn stack_blur(
     image: &mut BlurImageMut<u8>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
+) -> Result<(), BlurError> {
+    stack_blur_with_workspace(
+        image,
+        radius,
+        threading_policy,
+        edge_modes,
+        border_constant,
+        alpha_mode,
+        &mut BlurWorkspace::new(),
+    )
+}
+
+/// Fastest available blur option using reusable scratch memory.
+///
+/// Same as [stack_blur], but padded copy of the image required by edge modes other than
+/// [EdgeMode::Clamp] is taken from the provided [BlurWorkspace].
+/// Blur stacks live on the thread stack for radius below 128, so with [ThreadingPolicy::Single]
+/// repeated calls on images of the same size do not allocate.
+///
+/// # Arguments
+/// * `image` - mutable buffer contains image data that will be used as a source and destination.
+/// * `radius` - radius up to 65535, minimum is one.
+/// * `threading_policy` - Threads usage policy.
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+/// * `workspace` - Scratch memory reused between calls, see [BlurWorkspace].
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided,
+/// or radius exceeds 65535.
+pub fn stack_blur_with_workspace(
+    image: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
+    workspace: &mut BlurWorkspace<u8>,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
-    let radius = radius.clamp(1, 1449);
+    radius.check_max(MAX_INTEGER_RADIUS)?;
+    let radius = radius.max(1);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            stack_blur_with_workspace(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+                workspace,
+            )
+        });
+    }
+    if needs_padding(edge_modes) {
+        return blur_in_place_padded_with_workspace(
+            image,
+            radius,
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            workspace,
+            |padded, _| stack_blur_clamped(padded, radius, threading_policy),
+        );
+    }
+    stack_blur_clamped(image, radius, threading_policy)
+}
+
+/// Blurs the image treating every border as [EdgeMode::Clamp]
+fn stack_blur_clamped(
+    image: &mut BlurImageMut<u8>,
+    radius: AnisotropicRadius,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
     let thread_count = threading_policy.thread_count(image.width, image.height) as u32;
     let stride = image.row_stride();
     let width = image.width;
@@ -235,7 +361,7 @@ pub fn stack_blur(
         stack_blur_worker_vertical(&slice, stride, width, height, radius.y_axis, channels, 0, 1);
         return Ok(());
     }
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let slice = UnsafeSlice::new(image.data.borrow_mut());
     pool.parallel_for(|thread_id| {
         stack_blur_worker_horizontal(
@@ -268,6 +394,48 @@ pub fn stack_blur(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stack_blur_u8_horizontal_matches_scalar() {
+        let width = 53usize;
+        let height = 11usize;
+        let src = (0..width * height * 3)
+            .map(|i| ((i * 7919 + (i / 5) * 104729) % 256) as u8)
+            .collect::<Vec<_>>();
+        let radius = 6;
+        let stride = (width * 3) as u32;
+
+        let mut dispatched = src.clone();
+        stack_blur_worker_horizontal(
+            &UnsafeSlice::new(&mut dispatched),
+            stride,
+            width as u32,
+            height as u32,
+            radius,
+            FastBlurChannels::Channels3,
+            0,
+            1,
+        );
+
+        let mut reference = src.clone();
+        HorizontalStackBlurPass::<u8, i32, f32, 3>::default().pass(
+            &UnsafeSlice::new(&mut reference),
+            stride,
+            width as u32,
+            height as u32,
+            radius,
+            0,
+            1,
+        );
+
+        for (i, (&a, &b)) in dispatched.iter().zip(reference.iter()).enumerate() {
+            assert!(
+                a.abs_diff(b) <= 1,
+                "Row {}, expected {b}, but it was {a}",
+                i / (width * 3)
+            );
+        }
+    }
+
     #[test]
     fn test_stack_blur_u8_q_k5() {
         let width: usize = 148;
@@ -283,6 +451,9 @@ mod tests {
             &mut dst_image,
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for (i, &cn) in dst.iter().enumerate() {
@@ -293,4 +464,129 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_stack_blur_u8_edge_modes() {
+        let width: usize = 64;
+        let height: usize = 48;
+        for edge_mode in [EdgeMode::Wrap, EdgeMode::Reflect, EdgeMode::Reflect101] {
+            let mut dst = vec![126u8; width * height];
+            let mut dst_image = BlurImageMut::borrow(
+                &mut dst,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Plane,
+            );
+            stack_blur(
+                &mut dst_image,
+                AnisotropicRadius::new(7),
+                ThreadingPolicy::Single,
+                edge_mode.as_2d(),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .unwrap();
+            assert!(dst.iter().all(|&x| (x as i32 - 126).abs() <= 3));
+        }
+        let mut dst = vec![126u8; width * height];
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        stack_blur(
+            &mut dst_image,
+            AnisotropicRadius::new(7),
+            ThreadingPolicy::Single,
+            EdgeMode::Constant.as_2d(),
+            Scalar::dup(0.),
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        assert!(
+            dst[0] < 100,
+            "Corner expected to be darker but it was {}",
+            dst[0]
+        );
+        let center = dst[height / 2 * width + width / 2];
+        assert!((center as i32 - 126).abs() <= 3);
+    }
+
+    #[test]
+    fn test_stack_blur_u8_large_radius() {
+        let width: usize = 80;
+        let height: usize = 56;
+        let src: Vec<u8> = (0..width * height)
+            .map(|i| (((i % width) * 5 + (i / width) * 11) % 256) as u8)
+            .collect();
+        let blur_line = |line: &[u8], radius: i64| -> Vec<u8> {
+            let n = line.len() as i64;
+            let weight = ((radius + 1) * (radius + 1)) as f64;
+            (0..n)
+                .map(|i| {
+                    let sum: i64 = (-radius..=radius)
+                        .map(|k| {
+                            (radius + 1 - k.abs()) * line[(i + k).clamp(0, n - 1) as usize] as i64
+                        })
+                        .sum();
+                    (sum as f64 / weight).round() as u8
+                })
+                .collect()
+        };
+        for radius in [1500u32, 3000] {
+            let mut reference = src.clone();
+            for row in reference.chunks_exact_mut(width) {
+                let blurred = blur_line(row, radius as i64);
+                row.copy_from_slice(&blurred);
+            }
+            for x in 0..width {
+                let column: Vec<u8> = (0..height).map(|y| reference[y * width + x]).collect();
+                for (y, v) in blur_line(&column, radius as i64).into_iter().enumerate() {
+                    reference[y * width + x] = v;
+                }
+            }
+            let mut dst = src.clone();
+            let mut dst_image = BlurImageMut::borrow(
+                &mut dst,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Plane,
+            );
+            stack_blur(
+                &mut dst_image,
+                AnisotropicRadius::new(radius),
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            )
+            .unwrap();
+            for (i, (&a, &b)) in dst.iter().zip(reference.iter()).enumerate() {
+                let diff = (a as i32 - b as i32).abs();
+                assert!(
+                    diff <= 2,
+                    "Radius {radius}: diff expected to be less than 2 but it was {diff} at {i}"
+                );
+            }
+        }
+        let mut dst = src.clone();
+        let mut dst_image = BlurImageMut::borrow(
+            &mut dst,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        );
+        assert!(matches!(
+            stack_blur(
+                &mut dst_image,
+                AnisotropicRadius::new(70000),
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp.as_2d(),
+                Scalar::default(),
+                AlphaMode::Premultiplied,
+            ),
+            Err(BlurError::InvalidParameter { .. })
+        ));
+    }
 }
@@ -25,6 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::padded_blur::{blur_in_place_padded, needs_padding};
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::stackblur::neon::{
     HorizontalNeonStackBlurPassFloat16, VerticalNeonStackBlurPassFloat16,
@@ -33,7 +34,10 @@ use crate::stackblur::neon::{
 use crate::stackblur::sse::{HorizontalSseStackBlurPassFloat16, VerticalSseStackBlurPassFloat16};
 use crate::stackblur::{HorizontalStackBlurPass, StackBlurWorkingPass, VerticalStackBlurPass};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{AnisotropicRadius, BlurError, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AnisotropicRadius, BlurError, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels, Scalar,
+    ThreadingPolicy,
+};
 use core::f16;
 
 fn stack_blur_worker_horizontal(
@@ -67,6 +71,24 @@ fn stack_blur_worker_horizontal(
             }
             _executor.pass(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            let mut _executor: Box<dyn StackBlurWorkingPass<f16, 2>> =
+                Box::new(HorizontalStackBlurPass::<f16, f32, f32, 2>::default());
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            {
+                #[cfg(feature = "sse")]
+                if std::arch::is_x86_feature_detected!("sse4.1")
+                    && std::arch::is_x86_feature_detected!("f16c")
+                {
+                    _executor = Box::new(HorizontalSseStackBlurPassFloat16::<2>::default());
+                }
+            }
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            {
+                _executor = Box::new(HorizontalNeonStackBlurPassFloat16::<2>::default());
+            }
+            _executor.pass(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             let mut _executor: Box<dyn StackBlurWorkingPass<f16, 3>> =
                 Box::new(HorizontalStackBlurPass::<f16, f32, f32, 3>::default());
@@ -135,6 +157,24 @@ fn stack_blur_worker_vertical(
             }
             _executor.pass(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            let mut _executor: Box<dyn StackBlurWorkingPass<f16, 2>> =
+                Box::new(VerticalStackBlurPass::<f16, f32, f32, 2>::default());
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            {
+                #[cfg(feature = "sse")]
+                if std::arch::is_x86_feature_detected!("sse4.1")
+                    && std::arch::is_x86_feature_detected!("f16c")
+                {
+                    _executor = Box::new(VerticalSseStackBlurPassFloat16::<2>::default());
+                }
+            }
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            {
+                _executor = Box::new(VerticalNeonStackBlurPassFloat16::<2>::default());
+            }
+            _executor.pass(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             let mut _executor: Box<dyn StackBlurWorkingPass<f16, 3>> =
                 Box::new(VerticalStackBlurPass::<f16, f32, f32, 3>::default());
@@ -182,6 +222,8 @@ fn stack_blur_worker_vertical(
 /// * `image` - mutable buffer contains image data that will be used as a source and destination.
 /// * `radius` - radius almost is not limited, minimum is one
 /// * `threading_policy` - Threads usage policy
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
 ///
 /// # Complexity
 /// O(1) complexity.
@@ -191,9 +233,29 @@ pub fn stack_blur_f16(
     image: &mut BlurImageMut<f16>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
     let radius = radius.clamp(1, 1449);
+    if needs_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            radius,
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |padded, inner_modes| {
+                stack_blur_f16(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
@@ -205,7 +267,7 @@ pub fn stack_blur_f16(
         stack_blur_worker_vertical(&slice, stride, width, height, radius.y_axis, channels, 0, 1);
         return Ok(());
     }
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let slice = UnsafeSlice::new(image.data.borrow_mut());
     pool.parallel_for(|thread_index| {
         stack_blur_worker_horizontal(
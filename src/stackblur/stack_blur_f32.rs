@@ -25,6 +25,8 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::padded_blur::{blur_in_place_padded, needs_padding};
+use crate::premultiply::blur_straight_in_place;
 #[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::stackblur::neon::{
     HorizontalNeonStackBlurPassFloat32, VerticalNeonStackBlurPassFloat32,
@@ -33,7 +35,10 @@ use crate::stackblur::neon::{
 use crate::stackblur::sse::{HorizontalSseStackBlurPassFloat32, VerticalSseStackBlurPassFloat32};
 use crate::stackblur::*;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{AnisotropicRadius, BlurError, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels,
+    Scalar, ThreadingPolicy,
+};
 
 fn stack_blur_worker_horizontal(
     slice: &UnsafeSlice<f32>,
@@ -92,6 +97,9 @@ fn stack_blur_worker_horizontal(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -159,6 +167,9 @@ fn stack_blur_worker_vertical(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -176,6 +187,9 @@ fn stack_blur_worker_vertical(
 /// * `image` - mutable buffer contains image data that will be used as a source and destination.
 /// * `radius` - radius almost is not limited, minimum is one.
 /// * `threading_policy` - Threads usage policy
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
 ///
 /// # Complexity
 /// O(1) complexity.
@@ -183,9 +197,43 @@ pub fn stack_blur_f32(
     image: &mut BlurImageMut<f32>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
     image.check_layout(None)?;
     let radius = radius.max(1);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            stack_blur_f32(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            radius,
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |padded, inner_modes| {
+                stack_blur_f32(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let thread_count = threading_policy.thread_count(image.width, image.height) as u32;
     let stride = image.row_stride();
     let width = image.width;
@@ -197,7 +245,7 @@ pub fn stack_blur_f32(
         stack_blur_worker_vertical(&slice, stride, width, height, radius.y_axis, channels, 0, 1);
         return Ok(());
     }
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let slice = UnsafeSlice::new(image.data.borrow_mut());
     pool.parallel_for(|thread_index| {
         stack_blur_worker_horizontal(
@@ -245,6 +293,9 @@ mod tests {
             &mut dst_image,
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for (i, &cn) in dst.iter().enumerate() {
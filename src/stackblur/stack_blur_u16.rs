@@ -26,9 +26,14 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use crate::padded_blur::{blur_in_place_padded, needs_padding};
+use crate::premultiply::blur_straight_in_place;
 use crate::stackblur::{HorizontalStackBlurPass, StackBlurWorkingPass, VerticalStackBlurPass};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{AnisotropicRadius, BlurError, BlurImageMut, FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImageMut, EdgeMode, EdgeMode2D, FastBlurChannels,
+    MAX_INTEGER_RADIUS, Scalar, ThreadingPolicy,
+};
 
 const LARGE_RADIUS_CUTOFF: u32 = 135;
 
@@ -63,6 +68,9 @@ fn stack_blur_worker_horizontal(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -104,6 +112,9 @@ fn stack_blur_worker_vertical(
         FastBlurChannels::Plane => {
             pass::<1>(slice, stride, width, height, radius, thread, thread_count);
         }
+        FastBlurChannels::Channels2 => {
+            pass::<2>(slice, stride, width, height, radius, thread, thread_count);
+        }
         FastBlurChannels::Channels3 => {
             pass::<3>(slice, stride, width, height, radius, thread, thread_count);
         }
@@ -119,8 +130,15 @@ fn stack_blur_worker_vertical(
 ///
 /// # Arguments
 /// * `image` - mutable buffer contains image data that will be used as a source and destination.
-/// * `channels` - Count of channels of the image
+/// * `radius` - radius up to 65535, minimum is one.
 /// * `threading_policy` - Threads usage policy
+/// * `edge_modes` - Rule to handle edge mode, see [EdgeMode] for more info.
+/// * `border_constant` - If [EdgeMode::Constant] border will be replaced with this value.
+/// * `alpha_mode` - Alpha layout of [FastBlurChannels::Channels4] images, see [AlphaMode] for more info.
+///
+/// # Errors
+/// Returns an error if stride/width/height/channel configuration do not match provided,
+/// or radius exceeds 65535.
 ///
 /// # Complexity
 /// O(1) complexity.
@@ -128,13 +146,48 @@ pub fn stack_blur_u16(
     image: &mut BlurImageMut<u16>,
     radius: AnisotropicRadius,
     threading_policy: ThreadingPolicy,
+    edge_modes: EdgeMode2D,
+    border_constant: Scalar,
+    alpha_mode: AlphaMode,
 ) -> Result<(), BlurError> {
+    image.check_layout(None)?;
+    radius.check_max(MAX_INTEGER_RADIUS)?;
     let radius = radius.max(1);
+    if alpha_mode == AlphaMode::Straight && image.channels == FastBlurChannels::Channels4 {
+        return blur_straight_in_place(image, border_constant, |image, border_constant| {
+            stack_blur_u16(
+                image,
+                radius,
+                threading_policy,
+                edge_modes,
+                border_constant,
+                AlphaMode::Premultiplied,
+            )
+        });
+    }
+    if needs_padding(edge_modes) {
+        return blur_in_place_padded(
+            image,
+            radius,
+            edge_modes,
+            border_constant,
+            |mode| mode == EdgeMode::Clamp,
+            |padded, inner_modes| {
+                stack_blur_u16(
+                    padded,
+                    radius,
+                    threading_policy,
+                    inner_modes,
+                    border_constant,
+                    AlphaMode::Premultiplied,
+                )
+            },
+        );
+    }
     let stride = image.row_stride();
     let width = image.width;
     let height = image.height;
     let channels = image.channels;
-    let radius = radius.clamp(1, 2000);
     let thread_count = threading_policy.thread_count(width, height) as u32;
     if thread_count == 1 {
         let slice = UnsafeSlice::new(image.data.borrow_mut());
@@ -142,7 +195,7 @@ pub fn stack_blur_u16(
         stack_blur_worker_vertical(&slice, stride, width, height, radius.y_axis, channels, 0, 1);
         return Ok(());
     }
-    let pool = novtb::ThreadPool::new(thread_count as usize);
+    let pool = crate::executor::ThreadPool::new(thread_count as usize);
     let slice = UnsafeSlice::new(image.data.borrow_mut());
     pool.parallel_for(|thread_index| {
         stack_blur_worker_horizontal(
@@ -190,6 +243,9 @@ mod tests {
             &mut dst_image,
             AnisotropicRadius::new(5),
             ThreadingPolicy::Single,
+            EdgeMode::Clamp.as_2d(),
+            Scalar::default(),
+            AlphaMode::Premultiplied,
         )
         .unwrap();
         for (i, &cn) in dst.iter().enumerate() {
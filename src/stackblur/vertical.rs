@@ -30,6 +30,7 @@ use crate::primitives::PrimitiveCast;
 use crate::stackblur::sliding_window::SlidingWindow;
 use crate::stackblur::stack_blur_pass::StackBlurWorkingPass;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use std::marker::PhantomData;
 use std::ops::{AddAssign, Mul, Sub, SubAssign};
 
@@ -83,7 +84,8 @@ where
         let mut _yp;
         let mut sp;
         let mut stack_start;
-        let mut stacks = vec![SlidingWindow::<CN, J>::new(); div];
+        let mut stack_buffer = ScratchBuffer::<SlidingWindow<CN, J>, 1024>::new(div);
+        let stacks = stack_buffer.as_mut_slice();
 
         let mut sum: SlidingWindow<CN, J>;
         let mut sum_in: SlidingWindow<CN, J>;
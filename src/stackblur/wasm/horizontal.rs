@@ -28,6 +28,7 @@
  */
 use crate::stackblur::stack_blur_pass::StackBlurWorkingPass;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use crate::wasm32::*;
 use std::arch::wasm32::*;
 
@@ -57,7 +58,8 @@ impl<const CN: usize> HorizontalWasmStackBlurPass<CN> {
             let mut _xp;
             let mut sp;
             let mut stack_start;
-            let mut stacks = vec![0i32; 4 * div];
+            let mut scratch_buffer = ScratchBuffer::<i32, 1024>::new(4 * div);
+            let stacks = scratch_buffer.as_mut_slice();
 
             let mul_value = f32x4_splat(1. / ((radius as f32 + 1.) * (radius as f32 + 1.)));
 
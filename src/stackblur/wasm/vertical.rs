@@ -28,6 +28,7 @@
  */
 use crate::stackblur::stack_blur_pass::StackBlurWorkingPass;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use crate::wasm32::{
     load_u8_s32_fast, u16x8_pack_trunc_u8x16, u32x4_pack_trunc_u16x8, w_store_u8x8_m4,
 };
@@ -58,7 +59,8 @@ impl<const CN: usize> VerticalWasmStackBlurPass<CN> {
             let mut yp;
             let mut sp;
             let mut stack_start;
-            let mut stacks = vec![0i32; 4 * div];
+            let mut scratch_buffer = ScratchBuffer::<i32, 1024>::new(4 * div);
+            let stacks = scratch_buffer.as_mut_slice();
 
             let v_mul_value = f32x4_splat(1. / ((radius as f32 + 1.) * (radius as f32 + 1.)));
 
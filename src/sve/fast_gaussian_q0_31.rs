@@ -27,6 +27,7 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::unsafe_slice::UnsafeSlice;
 use crate::util::ScratchBuffer;
 use std::arch::aarch64::*;
@@ -64,7 +65,9 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SveI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SveI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let initial_sum = ((radius * radius) >> 1) as i32;
@@ -122,8 +125,8 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
                     svst1b_u32(pv_cn, dst_ptr3, svreinterpret_u32_s32(prepared_px3));
                     svst1b_u32(pv_cn, dst_ptr4, svreinterpret_u32_s32(prepared_px4));
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -158,7 +161,7 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
                     diffs3 = svadd_s32_x(pv_cn, diffs3, sub3);
                     diffs4 = svadd_s32_x(pv_cn, diffs4, sub4);
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = svld1_s32(pv_cn, da.as_ptr().cast());
                     let mut stored1 = svld1_s32(pv_cn, da[1..].as_ptr().cast());
@@ -194,7 +197,7 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
                 let pixel_color3 = svld1ub_s32(pv_cn, s_ptr3);
                 let pixel_color4 = svld1ub_s32(pv_cn, s_ptr4);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let da = buffer.get_unchecked_mut(arr_index);
 
                 svst1_s32(pv_cn, da.as_mut_ptr().cast(), pixel_color0);
@@ -239,8 +242,8 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
                         svreinterpret_u32_s32(prepared_px_s32),
                     );
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index).as_ptr();
                     let d_stored = svld1_s32(pv_cn, d_buf_ptr.cast());
@@ -254,7 +257,7 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
                         svsub_s32_x(pv_cn, a_stored, svadd_s32_x(pv_cn, d_stored, d_stored)),
                     );
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index).as_ptr();
                     let mut stored = svld1_s32(pv_cn, buf_ptr.cast());
                     stored = svlsl_n_s32_x(pv_cn, stored, 1);
@@ -266,7 +269,7 @@ fn fg_horizontal_pass_neon_sve<const CN: usize>(
 
                 let pixel_color = svld1ub_s32(pv_cn, bytes.get_ptr(current_y + next_row_px));
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = svadd_s32_x(pv_cn, diffs, pixel_color);
@@ -305,7 +308,9 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer = ScratchBuffer::<[SveI32x4; 5], 1024>::new(1024);
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut buffer = ScratchBuffer::<[SveI32x4; 5], 1024>::new(ring_size);
         let buffer = buffer.as_mut_slice();
 
         let pv_cn = svwhilelt_b32_u32(0u32, CN as u32);
@@ -378,8 +383,8 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
                         svreinterpret_u32_s32(prepared_px4),
                     );
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let da_b = buffer.get_unchecked(d_arr_index);
                     let da = buffer.get_unchecked(arr_index);
@@ -414,7 +419,7 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
                     diffs3 = svadd_s32_x(pv_cn, diffs3, sub3);
                     diffs4 = svadd_s32_x(pv_cn, diffs4, sub4);
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let da = buffer.get_unchecked(arr_index);
                     let mut stored0 = svld1_s32(pv_cn, da.as_ptr().cast());
                     let mut stored1 = svld1_s32(pv_cn, da[1..].as_ptr().cast());
@@ -444,7 +449,7 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
                 let pixel_color3 = svld1ub_s32(pv_cn, bytes.get_ptr(next_row_y + current_px3));
                 let pixel_color4 = svld1ub_s32(pv_cn, bytes.get_ptr(next_row_y + current_px4));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
 
                 let da = buffer.get_unchecked_mut(arr_index);
                 svst1_s32(pv_cn, da.as_mut_ptr().cast(), pixel_color0);
@@ -487,8 +492,8 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
                         svreinterpret_u32_s32(prepared_px),
                     );
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_stored =
                         svld1_s32(pv_cn, buffer.get_unchecked(d_arr_index).as_ptr().cast());
@@ -501,7 +506,7 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
                         svsub_s32_x(pv_cn, a_stored, svadd_s32_x(pv_cn, d_stored, d_stored)),
                     );
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let stored = svld1_s32(pv_cn, buffer.get_unchecked(arr_index).as_ptr().cast());
                     diffs = svsub_s32_x(pv_cn, diffs, svlsl_n_s32_x(pv_cn, stored, 1));
                 }
@@ -511,7 +516,7 @@ fn fg_vertical_pass_neon_sve<const CN: usize>(
 
                 let pixel_color = svld1ub_s32(pv_cn, bytes.get_ptr(next_row_y + current_px));
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 svst1_s32(
                     pv_cn,
                     buffer.get_unchecked_mut(arr_index).as_mut_ptr().cast(),
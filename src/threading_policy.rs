@@ -25,6 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::executor::executor_concurrency;
 use std::fmt::Display;
 use std::{num::NonZeroUsize, thread::available_parallelism};
 
@@ -32,13 +33,9 @@ use std::{num::NonZeroUsize, thread::available_parallelism};
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default, Hash)]
 /// Set threading policy.
 ///
-/// # Warning about outside Rayon scheduling
-///
-/// Implementation of `Fast Bilateral Blur` currently relies on Rayon, and due to how Rayon works
-/// accurate attention is required if API is expected to be used in MultiThreaded more
-/// scheduled by outside Rayon parallelism. It may cause excessive memory and CPU usage.
-/// If it is impossible to guarantee finite and predictable amount of memory used consider
-/// [ThreadingPolicy::Single] mode only.
+/// By default, multithreaded work runs on scoped threads spawned for the call.
+/// To run it on an existing thread pool or job system see [crate::with_executor],
+/// adaptive policies then use concurrency of the executor instead of available CPU count.
 pub enum ThreadingPolicy {
     /// Use only one thread, current is preferred.
     Single,
@@ -99,6 +96,9 @@ impl ThreadingPolicy {
     // Make always return at least some minimal amount of threads, if multi-threading were requested
     // At least on single core CPU have 2 threads is beneficial
     fn available_parallelism(min: usize) -> usize {
+        if let Some(concurrency) = executor_concurrency() {
+            return concurrency.max(min);
+        }
         available_parallelism()
             .unwrap_or_else(|_| NonZeroUsize::new(1).unwrap())
             .get()
@@ -0,0 +1,797 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::executor::{ParallelZonedIterator, TbSliceMut};
+use crate::gamma_curves::TransferFunction;
+use crate::{
+    AlphaMode, AnisotropicRadius, BlurError, BlurImage, BlurImageMut, BlurWorkspace,
+    ConvolutionMode, EdgeMode2D, FastBlurChannels, GaussianBlurParams, IeeeBinaryConvolutionMode,
+    Scalar, ThreadingPolicy, fast_gaussian, fast_gaussian_f32, fast_gaussian_u16, gaussian_blur,
+    gaussian_blur_f32, gaussian_blur_u16, stack_blur, stack_blur_f32, stack_blur_u16,
+};
+use std::fmt::Debug;
+
+/// Blur used to build the unsharp mask
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+pub enum UnsharpBlur {
+    /// See [crate::gaussian_blur], kernel size is `radius * 2 + 1`.
+    #[default]
+    Gaussian,
+    /// See [crate::fast_gaussian].
+    FastGaussian,
+    /// See [crate::stack_blur].
+    StackBlur,
+}
+
+/// Parameters of the unsharp mask
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnsharpMaskParams {
+    /// Radius of the inner blur.
+    pub radius: u32,
+    /// Strength of sharpening, 1.0 adds the full high-pass back to the image.
+    pub amount: f32,
+    /// Minimal difference between the source and the blurred image to be sharpened,
+    /// expressed in the source value range, e.g. `0..255` for u8.
+    pub threshold: f32,
+    /// Inner blur, see [UnsharpBlur] for more info.
+    pub blur: UnsharpBlur,
+    /// If set, sharpening is performed in linear light, see [TransferFunction] for more info.
+    pub transfer_function: Option<TransferFunction>,
+    /// Rule to handle edge mode, see [crate::EdgeMode] for more info.
+    pub edge_modes: EdgeMode2D,
+}
+
+impl UnsharpMaskParams {
+    pub fn new(radius: u32, amount: f32) -> UnsharpMaskParams {
+        UnsharpMaskParams {
+            radius,
+            amount,
+            threshold: 0.,
+            blur: UnsharpBlur::default(),
+            transfer_function: None,
+            edge_modes: EdgeMode2D::default(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if self.radius == 0
+            || !self.amount.is_finite()
+            || !self.threshold.is_finite()
+            || self.threshold < 0.
+        {
+            return Err(BlurError::InvalidArguments);
+        }
+        Ok(())
+    }
+}
+
+trait UnsharpSample: Copy + Clone + Default + Debug + Send + Sync + 'static {
+    fn blur(
+        src: &BlurImage<Self>,
+        dst: &mut BlurImageMut<Self>,
+        params: &UnsharpMaskParams,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<(), BlurError>;
+
+    /// Blurs image in place, gaussian cannot run in place and returns a new blurred image instead
+    fn blur_in_place(
+        image: &mut BlurImageMut<Self>,
+        params: &UnsharpMaskParams,
+        threading_policy: ThreadingPolicy,
+    ) -> Result<Option<BlurImageMut<'static, Self>>, BlurError>;
+}
+
+macro_rules! impl_unsharp_sample {
+    ($t: ty, $gaussian: ident, $fast_gaussian: ident, $stack_blur: ident, $hint: expr) => {
+        impl UnsharpSample for $t {
+            fn blur(
+                src: &BlurImage<Self>,
+                dst: &mut BlurImageMut<Self>,
+                params: &UnsharpMaskParams,
+                threading_policy: ThreadingPolicy,
+            ) -> Result<(), BlurError> {
+                match params.blur {
+                    UnsharpBlur::Gaussian => $gaussian(
+                        src,
+                        dst,
+                        GaussianBlurParams::new_from_kernel(params.radius as f64 * 2. + 1.),
+                        params.edge_modes,
+                        threading_policy,
+                        $hint,
+                        AlphaMode::Premultiplied,
+                    ),
+                    UnsharpBlur::FastGaussian | UnsharpBlur::StackBlur => {
+                        src.copy_to_mut(dst)?;
+                        Self::blur_in_place(dst, params, threading_policy).map(|_| ())
+                    }
+                }
+            }
+
+            fn blur_in_place(
+                image: &mut BlurImageMut<Self>,
+                params: &UnsharpMaskParams,
+                threading_policy: ThreadingPolicy,
+            ) -> Result<Option<BlurImageMut<'static, Self>>, BlurError> {
+                match params.blur {
+                    UnsharpBlur::Gaussian => {
+                        let mut blurred = BlurImageMut::default();
+                        Self::blur(
+                            &image.to_immutable_ref(),
+                            &mut blurred,
+                            params,
+                            threading_policy,
+                        )?;
+                        Ok(Some(blurred))
+                    }
+                    UnsharpBlur::FastGaussian => {
+                        $fast_gaussian(
+                            image,
+                            AnisotropicRadius::new(params.radius),
+                            threading_policy,
+                            params.edge_modes,
+                            Scalar::default(),
+                            AlphaMode::Premultiplied,
+                        )?;
+                        Ok(None)
+                    }
+                    UnsharpBlur::StackBlur => {
+                        $stack_blur(
+                            image,
+                            AnisotropicRadius::new(params.radius),
+                            threading_policy,
+                            params.edge_modes,
+                            Scalar::default(),
+                            AlphaMode::Premultiplied,
+                        )?;
+                        Ok(None)
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_unsharp_sample!(
+    u8,
+    gaussian_blur,
+    fast_gaussian,
+    stack_blur,
+    ConvolutionMode::FixedPoint
+);
+impl_unsharp_sample!(
+    u16,
+    gaussian_blur_u16,
+    fast_gaussian_u16,
+    stack_blur_u16,
+    ConvolutionMode::Exact
+);
+impl_unsharp_sample!(
+    f32,
+    gaussian_blur_f32,
+    fast_gaussian_f32,
+    stack_blur_f32,
+    IeeeBinaryConvolutionMode::Normal
+);
+
+/// Conversions of the fused pass, all arithmetic happens in f32 working range
+struct FusedOps<T, W, Src, Blurred, Store>
+where
+    Src: Fn(T) -> f32,
+    Blurred: Fn(W) -> f32,
+    Store: Fn(f32) -> T,
+{
+    source: Src,
+    blurred: Blurred,
+    store: Store,
+    /// Working range, result is saturated into it
+    min: f32,
+    max: f32,
+    amount: f32,
+    threshold: f32,
+    _phantom: std::marker::PhantomData<(T, W)>,
+}
+
+impl<T, W, Src, Blurred, Store> FusedOps<T, W, Src, Blurred, Store>
+where
+    Src: Fn(T) -> f32,
+    Blurred: Fn(W) -> f32,
+    Store: Fn(f32) -> T,
+{
+    #[inline(always)]
+    fn sharpen(&self, src: T, blurred: W) -> T {
+        let v = (self.source)(src);
+        let diff = v - (self.blurred)(blurred);
+        if diff.abs() < self.threshold {
+            return (self.store)(v);
+        }
+        (self.store)((diff * self.amount + v).max(self.min).min(self.max))
+    }
+}
+
+/// Sharpens the image in one pass, if `blurred` is `None` destination is expected to contain
+/// the blurred image and it is replaced in place.
+fn fused_unsharp<T, W, Src, Blurred, Store, const CN: usize>(
+    src: &BlurImage<T>,
+    blurred: Option<&BlurImage<W>>,
+    dst: &mut BlurImageMut<T>,
+    ops: &FusedOps<T, W, Src, Blurred, Store>,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Clone + Default + Debug + Send + Sync + Into<W>,
+    W: Copy + Clone + Default + Debug + Send + Sync,
+    Src: Fn(T) -> f32 + Send + Sync,
+    Blurred: Fn(W) -> f32 + Send + Sync,
+    Store: Fn(f32) -> T + Send + Sync,
+{
+    let row_size = src.width as usize * CN;
+    // Alpha of luma-alpha and RGBA images is kept as is
+    let color_channels = match CN {
+        2 => 1,
+        4 => 3,
+        _ => CN,
+    };
+    let src_stride = src.row_stride() as usize;
+    let dst_stride = dst.row_stride() as usize;
+    let pool =
+        crate::executor::ThreadPool::new(threading_policy.thread_count(src.width, src.height));
+    dst.data.borrow_mut()[..dst_stride * (src.height as usize - 1) + row_size]
+        .tb_par_chunks_mut(dst_stride)
+        .for_each_enumerated(&pool, |y, dst_row| {
+            let src_row = &src.data[y * src_stride..y * src_stride + row_size];
+            let dst_row = &mut dst_row[..row_size];
+            match blurred {
+                Some(blurred) => {
+                    let blurred_stride = blurred.row_stride() as usize;
+                    let blurred_row =
+                        &blurred.data[y * blurred_stride..y * blurred_stride + row_size];
+                    for ((dst, src), blurred) in dst_row
+                        .chunks_exact_mut(CN)
+                        .zip(src_row.chunks_exact(CN))
+                        .zip(blurred_row.chunks_exact(CN))
+                    {
+                        for c in 0..color_channels {
+                            dst[c] = ops.sharpen(src[c], blurred[c]);
+                        }
+                        if color_channels != CN {
+                            dst[CN - 1] = src[CN - 1];
+                        }
+                    }
+                }
+                None => {
+                    for (dst, src) in dst_row.chunks_exact_mut(CN).zip(src_row.chunks_exact(CN)) {
+                        for c in 0..color_channels {
+                            dst[c] = ops.sharpen(src[c], dst[c].into());
+                        }
+                        if color_channels != CN {
+                            dst[CN - 1] = src[CN - 1];
+                        }
+                    }
+                }
+            }
+        });
+}
+
+fn fused_dispatch<T, W, Src, Blurred, Store>(
+    src: &BlurImage<T>,
+    blurred: Option<&BlurImage<W>>,
+    dst: &mut BlurImageMut<T>,
+    ops: &FusedOps<T, W, Src, Blurred, Store>,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Clone + Default + Debug + Send + Sync + Into<W>,
+    W: Copy + Clone + Default + Debug + Send + Sync,
+    Src: Fn(T) -> f32 + Send + Sync,
+    Blurred: Fn(W) -> f32 + Send + Sync,
+    Store: Fn(f32) -> T + Send + Sync,
+{
+    match src.channels {
+        FastBlurChannels::Plane => {
+            fused_unsharp::<T, W, Src, Blurred, Store, 1>(src, blurred, dst, ops, threading_policy)
+        }
+        FastBlurChannels::Channels2 => {
+            fused_unsharp::<T, W, Src, Blurred, Store, 2>(src, blurred, dst, ops, threading_policy)
+        }
+        FastBlurChannels::Channels3 => {
+            fused_unsharp::<T, W, Src, Blurred, Store, 3>(src, blurred, dst, ops, threading_policy)
+        }
+        FastBlurChannels::Channels4 => {
+            fused_unsharp::<T, W, Src, Blurred, Store, 4>(src, blurred, dst, ops, threading_policy)
+        }
+    }
+}
+
+fn prepare<T: UnsharpSample>(
+    image: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: &UnsharpMaskParams,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    dst.check_layout(Some(image))?;
+    image.size_matches_mut(dst)?;
+    params.validate()
+}
+
+/// Builds a table from linear value in `0..65535` into gamma corrected integral value
+fn make_gamma_table<const MAX: u32>(transfer_function: TransferFunction) -> Vec<f32> {
+    (0..65536)
+        .map(|i| {
+            (transfer_function.gamma(i as f32 / 65535.) * MAX as f32)
+                .round()
+                .min(MAX as f32)
+        })
+        .collect()
+}
+
+/// Stores `map(src)` into the destination of the same size and channels.
+fn map_image<T, W>(src: &BlurImage<T>, dst: &mut BlurImageMut<W>, map: impl Fn(T) -> W)
+where
+    T: Copy + Clone + Default + Debug,
+    W: Copy + Clone + Default + Debug,
+{
+    let row_size = src.width as usize * src.channels.channels();
+    let dst_stride = dst.row_stride() as usize;
+    for (dst, src) in dst
+        .data
+        .borrow_mut()
+        .chunks_mut(dst_stride)
+        .zip(src.data.chunks(src.row_stride() as usize))
+    {
+        for (dst, &src) in dst[..row_size].iter_mut().zip(src[..row_size].iter()) {
+            *dst = map(src);
+        }
+    }
+}
+
+/// Performs unsharp masking on the image.
+///
+/// Result is `src + amount * (src - blur(src))` where absolute difference is not less
+/// than threshold, saturated into the type range.
+/// Blurred image is written into the destination and then sharpened in place in one fused pass.
+/// In linear light mode image is linearized into a 16-bit image, see [unsharp_mask_with_workspace]
+/// to reuse its memory between calls.
+/// Alpha channel of 4 channels images is preserved.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [UnsharpMaskParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn unsharp_mask(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: UnsharpMaskParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_with_workspace(
+        image,
+        destination,
+        params,
+        threading_policy,
+        &mut BlurWorkspace::new(),
+    )
+}
+
+/// Performs unsharp masking on the image using provided workspace
+///
+/// Same as [unsharp_mask], in linear light mode image is linearized into the 16-bit image
+/// taken from the provided [BlurWorkspace] and blurred there in place by
+/// [UnsharpBlur::FastGaussian] and [UnsharpBlur::StackBlur],
+/// [UnsharpBlur::Gaussian] takes one more image from the workspace for the blurred result.
+/// Workspace is not used when `transfer_function` is not set.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [UnsharpMaskParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+/// * `workspace`: Reusable scratch memory, see [BlurWorkspace] for more info.
+///
+/// returns: ()
+///
+pub fn unsharp_mask_with_workspace(
+    image: &BlurImage<u8>,
+    destination: &mut BlurImageMut<u8>,
+    params: UnsharpMaskParams,
+    threading_policy: ThreadingPolicy,
+    workspace: &mut BlurWorkspace<u16>,
+) -> Result<(), BlurError> {
+    prepare(image, destination, &params)?;
+    match params.transfer_function {
+        None => {
+            u8::blur(image, destination, &params, threading_policy)?;
+            let ops = FusedOps {
+                source: |v: u8| v as f32,
+                blurred: |v: u8| v as f32,
+                store: |v: f32| v.round() as u8,
+                min: 0.,
+                max: 255.,
+                amount: params.amount,
+                threshold: params.threshold,
+                _phantom: Default::default(),
+            };
+            fused_dispatch(
+                image,
+                None::<&BlurImage<u8>>,
+                destination,
+                &ops,
+                threading_policy,
+            );
+        }
+        Some(transfer_function) => {
+            let linearization: Vec<f32> = (0..256)
+                .map(|i| (transfer_function.linearize(i as f32 / 255.) * 65535.).round())
+                .collect();
+            let len = image.width as usize * image.channels.channels() * image.height as usize;
+            let (linear, blurred) = match params.blur {
+                UnsharpBlur::Gaussian => workspace.take(len * 2)?.split_at_mut(len),
+                UnsharpBlur::FastGaussian | UnsharpBlur::StackBlur => {
+                    workspace.take(len)?.split_at_mut(len)
+                }
+            };
+            let mut linear =
+                BlurImageMut::borrow(linear, image.width, image.height, image.channels);
+            map_image(image, &mut linear, |v: u8| linearization[v as usize] as u16);
+            let mut blurred =
+                BlurImageMut::borrow(blurred, image.width, image.height, image.channels);
+            match params.blur {
+                UnsharpBlur::Gaussian => u16::blur(
+                    &linear.to_immutable_ref(),
+                    &mut blurred,
+                    &params,
+                    threading_policy,
+                )?,
+                UnsharpBlur::FastGaussian | UnsharpBlur::StackBlur => {
+                    u16::blur_in_place(&mut linear, &params, threading_policy)?;
+                    blurred = linear;
+                }
+            }
+            let gamma = make_gamma_table::<255>(transfer_function);
+            let ops = FusedOps {
+                source: |v: u8| linearization[v as usize],
+                blurred: |v: u16| v as f32,
+                store: |v: f32| gamma[v.round() as usize] as u8,
+                min: 0.,
+                max: 65535.,
+                amount: params.amount,
+                threshold: params.threshold * (65535. / 255.),
+                _phantom: Default::default(),
+            };
+            let blurred = blurred.to_immutable_ref();
+            fused_dispatch(image, Some(&blurred), destination, &ops, threading_policy);
+        }
+    }
+    Ok(())
+}
+
+/// Performs unsharp masking on the image.
+///
+/// See [unsharp_mask] for more info.
+/// In linear light mode image is linearized straight into the destination and blurred there,
+/// only [UnsharpBlur::Gaussian] requires one temporary image for the blurred result.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [UnsharpMaskParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn unsharp_mask_u16(
+    image: &BlurImage<u16>,
+    destination: &mut BlurImageMut<u16>,
+    params: UnsharpMaskParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    prepare(image, destination, &params)?;
+    match params.transfer_function {
+        None => {
+            u16::blur(image, destination, &params, threading_policy)?;
+            let ops = FusedOps {
+                source: |v: u16| v as f32,
+                blurred: |v: u16| v as f32,
+                store: |v: f32| v.round() as u16,
+                min: 0.,
+                max: 65535.,
+                amount: params.amount,
+                threshold: params.threshold,
+                _phantom: Default::default(),
+            };
+            fused_dispatch(
+                image,
+                None::<&BlurImage<u16>>,
+                destination,
+                &ops,
+                threading_policy,
+            );
+        }
+        Some(transfer_function) => {
+            let linearization: Vec<f32> = (0..65536)
+                .map(|i| (transfer_function.linearize(i as f32 / 65535.) * 65535.).round())
+                .collect();
+            map_image(image, destination, |v: u16| {
+                linearization[v as usize] as u16
+            });
+            let blurred = u16::blur_in_place(destination, &params, threading_policy)?;
+            let gamma = make_gamma_table::<65535>(transfer_function);
+            let ops = FusedOps {
+                source: |v: u16| linearization[v as usize],
+                blurred: |v: u16| v as f32,
+                store: |v: f32| gamma[v.round() as usize] as u16,
+                min: 0.,
+                max: 65535.,
+                amount: params.amount,
+                threshold: params.threshold,
+                _phantom: Default::default(),
+            };
+            fused_dispatch(
+                image,
+                blurred.as_ref().map(|x| x.to_immutable_ref()).as_ref(),
+                destination,
+                &ops,
+                threading_policy,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Performs unsharp masking on the image.
+///
+/// See [unsharp_mask] for more info.
+/// In linear light mode image is linearized straight into the destination and blurred there,
+/// only [UnsharpBlur::Gaussian] requires one temporary image for the blurred result.
+/// Result is not clamped, in linear light mode image is expected to be in `0..1` range
+/// and result is clamped only from below.
+///
+/// # Arguments
+///
+/// * `image`: Source image, see [BlurImage] for more info.
+/// * `destination`: Destination image, see [BlurImageMut] for more info.
+/// * `params`: See [UnsharpMaskParams] for more info.
+/// * `threading_policy`: see [ThreadingPolicy] for more info.
+///
+/// returns: ()
+///
+pub fn unsharp_mask_f32(
+    image: &BlurImage<f32>,
+    destination: &mut BlurImageMut<f32>,
+    params: UnsharpMaskParams,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    prepare(image, destination, &params)?;
+    match params.transfer_function {
+        None => {
+            f32::blur(image, destination, &params, threading_policy)?;
+            let ops = FusedOps {
+                source: |v: f32| v,
+                blurred: |v: f32| v,
+                store: |v: f32| v,
+                min: f32::NEG_INFINITY,
+                max: f32::INFINITY,
+                amount: params.amount,
+                threshold: params.threshold,
+                _phantom: Default::default(),
+            };
+            fused_dispatch(
+                image,
+                None::<&BlurImage<f32>>,
+                destination,
+                &ops,
+                threading_policy,
+            );
+        }
+        Some(transfer_function) => {
+            map_image(image, destination, |v: f32| transfer_function.linearize(v));
+            let blurred = f32::blur_in_place(destination, &params, threading_policy)?;
+            let ops = FusedOps {
+                source: |v: f32| transfer_function.linearize(v),
+                blurred: |v: f32| v,
+                store: |v: f32| transfer_function.gamma(v),
+                min: 0.,
+                max: f32::INFINITY,
+                amount: params.amount,
+                threshold: params.threshold,
+                _phantom: Default::default(),
+            };
+            fused_dispatch(
+                image,
+                blurred.as_ref().map(|x| x.to_immutable_ref()).as_ref(),
+                destination,
+                &ops,
+                threading_policy,
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsharp_flat_and_step() {
+        let width = 32usize;
+        let height = 8usize;
+        let mut src = vec![0u8; width * height * 3];
+        for row in src.chunks_exact_mut(width * 3) {
+            for (x, px) in row.chunks_exact_mut(3).enumerate() {
+                px.fill(if x < width / 2 { 60 } else { 200 });
+            }
+        }
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for blur in [
+            UnsharpBlur::Gaussian,
+            UnsharpBlur::FastGaussian,
+            UnsharpBlur::StackBlur,
+        ] {
+            let mut dst = BlurImageMut::default();
+            let mut params = UnsharpMaskParams::new(3, 2.);
+            params.blur = blur;
+            unsharp_mask(&image, &mut dst, params, ThreadingPolicy::Single).unwrap();
+            let dst = dst.data.borrow();
+            // Flat areas are untouched
+            assert_eq!(dst[3 * 2], 60, "{blur:?}");
+            assert_eq!(dst[(width - 3) * 3], 200, "{blur:?}");
+            // Overshoot on both sides of the step, saturated into u8
+            assert!(dst[(width / 2 - 1) * 3] < 60, "{blur:?}");
+            assert!(dst[(width / 2) * 3] > 200, "{blur:?}");
+        }
+    }
+
+    #[test]
+    fn test_unsharp_threshold_and_linear() {
+        let width = 16usize;
+        let height = 16usize;
+        let src: Vec<u16> = (0..width * height)
+            .map(|x| if x % 2 == 0 { 1000 } else { 1010 })
+            .collect();
+        let image = BlurImage::borrow(&src, width as u32, height as u32, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::default();
+        let mut params = UnsharpMaskParams::new(2, 1.5);
+        params.threshold = 100.;
+        unsharp_mask_u16(&image, &mut dst, params, ThreadingPolicy::Single).unwrap();
+        assert_eq!(dst.data.borrow(), src.as_slice());
+
+        let flat = vec![0.25f32; width * height * 4];
+        let image = BlurImage::borrow(
+            &flat,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let mut dst = BlurImageMut::default();
+        let mut params = UnsharpMaskParams::new(2, 1.5);
+        params.transfer_function = Some(TransferFunction::Srgb);
+        unsharp_mask_f32(&image, &mut dst, params, ThreadingPolicy::Single).unwrap();
+        for &v in dst.data.borrow().iter() {
+            assert!((v - 0.25).abs() < 1e-4, "{v}");
+        }
+    }
+
+    #[test]
+    fn test_unsharp_linear_light_blurs() {
+        let width = 32usize;
+        let height = 8usize;
+        let mut src = vec![0u8; width * height * 3];
+        for row in src.chunks_exact_mut(width * 3) {
+            for (x, px) in row.chunks_exact_mut(3).enumerate() {
+                px.fill(if x < width / 2 { 60 } else { 200 });
+            }
+        }
+        let src16 = src.iter().map(|&x| x as u16 * 257).collect::<Vec<u16>>();
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        let image16 = BlurImage::borrow(
+            &src16,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels3,
+        );
+        for blur in [
+            UnsharpBlur::Gaussian,
+            UnsharpBlur::FastGaussian,
+            UnsharpBlur::StackBlur,
+        ] {
+            let mut params = UnsharpMaskParams::new(2, 1.);
+            params.blur = blur;
+            params.transfer_function = Some(TransferFunction::Srgb);
+            let mut dst = BlurImageMut::default();
+            unsharp_mask(&image, &mut dst, params, ThreadingPolicy::Single).unwrap();
+            let mut dst16 = BlurImageMut::default();
+            unsharp_mask_u16(&image16, &mut dst16, params, ThreadingPolicy::Single).unwrap();
+            let dst = dst.data.borrow();
+            let dst16 = dst16.data.borrow();
+            for (i, (&a, &b)) in dst.iter().zip(dst16.iter()).enumerate() {
+                let b = (b as f32 / 257.).round() as i32;
+                assert!((a as i32 - b).abs() <= 1, "{blur:?} at {i}: {a} vs {b}");
+            }
+            let row = &dst[4 * width * 3..5 * width * 3];
+            assert_eq!(row[0], 60, "{blur:?}");
+            assert_eq!(row[(width - 1) * 3], 200, "{blur:?}");
+            assert!(row[(width / 2 - 1) * 3] < 60, "{blur:?}");
+            assert!(row[(width / 2) * 3] > 200, "{blur:?}");
+        }
+    }
+
+    #[test]
+    fn test_unsharp_linear_light_workspace() {
+        let width = 17usize;
+        let height = 9usize;
+        let src: Vec<u8> = (0..width * height * 4)
+            .map(|i| ((i * 29) % 256) as u8)
+            .collect();
+        let image = BlurImage::borrow(
+            &src,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Channels4,
+        );
+        let len = width * height * 4;
+        // Same workspace is reused with stale contents from the previous filter
+        let mut workspace = BlurWorkspace::new();
+        for (blur, capacity) in [
+            (UnsharpBlur::FastGaussian, len),
+            (UnsharpBlur::Gaussian, len * 2),
+            (UnsharpBlur::StackBlur, len * 2),
+        ] {
+            let mut params = UnsharpMaskParams::new(3, 1.5);
+            params.blur = blur;
+            params.transfer_function = Some(TransferFunction::Srgb);
+            let mut expected = BlurImageMut::default();
+            unsharp_mask(&image, &mut expected, params, ThreadingPolicy::Single).unwrap();
+            let mut dst = BlurImageMut::default();
+            unsharp_mask_with_workspace(
+                &image,
+                &mut dst,
+                params,
+                ThreadingPolicy::Single,
+                &mut workspace,
+            )
+            .unwrap();
+            assert_eq!(workspace.capacity(), capacity, "{blur:?}");
+            assert_eq!(expected.data.borrow(), dst.data.borrow(), "{blur:?}");
+        }
+    }
+}
@@ -49,6 +49,16 @@ pub enum BlurError {
     NegativeOrZeroSigma,
     InvalidArguments,
     FftError(String),
+    RoiOutOfBounds,
+    /// Channels count is not one of supported layouts
+    InvalidChannels(usize),
+    /// Value doesn't correspond to any [crate::EdgeMode]
+    InvalidEdgeMode(usize),
+    /// Parameter is out of its valid range or not finite
+    InvalidParameter {
+        name: &'static str,
+        value: f64,
+    },
 }
 
 impl Error for BlurError {}
@@ -85,6 +95,18 @@ impl std::fmt::Display for BlurError {
             }
             BlurError::InvalidArguments => f.write_str("Invalid arguments"),
             BlurError::FftError(msg) => f.write_str(msg),
+            BlurError::RoiOutOfBounds => {
+                f.write_str("Region of interest must lie inside image bounds")
+            }
+            BlurError::InvalidChannels(channels) => f.write_fmt(format_args!(
+                "Channels count must be in range 1..=4, but received {channels}"
+            )),
+            BlurError::InvalidEdgeMode(value) => {
+                f.write_fmt(format_args!("Unknown edge mode for value: {value}"))
+            }
+            BlurError::InvalidParameter { name, value } => {
+                f.write_fmt(format_args!("Invalid value of `{name}`: {value}"))
+            }
         }
     }
 }
@@ -143,56 +165,505 @@ impl<T: Default + Copy, const N: usize> ScratchBuffer<T, N> {
     // }
 }
 
-use std::fmt::Debug;
-
-pub(crate) enum CowScratch<T: Copy + Debug + Default, const N: usize> {
-    Uninit,
-    Stack([T; N], usize),
-    Heap(Vec<T>),
-}
-
-impl<T: Copy + Debug + Default, const N: usize> CowScratch<T, N> {
-    pub(crate) const fn new() -> Self {
-        Self::Uninit
-    }
-
-    pub(crate) fn is_empty(&self) -> bool {
-        matches!(self, Self::Uninit)
-    }
-
-    /// Lazily initialize with a given size, no-op if already initialized
-    pub(crate) fn get_or_init(&mut self, size: usize) -> &mut [T] {
-        if self.is_empty() {
-            *self = if size <= N {
-                Self::Stack([T::default(); N], size)
-            } else {
-                Self::Heap(vec![T::default(); size])
-            };
-        }
-        self.borrow_mut()
-    }
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
 
-    // pub(crate) fn borrow(&self) -> &[T] {
-    //     match self {
-    //         Self::Uninit => &[],
-    //         Self::Stack(buf, len) => &buf[..*len],
-    //         Self::Heap(v) => v,
-    //     }
-    // }
-
-    pub(crate) fn borrow_mut(&mut self) -> &mut [T] {
-        match self {
-            Self::Uninit => &mut [],
-            Self::Stack(buf, len) => &mut buf[..*len],
-            Self::Heap(v) => v,
-        }
-    }
-
-    pub(crate) fn get_or_unwrap_mut(&mut self) -> &mut [T] {
-        match self {
-            Self::Uninit => panic!("CowScratch is uninitialized"),
-            Self::Stack(buf, len) => &mut buf[..*len],
-            Self::Heap(v) => v,
+    #[test]
+    fn test_hostile_parameters_do_not_panic() {
+        let mut panicked = Vec::new();
+        let mut probe = |name: String, f: &mut dyn FnMut()| {
+            if catch_unwind(AssertUnwindSafe(f)).is_err() {
+                panicked.push(name);
+            }
+        };
+        let sizes = [(1u32, 1u32), (3, 2)];
+        let channels = [
+            FastBlurChannels::Plane,
+            FastBlurChannels::Channels2,
+            FastBlurChannels::Channels3,
+            FastBlurChannels::Channels4,
+        ];
+        let edges = [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+            EdgeMode::Constant,
+        ];
+        let radii = [0u32, 1, 2, 3, 17];
+        let sigmas = [0f64, -1., f64::NAN, f64::INFINITY, 1e-9, 0.8, 5., 1e30];
+        let policies = [ThreadingPolicy::Single];
+        for &(w, h) in sizes.iter() {
+            for &cn in channels.iter() {
+                let len = w as usize * h as usize * cn.channels();
+                let data = vec![117u8; len];
+                let data16 = vec![1117u16; len];
+                let dataf = vec![0.5f32; len];
+                let src = BlurImage::borrow(&data, w, h, cn);
+                let src16 = BlurImage::borrow(&data16, w, h, cn);
+                let srcf = BlurImage::borrow(&dataf, w, h, cn);
+                for &edge in edges.iter() {
+                    let e = edge.as_2d();
+                    let b = Scalar::new(1., 2., 3., 4.);
+                    for &tp in policies.iter() {
+                        for &r in radii.iter() {
+                            let tag = format!("{w}x{h} cn{} {edge:?} r{r}", cn.channels());
+                            probe(format!("stack {tag}"), &mut || {
+                                let mut d = src.clone_as_mut();
+                                _ = stack_blur(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = src16.clone_as_mut();
+                                _ = stack_blur_u16(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = srcf.clone_as_mut();
+                                _ = stack_blur_f32(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                            });
+                            probe(format!("fast_gaussian {tag}"), &mut || {
+                                let mut d = src.clone_as_mut();
+                                _ = fast_gaussian(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = src16.clone_as_mut();
+                                _ = fast_gaussian_u16(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = srcf.clone_as_mut();
+                                _ = fast_gaussian_f32(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                            });
+                            probe(format!("fast_gaussian_next {tag}"), &mut || {
+                                let mut d = src.clone_as_mut();
+                                _ = fast_gaussian_next(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = src16.clone_as_mut();
+                                _ = fast_gaussian_next_u16(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                                let mut d = srcf.clone_as_mut();
+                                _ = fast_gaussian_next_f32(
+                                    &mut d,
+                                    AnisotropicRadius::new(r),
+                                    tp,
+                                    e,
+                                    b,
+                                    AlphaMode::Premultiplied,
+                                );
+                            });
+                            probe(format!("box {tag}"), &mut || {
+                                let p = BoxBlurParameters::new(r);
+                                _ = box_blur(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                                _ = box_blur_u16(
+                                    &src16,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                                _ = box_blur_f32(
+                                    &srcf,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                            });
+                            probe(format!("median {tag}"), &mut || {
+                                _ = median_blur(&src, &mut BlurImageMut::default(), r, tp);
+                                _ = median_blur_u16(
+                                    &src16,
+                                    &mut BlurImageMut::default(),
+                                    r,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                                _ = median_blur_f32(
+                                    &srcf,
+                                    &mut BlurImageMut::default(),
+                                    r,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                                _ = median_blur_constant_time(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    r,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            });
+                            probe(format!("bilateral {tag}"), &mut || {
+                                let p = BilateralBlurParams {
+                                    kernel_size: r as usize % 64,
+                                    spatial_sigma: 1.,
+                                    range_sigma: 1.,
+                                };
+                                _ = bilateral_filter(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            });
+                            probe(format!("motion {tag}"), &mut || {
+                                _ = motion_blur(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    f32::NAN,
+                                    r as usize % 64,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            });
+                        }
+                        for &s in sigmas.iter() {
+                            let tag = format!("{w}x{h} cn{} {edge:?} s{s}", cn.channels());
+                            probe(format!("gaussian {tag}"), &mut || {
+                                for p in [
+                                    GaussianBlurParams::new_from_sigma(s),
+                                    GaussianBlurParams::new(0, s),
+                                    GaussianBlurParams::new(2, s),
+                                    GaussianBlurParams::new(5, s),
+                                    GaussianBlurParams::new_from_kernel(s),
+                                    GaussianBlurParams::new_asymmetric_from_kernels(3., s),
+                                ] {
+                                    _ = gaussian_blur(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        p,
+                                        e,
+                                        tp,
+                                        ConvolutionMode::FixedPoint,
+                                        AlphaMode::Premultiplied,
+                                    );
+                                    _ = gaussian_blur_u16(
+                                        &src16,
+                                        &mut BlurImageMut::default(),
+                                        p,
+                                        e,
+                                        tp,
+                                        ConvolutionMode::Exact,
+                                        AlphaMode::Premultiplied,
+                                    );
+                                    _ = gaussian_blur_f32(
+                                        &srcf,
+                                        &mut BlurImageMut::default(),
+                                        p,
+                                        e,
+                                        tp,
+                                        IeeeBinaryConvolutionMode::Normal,
+                                        AlphaMode::Premultiplied,
+                                    );
+                                }
+                            });
+                            probe(format!("clt {tag}"), &mut || {
+                                let p = CLTParameters::new(s as f32);
+                                _ = tent_blur(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                                _ = gaussian_box_blur(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                                _ = gaussian_box_blur_f32(
+                                    &srcf,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                    AlphaMode::Premultiplied,
+                                );
+                            });
+                            probe(format!("fast_bilateral {tag}"), &mut || {
+                                let s = s as f32;
+                                _ = fast_bilateral_filter(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    s,
+                                    s,
+                                    tp,
+                                );
+                                _ = fast_bilateral_filter_f32(
+                                    &srcf,
+                                    &mut BlurImageMut::default(),
+                                    s,
+                                    s,
+                                    tp,
+                                );
+                            });
+                            probe(format!("bilateral sigma {tag}"), &mut || {
+                                let p = BilateralBlurParams {
+                                    kernel_size: 3,
+                                    spatial_sigma: s as f32,
+                                    range_sigma: s as f32,
+                                };
+                                _ = bilateral_filter(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    p,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            });
+                            probe(format!("unsharp {tag}"), &mut || {
+                                let p = UnsharpMaskParams::new(2, s as f32);
+                                _ = unsharp_mask(&src, &mut BlurImageMut::default(), p, tp);
+                            });
+                            probe(format!("guided {tag}"), &mut || {
+                                let p = GuidedFilterParams::new(2, s as f32);
+                                _ = guided_filter(&src, &src, &mut BlurImageMut::default(), p, tp);
+                            });
+                            probe(format!("canny {tag}"), &mut || {
+                                let p = CannyParams::new(s as f32, 1.);
+                                _ = canny(&src, &mut BlurImageMut::default(), p, tp);
+                            });
+                        }
+                        let tag = format!("{w}x{h} cn{} {edge:?}", cn.channels());
+                        probe(format!("filter_2d {tag}"), &mut || {
+                            let kernel = [1f32; 9];
+                            for shape in [
+                                KernelShape::new(0, 0),
+                                KernelShape::new(2, 2),
+                                KernelShape::new(3, 3),
+                                KernelShape::new(5, 5),
+                                KernelShape::new(9, 1),
+                            ] {
+                                _ = filter_2d::<u8, f32>(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    &kernel,
+                                    shape,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            }
+                            _ = filter_2d::<u8, f32>(
+                                &src,
+                                &mut BlurImageMut::default(),
+                                &[],
+                                KernelShape::new(0, 0),
+                                e,
+                                b,
+                                tp,
+                            );
+                        });
+                        #[cfg(feature = "fft")]
+                        probe(format!("filter_2d_fft {tag}"), &mut || {
+                            let kernel = [1f32; 9];
+                            let complex_kernel = [num_complex::Complex::new(1f32, 0.); 9];
+                            for (len, shape) in [
+                                (0, KernelShape::new(0, 0)),
+                                (0, KernelShape::new(0, 3)),
+                                (0, KernelShape::new(3, 0)),
+                                (9, KernelShape::new(0, 0)),
+                                (9, KernelShape::new(2, 2)),
+                                (9, KernelShape::new(3, 3)),
+                                (9, KernelShape::new(9, 1)),
+                            ] {
+                                for fft_mode in [
+                                    FftConvolutionMode::Whole,
+                                    FftConvolutionMode::Tiled {
+                                        tile_width: 4,
+                                        tile_height: 4,
+                                    },
+                                ] {
+                                    _ = filter_2d_fft::<u8, f32, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                    _ = filter_2d_fft_complex::<u8, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &complex_kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                    _ = filter_2d_rgb_fft::<u8, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                    _ = filter_2d_rgba_fft::<u8, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                    _ = filter_2d_rgb_fft_complex::<u8, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &complex_kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                    _ = filter_2d_rgba_fft_complex::<u8, f32>(
+                                        &src,
+                                        &mut BlurImageMut::default(),
+                                        &complex_kernel[..len],
+                                        shape,
+                                        e,
+                                        b,
+                                        tp,
+                                        fft_mode,
+                                    );
+                                }
+                            }
+                        });
+                        probe(format!("pyramid {tag}"), &mut || {
+                            _ = pyr_down(&src, &mut BlurImageMut::default(), e, b, tp);
+                            _ = pyr_up(&src, &mut BlurImageMut::default(), None, e, b, tp);
+                            _ = gaussian_pyramid(&src, 40, e, b, tp);
+                        });
+                        probe(format!("misc {tag}"), &mut || {
+                            _ = sobel(&src, &mut BlurImageMut::default(), e, b, tp);
+                            _ = laplacian(&src, &mut BlurImageMut::default(), e, b, tp);
+                            #[cfg(feature = "fft")]
+                            {
+                                _ = adaptive_blur(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    5,
+                                    TransferFunction::Srgb,
+                                    e,
+                                    b,
+                                );
+                            }
+                            for element in [
+                                StructuringElement::Rect(KernelShape::new(0, 0)),
+                                StructuringElement::Ellipse(KernelShape::new(4, 4)),
+                                StructuringElement::Custom {
+                                    mask: vec![1; 3],
+                                    shape: KernelShape::new(3, 3),
+                                },
+                                StructuringElement::Cross(KernelShape::new(301, 301)),
+                            ] {
+                                _ = morphology(
+                                    &src,
+                                    &mut BlurImageMut::default(),
+                                    MorphOp::Dilate,
+                                    &element,
+                                    e,
+                                    b,
+                                    tp,
+                                );
+                            }
+                        });
+                    }
+                }
+            }
         }
+        probe("kernels".to_string(), &mut || {
+            _ = lens_kernel(KernelShape::new(5, 5), 6., 0., 3., 0.);
+            _ = lens_kernel(KernelShape::new(1, 1), 6., 0., f32::NAN, 0.);
+            _ = laplacian_kernel(4);
+            _ = complex_gaussian_kernel(0., 1., 1.);
+            _ = complex_gaussian_kernel(f64::INFINITY, 1., 1.);
+            _ = generate_motion_kernel(0, f32::NAN);
+            _ = FastBlurChannels::try_from(7);
+            _ = EdgeMode::try_from(7);
+            _ = GaussianBlurParams::try_new_from_kernel(-3.);
+            _ = GaussianBlurParams::try_new_from_sigma(f64::NAN);
+        });
+        assert!(panicked.is_empty(), "Panicked: {panicked:#?}");
     }
 }
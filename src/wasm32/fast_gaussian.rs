@@ -27,7 +27,9 @@
 
 use crate::EdgeMode;
 use crate::edge_mode::clamp_edge;
+use crate::fast_gaussian::fg_ring_size;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::util::ScratchBuffer;
 use crate::wasm32::utils::{
     load_u8_s32_fast, u16x8_pack_trunc_u8x16, u32x4_pack_trunc_u16x8, w_store_u8x8_m4,
 };
@@ -61,7 +63,10 @@ fn fast_gaussian_horizontal_pass_impl<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[i32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
         let initial_sum = ((radius * radius) >> 1) as i32;
 
         let radius_64 = radius as i64;
@@ -89,8 +94,8 @@ fn fast_gaussian_horizontal_pass_impl<const CN: usize>(
                     let dst_ptr = bytes.get_ptr(bytes_offset);
                     w_store_u8x8_m4::<CN>(dst_ptr, prepared_u8);
 
-                    let arr_index = ((x - radius_64) & 1023) as usize;
-                    let d_arr_index = (x & 1023) as usize;
+                    let arr_index = ((x - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (x & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let mut d_stored = v128_load(d_buf_ptr.as_ptr().cast());
@@ -101,7 +106,7 @@ fn fast_gaussian_horizontal_pass_impl<const CN: usize>(
 
                     diffs = i32x4_add(diffs, i32x4_sub(a_stored, d_stored));
                 } else if x + radius_64 >= 0 {
-                    let arr_index = (x & 1023) as usize;
+                    let arr_index = (x & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = v128_load(buf_ptr.as_ptr().cast());
                     stored = i32x4_shr(stored, 1);
@@ -115,7 +120,7 @@ fn fast_gaussian_horizontal_pass_impl<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_px);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((x + radius_64) & 1023) as usize;
+                let arr_index = ((x + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = i32x4_add(diffs, pixel_color);
@@ -154,7 +159,10 @@ fn fast_gaussian_vertical_pass_wasm<const CN: usize>(
     edge_mode: EdgeMode,
 ) {
     unsafe {
-        let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
+        let ring_size = fg_ring_size(radius);
+        let ring_mask = ring_size as i64 - 1;
+        let mut full_buffer = ScratchBuffer::<[i32; 4], 1024>::new(ring_size);
+        let buffer = full_buffer.as_mut_slice();
         let initial_sum = ((radius * radius) >> 1) as i32;
 
         let height_wide = height as i64;
@@ -183,8 +191,8 @@ fn fast_gaussian_vertical_pass_wasm<const CN: usize>(
 
                     w_store_u8x8_m4::<CN>(bytes.get_ptr(bytes_offset), prepared_u8);
 
-                    let arr_index = ((y - radius_64) & 1023) as usize;
-                    let d_arr_index = (y & 1023) as usize;
+                    let arr_index = ((y - radius_64) & ring_mask) as usize;
+                    let d_arr_index = (y & ring_mask) as usize;
 
                     let d_buf_ptr = buffer.get_unchecked(d_arr_index);
                     let mut d_stored = v128_load(d_buf_ptr.as_ptr().cast());
@@ -195,7 +203,7 @@ fn fast_gaussian_vertical_pass_wasm<const CN: usize>(
 
                     diffs = i32x4_add(diffs, i32x4_sub(a_stored, d_stored));
                 } else if y + radius_64 >= 0 {
-                    let arr_index = (y & 1023) as usize;
+                    let arr_index = (y & ring_mask) as usize;
                     let buf_ptr = buffer.get_unchecked(arr_index);
                     let mut stored = v128_load(buf_ptr.as_ptr().cast());
                     stored = i32x4_shr(stored, 1);
@@ -209,7 +217,7 @@ fn fast_gaussian_vertical_pass_wasm<const CN: usize>(
                 let s_ptr = bytes.get_ptr(next_row_y + next_row_x);
                 let pixel_color = load_u8_s32_fast::<CN>(s_ptr);
 
-                let arr_index = ((y + radius_64) & 1023) as usize;
+                let arr_index = ((y + radius_64) & ring_mask) as usize;
                 let buf_ptr = buffer.get_unchecked_mut(arr_index).as_mut_ptr();
 
                 diffs = i32x4_add(diffs, pixel_color);
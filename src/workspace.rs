@@ -0,0 +1,350 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{BlurError, FastBlurChannels, ImageSize};
+use std::mem::MaybeUninit;
+
+/// Reusable scratch memory for blur routines.
+///
+/// Routines that accept a workspace take their intermediate buffers from it instead
+/// of allocating them on every call.
+/// Workspace only grows and never shrinks, so after the first frame of a fixed size
+/// no further allocations are made when [crate::ThreadingPolicy::Single] is used.
+/// Multithreaded calls reuse the same memory, but dispatching work to threads may allocate,
+/// see [crate::with_executor] to run on persistent threads.
+/// Contents of the workspace are unspecified between calls.
+#[derive(Debug, Clone, Default)]
+pub struct BlurWorkspace<T> {
+    buffer: Vec<T>,
+    /// Backing memory for tables of row slices, keeps no references between calls
+    rows: Vec<[usize; 2]>,
+}
+
+impl<T: Copy + Default> BlurWorkspace<T> {
+    /// Creates an empty workspace, memory is allocated on the first use.
+    pub fn new() -> BlurWorkspace<T> {
+        BlurWorkspace {
+            buffer: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Creates workspace with enough memory to hold one intermediate image of the given size.
+    ///
+    /// # Errors
+    /// Returns an error if required size exceeds pointer size or cannot be allocated.
+    pub fn with_capacity(
+        size: ImageSize,
+        channels: FastBlurChannels,
+    ) -> Result<BlurWorkspace<T>, BlurError> {
+        let mut workspace = BlurWorkspace::new();
+        let required = size
+            .width
+            .checked_mul(size.height)
+            .and_then(|x| x.checked_mul(channels.channels()))
+            .ok_or(BlurError::ExceedingPointerSize)?;
+        workspace.take(required)?;
+        Ok(workspace)
+    }
+
+    /// Amount of elements workspace currently holds.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Releases all memory held by the workspace.
+    pub fn clear(&mut self) {
+        self.buffer = Vec::new();
+        self.rows = Vec::new();
+    }
+
+    /// Returns scratch slice of `len` elements, growing workspace if required.
+    pub(crate) fn take(&mut self, len: usize) -> Result<&mut [T], BlurError> {
+        grow(&mut self.buffer, len, T::default())?;
+        Ok(&mut self.buffer[..len])
+    }
+
+    /// Returns scratch slice of `len` elements together with a table for `rows` row slices.
+    pub(crate) fn take_with_rows(
+        &mut self,
+        len: usize,
+        rows: usize,
+    ) -> Result<(&mut [T], RowTable<'_>), BlurError> {
+        grow(&mut self.buffer, len, T::default())?;
+        grow(&mut self.rows, rows, [0; 2])?;
+        Ok((
+            &mut self.buffer[..len],
+            RowTable {
+                storage: &mut self.rows[..rows],
+            },
+        ))
+    }
+}
+
+fn grow<V: Copy>(buffer: &mut Vec<V>, len: usize, value: V) -> Result<(), BlurError> {
+    if buffer.len() < len {
+        buffer
+            .try_reserve_exact(len - buffer.len())
+            .map_err(|_| BlurError::ExceedingPointerSize)?;
+        buffer.resize(len, value);
+    }
+    Ok(())
+}
+
+/// Workspace memory for a table of row slices, e.g. rows of a column kernel window.
+pub(crate) struct RowTable<'s> {
+    storage: &'s mut [[usize; 2]],
+}
+
+impl<'s> RowTable<'s> {
+    /// Fills the table with `row(i)` for every entry.
+    pub(crate) fn fill<'t: 's, R>(self, mut row: impl FnMut(usize) -> &'t [R]) -> &'s [&'t [R]] {
+        const {
+            assert!(size_of::<&[R]>() == size_of::<[usize; 2]>());
+            assert!(align_of::<&[R]>() <= align_of::<[usize; 2]>());
+        }
+        let len = self.storage.len();
+        // SAFETY: storage is exclusively borrowed for `'s`, it has the same size and
+        // sufficient alignment for slice references, and holds plain integers,
+        // so it is valid to reuse it as uninitialized references.
+        let table = unsafe {
+            std::slice::from_raw_parts_mut(
+                self.storage.as_mut_ptr().cast::<MaybeUninit<&'t [R]>>(),
+                len,
+            )
+        };
+        for (i, slot) in table.iter_mut().enumerate() {
+            slot.write(row(i));
+        }
+        // SAFETY: every entry is initialized above.
+        unsafe { &*(table as *mut [MaybeUninit<&'t [R]>] as *const [&'t [R]]) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AlphaMode, AnisotropicRadius, BlurImage, BlurImageMut, ConvolutionMode, EdgeMode,
+        GaussianBlurParams, GaussianPlan, IeeeBinaryConvolutionMode, Scalar, ThreadingPolicy,
+        fast_bilateral_filter_with_workspace, stack_blur_with_workspace,
+    };
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts allocations made by the current thread, so tests running in parallel
+    /// do not affect each other.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+            unsafe { System.alloc_zeroed(layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    fn count_allocations(f: impl FnOnce()) -> usize {
+        let start = ALLOCATIONS.with(|x| x.get());
+        f();
+        ALLOCATIONS.with(|x| x.get()) - start
+    }
+
+    fn make_image(width: usize, height: usize, cn: usize) -> Vec<u8> {
+        let mut seed = 0x2545f491u32;
+        (0..width * height * cn)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_workspace_grows_and_keeps_memory() {
+        let mut workspace = BlurWorkspace::<u8>::new();
+        assert_eq!(workspace.capacity(), 0);
+        assert_eq!(workspace.take(16).unwrap().len(), 16);
+        let ptr = workspace.take(8).unwrap().as_ptr();
+        assert_eq!(workspace.capacity(), 16);
+        assert_eq!(workspace.take(16).unwrap().as_ptr(), ptr);
+        let sized =
+            BlurWorkspace::<u16>::with_capacity(ImageSize::new(5, 3), FastBlurChannels::Channels3)
+                .unwrap();
+        assert_eq!(sized.capacity(), 45);
+        assert!(
+            BlurWorkspace::<u8>::with_capacity(
+                ImageSize::new(usize::MAX, 2),
+                FastBlurChannels::Plane
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_gaussian_plan_repeated_execution_does_not_allocate() {
+        let src = make_image(67, 53, 3);
+        let src_image = BlurImage::borrow(&src, 67, 53, FastBlurChannels::Channels3);
+        let mut dst = BlurImageMut::alloc(67, 53, FastBlurChannels::Channels3);
+        for kernel in [5., 71.] {
+            for (name, hint) in [
+                ("exact", ConvolutionMode::Exact),
+                ("fixed point", ConvolutionMode::FixedPoint),
+            ] {
+                let mut plan = GaussianPlan::<u8>::new(
+                    src_image.size(),
+                    FastBlurChannels::Channels3,
+                    GaussianBlurParams::new_from_kernel(kernel),
+                    EdgeMode::Reflect101.as_2d(),
+                    ThreadingPolicy::Single,
+                    hint,
+                    AlphaMode::Premultiplied,
+                )
+                .unwrap();
+                plan.execute(&src_image, &mut dst).unwrap();
+                let allocations = count_allocations(|| plan.execute(&src_image, &mut dst).unwrap());
+                assert_eq!(
+                    allocations, 0,
+                    "Repeated execution allocated for kernel {kernel} and {name} mode"
+                );
+            }
+        }
+
+        let src = src.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+        let src_image = BlurImage::borrow(&src, 67, 53, FastBlurChannels::Channels3);
+        let mut dst = BlurImageMut::alloc(67, 53, FastBlurChannels::Channels3);
+        let mut plan = GaussianPlan::<f32>::new(
+            src_image.size(),
+            FastBlurChannels::Channels3,
+            GaussianBlurParams::new_from_kernel(9.),
+            EdgeMode::Reflect.as_2d(),
+            ThreadingPolicy::Single,
+            IeeeBinaryConvolutionMode::Normal,
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        plan.execute(&src_image, &mut dst).unwrap();
+        assert_eq!(
+            count_allocations(|| plan.execute(&src_image, &mut dst).unwrap()),
+            0
+        );
+
+        let src = make_image(67, 53, 4);
+        let src_image = BlurImage::borrow(&src, 67, 53, FastBlurChannels::Channels4);
+        let mut dst = BlurImageMut::alloc(67, 53, FastBlurChannels::Channels4);
+        let mut plan = GaussianPlan::<u8>::new(
+            src_image.size(),
+            FastBlurChannels::Channels4,
+            GaussianBlurParams::new_from_kernel(9.),
+            EdgeMode::Reflect101.as_2d(),
+            ThreadingPolicy::Single,
+            ConvolutionMode::FixedPoint,
+            AlphaMode::Straight,
+        )
+        .unwrap();
+        plan.execute(&src_image, &mut dst).unwrap();
+        assert_eq!(
+            count_allocations(|| plan.execute(&src_image, &mut dst).unwrap()),
+            0,
+            "Repeated execution with straight alpha allocated"
+        );
+    }
+
+    #[test]
+    fn test_stack_blur_repeated_execution_does_not_allocate() {
+        for (edge_mode, alpha_mode) in [
+            (EdgeMode::Clamp, AlphaMode::Premultiplied),
+            (EdgeMode::Reflect, AlphaMode::Premultiplied),
+            (EdgeMode::Reflect, AlphaMode::Straight),
+        ] {
+            let mut data = make_image(67, 53, 4);
+            let mut image = BlurImageMut::borrow(&mut data, 67, 53, FastBlurChannels::Channels4);
+            let mut workspace = BlurWorkspace::new();
+            let mut run = || {
+                stack_blur_with_workspace(
+                    &mut image,
+                    AnisotropicRadius::new(10),
+                    ThreadingPolicy::Single,
+                    edge_mode.as_2d(),
+                    Scalar::default(),
+                    alpha_mode,
+                    &mut workspace,
+                )
+                .unwrap()
+            };
+            run();
+            assert_eq!(
+                count_allocations(run),
+                0,
+                "Repeated stack blur allocated for {edge_mode:?} and {alpha_mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fast_bilateral_repeated_execution_does_not_allocate() {
+        let src = make_image(67, 53, 3);
+        let src_image = BlurImage::borrow(&src, 67, 53, FastBlurChannels::Channels3);
+        let mut dst = BlurImageMut::alloc(67, 53, FastBlurChannels::Channels3);
+        let mut workspace = BlurWorkspace::new();
+        let mut run = || {
+            fast_bilateral_filter_with_workspace(
+                &src_image,
+                &mut dst,
+                5.,
+                0.3,
+                ThreadingPolicy::Single,
+                &mut workspace,
+            )
+            .unwrap()
+        };
+        // First run sizes the workspace, this also makes sure allocations are counted
+        assert_ne!(count_allocations(&mut run), 0);
+        assert_eq!(count_allocations(run), 0);
+    }
+}